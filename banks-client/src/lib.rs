@@ -7,7 +7,10 @@
 
 use borsh::BorshDeserialize;
 use futures::{future::join_all, Future, FutureExt};
-pub use solana_banks_interface::{BanksClient as TarpcClient, TransactionStatus};
+pub use solana_banks_interface::{
+    BanksClient as TarpcClient, BanksTransactionResultWithMetadata, TransactionMetadata,
+    TransactionStatus,
+};
 use solana_banks_interface::{BanksRequest, BanksResponse};
 use solana_program::{
     clock::Slot, fee_calculator::FeeCalculator, hash::Hash, program_pack::Pack, pubkey::Pubkey,
@@ -105,6 +108,25 @@ impl BanksClient {
             .get_account_with_commitment_and_context(ctx, address, commitment)
     }
 
+    pub fn simulate_transaction_with_commitment_and_context(
+        &mut self,
+        ctx: Context,
+        transaction: Transaction,
+        commitment: CommitmentLevel,
+    ) -> impl Future<Output = io::Result<BanksTransactionResultWithMetadata>> + '_ {
+        self.inner
+            .simulate_transaction_with_commitment_and_context(ctx, transaction, commitment)
+    }
+
+    pub fn process_transaction_with_metadata_and_context(
+        &mut self,
+        ctx: Context,
+        transaction: Transaction,
+    ) -> impl Future<Output = io::Result<Option<BanksTransactionResultWithMetadata>>> + '_ {
+        self.inner
+            .process_transaction_with_metadata_and_context(ctx, transaction)
+    }
+
     /// Send a transaction and return immediately. The server will resend the
     /// transaction until either it is accepted by the cluster or the transaction's
     /// blockhash expires.
@@ -168,6 +190,46 @@ impl BanksClient {
         self.process_transaction_with_commitment(transaction, CommitmentLevel::default())
     }
 
+    /// Send a transaction and return after it has been processed or rejected,
+    /// along with its logs, inner instructions, compute units consumed and
+    /// resulting accounts when the server's banks executed it
+    pub fn process_transaction_with_metadata(
+        &mut self,
+        transaction: Transaction,
+    ) -> impl Future<Output = io::Result<BanksTransactionResultWithMetadata>> + '_ {
+        let mut ctx = context::current();
+        ctx.deadline += Duration::from_secs(50);
+        self.process_transaction_with_metadata_and_context(ctx, transaction)
+            .map(|result| {
+                result?.ok_or_else(|| {
+                    Error::new(ErrorKind::TimedOut, "invalid blockhash or fee-payer")
+                })
+            })
+    }
+
+    /// Run a transaction against the bank at the given level of commitment
+    /// without committing it, returning its result along with its logs, inner
+    /// instructions, compute units consumed and resulting accounts
+    pub fn simulate_transaction_with_commitment(
+        &mut self,
+        transaction: Transaction,
+        commitment: CommitmentLevel,
+    ) -> impl Future<Output = io::Result<BanksTransactionResultWithMetadata>> + '_ {
+        self.simulate_transaction_with_commitment_and_context(
+            context::current(),
+            transaction,
+            commitment,
+        )
+    }
+
+    /// Run a transaction against the finalized bank without committing it
+    pub fn simulate_transaction(
+        &mut self,
+        transaction: Transaction,
+    ) -> impl Future<Output = io::Result<BanksTransactionResultWithMetadata>> + '_ {
+        self.simulate_transaction_with_commitment(transaction, CommitmentLevel::default())
+    }
+
     pub async fn process_transactions_with_commitment(
         &mut self,
         transactions: Vec<Transaction>,
@@ -319,7 +381,10 @@ mod tests {
         bank::Bank, bank_forks::BankForks, commitment::BlockCommitmentCache,
         genesis_utils::create_genesis_config,
     };
-    use solana_sdk::{message::Message, signature::Signer, system_instruction};
+    use solana_sdk::{
+        instruction::InstructionError, message::Message, signature::Signer, system_instruction,
+        transaction::TransactionError,
+    };
     use std::sync::{Arc, RwLock};
     use tarpc::transport;
     use tokio::{runtime::Runtime, time::sleep};
@@ -403,4 +468,79 @@ mod tests {
             Ok(())
         })
     }
+
+    #[test]
+    fn test_banks_server_simulate_and_process_with_metadata() -> io::Result<()> {
+        let genesis = create_genesis_config(10);
+        let bank = Bank::new(&genesis.genesis_config);
+        let slot = bank.slot();
+        let block_commitment_cache = Arc::new(RwLock::new(
+            BlockCommitmentCache::new_for_tests_with_slots(slot, slot),
+        ));
+        let bank_forks = Arc::new(RwLock::new(BankForks::new(bank)));
+
+        let mint_pubkey = genesis.mint_keypair.pubkey();
+        let bob_pubkey = solana_sdk::pubkey::new_rand();
+        let instruction = system_instruction::transfer(&mint_pubkey, &bob_pubkey, 1);
+        let message = Message::new(&[instruction], Some(&mint_pubkey));
+
+        Runtime::new()?.block_on(async {
+            let client_transport = start_local_server(bank_forks, block_commitment_cache).await;
+            let mut banks_client = start_client(client_transport).await?;
+            let recent_blockhash = banks_client.get_recent_blockhash().await?;
+            let transaction = Transaction::new(&[&genesis.mint_keypair], message, recent_blockhash);
+
+            // Simulation reports the outcome without changing any account
+            let simulation = banks_client
+                .simulate_transaction(transaction.clone())
+                .await?;
+            assert_eq!(simulation.result, Ok(()));
+            let metadata = simulation.metadata.unwrap();
+            assert!(!metadata.log_messages.is_empty());
+            assert_eq!(metadata.inner_instructions, vec![vec![]]);
+            assert_eq!(
+                metadata.post_accounts[1],
+                (
+                    bob_pubkey,
+                    Account::new(1, 0, &solana_sdk::system_program::id())
+                )
+            );
+            assert_eq!(banks_client.get_balance(bob_pubkey).await?, 0);
+
+            let processed = banks_client
+                .process_transaction_with_metadata(transaction.clone())
+                .await?;
+            assert_eq!(processed.result, Ok(()));
+            assert_eq!(
+                processed.metadata.unwrap().post_accounts,
+                metadata.post_accounts
+            );
+            assert_eq!(banks_client.get_balance(bob_pubkey).await?, 1);
+
+            // A failed instruction is reported along with the metadata
+            let instruction = system_instruction::transfer(&mint_pubkey, &bob_pubkey, 100);
+            let message = Message::new(&[instruction], Some(&mint_pubkey));
+            let overdraft = Transaction::new(&[&genesis.mint_keypair], message, recent_blockhash);
+            let processed = banks_client
+                .process_transaction_with_metadata(overdraft)
+                .await?;
+            assert_eq!(
+                processed.result,
+                Err(TransactionError::InstructionError(
+                    0,
+                    InstructionError::Custom(1)
+                ))
+            );
+            assert!(processed.metadata.is_some());
+            assert_eq!(banks_client.get_balance(bob_pubkey).await?, 1);
+
+            // A transaction that is rejected before execution has no metadata
+            let processed = banks_client
+                .process_transaction_with_metadata(transaction)
+                .await?;
+            assert_eq!(processed.result, Err(TransactionError::DuplicateSignature));
+            assert_eq!(processed.metadata, None);
+            Ok(())
+        })
+    }
 }
@@ -5,9 +5,10 @@ use solana_sdk::{
     commitment_config::CommitmentLevel,
     fee_calculator::FeeCalculator,
     hash::Hash,
+    instruction::CompiledInstruction,
     pubkey::Pubkey,
     signature::Signature,
-    transaction::{self, Transaction, TransactionError},
+    transaction::{self, Transaction, TransactionError, TransactionReturnData},
 };
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -25,6 +26,26 @@ pub struct TransactionStatus {
     pub confirmation_status: Option<TransactionConfirmationStatus>,
 }
 
+/// What was recorded during the execution of a transaction
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TransactionMetadata {
+    pub log_messages: Vec<String>,
+    /// The instructions invoked by each instruction of the transaction
+    pub inner_instructions: Vec<Vec<CompiledInstruction>>,
+    pub return_data: Option<TransactionReturnData>,
+    pub compute_units_consumed: u64,
+    /// The accounts of the transaction after its execution, in message order
+    pub post_accounts: Vec<(Pubkey, Account)>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BanksTransactionResultWithMetadata {
+    pub result: transaction::Result<()>,
+    /// None if the transaction was rejected before it could be executed, or
+    /// if it was processed by the cluster rather than by the server's banks
+    pub metadata: Option<TransactionMetadata>,
+}
+
 #[tarpc::service]
 pub trait Banks {
     async fn send_transaction_with_context(transaction: Transaction);
@@ -42,6 +63,13 @@ pub trait Banks {
         address: Pubkey,
         commitment: CommitmentLevel,
     ) -> Option<Account>;
+    async fn simulate_transaction_with_commitment_and_context(
+        transaction: Transaction,
+        commitment: CommitmentLevel,
+    ) -> BanksTransactionResultWithMetadata;
+    async fn process_transaction_with_metadata_and_context(
+        transaction: Transaction,
+    ) -> Option<BanksTransactionResultWithMetadata>;
 }
 
 #[cfg(test)]
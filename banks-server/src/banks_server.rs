@@ -5,9 +5,14 @@ use futures::{
     prelude::stream::{self, StreamExt},
 };
 use solana_banks_interface::{
-    Banks, BanksRequest, BanksResponse, TransactionConfirmationStatus, TransactionStatus,
+    Banks, BanksRequest, BanksResponse, BanksTransactionResultWithMetadata,
+    TransactionConfirmationStatus, TransactionMetadata, TransactionStatus,
+};
+use solana_runtime::{
+    bank::{Bank, TransactionExecutionDetails},
+    bank_forks::BankForks,
+    commitment::BlockCommitmentCache,
 };
-use solana_runtime::{bank::Bank, bank_forks::BankForks, commitment::BlockCommitmentCache};
 use solana_sdk::{
     account::Account,
     clock::Slot,
@@ -16,7 +21,7 @@ use solana_sdk::{
     hash::Hash,
     pubkey::Pubkey,
     signature::Signature,
-    transaction::{self, Transaction},
+    transaction::{self, Transaction, TransactionError},
 };
 use std::{
     io,
@@ -43,6 +48,9 @@ struct BanksServer {
     bank_forks: Arc<RwLock<BankForks>>,
     block_commitment_cache: Arc<RwLock<BlockCommitmentCache>>,
     transaction_sender: Sender<TransactionInfo>,
+    /// Whether transactions are executed by the banks in `bank_forks`, rather
+    /// than forwarded to the cluster
+    is_loopback: bool,
 }
 
 impl BanksServer {
@@ -59,6 +67,7 @@ impl BanksServer {
             bank_forks,
             block_commitment_cache,
             transaction_sender,
+            is_loopback: false,
         }
     }
 
@@ -95,7 +104,10 @@ impl BanksServer {
             .name("solana-bank-forks-client".to_string())
             .spawn(move || Self::run(server_bank_forks, transaction_receiver))
             .unwrap();
-        Self::new(bank_forks, block_commitment_cache, transaction_sender)
+        Self {
+            is_loopback: true,
+            ..Self::new(bank_forks, block_commitment_cache, transaction_sender)
+        }
     }
 
     fn slot(&self, commitment: CommitmentLevel) -> Slot {
@@ -141,6 +153,31 @@ fn verify_transaction(transaction: &Transaction) -> transaction::Result<()> {
     }
 }
 
+fn result_with_metadata(
+    details: TransactionExecutionDetails,
+) -> BanksTransactionResultWithMetadata {
+    // Other errors reject the transaction before any of it is executed
+    let executed = matches!(
+        details.status,
+        Ok(()) | Err(TransactionError::InstructionError(_, _))
+    );
+    let metadata = if executed {
+        Some(TransactionMetadata {
+            log_messages: details.log_messages,
+            inner_instructions: details.inner_instructions,
+            return_data: details.return_data,
+            compute_units_consumed: details.executed_units,
+            post_accounts: details.post_accounts,
+        })
+    } else {
+        None
+    };
+    BanksTransactionResultWithMetadata {
+        result: details.status,
+        metadata,
+    }
+}
+
 #[tarpc::server]
 impl Banks for BanksServer {
     async fn send_transaction_with_context(self, _: Context, transaction: Transaction) {
@@ -244,6 +281,56 @@ impl Banks for BanksServer {
         let bank = self.bank(commitment);
         bank.get_account(&address)
     }
+
+    async fn simulate_transaction_with_commitment_and_context(
+        self,
+        _: Context,
+        transaction: Transaction,
+        commitment: CommitmentLevel,
+    ) -> BanksTransactionResultWithMetadata {
+        if let Err(err) = verify_transaction(&transaction) {
+            return BanksTransactionResultWithMetadata {
+                result: Err(err),
+                metadata: None,
+            };
+        }
+        result_with_metadata(
+            self.bank(commitment)
+                .simulate_transaction_unchecked(transaction),
+        )
+    }
+
+    async fn process_transaction_with_metadata_and_context(
+        self,
+        ctx: Context,
+        transaction: Transaction,
+    ) -> Option<BanksTransactionResultWithMetadata> {
+        if !self.is_loopback {
+            // The cluster does not report what happened during execution
+            let result = self
+                .process_transaction_with_commitment_and_context(
+                    ctx,
+                    transaction,
+                    CommitmentLevel::Processed,
+                )
+                .await?;
+            return Some(BanksTransactionResultWithMetadata {
+                result,
+                metadata: None,
+            });
+        }
+
+        if let Err(err) = verify_transaction(&transaction) {
+            return Some(BanksTransactionResultWithMetadata {
+                result: Err(err),
+                metadata: None,
+            });
+        }
+        let bank = self.bank_forks.read().unwrap().working_bank();
+        Some(result_with_metadata(
+            bank.process_transaction_with_details(&transaction),
+        ))
+    }
 }
 
 pub async fn start_local_server(
@@ -1824,13 +1824,12 @@ impl fmt::Display for CliBlock {
             self.encoded_confirmed_block.transactions.iter().enumerate()
         {
             writeln!(f, "Transaction {}:", index)?;
-            writeln_transaction(
-                f,
-                &transaction_with_meta.transaction.decode().unwrap(),
-                &transaction_with_meta.meta,
-                "  ",
-                None,
-            )?;
+            match transaction_with_meta.transaction.decode() {
+                Some(transaction) => {
+                    writeln_transaction(f, &transaction, &transaction_with_meta.meta, "  ", None)?
+                }
+                None => writeln!(f, "  Unsupported transaction version")?,
+            }
         }
         Ok(())
     }
@@ -94,6 +94,15 @@ pub enum ProgramCliCommand {
         account_pubkey: Option<Pubkey>,
         output_location: String,
     },
+    Close {
+        account_pubkey: Pubkey,
+        recipient_pubkey: Pubkey,
+        authority_index: SignerIndex,
+    },
+    Extend {
+        program_pubkey: Pubkey,
+        additional_bytes: u32,
+    },
 }
 
 pub trait ProgramSubCommands {
@@ -290,6 +299,53 @@ impl ProgramSubCommands for App<'_, '_> {
                                 .help("/path/to/program.so"),
                         ),
                 )
+                .subcommand(
+                    SubCommand::with_name("close")
+                        .about("Close a buffer or program account and withdraw all lamports")
+                        .arg(
+                            Arg::with_name("account")
+                                .index(1)
+                                .value_name("ACCOUNT_ADDRESS")
+                                .takes_value(true)
+                                .required(true)
+                                .help("Address of the buffer or program to close")
+                        )
+                        .arg(
+                            Arg::with_name("authority")
+                                .long("authority")
+                                .value_name("AUTHORITY_SIGNER")
+                                .takes_value(true)
+                                .validator(is_valid_signer)
+                                .help("Buffer or upgrade authority [default: the default configured keypair]")
+                        )
+                        .arg(
+                            pubkey!(Arg::with_name("recipient_account")
+                                .long("recipient")
+                                .value_name("RECIPIENT_ADDRESS"),
+                                "Address of the account to deposit the closed account's lamports [default: the default configured keypair]. "),
+                        )
+                )
+                .subcommand(
+                    SubCommand::with_name("extend")
+                        .about("Extend the length of an upgradeable program to allow larger upgrades")
+                        .arg(
+                            Arg::with_name("program_id")
+                                .index(1)
+                                .value_name("PROGRAM_ADDRESS")
+                                .takes_value(true)
+                                .required(true)
+                                .help("Address of the program to extend")
+                        )
+                        .arg(
+                            Arg::with_name("additional_bytes")
+                                .index(2)
+                                .value_name("ADDITIONAL_BYTES")
+                                .takes_value(true)
+                                .required(true)
+                                .validator(is_parsable::<u32>)
+                                .help("Number of bytes that will be allocated for the program's data")
+                        )
+                )
         )
     }
 }
@@ -500,6 +556,55 @@ pub fn parse_program_subcommand(
             }),
             signers: vec![],
         },
+        ("close", Some(matches)) => {
+            let account_pubkey = pubkey_of(matches, "account").unwrap();
+
+            let (authority_signer, authority_pubkey) =
+                signer_of(matches, "authority", wallet_manager)?;
+            let default_signer_pubkey = default_signer
+                .signer_from_path(matches, wallet_manager)?
+                .pubkey();
+            let recipient_pubkey = pubkey_of_signer(matches, "recipient_account", wallet_manager)?
+                .unwrap_or(default_signer_pubkey);
+
+            let signer_info = default_signer.generate_unique_signers(
+                vec![
+                    Some(default_signer.signer_from_path(matches, wallet_manager)?),
+                    authority_signer,
+                ],
+                matches,
+                wallet_manager,
+            )?;
+
+            CliCommandInfo {
+                command: CliCommand::Program(ProgramCliCommand::Close {
+                    account_pubkey,
+                    recipient_pubkey,
+                    authority_index: signer_info.index_of(authority_pubkey).unwrap(),
+                }),
+                signers: signer_info.signers,
+            }
+        }
+        ("extend", Some(matches)) => {
+            let program_pubkey = pubkey_of(matches, "program_id").unwrap();
+            let additional_bytes = value_of(matches, "additional_bytes").unwrap();
+
+            let signer_info = default_signer.generate_unique_signers(
+                vec![Some(
+                    default_signer.signer_from_path(matches, wallet_manager)?,
+                )],
+                matches,
+                wallet_manager,
+            )?;
+
+            CliCommandInfo {
+                command: CliCommand::Program(ProgramCliCommand::Extend {
+                    program_pubkey,
+                    additional_bytes,
+                }),
+                signers: signer_info.signers,
+            }
+        }
         _ => unreachable!(),
     };
     Ok(response)
@@ -580,6 +685,21 @@ pub fn process_program_subcommand(
             account_pubkey,
             output_location,
         } => process_dump(&rpc_client, config, *account_pubkey, output_location),
+        ProgramCliCommand::Close {
+            account_pubkey,
+            recipient_pubkey,
+            authority_index,
+        } => process_close(
+            &rpc_client,
+            config,
+            *account_pubkey,
+            *recipient_pubkey,
+            *authority_index,
+        ),
+        ProgramCliCommand::Extend {
+            program_pubkey,
+            additional_bytes,
+        } => process_extend(&rpc_client, config, *program_pubkey, *additional_bytes),
     }
 }
 
@@ -1040,6 +1160,181 @@ fn process_dump(
     }
 }
 
+fn process_close(
+    rpc_client: &RpcClient,
+    config: &CliConfig,
+    account_pubkey: Pubkey,
+    recipient_pubkey: Pubkey,
+    authority_index: SignerIndex,
+) -> ProcessResult {
+    let authority_signer = config.signers[authority_index];
+
+    let account = rpc_client
+        .get_account_with_commitment(&account_pubkey, config.commitment)?
+        .value
+        .ok_or("Unable to find the account")?;
+    if account.owner != bpf_loader_upgradeable::id() {
+        return Err("Account is not owned by the upgradeable loader".into());
+    }
+
+    let (instruction, account_type, lamports) = match account.state()? {
+        UpgradeableLoaderState::Buffer { authority_address } => {
+            if authority_address != Some(authority_signer.pubkey()) {
+                return Err(format!(
+                    "Buffer's authority {:?} does not match authority provided {}",
+                    authority_address,
+                    authority_signer.pubkey()
+                )
+                .into());
+            }
+            (
+                bpf_loader_upgradeable::close_buffer(
+                    &account_pubkey,
+                    &recipient_pubkey,
+                    &authority_signer.pubkey(),
+                ),
+                "Buffer",
+                account.lamports,
+            )
+        }
+        UpgradeableLoaderState::Program {
+            programdata_address,
+        } => {
+            let programdata_account = rpc_client
+                .get_account_with_commitment(&programdata_address, config.commitment)?
+                .value
+                .ok_or("Failed to find associated ProgramData account for the provided program")?;
+            if let Ok(UpgradeableLoaderState::ProgramData {
+                upgrade_authority_address,
+                ..
+            }) = programdata_account.state()
+            {
+                if upgrade_authority_address != Some(authority_signer.pubkey()) {
+                    return Err(format!(
+                        "Program's authority {:?} does not match authority provided {}",
+                        upgrade_authority_address,
+                        authority_signer.pubkey()
+                    )
+                    .into());
+                }
+            } else {
+                return Err("Invalid associated ProgramData account found for the program".into());
+            }
+            (
+                bpf_loader_upgradeable::close_program(
+                    &account_pubkey,
+                    &recipient_pubkey,
+                    &authority_signer.pubkey(),
+                ),
+                "Program",
+                programdata_account.lamports,
+            )
+        }
+        _ => return Err("Not a buffer or program account".into()),
+    };
+
+    let (blockhash, _) = rpc_client.get_recent_blockhash()?;
+    let mut tx = Transaction::new_unsigned(Message::new(
+        &[instruction],
+        Some(&config.signers[0].pubkey()),
+    ));
+    tx.try_sign(&[config.signers[0], authority_signer], blockhash)?;
+    rpc_client
+        .send_and_confirm_transaction_with_spinner_and_config(
+            &tx,
+            config.commitment,
+            RpcSendTransactionConfig {
+                skip_preflight: true,
+                preflight_commitment: Some(config.commitment.commitment),
+                ..RpcSendTransactionConfig::default()
+            },
+        )
+        .map_err(|e| format!("Closing {} failed: {}", account_type.to_lowercase(), e))?;
+
+    Ok(format!(
+        "Closed {} {}, {} reclaimed",
+        account_type,
+        account_pubkey,
+        Sol(lamports)
+    ))
+}
+
+fn process_extend(
+    rpc_client: &RpcClient,
+    config: &CliConfig,
+    program_pubkey: Pubkey,
+    additional_bytes: u32,
+) -> ProcessResult {
+    if additional_bytes == 0 {
+        return Err("Additional bytes must be greater than zero".into());
+    }
+
+    let program_account = rpc_client
+        .get_account_with_commitment(&program_pubkey, config.commitment)?
+        .value
+        .ok_or("Unable to find the program account")?;
+    let programdata_address = if let Ok(UpgradeableLoaderState::Program {
+        programdata_address,
+    }) = program_account.state()
+    {
+        programdata_address
+    } else {
+        return Err(format!("{} is not an upgradeable program", program_pubkey).into());
+    };
+    let programdata_account = rpc_client
+        .get_account_with_commitment(&programdata_address, config.commitment)?
+        .value
+        .ok_or("Failed to find associated ProgramData account for the provided program")?;
+    if let Ok(UpgradeableLoaderState::ProgramData {
+        upgrade_authority_address,
+        ..
+    }) = programdata_account.state()
+    {
+        if upgrade_authority_address.is_none() {
+            return Err("Program is immutable and cannot be extended".into());
+        }
+    } else {
+        return Err("Invalid associated ProgramData account found for the program".into());
+    }
+
+    let new_len = programdata_account.data.len() + additional_bytes as usize;
+    let required_payment = rpc_client
+        .get_minimum_balance_for_rent_exemption(new_len)?
+        .saturating_sub(programdata_account.lamports);
+
+    let message = Message::new(
+        &[bpf_loader_upgradeable::extend_program(
+            &program_pubkey,
+            &config.signers[0].pubkey(),
+            additional_bytes,
+        )],
+        Some(&config.signers[0].pubkey()),
+    );
+    check_payer(rpc_client, config, required_payment, &[&message])?;
+
+    let (blockhash, _) = rpc_client.get_recent_blockhash()?;
+    let mut tx = Transaction::new_unsigned(message);
+    tx.try_sign(&[config.signers[0]], blockhash)?;
+    rpc_client
+        .send_and_confirm_transaction_with_spinner_and_config(
+            &tx,
+            config.commitment,
+            RpcSendTransactionConfig {
+                skip_preflight: true,
+                preflight_commitment: Some(config.commitment.commitment),
+                ..RpcSendTransactionConfig::default()
+            },
+        )
+        .map_err(|e| format!("Extending program failed: {}", e))?;
+
+    Ok(format!(
+        "Extended program {} by {} bytes, new data length {}",
+        program_pubkey,
+        additional_bytes,
+        new_len.saturating_sub(UpgradeableLoaderState::programdata_data_offset()?)
+    ))
+}
+
 /// Deploy using non-upgradeable loader
 pub fn process_deploy(
     rpc_client: &RpcClient,
@@ -2290,6 +2585,110 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_cli_parse_close() {
+        let test_commands = app("test", "desc", "version");
+
+        let default_keypair = Keypair::new();
+        let keypair_file = make_tmp_path("keypair_file");
+        write_keypair_file(&default_keypair, &keypair_file).unwrap();
+        let default_signer = DefaultSigner {
+            path: keypair_file.clone(),
+            arg_name: "".to_string(),
+        };
+
+        let account_pubkey = Pubkey::new_unique();
+        let test_close = test_commands.clone().get_matches_from(vec![
+            "test",
+            "program",
+            "close",
+            &account_pubkey.to_string(),
+        ]);
+        assert_eq!(
+            parse_command(&test_close, &default_signer, &mut None).unwrap(),
+            CliCommandInfo {
+                command: CliCommand::Program(ProgramCliCommand::Close {
+                    account_pubkey,
+                    recipient_pubkey: default_keypair.pubkey(),
+                    authority_index: 0,
+                }),
+                signers: vec![read_keypair_file(&keypair_file).unwrap().into()],
+            }
+        );
+
+        let authority_keypair = Keypair::new();
+        let authority_keypair_file = make_tmp_path("authority_keypair_file");
+        write_keypair_file(&authority_keypair, &authority_keypair_file).unwrap();
+        let recipient_pubkey = Pubkey::new_unique();
+        let test_close = test_commands.clone().get_matches_from(vec![
+            "test",
+            "program",
+            "close",
+            &account_pubkey.to_string(),
+            "--authority",
+            &authority_keypair_file,
+            "--recipient",
+            &recipient_pubkey.to_string(),
+        ]);
+        assert_eq!(
+            parse_command(&test_close, &default_signer, &mut None).unwrap(),
+            CliCommandInfo {
+                command: CliCommand::Program(ProgramCliCommand::Close {
+                    account_pubkey,
+                    recipient_pubkey,
+                    authority_index: 1,
+                }),
+                signers: vec![
+                    read_keypair_file(&keypair_file).unwrap().into(),
+                    read_keypair_file(&authority_keypair_file).unwrap().into(),
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn test_cli_parse_extend() {
+        let test_commands = app("test", "desc", "version");
+
+        let default_keypair = Keypair::new();
+        let keypair_file = make_tmp_path("keypair_file");
+        write_keypair_file(&default_keypair, &keypair_file).unwrap();
+        let default_signer = DefaultSigner {
+            path: keypair_file.clone(),
+            arg_name: "".to_string(),
+        };
+
+        let program_pubkey = Pubkey::new_unique();
+        let test_extend = test_commands.clone().get_matches_from(vec![
+            "test",
+            "program",
+            "extend",
+            &program_pubkey.to_string(),
+            "1024",
+        ]);
+        assert_eq!(
+            parse_command(&test_extend, &default_signer, &mut None).unwrap(),
+            CliCommandInfo {
+                command: CliCommand::Program(ProgramCliCommand::Extend {
+                    program_pubkey,
+                    additional_bytes: 1024,
+                }),
+                signers: vec![read_keypair_file(&keypair_file).unwrap().into()],
+            }
+        );
+
+        assert!(test_commands
+            .clone()
+            .get_matches_from_safe(vec![
+                "test",
+                "program",
+                "extend",
+                &program_pubkey.to_string(),
+                "not-a-number",
+            ])
+            .is_err());
+    }
+
     #[test]
     fn test_cli_keypair_file() {
         solana_logger::setup();
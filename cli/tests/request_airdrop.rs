@@ -1,11 +1,15 @@
 use solana_cli::cli::{process_command, CliCommand, CliConfig};
-use solana_client::rpc_client::RpcClient;
+use solana_client::{
+    http_sender::HttpSender, recording_sender::RecordingSender, replay_sender::ReplaySender,
+    rpc_client::RpcClient,
+};
 use solana_core::test_validator::TestValidator;
 use solana_faucet::faucet::run_local_faucet;
 use solana_sdk::{
     commitment_config::CommitmentConfig,
-    signature::{Keypair, Signer},
+    signature::{keypair_from_seed, Keypair, Signer},
 };
+use std::{env, fs};
 
 #[test]
 fn test_cli_request_airdrop() {
@@ -36,3 +40,57 @@ fn test_cli_request_airdrop() {
         .unwrap();
     assert_eq!(balance, 50);
 }
+
+// A session with a local validator, replayed so that the test runs without
+// one. Run the test with SOLANA_RECORD_RPC set to record it again.
+const RPC_RECORDING: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/request_airdrop.jsonl"
+);
+
+#[test]
+fn test_cli_request_airdrop_replayed() {
+    // Fixed keys, so that the transactions sent match the recording
+    let mint_keypair = keypair_from_seed(&[1; 32]).unwrap();
+    let keypair = keypair_from_seed(&[2; 32]).unwrap();
+
+    let (rpc_client, _test_validator) = if env::var_os("SOLANA_RECORD_RPC").is_some() {
+        let test_validator = TestValidator::with_no_fees(mint_keypair.pubkey());
+        let _ = fs::remove_file(RPC_RECORDING);
+        let sender =
+            RecordingSender::new(HttpSender::new(test_validator.rpc_url()), RPC_RECORDING).unwrap();
+        (
+            RpcClient::new_sender(sender, CommitmentConfig::processed()),
+            Some(test_validator),
+        )
+    } else {
+        let sender = ReplaySender::load(RPC_RECORDING).unwrap();
+        (
+            RpcClient::new_sender(sender, CommitmentConfig::processed()),
+            None,
+        )
+    };
+
+    let faucet_addr = run_local_faucet(mint_keypair, None);
+
+    let mut bob_config = CliConfig::recent_for_tests();
+    bob_config.rpc_client = Some(rpc_client);
+    bob_config.command = CliCommand::Airdrop {
+        faucet_host: Some(faucet_addr.ip()),
+        faucet_port: faucet_addr.port(),
+        pubkey: None,
+        lamports: 50,
+    };
+    bob_config.signers = vec![&keypair];
+
+    let sig_response = process_command(&bob_config);
+    sig_response.unwrap();
+
+    let balance = bob_config
+        .rpc_client
+        .as_ref()
+        .unwrap()
+        .get_balance(&bob_config.signers[0].pubkey())
+        .unwrap();
+    assert_eq!(balance, 50);
+}
@@ -0,0 +1,597 @@
+//! An `RpcSender` that spreads requests over a pool of RPC endpoints,
+//! failing over to the next endpoint when one is unhealthy or unreachable
+
+use crate::{
+    client_error::{ClientError, ClientErrorKind, Result},
+    http_sender::{ExponentialBackoff, HttpSender, HttpSenderConfig},
+    rpc_custom_error,
+    rpc_request::{RpcError, RpcRequest},
+    rpc_sender::{RpcSender, RpcTransportStats},
+};
+use async_trait::async_trait;
+use futures::future::join_all;
+use log::*;
+use serde_json::Value;
+use solana_sdk::clock::Slot;
+use std::{
+    collections::HashSet,
+    fmt,
+    sync::{Arc, Mutex, RwLock},
+    time::{Duration, Instant},
+};
+
+/// Responses of endpoints that disagreed on the result of a cross-checked
+/// request
+#[derive(Debug, Clone)]
+pub struct RpcDisagreement {
+    pub request: RpcRequest,
+    pub params: Value,
+    /// The successful response of each cross-checked endpoint, by url
+    pub responses: Vec<(String, Value)>,
+}
+
+pub type DisagreementHandler = Arc<dyn Fn(&RpcDisagreement) + Send + Sync>;
+
+/// Requests that are sent to several endpoints, whose results are compared
+#[derive(Debug, Clone)]
+pub struct CrossCheckConfig {
+    pub requests: HashSet<RpcRequest>,
+    /// Number of healthy endpoints that each request is sent to
+    pub endpoints: usize,
+}
+
+#[derive(Clone)]
+pub struct FailoverSenderConfig {
+    /// How often the health and slot of each endpoint are checked
+    pub health_check_interval: Duration,
+    /// Timeout of the health and slot queries, which are never retried so
+    /// that an unreachable endpoint only briefly delays the request that
+    /// triggers the check
+    pub health_check_timeout: Duration,
+    /// Endpoints whose slot is further than this behind the most advanced
+    /// endpoint are considered unhealthy
+    pub max_slot_lag: u64,
+    pub cross_check: Option<CrossCheckConfig>,
+    /// Called whenever cross-checked endpoints disagree, in addition to a
+    /// warning being logged
+    pub on_disagreement: Option<DisagreementHandler>,
+}
+
+impl Default for FailoverSenderConfig {
+    fn default() -> Self {
+        Self {
+            health_check_interval: Duration::from_secs(10),
+            health_check_timeout: Duration::from_secs(1),
+            max_slot_lag: 150,
+            cross_check: None,
+            on_disagreement: None,
+        }
+    }
+}
+
+impl fmt::Debug for FailoverSenderConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FailoverSenderConfig")
+            .field("health_check_interval", &self.health_check_interval)
+            .field("health_check_timeout", &self.health_check_timeout)
+            .field("max_slot_lag", &self.max_slot_lag)
+            .field("cross_check", &self.cross_check)
+            .finish()
+    }
+}
+
+struct Endpoint {
+    url: String,
+    sender: Arc<dyn RpcSender + Send + Sync + 'static>,
+    /// Sends the health and slot queries
+    health_sender: Arc<dyn RpcSender + Send + Sync + 'static>,
+    healthy: RwLock<bool>,
+}
+
+impl Endpoint {
+    fn is_healthy(&self) -> bool {
+        *self.healthy.read().unwrap()
+    }
+
+    fn set_healthy(&self, healthy: bool) {
+        let mut w_healthy = self.healthy.write().unwrap();
+        if *w_healthy != healthy {
+            info!(
+                "RPC endpoint {} is {}",
+                self.url,
+                if healthy { "healthy" } else { "unhealthy" }
+            );
+        }
+        *w_healthy = healthy;
+    }
+
+    /// Returns the slot of a healthy endpoint
+    async fn check_health(&self) -> Option<Slot> {
+        if let Err(err) = self
+            .health_sender
+            .send(RpcRequest::GetHealth, Value::Null)
+            .await
+        {
+            debug!("RPC endpoint {} health check failed: {}", self.url, err);
+            return None;
+        }
+        match self
+            .health_sender
+            .send(RpcRequest::GetSlot, Value::Null)
+            .await
+        {
+            Ok(slot) => slot.as_u64(),
+            Err(err) => {
+                debug!("RPC endpoint {} slot query failed: {}", self.url, err);
+                None
+            }
+        }
+    }
+}
+
+pub struct FailoverSender {
+    endpoints: Vec<Endpoint>,
+    config: FailoverSenderConfig,
+    last_health_check: Mutex<Option<Instant>>,
+}
+
+impl FailoverSender {
+    pub fn new(urls: Vec<String>) -> Self {
+        Self::new_with_config(urls, FailoverSenderConfig::default())
+    }
+
+    pub fn new_with_config(urls: Vec<String>, config: FailoverSenderConfig) -> Self {
+        let health_sender_config = || HttpSenderConfig {
+            timeout: config.health_check_timeout,
+            retry_policy: Arc::new(ExponentialBackoff {
+                max_retries: 0,
+                ..ExponentialBackoff::default()
+            }),
+            ..HttpSenderConfig::default()
+        };
+        let endpoints = urls
+            .into_iter()
+            .map(|url| Endpoint {
+                sender: Arc::new(HttpSender::new(url.clone())),
+                health_sender: Arc::new(HttpSender::new_with_config(
+                    url.clone(),
+                    health_sender_config(),
+                )),
+                url,
+                healthy: RwLock::new(true),
+            })
+            .collect();
+        Self::new_with_endpoints(endpoints, config)
+    }
+
+    /// Endpoints are preferred in the order given, as long as they are healthy.
+    /// Health checks are sent through the same senders
+    pub fn new_with_senders(
+        senders: Vec<(String, Box<dyn RpcSender + Send + Sync + 'static>)>,
+        config: FailoverSenderConfig,
+    ) -> Self {
+        let endpoints = senders
+            .into_iter()
+            .map(|(url, sender)| {
+                let sender: Arc<dyn RpcSender + Send + Sync> = Arc::from(sender);
+                Endpoint {
+                    url,
+                    health_sender: sender.clone(),
+                    sender,
+                    healthy: RwLock::new(true),
+                }
+            })
+            .collect();
+        Self::new_with_endpoints(endpoints, config)
+    }
+
+    fn new_with_endpoints(endpoints: Vec<Endpoint>, config: FailoverSenderConfig) -> Self {
+        assert!(!endpoints.is_empty(), "no RPC endpoints");
+        Self {
+            endpoints,
+            config,
+            last_health_check: Mutex::new(None),
+        }
+    }
+
+    async fn maybe_check_health(&self) {
+        {
+            let mut last_health_check = self.last_health_check.lock().unwrap();
+            if let Some(last) = *last_health_check {
+                if last.elapsed() < self.config.health_check_interval {
+                    return;
+                }
+            }
+            *last_health_check = Some(Instant::now());
+        }
+
+        let slots = join_all(self.endpoints.iter().map(Endpoint::check_health)).await;
+        let max_slot = slots.iter().flatten().max().copied().unwrap_or_default();
+        for (endpoint, slot) in self.endpoints.iter().zip(slots) {
+            endpoint.set_healthy(matches!(
+                slot,
+                Some(slot) if max_slot.saturating_sub(slot) <= self.config.max_slot_lag
+            ));
+        }
+    }
+
+    /// Healthy endpoints first, unhealthy endpoints as a last resort
+    fn ordered_endpoints(&self) -> impl Iterator<Item = &Endpoint> {
+        let (healthy, unhealthy): (Vec<_>, Vec<_>) = self
+            .endpoints
+            .iter()
+            .partition(|endpoint| endpoint.is_healthy());
+        healthy.into_iter().chain(unhealthy)
+    }
+
+    async fn send_cross_checked(
+        &self,
+        request: RpcRequest,
+        params: Value,
+        endpoints: usize,
+    ) -> Result<Value> {
+        let endpoints: Vec<_> = self.ordered_endpoints().take(endpoints.max(1)).collect();
+        let results = join_all(
+            endpoints
+                .iter()
+                .map(|endpoint| endpoint.sender.send(request, params.clone())),
+        )
+        .await;
+
+        let mut first_err = None;
+        let mut responses = vec![];
+        for (endpoint, result) in endpoints.iter().zip(results) {
+            match result {
+                Ok(response) => responses.push((endpoint.url.clone(), response)),
+                Err(err) => {
+                    warn!("RPC endpoint {} {} failed: {}", endpoint.url, request, err);
+                    first_err.get_or_insert(err);
+                }
+            }
+        }
+        if responses.is_empty() {
+            return Err(first_err.unwrap());
+        }
+
+        // Group the responses by value. Ties go to the group answered at the
+        // most recent slot, then to the most preferred endpoint
+        let mut groups: Vec<ResponseGroup> = vec![];
+        for (index, (_url, response)) in responses.iter().enumerate() {
+            let value = response_value(response);
+            let slot = response_slot(response);
+            match groups.iter_mut().find(|group| group.value == value) {
+                Some(group) => {
+                    group.count += 1;
+                    group.max_slot = group.max_slot.max(slot);
+                }
+                None => groups.push(ResponseGroup {
+                    value,
+                    first_index: index,
+                    count: 1,
+                    max_slot: slot,
+                }),
+            }
+        }
+        let majority_index = groups
+            .iter()
+            .max_by(|a, b| {
+                (a.count, a.max_slot)
+                    .cmp(&(b.count, b.max_slot))
+                    .then(b.first_index.cmp(&a.first_index))
+            })
+            .unwrap()
+            .first_index;
+
+        // Endpoints that answered at different slots may rightly disagree,
+        // only differing values at a common slot are reported
+        let disagree = responses.iter().enumerate().any(|(i, (_url, a))| {
+            responses[i + 1..].iter().any(|(_url, b)| {
+                response_slot(a) == response_slot(b) && response_value(a) != response_value(b)
+            })
+        });
+        if disagree {
+            let disagreement = RpcDisagreement {
+                request,
+                params,
+                responses: responses.clone(),
+            };
+            warn!("RPC endpoints disagree: {:?}", disagreement);
+            if let Some(on_disagreement) = &self.config.on_disagreement {
+                on_disagreement(&disagreement);
+            }
+        }
+        Ok(responses.swap_remove(majority_index).1)
+    }
+}
+
+struct ResponseGroup<'a> {
+    value: &'a Value,
+    /// Index of the first response with this value
+    first_index: usize,
+    count: usize,
+    max_slot: Option<Slot>,
+}
+
+/// The context slot of responses that carry a context
+fn response_slot(response: &Value) -> Option<Slot> {
+    response.get("context")?.get("slot")?.as_u64()
+}
+
+/// The `value` of responses that carry a context, or the whole response
+fn response_value(response: &Value) -> &Value {
+    match (response.get("context"), response.get("value")) {
+        (Some(_context), Some(value)) => value,
+        _ => response,
+    }
+}
+
+/// Whether a failed request should be sent to the next endpoint. Requests that aren't idempotent
+/// only fail over when they could not have reached the endpoint
+fn should_fail_over(is_idempotent: bool, err: &ClientError) -> bool {
+    match err.kind() {
+        ClientErrorKind::Reqwest(err) if err.is_connect() => true,
+        ClientErrorKind::Reqwest(_) | ClientErrorKind::Io(_) => is_idempotent,
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. }) => {
+            is_idempotent
+                && (*code == rpc_custom_error::JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY
+                    || *code == rpc_custom_error::JSON_RPC_SERVER_ERROR_BLOCK_CLEANED_UP)
+        }
+        _ => false,
+    }
+}
+
+#[async_trait]
+impl RpcSender for FailoverSender {
+    async fn send(&self, request: RpcRequest, params: Value) -> Result<Value> {
+        self.maybe_check_health().await;
+        if let Some(cross_check) = &self.config.cross_check {
+            if cross_check.requests.contains(&request) {
+                return self
+                    .send_cross_checked(request, params, cross_check.endpoints)
+                    .await;
+            }
+        }
+
+        let mut last_err = None;
+        for endpoint in self.ordered_endpoints() {
+            match endpoint.sender.send(request, params.clone()).await {
+                Ok(response) => return Ok(response),
+                Err(err) if should_fail_over(request.is_idempotent(), &err) => {
+                    warn!("RPC endpoint {} {} failed: {}", endpoint.url, request, err);
+                    endpoint.set_healthy(false);
+                    last_err = Some(err);
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        Err(last_err.unwrap())
+    }
+
+    /// Batches are sent to one endpoint at a time, without cross-checking
+    async fn send_batch(&self, requests: Vec<(RpcRequest, Value)>) -> Result<Vec<Result<Value>>> {
+        self.maybe_check_health().await;
+        let is_idempotent = requests
+            .iter()
+            .all(|(request, _params)| request.is_idempotent());
+
+        let mut last_err = None;
+        for endpoint in self.ordered_endpoints() {
+            match endpoint.sender.send_batch(requests.clone()).await {
+                Ok(results) => return Ok(results),
+                Err(err) if should_fail_over(is_idempotent, &err) => {
+                    warn!("RPC endpoint {} batch failed: {}", endpoint.url, err);
+                    endpoint.set_healthy(false);
+                    last_err = Some(err);
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        Err(last_err.unwrap())
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.endpoints
+            .iter()
+            .map(|endpoint| endpoint.sender.get_transport_stats())
+            .fold(RpcTransportStats::default(), |mut total, stats| {
+                total.request_count += stats.request_count;
+                total.error_count += stats.error_count;
+                total.retry_count += stats.retry_count;
+                total.elapsed_time += stats.elapsed_time;
+                total.max_elapsed_time = total.max_elapsed_time.max(stats.max_elapsed_time);
+                total.rate_limited_time += stats.rate_limited_time;
+                total
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        mock_sender::{MockSender, Mocks},
+        rpc_response::{Response, RpcResponseContext},
+    };
+    use serde_json::json;
+    use std::io;
+
+    /// Passes health checks, but fails every other request
+    struct FailingSender;
+
+    #[async_trait]
+    impl RpcSender for FailingSender {
+        async fn send(&self, request: RpcRequest, _params: Value) -> Result<Value> {
+            match request {
+                RpcRequest::GetHealth => Ok(json!("ok")),
+                RpcRequest::GetSlot => Ok(json!(0)),
+                _ => Err(io::Error::new(io::ErrorKind::ConnectionReset, "reset").into()),
+            }
+        }
+
+        fn get_transport_stats(&self) -> RpcTransportStats {
+            RpcTransportStats::default()
+        }
+    }
+
+    fn balance_response(slot: Slot, balance: u64) -> Value {
+        json!(Response {
+            context: RpcResponseContext { slot },
+            value: balance,
+        })
+    }
+
+    fn mock_sender(mocks: Vec<(RpcRequest, Value)>) -> Box<dyn RpcSender + Send + Sync> {
+        Box::new(MockSender::new_with_mocks(
+            "succeeds".to_string(),
+            mocks.into_iter().collect::<Mocks>(),
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_failover() {
+        let new_sender = || {
+            FailoverSender::new_with_senders(
+                vec![
+                    ("failing".to_string(), Box::new(FailingSender)),
+                    (
+                        "mock".to_string(),
+                        mock_sender(vec![(RpcRequest::GetBalance, balance_response(1, 50))]),
+                    ),
+                ],
+                FailoverSenderConfig::default(),
+            )
+        };
+
+        let sender = new_sender();
+        let balance = sender
+            .send(RpcRequest::GetBalance, json!(["pubkey"]))
+            .await
+            .unwrap();
+        assert_eq!(balance["value"], 50);
+        assert!(!sender.endpoints[0].is_healthy());
+        assert!(sender.endpoints[1].is_healthy());
+
+        // Requests that may not be repeated are not sent to another endpoint
+        // after an ambiguous failure
+        let sender = new_sender();
+        assert!(sender
+            .send(RpcRequest::SendTransaction, json!(["transaction"]))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_unreachable_endpoints_are_not_retried() {
+        let unreachable_url = || {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            format!("http://{}", listener.local_addr().unwrap())
+        };
+        let sender = FailoverSender::new(vec![unreachable_url(), unreachable_url()]);
+        assert!(sender
+            .send(RpcRequest::GetBalance, json!(["pubkey"]))
+            .await
+            .is_err());
+        for endpoint in &sender.endpoints {
+            assert!(!endpoint.is_healthy());
+            assert_eq!(endpoint.sender.get_transport_stats().retry_count, 0);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_slot_lag() {
+        let sender = FailoverSender::new_with_senders(
+            vec![
+                (
+                    "lagging".to_string(),
+                    mock_sender(vec![
+                        (RpcRequest::GetSlot, json!(100)),
+                        (RpcRequest::GetBalance, balance_response(100, 1)),
+                    ]),
+                ),
+                (
+                    "current".to_string(),
+                    mock_sender(vec![
+                        (RpcRequest::GetSlot, json!(1_000)),
+                        (RpcRequest::GetBalance, balance_response(1_000, 2)),
+                    ]),
+                ),
+            ],
+            FailoverSenderConfig::default(),
+        );
+        let balance = sender
+            .send(RpcRequest::GetBalance, json!(["pubkey"]))
+            .await
+            .unwrap();
+        assert_eq!(balance["value"], 2);
+        assert!(!sender.endpoints[0].is_healthy());
+    }
+
+    #[tokio::test]
+    async fn test_cross_check() {
+        let disagreements = Arc::new(Mutex::new(vec![]));
+        let config = FailoverSenderConfig {
+            cross_check: Some(CrossCheckConfig {
+                requests: vec![RpcRequest::GetBalance].into_iter().collect(),
+                endpoints: 3,
+            }),
+            on_disagreement: Some({
+                let disagreements = disagreements.clone();
+                Arc::new(move |disagreement: &RpcDisagreement| {
+                    disagreements.lock().unwrap().push(disagreement.clone())
+                })
+            }),
+            ..FailoverSenderConfig::default()
+        };
+        let new_sender = |responses: Vec<(&str, Value)>| {
+            FailoverSender::new_with_senders(
+                responses
+                    .into_iter()
+                    .map(|(url, response)| {
+                        (
+                            url.to_string(),
+                            mock_sender(vec![(RpcRequest::GetBalance, response)]),
+                        )
+                    })
+                    .collect(),
+                config.clone(),
+            )
+        };
+
+        // Endpoints answering at different slots are not in disagreement, the
+        // most recent answer wins ties
+        let sender = new_sender(vec![
+            ("a", balance_response(1, 10)),
+            ("b", balance_response(2, 20)),
+        ]);
+        let balance = sender
+            .send(RpcRequest::GetBalance, json!(["pubkey"]))
+            .await
+            .unwrap();
+        assert_eq!(balance["value"], 20);
+        assert!(disagreements.lock().unwrap().is_empty());
+
+        // The majority wins
+        let sender = new_sender(vec![
+            ("a", balance_response(2, 10)),
+            ("b", balance_response(2, 20)),
+            ("c", balance_response(3, 20)),
+        ]);
+        let balance = sender
+            .send(RpcRequest::GetBalance, json!(["pubkey"]))
+            .await
+            .unwrap();
+        assert_eq!(balance["value"], 20);
+
+        // Requests that are not cross-checked go to the preferred endpoint
+        assert_eq!(
+            sender.send(RpcRequest::GetSlot, Value::Null).await.unwrap(),
+            0
+        );
+
+        let disagreements = disagreements.lock().unwrap();
+        assert_eq!(disagreements.len(), 1);
+        assert_eq!(disagreements[0].request, RpcRequest::GetBalance);
+        assert_eq!(disagreements[0].responses.len(), 3);
+        assert_eq!(disagreements[0].responses[0].0, "a");
+    }
+}
@@ -1,31 +1,185 @@
 use crate::{
-    client_error::Result,
+    client_error::{ClientError, Result},
     rpc_custom_error,
     rpc_request::{RpcError, RpcRequest, RpcResponseErrorData},
     rpc_response::RpcSimulateTransactionResult,
-    rpc_sender::RpcSender,
+    rpc_sender::{RpcSender, RpcTransportStats},
 };
+use async_trait::async_trait;
 use log::*;
-use reqwest::{self, header::CONTENT_TYPE, StatusCode};
-use std::{thread::sleep, time::Duration};
+use rand::Rng;
+use reqwest::{
+    self,
+    header::{CONTENT_TYPE, RETRY_AFTER},
+    StatusCode,
+};
+use std::{
+    cmp::min,
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+    time::{Duration, Instant},
+};
+use tokio::time::sleep;
+
+/// Why an attempt to send a request failed in a way that may be retried
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryCause {
+    /// The server responded with HTTP 429, optionally asking the client to
+    /// wait `retry_after` before trying again
+    TooManyRequests { retry_after: Option<Duration> },
+    /// The connection could not be established or was reset before a
+    /// response was received
+    Connection,
+}
+
+/// Decides whether, and after how long, a failed request is retried
+pub trait RetryPolicy: Send + Sync {
+    /// Returns the delay before retry number `retry` (starting at 1) of
+    /// `request`, or `None` to return the failure to the caller
+    fn retry_delay(&self, request: RpcRequest, retry: usize, cause: RetryCause)
+        -> Option<Duration>;
+}
+
+/// Retries with exponentially growing delays.
+///
+/// Rate limited requests wait for the server's `Retry-After` delay when one
+/// is given, up to `max_delay`. Connection failures are only retried when
+/// `retry_connection_errors` is set, and then only for idempotent requests.
+#[derive(Debug, Clone)]
+pub struct ExponentialBackoff {
+    /// Number of retries before giving up
+    pub max_retries: usize,
+    /// Delay before the first retry, doubled for every following retry
+    pub initial_delay: Duration,
+    /// Upper bound of every delay, including those requested by the server
+    pub max_delay: Duration,
+    /// Pick each delay at random between half and all of its backoff so
+    /// that clients which failed together do not retry together
+    pub jitter: bool,
+    /// Also retry requests that failed to connect. Off by default, so that an
+    /// unreachable server is reported at once rather than after every retry
+    pub retry_connection_errors: bool,
+}
+
+impl Default for ExponentialBackoff {
+    fn default() -> Self {
+        Self {
+            max_retries: 5,
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(10),
+            jitter: true,
+            retry_connection_errors: false,
+        }
+    }
+}
+
+impl RetryPolicy for ExponentialBackoff {
+    fn retry_delay(
+        &self,
+        request: RpcRequest,
+        retry: usize,
+        cause: RetryCause,
+    ) -> Option<Duration> {
+        if retry == 0 || retry > self.max_retries {
+            return None;
+        }
+        let retry_after = match cause {
+            RetryCause::TooManyRequests { retry_after } => retry_after,
+            RetryCause::Connection if self.retry_connection_errors && request.is_idempotent() => {
+                None
+            }
+            RetryCause::Connection => return None,
+        };
+        if let Some(retry_after) = retry_after {
+            return Some(min(retry_after, self.max_delay));
+        }
+
+        let exponent = min(retry - 1, 31) as u32;
+        let backoff = self
+            .initial_delay
+            .checked_mul(1 << exponent)
+            .map_or(self.max_delay, |backoff| min(backoff, self.max_delay));
+        if self.jitter {
+            let backoff_nanos = backoff.as_nanos() as u64;
+            Some(Duration::from_nanos(
+                rand::thread_rng().gen_range(backoff_nanos / 2, backoff_nanos + 1),
+            ))
+        } else {
+            Some(backoff)
+        }
+    }
+}
+
+pub struct HttpSenderConfig {
+    /// Timeout of requests without an entry in `request_timeouts`
+    pub timeout: Duration,
+    /// Timeouts of individual request types, e.g. a longer timeout for
+    /// `getProgramAccounts`
+    pub request_timeouts: HashMap<RpcRequest, Duration>,
+    pub retry_policy: Arc<dyn RetryPolicy>,
+}
+
+impl Default for HttpSenderConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30),
+            request_timeouts: HashMap::new(),
+            retry_policy: Arc::new(ExponentialBackoff::default()),
+        }
+    }
+}
 
 pub struct HttpSender {
-    client: reqwest::blocking::Client,
+    client: reqwest::Client,
     url: String,
+    config: HttpSenderConfig,
+    request_id: AtomicU64,
+    stats: RwLock<RpcTransportStats>,
 }
 
 impl HttpSender {
     pub fn new(url: String) -> Self {
-        Self::new_with_timeout(url, Duration::from_secs(30))
+        Self::new_with_config(url, HttpSenderConfig::default())
     }
 
     pub fn new_with_timeout(url: String, timeout: Duration) -> Self {
-        let client = reqwest::blocking::Client::builder()
-            .timeout(timeout)
+        Self::new_with_config(
+            url,
+            HttpSenderConfig {
+                timeout,
+                ..HttpSenderConfig::default()
+            },
+        )
+    }
+
+    pub fn new_with_config(url: String, config: HttpSenderConfig) -> Self {
+        let client = reqwest::Client::builder()
             .build()
             .expect("build rpc client");
 
-        Self { client, url }
+        Self {
+            client,
+            url,
+            config,
+            request_id: AtomicU64::new(0),
+            stats: RwLock::new(RpcTransportStats::default()),
+        }
+    }
+
+    fn record_request(&self, elapsed_time: Duration, rate_limited_time: Duration, retries: usize) {
+        let mut stats = self.stats.write().unwrap();
+        stats.request_count += 1;
+        stats.retry_count += retries;
+        stats.elapsed_time += elapsed_time;
+        stats.max_elapsed_time = stats.max_elapsed_time.max(elapsed_time);
+        stats.rate_limited_time += rate_limited_time;
+    }
+
+    fn record_error(&self) {
+        self.stats.write().unwrap().error_count += 1;
     }
 }
 
@@ -36,87 +190,374 @@ struct RpcErrorObject {
     data: serde_json::Value,
 }
 
-impl RpcSender for HttpSender {
-    fn send(&self, request: RpcRequest, params: serde_json::Value) -> Result<serde_json::Value> {
-        // Concurrent requests are not supported so reuse the same request id for all requests
-        let request_id = 1;
+fn retry_after(response: &reqwest::Response) -> Option<Duration> {
+    // Only the delay-seconds form of the header is supported, HTTP dates are
+    // left to the retry policy's own backoff
+    response
+        .headers()
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
 
-        let request_json = request.build_request_json(request_id, params);
+pub(crate) fn parse_response_json(json: serde_json::Value) -> Result<serde_json::Value> {
+    if json["error"].is_object() {
+        return match serde_json::from_value::<RpcErrorObject>(json["error"].clone()) {
+            Ok(rpc_error_object) => {
+                let data = match rpc_error_object.code {
+                    rpc_custom_error::JSON_RPC_SERVER_ERROR_SEND_TRANSACTION_PREFLIGHT_FAILURE => {
+                        match serde_json::from_value::<RpcSimulateTransactionResult>(
+                            json["error"]["data"].clone(),
+                        ) {
+                            Ok(data) => RpcResponseErrorData::SendTransactionPreflightFailure(data),
+                            Err(err) => {
+                                debug!(
+                                    "Failed to deserialize RpcSimulateTransactionResult: {:?}",
+                                    err
+                                );
+                                RpcResponseErrorData::Empty
+                            }
+                        }
+                    }
+                    rpc_custom_error::JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY => {
+                        match serde_json::from_value::<rpc_custom_error::NodeUnhealthyErrorData>(
+                            json["error"]["data"].clone(),
+                        ) {
+                            Ok(rpc_custom_error::NodeUnhealthyErrorData { num_slots_behind }) => {
+                                RpcResponseErrorData::NodeUnhealthy { num_slots_behind }
+                            }
+                            Err(_err) => RpcResponseErrorData::Empty,
+                        }
+                    }
+                    _ => RpcResponseErrorData::Empty,
+                };
 
-        let mut too_many_requests_retries = 5;
-        loop {
-            match self
+                Err(RpcError::RpcResponseError {
+                    code: rpc_error_object.code,
+                    message: rpc_error_object.message,
+                    data,
+                }
+                .into())
+            }
+            Err(err) => Err(RpcError::RpcRequestError(format!(
+                "Failed to deserialize RPC error response: {} [{}]",
+                serde_json::to_string(&json["error"]).unwrap(),
+                err
+            ))
+            .into()),
+        };
+    }
+    Ok(json["result"].clone())
+}
+
+impl HttpSender {
+    /// Post `body` to the server, retrying as `request` allows, and return
+    /// the JSON response
+    async fn post(
+        &self,
+        request: RpcRequest,
+        body: String,
+        timeout: Duration,
+    ) -> Result<serde_json::Value> {
+        let start = Instant::now();
+        let mut rate_limited_time = Duration::default();
+        let mut retries = 0;
+        let result = loop {
+            let (cause, err): (RetryCause, ClientError) = match self
                 .client
                 .post(&self.url)
                 .header(CONTENT_TYPE, "application/json")
-                .body(request_json.to_string())
+                .body(body.clone())
+                .timeout(timeout)
                 .send()
+                .await
             {
                 Ok(response) => {
                     if !response.status().is_success() {
-                        if response.status() == StatusCode::TOO_MANY_REQUESTS
-                            && too_many_requests_retries > 0
-                        {
-                            too_many_requests_retries -= 1;
-                            debug!(
-                                "Server responded with {:?}, {} retries left",
-                                response, too_many_requests_retries
-                            );
-
-                            // Sleep for 500ms to give the server a break
-                            sleep(Duration::from_millis(500));
-                            continue;
+                        if response.status() != StatusCode::TOO_MANY_REQUESTS {
+                            break Err(response.error_for_status().unwrap_err().into());
                         }
-                        return Err(response.error_for_status().unwrap_err().into());
-                    }
-
-                    let json: serde_json::Value = serde_json::from_str(&response.text()?)?;
-                    if json["error"].is_object() {
-                        return match serde_json::from_value::<RpcErrorObject>(json["error"].clone())
-                        {
-                            Ok(rpc_error_object) => {
-                                let data = match rpc_error_object.code {
-                                    rpc_custom_error::JSON_RPC_SERVER_ERROR_SEND_TRANSACTION_PREFLIGHT_FAILURE => {
-                                        match serde_json::from_value::<RpcSimulateTransactionResult>(json["error"]["data"].clone()) {
-                                            Ok(data) => RpcResponseErrorData::SendTransactionPreflightFailure(data),
-                                            Err(err) => {
-                                                debug!("Failed to deserialize RpcSimulateTransactionResult: {:?}", err);
-                                                RpcResponseErrorData::Empty
-                                            }
-                                        }
-                                    },
-                                    rpc_custom_error::JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY => {
-                                        match serde_json::from_value::<rpc_custom_error::NodeUnhealthyErrorData>(json["error"]["data"].clone()) {
-                                            Ok(rpc_custom_error::NodeUnhealthyErrorData {num_slots_behind}) => RpcResponseErrorData::NodeUnhealthy {num_slots_behind},
-                                            Err(_err) => {
-                                                RpcResponseErrorData::Empty
-                                            }
-                                        }
-                                    },
-                                    _ => RpcResponseErrorData::Empty
-                                };
-
-                                Err(RpcError::RpcResponseError {
-                                    code: rpc_error_object.code,
-                                    message: rpc_error_object.message,
-                                    data,
-                                }
-                                .into())
-                            }
-                            Err(err) => Err(RpcError::RpcRequestError(format!(
-                                "Failed to deserialize RPC error response: {} [{}]",
-                                serde_json::to_string(&json["error"]).unwrap(),
-                                err
-                            ))
-                            .into()),
+                        let retry_after = retry_after(&response);
+                        debug!("Server responded with {:?}", response);
+                        (
+                            RetryCause::TooManyRequests { retry_after },
+                            response.error_for_status().unwrap_err().into(),
+                        )
+                    } else {
+                        break match response.text().await {
+                            Ok(text) => serde_json::from_str(&text).map_err(ClientError::from),
+                            Err(err) => Err(err.into()),
                         };
                     }
-                    return Ok(json["result"].clone());
                 }
-                Err(err) => {
-                    return Err(err.into());
+                Err(err) if !err.is_timeout() && (err.is_connect() || err.is_request()) => {
+                    debug!("Request failed: {:?}", err);
+                    (RetryCause::Connection, err.into())
+                }
+                Err(err) => break Err(err.into()),
+            };
+
+            match self
+                .config
+                .retry_policy
+                .retry_delay(request, retries + 1, cause)
+            {
+                Some(delay) => {
+                    retries += 1;
+                    debug!(
+                        "Retrying {} in {:?}, attempt {}: {:?}",
+                        request, delay, retries, cause
+                    );
+                    if let RetryCause::TooManyRequests { .. } = cause {
+                        rate_limited_time += delay;
+                    }
+                    sleep(delay).await;
                 }
+                None => break Err(err),
             }
+        };
+
+        self.record_request(start.elapsed(), rate_limited_time, retries);
+        result
+    }
+
+    fn timeout(&self, request: RpcRequest) -> Duration {
+        self.config
+            .request_timeouts
+            .get(&request)
+            .copied()
+            .unwrap_or(self.config.timeout)
+    }
+}
+
+#[async_trait]
+impl RpcSender for HttpSender {
+    async fn send(
+        &self,
+        request: RpcRequest,
+        params: serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        let request_id = self.request_id.fetch_add(1, Ordering::Relaxed);
+        let request_json = request.build_request_json(request_id, params).to_string();
+
+        let result = self
+            .post(request, request_json, self.timeout(request))
+            .await
+            .and_then(parse_response_json);
+        if result.is_err() {
+            self.record_error();
+        }
+        result
+    }
+
+    async fn send_batch(
+        &self,
+        requests: Vec<(RpcRequest, serde_json::Value)>,
+    ) -> Result<Vec<Result<serde_json::Value>>> {
+        if requests.is_empty() {
+            return Ok(vec![]);
         }
+        // The batch is only retried after a connection failure if every
+        // request in it may be
+        let retry_request = requests
+            .iter()
+            .map(|(request, _params)| *request)
+            .find(|request| !request.is_idempotent())
+            .unwrap_or(requests[0].0);
+        let timeout = requests
+            .iter()
+            .map(|(request, _params)| self.timeout(*request))
+            .max()
+            .unwrap_or(self.config.timeout);
+
+        let first_request_id = self
+            .request_id
+            .fetch_add(requests.len() as u64, Ordering::Relaxed);
+        let request_ids = first_request_id..first_request_id + requests.len() as u64;
+        let batch_json: Vec<_> = requests
+            .into_iter()
+            .zip(request_ids.clone())
+            .map(|((request, params), request_id)| request.build_request_json(request_id, params))
+            .collect();
+
+        let responses = match self
+            .post(
+                retry_request,
+                serde_json::Value::Array(batch_json).to_string(),
+                timeout,
+            )
+            .await
+        {
+            // A server that rejects the whole batch answers with a single error
+            Ok(serde_json::Value::Array(responses)) => Ok(responses),
+            Ok(json) => Err(parse_response_json(json).err().unwrap_or_else(|| {
+                RpcError::RpcRequestError("Unexpected response to batch request".to_string()).into()
+            })),
+            Err(err) => Err(err),
+        }
+        .map_err(|err| {
+            self.record_error();
+            err
+        })?;
+
+        let mut responses: HashMap<u64, serde_json::Value> = responses
+            .into_iter()
+            .filter_map(|response| Some((response["id"].as_u64()?, response)))
+            .collect();
+        Ok(request_ids
+            .map(|request_id| match responses.remove(&request_id) {
+                Some(response) => parse_response_json(response),
+                None => Err(RpcError::RpcRequestError(format!(
+                    "Missing response to batch request {}",
+                    request_id
+                ))
+                .into()),
+            })
+            .collect())
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.stats.read().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    #[test]
+    fn test_exponential_backoff() {
+        let policy = ExponentialBackoff {
+            max_retries: 4,
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(300),
+            jitter: false,
+            retry_connection_errors: false,
+        };
+        let rate_limited = RetryCause::TooManyRequests { retry_after: None };
+        let delays: Vec<_> = (1..=5)
+            .map(|retry| policy.retry_delay(RpcRequest::GetBalance, retry, rate_limited))
+            .collect();
+        assert_eq!(
+            delays,
+            vec![
+                Some(Duration::from_millis(100)),
+                Some(Duration::from_millis(200)),
+                Some(Duration::from_millis(300)),
+                Some(Duration::from_millis(300)),
+                None,
+            ]
+        );
+
+        // The server's requested delay is honored, up to the maximum delay
+        let retry_after = |retry_after| RetryCause::TooManyRequests {
+            retry_after: Some(retry_after),
+        };
+        assert_eq!(
+            policy.retry_delay(
+                RpcRequest::GetBalance,
+                1,
+                retry_after(Duration::from_millis(250))
+            ),
+            Some(Duration::from_millis(250))
+        );
+        assert_eq!(
+            policy.retry_delay(
+                RpcRequest::GetBalance,
+                1,
+                retry_after(Duration::from_secs(2))
+            ),
+            Some(Duration::from_millis(300))
+        );
+
+        // Connection failures are only retried on request, and then only for idempotent requests
+        assert_eq!(
+            policy.retry_delay(RpcRequest::GetBalance, 1, RetryCause::Connection),
+            None
+        );
+        let policy = ExponentialBackoff {
+            retry_connection_errors: true,
+            ..policy
+        };
+        assert_eq!(
+            policy.retry_delay(RpcRequest::GetBalance, 1, RetryCause::Connection),
+            Some(Duration::from_millis(100))
+        );
+        assert_eq!(
+            policy.retry_delay(RpcRequest::SendTransaction, 1, RetryCause::Connection),
+            None
+        );
+
+        let policy = ExponentialBackoff {
+            jitter: true,
+            ..policy
+        };
+        for _ in 0..10 {
+            let delay = policy
+                .retry_delay(RpcRequest::GetBalance, 2, rate_limited)
+                .unwrap();
+            assert!(delay >= Duration::from_millis(100) && delay <= Duration::from_millis(200));
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_rate_limit_retry() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            let responses = [
+                "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 0\r\nConnection: close\r\nContent-Length: 0\r\n\r\n"
+                    .to_string(),
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nConnection: close\r\nContent-Length: {}\r\n\r\n{}",
+                    r#"{"jsonrpc":"2.0","result":50,"id":1}"#.len(),
+                    r#"{"jsonrpc":"2.0","result":50,"id":1}"#
+                ),
+            ];
+            for response in responses.iter() {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut buf = [0; 4096];
+                let _ = stream.read(&mut buf).unwrap();
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+
+        let sender = HttpSender::new(url);
+        let balance = sender
+            .send(RpcRequest::GetBalance, serde_json::Value::Null)
+            .await
+            .unwrap();
+        assert_eq!(balance, serde_json::json!(50));
+
+        let stats = sender.get_transport_stats();
+        assert_eq!(stats.request_count, 1);
+        assert_eq!(stats.retry_count, 1);
+        assert_eq!(stats.error_count, 0);
+        assert_eq!(stats.rate_limited_time, Duration::default());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_connection_refused_is_not_retried() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+
+        let sender = HttpSender::new(url);
+        assert!(sender
+            .send(RpcRequest::GetBalance, serde_json::Value::Null)
+            .await
+            .is_err());
+        let stats = sender.get_transport_stats();
+        assert_eq!(stats.retry_count, 0);
+        assert_eq!(stats.error_count, 1);
     }
 }
@@ -4,11 +4,19 @@ extern crate serde_derive;
 
 pub mod blockhash_query;
 pub mod client_error;
+pub mod failover_sender;
 pub mod http_sender;
 pub mod mock_sender;
+pub mod multiplexed_pubsub_client;
+pub mod nonblocking;
 pub mod nonce_utils;
 pub mod perf_utils;
 pub mod pubsub_client;
+pub mod recording_sender;
+pub mod replay_sender;
+#[cfg(any(test, feature = "replay-server"))]
+pub mod replay_server;
+pub mod rpc_batch;
 pub mod rpc_cache;
 pub mod rpc_client;
 pub mod rpc_config;
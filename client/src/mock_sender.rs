@@ -2,8 +2,9 @@ use crate::{
     client_error::Result,
     rpc_request::RpcRequest,
     rpc_response::{Response, RpcResponseContext, RpcVersionInfo},
-    rpc_sender::RpcSender,
+    rpc_sender::{RpcSender, RpcTransportStats},
 };
+use async_trait::async_trait;
 use serde_json::{json, Number, Value};
 use solana_sdk::{
     epoch_info::EpochInfo,
@@ -39,8 +40,17 @@ impl MockSender {
     }
 }
 
+#[async_trait]
 impl RpcSender for MockSender {
-    fn send(&self, request: RpcRequest, params: serde_json::Value) -> Result<serde_json::Value> {
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        RpcTransportStats::default()
+    }
+
+    async fn send(
+        &self,
+        request: RpcRequest,
+        params: serde_json::Value,
+    ) -> Result<serde_json::Value> {
         if let Some(value) = self.mocks.write().unwrap().remove(&request) {
             return Ok(value);
         }
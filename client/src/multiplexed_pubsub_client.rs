@@ -0,0 +1,697 @@
+//! A PubSub client that multiplexes any number of subscriptions over a single websocket
+//! connection. One background thread owns the connection and routes every notification to the
+//! typed channel of its subscription. If the connection is lost, the thread reconnects and
+//! resubscribes all active subscriptions, so subscribers keep receiving notifications from the
+//! same channel.
+
+use crate::{
+    pubsub_client::PubsubClientError,
+    rpc_config::{
+        RpcAccountInfoConfig, RpcBlockSubscribeConfig, RpcBlockSubscribeFilter,
+        RpcProgramAccountsConfig, RpcSignatureSubscribeConfig, RpcTransactionLogsConfig,
+        RpcTransactionLogsFilter,
+    },
+    rpc_response::{
+        Response as RpcResponse, RpcBlockUpdate, RpcKeyedAccount, RpcLogsResponse,
+        RpcSignatureResult, RpcVote, SlotInfo,
+    },
+};
+use log::*;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use solana_account_decoder::UiAccount;
+use solana_sdk::{clock::Slot, pubkey::Pubkey, signature::Signature};
+use std::{
+    collections::HashMap,
+    io,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc::{channel, Iter, Receiver, RecvError, RecvTimeoutError, Sender, TryRecvError},
+        Arc, Mutex,
+    },
+    thread::{sleep, Builder, JoinHandle},
+    time::{Duration, Instant},
+};
+use tungstenite::{client::AutoStream, connect, stream::Stream, Message, WebSocket};
+use url::Url;
+
+/// How long the connection thread waits on the socket before servicing new requests
+const SOCKET_READ_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Delay before the first reconnection attempt, doubled after every failed attempt
+const INITIAL_RECONNECT_DELAY: Duration = Duration::from_millis(250);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// How long a subscribe call waits for the server to accept or reject the subscription
+const SUBSCRIBE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Delivers a notification to its subscription, returning false once the subscription's
+/// receiver has been dropped
+type Notifier = Box<dyn Fn(Value) -> bool + Send>;
+
+enum Command {
+    Subscribe {
+        id: u64,
+        operation: &'static str,
+        params: Value,
+        notifier: Notifier,
+        reply: Sender<Result<(), PubsubClientError>>,
+    },
+    Unsubscribe {
+        id: u64,
+    },
+}
+
+/// A typed stream of notifications. Dropping the subscription unsubscribes from the server.
+pub struct PubsubSubscription<T> {
+    id: u64,
+    receiver: Receiver<T>,
+    command_sender: Sender<Command>,
+}
+
+impl<T> PubsubSubscription<T> {
+    pub fn recv(&self) -> Result<T, RecvError> {
+        self.receiver.recv()
+    }
+
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        self.receiver.recv_timeout(timeout)
+    }
+
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        self.receiver.try_recv()
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.receiver.iter()
+    }
+}
+
+impl<T> Drop for PubsubSubscription<T> {
+    fn drop(&mut self) {
+        // The connection thread may already be gone, in which case there is nothing to cancel
+        let _ = self
+            .command_sender
+            .send(Command::Unsubscribe { id: self.id });
+    }
+}
+
+pub struct MultiplexedPubsubClient {
+    command_sender: Mutex<Sender<Command>>,
+    next_subscription_id: AtomicU64,
+    exit: Arc<AtomicBool>,
+    t_connection: Option<JoinHandle<()>>,
+}
+
+impl Drop for MultiplexedPubsubClient {
+    fn drop(&mut self) {
+        self.exit.store(true, Ordering::Relaxed);
+        if let Some(t_connection) = self.t_connection.take() {
+            t_connection
+                .join()
+                .unwrap_or_else(|_| warn!("pubsub connection thread panicked"));
+        }
+    }
+}
+
+impl MultiplexedPubsubClient {
+    /// Connects to the PubSub server at `url`. Only the initial connection attempt is reported
+    /// as an error, later disconnections are retried until the client is dropped.
+    pub fn new(url: &str) -> Result<Self, PubsubClientError> {
+        let url = Url::parse(url)?;
+        let socket = connect_socket(&url)?;
+        let (command_sender, command_receiver) = channel();
+        let exit = Arc::new(AtomicBool::new(false));
+
+        let connection = Connection {
+            url,
+            socket: Some(socket),
+            command_receiver,
+            subscriptions: HashMap::new(),
+            server_subscription_ids: HashMap::new(),
+            pending_requests: HashMap::new(),
+            next_request_id: 0,
+            exit: exit.clone(),
+        };
+        let t_connection = Builder::new()
+            .name("solana-pubsub-client".to_string())
+            .spawn(move || connection.run())
+            .unwrap();
+
+        Ok(Self {
+            command_sender: Mutex::new(command_sender),
+            next_subscription_id: AtomicU64::new(0),
+            exit,
+            t_connection: Some(t_connection),
+        })
+    }
+
+    pub fn account_subscribe(
+        &self,
+        pubkey: &Pubkey,
+        config: Option<RpcAccountInfoConfig>,
+    ) -> Result<PubsubSubscription<RpcResponse<UiAccount>>, PubsubClientError> {
+        self.subscribe("account", json!([pubkey.to_string(), config]))
+    }
+
+    pub fn program_subscribe(
+        &self,
+        program_id: &Pubkey,
+        config: Option<RpcProgramAccountsConfig>,
+    ) -> Result<PubsubSubscription<RpcResponse<RpcKeyedAccount>>, PubsubClientError> {
+        self.subscribe("program", json!([program_id.to_string(), config]))
+    }
+
+    pub fn logs_subscribe(
+        &self,
+        filter: RpcTransactionLogsFilter,
+        config: RpcTransactionLogsConfig,
+    ) -> Result<PubsubSubscription<RpcResponse<RpcLogsResponse>>, PubsubClientError> {
+        self.subscribe("logs", json!([filter, config]))
+    }
+
+    /// Signature subscriptions end once the signature is processed at the requested commitment,
+    /// after which the subscription's channel is closed
+    pub fn signature_subscribe(
+        &self,
+        signature: &Signature,
+        config: Option<RpcSignatureSubscribeConfig>,
+    ) -> Result<PubsubSubscription<RpcResponse<RpcSignatureResult>>, PubsubClientError> {
+        self.subscribe("signature", json!([signature.to_string(), config]))
+    }
+
+    pub fn slot_subscribe(&self) -> Result<PubsubSubscription<SlotInfo>, PubsubClientError> {
+        self.subscribe("slot", json!([]))
+    }
+
+    pub fn vote_subscribe(&self) -> Result<PubsubSubscription<RpcVote>, PubsubClientError> {
+        self.subscribe("vote", json!([]))
+    }
+
+    pub fn root_subscribe(&self) -> Result<PubsubSubscription<Slot>, PubsubClientError> {
+        self.subscribe("root", json!([]))
+    }
+
+    pub fn block_subscribe(
+        &self,
+        filter: RpcBlockSubscribeFilter,
+        config: Option<RpcBlockSubscribeConfig>,
+    ) -> Result<PubsubSubscription<RpcResponse<RpcBlockUpdate>>, PubsubClientError> {
+        self.subscribe("block", json!([filter, config]))
+    }
+
+    /// Blocks until the server accepts or rejects the subscription. While disconnected, the
+    /// request is sent once the connection is re-established, unless `SUBSCRIBE_TIMEOUT` passes
+    /// first.
+    fn subscribe<T>(
+        &self,
+        operation: &'static str,
+        params: Value,
+    ) -> Result<PubsubSubscription<T>, PubsubClientError>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        let (sender, receiver) = channel();
+        let notifier: Notifier = Box::new(move |value| match serde_json::from_value::<T>(value) {
+            Ok(notification) => sender.send(notification).is_ok(),
+            Err(err) => {
+                warn!("unable to parse {} notification: {}", operation, err);
+                true
+            }
+        });
+        let id = self.next_subscription_id.fetch_add(1, Ordering::Relaxed);
+        let (reply_sender, reply_receiver) = channel();
+        let command_sender = self.command_sender.lock().unwrap().clone();
+        command_sender
+            .send(Command::Subscribe {
+                id,
+                operation,
+                params,
+                notifier,
+                reply: reply_sender,
+            })
+            .map_err(|_| PubsubClientError::ConnectionClosed)?;
+        match reply_receiver.recv_timeout(SUBSCRIBE_TIMEOUT) {
+            Ok(result) => result?,
+            Err(RecvTimeoutError::Timeout) => {
+                let _ = command_sender.send(Command::Unsubscribe { id });
+                return Err(PubsubClientError::SubscribeTimeout);
+            }
+            Err(RecvTimeoutError::Disconnected) => return Err(PubsubClientError::ConnectionClosed),
+        }
+
+        Ok(PubsubSubscription {
+            id,
+            receiver,
+            command_sender,
+        })
+    }
+}
+
+fn connect_socket(url: &Url) -> Result<WebSocket<AutoStream>, PubsubClientError> {
+    let (socket, _response) = connect(url.clone())?;
+    // Reads must time out so that the connection thread can send new requests
+    let tcp_stream = match socket.get_ref() {
+        Stream::Plain(stream) => stream,
+        Stream::Tls(stream) => stream.get_ref(),
+    };
+    tcp_stream
+        .set_read_timeout(Some(SOCKET_READ_TIMEOUT))
+        .map_err(tungstenite::Error::Io)?;
+    Ok(socket)
+}
+
+struct Subscription {
+    operation: &'static str,
+    params: Value,
+    notifier: Notifier,
+    server_id: Option<u64>,
+    // Taken once the server first replies to the subscribe request
+    reply: Option<Sender<Result<(), PubsubClientError>>>,
+}
+
+enum PendingRequest {
+    Subscribe { id: u64, operation: &'static str },
+    Unsubscribe,
+}
+
+struct Connection {
+    url: Url,
+    socket: Option<WebSocket<AutoStream>>,
+    command_receiver: Receiver<Command>,
+    subscriptions: HashMap<u64, Subscription>,
+    // Server subscription id to client subscription id, only for the current connection
+    server_subscription_ids: HashMap<u64, u64>,
+    pending_requests: HashMap<u64, PendingRequest>,
+    next_request_id: u64,
+    exit: Arc<AtomicBool>,
+}
+
+impl Connection {
+    fn run(mut self) {
+        let mut reconnect_delay = INITIAL_RECONNECT_DELAY;
+        while !self.exit.load(Ordering::Relaxed) {
+            if let Err(err) = self.process_commands() {
+                self.disconnect(err);
+            }
+
+            let socket = match self.socket.as_mut() {
+                Some(socket) => socket,
+                None => {
+                    self.wait(reconnect_delay);
+                    reconnect_delay = (reconnect_delay * 2).min(MAX_RECONNECT_DELAY);
+                    self.reconnect();
+                    if self.socket.is_some() {
+                        reconnect_delay = INITIAL_RECONNECT_DELAY;
+                    }
+                    continue;
+                }
+            };
+            match socket.read_message() {
+                Ok(Message::Text(text)) => self.process_message(&text),
+                Ok(Message::Close(_)) => self.disconnect(tungstenite::Error::ConnectionClosed),
+                Ok(_) => (),
+                Err(tungstenite::Error::Io(err))
+                    if err.kind() == io::ErrorKind::WouldBlock
+                        || err.kind() == io::ErrorKind::TimedOut => {}
+                Err(err) => self.disconnect(err),
+            }
+        }
+
+        if let Some(mut socket) = self.socket.take() {
+            socket
+                .close(None)
+                .unwrap_or_else(|_| warn!("unable to close websocket"));
+        }
+        info!("pubsub client - exited connection loop");
+    }
+
+    /// Sleeps for `delay` while still accepting new subscriptions
+    fn wait(&mut self, delay: Duration) {
+        let start = Instant::now();
+        while start.elapsed() < delay && !self.exit.load(Ordering::Relaxed) {
+            // Commands never fail while disconnected
+            let _ = self.process_commands();
+            sleep(SOCKET_READ_TIMEOUT.min(delay));
+        }
+    }
+
+    fn reconnect(&mut self) {
+        match connect_socket(&self.url) {
+            Ok(socket) => {
+                info!("pubsub client - reconnected to {}", self.url);
+                self.socket = Some(socket);
+                let ids: Vec<_> = self.subscriptions.keys().copied().collect();
+                for id in ids {
+                    if let Err(err) = self.send_subscribe(id) {
+                        self.disconnect(err);
+                        return;
+                    }
+                }
+            }
+            Err(err) => warn!("pubsub client - unable to reconnect: {}", err),
+        }
+    }
+
+    fn disconnect(&mut self, err: tungstenite::Error) {
+        warn!("pubsub client - disconnected: {}", err);
+        self.socket = None;
+        self.server_subscription_ids.clear();
+        self.pending_requests.clear();
+        for subscription in self.subscriptions.values_mut() {
+            subscription.server_id = None;
+        }
+    }
+
+    fn process_commands(&mut self) -> Result<(), tungstenite::Error> {
+        loop {
+            match self.command_receiver.try_recv() {
+                Ok(Command::Subscribe {
+                    id,
+                    operation,
+                    params,
+                    notifier,
+                    reply,
+                }) => {
+                    self.subscriptions.insert(
+                        id,
+                        Subscription {
+                            operation,
+                            params,
+                            notifier,
+                            server_id: None,
+                            reply: Some(reply),
+                        },
+                    );
+                    self.send_subscribe(id)?;
+                }
+                Ok(Command::Unsubscribe { id }) => self.unsubscribe(id)?,
+                Err(TryRecvError::Empty) => return Ok(()),
+                Err(TryRecvError::Disconnected) => {
+                    // The client and all of its subscriptions have been dropped
+                    self.exit.store(true, Ordering::Relaxed);
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    fn send_request(
+        &mut self,
+        method: String,
+        params: Value,
+        pending_request: PendingRequest,
+    ) -> Result<(), tungstenite::Error> {
+        let socket = match self.socket.as_mut() {
+            Some(socket) => socket,
+            // Subscriptions are sent again once reconnected
+            None => return Ok(()),
+        };
+        let request_id = self.next_request_id;
+        self.next_request_id += 1;
+        self.pending_requests.insert(request_id, pending_request);
+        socket.write_message(Message::Text(
+            json!({"jsonrpc":"2.0","id":request_id,"method":method,"params":params}).to_string(),
+        ))
+    }
+
+    fn send_subscribe(&mut self, id: u64) -> Result<(), tungstenite::Error> {
+        let (operation, params) = match self.subscriptions.get(&id) {
+            Some(subscription) => (subscription.operation, subscription.params.clone()),
+            None => return Ok(()),
+        };
+        self.send_request(
+            format!("{}Subscribe", operation),
+            params,
+            PendingRequest::Subscribe { id, operation },
+        )
+    }
+
+    fn send_unsubscribe(
+        &mut self,
+        operation: &'static str,
+        server_id: u64,
+    ) -> Result<(), tungstenite::Error> {
+        self.send_request(
+            format!("{}Unsubscribe", operation),
+            json!([server_id]),
+            PendingRequest::Unsubscribe,
+        )
+    }
+
+    fn unsubscribe(&mut self, id: u64) -> Result<(), tungstenite::Error> {
+        if let Some(subscription) = self.subscriptions.remove(&id) {
+            if let Some(server_id) = subscription.server_id {
+                self.server_subscription_ids.remove(&server_id);
+                self.send_unsubscribe(subscription.operation, server_id)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn process_message(&mut self, text: &str) {
+        let message: Value = match serde_json::from_str(text) {
+            Ok(message) => message,
+            Err(err) => {
+                warn!("pubsub client - unable to parse message: {}", err);
+                return;
+            }
+        };
+
+        if let Some(request_id) = message.get("id").and_then(Value::as_u64) {
+            if let Some(PendingRequest::Subscribe { id, operation }) =
+                self.pending_requests.remove(&request_id)
+            {
+                let result = match message.get("result").and_then(Value::as_u64) {
+                    Some(server_id) => Ok(server_id),
+                    None => Err(PubsubClientError::SubscribeFailed(
+                        message
+                            .get("error")
+                            .map(|error| error.to_string())
+                            .unwrap_or_else(|| text.to_string()),
+                    )),
+                };
+                if let Err(err) = self.process_subscribe_result(id, operation, result) {
+                    self.disconnect(err);
+                }
+            }
+        } else if let Some(params) = message.get("params") {
+            if let Some(server_id) = params.get("subscription").and_then(Value::as_u64) {
+                let result = params.get("result").cloned().unwrap_or(Value::Null);
+                if let Err(err) = self.notify(server_id, result) {
+                    self.disconnect(err);
+                }
+            }
+        }
+    }
+
+    fn process_subscribe_result(
+        &mut self,
+        id: u64,
+        operation: &'static str,
+        result: Result<u64, PubsubClientError>,
+    ) -> Result<(), tungstenite::Error> {
+        match result {
+            Ok(server_id) => match self.subscriptions.get_mut(&id) {
+                Some(subscription) => {
+                    subscription.server_id = Some(server_id);
+                    if let Some(reply) = subscription.reply.take() {
+                        let _ = reply.send(Ok(()));
+                    }
+                    self.server_subscription_ids.insert(server_id, id);
+                }
+                // Unsubscribed before the server replied
+                None => self.send_unsubscribe(operation, server_id)?,
+            },
+            Err(err) => {
+                if let Some(subscription) = self.subscriptions.remove(&id) {
+                    match subscription.reply {
+                        Some(reply) => {
+                            let _ = reply.send(Err(err));
+                        }
+                        None => warn!("pubsub client - unable to resubscribe: {}", err),
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn notify(&mut self, server_id: u64, result: Value) -> Result<(), tungstenite::Error> {
+        let id = match self.server_subscription_ids.get(&server_id) {
+            Some(id) => *id,
+            None => return Ok(()),
+        };
+        let subscription = &self.subscriptions[&id];
+        // The server ends a signature subscription after notifying that the signature was
+        // processed, as opposed to only received
+        let is_final = subscription.operation == "signature"
+            && !result.get("value").map_or(false, Value::is_string);
+        if !(subscription.notifier)(result) {
+            self.unsubscribe(id)?;
+        } else if is_final {
+            self.subscriptions.remove(&id);
+            self.server_subscription_ids.remove(&server_id);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    struct MockServer {
+        socket: WebSocket<std::net::TcpStream>,
+    }
+
+    impl MockServer {
+        fn accept(listener: &TcpListener) -> Self {
+            let (stream, _) = listener.accept().unwrap();
+            Self {
+                socket: tungstenite::accept(stream).unwrap(),
+            }
+        }
+
+        fn read_request(&mut self) -> Value {
+            let message = self.socket.read_message().unwrap();
+            serde_json::from_str(message.to_text().unwrap()).unwrap()
+        }
+
+        fn reply(&mut self, request: &Value, result: Value) {
+            self.send(json!({"jsonrpc":"2.0","result":result,"id":request["id"]}));
+        }
+
+        fn reply_error(&mut self, request: &Value, message: &str) {
+            self.send(json!({
+                "jsonrpc":"2.0",
+                "error":{"code":-32602,"message":message},
+                "id":request["id"],
+            }));
+        }
+
+        fn notify(&mut self, method: &str, server_id: u64, result: Value) {
+            self.send(json!({
+                "jsonrpc":"2.0",
+                "method":method,
+                "params":{"result":result,"subscription":server_id},
+            }));
+        }
+
+        fn send(&mut self, message: Value) {
+            self.socket
+                .write_message(Message::Text(message.to_string()))
+                .unwrap();
+        }
+    }
+
+    const RECV_TIMEOUT: Duration = Duration::from_secs(10);
+
+    #[test]
+    fn test_multiplexed_subscriptions() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let pubkey = Pubkey::new_unique();
+
+        let server = std::thread::spawn(move || {
+            let mut server = MockServer::accept(&listener);
+            let slot_request = server.read_request();
+            assert_eq!(slot_request["method"], "slotSubscribe");
+            server.reply(&slot_request, json!(7));
+            let root_request = server.read_request();
+            assert_eq!(root_request["method"], "rootSubscribe");
+            server.reply(&root_request, json!(8));
+            let account_request = server.read_request();
+            assert_eq!(account_request["method"], "accountSubscribe");
+            assert_eq!(account_request["params"][0], pubkey.to_string());
+            server.reply_error(&account_request, "Invalid Request: Invalid pubkey provided");
+
+            server.notify("rootNotification", 8, json!(2));
+            server.notify("slotNotification", 7, json!({"slot":3,"parent":2,"root":1}));
+
+            // Drop the connection, the client resubscribes once reconnected
+            drop(server);
+            let mut server = MockServer::accept(&listener);
+            let mut requests = [server.read_request(), server.read_request()];
+            requests.sort_by_key(|request| request["method"].as_str().unwrap().to_string());
+            assert_eq!(requests[0]["method"], "rootSubscribe");
+            assert_eq!(requests[1]["method"], "slotSubscribe");
+            server.reply(&requests[0], json!(1));
+            server.reply(&requests[1], json!(0));
+            server.notify("slotNotification", 0, json!({"slot":4,"parent":3,"root":1}));
+
+            let unsubscribe_request = server.read_request();
+            assert_eq!(unsubscribe_request["method"], "rootUnsubscribe");
+            assert_eq!(unsubscribe_request["params"], json!([1]));
+            server.reply(&unsubscribe_request, json!(true));
+        });
+
+        let client = MultiplexedPubsubClient::new(&url).unwrap();
+        let slot_subscription = client.slot_subscribe().unwrap();
+        let root_subscription = client.root_subscribe().unwrap();
+        // Rejected subscriptions return the server's error
+        assert!(matches!(
+            client.account_subscribe(&pubkey, None),
+            Err(PubsubClientError::SubscribeFailed(_))
+        ));
+
+        assert_eq!(root_subscription.recv_timeout(RECV_TIMEOUT).unwrap(), 2);
+        let slot_info = slot_subscription.recv_timeout(RECV_TIMEOUT).unwrap();
+        assert_eq!(
+            (slot_info.slot, slot_info.parent, slot_info.root),
+            (3, 2, 1)
+        );
+        assert_eq!(
+            slot_subscription.recv_timeout(RECV_TIMEOUT).unwrap().slot,
+            4
+        );
+        drop(root_subscription);
+
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_signature_subscription_ends() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let signature = Signature::new(&[1; 64]);
+
+        let server = std::thread::spawn(move || {
+            let mut server = MockServer::accept(&listener);
+            let request = server.read_request();
+            assert_eq!(request["method"], "signatureSubscribe");
+            assert_eq!(request["params"][0], signature.to_string());
+            server.reply(&request, json!(3));
+            server.notify(
+                "signatureNotification",
+                3,
+                json!({"context":{"slot":5},"value":"receivedSignature"}),
+            );
+            server.notify(
+                "signatureNotification",
+                3,
+                json!({"context":{"slot":6},"value":{"err":null}}),
+            );
+            server
+        });
+
+        let client = MultiplexedPubsubClient::new(&url).unwrap();
+        let subscription = client.signature_subscribe(&signature, None).unwrap();
+        let received = subscription.recv_timeout(RECV_TIMEOUT).unwrap();
+        assert_eq!(received.context.slot, 5);
+        let processed = subscription.recv_timeout(RECV_TIMEOUT).unwrap();
+        assert_eq!(processed.context.slot, 6);
+        assert!(matches!(
+            processed.value,
+            RpcSignatureResult::ProcessedSignature(_)
+        ));
+        // The channel closes once the signature is processed
+        assert!(matches!(
+            subscription.recv_timeout(RECV_TIMEOUT),
+            Err(RecvTimeoutError::Disconnected)
+        ));
+
+        let _server = server.join().unwrap();
+    }
+}
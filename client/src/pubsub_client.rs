@@ -1,6 +1,11 @@
 use crate::{
-    rpc_config::{RpcSignatureSubscribeConfig, RpcTransactionLogsConfig, RpcTransactionLogsFilter},
-    rpc_response::{Response as RpcResponse, RpcLogsResponse, RpcSignatureResult, SlotInfo},
+    rpc_config::{
+        RpcBlockSubscribeConfig, RpcBlockSubscribeFilter, RpcSignatureSubscribeConfig,
+        RpcTransactionLogsConfig, RpcTransactionLogsFilter,
+    },
+    rpc_response::{
+        Response as RpcResponse, RpcBlockUpdate, RpcLogsResponse, RpcSignatureResult, SlotInfo,
+    },
 };
 use log::*;
 use serde::de::DeserializeOwned;
@@ -36,6 +41,15 @@ pub enum PubsubClientError {
 
     #[error("unexpected message format: {0}")]
     UnexpectedMessageError(String),
+
+    #[error("subscribe failed: {0}")]
+    SubscribeFailed(String),
+
+    #[error("subscribe timed out")]
+    SubscribeTimeout,
+
+    #[error("connection closed")]
+    ConnectionClosed,
 }
 
 pub struct PubsubClientSubscription<T>
@@ -151,6 +165,10 @@ pub type LogsSubscription = (
     Receiver<RpcResponse<RpcLogsResponse>>,
 );
 pub type SlotsSubscription = (PubsubClientSubscription<SlotInfo>, Receiver<SlotInfo>);
+pub type BlockSubscription = (
+    PubsubClientSubscription<RpcResponse<RpcBlockUpdate>>,
+    Receiver<RpcResponse<RpcBlockUpdate>>,
+);
 pub type SignatureSubscription = (
     PubsubClientSubscription<RpcResponse<RpcSignatureResult>>,
     Receiver<RpcResponse<RpcSignatureResult>>,
@@ -218,6 +236,65 @@ impl PubsubClient {
         Ok((result, receiver))
     }
 
+    pub fn block_subscribe(
+        url: &str,
+        filter: RpcBlockSubscribeFilter,
+        config: Option<RpcBlockSubscribeConfig>,
+    ) -> Result<BlockSubscription, PubsubClientError> {
+        let url = Url::parse(url)?;
+        let (socket, _response) = connect(url)?;
+        let (sender, receiver) = channel();
+
+        let socket = Arc::new(RwLock::new(socket));
+        let socket_clone = socket.clone();
+        let exit = Arc::new(AtomicBool::new(false));
+        let exit_clone = exit.clone();
+
+        let subscription_id =
+            PubsubClientSubscription::<RpcResponse<RpcBlockUpdate>>::send_subscribe(
+                &socket_clone,
+                json!({
+                    "jsonrpc":"2.0","id":1,"method":"blockSubscribe","params":[filter, config]
+                })
+                .to_string(),
+            )?;
+
+        let t_cleanup = std::thread::spawn(move || {
+            loop {
+                if exit_clone.load(Ordering::Relaxed) {
+                    break;
+                }
+
+                match PubsubClientSubscription::read_message(&socket_clone) {
+                    Ok(message) => match sender.send(message) {
+                        Ok(_) => (),
+                        Err(err) => {
+                            info!("receive error: {:?}", err);
+                            break;
+                        }
+                    },
+                    Err(err) => {
+                        info!("receive error: {:?}", err);
+                        break;
+                    }
+                }
+            }
+
+            info!("websocket - exited receive loop");
+        });
+
+        let result = PubsubClientSubscription {
+            message_type: PhantomData,
+            operation: "block",
+            socket,
+            subscription_id,
+            t_cleanup: Some(t_cleanup),
+            exit,
+        };
+
+        Ok((result, receiver))
+    }
+
     pub fn slot_subscribe(url: &str) -> Result<SlotsSubscription, PubsubClientError> {
         let url = Url::parse(url)?;
         let (socket, _response) = connect(url)?;
@@ -0,0 +1,138 @@
+//! An `RpcSender` that records the requests sent through another sender and
+//! their responses, for later replay by a `ReplaySender`
+
+use crate::{
+    client_error::{ClientErrorKind, Result},
+    rpc_custom_error::NodeUnhealthyErrorData,
+    rpc_request::{RpcError, RpcRequest, RpcResponseErrorData},
+    rpc_sender::{RpcSender, RpcTransportStats},
+};
+use async_trait::async_trait;
+use log::*;
+use serde_json::{json, Value};
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::Path,
+    sync::Mutex,
+};
+
+/// A request and the response of the server to it. Recordings are stored
+/// one record per line, in JSON.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RpcRecord {
+    pub method: String,
+    pub params: Value,
+    #[serde(flatten)]
+    pub response: RpcRecordedResponse,
+}
+
+/// Mirrors the `result` or `error` member of a JSON-RPC response
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum RpcRecordedResponse {
+    Result(Value),
+    /// A JSON-RPC error object
+    Error(Value),
+}
+
+impl RpcRecord {
+    /// Record the outcome of a request. Failures that did not come from the
+    /// server, such as connection errors, are not recorded.
+    fn new(request: RpcRequest, params: Value, result: &Result<Value>) -> Option<Self> {
+        let response = match result {
+            Ok(value) => RpcRecordedResponse::Result(value.clone()),
+            Err(err) => match err.kind() {
+                ClientErrorKind::RpcError(RpcError::RpcResponseError {
+                    code,
+                    message,
+                    data,
+                }) => RpcRecordedResponse::Error(json!({
+                    "code": code,
+                    "message": message,
+                    "data": error_data_json(data),
+                })),
+                _ => return None,
+            },
+        };
+        Some(Self {
+            method: request.to_string(),
+            params,
+            response,
+        })
+    }
+}
+
+fn error_data_json(data: &RpcResponseErrorData) -> Value {
+    match data {
+        RpcResponseErrorData::Empty => Value::Null,
+        RpcResponseErrorData::SendTransactionPreflightFailure(result) => json!(result),
+        RpcResponseErrorData::NodeUnhealthy { num_slots_behind } => json!(NodeUnhealthyErrorData {
+            num_slots_behind: *num_slots_behind,
+        }),
+    }
+}
+
+/// Read a recording written by a `RecordingSender`
+pub fn load_records<P: AsRef<Path>>(path: P) -> io::Result<Vec<RpcRecord>> {
+    let file = File::open(path)?;
+    BufReader::new(file)
+        .lines()
+        .filter(|line| !matches!(line, Ok(line) if line.trim().is_empty()))
+        .map(|line| serde_json::from_str(&line?).map_err(io::Error::from))
+        .collect()
+}
+
+pub struct RecordingSender {
+    sender: Box<dyn RpcSender + Send + Sync + 'static>,
+    writer: Mutex<BufWriter<File>>,
+}
+
+impl RecordingSender {
+    /// Records are appended to the file at `path`, which is created if it
+    /// does not exist
+    pub fn new<T: RpcSender + Send + Sync + 'static, P: AsRef<Path>>(
+        sender: T,
+        path: P,
+    ) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            sender: Box::new(sender),
+            writer: Mutex::new(BufWriter::new(file)),
+        })
+    }
+
+    fn record(&self, request: RpcRequest, params: Value, result: &Result<Value>) {
+        if let Some(record) = RpcRecord::new(request, params, result) {
+            let mut writer = self.writer.lock().unwrap();
+            if let Err(err) = serde_json::to_writer(&mut *writer, &record)
+                .map_err(io::Error::from)
+                .and_then(|()| writer.write_all(b"\n"))
+                .and_then(|()| writer.flush())
+            {
+                warn!("Failed to record {} response: {}", request, err);
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl RpcSender for RecordingSender {
+    async fn send(&self, request: RpcRequest, params: Value) -> Result<Value> {
+        let result = self.sender.send(request, params.clone()).await;
+        self.record(request, params, &result);
+        result
+    }
+
+    async fn send_batch(&self, requests: Vec<(RpcRequest, Value)>) -> Result<Vec<Result<Value>>> {
+        let results = self.sender.send_batch(requests.clone()).await?;
+        for ((request, params), result) in requests.into_iter().zip(&results) {
+            self.record(request, params, result);
+        }
+        Ok(results)
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.sender.get_transport_stats()
+    }
+}
@@ -0,0 +1,170 @@
+//! Answer requests with the responses captured by a `RecordingSender`
+
+use crate::{
+    client_error::Result,
+    http_sender::parse_response_json,
+    recording_sender::{load_records, RpcRecord, RpcRecordedResponse},
+    rpc_request::{RpcError, RpcRequest},
+    rpc_sender::{RpcSender, RpcTransportStats},
+};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::{
+    collections::{HashMap, VecDeque},
+    io,
+    path::Path,
+    sync::Mutex,
+};
+
+/// Answers each request with the next recorded response to the same method
+/// and params. Once those are used up, the last of them is repeated, so
+/// that a client polling for a status ends with the recorded outcome.
+///
+/// Requests are matched exactly, so params that change from run to run,
+/// such as transactions signed by new keypairs, are not replayed.
+#[derive(Default)]
+pub struct ReplaySender {
+    responses: Mutex<HashMap<(String, String), VecDeque<RpcRecordedResponse>>>,
+}
+
+impl ReplaySender {
+    pub fn new(records: Vec<RpcRecord>) -> Self {
+        let mut responses = HashMap::<_, VecDeque<_>>::new();
+        for record in records {
+            responses
+                .entry((record.method, record.params.to_string()))
+                .or_default()
+                .push_back(record.response);
+        }
+        Self {
+            responses: Mutex::new(responses),
+        }
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        load_records(path).map(Self::new)
+    }
+
+    pub(crate) fn replay(&self, method: &str, params: &Value) -> Option<RpcRecordedResponse> {
+        let mut responses = self.responses.lock().unwrap();
+        let responses = responses.get_mut(&(method.to_string(), params.to_string()))?;
+        if responses.len() > 1 {
+            responses.pop_front()
+        } else {
+            responses.front().cloned()
+        }
+    }
+}
+
+#[async_trait]
+impl RpcSender for ReplaySender {
+    async fn send(&self, request: RpcRequest, params: Value) -> Result<Value> {
+        match self.replay(&request.to_string(), &params) {
+            Some(RpcRecordedResponse::Result(value)) => Ok(value),
+            Some(RpcRecordedResponse::Error(error)) => {
+                parse_response_json(json!({ "error": error }))
+            }
+            None => Err(RpcError::RpcRequestError(format!(
+                "No recorded response for {} {}",
+                request, params
+            ))
+            .into()),
+        }
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        RpcTransportStats::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        client_error::ClientErrorKind,
+        mock_sender::{MockSender, Mocks},
+        recording_sender::RecordingSender,
+        rpc_client::RpcClient,
+        rpc_custom_error,
+    };
+    use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
+
+    fn record_session(path: &Path) {
+        let mocks: Mocks = vec![(RpcRequest::GetSlot, json!(42))].into_iter().collect();
+        let rpc_client = RpcClient::new_sender(
+            RecordingSender::new(
+                MockSender::new_with_mocks("succeeds".to_string(), mocks),
+                path,
+            )
+            .unwrap(),
+            CommitmentConfig::default(),
+        );
+        assert_eq!(rpc_client.get_slot().unwrap(), 42);
+        assert_eq!(rpc_client.get_slot().unwrap(), 0);
+        assert_eq!(rpc_client.get_balance(&Pubkey::default()).unwrap(), 50);
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rpc.json");
+        record_session(&path);
+        let records = load_records(&path).unwrap();
+        // The client queries the node version to map its commitment
+        let methods: Vec<_> = records
+            .iter()
+            .map(|record| record.method.as_str())
+            .collect();
+        assert_eq!(
+            methods,
+            vec!["getVersion", "getSlot", "getSlot", "getBalance"]
+        );
+
+        let rpc_client = RpcClient::new_sender(
+            ReplaySender::new(records.clone()),
+            CommitmentConfig::default(),
+        );
+        assert_eq!(rpc_client.get_slot().unwrap(), 42);
+        assert_eq!(rpc_client.get_slot().unwrap(), 0);
+        // The last response is repeated
+        assert_eq!(rpc_client.get_slot().unwrap(), 0);
+        assert_eq!(rpc_client.get_balance(&Pubkey::default()).unwrap(), 50);
+        // Unrecorded params are not answered
+        assert!(rpc_client.get_balance(&Pubkey::new_unique()).is_err());
+    }
+
+    #[test]
+    fn test_replay_error() {
+        let record = RpcRecord {
+            method: "getSlot".to_string(),
+            params: Value::Null,
+            response: RpcRecordedResponse::Error(json!({
+                "code": rpc_custom_error::JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY,
+                "message": "Node is behind by 42 slots",
+                "data": {"numSlotsBehind": 42},
+            })),
+        };
+        let line = serde_json::to_string(&record).unwrap();
+        assert!(line.contains("\"error\":{"));
+        assert_eq!(serde_json::from_str::<RpcRecord>(&line).unwrap(), record);
+
+        let check_error = |rpc_client: RpcClient| {
+            let err = rpc_client
+                .send::<u64>(RpcRequest::GetSlot, Value::Null)
+                .unwrap_err();
+            match err.kind() {
+                ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. }) => {
+                    assert_eq!(
+                        *code,
+                        rpc_custom_error::JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY
+                    )
+                }
+                _ => panic!("unexpected error {}", err),
+            }
+        };
+        check_error(RpcClient::new_sender(
+            ReplaySender::new(vec![record]),
+            CommitmentConfig::default(),
+        ));
+    }
+}
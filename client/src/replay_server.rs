@@ -0,0 +1,144 @@
+//! A local JSON-RPC server that replays the responses captured by a
+//! `RecordingSender`, for programs that connect to a cluster by url
+
+use crate::{
+    recording_sender::{load_records, RpcRecord, RpcRecordedResponse},
+    replay_sender::ReplaySender,
+};
+use jsonrpc_core::{futures::future, Error, IoHandler, Params};
+use jsonrpc_http_server::{Server, ServerBuilder};
+use serde_json::Value;
+use std::{io, net::SocketAddr, path::Path, sync::Arc};
+
+/// A local JSON-RPC server that stands in for a cluster, replaying a
+/// recording to the clients that connect to it
+pub struct ReplayServer {
+    server: Option<Server>,
+}
+
+impl ReplayServer {
+    pub fn start(records: Vec<RpcRecord>) -> io::Result<Self> {
+        let mut methods: Vec<_> = records.iter().map(|record| record.method.clone()).collect();
+        methods.sort();
+        methods.dedup();
+
+        let replay = Arc::new(ReplaySender::new(records));
+        let mut io = IoHandler::default();
+        for method in methods {
+            let replay = replay.clone();
+            io.add_method(&method.clone(), move |params: Params| {
+                let params = match params {
+                    Params::None => Value::Null,
+                    params => params.into(),
+                };
+                future::ready(match replay.replay(&method, &params) {
+                    Some(RpcRecordedResponse::Result(value)) => Ok(value),
+                    Some(RpcRecordedResponse::Error(error)) => {
+                        Err(serde_json::from_value(error)
+                            .unwrap_or_else(|_| Error::internal_error()))
+                    }
+                    None => Err(Error::invalid_params(format!(
+                        "No recorded response for {} {}",
+                        method, params
+                    ))),
+                })
+            });
+        }
+
+        let server = ServerBuilder::new(io)
+            .threads(1)
+            .start_http(&SocketAddr::from(([127, 0, 0, 1], 0)))?;
+        Ok(Self {
+            server: Some(server),
+        })
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        load_records(path).and_then(Self::start)
+    }
+
+    pub fn rpc_addr(&self) -> SocketAddr {
+        *self.server.as_ref().unwrap().address()
+    }
+
+    pub fn rpc_url(&self) -> String {
+        format!("http://{}", self.rpc_addr())
+    }
+}
+
+impl Drop for ReplayServer {
+    fn drop(&mut self) {
+        if let Some(server) = self.server.take() {
+            server.close();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        client_error::ClientErrorKind,
+        rpc_client::RpcClient,
+        rpc_custom_error,
+        rpc_request::{RpcError, RpcRequest},
+    };
+    use serde_json::json;
+    use solana_sdk::pubkey::Pubkey;
+
+    fn record(method: &str, params: Value, result: Value) -> RpcRecord {
+        RpcRecord {
+            method: method.to_string(),
+            params,
+            response: RpcRecordedResponse::Result(result),
+        }
+    }
+
+    #[test]
+    fn test_replay_server() {
+        let commitment = json!({"commitment": "finalized"});
+        let records = vec![
+            // The client queries the node version to map its commitment
+            record("getVersion", Value::Null, json!({"solana-core": "1.6.0"})),
+            record("getSlot", json!([commitment]), json!(42)),
+            record("getSlot", json!([commitment]), json!(43)),
+            record(
+                "getBalance",
+                json!([Pubkey::default().to_string(), commitment]),
+                json!({"context": {"slot": 43}, "value": 50}),
+            ),
+            RpcRecord {
+                method: "getEpochInfo".to_string(),
+                params: Value::Null,
+                response: RpcRecordedResponse::Error(json!({
+                    "code": rpc_custom_error::JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY,
+                    "message": "Node is behind by 42 slots",
+                    "data": {"numSlotsBehind": 42},
+                })),
+            },
+        ];
+        let server = ReplayServer::start(records).unwrap();
+        let rpc_client = RpcClient::new(server.rpc_url());
+        assert_eq!(rpc_client.get_slot().unwrap(), 42);
+        assert_eq!(rpc_client.get_slot().unwrap(), 43);
+        // The last response is repeated
+        assert_eq!(rpc_client.get_slot().unwrap(), 43);
+        assert_eq!(rpc_client.get_balance(&Pubkey::default()).unwrap(), 50);
+        // Unrecorded params and methods are not answered
+        assert!(rpc_client.get_balance(&Pubkey::new_unique()).is_err());
+        assert!(rpc_client.get_genesis_hash().is_err());
+
+        let err = rpc_client
+            .send::<Value>(RpcRequest::GetEpochInfo, Value::Null)
+            .unwrap_err();
+        match err.kind() {
+            ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. }) => {
+                assert_eq!(
+                    *code,
+                    rpc_custom_error::JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY
+                )
+            }
+            _ => panic!("unexpected error {}", err),
+        }
+    }
+}
@@ -0,0 +1,149 @@
+//! Typed JSON-RPC batches, which send several requests to the server in a
+//! single round trip
+
+use crate::{
+    client_error::{ClientError, Result as ClientResult},
+    rpc_request::{RpcError, RpcRequest},
+};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::marker::PhantomData;
+
+/// Requests to be sent together with `RpcClient::send_batch`
+#[derive(Debug, Default)]
+pub struct RpcBatch {
+    requests: Vec<(RpcRequest, Value)>,
+}
+
+/// Refers to one request of an `RpcBatch` and the type of its result
+#[derive(Debug)]
+pub struct RpcBatchItem<T> {
+    index: usize,
+    request: RpcRequest,
+    result: PhantomData<fn() -> T>,
+}
+
+impl RpcBatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a request whose result deserializes into `T`. The params must be
+    /// an array or null
+    pub fn add<T: DeserializeOwned>(
+        &mut self,
+        request: RpcRequest,
+        params: Value,
+    ) -> ClientResult<RpcBatchItem<T>> {
+        if !params.is_array() && !params.is_null() {
+            return Err(ClientError::new_with_request(
+                RpcError::RpcRequestError(format!(
+                    "Batch request params must be an array or null, found {}",
+                    params
+                ))
+                .into(),
+                request,
+            ));
+        }
+        self.requests.push((request, params));
+        Ok(RpcBatchItem {
+            index: self.requests.len() - 1,
+            request,
+            result: PhantomData,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.requests.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.requests.is_empty()
+    }
+
+    pub(crate) fn into_requests(self) -> Vec<(RpcRequest, Value)> {
+        self.requests
+    }
+}
+
+/// The results of the requests of an `RpcBatch`
+#[derive(Debug)]
+pub struct RpcBatchResponse {
+    results: Vec<Option<ClientResult<Value>>>,
+}
+
+impl RpcBatchResponse {
+    pub(crate) fn new(results: Vec<ClientResult<Value>>) -> Self {
+        Self {
+            results: results.into_iter().map(Some).collect(),
+        }
+    }
+
+    /// Take the result of `item`, which must belong to the batch that this
+    /// response answers
+    pub fn take<T: DeserializeOwned>(&mut self, item: RpcBatchItem<T>) -> ClientResult<T> {
+        let result = self
+            .results
+            .get_mut(item.index)
+            .and_then(Option::take)
+            .unwrap_or_else(|| {
+                Err(RpcError::RpcRequestError(format!(
+                    "No result for batch request {}",
+                    item.index
+                ))
+                .into())
+            });
+        result
+            .map_err(|err| err.into_with_request(item.request))
+            .and_then(|value| {
+                serde_json::from_value(value)
+                    .map_err(|err| ClientError::new_with_request(err.into(), item.request))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_take() {
+        let mut batch = RpcBatch::new();
+        let balance = batch
+            .add::<u64>(RpcRequest::GetBalance, json!(["pubkey"]))
+            .unwrap();
+        let slot = batch.add::<u64>(RpcRequest::GetSlot, Value::Null).unwrap();
+        let genesis_hash = batch
+            .add::<String>(RpcRequest::GetGenesisHash, Value::Null)
+            .unwrap();
+        assert_eq!(batch.len(), 3);
+
+        let mut response = RpcBatchResponse::new(vec![
+            Ok(json!(50)),
+            Err(RpcError::ForUser("slot unavailable".to_string()).into()),
+            Ok(json!(1)),
+        ]);
+        assert_eq!(response.take(balance).unwrap(), 50);
+        assert_eq!(
+            response.take(slot).unwrap_err().request(),
+            Some(&RpcRequest::GetSlot)
+        );
+        // A result of the wrong type is a deserialization error
+        assert!(response.take(genesis_hash).is_err());
+
+        let mut batch = RpcBatch::new();
+        let missing = batch.add::<u64>(RpcRequest::GetSlot, Value::Null).unwrap();
+        assert!(RpcBatchResponse::new(vec![]).take(missing).is_err());
+    }
+
+    #[test]
+    fn test_add_rejects_object_params() {
+        let mut batch = RpcBatch::new();
+        let err = batch
+            .add::<u64>(RpcRequest::GetBalance, json!({"pubkey": "pubkey"}))
+            .unwrap_err();
+        assert_eq!(err.request(), Some(&RpcRequest::GetBalance));
+        assert!(batch.is_empty());
+    }
+}
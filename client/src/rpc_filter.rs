@@ -1,10 +1,23 @@
+use serde::{Deserialize, Deserializer, Serializer};
+use solana_sdk::{account::Account, pubkey::Pubkey};
+use std::{borrow::Cow, str::FromStr};
 use thiserror::Error;
 
+/// Maximum number of bytes a `Memcmp` filter may compare
+const MAX_DATA_SIZE: usize = 128;
+/// Length of `MAX_DATA_SIZE` bytes encoded as base-58
+const MAX_DATA_BASE58_SIZE: usize = 175;
+/// Length of `MAX_DATA_SIZE` bytes encoded as base-64
+const MAX_DATA_BASE64_SIZE: usize = 172;
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub enum RpcFilterType {
     DataSize(u64),
     Memcmp(Memcmp),
+    /// Address of the account owner, base-58 encoded
+    Owner(#[serde(with = "pubkey_as_string")] Pubkey),
+    Lamports(LamportsRange),
 }
 
 impl RpcFilterType {
@@ -13,43 +26,93 @@ impl RpcFilterType {
             RpcFilterType::DataSize(_) => Ok(()),
             RpcFilterType::Memcmp(compare) => {
                 let encoding = compare.encoding.as_ref().unwrap_or(&MemcmpEncoding::Binary);
-                match encoding {
-                    MemcmpEncoding::Binary => {
-                        let MemcmpEncodedBytes::Binary(bytes) = &compare.bytes;
-
-                        if bytes.len() > 128 {
-                            Err(RpcFilterError::Base58DataTooLarge)
-                        } else {
-                            bs58::decode(&bytes)
-                                .into_vec()
-                                .map(|_| ())
-                                .map_err(|e| e.into())
+                let bytes = match &compare.bytes {
+                    MemcmpEncodedBytes::Binary(bytes) => match encoding {
+                        MemcmpEncoding::Binary | MemcmpEncoding::Base58 => {
+                            if bytes.len() > MAX_DATA_BASE58_SIZE {
+                                return Err(RpcFilterError::Base58DataTooLarge);
+                            }
+                            Cow::Owned(bs58::decode(&bytes).into_vec()?)
                         }
-                    }
+                        MemcmpEncoding::Base64 => {
+                            if bytes.len() > MAX_DATA_BASE64_SIZE {
+                                return Err(RpcFilterError::Base64DataTooLarge);
+                            }
+                            Cow::Owned(base64::decode(&bytes)?)
+                        }
+                    },
+                    MemcmpEncodedBytes::Bytes(bytes) => Cow::Borrowed(bytes),
+                };
+                if bytes.len() > MAX_DATA_SIZE {
+                    Err(RpcFilterError::DataTooLarge)
+                } else {
+                    Ok(())
                 }
             }
+            RpcFilterType::Owner(_) => Ok(()),
+            RpcFilterType::Lamports(range) => match (range.min, range.max) {
+                (Some(min), Some(max)) if min > max => Err(RpcFilterError::InvalidLamportsRange),
+                _ => Ok(()),
+            },
+        }
+    }
+
+    /// Returns true if `account` meets the filter criteria
+    pub fn allows(&self, account: &Account) -> bool {
+        match self {
+            RpcFilterType::DataSize(size) => account.data.len() as u64 == *size,
+            RpcFilterType::Memcmp(compare) => compare.bytes_match(&account.data),
+            RpcFilterType::Owner(owner) => account.owner == *owner,
+            RpcFilterType::Lamports(range) => range.contains(account.lamports),
         }
     }
 }
 
+mod pubkey_as_string {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(pubkey: &Pubkey, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(pubkey)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Pubkey, D::Error> {
+        let pubkey = String::deserialize(deserializer)?;
+        Pubkey::from_str(&pubkey).map_err(serde::de::Error::custom)
+    }
+}
+
 #[derive(Error, PartialEq, Debug)]
 pub enum RpcFilterError {
     #[error("bs58 decode error")]
     DecodeError(#[from] bs58::decode::Error),
-    #[error("encoded binary (base 58) data should be less than 129 bytes")]
+    #[error("encoded binary (base 58) data should be less than 176 bytes")]
     Base58DataTooLarge,
+    #[error("base64 decode error")]
+    Base64DecodeError(#[from] base64::DecodeError),
+    #[error("encoded binary (base 64) data should be less than 173 bytes")]
+    Base64DataTooLarge,
+    #[error("compared data should be less than 129 bytes")]
+    DataTooLarge,
+    #[error("lamports range minimum exceeds its maximum")]
+    InvalidLamportsRange,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub enum MemcmpEncoding {
+    /// Base-58, the default
     Binary,
+    Base58,
+    Base64,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase", untagged)]
 pub enum MemcmpEncodedBytes {
+    /// Bytes encoded as a string, decoded according to `Memcmp::encoding`
     Binary(String),
+    /// Raw bytes, the encoding is ignored
+    Bytes(Vec<u8>),
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -63,14 +126,24 @@ pub struct Memcmp {
 }
 
 impl Memcmp {
-    pub fn bytes_match(&self, data: &[u8]) -> bool {
+    /// Returns the decoded bytes to compare, or None if they cannot be decoded
+    pub fn bytes(&self) -> Option<Cow<Vec<u8>>> {
         match &self.bytes {
             MemcmpEncodedBytes::Binary(bytes) => {
-                let bytes = bs58::decode(bytes).into_vec();
-                if bytes.is_err() {
-                    return false;
+                match self.encoding.as_ref().unwrap_or(&MemcmpEncoding::Binary) {
+                    MemcmpEncoding::Binary | MemcmpEncoding::Base58 => {
+                        bs58::decode(bytes).into_vec().ok().map(Cow::Owned)
+                    }
+                    MemcmpEncoding::Base64 => base64::decode(bytes).ok().map(Cow::Owned),
                 }
-                let bytes = bytes.unwrap();
+            }
+            MemcmpEncodedBytes::Bytes(bytes) => Some(Cow::Borrowed(bytes)),
+        }
+    }
+
+    pub fn bytes_match(&self, data: &[u8]) -> bool {
+        match self.bytes() {
+            Some(bytes) => {
                 if self.offset > data.len() {
                     return false;
                 }
@@ -79,10 +152,25 @@ impl Memcmp {
                 }
                 data[self.offset..self.offset + bytes.len()] == bytes[..]
             }
+            None => false,
         }
     }
 }
 
+/// Inclusive range of account lamports, unbounded on a side when its limit is omitted
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LamportsRange {
+    pub min: Option<u64>,
+    pub max: Option<u64>,
+}
+
+impl LamportsRange {
+    pub fn contains(&self, lamports: u64) -> bool {
+        self.min.map(|min| lamports >= min).unwrap_or(true)
+            && self.max.map(|max| lamports <= max).unwrap_or(true)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -148,35 +236,196 @@ mod tests {
         .bytes_match(&data));
     }
 
+    #[test]
+    fn test_bytes_match_encodings() {
+        let data = vec![1, 2, 3, 4, 5];
+
+        let base58 = Memcmp {
+            offset: 1,
+            bytes: MemcmpEncodedBytes::Binary(bs58::encode(vec![2, 3]).into_string()),
+            encoding: Some(MemcmpEncoding::Base58),
+        };
+        assert!(base58.bytes_match(&data));
+
+        let base64 = Memcmp {
+            offset: 1,
+            bytes: MemcmpEncodedBytes::Binary(base64::encode(vec![2, 3])),
+            encoding: Some(MemcmpEncoding::Base64),
+        };
+        assert!(base64.bytes_match(&data));
+        assert!(!Memcmp {
+            encoding: None,
+            ..base64
+        }
+        .bytes_match(&data));
+
+        let bytes = Memcmp {
+            offset: 1,
+            bytes: MemcmpEncodedBytes::Bytes(vec![2, 3]),
+            encoding: None,
+        };
+        assert!(bytes.bytes_match(&data));
+        assert!(!Memcmp { offset: 2, ..bytes }.bytes_match(&data));
+    }
+
+    #[test]
+    fn test_deserialize_memcmp() {
+        let memcmp: RpcFilterType =
+            serde_json::from_str(r#"{"memcmp":{"offset":4,"bytes":"3Mc6vR"}}"#).unwrap();
+        assert_eq!(
+            memcmp,
+            RpcFilterType::Memcmp(Memcmp {
+                offset: 4,
+                bytes: MemcmpEncodedBytes::Binary("3Mc6vR".to_string()),
+                encoding: None,
+            })
+        );
+
+        let memcmp: RpcFilterType =
+            serde_json::from_str(r#"{"memcmp":{"offset":4,"bytes":"AQI=","encoding":"base64"}}"#)
+                .unwrap();
+        assert_eq!(
+            memcmp,
+            RpcFilterType::Memcmp(Memcmp {
+                offset: 4,
+                bytes: MemcmpEncodedBytes::Binary("AQI=".to_string()),
+                encoding: Some(MemcmpEncoding::Base64),
+            })
+        );
+
+        let memcmp: RpcFilterType =
+            serde_json::from_str(r#"{"memcmp":{"offset":4,"bytes":[1,2]}}"#).unwrap();
+        assert_eq!(
+            memcmp,
+            RpcFilterType::Memcmp(Memcmp {
+                offset: 4,
+                bytes: MemcmpEncodedBytes::Bytes(vec![1, 2]),
+                encoding: None,
+            })
+        );
+    }
+
     #[test]
     fn test_verify_memcmp() {
-        let base58_bytes = "\
-            1111111111111111111111111111111111111111111111111111111111111111\
-            1111111111111111111111111111111111111111111111111111111111111111";
-        assert_eq!(base58_bytes.len(), 128);
+        let base58_bytes = bs58::encode(vec![255; MAX_DATA_SIZE]).into_string();
+        assert_eq!(base58_bytes.len(), MAX_DATA_BASE58_SIZE);
         assert_eq!(
             RpcFilterType::Memcmp(Memcmp {
                 offset: 0,
-                bytes: MemcmpEncodedBytes::Binary(base58_bytes.to_string()),
+                bytes: MemcmpEncodedBytes::Binary(base58_bytes),
                 encoding: None,
             })
             .verify(),
             Ok(())
         );
 
-        let base58_bytes = "\
-            1111111111111111111111111111111111111111111111111111111111111111\
-            1111111111111111111111111111111111111111111111111111111111111111\
-            1";
-        assert_eq!(base58_bytes.len(), 129);
+        let base58_bytes = bs58::encode(vec![255; MAX_DATA_SIZE + 1]).into_string();
         assert_eq!(
             RpcFilterType::Memcmp(Memcmp {
                 offset: 0,
-                bytes: MemcmpEncodedBytes::Binary(base58_bytes.to_string()),
+                bytes: MemcmpEncodedBytes::Binary(base58_bytes),
                 encoding: None,
             })
             .verify(),
             Err(RpcFilterError::Base58DataTooLarge)
         );
+
+        // Leading zeros encode to a single base-58 character each
+        let base58_bytes = "1".repeat(MAX_DATA_SIZE + 1);
+        assert_eq!(
+            RpcFilterType::Memcmp(Memcmp {
+                offset: 0,
+                bytes: MemcmpEncodedBytes::Binary(base58_bytes),
+                encoding: Some(MemcmpEncoding::Base58),
+            })
+            .verify(),
+            Err(RpcFilterError::DataTooLarge)
+        );
+
+        let base64_bytes = base64::encode(vec![0; MAX_DATA_SIZE]);
+        assert_eq!(base64_bytes.len(), MAX_DATA_BASE64_SIZE);
+        assert_eq!(
+            RpcFilterType::Memcmp(Memcmp {
+                offset: 0,
+                bytes: MemcmpEncodedBytes::Binary(base64_bytes),
+                encoding: Some(MemcmpEncoding::Base64),
+            })
+            .verify(),
+            Ok(())
+        );
+
+        let base64_bytes = base64::encode(vec![0; MAX_DATA_SIZE + 3]);
+        assert_eq!(
+            RpcFilterType::Memcmp(Memcmp {
+                offset: 0,
+                bytes: MemcmpEncodedBytes::Binary(base64_bytes),
+                encoding: Some(MemcmpEncoding::Base64),
+            })
+            .verify(),
+            Err(RpcFilterError::Base64DataTooLarge)
+        );
+
+        assert!(matches!(
+            RpcFilterType::Memcmp(Memcmp {
+                offset: 0,
+                bytes: MemcmpEncodedBytes::Binary("A!==".to_string()),
+                encoding: Some(MemcmpEncoding::Base64),
+            })
+            .verify(),
+            Err(RpcFilterError::Base64DecodeError(_))
+        ));
+
+        assert_eq!(
+            RpcFilterType::Memcmp(Memcmp {
+                offset: 0,
+                bytes: MemcmpEncodedBytes::Bytes(vec![0; MAX_DATA_SIZE + 1]),
+                encoding: None,
+            })
+            .verify(),
+            Err(RpcFilterError::DataTooLarge)
+        );
+    }
+
+    #[test]
+    fn test_owner_and_lamports_filters() {
+        let owner = Pubkey::new_unique();
+        let account = Account::new(42, 0, &owner);
+
+        let owner_filter = RpcFilterType::Owner(owner);
+        assert_eq!(owner_filter.verify(), Ok(()));
+        assert!(owner_filter.allows(&account));
+        assert!(!RpcFilterType::Owner(Pubkey::new_unique()).allows(&account));
+
+        // The owner is parsed once, as the filter is deserialized
+        let json = format!(r#"{{"owner":"{}"}}"#, owner);
+        assert_eq!(serde_json::to_string(&owner_filter).unwrap(), json);
+        assert_eq!(
+            serde_json::from_str::<RpcFilterType>(&json).unwrap(),
+            owner_filter
+        );
+        assert!(serde_json::from_str::<RpcFilterType>(r#"{"owner":"III"}"#).is_err());
+
+        let lamports_filter = |min, max| RpcFilterType::Lamports(LamportsRange { min, max });
+        assert!(lamports_filter(None, None).allows(&account));
+        assert!(lamports_filter(Some(42), Some(42)).allows(&account));
+        assert!(lamports_filter(Some(1), None).allows(&account));
+        assert!(lamports_filter(None, Some(100)).allows(&account));
+        assert!(!lamports_filter(Some(43), None).allows(&account));
+        assert!(!lamports_filter(None, Some(41)).allows(&account));
+        assert_eq!(lamports_filter(Some(1), Some(2)).verify(), Ok(()));
+        assert_eq!(
+            lamports_filter(Some(2), Some(1)).verify(),
+            Err(RpcFilterError::InvalidLamportsRange)
+        );
+
+        let lamports_filter: RpcFilterType =
+            serde_json::from_str(r#"{"lamports":{"min":10}}"#).unwrap();
+        assert_eq!(
+            lamports_filter,
+            RpcFilterType::Lamports(LamportsRange {
+                min: Some(10),
+                max: None,
+            })
+        );
     }
 }
@@ -126,6 +126,7 @@ pub const MAX_GET_CONFIRMED_SIGNATURES_FOR_ADDRESS_SLOT_RANGE: u64 = 10_000;
 pub const MAX_GET_CONFIRMED_BLOCKS_RANGE: u64 = 500_000;
 pub const MAX_GET_CONFIRMED_SIGNATURES_FOR_ADDRESS2_LIMIT: usize = 1_000;
 pub const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+pub const MAX_BATCH_REQUESTS: usize = 100;
 pub const NUM_LARGEST_ACCOUNTS: usize = 20;
 pub const MAX_GET_PROGRAM_ACCOUNT_FILTERS: usize = 4;
 
@@ -142,6 +143,61 @@ impl RpcRequest {
            "params": params,
         })
     }
+
+    /// Whether repeating the request has no effect beyond that of sending it
+    /// once, so that it may be safely retried after a connection failure.
+    /// Only read-only requests are, new requests must be added here explicitly
+    pub fn is_idempotent(self) -> bool {
+        matches!(
+            self,
+            RpcRequest::GetAccountInfo
+                | RpcRequest::GetBalance
+                | RpcRequest::GetBlockTime
+                | RpcRequest::GetClusterNodes
+                | RpcRequest::GetConfirmedBlock
+                | RpcRequest::GetConfirmedBlocks
+                | RpcRequest::GetConfirmedBlocksWithLimit
+                | RpcRequest::GetConfirmedSignaturesForAddress
+                | RpcRequest::GetConfirmedSignaturesForAddress2
+                | RpcRequest::GetConfirmedTransaction
+                | RpcRequest::GetEpochInfo
+                | RpcRequest::GetEpochSchedule
+                | RpcRequest::GetFeeCalculatorForBlockhash
+                | RpcRequest::GetFeeRateGovernor
+                | RpcRequest::GetFees
+                | RpcRequest::GetFirstAvailableBlock
+                | RpcRequest::GetGenesisHash
+                | RpcRequest::GetHealth
+                | RpcRequest::GetIdentity
+                | RpcRequest::GetInflationGovernor
+                | RpcRequest::GetInflationRate
+                | RpcRequest::GetLargestAccounts
+                | RpcRequest::GetLeaderSchedule
+                | RpcRequest::GetMinimumBalanceForRentExemption
+                | RpcRequest::GetMultipleAccounts
+                | RpcRequest::GetProgramAccounts
+                | RpcRequest::GetRecentBlockhash
+                | RpcRequest::GetSnapshotSlot
+                | RpcRequest::GetSignatureStatuses
+                | RpcRequest::GetSlot
+                | RpcRequest::GetSlotLeader
+                | RpcRequest::GetStorageTurn
+                | RpcRequest::GetStorageTurnRate
+                | RpcRequest::GetSlotsPerSegment
+                | RpcRequest::GetStoragePubkeysForSlot
+                | RpcRequest::GetSupply
+                | RpcRequest::GetTokenAccountBalance
+                | RpcRequest::GetTokenAccountsByDelegate
+                | RpcRequest::GetTokenAccountsByOwner
+                | RpcRequest::GetTokenSupply
+                | RpcRequest::GetTotalSupply
+                | RpcRequest::GetTransactionCount
+                | RpcRequest::GetVersion
+                | RpcRequest::GetVoteAccounts
+                | RpcRequest::MinimumLedgerSlot
+                | RpcRequest::SimulateTransaction
+        )
+    }
 }
 
 #[derive(Debug)]
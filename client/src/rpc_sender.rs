@@ -1,5 +1,57 @@
 use crate::{client_error::Result, rpc_request::RpcRequest};
+use async_trait::async_trait;
+use std::time::Duration;
 
+/// Statistics about the requests issued by an `RpcSender`
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct RpcTransportStats {
+    /// Number of RPC requests issued
+    pub request_count: usize,
+    /// Number of requests that ultimately failed
+    pub error_count: usize,
+    /// Number of times a failed request was retried
+    pub retry_count: usize,
+    /// Total amount of time spent transacting with the RPC server, including
+    /// retries
+    pub elapsed_time: Duration,
+    /// Longest amount of time spent on a single request, including retries
+    pub max_elapsed_time: Duration,
+    /// Total amount of time spent waiting on RPC server rate limiting
+    pub rate_limited_time: Duration,
+}
+
+impl RpcTransportStats {
+    /// Average amount of time spent on a request
+    pub fn mean_elapsed_time(&self) -> Duration {
+        if self.request_count == 0 {
+            Duration::default()
+        } else {
+            self.elapsed_time / self.request_count as u32
+        }
+    }
+}
+
+#[async_trait]
 pub trait RpcSender {
-    fn send(&self, request: RpcRequest, params: serde_json::Value) -> Result<serde_json::Value>;
+    async fn send(
+        &self,
+        request: RpcRequest,
+        params: serde_json::Value,
+    ) -> Result<serde_json::Value>;
+
+    /// Send `requests` together, returning the result of each request in
+    /// order. Senders without a batch transport send the requests one at a
+    /// time.
+    async fn send_batch(
+        &self,
+        requests: Vec<(RpcRequest, serde_json::Value)>,
+    ) -> Result<Vec<Result<serde_json::Value>>> {
+        let mut results = Vec::with_capacity(requests.len());
+        for (request, params) in requests {
+            results.push(self.send(request, params).await);
+        }
+        Ok(results)
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats;
 }
@@ -37,17 +37,19 @@ use solana_sdk::{
         Slot, DEFAULT_TICKS_PER_SLOT, MAX_PROCESSING_AGE, MAX_TRANSACTION_FORWARDING_DELAY,
         MAX_TRANSACTION_FORWARDING_DELAY_GPU,
     },
+    compute_budget::{ComputeBudgetRequest, MICRO_LAMPORTS_PER_LAMPORT},
+    fee_calculator::{FeeCalculator, FeeConfig},
     poh_config::PohConfig,
     pubkey::Pubkey,
     timing::{duration_as_ms, timestamp},
-    transaction::{self, Transaction, TransactionError},
+    transaction::{self, Transaction, TransactionError, VersionedTransaction},
 };
 use solana_transaction_status::token_balances::{
     collect_token_balances, TransactionTokenBalancesSet,
 };
 use std::{
     cmp,
-    collections::{HashMap, VecDeque},
+    collections::{HashMap, HashSet, VecDeque},
     env,
     net::UdpSocket,
     sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
@@ -58,7 +60,9 @@ use std::{
     time::Instant,
 };
 
-type PacketsAndOffsets = (Packets, Vec<usize>);
+// Buffered packets, the indexes of those still unprocessed and, once a bank has seen them, the
+// slot of that bank along with the priority of each packet
+type PacketsAndOffsets = (Packets, Vec<usize>, Option<(Slot, Vec<u64>)>);
 pub type UnprocessedPackets = VecDeque<PacketsAndOffsets>;
 
 /// Transaction forwarding
@@ -82,6 +86,8 @@ pub struct BankingStageStats {
     current_buffered_packets_count: AtomicUsize,
     rebuffered_packets_count: AtomicUsize,
     consumed_buffered_packets_count: AtomicUsize,
+    reordered_packets_count: AtomicUsize,
+    deferred_transactions_count: AtomicUsize,
 }
 
 impl BankingStageStats {
@@ -140,6 +146,16 @@ impl BankingStageStats {
                     self.rebuffered_packets_count.swap(0, Ordering::Relaxed) as i64,
                     i64
                 ),
+                (
+                    "reordered_packets_count",
+                    self.reordered_packets_count.swap(0, Ordering::Relaxed) as i64,
+                    i64
+                ),
+                (
+                    "deferred_transactions_count",
+                    self.deferred_transactions_count.swap(0, Ordering::Relaxed) as i64,
+                    i64
+                ),
             );
         }
     }
@@ -235,7 +251,7 @@ impl BankingStage {
         all_packets: impl Iterator<Item = &'a PacketsAndOffsets>,
     ) -> Vec<&'a Packet> {
         all_packets
-            .flat_map(|(p, valid_indexes)| valid_indexes.iter().map(move |x| &p.packets[*x]))
+            .flat_map(|(p, valid_indexes, _)| valid_indexes.iter().map(move |x| &p.packets[*x]))
             .collect()
     }
 
@@ -277,11 +293,17 @@ impl BankingStage {
         banking_stage_stats: Option<&BankingStageStats>,
     ) {
         let mut rebuffered_packets_len = 0;
+        let mut reordered_packets_len = 0;
+        let mut deferred_txs_len = 0;
         let mut new_tx_count = 0;
-        let buffered_len = buffered_packets.len();
         let mut proc_start = Measure::start("consume_buffered_process");
+        let bank = poh_recorder.lock().unwrap().bank();
+        if let Some(bank) = bank {
+            reordered_packets_len = Self::prioritize_buffered_packets(buffered_packets, &bank);
+        }
+        let buffered_len = buffered_packets.len();
         let mut reached_end_of_slot = None;
-        buffered_packets.retain_mut(|(msgs, ref mut original_unprocessed_indexes)| {
+        buffered_packets.retain_mut(|(msgs, ref mut original_unprocessed_indexes, _)| {
             if let Some((next_leader, bank)) = &reached_end_of_slot {
                 // We've hit the end of this slot, no need to perform more processing,
                 // just filter the remaining packets for the invalid (e.g. too old) ones
@@ -299,7 +321,7 @@ impl BankingStage {
             } else {
                 let bank = poh_recorder.lock().unwrap().bank();
                 if let Some(bank) = bank {
-                    let (processed, verified_txs_len, new_unprocessed_indexes) =
+                    let (processed, verified_txs_len, new_unprocessed_indexes, deferred) =
                         Self::process_received_packets(
                             &bank,
                             &poh_recorder,
@@ -313,6 +335,7 @@ impl BankingStage {
                             Some((poh_recorder.lock().unwrap().next_slot_leader(), bank));
                     }
                     new_tx_count += processed;
+                    deferred_txs_len += deferred;
                     // Out of the buffered packets just retried, collect any still unprocessed
                     // transactions in this batch for forwarding
                     rebuffered_packets_len += new_unprocessed_indexes.len();
@@ -355,9 +378,85 @@ impl BankingStage {
             stats
                 .consumed_buffered_packets_count
                 .fetch_add(new_tx_count, Ordering::Relaxed);
+            stats
+                .reordered_packets_count
+                .fetch_add(reordered_packets_len, Ordering::Relaxed);
+            stats
+                .deferred_transactions_count
+                .fetch_add(deferred_txs_len, Ordering::Relaxed);
         }
     }
 
+    // Orders the buffered batches so that the batch holding the highest priority unprocessed
+    // packet is consumed first, the packets of a batch being ordered when it is processed. The
+    // priorities of a batch are computed once per working bank, with its fee calculator and
+    // features, and batches of equal priority keep their arrival order. Returns the number of packets in
+    // batches that were moved
+    fn prioritize_buffered_packets(
+        buffered_packets: &mut UnprocessedPackets,
+        bank: &Bank,
+    ) -> usize {
+        let (_, fee_calculator) = bank.last_blockhash_with_fee_calculator();
+        let fee_config = Self::fee_config(bank);
+        let batch_priorities = buffered_packets
+            .iter_mut()
+            .map(|(msgs, indexes, priorities)| {
+                if priorities.as_ref().map(|(slot, _)| *slot) != Some(bank.slot()) {
+                    *priorities = Some((
+                        bank.slot(),
+                        Self::packet_priorities(bank, msgs, indexes, &fee_calculator, &fee_config),
+                    ));
+                }
+                let (_, priorities) = priorities.as_ref().unwrap();
+                indexes
+                    .iter()
+                    .map(|index| priorities[*index])
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect_vec();
+        let order = (0..batch_priorities.len())
+            .sorted_by(|a, b| batch_priorities[*b].cmp(&batch_priorities[*a]))
+            .collect_vec();
+        let reordered_count = order
+            .iter()
+            .enumerate()
+            .filter(|(position, index)| *position != **index)
+            .map(|(_, index)| buffered_packets[*index].1.len())
+            .sum();
+        if reordered_count == 0 {
+            return 0;
+        }
+
+        let mut batches = buffered_packets.drain(..).map(Some).collect_vec();
+        buffered_packets.extend(
+            order
+                .into_iter()
+                .map(|index| batches[index].take().unwrap()),
+        );
+        reordered_count
+    }
+
+    // Returns the priority of each unprocessed packet of a batch, by packet index
+    fn packet_priorities(
+        bank: &Bank,
+        msgs: &Packets,
+        packet_indexes: &[usize],
+        fee_calculator: &FeeCalculator,
+        fee_config: &FeeConfig,
+    ) -> Vec<u64> {
+        let mut priorities = vec![0; msgs.packets.len()];
+        for index in packet_indexes {
+            let packet = &msgs.packets[*index];
+            priorities[*index] = limited_deserialize(&packet.data[0..packet.meta.size])
+                .ok()
+                .and_then(|tx: VersionedTransaction| bank.resolve_versioned_transaction(&tx).ok())
+                .map(|tx| Self::transaction_priority(&tx, fee_calculator, fee_config))
+                .unwrap_or(0);
+        }
+        priorities
+    }
+
     fn consume_or_forward_packets(
         my_pubkey: &Pubkey,
         leader_pubkey: Option<Pubkey>,
@@ -537,7 +636,7 @@ impl BankingStage {
     }
 
     /// Convert the transactions from a blob of binary data to a vector of transactions
-    fn deserialize_transactions(p: &Packets) -> Vec<Option<Transaction>> {
+    fn deserialize_transactions(p: &Packets) -> Vec<Option<VersionedTransaction>> {
         p.packets
             .iter()
             .map(|x| limited_deserialize(&x.data[0..x.meta.size]).ok())
@@ -547,7 +646,7 @@ impl BankingStage {
     #[allow(clippy::match_wild_err_arm)]
     fn record_transactions(
         bank_slot: Slot,
-        txs: &[Transaction],
+        txs: &[VersionedTransaction],
         results: &[TransactionExecutionResult],
         poh: &Arc<Mutex<PohRecorder>>,
     ) -> (Result<usize, PohRecorderError>, Vec<usize>) {
@@ -603,6 +702,7 @@ impl BankingStage {
         bank: &Arc<Bank>,
         poh: &Arc<Mutex<PohRecorder>>,
         batch: &TransactionBatch,
+        versioned_txs: &[VersionedTransaction],
         transaction_status_sender: Option<TransactionStatusSender>,
         gossip_vote_sender: &ReplayVoteSender,
     ) -> (Result<usize, PohRecorderError>, Vec<usize>) {
@@ -633,6 +733,8 @@ impl BankingStage {
             results,
             inner_instructions,
             transaction_logs,
+            transaction_return_data,
+            _executed_units,
             mut retryable_txs,
             tx_count,
             signature_count,
@@ -649,7 +751,7 @@ impl BankingStage {
 
         let mut record_time = Measure::start("record_time");
         let (num_to_commit, retryable_record_txs) =
-            Self::record_transactions(bank.slot(), txs, &results, poh);
+            Self::record_transactions(bank.slot(), versioned_txs, &results, poh);
         retryable_txs.extend(retryable_record_txs);
         if num_to_commit.is_err() {
             return (num_to_commit, retryable_txs);
@@ -678,12 +780,14 @@ impl BankingStage {
                 send_transaction_status_batch(
                     bank.clone(),
                     batch.transactions(),
+                    versioned_txs,
                     batch.iteration_order_vec(),
                     tx_results.execution_results,
                     TransactionBalancesSet::new(pre_balances, post_balances),
                     TransactionTokenBalancesSet::new(pre_token_balances, post_token_balances),
                     inner_instructions,
                     transaction_logs,
+                    transaction_return_data,
                     transaction_status_sender,
                 );
             }
@@ -707,6 +811,7 @@ impl BankingStage {
     pub fn process_and_record_transactions(
         bank: &Arc<Bank>,
         txs: &[Transaction],
+        versioned_txs: &[VersionedTransaction],
         poh: &Arc<Mutex<PohRecorder>>,
         chunk_offset: usize,
         transaction_status_sender: Option<TransactionStatusSender>,
@@ -722,6 +827,7 @@ impl BankingStage {
             bank,
             poh,
             &batch,
+            versioned_txs,
             transaction_status_sender,
             gossip_vote_sender,
         );
@@ -743,30 +849,39 @@ impl BankingStage {
         (result, retryable_txs)
     }
 
-    /// Sends transactions to the bank.
+    /// Sends transactions to the bank in the conflict free batches built by
+    /// `schedule_transaction_batches`.
     ///
     /// Returns the number of transactions successfully processed by the bank, which may be less
-    /// than the total number if max PoH height was reached and the bank halted
+    /// than the total number if max PoH height was reached and the bank halted, the indexes of
+    /// the unprocessed transactions, and the number of transactions deferred to a later batch
     fn process_transactions(
         bank: &Arc<Bank>,
         transactions: &[Transaction],
+        versioned_transactions: &[VersionedTransaction],
         poh: &Arc<Mutex<PohRecorder>>,
         transaction_status_sender: Option<TransactionStatusSender>,
         gossip_vote_sender: &ReplayVoteSender,
-    ) -> (usize, Vec<usize>) {
-        let mut chunk_start = 0;
+    ) -> (usize, Vec<usize>, usize) {
+        let (batches, deferred_count) = Self::schedule_transaction_batches(transactions);
+        let mut processed = 0;
         let mut unprocessed_txs = vec![];
-        while chunk_start != transactions.len() {
-            let chunk_end = std::cmp::min(
-                transactions.len(),
-                chunk_start + MAX_NUM_TRANSACTIONS_PER_BATCH,
-            );
+        for (batch_index, batch) in batches.iter().enumerate() {
+            let batch_transactions = batch
+                .iter()
+                .map(|index| transactions[*index].clone())
+                .collect_vec();
+            let batch_versioned_transactions = batch
+                .iter()
+                .map(|index| versioned_transactions[*index].clone())
+                .collect_vec();
 
-            let (result, retryable_txs_in_chunk) = Self::process_and_record_transactions(
+            let (result, retryable_txs_in_batch) = Self::process_and_record_transactions(
                 bank,
-                &transactions[chunk_start..chunk_end],
+                &batch_transactions,
+                &batch_versioned_transactions,
                 poh,
-                chunk_start,
+                0,
                 transaction_status_sender.clone(),
                 gossip_vote_sender,
             );
@@ -774,7 +889,7 @@ impl BankingStage {
 
             // Add the retryable txs (transactions that errored in a way that warrants a retry)
             // to the list of unprocessed txs.
-            unprocessed_txs.extend_from_slice(&retryable_txs_in_chunk);
+            unprocessed_txs.extend(retryable_txs_in_batch.into_iter().map(|x| batch[x]));
             if let Err(PohRecorderError::MaxHeightReached) = result {
                 info!(
                     "process transactions: max height reached slot: {} height: {}",
@@ -782,24 +897,68 @@ impl BankingStage {
                     bank.tick_height()
                 );
                 // process_and_record_transactions has returned all retryable errors in
-                // transactions[chunk_start..chunk_end], so we just need to push the remaining
-                // transactions into the unprocessed queue.
-                unprocessed_txs.extend(chunk_end..transactions.len());
+                // this batch, so we just need to push the transactions of the remaining
+                // batches into the unprocessed queue.
+                unprocessed_txs.extend(batches[batch_index + 1..].iter().flatten());
                 break;
             }
             // Don't exit early on any other type of error, continue processing...
-            chunk_start = chunk_end;
+            processed += batch.len();
+        }
+
+        (processed, unprocessed_txs, deferred_count)
+    }
+
+    // This function groups transactions, which are expected in priority order, into batches of
+    // at most `MAX_NUM_TRANSACTIONS_PER_BATCH` transactions whose account locks don't conflict.
+    // A transaction that write locks an account already locked in the batch being built, or read
+    // locks an account that is write locked, is deferred to the next batch ahead of any lower
+    // priority transactions. Returns the batches as indexes into `transactions` and the number of
+    // deferred transactions
+    fn schedule_transaction_batches(transactions: &[Transaction]) -> (Vec<Vec<usize>>, usize) {
+        let mut batches = vec![];
+        let mut deferred = vec![false; transactions.len()];
+        let mut pending_indexes = (0..transactions.len()).collect_vec();
+        while !pending_indexes.is_empty() {
+            let mut batch = vec![];
+            let mut write_locks: HashSet<&Pubkey> = HashSet::new();
+            let mut read_locks: HashSet<&Pubkey> = HashSet::new();
+            let mut next_pending_indexes = vec![];
+            for index in pending_indexes {
+                if batch.len() == MAX_NUM_TRANSACTIONS_PER_BATCH {
+                    next_pending_indexes.push(index);
+                    continue;
+                }
+                let (writable_keys, readonly_keys) = transactions[index]
+                    .message()
+                    .get_account_keys_by_lock_type();
+                let has_conflict = writable_keys
+                    .iter()
+                    .any(|key| write_locks.contains(key) || read_locks.contains(key))
+                    || readonly_keys.iter().any(|key| write_locks.contains(key));
+                if has_conflict {
+                    deferred[index] = true;
+                    next_pending_indexes.push(index);
+                } else {
+                    write_locks.extend(writable_keys);
+                    read_locks.extend(readonly_keys);
+                    batch.push(index);
+                }
+            }
+            batches.push(batch);
+            pending_indexes = next_pending_indexes;
         }
 
-        (chunk_start, unprocessed_txs)
+        let deferred_count = deferred.into_iter().filter(|deferred| *deferred).count();
+        (batches, deferred_count)
     }
 
     // This function returns a vector of transactions that are not None. It also returns a vector
     // with position of the transaction in the input list
-    fn filter_transaction_indexes(
-        transactions: Vec<Option<Transaction>>,
+    fn filter_transaction_indexes<T>(
+        transactions: Vec<Option<T>>,
         indexes: &[usize],
-    ) -> (Vec<Transaction>, Vec<usize>) {
+    ) -> (Vec<T>, Vec<usize>) {
         transactions
             .into_iter()
             .zip(indexes)
@@ -836,12 +995,66 @@ impl BankingStage {
             .collect()
     }
 
-    // This function deserializes packets into transactions and returns non-None transactions
+    // This function returns the fee-per-resource priority of a transaction, the fee it pays in
+    // micro-lamports for each compute unit it may consume. Transactions with invalid compute
+    // budget instructions will fail and have the lowest priority
+    fn transaction_priority(
+        transaction: &Transaction,
+        fee_calculator: &FeeCalculator,
+        fee_config: &FeeConfig,
+    ) -> u64 {
+        match ComputeBudgetRequest::try_from_message(transaction.message()) {
+            Ok(request) => {
+                let fee = u128::from(
+                    fee_calculator.calculate_fee_with_config(transaction.message(), fee_config),
+                );
+                let priority = fee.saturating_mul(u128::from(MICRO_LAMPORTS_PER_LAMPORT))
+                    / u128::from(request.compute_units().max(1));
+                priority.min(u128::from(u64::MAX)) as u64
+            }
+            Err(_) => 0,
+        }
+    }
+
+    // Returns the fee config matching the features active in the bank, so that priorities only
+    // count the fees the bank charges
+    fn fee_config(bank: &Bank) -> FeeConfig {
+        FeeConfig {
+            secp256k1_program_enabled: bank.secp256k1_program_enabled(),
+            compute_budget_program_enabled: bank.compute_budget_program_enabled(),
+        }
+    }
+
+    // This function orders transactions, paired with the versioned transactions they were
+    // resolved from, by descending fee-per-resource so that transactions paying for priority are
+    // processed and forwarded first. Transactions with the same priority keep their relative order
+    fn sort_transactions_by_priority(
+        transactions: Vec<(Transaction, VersionedTransaction)>,
+        indexes: Vec<usize>,
+        fee_calculator: &FeeCalculator,
+        fee_config: &FeeConfig,
+    ) -> (Vec<(Transaction, VersionedTransaction)>, Vec<usize>) {
+        transactions
+            .into_iter()
+            .zip(indexes)
+            .map(|(tx, index)| {
+                let priority = Self::transaction_priority(&tx.0, fee_calculator, fee_config);
+                (priority, tx, index)
+            })
+            .sorted_by(|a, b| b.0.cmp(&a.0))
+            .map(|(_, tx, index)| (tx, index))
+            .unzip()
+    }
+
+    // This function deserializes packets into versioned transactions, resolves them against the
+    // bank and returns the resolved transactions ordered by priority, along with the versioned
+    // transactions that are recorded in entries
     fn transactions_from_packets(
+        bank: &Bank,
         msgs: &Packets,
         transaction_indexes: &[usize],
-        secp256k1_program_enabled: bool,
-    ) -> (Vec<Transaction>, Vec<usize>) {
+        fee_calculator: &FeeCalculator,
+    ) -> (Vec<Transaction>, Vec<VersionedTransaction>, Vec<usize>) {
         let packets = Packets::new(
             transaction_indexes
                 .iter()
@@ -849,27 +1062,29 @@ impl BankingStage {
                 .collect_vec(),
         );
 
-        let transactions = Self::deserialize_transactions(&packets);
-        let maybe_secp_verified_transactions: Vec<_> = if secp256k1_program_enabled {
-            transactions
-                .into_iter()
-                .map(|tx| {
-                    if let Some(tx) = tx {
-                        if tx.verify_precompiles().is_ok() {
-                            Some(tx)
-                        } else {
-                            None
-                        }
-                    } else {
-                        None
-                    }
-                })
-                .collect()
-        } else {
-            transactions
-        };
+        let secp256k1_program_enabled = bank.secp256k1_program_enabled();
+        let resolved_transactions = Self::deserialize_transactions(&packets)
+            .into_iter()
+            .map(|tx| {
+                let tx = tx?;
+                if secp256k1_program_enabled && tx.verify_precompiles().is_err() {
+                    return None;
+                }
+                let resolved_tx = bank.resolve_versioned_transaction(&tx).ok()?;
+                Some((resolved_tx, tx))
+            })
+            .collect_vec();
 
-        Self::filter_transaction_indexes(maybe_secp_verified_transactions, &transaction_indexes)
+        let (transactions, transaction_indexes) =
+            Self::filter_transaction_indexes(resolved_transactions, transaction_indexes);
+        let (transactions, transaction_indexes) = Self::sort_transactions_by_priority(
+            transactions,
+            transaction_indexes,
+            fee_calculator,
+            &Self::fee_config(bank),
+        );
+        let (transactions, versioned_transactions) = transactions.into_iter().unzip();
+        (transactions, versioned_transactions, transaction_indexes)
     }
 
     /// This function filters pending packets that are still valid
@@ -917,12 +1132,10 @@ impl BankingStage {
         packet_indexes: Vec<usize>,
         transaction_status_sender: Option<TransactionStatusSender>,
         gossip_vote_sender: &ReplayVoteSender,
-    ) -> (usize, usize, Vec<usize>) {
-        let (transactions, transaction_to_packet_indexes) = Self::transactions_from_packets(
-            msgs,
-            &packet_indexes,
-            bank.secp256k1_program_enabled(),
-        );
+    ) -> (usize, usize, Vec<usize>, usize) {
+        let (_, fee_calculator) = bank.last_blockhash_with_fee_calculator();
+        let (transactions, versioned_transactions, transaction_to_packet_indexes) =
+            Self::transactions_from_packets(bank, msgs, &packet_indexes, &fee_calculator);
         debug!(
             "bank: {} filtered transactions {}",
             bank.slot(),
@@ -931,9 +1144,10 @@ impl BankingStage {
 
         let tx_len = transactions.len();
 
-        let (processed, unprocessed_tx_indexes) = Self::process_transactions(
+        let (processed, unprocessed_tx_indexes, deferred_count) = Self::process_transactions(
             bank,
             &transactions,
+            &versioned_transactions,
             poh,
             transaction_status_sender,
             gossip_vote_sender,
@@ -952,7 +1166,12 @@ impl BankingStage {
             unprocessed_tx_count.saturating_sub(filtered_unprocessed_packet_indexes.len())
         );
 
-        (processed, tx_len, filtered_unprocessed_packet_indexes)
+        (
+            processed,
+            tx_len,
+            filtered_unprocessed_packet_indexes,
+            deferred_count,
+        )
     }
 
     fn filter_unprocessed_packets(
@@ -971,11 +1190,9 @@ impl BankingStage {
             }
         }
 
-        let (transactions, transaction_to_packet_indexes) = Self::transactions_from_packets(
-            msgs,
-            &transaction_indexes,
-            bank.secp256k1_program_enabled(),
-        );
+        let (_, fee_calculator) = bank.last_blockhash_with_fee_calculator();
+        let (transactions, _, transaction_to_packet_indexes) =
+            Self::transactions_from_packets(bank, msgs, transaction_indexes, &fee_calculator);
 
         let tx_count = transaction_to_packet_indexes.len();
 
@@ -1045,6 +1262,7 @@ impl BankingStage {
         let mut mms_iter = mms.into_iter();
         let mut dropped_batches_count = 0;
         let mut newly_buffered_packets_count = 0;
+        let mut deferred_txs_count = 0;
         while let Some(msgs) = mms_iter.next() {
             let packet_indexes = Self::generate_packet_indexes(&msgs.packets);
             let bank = poh.lock().unwrap().bank();
@@ -1061,16 +1279,18 @@ impl BankingStage {
             }
             let bank = bank.unwrap();
 
-            let (processed, verified_txs_len, unprocessed_indexes) = Self::process_received_packets(
-                &bank,
-                &poh,
-                &msgs,
-                packet_indexes,
-                transaction_status_sender.clone(),
-                gossip_vote_sender,
-            );
+            let (processed, verified_txs_len, unprocessed_indexes, deferred_count) =
+                Self::process_received_packets(
+                    &bank,
+                    &poh,
+                    &msgs,
+                    packet_indexes,
+                    transaction_status_sender.clone(),
+                    gossip_vote_sender,
+                );
 
             new_tx_count += processed;
+            deferred_txs_count += deferred_count;
 
             // Collect any unprocessed transactions in this batch for forwarding
             Self::push_unprocessed(
@@ -1132,6 +1352,9 @@ impl BankingStage {
         banking_stage_stats
             .newly_buffered_packets_count
             .fetch_add(newly_buffered_packets_count, Ordering::Relaxed);
+        banking_stage_stats
+            .deferred_transactions_count
+            .fetch_add(deferred_txs_count, Ordering::Relaxed);
         banking_stage_stats
             .current_buffered_packets_count
             .swap(buffered_packets.len(), Ordering::Relaxed);
@@ -1153,7 +1376,7 @@ impl BankingStage {
                 unprocessed_packets.pop_front();
             }
             *newly_buffered_packets_count += packet_indexes.len();
-            unprocessed_packets.push_back((packets, packet_indexes));
+            unprocessed_packets.push_back((packets, packet_indexes, None));
         }
     }
 
@@ -1223,9 +1446,12 @@ mod tests {
     };
     use solana_perf::packet::to_packets_chunked;
     use solana_sdk::{
+        compute_budget::ComputeBudgetInstruction,
+        feature_set,
         instruction::InstructionError,
+        message::{v0, VersionedMessage},
         signature::{Keypair, Signer},
-        system_instruction::SystemError,
+        system_instruction::{self, SystemError},
         system_transaction,
         transaction::TransactionError,
     };
@@ -1333,6 +1559,10 @@ mod tests {
         with_vers.into_iter().map(|(b, _)| b).collect()
     }
 
+    fn to_versioned_transactions(transactions: &[Transaction]) -> Vec<VersionedTransaction> {
+        transactions.iter().cloned().map(Into::into).collect()
+    }
+
     #[test]
     fn test_banking_stage_entries_only() {
         solana_logger::setup();
@@ -1429,7 +1659,12 @@ mod tests {
                 if !entries.is_empty() {
                     blockhash = entries.last().unwrap().hash;
                     for entry in entries {
-                        bank.process_transactions(&entry.transactions)
+                        let transactions = entry
+                            .transactions
+                            .iter()
+                            .map(|tx| bank.resolve_versioned_transaction(tx).unwrap())
+                            .collect_vec();
+                        bank.process_transactions(&transactions)
                             .iter()
                             .for_each(|x| assert_eq!(*x, Ok(())));
                     }
@@ -1540,7 +1775,12 @@ mod tests {
 
             let bank = Bank::new(&genesis_config);
             for entry in &entries {
-                bank.process_transactions(&entry.transactions)
+                let transactions = entry
+                    .transactions
+                    .iter()
+                    .map(|tx| bank.resolve_versioned_transaction(tx).unwrap())
+                    .collect_vec();
+                bank.process_transactions(&transactions)
                     .iter()
                     .for_each(|x| assert_eq!(*x, Ok(())));
             }
@@ -1588,9 +1828,10 @@ mod tests {
             let keypair2 = Keypair::new();
             let pubkey2 = solana_sdk::pubkey::new_rand();
 
-            let transactions = vec![
-                system_transaction::transfer(&mint_keypair, &pubkey, 1, genesis_config.hash()),
-                system_transaction::transfer(&keypair2, &pubkey2, 1, genesis_config.hash()),
+            let transactions: Vec<VersionedTransaction> = vec![
+                system_transaction::transfer(&mint_keypair, &pubkey, 1, genesis_config.hash())
+                    .into(),
+                system_transaction::transfer(&keypair2, &pubkey2, 1, genesis_config.hash()).into(),
             ];
 
             let mut results = vec![(Ok(()), None), (Ok(()), None)];
@@ -1735,6 +1976,286 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_sort_transactions_by_priority() {
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config(10_000);
+        let pubkey = solana_sdk::pubkey::new_rand();
+        let transfer_with_price = |price: Option<u64>| {
+            let mut instructions = vec![];
+            if let Some(price) = price {
+                instructions.push(ComputeBudgetInstruction::set_compute_unit_price(price));
+            }
+            instructions.push(system_instruction::transfer(
+                &mint_keypair.pubkey(),
+                &pubkey,
+                1,
+            ));
+            Transaction::new_signed_with_payer(
+                &instructions,
+                Some(&mint_keypair.pubkey()),
+                &[&mint_keypair],
+                genesis_config.hash(),
+            )
+        };
+
+        // Requesting fewer compute units raises the fee paid per compute unit
+        let transfer_with_limit = Transaction::new_signed_with_payer(
+            &[
+                ComputeBudgetInstruction::set_compute_unit_limit(1_000),
+                system_instruction::transfer(&mint_keypair.pubkey(), &pubkey, 1),
+            ],
+            Some(&mint_keypair.pubkey()),
+            &[&mint_keypair],
+            genesis_config.hash(),
+        );
+
+        let transactions = vec![
+            transfer_with_price(None),
+            transfer_with_price(Some(5)),
+            transfer_with_price(Some(0)),
+            transfer_with_price(Some(10)),
+            transfer_with_price(Some(5)),
+            transfer_with_limit,
+        ]
+        .into_iter()
+        .map(|tx| (tx.clone(), VersionedTransaction::from(tx)))
+        .collect_vec();
+        let (sorted_transactions, sorted_indexes) = BankingStage::sort_transactions_by_priority(
+            transactions.clone(),
+            vec![3, 5, 7, 9, 11, 13],
+            &FeeCalculator::new(5_000),
+            &FeeConfig::default(),
+        );
+        assert_eq!(sorted_indexes, vec![13, 9, 5, 11, 3, 7]);
+        assert_eq!(
+            sorted_transactions,
+            vec![
+                transactions[5].clone(),
+                transactions[3].clone(),
+                transactions[1].clone(),
+                transactions[4].clone(),
+                transactions[0].clone(),
+                transactions[2].clone(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_schedule_transaction_batches() {
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config(10_000);
+        let keypairs = (0..3).map(|_| Keypair::new()).collect_vec();
+        let pubkey = solana_sdk::pubkey::new_rand();
+
+        let transactions = vec![
+            system_transaction::transfer(&mint_keypair, &pubkey, 1, genesis_config.hash()),
+            // Write locks the mint, which is already write locked
+            system_transaction::transfer(&mint_keypair, &pubkey, 2, genesis_config.hash()),
+            // Write locks `pubkey`, which is already write locked
+            system_transaction::transfer(&keypairs[0], &pubkey, 1, genesis_config.hash()),
+            system_transaction::transfer(
+                &keypairs[1],
+                &keypairs[2].pubkey(),
+                1,
+                genesis_config.hash(),
+            ),
+        ];
+        assert_eq!(
+            BankingStage::schedule_transaction_batches(&transactions),
+            (vec![vec![0, 3], vec![1], vec![2]], 2)
+        );
+
+        // Non-conflicting transactions are split into batches of at most
+        // `MAX_NUM_TRANSACTIONS_PER_BATCH`
+        let transactions = (0..MAX_NUM_TRANSACTIONS_PER_BATCH + 1)
+            .map(|_| {
+                system_transaction::transfer(
+                    &Keypair::new(),
+                    &solana_sdk::pubkey::new_rand(),
+                    1,
+                    genesis_config.hash(),
+                )
+            })
+            .collect_vec();
+        let (batches, deferred_count) = BankingStage::schedule_transaction_batches(&transactions);
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[0].len(), MAX_NUM_TRANSACTIONS_PER_BATCH);
+        assert_eq!(batches[1], vec![MAX_NUM_TRANSACTIONS_PER_BATCH]);
+        assert_eq!(deferred_count, 0);
+    }
+
+    #[test]
+    fn test_prioritize_buffered_packets() {
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config(10_000);
+        let pubkey = solana_sdk::pubkey::new_rand();
+        let transfer_with_price = |price: u64| {
+            Transaction::new_signed_with_payer(
+                &[
+                    ComputeBudgetInstruction::set_compute_unit_price(price),
+                    system_instruction::transfer(&mint_keypair.pubkey(), &pubkey, 1),
+                ],
+                Some(&mint_keypair.pubkey()),
+                &[&mint_keypair],
+                genesis_config.hash(),
+            )
+        };
+        let bank = Bank::new(&genesis_config);
+
+        let low_priority_packets = to_packets_chunked(&[transfer_with_price(1)], 1).remove(0);
+        let high_priority_packets =
+            to_packets_chunked(&[transfer_with_price(10), transfer_with_price(100)], 2).remove(0);
+
+        // Batches that are already in priority order are left as is
+        let mut buffered_packets: UnprocessedPackets = vec![
+            (high_priority_packets.clone(), vec![1], None),
+            (low_priority_packets.clone(), vec![0], None),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(
+            BankingStage::prioritize_buffered_packets(&mut buffered_packets, &bank),
+            0
+        );
+        assert_eq!(buffered_packets.len(), 2);
+
+        // Batches are ordered by their highest priority unprocessed packet, and the priorities
+        // of the unprocessed packets are kept with the batch
+        let mut buffered_packets: UnprocessedPackets = vec![
+            (low_priority_packets.clone(), vec![0], None),
+            (high_priority_packets.clone(), vec![1], None),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(
+            BankingStage::prioritize_buffered_packets(&mut buffered_packets, &bank),
+            2
+        );
+        assert_eq!(buffered_packets.len(), 2);
+        let (packets, indexes, priorities) = &buffered_packets[0];
+        assert_eq!(packets.packets[1], high_priority_packets.packets[1]);
+        assert_eq!(indexes, &vec![1]);
+        let (slot, priorities) = priorities.as_ref().unwrap();
+        assert_eq!(*slot, bank.slot());
+        assert_eq!(priorities[0], 0);
+        assert!(priorities[1] > buffered_packets[1].2.as_ref().unwrap().1[0]);
+
+        // Priorities are not computed again for the same bank
+        buffered_packets[0].2 = Some((bank.slot(), vec![0, 0]));
+        assert_eq!(
+            BankingStage::prioritize_buffered_packets(&mut buffered_packets, &bank),
+            2
+        );
+        assert_eq!(
+            buffered_packets[0].0.packets[0],
+            low_priority_packets.packets[0]
+        );
+
+        // A new working bank computes them afresh, restoring the order by fee
+        let bank = Arc::new(bank);
+        let child_bank = Bank::new_from_parent(&bank, &Pubkey::default(), bank.slot() + 1);
+        assert_eq!(
+            BankingStage::prioritize_buffered_packets(&mut buffered_packets, &child_bank),
+            2
+        );
+        assert_eq!(
+            buffered_packets[0].0.packets[1],
+            high_priority_packets.packets[1]
+        );
+        assert_eq!(
+            buffered_packets[1].0.packets[0],
+            low_priority_packets.packets[0]
+        );
+        for (_, _, priorities) in &buffered_packets {
+            assert_eq!(priorities.as_ref().unwrap().0, child_bank.slot());
+        }
+
+        // Until the bank charges for compute unit prices, a higher price does not raise the
+        // priority of a transaction
+        let mut genesis_config = genesis_config;
+        genesis_config
+            .accounts
+            .remove(&feature_set::compute_budget_program::id());
+        let bank = Bank::new(&genesis_config);
+        assert!(!bank.compute_budget_program_enabled());
+        let mut buffered_packets: UnprocessedPackets = vec![
+            (low_priority_packets.clone(), vec![0], None),
+            (high_priority_packets, vec![1], None),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(
+            BankingStage::prioritize_buffered_packets(&mut buffered_packets, &bank),
+            0
+        );
+        assert_eq!(
+            buffered_packets[0].0.packets[0],
+            low_priority_packets.packets[0]
+        );
+        let (_, priorities) = buffered_packets[0].2.as_ref().unwrap();
+        assert_eq!(priorities[0], buffered_packets[1].2.as_ref().unwrap().1[1]);
+    }
+
+    #[test]
+    fn test_transactions_from_packets_with_versioned_transactions() {
+        let GenesisConfigInfo {
+            mut genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config(10_000);
+        let pubkey = solana_sdk::pubkey::new_rand();
+        let transfer =
+            system_transaction::transfer(&mint_keypair, &pubkey, 1, genesis_config.hash());
+        let versioned_transfer = VersionedTransaction::try_new(
+            VersionedMessage::V0(v0::Message {
+                header: transfer.message.header.clone(),
+                account_keys: transfer.message.account_keys.clone(),
+                recent_blockhash: transfer.message.recent_blockhash,
+                instructions: transfer.message.instructions.clone(),
+                address_table_lookups: vec![],
+            }),
+            &[&mint_keypair],
+        )
+        .unwrap();
+        let packets = to_packets_chunked(&[versioned_transfer.clone()], 1).remove(0);
+
+        // The bank executes the resolved transaction, and the original is kept for recording
+        let bank = Bank::new(&genesis_config);
+        let (_, fee_calculator) = bank.last_blockhash_with_fee_calculator();
+        let (transactions, versioned_transactions, indexes) =
+            BankingStage::transactions_from_packets(&bank, &packets, &[0], &fee_calculator);
+        assert_eq!(
+            transactions,
+            vec![Transaction {
+                signatures: versioned_transfer.signatures.clone(),
+                message: transfer.message,
+            }]
+        );
+        assert_eq!(versioned_transactions, vec![versioned_transfer]);
+        assert_eq!(indexes, vec![0]);
+
+        // v0 transactions are dropped until versioned messages are enabled
+        genesis_config
+            .accounts
+            .remove(&feature_set::versioned_tx_message_enabled::id());
+        let bank = Bank::new(&genesis_config);
+        let (transactions, versioned_transactions, indexes) =
+            BankingStage::transactions_from_packets(&bank, &packets, &[0], &fee_calculator);
+        assert!(transactions.is_empty());
+        assert!(versioned_transactions.is_empty());
+        assert!(indexes.is_empty());
+    }
+
     #[test]
     fn test_bank_prepare_filter_for_pending_transaction() {
         let GenesisConfigInfo {
@@ -1897,6 +2418,7 @@ mod tests {
             BankingStage::process_and_record_transactions(
                 &bank,
                 &transactions,
+                &to_versioned_transactions(&transactions),
                 &poh_recorder,
                 0,
                 None,
@@ -1934,6 +2456,7 @@ mod tests {
                 BankingStage::process_and_record_transactions(
                     &bank,
                     &transactions,
+                    &to_versioned_transactions(&transactions),
                     &poh_recorder,
                     0,
                     None,
@@ -1994,6 +2517,7 @@ mod tests {
             let (result, unprocessed) = BankingStage::process_and_record_transactions(
                 &bank,
                 &transactions,
+                &to_versioned_transactions(&transactions),
                 &poh_recorder,
                 0,
                 None,
@@ -2024,7 +2548,7 @@ mod tests {
                 let valid_indexes = (0..32)
                     .filter_map(|x| if x % 2 != 0 { Some(x as usize) } else { None })
                     .collect_vec();
-                (packets, valid_indexes)
+                (packets, valid_indexes, None)
             })
             .collect_vec();
 
@@ -2083,10 +2607,11 @@ mod tests {
 
             let (gossip_vote_sender, _gossip_vote_receiver) = unbounded();
 
-            let (processed_transactions_count, mut retryable_txs) =
+            let (processed_transactions_count, mut retryable_txs, _) =
                 BankingStage::process_transactions(
                     &bank,
                     &transactions,
+                    &to_versioned_transactions(&transactions),
                     &poh_recorder,
                     None,
                     &gossip_vote_sender,
@@ -2172,6 +2697,7 @@ mod tests {
             let _ = BankingStage::process_and_record_transactions(
                 &bank,
                 &transactions,
+                &to_versioned_transactions(&transactions),
                 &poh_recorder,
                 0,
                 Some(TransactionStatusSender {
@@ -2266,6 +2792,7 @@ mod tests {
             let mut buffered_packets: UnprocessedPackets = vec![(
                 all_packets,
                 (0..num_conflicting_transactions).into_iter().collect(),
+                None,
             )]
             .into_iter()
             .collect();
@@ -2284,25 +2811,32 @@ mod tests {
                 None,
             );
             assert_eq!(buffered_packets[0].1.len(), num_conflicting_transactions);
-            // When the poh recorder has a bank, should process all non conflicting buffered packets.
-            // Processes one packet per iteration of the loop
-            for num_expected_unprocessed in (0..num_conflicting_transactions).rev() {
-                poh_recorder.lock().unwrap().set_bank(&bank);
-                BankingStage::consume_buffered_packets(
-                    &Pubkey::default(),
-                    &poh_recorder,
-                    &mut buffered_packets,
-                    None,
-                    &gossip_vote_sender,
-                    None::<Box<dyn Fn()>>,
-                    None,
-                );
-                if num_expected_unprocessed == 0 {
-                    assert!(buffered_packets.is_empty())
-                } else {
-                    assert_eq!(buffered_packets[0].1.len(), num_expected_unprocessed);
-                }
-            }
+            // When the poh recorder has a bank, should process all buffered packets. Conflicting
+            // transactions are deferred to later batches instead of being rebuffered
+            poh_recorder.lock().unwrap().set_bank(&bank);
+            let banking_stage_stats = BankingStageStats::default();
+            BankingStage::consume_buffered_packets(
+                &Pubkey::default(),
+                &poh_recorder,
+                &mut buffered_packets,
+                None,
+                &gossip_vote_sender,
+                None::<Box<dyn Fn()>>,
+                Some(&banking_stage_stats),
+            );
+            assert!(buffered_packets.is_empty());
+            assert_eq!(
+                banking_stage_stats
+                    .consumed_buffered_packets_count
+                    .load(Ordering::Relaxed),
+                num_conflicting_transactions
+            );
+            assert_eq!(
+                banking_stage_stats
+                    .deferred_transactions_count
+                    .load(Ordering::Relaxed),
+                num_conflicting_transactions - 1
+            );
         }
         Blockstore::destroy(&ledger_path).unwrap();
     }
@@ -2322,7 +2856,7 @@ mod tests {
             let mut buffered_packets: UnprocessedPackets = packets_vec
                 .clone()
                 .into_iter()
-                .map(|single_packets| (single_packets, vec![0]))
+                .map(|single_packets| (single_packets, vec![0], None))
                 .collect();
 
             let (continue_sender, continue_receiver) = unbounded();
@@ -2360,7 +2894,7 @@ mod tests {
                         buffered_packets.len(),
                         packets_vec[interrupted_iteration + 1..].iter().count()
                     );
-                    for ((remaining_unprocessed_packet, _), original_packet) in buffered_packets
+                    for ((remaining_unprocessed_packet, _, _), original_packet) in buffered_packets
                         .iter()
                         .zip(&packets_vec[interrupted_iteration + 1..])
                     {
@@ -2393,7 +2927,7 @@ mod tests {
         // Create `Packets` with 1 unprocessed element
         let single_element_packets = Packets::new(vec![Packet::default()]);
         let mut unprocessed_packets: UnprocessedPackets =
-            vec![(single_element_packets.clone(), vec![0])]
+            vec![(single_element_packets.clone(), vec![0], None)]
                 .into_iter()
                 .collect();
         // Set the limit to 2
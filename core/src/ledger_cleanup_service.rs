@@ -1,17 +1,17 @@
 //! The `ledger_cleanup_service` drops older ledger data to limit disk space usage
 
 use rand::{thread_rng, Rng};
-use solana_ledger::blockstore::{Blockstore, PurgeType};
+use solana_ledger::blockstore::{Blockstore, ColumnGroup, PurgeType};
 use solana_ledger::blockstore_db::Result as BlockstoreResult;
 use solana_measure::measure::Measure;
-use solana_sdk::clock::{Slot, DEFAULT_TICKS_PER_SLOT, TICKS_PER_DAY};
+use solana_sdk::clock::{Slot, UnixTimestamp, DEFAULT_TICKS_PER_SLOT, TICKS_PER_DAY};
 use std::string::ToString;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::mpsc::{Receiver, RecvTimeoutError};
 use std::sync::Arc;
 use std::thread;
 use std::thread::{sleep, Builder, JoinHandle};
-use std::time::Duration;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 // - To try and keep the RocksDB size under 400GB:
 //   Seeing about 1600b/shred, using 2000b/shred for margin, so 200m shreds can be stored in 400gb.
@@ -34,6 +34,80 @@ pub const DEFAULT_PURGE_SLOT_INTERVAL: u64 = 512;
 // Once a day should be ample
 const DEFAULT_COMPACTION_SLOT_INTERVAL: u64 = TICKS_PER_DAY / DEFAULT_TICKS_PER_SLOT;
 
+/// How much of a column group the ledger keeps. Slots beyond any of the set limits are purged
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RetentionLimits {
+    /// Keep the slots holding the newest `max_shreds` shreds. Only the slots whose shreds are
+    /// still in the ledger are counted
+    pub max_shreds: Option<u64>,
+    /// Keep the column group under `max_bytes` bytes, on disk and in memory
+    pub max_bytes: Option<u64>,
+    /// Keep the slots whose block time is within `max_age` of now
+    pub max_age: Option<Duration>,
+}
+
+impl RetentionLimits {
+    pub fn is_unlimited(&self) -> bool {
+        self.max_shreds.is_none() && self.max_bytes.is_none() && self.max_age.is_none()
+    }
+}
+
+/// The retention limits of every column group of the ledger
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LedgerRetention {
+    pub shreds: RetentionLimits,
+    pub block_metadata: RetentionLimits,
+    pub transaction_status: RetentionLimits,
+}
+
+impl LedgerRetention {
+    /// Keep the slots holding the newest `max_ledger_shreds` shreds, in every column group
+    pub fn with_max_ledger_shreds(max_ledger_shreds: u64) -> Self {
+        let limits = RetentionLimits {
+            max_shreds: Some(max_ledger_shreds),
+            ..RetentionLimits::default()
+        };
+        Self {
+            shreds: limits,
+            block_metadata: limits,
+            transaction_status: limits,
+        }
+    }
+
+    pub fn limits(&self, column_group: ColumnGroup) -> &RetentionLimits {
+        match column_group {
+            ColumnGroup::Shreds => &self.shreds,
+            ColumnGroup::BlockMetadata => &self.block_metadata,
+            ColumnGroup::TransactionStatus => &self.transaction_status,
+        }
+    }
+
+    pub fn limits_mut(&mut self, column_group: ColumnGroup) -> &mut RetentionLimits {
+        match column_group {
+            ColumnGroup::Shreds => &mut self.shreds,
+            ColumnGroup::BlockMetadata => &mut self.block_metadata,
+            ColumnGroup::TransactionStatus => &mut self.transaction_status,
+        }
+    }
+
+    pub fn is_unlimited(&self) -> bool {
+        ColumnGroup::ALL
+            .iter()
+            .all(|column_group| self.limits(*column_group).is_unlimited())
+    }
+}
+
+/// The slots [first_slot, last_slot] to purge from a column group
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct ColumnGroupPurge {
+    column_group: ColumnGroup,
+    first_slot: Slot,
+    last_slot: Slot,
+    // Purges to bring a column group under its byte limit are compacted right away, so that
+    // the next size measurement reflects them
+    compact: bool,
+}
+
 pub struct LedgerCleanupService {
     t_cleanup: JoinHandle<()>,
     t_compact: JoinHandle<()>,
@@ -43,15 +117,12 @@ impl LedgerCleanupService {
     pub fn new(
         new_root_receiver: Receiver<Slot>,
         blockstore: Arc<Blockstore>,
-        max_ledger_shreds: u64,
+        retention: LedgerRetention,
         exit: &Arc<AtomicBool>,
         compaction_interval: Option<u64>,
         max_compaction_jitter: Option<u64>,
     ) -> Self {
-        info!(
-            "LedgerCleanupService active. Max Ledger Slots {}",
-            max_ledger_shreds
-        );
+        info!("LedgerCleanupService active. Retention {:?}", retention);
         let exit = exit.clone();
         let mut last_purge_slot = 0;
         let mut last_compaction_slot = 0;
@@ -72,7 +143,7 @@ impl LedgerCleanupService {
                 if let Err(e) = Self::cleanup_ledger(
                     &new_root_receiver,
                     &blockstore,
-                    max_ledger_shreds,
+                    &retention,
                     &mut last_purge_slot,
                     DEFAULT_PURGE_SLOT_INTERVAL,
                     &last_compact_slot,
@@ -109,50 +180,181 @@ impl LedgerCleanupService {
         }
     }
 
-    fn find_slots_to_clean(
-        blockstore: &Arc<Blockstore>,
-        root: Slot,
-        max_ledger_shreds: u64,
-    ) -> (bool, Slot, Slot, u64) {
+    /// The number of shreds received in each slot up to the first slot past `root`
+    fn slot_shred_counts(blockstore: &Arc<Blockstore>, root: Slot) -> Vec<(Slot, u64)> {
         let mut total_slots = Vec::new();
         let mut iterate_time = Measure::start("iterate_time");
-        let mut total_shreds = 0;
-        let mut first_slot = 0;
         for (i, (slot, meta)) in blockstore.slot_meta_iterator(0).unwrap().enumerate() {
             if i == 0 {
-                first_slot = slot;
                 debug!("purge: searching from slot: {}", slot);
             }
             // Not exact since non-full slots will have holes
-            total_shreds += meta.received;
             total_slots.push((slot, meta.received));
             if slot > root {
                 break;
             }
         }
         iterate_time.stop();
-        info!(
-            "first_slot={} total_slots={} total_shreds={} max_ledger_shreds={}, {}",
-            first_slot,
-            total_slots.len(),
-            total_shreds,
-            max_ledger_shreds,
-            iterate_time
-        );
-        if (total_shreds as u64) < max_ledger_shreds {
-            return (false, 0, 0, total_shreds);
+        info!("total_slots={}, {}", total_slots.len(), iterate_time);
+        total_slots
+    }
+
+    /// The last slot to purge so that the newest `max_ledger_shreds` shreds are kept
+    fn find_last_slot_by_shreds(
+        total_slots: &[(Slot, u64)],
+        max_ledger_shreds: u64,
+    ) -> Option<Slot> {
+        let total_shreds: u64 = total_slots.iter().map(|(_, num_shreds)| num_shreds).sum();
+        if total_shreds < max_ledger_shreds {
+            return None;
         }
         let mut num_shreds_to_clean = 0;
         let mut lowest_cleanup_slot = total_slots[0].0;
         for (slot, num_shreds) in total_slots.iter().rev() {
-            num_shreds_to_clean += *num_shreds as u64;
+            num_shreds_to_clean += *num_shreds;
             if num_shreds_to_clean > max_ledger_shreds {
                 lowest_cleanup_slot = *slot;
                 break;
             }
         }
+        Some(lowest_cleanup_slot)
+    }
+
+    /// The last slot to purge so that `column_group` fits in `max_bytes`, assuming its bytes are
+    /// spread evenly over the slots from its lowest slot to `root`
+    fn find_last_slot_by_bytes(
+        blockstore: &Blockstore,
+        column_group: ColumnGroup,
+        first_slot: Slot,
+        root: Slot,
+        max_bytes: u64,
+    ) -> Option<Slot> {
+        let storage_size = match blockstore.column_group_storage_size(column_group) {
+            Ok(storage_size) => storage_size,
+            Err(err) => {
+                warn!(
+                    "Unable to measure the size of {:?}: {:?}",
+                    column_group, err
+                );
+                return None;
+            }
+        };
+        if storage_size <= max_bytes || first_slot > root {
+            return None;
+        }
+        let num_slots = root - first_slot + 1;
+        let bytes_per_slot = (storage_size / num_slots).max(1);
+        let slots_to_keep = max_bytes / bytes_per_slot;
+        info!(
+            "{:?}: {} bytes over {} slots exceeds {} bytes, keeping {} slots",
+            column_group, storage_size, num_slots, max_bytes, slots_to_keep
+        );
+        Some(root.saturating_sub(slots_to_keep))
+    }
 
-        (true, first_slot, lowest_cleanup_slot, total_shreds)
+    /// The last slot to purge so that only slots with a block time within `max_age` of `now`
+    /// are kept
+    fn find_last_slot_by_age(
+        blockstore: &Blockstore,
+        now: UnixTimestamp,
+        max_age: Duration,
+    ) -> Option<Slot> {
+        let oldest_timestamp = now.saturating_sub(max_age.as_secs() as UnixTimestamp);
+        blockstore
+            .highest_slot_with_block_time_before(oldest_timestamp)
+            .unwrap_or_else(|err| {
+                warn!("Unable to read block times: {:?}", err);
+                None
+            })
+    }
+
+    /// The slots to purge from each column group to keep it within its retention limits.
+    /// Returns them along with the number of shreds in the ledger, if they were counted
+    fn find_slots_to_clean(
+        blockstore: &Arc<Blockstore>,
+        root: Slot,
+        retention: &LedgerRetention,
+    ) -> (Vec<ColumnGroupPurge>, u64) {
+        let total_slots = if ColumnGroup::ALL
+            .iter()
+            .any(|column_group| retention.limits(*column_group).max_shreds.is_some())
+        {
+            Self::slot_shred_counts(blockstore, root)
+        } else {
+            vec![]
+        };
+        let total_shreds = total_slots.iter().map(|(_, num_shreds)| num_shreds).sum();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as UnixTimestamp;
+
+        // Block times date the slots of every column group retained by age, so they are kept
+        // until those column groups are purged past them
+        let column_groups = [
+            ColumnGroup::Shreds,
+            ColumnGroup::TransactionStatus,
+            ColumnGroup::BlockMetadata,
+        ];
+        let mut lowest_slots_dated_by_age = vec![];
+        let mut purges = vec![];
+        for column_group in column_groups.iter().copied() {
+            let limits = retention.limits(column_group);
+            if limits.is_unlimited() {
+                continue;
+            }
+            let first_slot = match blockstore.lowest_slot_of_column_group(column_group) {
+                Ok(Some(first_slot)) => first_slot,
+                Ok(None) => continue,
+                Err(err) => {
+                    warn!(
+                        "Unable to find the lowest slot of {:?}: {:?}",
+                        column_group, err
+                    );
+                    continue;
+                }
+            };
+            let by_shreds = limits.max_shreds.and_then(|max_ledger_shreds| {
+                Self::find_last_slot_by_shreds(&total_slots, max_ledger_shreds)
+            });
+            let by_bytes = limits.max_bytes.and_then(|max_bytes| {
+                Self::find_last_slot_by_bytes(blockstore, column_group, first_slot, root, max_bytes)
+            });
+            let by_age = limits
+                .max_age
+                .and_then(|max_age| Self::find_last_slot_by_age(blockstore, now, max_age));
+            info!(
+                "{:?}: first_slot={} last slot to purge by shreds={:?} by bytes={:?} by age={:?}",
+                column_group, first_slot, by_shreds, by_bytes, by_age
+            );
+            let mut last_slot = match by_shreds.max(by_bytes).max(by_age) {
+                Some(last_slot) => last_slot,
+                None => {
+                    if limits.max_age.is_some() {
+                        lowest_slots_dated_by_age.push(first_slot);
+                    }
+                    continue;
+                }
+            };
+            if column_group == ColumnGroup::BlockMetadata {
+                if let Some(lowest_slot) = lowest_slots_dated_by_age.iter().min() {
+                    last_slot = last_slot.min(lowest_slot.saturating_sub(1));
+                }
+            }
+            if limits.max_age.is_some() {
+                lowest_slots_dated_by_age.push(first_slot.max(last_slot + 1));
+            }
+            if last_slot < first_slot {
+                continue;
+            }
+            purges.push(ColumnGroupPurge {
+                column_group,
+                first_slot,
+                last_slot,
+                compact: by_bytes.is_some() && by_bytes >= by_shreds.max(by_age),
+            });
+        }
+        (purges, total_shreds)
     }
 
     fn receive_new_roots(new_root_receiver: &Receiver<Slot>) -> Result<Slot, RecvTimeoutError> {
@@ -167,7 +369,7 @@ impl LedgerCleanupService {
     pub fn cleanup_ledger(
         new_root_receiver: &Receiver<Slot>,
         blockstore: &Arc<Blockstore>,
-        max_ledger_shreds: u64,
+        retention: &LedgerRetention,
         last_purge_slot: &mut u64,
         purge_interval: u64,
         last_compact_slot: &Arc<AtomicU64>,
@@ -185,10 +387,9 @@ impl LedgerCleanupService {
 
         *last_purge_slot = root;
 
-        let (slots_to_clean, purge_first_slot, lowest_cleanup_slot, total_shreds) =
-            Self::find_slots_to_clean(&blockstore, root, max_ledger_shreds);
+        let (purges, total_shreds) = Self::find_slots_to_clean(&blockstore, root, retention);
 
-        if slots_to_clean {
+        if !purges.is_empty() {
             let purge_complete = Arc::new(AtomicBool::new(false));
             let blockstore = blockstore.clone();
             let purge_complete1 = purge_complete.clone();
@@ -196,25 +397,45 @@ impl LedgerCleanupService {
             let _t_purge = Builder::new()
                 .name("solana-ledger-purge".to_string())
                 .spawn(move || {
-                    let mut slot_update_time = Measure::start("slot_update");
-                    *blockstore.lowest_cleanup_slot.write().unwrap() = lowest_cleanup_slot;
-                    slot_update_time.stop();
-
-                    info!(
-                        "purging data from slots {} to {}",
-                        purge_first_slot, lowest_cleanup_slot
-                    );
-
-                    let mut purge_time = Measure::start("purge_slots");
-                    blockstore.purge_slots(
-                        purge_first_slot,
-                        lowest_cleanup_slot,
-                        PurgeType::PrimaryIndex,
-                    );
-                    purge_time.stop();
-                    info!("{}", purge_time);
+                    for purge in &purges {
+                        if purge.column_group == ColumnGroup::Shreds {
+                            let mut slot_update_time = Measure::start("slot_update");
+                            *blockstore.lowest_cleanup_slot.write().unwrap() = purge.last_slot;
+                            slot_update_time.stop();
+                        }
+
+                        info!(
+                            "purging {:?} from slots {} to {}",
+                            purge.column_group, purge.first_slot, purge.last_slot
+                        );
+
+                        let mut purge_time = Measure::start("purge_slots");
+                        blockstore.purge_column_groups(
+                            &[purge.column_group],
+                            purge.first_slot,
+                            purge.last_slot,
+                            PurgeType::PrimaryIndex,
+                        );
+                        purge_time.stop();
+                        info!("{}", purge_time);
+
+                        if purge.compact {
+                            if let Err(err) = blockstore.compact_column_groups(
+                                &[purge.column_group],
+                                purge.first_slot,
+                                purge.last_slot,
+                            ) {
+                                error!(
+                                    "Error: {:?}; Couldn't compact {:?} from {:?} to {:?}",
+                                    err, purge.column_group, purge.first_slot, purge.last_slot
+                                );
+                            }
+                        }
+                    }
 
-                    last_compact_slot1.store(lowest_cleanup_slot, Ordering::Relaxed);
+                    let highest_purged_slot =
+                        purges.iter().map(|purge| purge.last_slot).max().unwrap();
+                    last_compact_slot1.fetch_max(highest_purged_slot, Ordering::Relaxed);
 
                     purge_complete1.store(true, Ordering::Relaxed);
                 })
@@ -313,7 +534,7 @@ mod tests {
         LedgerCleanupService::cleanup_ledger(
             &receiver,
             &blockstore,
-            5,
+            &LedgerRetention::with_max_ledger_shreds(5),
             &mut last_purge_slot,
             10,
             &highest_compaction_slot,
@@ -344,6 +565,113 @@ mod tests {
         Blockstore::destroy(&blockstore_path).expect("Expected successful database destruction");
     }
 
+    #[test]
+    fn test_cleanup_by_column_group() {
+        solana_logger::setup();
+        let blockstore_path = get_tmp_ledger_path!();
+        let blockstore = Blockstore::open(&blockstore_path).unwrap();
+        let (shreds, _) = make_many_slot_entries(0, 50, 5);
+        blockstore.insert_shreds(shreds, None, false).unwrap();
+        let roots: Vec<Slot> = (0..50).collect();
+        blockstore.set_roots(&roots).unwrap();
+        // A slot an hour
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as UnixTimestamp;
+        for slot in 0..50 {
+            blockstore
+                .cache_block_time(slot, now - (50 - slot as UnixTimestamp) * 3600)
+                .unwrap();
+        }
+        let blockstore = Arc::new(blockstore);
+
+        // Block metadata is retained for 20 hours, but transaction statuses for 40 hours
+        let hours = |hours: f64| Some(Duration::from_secs_f64(hours * 3600.0));
+        let retention = LedgerRetention {
+            shreds: RetentionLimits {
+                max_shreds: Some(5),
+                ..RetentionLimits::default()
+            },
+            block_metadata: RetentionLimits {
+                max_age: hours(20.5),
+                ..RetentionLimits::default()
+            },
+            transaction_status: RetentionLimits::default(),
+        };
+        let (purges, _) = LedgerCleanupService::find_slots_to_clean(&blockstore, 49, &retention);
+        assert_eq!(
+            purges,
+            vec![
+                ColumnGroupPurge {
+                    column_group: ColumnGroup::Shreds,
+                    first_slot: 0,
+                    last_slot: 44,
+                    compact: false,
+                },
+                ColumnGroupPurge {
+                    column_group: ColumnGroup::BlockMetadata,
+                    first_slot: 0,
+                    last_slot: 29,
+                    compact: false,
+                },
+            ]
+        );
+
+        // Block times are kept as long as they date the transaction statuses
+        let retention = LedgerRetention {
+            transaction_status: RetentionLimits {
+                max_age: hours(40.5),
+                ..RetentionLimits::default()
+            },
+            ..retention
+        };
+        let (purges, _) = LedgerCleanupService::find_slots_to_clean(&blockstore, 49, &retention);
+        assert_eq!(
+            purges[1..],
+            [
+                ColumnGroupPurge {
+                    column_group: ColumnGroup::TransactionStatus,
+                    first_slot: 0,
+                    last_slot: 9,
+                    compact: false,
+                },
+                ColumnGroupPurge {
+                    column_group: ColumnGroup::BlockMetadata,
+                    first_slot: 0,
+                    last_slot: 9,
+                    compact: false,
+                },
+            ]
+        );
+
+        let (sender, receiver) = channel();
+        let mut last_purge_slot = 0;
+        let highest_compaction_slot = Arc::new(AtomicU64::new(0));
+        sender.send(49).unwrap();
+        LedgerCleanupService::cleanup_ledger(
+            &receiver,
+            &blockstore,
+            &retention,
+            &mut last_purge_slot,
+            10,
+            &highest_compaction_slot,
+        )
+        .unwrap();
+        assert_eq!(highest_compaction_slot.load(Ordering::Relaxed), 44);
+        assert!(blockstore.meta(20).unwrap().is_none());
+        assert!(blockstore.is_root(20));
+        assert_eq!(
+            blockstore.get_block_time(20).unwrap(),
+            Some(now - 30 * 3600)
+        );
+        assert!(!blockstore.is_root(9));
+        assert!(blockstore.get_block_time(9).is_err());
+
+        drop(blockstore);
+        Blockstore::destroy(&blockstore_path).expect("Expected successful database destruction");
+    }
+
     #[test]
     fn test_cleanup_speed() {
         solana_logger::setup();
@@ -383,7 +711,7 @@ mod tests {
             LedgerCleanupService::cleanup_ledger(
                 &receiver,
                 &blockstore,
-                initial_slots,
+                &LedgerRetention::with_max_ledger_shreds(initial_slots),
                 &mut last_purge_slot,
                 10,
                 &last_compaction_slot,
@@ -56,13 +56,13 @@ use solana_sdk::{
     epoch_info::EpochInfo,
     epoch_schedule::EpochSchedule,
     hash::Hash,
-    pubkey::Pubkey,
+    pubkey::{Pubkey, PUBKEY_BYTES},
     sanitize::Sanitize,
     signature::Signature,
     stake_history::StakeHistory,
     system_instruction,
     sysvar::stake_history,
-    transaction::{self, Transaction},
+    transaction::{self, Transaction, VersionedTransaction},
 };
 use solana_stake_program::stake_state::StakeState;
 use solana_transaction_status::{
@@ -118,6 +118,7 @@ pub struct JsonRpcConfig {
     pub enable_bigtable_ledger_storage: bool,
     pub enable_bigtable_ledger_upload: bool,
     pub max_multiple_accounts: Option<usize>,
+    pub max_batch_requests: Option<usize>,
     pub account_indexes: HashSet<AccountIndex>,
     pub rpc_threads: usize,
     pub rpc_bigtable_timeout: Option<Duration>,
@@ -1403,12 +1404,19 @@ impl JsonRpcRequestProcessor {
         filters: Vec<RpcFilterType>,
     ) -> Vec<(Pubkey, Account)> {
         let filter_closure = |account: &Account| {
-            filters.iter().all(|filter_type| match filter_type {
-                RpcFilterType::DataSize(size) => account.data.len() as u64 == *size,
-                RpcFilterType::Memcmp(compare) => compare.bytes_match(&account.data),
-            })
+            filters
+                .iter()
+                .all(|filter_type| filter_type.allows(account))
         };
-        if self
+        if let Some(index_key) =
+            get_program_data_index_key(program_id, &filters, &self.config.account_indexes)
+        {
+            bank.get_filtered_indexed_accounts(&index_key, |account| {
+                // Like the program-id account index below, the program data index may still
+                // reference accounts that were wiped since they were indexed
+                account.owner == *program_id && filter_closure(account)
+            })
+        } else if self
             .config
             .account_indexes
             .contains(&AccountIndex::ProgramId)
@@ -1456,10 +1464,9 @@ impl JsonRpcRequestProcessor {
         {
             bank.get_filtered_indexed_accounts(&IndexKey::SplTokenOwner(*owner_key), |account| {
                 account.owner == spl_token_id_v2_0()
-                    && filters.iter().all(|filter_type| match filter_type {
-                        RpcFilterType::DataSize(size) => account.data.len() as u64 == *size,
-                        RpcFilterType::Memcmp(compare) => compare.bytes_match(&account.data),
-                    })
+                    && filters
+                        .iter()
+                        .all(|filter_type| filter_type.allows(account))
             })
         } else {
             self.get_filtered_program_accounts(bank, &spl_token_id_v2_0(), filters)
@@ -1495,10 +1502,9 @@ impl JsonRpcRequestProcessor {
         {
             bank.get_filtered_indexed_accounts(&IndexKey::SplTokenMint(*mint_key), |account| {
                 account.owner == spl_token_id_v2_0()
-                    && filters.iter().all(|filter_type| match filter_type {
-                        RpcFilterType::DataSize(size) => account.data.len() as u64 == *size,
-                        RpcFilterType::Memcmp(compare) => compare.bytes_match(&account.data),
-                    })
+                    && filters
+                        .iter()
+                        .all(|filter_type| filter_type.allows(account))
             })
         } else {
             self.get_filtered_program_accounts(bank, &spl_token_id_v2_0(), filters)
@@ -1506,7 +1512,7 @@ impl JsonRpcRequestProcessor {
     }
 }
 
-fn verify_transaction(transaction: &Transaction) -> Result<()> {
+fn verify_transaction(transaction: &VersionedTransaction) -> Result<()> {
     if transaction.verify().is_err() {
         return Err(RpcCustomError::TransactionSignatureVerificationFailure.into());
     }
@@ -1518,6 +1524,13 @@ fn verify_transaction(transaction: &Transaction) -> Result<()> {
     Ok(())
 }
 
+// Resolve a transaction into the form that `bank` executes. Its signatures cover the versioned
+// message, so they are verified on `transaction` rather than on the result
+fn resolve_transaction(bank: &Bank, transaction: &VersionedTransaction) -> Result<Transaction> {
+    bank.resolve_versioned_transaction(transaction)
+        .map_err(|err| Error::invalid_params(format!("invalid transaction: {}", err)))
+}
+
 fn verify_filter(input: &RpcFilterType) -> Result<()> {
     input
         .verify()
@@ -1602,6 +1615,29 @@ fn get_encoded_account(
     Ok(response)
 }
 
+/// Returns the key of a configured program data index that can serve a memcmp filter
+/// comparing the entire indexed field of `program_id` accounts
+fn get_program_data_index_key(
+    program_id: &Pubkey,
+    filters: &[RpcFilterType],
+    account_indexes: &HashSet<AccountIndex>,
+) -> Option<IndexKey> {
+    account_indexes
+        .iter()
+        .find_map(|account_index| match account_index {
+            AccountIndex::ProgramData(index) if index.program_id == *program_id => {
+                filters.iter().find_map(|filter| match filter {
+                    RpcFilterType::Memcmp(compare) if compare.offset == index.offset => compare
+                        .bytes()
+                        .and_then(|bytes| index.key_from_field(&bytes))
+                        .map(|key| IndexKey::ProgramData(*index, key)),
+                    _ => None,
+                })
+            }
+            _ => None,
+        })
+}
+
 fn get_spl_token_owner_filter(program_id: &Pubkey, filters: &[RpcFilterType]) -> Option<Pubkey> {
     if program_id != &spl_token_id_v2_0() {
         return None;
@@ -1611,13 +1647,11 @@ fn get_spl_token_owner_filter(program_id: &Pubkey, filters: &[RpcFilterType]) ->
     for filter in filters {
         match filter {
             RpcFilterType::DataSize(size) => data_size_filter = Some(*size),
-            RpcFilterType::Memcmp(Memcmp {
-                offset: SPL_TOKEN_ACCOUNT_OWNER_OFFSET,
-                bytes: MemcmpEncodedBytes::Binary(bytes),
-                ..
-            }) => {
-                if let Ok(key) = Pubkey::from_str(bytes) {
-                    owner_key = Some(key)
+            RpcFilterType::Memcmp(compare) if compare.offset == SPL_TOKEN_ACCOUNT_OWNER_OFFSET => {
+                if let Some(bytes) = compare.bytes() {
+                    if bytes.len() == PUBKEY_BYTES {
+                        owner_key = Some(Pubkey::new(&bytes))
+                    }
                 }
             }
             _ => {}
@@ -1639,13 +1673,11 @@ fn get_spl_token_mint_filter(program_id: &Pubkey, filters: &[RpcFilterType]) ->
     for filter in filters {
         match filter {
             RpcFilterType::DataSize(size) => data_size_filter = Some(*size),
-            RpcFilterType::Memcmp(Memcmp {
-                offset: SPL_TOKEN_ACCOUNT_MINT_OFFSET,
-                bytes: MemcmpEncodedBytes::Binary(bytes),
-                ..
-            }) => {
-                if let Ok(key) = Pubkey::from_str(bytes) {
-                    mint = Some(key)
+            RpcFilterType::Memcmp(compare) if compare.offset == SPL_TOKEN_ACCOUNT_MINT_OFFSET => {
+                if let Some(bytes) = compare.bytes() {
+                    if bytes.len() == PUBKEY_BYTES {
+                        mint = Some(Pubkey::new(&bytes))
+                    }
                 }
             }
             _ => {}
@@ -2629,12 +2661,14 @@ impl RpcSol for RpcSolImpl {
         debug!("send_transaction rpc request received");
         let config = config.unwrap_or_default();
         let encoding = config.encoding.unwrap_or(UiTransactionEncoding::Base58);
-        let (wire_transaction, transaction) = deserialize_transaction(data, encoding)?;
+        let (wire_transaction, versioned_transaction) =
+            deserialize_transaction::<VersionedTransaction>(data, encoding)?;
 
         let preflight_commitment = config
             .preflight_commitment
             .map(|commitment| CommitmentConfig { commitment });
         let preflight_bank = &*meta.bank(preflight_commitment);
+        let transaction = resolve_transaction(preflight_bank, &versioned_transaction)?;
 
         let mut last_valid_slot = preflight_bank
             .get_blockhash_last_valid_slot(&transaction.message.recent_blockhash)
@@ -2654,7 +2688,7 @@ impl RpcSol for RpcSolImpl {
         }
 
         if !config.skip_preflight {
-            if let Err(e) = verify_transaction(&transaction) {
+            if let Err(e) = verify_transaction(&versioned_transaction) {
                 return Err(e);
             }
 
@@ -2668,12 +2702,15 @@ impl RpcSol for RpcSolImpl {
                 }
             }
 
-            if let (Err(err), logs) = preflight_bank.simulate_transaction(transaction.clone()) {
+            if let (Err(err), logs, return_data) =
+                preflight_bank.simulate_transaction(transaction.clone())
+            {
                 return Err(RpcCustomError::SendTransactionPreflightFailure {
                     message: format!("Transaction simulation failed: {}", err),
                     result: RpcSimulateTransactionResult {
                         err: Some(err),
                         logs: Some(logs),
+                        return_data: return_data.map(|return_data| return_data.into()),
                     },
                 }
                 .into());
@@ -2698,22 +2735,26 @@ impl RpcSol for RpcSolImpl {
         debug!("simulate_transaction rpc request received");
         let config = config.unwrap_or_default();
         let encoding = config.encoding.unwrap_or(UiTransactionEncoding::Base58);
-        let (_, transaction) = deserialize_transaction(data, encoding)?;
+        let (_, versioned_transaction) =
+            deserialize_transaction::<VersionedTransaction>(data, encoding)?;
 
         if config.sig_verify {
-            if let Err(e) = verify_transaction(&transaction) {
+            if let Err(e) = verify_transaction(&versioned_transaction) {
                 return Err(e);
             }
         }
 
         let bank = &*meta.bank(config.commitment);
-        let (result, logs) = bank.simulate_transaction(transaction);
+        let transaction = resolve_transaction(bank, &versioned_transaction)?;
+
+        let (result, logs, return_data) = bank.simulate_transaction(transaction);
 
         Ok(new_response(
             &bank,
             RpcSimulateTransactionResult {
                 err: result.err(),
                 logs: Some(logs),
+                return_data: return_data.map(|return_data| return_data.into()),
             },
         ))
     }
@@ -2979,10 +3020,13 @@ impl RpcSol for RpcSolImpl {
 
 const WORST_CASE_BASE58_TX: usize = 1683; // Golden, bump if PACKET_DATA_SIZE changes
 const WORST_CASE_BASE64_TX: usize = 1644; // Golden, bump if PACKET_DATA_SIZE changes
-fn deserialize_transaction(
+fn deserialize_transaction<T>(
     encoded_transaction: String,
     encoding: UiTransactionEncoding,
-) -> Result<(Vec<u8>, Transaction)> {
+) -> Result<(Vec<u8>, T)>
+where
+    T: serde::de::DeserializeOwned + Sanitize,
+{
     let wire_transaction = match encoding {
         UiTransactionEncoding::Base58 => {
             inc_new_counter_info!("rpc-base58_encoded_tx", 1);
@@ -3036,7 +3080,7 @@ fn deserialize_transaction(
             info!("transaction deserialize error: {:?}", err);
             Error::invalid_params(&err.to_string())
         })
-        .and_then(|transaction: Transaction| {
+        .and_then(|transaction: T| {
             if let Err(err) = transaction.sanitize() {
                 Err(Error::invalid_params(format!(
                     "invalid transaction: {}",
@@ -3071,14 +3115,15 @@ pub mod tests {
     use bincode::deserialize;
     use jsonrpc_core::{futures, ErrorCode, MetaIoHandler, Output, Response, Value};
     use jsonrpc_core_client::transports::local;
-    use solana_client::rpc_filter::{Memcmp, MemcmpEncodedBytes};
+    use solana_client::rpc_filter::{LamportsRange, Memcmp, MemcmpEncodedBytes, MemcmpEncoding};
     use solana_ledger::{
         blockstore_meta::PerfSample,
         blockstore_processor::fill_blockstore_slot_with_ticks,
         genesis_utils::{create_genesis_config, GenesisConfigInfo},
     };
     use solana_runtime::{
-        accounts_background_service::AbsRequestSender, commitment::BlockCommitment,
+        accounts_background_service::AbsRequestSender, accounts_index::ProgramDataIndex,
+        commitment::BlockCommitment,
     };
     use solana_sdk::{
         clock::MAX_RECENT_BLOCKHASHES,
@@ -4798,6 +4843,11 @@ pub mod tests {
             encoding: None,
         });
         assert!(verify_filter(&filter).is_err());
+        let filter = RpcFilterType::Lamports(LamportsRange {
+            min: Some(2),
+            max: Some(1),
+        });
+        assert!(verify_filter(&filter).is_err());
     }
 
     #[test]
@@ -6156,6 +6206,61 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn test_get_program_data_index_key() {
+        let program_data_index = ProgramDataIndex {
+            program_id: Pubkey::new_unique(),
+            offset: 8,
+            length: 4,
+        };
+        let mut account_indexes = HashSet::new();
+        account_indexes.insert(AccountIndex::ProgramId);
+        account_indexes.insert(AccountIndex::ProgramData(program_data_index));
+        let memcmp = |offset, bytes: Vec<u8>| {
+            RpcFilterType::Memcmp(Memcmp {
+                offset,
+                bytes: MemcmpEncodedBytes::Bytes(bytes),
+                encoding: None,
+            })
+        };
+
+        match get_program_data_index_key(
+            &program_data_index.program_id,
+            &[RpcFilterType::DataSize(16), memcmp(8, vec![1, 2, 3, 4])],
+            &account_indexes,
+        ) {
+            Some(IndexKey::ProgramData(index, key)) => {
+                assert_eq!(index, program_data_index);
+                assert_eq!(Some(key), program_data_index.key_from_field(&[1, 2, 3, 4]));
+            }
+            index_key => panic!("unexpected index key: {:?}", index_key),
+        }
+
+        // Partial match of the indexed field
+        assert!(get_program_data_index_key(
+            &program_data_index.program_id,
+            &[memcmp(8, vec![1, 2, 3])],
+            &account_indexes,
+        )
+        .is_none());
+
+        // Wrong offset
+        assert!(get_program_data_index_key(
+            &program_data_index.program_id,
+            &[memcmp(4, vec![1, 2, 3, 4])],
+            &account_indexes,
+        )
+        .is_none());
+
+        // Wrong program id
+        assert!(get_program_data_index_key(
+            &Pubkey::new_unique(),
+            &[memcmp(8, vec![1, 2, 3, 4])],
+            &account_indexes,
+        )
+        .is_none());
+    }
+
     #[test]
     fn test_get_spl_token_owner_filter() {
         let owner = Pubkey::new_unique();
@@ -6175,6 +6280,31 @@ pub mod tests {
             owner
         );
 
+        // Owner address encoded as base-64 or raw bytes
+        for memcmp in &[
+            Memcmp {
+                offset: 32,
+                bytes: MemcmpEncodedBytes::Binary(base64::encode(owner)),
+                encoding: Some(MemcmpEncoding::Base64),
+            },
+            Memcmp {
+                offset: 32,
+                bytes: MemcmpEncodedBytes::Bytes(owner.to_bytes().to_vec()),
+                encoding: None,
+            },
+        ] {
+            assert_eq!(
+                get_spl_token_owner_filter(
+                    &Pubkey::from_str("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA").unwrap(),
+                    &[
+                        RpcFilterType::Memcmp(memcmp.clone()),
+                        RpcFilterType::DataSize(165)
+                    ],
+                ),
+                Some(owner)
+            );
+        }
+
         // Filtering on mint instead of owner
         assert!(get_spl_token_owner_filter(
             &Pubkey::from_str("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA").unwrap(),
@@ -6347,7 +6477,8 @@ pub mod tests {
             tx58_len, WORST_CASE_BASE58_TX, PACKET_DATA_SIZE,
         ));
         assert_eq!(
-            deserialize_transaction(tx58, UiTransactionEncoding::Base58).unwrap_err(),
+            deserialize_transaction::<Transaction>(tx58, UiTransactionEncoding::Base58)
+                .unwrap_err(),
             expect58
         );
         let tx64 = base64::encode(&tx_ser);
@@ -6357,7 +6488,8 @@ pub mod tests {
             tx64_len, WORST_CASE_BASE64_TX, PACKET_DATA_SIZE,
         ));
         assert_eq!(
-            deserialize_transaction(tx64, UiTransactionEncoding::Base64).unwrap_err(),
+            deserialize_transaction::<Transaction>(tx64, UiTransactionEncoding::Base64)
+                .unwrap_err(),
             expect64
         );
         let too_big = PACKET_DATA_SIZE + 1;
@@ -6368,12 +6500,14 @@ pub mod tests {
             too_big, PACKET_DATA_SIZE
         ));
         assert_eq!(
-            deserialize_transaction(tx58, UiTransactionEncoding::Base58).unwrap_err(),
+            deserialize_transaction::<Transaction>(tx58, UiTransactionEncoding::Base58)
+                .unwrap_err(),
             expect
         );
         let tx64 = base64::encode(&tx_ser);
         assert_eq!(
-            deserialize_transaction(tx64, UiTransactionEncoding::Base64).unwrap_err(),
+            deserialize_transaction::<Transaction>(tx64, UiTransactionEncoding::Base64)
+                .unwrap_err(),
             expect
         );
     }
@@ -6389,7 +6523,8 @@ pub mod tests {
         let expect58 =
             Error::invalid_params("invalid transaction: index out of bounds".to_string());
         assert_eq!(
-            deserialize_transaction(unsanitary_tx58, UiTransactionEncoding::Base58).unwrap_err(),
+            deserialize_transaction::<Transaction>(unsanitary_tx58, UiTransactionEncoding::Base58)
+                .unwrap_err(),
             expect58
         );
     }
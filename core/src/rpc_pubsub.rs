@@ -1,17 +1,20 @@
 //! The `pubsub` module implements a threaded subscription service on client RPC request
 
-use crate::rpc_subscriptions::{RpcSubscriptions, RpcVote};
+use crate::rpc_subscriptions::RpcSubscriptions;
 use jsonrpc_core::{Error, ErrorCode, Result};
 use jsonrpc_derive::rpc;
 use jsonrpc_pubsub::{typed::Subscriber, Session, SubscriptionId};
 use solana_account_decoder::UiAccount;
 use solana_client::{
     rpc_config::{
-        RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcSignatureSubscribeConfig,
-        RpcTransactionLogsConfig, RpcTransactionLogsFilter,
+        RpcAccountInfoConfig, RpcBlockSubscribeConfig, RpcBlockSubscribeFilter,
+        RpcProgramAccountsConfig, RpcSignatureSubscribeConfig, RpcTransactionLogsConfig,
+        RpcTransactionLogsFilter,
     },
+    rpc_filter::RpcFilterType,
     rpc_response::{
-        Response as RpcResponse, RpcKeyedAccount, RpcLogsResponse, RpcSignatureResult, SlotInfo,
+        Response as RpcResponse, RpcBlockUpdate, RpcKeyedAccount, RpcLogsResponse,
+        RpcSignatureResult, RpcVote, SlotInfo,
     },
 };
 #[cfg(test)]
@@ -127,6 +130,24 @@ pub trait RpcSolPubSub {
         id: SubscriptionId,
     ) -> Result<bool>;
 
+    // Get notification when a block reaches the requested commitment
+    #[pubsub(subscription = "blockNotification", subscribe, name = "blockSubscribe")]
+    fn block_subscribe(
+        &self,
+        meta: Self::Metadata,
+        subscriber: Subscriber<RpcResponse<RpcBlockUpdate>>,
+        filter: RpcBlockSubscribeFilter,
+        config: Option<RpcBlockSubscribeConfig>,
+    );
+
+    // Unsubscribe from block notification subscription.
+    #[pubsub(
+        subscription = "blockNotification",
+        unsubscribe,
+        name = "blockUnsubscribe"
+    )]
+    fn block_unsubscribe(&self, meta: Option<Self::Metadata>, id: SubscriptionId) -> Result<bool>;
+
     // Get notification when slot is encountered
     #[pubsub(subscription = "slotNotification", subscribe, name = "slotSubscribe")]
     fn slot_subscribe(&self, meta: Self::Metadata, subscriber: Subscriber<SlotInfo>);
@@ -207,6 +228,17 @@ fn param<T: FromStr>(param_str: &str, thing: &str) -> Result<T> {
     })
 }
 
+fn verify_filters(filters: &[RpcFilterType]) -> Result<()> {
+    for filter in filters {
+        filter.verify().map_err(|e| Error {
+            code: ErrorCode::InvalidParams,
+            message: format!("Invalid Request: Invalid filter provided: {}", e),
+            data: None,
+        })?;
+    }
+    Ok(())
+}
+
 impl RpcSolPubSub for RpcSolPubSubImpl {
     type Metadata = Arc<Session>;
 
@@ -261,7 +293,13 @@ impl RpcSolPubSub for RpcSolPubSubImpl {
             subscriber.reject(err).unwrap_or_default();
             return;
         }
-        match param::<Pubkey>(&pubkey_str, "pubkey") {
+        let filters = config
+            .as_ref()
+            .and_then(|config| config.filters.as_deref())
+            .unwrap_or_default();
+        match param::<Pubkey>(&pubkey_str, "pubkey")
+            .and_then(|pubkey| verify_filters(filters).map(|_| pubkey))
+        {
             Ok(pubkey) => {
                 let id = self.uid.fetch_add(1, atomic::Ordering::Relaxed);
                 let sub_id = SubscriptionId::Number(id as u64);
@@ -403,6 +441,67 @@ impl RpcSolPubSub for RpcSolPubSubImpl {
         }
     }
 
+    fn block_subscribe(
+        &self,
+        _meta: Self::Metadata,
+        subscriber: Subscriber<RpcResponse<RpcBlockUpdate>>,
+        filter: RpcBlockSubscribeFilter,
+        config: Option<RpcBlockSubscribeConfig>,
+    ) {
+        info!("block_subscribe");
+        if let Err(err) = self.check_subscription_count() {
+            subscriber.reject(err).unwrap_or_default();
+            return;
+        }
+        let commitment = config.as_ref().and_then(|config| config.commitment);
+        if commitment.map_or(false, |commitment| commitment.is_processed()) {
+            // Transaction statuses may not be available for blocks that are only processed
+            subscriber
+                .reject(Error {
+                    code: ErrorCode::InvalidParams,
+                    message: "Invalid Request: Only confirmed and finalized commitments supported"
+                        .into(),
+                    data: None,
+                })
+                .unwrap_or_default();
+            return;
+        }
+        let mentioned_address = match filter {
+            RpcBlockSubscribeFilter::All => None,
+            RpcBlockSubscribeFilter::MentionsAccountOrProgram(address) => {
+                match param::<Pubkey>(&address, "mentionsAccountOrProgram") {
+                    Ok(address) => Some(address),
+                    Err(e) => {
+                        subscriber.reject(e).unwrap_or_default();
+                        return;
+                    }
+                }
+            }
+        };
+
+        let id = self.uid.fetch_add(1, atomic::Ordering::Relaxed);
+        let sub_id = SubscriptionId::Number(id as u64);
+        info!(
+            "block_subscribe: mentions={:?} id={:?}",
+            mentioned_address, sub_id
+        );
+        self.subscriptions
+            .add_block_subscription(mentioned_address, config, sub_id, subscriber)
+    }
+
+    fn block_unsubscribe(&self, _meta: Option<Self::Metadata>, id: SubscriptionId) -> Result<bool> {
+        info!("block_unsubscribe: id={:?}", id);
+        if self.subscriptions.remove_block_subscription(&id) {
+            Ok(true)
+        } else {
+            Err(Error {
+                code: ErrorCode::InvalidParams,
+                message: "Invalid Request: Subscription id does not exist".into(),
+                data: None,
+            })
+        }
+    }
+
     fn slot_subscribe(&self, _meta: Self::Metadata, subscriber: Subscriber<SlotInfo>) {
         info!("slot_subscribe");
         if let Err(err) = self.check_subscription_count() {
@@ -716,6 +815,31 @@ mod tests {
         assert_eq!(expected, result);
     }
 
+    #[test]
+    #[serial]
+    fn test_program_subscribe_invalid_filter() {
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(10_000);
+        let bank = Bank::new(&genesis_config);
+        let bank_forks = Arc::new(RwLock::new(BankForks::new(bank)));
+
+        let session = create_session();
+
+        let mut io = PubSubHandler::default();
+        let rpc = RpcSolPubSubImpl::default_with_bank_forks(bank_forks);
+        io.extend_with(rpc.to_delegate());
+
+        let req = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"programSubscribe","params":["{}",{{"filters":[{{"lamports":{{"min":2,"max":1}}}}]}}]}}"#,
+            solana_stake_program::id()
+        );
+        let res = io.handle_request_sync(&req, session);
+        let expected = r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Invalid Request: Invalid filter provided: lamports range minimum exceeds its maximum"},"id":1}"#;
+        let expected: Response = serde_json::from_str(&expected).unwrap();
+
+        let result: Response = serde_json::from_str(&res.unwrap()).unwrap();
+        assert_eq!(expected, result);
+    }
+
     #[test]
     #[serial]
     fn test_account_subscribe() {
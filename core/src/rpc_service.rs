@@ -11,13 +11,20 @@ use crate::{
     send_transaction_service::{LeaderInfo, SendTransactionService},
     validator::ValidatorExit,
 };
-use jsonrpc_core::{futures::prelude::*, MetaIoHandler};
+use jsonrpc_core::{
+    futures::{
+        future::{self, Either},
+        prelude::*,
+    },
+    middleware::NoopFuture,
+    Error, ErrorCode, MetaIoHandler, Metadata, Middleware, Request, Response, Version,
+};
 use jsonrpc_http_server::{
     hyper, AccessControlAllowOrigin, CloseHandle, DomainsValidation, RequestMiddleware,
     RequestMiddlewareAction, ServerBuilder,
 };
 use regex::Regex;
-use solana_client::rpc_cache::LargestAccountsCache;
+use solana_client::{rpc_cache::LargestAccountsCache, rpc_request::MAX_BATCH_REQUESTS};
 use solana_ledger::blockstore::Blockstore;
 use solana_metrics::inc_new_counter_info;
 use solana_runtime::{
@@ -231,6 +238,42 @@ impl RequestMiddleware for RpcRequestMiddleware {
     }
 }
 
+/// Rejects JSON-RPC batches of more than `max_batch_requests` calls. The
+/// calls of a batch are processed back to back by a single RPC thread, so an
+/// unbounded batch could occupy that thread indefinitely.
+struct RpcBatchLimitMiddleware {
+    max_batch_requests: usize,
+}
+
+impl<M: Metadata> Middleware<M> for RpcBatchLimitMiddleware {
+    type Future = NoopFuture;
+    type CallFuture = jsonrpc_core::middleware::NoopCallFuture;
+
+    fn on_request<F, X>(&self, request: Request, meta: M, next: F) -> Either<Self::Future, X>
+    where
+        F: Fn(Request, M) -> X + Send + Sync,
+        X: Future<Output = Option<Response>> + Send + 'static,
+    {
+        if let Request::Batch(calls) = &request {
+            if calls.len() > self.max_batch_requests {
+                inc_new_counter_info!("rpc-batch-requests-rejected", 1);
+                let error = Error {
+                    code: ErrorCode::InvalidRequest,
+                    message: format!(
+                        "Too many requests in batch: {}, maximum: {}",
+                        calls.len(),
+                        self.max_batch_requests
+                    ),
+                    data: None,
+                };
+                let response = Response::from(error, Some(Version::V2));
+                return Either::Left(Box::pin(future::ready(Some(response))));
+            }
+        }
+        Either::Right(next(request, meta))
+    }
+}
+
 fn process_rest(bank_forks: &Arc<RwLock<BankForks>>, path: &str) -> Option<String> {
     match path {
         "/v0/circulating-supply" => {
@@ -278,6 +321,7 @@ impl JsonRpcService {
         info!("rpc bound to {:?}", rpc_addr);
         info!("rpc configuration: {:?}", config);
         let rpc_threads = 1.max(config.rpc_threads);
+        let max_batch_requests = config.max_batch_requests.unwrap_or(MAX_BATCH_REQUESTS);
 
         let health = Arc::new(RpcHealth::new(
             cluster_info.clone(),
@@ -391,7 +435,8 @@ impl JsonRpcService {
         let thread_hdl = Builder::new()
             .name("solana-jsonrpc".to_string())
             .spawn(move || {
-                let mut io = MetaIoHandler::default();
+                let mut io =
+                    MetaIoHandler::with_middleware(RpcBatchLimitMiddleware { max_batch_requests });
                 let rpc = RpcSolImpl;
                 io.extend_with(rpc.to_delegate());
 
@@ -529,6 +574,31 @@ mod tests {
         rpc_service.join().unwrap();
     }
 
+    #[test]
+    fn test_batch_limit() {
+        let mut io = MetaIoHandler::with_middleware(RpcBatchLimitMiddleware {
+            max_batch_requests: 2,
+        });
+        io.add_sync_method("getSlot", |_params| Ok(serde_json::json!(0)));
+
+        let batch = |len: usize| {
+            let calls: Vec<_> = (0..len)
+                .map(|id| serde_json::json!({"jsonrpc": "2.0", "id": id, "method": "getSlot"}))
+                .collect();
+            serde_json::Value::Array(calls).to_string()
+        };
+
+        let response: serde_json::Value =
+            serde_json::from_str(&io.handle_request_sync(&batch(2), ()).unwrap()).unwrap();
+        assert_eq!(response.as_array().unwrap().len(), 2);
+        assert_eq!(response[1]["result"], 0);
+
+        let response: serde_json::Value =
+            serde_json::from_str(&io.handle_request_sync(&batch(3), ()).unwrap()).unwrap();
+        assert_eq!(response["error"]["code"], -32600);
+        assert_eq!(response["id"], serde_json::Value::Null);
+    }
+
     fn create_bank_forks() -> Arc<RwLock<BankForks>> {
         let GenesisConfigInfo {
             mut genesis_config, ..
@@ -3,6 +3,7 @@
 use crate::{
     optimistically_confirmed_bank_tracker::OptimisticallyConfirmedBank,
     rpc::{get_parsed_token_account, get_parsed_token_accounts},
+    rpc_pubsub_service::PubSubConfig,
 };
 use core::hash::Hash;
 use jsonrpc_pubsub::{
@@ -12,13 +13,17 @@ use jsonrpc_pubsub::{
 use serde::Serialize;
 use solana_account_decoder::{parse_token::spl_token_id_v2_0, UiAccount, UiAccountEncoding};
 use solana_client::{
-    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcSignatureSubscribeConfig},
+    rpc_config::{
+        RpcAccountInfoConfig, RpcBlockSubscribeConfig, RpcProgramAccountsConfig,
+        RpcSignatureSubscribeConfig,
+    },
     rpc_filter::RpcFilterType,
     rpc_response::{
-        ProcessedSignatureResult, ReceivedSignatureResult, Response, RpcKeyedAccount,
-        RpcLogsResponse, RpcResponseContext, RpcSignatureResult, SlotInfo,
+        ProcessedSignatureResult, ReceivedSignatureResult, Response, RpcBlockUpdate,
+        RpcKeyedAccount, RpcLogsResponse, RpcResponseContext, RpcSignatureResult, SlotInfo,
     },
 };
+use solana_ledger::blockstore::Blockstore;
 use solana_measure::measure::Measure;
 use solana_runtime::{
     bank::{
@@ -28,13 +33,10 @@ use solana_runtime::{
     commitment::{BlockCommitmentCache, CommitmentSlots},
 };
 use solana_sdk::{
-    account::Account,
-    clock::{Slot, UnixTimestamp},
-    commitment_config::CommitmentConfig,
-    pubkey::Pubkey,
-    signature::Signature,
-    transaction,
+    account::Account, clock::Slot, commitment_config::CommitmentConfig, pubkey::Pubkey,
+    signature::Signature, transaction,
 };
+use solana_transaction_status::UiTransactionEncoding;
 use solana_vote_program::vote_state::Vote;
 use std::{
     collections::{HashMap, HashSet},
@@ -48,6 +50,9 @@ use std::{
     time::Duration,
 };
 
+// RpcVote moved to the client, and is re-exported for code that imports it from here
+pub use solana_client::rpc_response::RpcVote;
+
 const RECEIVE_DELAY_MILLIS: u64 = 100;
 
 trait BankGetTransactionLogsAdapter {
@@ -72,14 +77,6 @@ impl BankGetTransactionLogsAdapter for Bank {
     }
 }
 
-// A more human-friendly version of Vote, with the bank state signature base58 encoded.
-#[derive(Serialize, Deserialize, Debug)]
-pub struct RpcVote {
-    pub slots: Vec<Slot>,
-    pub hash: String,
-    pub timestamp: Option<UnixTimestamp>,
-}
-
 enum NotificationEntry {
     Slot(SlotInfo),
     Vote(Vote),
@@ -141,6 +138,12 @@ type RpcSignatureSubscriptions = RwLock<
         HashMap<SubscriptionId, SubscriptionData<Response<RpcSignatureResult>, bool>>,
     >,
 >;
+type RpcBlockSubscriptions = RwLock<
+    HashMap<
+        Option<Pubkey>,
+        HashMap<SubscriptionId, SubscriptionData<Response<RpcBlockUpdate>, UiTransactionEncoding>>,
+    >,
+>;
 type RpcSlotSubscriptions = RwLock<HashMap<SubscriptionId, Sink<SlotInfo>>>;
 type RpcVoteSubscriptions = RwLock<HashMap<SubscriptionId, Sink<RpcVote>>>;
 type RpcRootSubscriptions = RwLock<HashMap<SubscriptionId, Sink<Slot>>>;
@@ -250,8 +253,10 @@ where
                         sink,
                     );
                     notified_set.insert(sub_id.clone());
-                    *w_last_notified_slot = result_slot;
                 }
+                // Advance even when nothing was notified, so that block subscriptions whose
+                // filter matched nothing don't scan the same slots again
+                *w_last_notified_slot = result_slot;
             }
         }
     }
@@ -325,10 +330,9 @@ fn filter_program_results(
     let filters = config.filters;
     let accounts_is_empty = accounts.is_empty();
     let keyed_accounts = accounts.into_iter().filter(move |(_, account)| {
-        filters.iter().all(|filter_type| match filter_type {
-            RpcFilterType::DataSize(size) => account.data.len() as u64 == *size,
-            RpcFilterType::Memcmp(compare) => compare.bytes_match(&account.data),
-        })
+        filters
+            .iter()
+            .all(|filter_type| filter_type.allows(account))
     });
     let accounts: Box<dyn Iterator<Item = RpcKeyedAccount>> = if program_id == &spl_token_id_v2_0()
         && encoding == UiAccountEncoding::JsonParsed
@@ -366,6 +370,63 @@ fn filter_logs_results(
     }
 }
 
+fn filter_block_results(
+    blockstore: &Blockstore,
+    mentioned_address: &Option<Pubkey>,
+    last_notified_slot: Slot,
+    encoding: Option<UiTransactionEncoding>,
+    bank: Arc<Bank>,
+) -> (Box<dyn Iterator<Item = RpcBlockUpdate>>, Slot) {
+    let encoding = encoding.unwrap_or(UiTransactionEncoding::Json);
+    let mut updates = vec![];
+    for slot in unnotified_block_slots(blockstore, &bank, last_notified_slot) {
+        let mut block = match blockstore.get_complete_block(slot, false) {
+            Ok(block) => block,
+            Err(err) => {
+                warn!("block notification - block {} unavailable: {:?}", slot, err);
+                continue;
+            }
+        };
+        if let Some(address) = mentioned_address {
+            block
+                .transactions
+                .retain(|tx| tx.account_keys().contains(address));
+            if block.transactions.is_empty() {
+                continue;
+            }
+        }
+        updates.push(RpcBlockUpdate {
+            slot,
+            block: block.encode(encoding),
+        });
+    }
+    (
+        Box::new(updates.into_iter()),
+        bank.slot().max(last_notified_slot),
+    )
+}
+
+// Slots on the fork of `bank` that are newer than `last_notified_slot`, oldest first. Commitment
+// may advance by several slots at once, so the fork is walked back through the blockstore to
+// avoid skipping any block.
+fn unnotified_block_slots(
+    blockstore: &Blockstore,
+    bank: &Bank,
+    last_notified_slot: Slot,
+) -> Vec<Slot> {
+    let mut slots = vec![];
+    let mut slot = bank.slot();
+    while slot > last_notified_slot {
+        slots.push(slot);
+        match blockstore.meta(slot) {
+            Ok(Some(meta)) if meta.parent_slot < slot => slot = meta.parent_slot,
+            _ => break,
+        }
+    }
+    slots.reverse();
+    slots
+}
+
 fn total_nested_subscriptions<K, L, V>(
     subscription_map: &RwLock<HashMap<K, HashMap<L, V>>>,
 ) -> usize {
@@ -386,6 +447,8 @@ struct Subscriptions {
     gossip_logs_subscriptions: Arc<RpcLogsSubscriptions>,
     gossip_program_subscriptions: Arc<RpcProgramSubscriptions>,
     gossip_signature_subscriptions: Arc<RpcSignatureSubscriptions>,
+    block_subscriptions: Arc<RpcBlockSubscriptions>,
+    gossip_block_subscriptions: Arc<RpcBlockSubscriptions>,
     slot_subscriptions: Arc<RpcSlotSubscriptions>,
     vote_subscriptions: Arc<RpcVoteSubscriptions>,
     root_subscriptions: Arc<RpcRootSubscriptions>,
@@ -402,6 +465,8 @@ impl Subscriptions {
         total += total_nested_subscriptions(&self.gossip_logs_subscriptions);
         total += total_nested_subscriptions(&self.gossip_program_subscriptions);
         total += total_nested_subscriptions(&self.gossip_signature_subscriptions);
+        total += total_nested_subscriptions(&self.block_subscriptions);
+        total += total_nested_subscriptions(&self.gossip_block_subscriptions);
         total += self.slot_subscriptions.read().unwrap().len();
         total += self.vote_subscriptions.read().unwrap().len();
         total += self.root_subscriptions.read().unwrap().len();
@@ -418,6 +483,8 @@ pub struct RpcSubscriptions {
     optimistically_confirmed_bank: Arc<RwLock<OptimisticallyConfirmedBank>>,
     exit: Arc<AtomicBool>,
     enable_vote_subscription: bool,
+    // Only set when block subscriptions are enabled
+    blockstore: Option<Arc<Blockstore>>,
 }
 
 impl Drop for RpcSubscriptions {
@@ -451,6 +518,30 @@ impl RpcSubscriptions {
         optimistically_confirmed_bank: Arc<RwLock<OptimisticallyConfirmedBank>>,
         enable_vote_subscription: bool,
     ) -> Self {
+        Self::new_with_config(
+            exit,
+            bank_forks,
+            block_commitment_cache,
+            optimistically_confirmed_bank,
+            &PubSubConfig {
+                enable_vote_subscription,
+                ..PubSubConfig::default()
+            },
+            None,
+        )
+    }
+
+    /// Block subscriptions are only enabled if `pubsub_config` enables them and a `blockstore`
+    /// to read blocks from is provided
+    pub fn new_with_config(
+        exit: &Arc<AtomicBool>,
+        bank_forks: Arc<RwLock<BankForks>>,
+        block_commitment_cache: Arc<RwLock<BlockCommitmentCache>>,
+        optimistically_confirmed_bank: Arc<RwLock<OptimisticallyConfirmedBank>>,
+        pubsub_config: &PubSubConfig,
+        blockstore: Option<Arc<Blockstore>>,
+    ) -> Self {
+        let blockstore = blockstore.filter(|_| pubsub_config.enable_block_subscription);
         let (notification_sender, notification_receiver): (
             Sender<NotificationEntry>,
             Receiver<NotificationEntry>,
@@ -464,6 +555,8 @@ impl RpcSubscriptions {
         let gossip_logs_subscriptions = Arc::new(RpcLogsSubscriptions::default());
         let gossip_program_subscriptions = Arc::new(RpcProgramSubscriptions::default());
         let gossip_signature_subscriptions = Arc::new(RpcSignatureSubscriptions::default());
+        let block_subscriptions = Arc::new(RpcBlockSubscriptions::default());
+        let gossip_block_subscriptions = Arc::new(RpcBlockSubscriptions::default());
         let slot_subscriptions = Arc::new(RpcSlotSubscriptions::default());
         let vote_subscriptions = Arc::new(RpcVoteSubscriptions::default());
         let root_subscriptions = Arc::new(RpcRootSubscriptions::default());
@@ -471,6 +564,7 @@ impl RpcSubscriptions {
 
         let _bank_forks = bank_forks.clone();
         let _block_commitment_cache = block_commitment_cache.clone();
+        let _blockstore = blockstore.clone();
         let exit_clone = exit.clone();
         let subscriptions = Subscriptions {
             account_subscriptions,
@@ -481,6 +575,8 @@ impl RpcSubscriptions {
             gossip_logs_subscriptions,
             gossip_program_subscriptions,
             gossip_signature_subscriptions,
+            block_subscriptions,
+            gossip_block_subscriptions,
             slot_subscriptions,
             vote_subscriptions,
             root_subscriptions,
@@ -497,6 +593,7 @@ impl RpcSubscriptions {
                     notification_receiver,
                     _subscriptions,
                     _bank_forks,
+                    _blockstore,
                 );
             })
             .unwrap();
@@ -509,7 +606,8 @@ impl RpcSubscriptions {
             block_commitment_cache,
             optimistically_confirmed_bank,
             exit: exit.clone(),
-            enable_vote_subscription,
+            enable_vote_subscription: pubsub_config.enable_vote_subscription,
+            blockstore,
         }
     }
 
@@ -609,6 +707,34 @@ impl RpcSubscriptions {
         notified_ids
     }
 
+    fn check_block(
+        mentioned_address: &Option<Pubkey>,
+        bank_forks: &Arc<RwLock<BankForks>>,
+        blockstore: &Blockstore,
+        block_subscriptions: Arc<RpcBlockSubscriptions>,
+        notifier: &RpcNotifier,
+        commitment_slots: &CommitmentSlots,
+    ) -> HashSet<SubscriptionId> {
+        let subscriptions = block_subscriptions.read().unwrap();
+        check_commitment_and_notify(
+            &subscriptions,
+            mentioned_address,
+            bank_forks,
+            commitment_slots,
+            |_bank, _mentioned_address| (),
+            |(), mentioned_address, last_notified_slot, encoding, bank| {
+                filter_block_results(
+                    blockstore,
+                    mentioned_address,
+                    last_notified_slot,
+                    encoding,
+                    bank,
+                )
+            },
+            notifier,
+        )
+    }
+
     pub fn total(&self) -> usize {
         self.subscriptions.total()
     }
@@ -891,6 +1017,72 @@ impl RpcSubscriptions {
         }
     }
 
+    pub fn add_block_subscription(
+        &self,
+        mentioned_address: Option<Pubkey>,
+        config: Option<RpcBlockSubscribeConfig>,
+        sub_id: SubscriptionId,
+        subscriber: Subscriber<Response<RpcBlockUpdate>>,
+    ) {
+        if self.blockstore.is_none() {
+            let _ = subscriber.reject(jsonrpc_core::Error::new(
+                jsonrpc_core::ErrorCode::MethodNotFound,
+            ));
+            return;
+        }
+        let config = config.unwrap_or_default();
+        let commitment = config
+            .commitment
+            .unwrap_or_else(CommitmentConfig::confirmed);
+
+        // Only blocks that reach the requested commitment after subscribing are notified
+        let (mut subscriptions, last_notified_slot) = if commitment.is_finalized() {
+            (
+                self.subscriptions.block_subscriptions.write().unwrap(),
+                self.block_commitment_cache
+                    .read()
+                    .unwrap()
+                    .highest_confirmed_root(),
+            )
+        } else {
+            (
+                self.subscriptions
+                    .gossip_block_subscriptions
+                    .write()
+                    .unwrap(),
+                self.optimistically_confirmed_bank
+                    .read()
+                    .unwrap()
+                    .bank
+                    .slot(),
+            )
+        };
+
+        add_subscription(
+            &mut subscriptions,
+            mentioned_address,
+            commitment,
+            sub_id,
+            subscriber,
+            last_notified_slot,
+            config.encoding,
+        );
+    }
+
+    pub fn remove_block_subscription(&self, id: &SubscriptionId) -> bool {
+        let mut subscriptions = self.subscriptions.block_subscriptions.write().unwrap();
+        if remove_subscription(&mut subscriptions, id) {
+            true
+        } else {
+            let mut subscriptions = self
+                .subscriptions
+                .gossip_block_subscriptions
+                .write()
+                .unwrap();
+            remove_subscription(&mut subscriptions, id)
+        }
+    }
+
     /// Notify subscribers of changes to any accounts or new signatures since
     /// the bank's last checkpoint.
     pub fn notify_subscribers(&self, commitment_slots: CommitmentSlots) {
@@ -984,6 +1176,7 @@ impl RpcSubscriptions {
         notification_receiver: Receiver<NotificationEntry>,
         subscriptions: Subscriptions,
         bank_forks: Arc<RwLock<BankForks>>,
+        blockstore: Option<Arc<Blockstore>>,
     ) {
         loop {
             if exit.load(Ordering::Relaxed) {
@@ -1053,7 +1246,16 @@ impl RpcSubscriptions {
                             &commitment_slots,
                             &notifier,
                             "bank",
-                        )
+                        );
+                        if let Some(blockstore) = &blockstore {
+                            Self::notify_blocks(
+                                &subscriptions.block_subscriptions,
+                                &bank_forks,
+                                blockstore,
+                                &commitment_slots,
+                                &notifier,
+                            );
+                        }
                     }
                     NotificationEntry::Gossip(slot) => {
                         Self::process_gossip_notification(
@@ -1061,6 +1263,7 @@ impl RpcSubscriptions {
                             &notifier,
                             &subscriptions,
                             &bank_forks,
+                            blockstore.as_deref(),
                         );
                     }
                     NotificationEntry::SignaturesReceived(slot_signatures) => {
@@ -1092,6 +1295,7 @@ impl RpcSubscriptions {
         notifier: &RpcNotifier,
         subscriptions: &Subscriptions,
         bank_forks: &Arc<RwLock<BankForks>>,
+        blockstore: Option<&Blockstore>,
     ) {
         let commitment_slots = CommitmentSlots {
             highest_confirmed_slot: slot,
@@ -1107,6 +1311,41 @@ impl RpcSubscriptions {
             &notifier,
             "gossip",
         );
+        if let Some(blockstore) = blockstore {
+            Self::notify_blocks(
+                &subscriptions.gossip_block_subscriptions,
+                bank_forks,
+                blockstore,
+                &commitment_slots,
+                notifier,
+            );
+        }
+    }
+
+    fn notify_blocks(
+        block_subscriptions: &Arc<RpcBlockSubscriptions>,
+        bank_forks: &Arc<RwLock<BankForks>>,
+        blockstore: &Blockstore,
+        commitment_slots: &CommitmentSlots,
+        notifier: &RpcNotifier,
+    ) {
+        let mentioned_addresses: Vec<_> = {
+            let subs = block_subscriptions.read().unwrap();
+            subs.keys().cloned().collect()
+        };
+        let mut num_blocks_notified = 0;
+        for mentioned_address in &mentioned_addresses {
+            num_blocks_notified += Self::check_block(
+                mentioned_address,
+                bank_forks,
+                blockstore,
+                block_subscriptions.clone(),
+                notifier,
+                commitment_slots,
+            )
+            .len();
+        }
+        inc_new_counter_info!("rpc-subscription-notify-block", num_blocks_notified);
     }
 
     fn notify_accounts_logs_programs_signatures(
@@ -1283,16 +1522,26 @@ pub(crate) mod tests {
     use jsonrpc_core::futures::StreamExt;
     use jsonrpc_pubsub::typed::Subscriber;
     use serial_test::serial;
+    use solana_ledger::{
+        blockstore::entries_to_test_shreds,
+        entry::{next_entry, next_hash, Entry},
+        get_tmp_ledger_path,
+    };
     use solana_runtime::{
         commitment::BlockCommitment,
         genesis_utils::{create_genesis_config, GenesisConfigInfo},
     };
     use solana_sdk::{
-        message::Message,
-        signature::{Keypair, Signer},
+        instruction::CompiledInstruction,
+        message::{
+            v0::{self, LoadedAddresses, MessageAddressTableLookup},
+            Message, MessageHeader, VersionedMessage,
+        },
+        signature::{Keypair, Signature, Signer},
         system_instruction, system_program, system_transaction,
-        transaction::Transaction,
+        transaction::{Transaction, VersionedTransaction},
     };
+    use solana_transaction_status::TransactionStatusMeta;
     use std::{fmt::Debug, sync::mpsc::channel};
     use tokio::{
         runtime::Runtime,
@@ -2240,4 +2489,230 @@ pub(crate) mod tests {
         subscriptions.remove_root_subscription(&root_sub_id);
         assert_eq!(subscriptions.total(), 0);
     }
+
+    #[test]
+    #[serial]
+    fn test_check_block_subscribe() {
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config(100);
+        let bank = Bank::new(&genesis_config);
+        let blockhash = bank.last_blockhash();
+        let bank_forks = Arc::new(RwLock::new(BankForks::new(bank)));
+        let bank0 = bank_forks.read().unwrap().get(0).unwrap().clone();
+        let bank1 = Bank::new_from_parent(&bank0, &Pubkey::default(), 1);
+        bank_forks.write().unwrap().insert(bank1);
+        let bank1 = bank_forks.read().unwrap().get(1).unwrap().clone();
+        let bank2 = Bank::new_from_parent(&bank1, &Pubkey::default(), 2);
+        bank_forks.write().unwrap().insert(bank2);
+
+        let ledger_path = get_tmp_ledger_path!();
+        let blockstore = Arc::new(Blockstore::open(&ledger_path).unwrap());
+        let alice = Pubkey::new_unique();
+        let bob = Pubkey::new_unique();
+        let carol = Pubkey::new_unique();
+        // Slot 2 also holds a transaction that only mentions carol through a lookup table
+        let versioned_tx = VersionedTransaction {
+            signatures: vec![Signature::new(&[1u8; 64])],
+            message: VersionedMessage::V0(v0::Message {
+                header: MessageHeader {
+                    num_required_signatures: 1,
+                    num_readonly_signed_accounts: 0,
+                    num_readonly_unsigned_accounts: 1,
+                },
+                account_keys: vec![mint_keypair.pubkey(), system_program::id()],
+                recent_blockhash: blockhash,
+                instructions: vec![CompiledInstruction::new(1, &(), vec![0, 2])],
+                address_table_lookups: vec![MessageAddressTableLookup {
+                    account_key: Pubkey::new_unique(),
+                    writable_indexes: vec![0],
+                    readonly_indexes: vec![],
+                }],
+            }),
+        };
+        for (slot, to) in [(1, alice), (2, bob)].iter() {
+            let tx = system_transaction::transfer(&mint_keypair, to, 1, blockhash);
+            let mut entries = vec![next_entry(&blockhash, 1, vec![tx])];
+            if *slot == 2 {
+                entries.push(Entry {
+                    num_hashes: 1,
+                    hash: next_hash(&entries[0].hash, 1, &[versioned_tx.clone()]),
+                    transactions: vec![versioned_tx.clone()],
+                });
+            }
+            let shreds = entries_to_test_shreds(entries, *slot, *slot - 1, true, 0);
+            blockstore.insert_shreds(shreds, None, false).unwrap();
+        }
+        blockstore
+            .write_transaction_status(
+                2,
+                versioned_tx.signatures[0],
+                vec![&mint_keypair.pubkey(), &carol],
+                vec![&system_program::id()],
+                &TransactionStatusMeta {
+                    loaded_addresses: LoadedAddresses {
+                        writable: vec![carol],
+                        readonly: vec![],
+                    },
+                    ..TransactionStatusMeta::default()
+                },
+            )
+            .unwrap();
+
+        let exit = Arc::new(AtomicBool::new(false));
+        let subscriptions = RpcSubscriptions::new_with_config(
+            &exit,
+            bank_forks.clone(),
+            Arc::new(RwLock::new(BlockCommitmentCache::default())),
+            OptimisticallyConfirmedBank::locked_from_bank_forks_root(&bank_forks),
+            &PubSubConfig {
+                enable_block_subscription: true,
+                ..PubSubConfig::default()
+            },
+            Some(blockstore),
+        );
+        let (all_sub, _id_receiver, all_recv) = Subscriber::new_test("blockNotification");
+        let (bob_sub, _id_receiver, bob_recv) = Subscriber::new_test("blockNotification");
+        let (carol_sub, _id_receiver, carol_recv) = Subscriber::new_test("blockNotification");
+        let all_sub_id = SubscriptionId::Number(0);
+        let bob_sub_id = SubscriptionId::Number(1);
+        let carol_sub_id = SubscriptionId::Number(2);
+        subscriptions.add_block_subscription(None, None, all_sub_id.clone(), all_sub);
+        subscriptions.add_block_subscription(
+            Some(bob),
+            Some(RpcBlockSubscribeConfig {
+                commitment: Some(CommitmentConfig::confirmed()),
+                encoding: Some(UiTransactionEncoding::Base64),
+            }),
+            bob_sub_id.clone(),
+            bob_sub,
+        );
+        subscriptions.add_block_subscription(
+            Some(carol),
+            Some(RpcBlockSubscribeConfig {
+                commitment: Some(CommitmentConfig::confirmed()),
+                encoding: None,
+            }),
+            carol_sub_id.clone(),
+            carol_sub,
+        );
+        assert_eq!(subscriptions.total(), 3);
+
+        // Both blocks became confirmed at once, and each is notified
+        subscriptions.notify_gossip_subscribers(2);
+        let (response, all_recv) = robust_poll_or_panic(all_recv);
+        let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+        assert_eq!(response["params"]["result"]["context"]["slot"], 2);
+        assert_eq!(response["params"]["result"]["value"]["slot"], 1);
+        let block = &response["params"]["result"]["value"]["block"];
+        assert_eq!(block["parentSlot"], 0);
+        assert_eq!(block["transactions"].as_array().unwrap().len(), 1);
+        let (response, _) = robust_poll_or_panic(all_recv);
+        let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+        assert_eq!(response["params"]["result"]["value"]["slot"], 2);
+
+        // Blocks that don't mention the filtered address are skipped
+        let (response, _) = robust_poll_or_panic(bob_recv);
+        let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+        assert_eq!(response["params"]["result"]["value"]["slot"], 2);
+        let transactions = response["params"]["result"]["value"]["block"]["transactions"]
+            .as_array()
+            .unwrap();
+        assert_eq!(transactions.len(), 1);
+        assert_eq!(transactions[0]["transaction"][1], "base64");
+
+        // Addresses loaded from lookup tables are mentions too
+        let (response, _) = robust_poll_or_panic(carol_recv);
+        let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+        assert_eq!(response["params"]["result"]["value"]["slot"], 2);
+        let transactions = response["params"]["result"]["value"]["block"]["transactions"]
+            .as_array()
+            .unwrap();
+        assert_eq!(transactions.len(), 1);
+        assert_eq!(
+            transactions[0]["meta"]["loadedAddresses"]["writable"][0],
+            carol.to_string()
+        );
+
+        assert!(subscriptions.remove_block_subscription(&all_sub_id));
+        assert!(subscriptions.remove_block_subscription(&bob_sub_id));
+        assert!(subscriptions.remove_block_subscription(&carol_sub_id));
+        assert!(!subscriptions.remove_block_subscription(&bob_sub_id));
+        assert_eq!(subscriptions.total(), 0);
+    }
+    #[test]
+    #[serial]
+    fn test_check_block_subscribe_without_matches() {
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config(100);
+        let bank = Bank::new(&genesis_config);
+        let blockhash = bank.last_blockhash();
+        let bank_forks = Arc::new(RwLock::new(BankForks::new(bank)));
+        let bank0 = bank_forks.read().unwrap().get(0).unwrap().clone();
+        let bank1 = Bank::new_from_parent(&bank0, &Pubkey::default(), 1);
+        bank_forks.write().unwrap().insert(bank1);
+        let bank1 = bank_forks.read().unwrap().get(1).unwrap().clone();
+        let bank2 = Bank::new_from_parent(&bank1, &Pubkey::default(), 2);
+        bank_forks.write().unwrap().insert(bank2);
+        let bank2 = bank_forks.read().unwrap().get(2).unwrap().clone();
+
+        let ledger_path = get_tmp_ledger_path!();
+        let blockstore = Arc::new(Blockstore::open(&ledger_path).unwrap());
+        for slot in 1..=2 {
+            let tx =
+                system_transaction::transfer(&mint_keypair, &Pubkey::new_unique(), 1, blockhash);
+            let entries = vec![next_entry(&blockhash, 1, vec![tx])];
+            let shreds = entries_to_test_shreds(entries, slot, slot - 1, true, 0);
+            blockstore.insert_shreds(shreds, None, false).unwrap();
+        }
+
+        let exit = Arc::new(AtomicBool::new(false));
+        let subscriptions = RpcSubscriptions::new_with_config(
+            &exit,
+            bank_forks.clone(),
+            Arc::new(RwLock::new(BlockCommitmentCache::default())),
+            OptimisticallyConfirmedBank::locked_from_bank_forks_root(&bank_forks),
+            &PubSubConfig {
+                enable_block_subscription: true,
+                ..PubSubConfig::default()
+            },
+            Some(blockstore.clone()),
+        );
+        let (sub, _id_receiver, _recv) = Subscriber::new_test("blockNotification");
+        let sub_id = SubscriptionId::Number(0);
+        let dave = Pubkey::new_unique();
+        subscriptions.add_block_subscription(Some(dave), None, sub_id.clone(), sub);
+
+        let block_subscriptions = subscriptions
+            .subscriptions
+            .gossip_block_subscriptions
+            .clone();
+        let notified = RpcSubscriptions::check_block(
+            &Some(dave),
+            &bank_forks,
+            &blockstore,
+            block_subscriptions.clone(),
+            &RpcNotifier,
+            &CommitmentSlots {
+                highest_confirmed_slot: 2,
+                ..CommitmentSlots::default()
+            },
+        );
+        assert!(notified.is_empty());
+
+        // No block mentions dave, yet the slots checked are not scanned again
+        let last_notified_slot = *block_subscriptions.read().unwrap()[&Some(dave)][&sub_id]
+            .last_notified_slot
+            .read()
+            .unwrap();
+        assert_eq!(last_notified_slot, 2);
+        assert!(unnotified_block_slots(&blockstore, &bank2, last_notified_slot).is_empty());
+
+        assert!(subscriptions.remove_block_subscription(&sub_id));
+    }
 }
@@ -50,6 +50,7 @@ pub struct TestValidatorGenesis {
     rpc_ports: Option<(u16, u16)>, // (JsonRpc, JsonRpcPubSub), None == random ports
     warp_slot: Option<Slot>,
     no_bpf_jit: bool,
+    blockstore_in_memory: bool,
     accounts: HashMap<Pubkey, Account>,
     programs: Vec<ProgramInfo>,
 }
@@ -90,6 +91,12 @@ impl TestValidatorGenesis {
         self
     }
 
+    /// Keep the blockstore in memory instead of writing it to the ledger directory
+    pub fn blockstore_in_memory(&mut self, blockstore_in_memory: bool) -> &mut Self {
+        self.blockstore_in_memory = blockstore_in_memory;
+        self
+    }
+
     /// Add an account to the test environment
     pub fn add_account(&mut self, address: Pubkey, account: Account) -> &mut Self {
         self.accounts.insert(address, account);
@@ -401,6 +408,7 @@ impl TestValidator {
             enforce_ulimit_nofile: false,
             warp_slot: config.warp_slot,
             bpf_jit: !config.no_bpf_jit,
+            blockstore_in_memory: config.blockstore_in_memory,
             ..ValidatorConfig::default()
         };
 
@@ -5,6 +5,7 @@ use solana_runtime::{
     bank::{Bank, InnerInstructionsList, NonceRollbackInfo, TransactionLogMessages},
     transaction_utils::OrderedIterator,
 };
+use solana_sdk::{fee_calculator::FeeConfig, message::v0::LoadedAddresses};
 use solana_transaction_status::{InnerInstructions, TransactionStatusMeta};
 use std::{
     sync::{
@@ -51,15 +52,21 @@ impl TransactionStatusService {
         let TransactionStatusBatch {
             bank,
             transactions,
+            versioned_transactions,
             iteration_order,
             statuses,
             balances,
             token_balances,
             inner_instructions,
             transaction_logs,
+            transaction_return_data,
         } = write_transaction_status_receiver.recv_timeout(Duration::from_secs(1))?;
 
         let slot = bank.slot();
+        let fee_config = FeeConfig {
+            secp256k1_program_enabled: bank.secp256k1_program_enabled(),
+            compute_budget_program_enabled: bank.compute_budget_program_enabled(),
+        };
         let inner_instructions_iter: Box<dyn Iterator<Item = Option<InnerInstructionsList>>> =
             if let Some(inner_instructions) = inner_instructions {
                 Box::new(inner_instructions.into_iter())
@@ -73,7 +80,7 @@ impl TransactionStatusService {
                 Box::new(std::iter::repeat_with(Vec::new))
             };
         for (
-            (_, transaction),
+            (index, transaction),
             (status, nonce_rollback),
             pre_balances,
             post_balances,
@@ -81,6 +88,7 @@ impl TransactionStatusService {
             post_token_balances,
             inner_instructions,
             log_messages,
+            return_data,
         ) in izip!(
             OrderedIterator::new(&transactions, iteration_order.as_deref()),
             statuses,
@@ -89,7 +97,8 @@ impl TransactionStatusService {
             token_balances.pre_token_balances,
             token_balances.post_token_balances,
             inner_instructions_iter,
-            transaction_logs_iter
+            transaction_logs_iter,
+            transaction_return_data
         ) {
             if Bank::can_commit(&status) && !transaction.signatures.is_empty() {
                 let fee_calculator = nonce_rollback
@@ -98,7 +107,8 @@ impl TransactionStatusService {
                         bank.get_fee_calculator(&transaction.message().recent_blockhash)
                     })
                     .expect("FeeCalculator must exist");
-                let fee = fee_calculator.calculate_fee(transaction.message());
+                let fee =
+                    fee_calculator.calculate_fee_with_config(transaction.message(), &fee_config);
                 let (writable_keys, readonly_keys) =
                     transaction.message.get_account_keys_by_lock_type();
 
@@ -118,22 +128,28 @@ impl TransactionStatusService {
                 let pre_token_balances = Some(pre_token_balances);
                 let post_token_balances = Some(post_token_balances);
 
+                let mut transaction_status_meta = TransactionStatusMeta {
+                    status,
+                    fee,
+                    pre_balances,
+                    post_balances,
+                    inner_instructions,
+                    log_messages,
+                    pre_token_balances,
+                    post_token_balances,
+                    return_data,
+                    loaded_addresses: LoadedAddresses::default(),
+                };
+                transaction_status_meta
+                    .record_loaded_addresses(transaction, &versioned_transactions[index]);
+
                 blockstore
                     .write_transaction_status(
                         slot,
                         transaction.signatures[0],
                         writable_keys,
                         readonly_keys,
-                        &TransactionStatusMeta {
-                            status,
-                            fee,
-                            pre_balances,
-                            post_balances,
-                            inner_instructions,
-                            log_messages,
-                            pre_token_balances,
-                            post_token_balances,
-                        },
+                        &transaction_status_meta,
                     )
                     .expect("Expect database write to succeed");
             }
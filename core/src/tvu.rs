@@ -10,7 +10,7 @@ use crate::{
     cluster_slots::ClusterSlots,
     completed_data_sets_service::CompletedDataSetsSender,
     consensus::Tower,
-    ledger_cleanup_service::LedgerCleanupService,
+    ledger_cleanup_service::{LedgerCleanupService, LedgerRetention},
     max_slots::MaxSlots,
     optimistically_confirmed_bank_tracker::BankNotificationSender,
     poh_recorder::PohRecorder,
@@ -73,7 +73,7 @@ pub struct Sockets {
 
 #[derive(Default)]
 pub struct TvuConfig {
-    pub max_ledger_shreds: Option<u64>,
+    pub ledger_retention: Option<LedgerRetention>,
     pub shred_version: u16,
     pub halt_on_trusted_validators_accounts_hash_mismatch: bool,
     pub trusted_validators: Option<HashSet<Pubkey>>,
@@ -270,11 +270,11 @@ impl Tvu {
             replay_vote_sender,
         );
 
-        let ledger_cleanup_service = tvu_config.max_ledger_shreds.map(|max_ledger_shreds| {
+        let ledger_cleanup_service = tvu_config.ledger_retention.map(|ledger_retention| {
             LedgerCleanupService::new(
                 ledger_cleanup_slot_receiver,
                 blockstore.clone(),
-                max_ledger_shreds,
+                ledger_retention,
                 &exit,
                 compaction_interval,
                 max_compaction_jitter,
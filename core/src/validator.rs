@@ -12,6 +12,7 @@ use crate::{
     consensus::{reconcile_blockstore_roots_with_tower, Tower},
     contact_info::ContactInfo,
     gossip_service::GossipService,
+    ledger_cleanup_service::LedgerRetention,
     max_slots::MaxSlots,
     optimistically_confirmed_bank_tracker::{
         OptimisticallyConfirmedBank, OptimisticallyConfirmedBankTracker,
@@ -91,7 +92,8 @@ pub struct ValidatorConfig {
     pub rpc_addrs: Option<(SocketAddr, SocketAddr)>, // (JsonRpc, JsonRpcPubSub)
     pub pubsub_config: PubSubConfig,
     pub snapshot_config: Option<SnapshotConfig>,
-    pub max_ledger_shreds: Option<u64>,
+    pub ledger_retention: Option<LedgerRetention>,
+    pub blockstore_in_memory: bool, // Nothing is written back to the ledger directory
     pub broadcast_stage_type: BroadcastStageType,
     pub enable_partition: Option<Arc<AtomicBool>>,
     pub enforce_ulimit_nofile: bool,
@@ -136,7 +138,8 @@ impl Default for ValidatorConfig {
             expected_bank_hash: None,
             expected_shred_version: None,
             voting_disabled: false,
-            max_ledger_shreds: None,
+            ledger_retention: None,
+            blockstore_in_memory: false,
             account_paths: Vec::new(),
             account_shrink_paths: None,
             rpc_config: JsonRpcConfig::default(),
@@ -407,12 +410,13 @@ impl Validator {
         let optimistically_confirmed_bank =
             OptimisticallyConfirmedBank::locked_from_bank_forks_root(&bank_forks);
 
-        let subscriptions = Arc::new(RpcSubscriptions::new_with_vote_subscription(
+        let subscriptions = Arc::new(RpcSubscriptions::new_with_config(
             &exit,
             bank_forks.clone(),
             block_commitment_cache.clone(),
             optimistically_confirmed_bank.clone(),
-            config.pubsub_config.enable_vote_subscription,
+            &config.pubsub_config,
+            Some(blockstore.clone()),
         ));
 
         let max_slots = Arc::new(MaxSlots::default());
@@ -645,7 +649,7 @@ impl Validator {
             completed_data_sets_sender,
             bank_notification_sender.clone(),
             TvuConfig {
-                max_ledger_shreds: config.max_ledger_shreds,
+                ledger_retention: config.ledger_retention.clone(),
                 halt_on_trusted_validators_accounts_hash_mismatch: config
                     .halt_on_trusted_validators_accounts_hash_mismatch,
                 shred_version: node.info.shred_version,
@@ -972,11 +976,15 @@ fn new_banks_from_ledger(
         ledger_signal_receiver,
         completed_slots_receiver,
         ..
-    } = Blockstore::open_with_signal(
-        ledger_path,
-        config.wal_recovery_mode.clone(),
-        enforce_ulimit_nofile,
-    )
+    } = if config.blockstore_in_memory {
+        Blockstore::open_in_memory_with_signal(ledger_path, config.wal_recovery_mode.clone())
+    } else {
+        Blockstore::open_with_signal(
+            ledger_path,
+            config.wal_recovery_mode.clone(),
+            enforce_ulimit_nofile,
+        )
+    }
     .expect("Failed to open ledger database");
     blockstore.set_no_compaction(config.no_rocksdb_compaction);
 
@@ -4,7 +4,7 @@
 #[cfg(test)]
 mod tests {
     use log::*;
-    use solana_core::ledger_cleanup_service::LedgerCleanupService;
+    use solana_core::ledger_cleanup_service::{LedgerCleanupService, LedgerRetention};
     use solana_ledger::blockstore::{make_many_slot_entries, Blockstore};
     use solana_ledger::get_tmp_ledger_path;
     use solana_ledger::shred::Shred;
@@ -230,7 +230,7 @@ mod tests {
         let cleaner = LedgerCleanupService::new(
             receiver,
             blockstore.clone(),
-            max_ledger_shreds,
+            LedgerRetention::with_max_ledger_shreds(max_ledger_shreds),
             &exit,
             None,
             None,
@@ -508,7 +508,7 @@ mod tests {
         LedgerCleanupService::cleanup_ledger(
             &receiver,
             &blockstore,
-            max_ledger_shreds,
+            &LedgerRetention::with_max_ledger_shreds(max_ledger_shreds),
             &mut last_purge_slot,
             10,
             &highest_compact_slot,
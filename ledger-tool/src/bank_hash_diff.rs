@@ -0,0 +1,468 @@
+/// The `bank-hash-diff` subcommand: replays two ledgers side by side and reports where and why
+/// their bank hashes diverge
+use log::*;
+use solana_ledger::{
+    bank_forks_utils, blockstore_db::AccessType, blockstore_db::BlockstoreRecoveryMode,
+    blockstore_processor::ProcessOptions,
+};
+use solana_runtime::{
+    bank::{Bank, BankHashInputs},
+    bank_forks::{ArchiveFormat, SnapshotConfig},
+    snapshot_utils::{self, SnapshotVersion},
+};
+use solana_sdk::{
+    account::Account, clock::Slot, genesis_config::GenesisConfig, hash::Hash, pubkey::Pubkey,
+};
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    path::PathBuf,
+    sync::{
+        mpsc::{channel, Sender},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+};
+
+/// A ledger to replay, either from genesis or from the latest snapshot archive in
+/// `snapshot_archive_path`
+#[derive(Clone)]
+pub struct ReplaySource {
+    pub name: &'static str,
+    pub ledger_path: PathBuf,
+    pub genesis_config: GenesisConfig,
+    pub snapshot_archive_path: Option<PathBuf>,
+}
+
+impl ReplaySource {
+    /// The first slot replayed, which follows the snapshot the replay starts from if any
+    fn first_replayed_slot(&self) -> Slot {
+        self.snapshot_archive_path
+            .as_ref()
+            .and_then(snapshot_utils::get_highest_snapshot_archive_path)
+            .map(|(_archive_path, (slot, _hash, _format))| slot + 1)
+            .unwrap_or(0)
+    }
+}
+
+/// What a replayed bank hashed, recorded as soon as the bank is frozen
+struct SlotDetails {
+    slot: Slot,
+    hash: Hash,
+    hash_inputs: BankHashInputs,
+}
+
+impl SlotDetails {
+    fn new(bank: &Bank) -> Self {
+        Self {
+            slot: bank.slot(),
+            hash: bank.hash(),
+            hash_inputs: bank.hash_inputs(),
+        }
+    }
+}
+
+enum ReplayEvent {
+    Frozen(usize, SlotDetails),
+    Done(usize, Result<(), String>),
+}
+
+fn load(
+    source: ReplaySource,
+    process_options: ProcessOptions,
+    wal_recovery_mode: Option<BlockstoreRecoveryMode>,
+) -> Result<(), String> {
+    let ReplaySource {
+        name,
+        ledger_path,
+        genesis_config,
+        snapshot_archive_path,
+    } = source;
+    let blockstore = crate::open_blockstore(
+        &ledger_path,
+        AccessType::TryPrimaryThenSecondary,
+        wal_recovery_mode,
+    );
+    // Scratch space, so that neither replay touches the accounts of the ledger
+    let tempdir = |name: &str| {
+        tempfile::Builder::new()
+            .prefix(&format!("{}.", name))
+            .tempdir_in(&ledger_path)
+            .map_err(|err| format!("Unable to create {} directory: {}", name, err))
+    };
+    let accounts_dir = tempdir("bank-hash-diff-accounts")?;
+    let snapshot_dir = tempdir("bank-hash-diff-snapshot")?;
+    let snapshot_config =
+        snapshot_archive_path.map(|snapshot_package_output_path| SnapshotConfig {
+            snapshot_interval_slots: 0, // Value doesn't matter
+            snapshot_package_output_path,
+            snapshot_path: snapshot_dir.path().to_path_buf(),
+            archive_format: ArchiveFormat::TarBzip2,
+            snapshot_version: SnapshotVersion::default(),
+        });
+    bank_forks_utils::load(
+        &genesis_config,
+        &blockstore,
+        vec![accounts_dir.path().to_path_buf()],
+        None,
+        snapshot_config.as_ref(),
+        process_options,
+        None,
+    )
+    .map(|_| ())
+    .map_err(|err| format!("Failed to load {}: {:?}", name, err))
+}
+
+fn replay(
+    index: usize,
+    source: ReplaySource,
+    mut process_options: ProcessOptions,
+    wal_recovery_mode: Option<BlockstoreRecoveryMode>,
+    starting_slot: Slot,
+    sender: Sender<ReplayEvent>,
+) -> JoinHandle<()> {
+    let slot_sender = Mutex::new(sender.clone());
+    process_options.slot_callback = Some(Arc::new(move |bank: &Bank| {
+        if bank.slot() >= starting_slot {
+            let _ = slot_sender
+                .lock()
+                .unwrap()
+                .send(ReplayEvent::Frozen(index, SlotDetails::new(bank)));
+        }
+    }));
+
+    thread::Builder::new()
+        .name(format!("bankHashDiff{}", index))
+        .spawn(move || {
+            let result = load(source, process_options, wal_recovery_mode);
+            let _ = sender.send(ReplayEvent::Done(index, result));
+        })
+        .unwrap()
+}
+
+/// Replay up to `slot` to collect the accounts stored in it, sorted by pubkey
+fn replay_accounts_delta(
+    index: usize,
+    source: ReplaySource,
+    mut process_options: ProcessOptions,
+    wal_recovery_mode: Option<BlockstoreRecoveryMode>,
+    slot: Slot,
+) -> JoinHandle<Result<Vec<(Pubkey, Account)>, String>> {
+    let accounts_delta = Arc::new(Mutex::new(None));
+    let slot_accounts_delta = accounts_delta.clone();
+    process_options.dev_halt_at_slot = Some(slot);
+    process_options.slot_callback = Some(Arc::new(move |bank: &Bank| {
+        if bank.slot() == slot {
+            *slot_accounts_delta.lock().unwrap() = Some(bank.get_accounts_delta());
+        }
+    }));
+
+    thread::Builder::new()
+        .name(format!("bankHashDelta{}", index))
+        .spawn(move || {
+            let name = source.name;
+            load(source, process_options, wal_recovery_mode)?;
+            let accounts_delta = accounts_delta.lock().unwrap().take();
+            accounts_delta.ok_or_else(|| format!("{} did not replay slot {} again", name, slot))
+        })
+        .unwrap()
+}
+
+fn print_hash_inputs_diff(names: [&str; 2], inputs: [&BankHashInputs; 2]) {
+    let print_diff = |field: &str, values: [String; 2]| {
+        if values[0] != values[1] {
+            println!("  {} differs:", field);
+            for (name, value) in names.iter().zip(values.iter()) {
+                println!("    {:>10}: {}", name, value);
+            }
+        }
+    };
+    print_diff(
+        "parent hash",
+        [
+            inputs[0].parent_hash.to_string(),
+            inputs[1].parent_hash.to_string(),
+        ],
+    );
+    print_diff(
+        "accounts delta hash",
+        [
+            inputs[0].accounts_delta_hash.to_string(),
+            inputs[1].accounts_delta_hash.to_string(),
+        ],
+    );
+    print_diff(
+        "signature count",
+        [
+            inputs[0].signature_count.to_string(),
+            inputs[1].signature_count.to_string(),
+        ],
+    );
+    print_diff(
+        "last blockhash",
+        [
+            inputs[0].last_blockhash.to_string(),
+            inputs[1].last_blockhash.to_string(),
+        ],
+    );
+    print_diff(
+        "hard fork data",
+        [
+            format!("{:?}", inputs[0].hard_fork_data),
+            format!("{:?}", inputs[1].hard_fork_data),
+        ],
+    );
+}
+
+fn print_account_diff(names: [&str; 2], pubkey: &Pubkey, accounts: [&Account; 2]) {
+    println!("  account {} differs:", pubkey);
+    let print_field = |field: &str, values: [String; 2]| {
+        if values[0] != values[1] {
+            println!(
+                "    {}: {} {}, {} {}",
+                field, names[0], values[0], names[1], values[1]
+            );
+        }
+    };
+    print_field(
+        "lamports",
+        [
+            accounts[0].lamports.to_string(),
+            accounts[1].lamports.to_string(),
+        ],
+    );
+    print_field(
+        "owner",
+        [accounts[0].owner.to_string(), accounts[1].owner.to_string()],
+    );
+    print_field(
+        "executable",
+        [
+            accounts[0].executable.to_string(),
+            accounts[1].executable.to_string(),
+        ],
+    );
+    print_field(
+        "rent epoch",
+        [
+            accounts[0].rent_epoch.to_string(),
+            accounts[1].rent_epoch.to_string(),
+        ],
+    );
+    let (data0, data1) = (&accounts[0].data, &accounts[1].data);
+    if data0 != data1 {
+        let offset = data0
+            .iter()
+            .zip(data1.iter())
+            .position(|(byte0, byte1)| byte0 != byte1)
+            .unwrap_or_else(|| data0.len().min(data1.len()));
+        println!(
+            "    data: {} {} bytes, {} {} bytes, first difference at offset {}",
+            names[0],
+            data0.len(),
+            names[1],
+            data1.len(),
+            offset
+        );
+        let end = |data: &[u8]| data.len().min(offset + 32);
+        println!("      {}: {:02x?}", names[0], &data0[offset..end(data0)]);
+        println!("      {}: {:02x?}", names[1], &data1[offset..end(data1)]);
+    }
+}
+
+fn print_slot_diff(
+    names: [&str; 2],
+    details: [&SlotDetails; 2],
+    accounts_deltas: [&[(Pubkey, Account)]; 2],
+) {
+    println!(
+        "Slot {} hashes differ: {} {}, {} {}",
+        details[0].slot, names[0], details[0].hash, names[1], details[1].hash
+    );
+    print_hash_inputs_diff(names, [&details[0].hash_inputs, &details[1].hash_inputs]);
+    if details[0].hash_inputs.parent_hash != details[1].hash_inputs.parent_hash {
+        println!("  the parent banks already differ, the divergence started earlier");
+    }
+
+    // Both deltas are sorted by pubkey
+    let (mut accounts0, mut accounts1) = (
+        accounts_deltas[0].iter().peekable(),
+        accounts_deltas[1].iter().peekable(),
+    );
+    loop {
+        let order = match (accounts0.peek(), accounts1.peek()) {
+            (None, None) => break,
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (Some((pubkey0, _)), Some((pubkey1, _))) => pubkey0.cmp(pubkey1),
+        };
+        match order {
+            Ordering::Less => {
+                let (pubkey, account) = accounts0.next().unwrap();
+                println!(
+                    "  account {} only stored by {}: {} lamports, {} bytes, owner {}",
+                    pubkey,
+                    names[0],
+                    account.lamports,
+                    account.data.len(),
+                    account.owner
+                );
+            }
+            Ordering::Greater => {
+                let (pubkey, account) = accounts1.next().unwrap();
+                println!(
+                    "  account {} only stored by {}: {} lamports, {} bytes, owner {}",
+                    pubkey,
+                    names[1],
+                    account.lamports,
+                    account.data.len(),
+                    account.owner
+                );
+            }
+            Ordering::Equal => {
+                let (pubkey, account0) = accounts0.next().unwrap();
+                let (_, account1) = accounts1.next().unwrap();
+                if account0 != account1 {
+                    print_account_diff(names, pubkey, [account0, account1]);
+                }
+            }
+        }
+    }
+}
+
+/// Replay both sources, comparing every slot at or after `starting_slot` that both replayed.
+/// Reports the first slot whose bank hashes differ and returns whether all slots matched.
+///
+/// Only the hashes are compared during replay, the accounts stored in the first differing slot
+/// are collected by replaying both sources again up to it
+pub fn diff_bank_hashes(
+    sources: [ReplaySource; 2],
+    process_options: ProcessOptions,
+    wal_recovery_mode: Option<BlockstoreRecoveryMode>,
+    starting_slot: Slot,
+) -> Result<bool, String> {
+    let names = [sources[0].name, sources[1].name];
+    // Slots before either source starts replaying cannot be compared
+    let starting_slot = starting_slot
+        .max(sources[0].first_replayed_slot())
+        .max(sources[1].first_replayed_slot());
+    info!("Comparing bank hashes from slot {}", starting_slot);
+
+    let (sender, receiver) = channel();
+    let replays = vec![
+        replay(
+            0,
+            sources[0].clone(),
+            process_options.clone(),
+            wal_recovery_mode.clone(),
+            starting_slot,
+            sender.clone(),
+        ),
+        replay(
+            1,
+            sources[1].clone(),
+            process_options.clone(),
+            wal_recovery_mode.clone(),
+            starting_slot,
+            sender,
+        ),
+    ];
+
+    // Slots replayed by only one side so far
+    let mut pending: [HashMap<Slot, SlotDetails>; 2] = [HashMap::new(), HashMap::new()];
+    let mut first_mismatch: Option<[SlotDetails; 2]> = None;
+    let mut compared_slots = 0;
+    let mut mismatched_slots = 0;
+    let mut result = Ok(());
+    let mut done = 0;
+    while done < 2 {
+        match receiver.recv() {
+            Ok(ReplayEvent::Frozen(index, details)) => {
+                let other = match pending[1 - index].remove(&details.slot) {
+                    Some(other) => other,
+                    None => {
+                        pending[index].insert(details.slot, details);
+                        continue;
+                    }
+                };
+                let pair = if index == 0 {
+                    [details, other]
+                } else {
+                    [other, details]
+                };
+                compared_slots += 1;
+                if pair[0].hash != pair[1].hash {
+                    mismatched_slots += 1;
+                    warn!("Slot {} hashes differ", pair[0].slot);
+                    if first_mismatch
+                        .as_ref()
+                        .map(|first_mismatch| pair[0].slot < first_mismatch[0].slot)
+                        .unwrap_or(true)
+                    {
+                        first_mismatch = Some(pair);
+                    }
+                }
+            }
+            Ok(ReplayEvent::Done(_, replay_result)) => {
+                done += 1;
+                result = result.and(replay_result);
+            }
+            Err(_) => break,
+        }
+    }
+    for replay in replays {
+        replay.join().unwrap();
+    }
+    result?;
+
+    for (name, pending) in names.iter().zip(pending.iter()) {
+        if !pending.is_empty() {
+            println!(
+                "{} slots were only replayed by {}, lowest {}",
+                pending.len(),
+                name,
+                pending.keys().min().unwrap()
+            );
+        }
+    }
+    println!(
+        "{} slots compared, {} differ",
+        compared_slots, mismatched_slots
+    );
+    let [details0, details1] = match first_mismatch {
+        Some(first_mismatch) => first_mismatch,
+        None => return Ok(true),
+    };
+
+    info!(
+        "Replaying again to slot {} to compare the accounts stored in it",
+        details0.slot
+    );
+    let [source0, source1] = sources;
+    let replays = vec![
+        replay_accounts_delta(
+            0,
+            source0,
+            process_options.clone(),
+            wal_recovery_mode.clone(),
+            details0.slot,
+        ),
+        replay_accounts_delta(
+            1,
+            source1,
+            process_options,
+            wal_recovery_mode,
+            details0.slot,
+        ),
+    ];
+    let accounts_deltas = replays
+        .into_iter()
+        .map(|replay| replay.join().unwrap())
+        .collect::<Result<Vec<_>, _>>()?;
+    print_slot_diff(
+        names,
+        [&details0, &details1],
+        [&accounts_deltas[0], &accounts_deltas[1]],
+    );
+    Ok(false)
+}
@@ -89,12 +89,19 @@ async fn confirm(signature: &Signature, verbose: bool) -> Result<(), Box<dyn std
                     "\nTransaction executed in slot {}:",
                     confirmed_transaction.slot
                 );
-                println_transaction(
-                    &confirmed_transaction.transaction.transaction,
-                    &confirmed_transaction.transaction.meta.map(|m| m.into()),
-                    "  ",
-                    None,
-                );
+                let transaction = &confirmed_transaction.transaction.transaction;
+                match transaction.clone().into_legacy_transaction() {
+                    Some(legacy_transaction) => println_transaction(
+                        &legacy_transaction,
+                        &confirmed_transaction.transaction.meta.map(|m| m.into()),
+                        "  ",
+                        None,
+                    ),
+                    None => println!(
+                        "  Unsupported transaction version: {}",
+                        transaction.message.version().unwrap_or_default()
+                    ),
+                }
             }
             Ok(None) => println!("Finalized transaction details not available"),
             Err(err) => println!("Unable to get finalized transaction details: {}", err),
@@ -169,12 +176,19 @@ pub async fn transaction_history(
                                     );
                                 }
                                 Some(transaction_with_meta) => {
-                                    println_transaction(
-                                        &transaction_with_meta.transaction,
-                                        &transaction_with_meta.meta.clone().map(|m| m.into()),
-                                        "  ",
-                                        None,
-                                    );
+                                    let transaction = &transaction_with_meta.transaction;
+                                    match transaction.clone().into_legacy_transaction() {
+                                        Some(legacy_transaction) => println_transaction(
+                                            &legacy_transaction,
+                                            &transaction_with_meta.meta.clone().map(|m| m.into()),
+                                            "  ",
+                                            None,
+                                        ),
+                                        None => println!(
+                                            "  Unsupported transaction version: {}",
+                                            transaction.message.version().unwrap_or_default()
+                                        ),
+                                    }
                                 }
                             }
                             break;
@@ -0,0 +1,775 @@
+/// The `export` subcommand
+use clap::{value_t, value_t_or_exit, App, Arg, ArgMatches, SubCommand};
+use log::*;
+use parquet::{
+    basic::Compression,
+    column::writer::ColumnWriter,
+    data_type::ByteArray,
+    errors::ParquetError,
+    file::{
+        properties::WriterProperties,
+        writer::{FileWriter, SerializedFileWriter},
+    },
+    schema::{parser::parse_message_type, types::Type},
+};
+use serde::{Deserialize, Serialize};
+use solana_clap_utils::{
+    input_parsers::pubkeys_of,
+    input_validators::{is_slot, is_valid_pubkey},
+};
+use solana_ledger::{blockstore::Blockstore, blockstore_db::AccessType};
+use solana_sdk::{
+    clock::{Slot, UnixTimestamp},
+    pubkey::Pubkey,
+};
+use solana_transaction_status::{
+    ConfirmedBlock, EncodedTransaction, Reward, TransactionWithStatusMeta, UiTransactionEncoding,
+    UiTransactionStatusMeta,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    process::exit,
+    rc::Rc,
+};
+
+const CHECKPOINT_FILE: &str = "checkpoint.json";
+
+// Parquet rows are buffered and written to a new file of each table, along with a checkpoint,
+// once this many are pending
+const PARQUET_ROWS_PER_CHECKPOINT: usize = 100_000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ExportFormat {
+    Json,
+    Csv,
+    Parquet,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ColumnType {
+    Integer,
+    Text,
+}
+
+const BLOCK_COLUMNS: &[(&str, ColumnType)] = &[
+    ("slot", ColumnType::Integer),
+    ("parent_slot", ColumnType::Integer),
+    ("blockhash", ColumnType::Text),
+    ("previous_blockhash", ColumnType::Text),
+    ("block_time", ColumnType::Integer),
+    ("transaction_count", ColumnType::Integer),
+];
+
+const TRANSACTION_COLUMNS: &[(&str, ColumnType)] = &[
+    ("slot", ColumnType::Integer),
+    ("block_time", ColumnType::Integer),
+    ("index", ColumnType::Integer),
+    ("signature", ColumnType::Text),
+    ("err", ColumnType::Text),
+    ("fee", ColumnType::Integer),
+    ("program_ids", ColumnType::Text),
+    ("account_keys", ColumnType::Text),
+    ("pre_balances", ColumnType::Text),
+    ("post_balances", ColumnType::Text),
+    ("log_messages", ColumnType::Text),
+];
+
+const REWARD_COLUMNS: &[(&str, ColumnType)] = &[
+    ("slot", ColumnType::Integer),
+    ("pubkey", ColumnType::Text),
+    ("lamports", ColumnType::Integer),
+    ("post_balance", ColumnType::Integer),
+    ("reward_type", ColumnType::Text),
+];
+
+impl ExportFormat {
+    fn name(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Parquet => "parquet",
+        }
+    }
+
+    /// Output files, or directories of Parquet files, and their columns
+    fn tables(&self) -> Vec<(&'static str, &'static [(&'static str, ColumnType)])> {
+        match self {
+            ExportFormat::Json => vec![("blocks.ndjson", &[]), ("transactions.ndjson", &[])],
+            ExportFormat::Csv => vec![
+                ("blocks.csv", BLOCK_COLUMNS),
+                ("transactions.csv", TRANSACTION_COLUMNS),
+                ("rewards.csv", REWARD_COLUMNS),
+            ],
+            ExportFormat::Parquet => vec![
+                ("blocks", BLOCK_COLUMNS),
+                ("transactions", TRANSACTION_COLUMNS),
+                ("rewards", REWARD_COLUMNS),
+            ],
+        }
+    }
+}
+
+/// A field of a CSV or Parquet record, empty when `None`
+#[derive(Clone, Debug, PartialEq)]
+enum Field {
+    Integer(Option<i64>),
+    Text(Option<String>),
+}
+
+impl Field {
+    fn is_empty(&self) -> bool {
+        matches!(self, Field::Integer(None) | Field::Text(None))
+    }
+
+    fn to_csv(&self) -> String {
+        match self {
+            Field::Integer(value) => value.map(|value| value.to_string()).unwrap_or_default(),
+            Field::Text(value) => value.clone().unwrap_or_default(),
+        }
+    }
+}
+
+/// Progress of an export, saved after every block, or every few Parquet files, so that an
+/// interrupted export resumes from the last saved block
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Checkpoint {
+    format: String,
+    program_ids: BTreeSet<String>,
+    slot: Slot,
+    file_lengths: BTreeMap<String, u64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BlockRecord<'a> {
+    slot: Slot,
+    parent_slot: Slot,
+    blockhash: &'a str,
+    previous_blockhash: &'a str,
+    block_time: Option<UnixTimestamp>,
+    transaction_count: usize,
+    rewards: &'a [Reward],
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TransactionRecord {
+    slot: Slot,
+    block_time: Option<UnixTimestamp>,
+    index: usize,
+    signature: String,
+    transaction: EncodedTransaction,
+    meta: Option<UiTransactionStatusMeta>,
+}
+
+/// An output file, opened for appending
+struct Table {
+    name: &'static str,
+    writer: BufWriter<File>,
+}
+
+impl Table {
+    fn open(
+        output_dir: &Path,
+        name: &'static str,
+        columns: &[(&str, ColumnType)],
+        length: Option<u64>,
+    ) -> io::Result<Self> {
+        let path = output_dir.join(name);
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        match length {
+            // Drop whatever was written after the checkpoint
+            Some(length) => file.set_len(length)?,
+            None if file.metadata()?.len() > 0 => {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("{} exists but has no checkpoint", path.display()),
+                ));
+            }
+            None => {}
+        }
+        let mut table = Self {
+            name,
+            writer: BufWriter::new(file),
+        };
+        if length.is_none() && !columns.is_empty() {
+            let header: Vec<_> = columns.iter().map(|(column, _)| *column).collect();
+            table.write_csv(&header)?;
+        }
+        Ok(table)
+    }
+
+    fn write_json<T: Serialize>(&mut self, record: &T) -> io::Result<()> {
+        serde_json::to_writer(&mut self.writer, record)?;
+        self.writer.write_all(b"\n")
+    }
+
+    fn write_csv<T: AsRef<[u8]>>(&mut self, fields: &[T]) -> io::Result<()> {
+        let mut writer = csv::WriterBuilder::new().from_writer(&mut self.writer);
+        writer.write_record(fields)?;
+        writer.flush()
+    }
+
+    /// Flush the buffered records, returning the length of the file
+    fn flush(&mut self) -> io::Result<u64> {
+        self.writer.flush()?;
+        Ok(self.writer.get_ref().metadata()?.len())
+    }
+}
+
+/// A directory of Parquet files, a file being added by each checkpoint with the rows written
+/// since the previous one
+struct ParquetTable {
+    name: &'static str,
+    output_dir: PathBuf,
+    schema: Rc<Type>,
+    rows: Vec<Vec<Field>>,
+    // Files written so far and their lengths, by path relative to the output directory
+    files: BTreeMap<String, u64>,
+}
+
+impl ParquetTable {
+    fn open(
+        output_dir: &Path,
+        name: &'static str,
+        columns: &[(&str, ColumnType)],
+        file_lengths: Option<&BTreeMap<String, u64>>,
+    ) -> io::Result<Self> {
+        let dir = output_dir.join(name);
+        fs::create_dir_all(&dir)?;
+        let prefix = format!("{}/", name);
+        let files: BTreeMap<_, _> = file_lengths
+            .map(|file_lengths| {
+                file_lengths
+                    .iter()
+                    .filter(|(file, _)| file.starts_with(&prefix))
+                    .map(|(file, length)| (file.clone(), *length))
+                    .collect()
+            })
+            .unwrap_or_default();
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            let file_name = path.file_name().unwrap().to_string_lossy();
+            if files.contains_key(&format!("{}{}", prefix, file_name)) {
+                continue;
+            }
+            if file_lengths.is_none() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("{} exists but has no checkpoint", path.display()),
+                ));
+            }
+            // Drop whatever was written after the checkpoint
+            fs::remove_file(&path)?;
+        }
+
+        let fields: String = columns
+            .iter()
+            .map(|(column, column_type)| match column_type {
+                ColumnType::Integer => format!("OPTIONAL INT64 {};", column),
+                ColumnType::Text => format!("OPTIONAL BYTE_ARRAY {} (UTF8);", column),
+            })
+            .collect();
+        let schema = parse_message_type(&format!("message {} {{ {} }}", name, fields))
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+        Ok(Self {
+            name,
+            output_dir: output_dir.to_path_buf(),
+            schema: Rc::new(schema),
+            rows: vec![],
+            files,
+        })
+    }
+
+    fn write_row(&mut self, fields: Vec<Field>) {
+        self.rows.push(fields);
+    }
+
+    /// Write the buffered rows to a new file, returning the files of the table and their
+    /// lengths
+    fn flush(&mut self) -> io::Result<&BTreeMap<String, u64>> {
+        if !self.rows.is_empty() {
+            let file = format!("{}/part-{:05}.parquet", self.name, self.files.len());
+            let path = self.output_dir.join(&file);
+            self.write_file(&path)
+                .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+            self.files.insert(file, fs::metadata(&path)?.len());
+            self.rows.clear();
+        }
+        Ok(&self.files)
+    }
+
+    fn write_file(&self, path: &Path) -> Result<(), ParquetError> {
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build();
+        let mut writer = SerializedFileWriter::new(
+            File::create(path)?,
+            self.schema.clone(),
+            Rc::new(properties),
+        )?;
+        let mut row_group = writer.next_row_group()?;
+        let mut index = 0;
+        while let Some(mut column) = row_group.next_column()? {
+            let fields = self.rows.iter().map(|row| &row[index]);
+            let definition_levels: Vec<i16> = fields
+                .clone()
+                .map(|field| if field.is_empty() { 0 } else { 1 })
+                .collect();
+            match &mut column {
+                ColumnWriter::Int64ColumnWriter(column_writer) => {
+                    let values: Vec<_> = fields
+                        .filter_map(|field| match field {
+                            Field::Integer(value) => *value,
+                            Field::Text(_) => None,
+                        })
+                        .collect();
+                    column_writer.write_batch(&values, Some(&definition_levels), None)?;
+                }
+                ColumnWriter::ByteArrayColumnWriter(column_writer) => {
+                    let values: Vec<_> = fields
+                        .filter_map(|field| match field {
+                            Field::Text(value) => value.as_deref().map(ByteArray::from),
+                            Field::Integer(_) => None,
+                        })
+                        .collect();
+                    column_writer.write_batch(&values, Some(&definition_levels), None)?;
+                }
+                _ => unreachable!("Exported columns are integers or text"),
+            }
+            row_group.close_column(column)?;
+            index += 1;
+        }
+        writer.close_row_group(row_group)?;
+        writer.close()?;
+        Ok(())
+    }
+}
+
+/// Whether a transaction invokes one of `program_ids`, directly or through its inner
+/// instructions
+fn invokes_any(transaction: &TransactionWithStatusMeta, program_ids: &HashSet<Pubkey>) -> bool {
+    let account_keys = transaction.account_keys();
+    let is_invoked = |program_id_index: u8| {
+        account_keys
+            .get(program_id_index as usize)
+            .map(|program_id| program_ids.contains(program_id))
+            .unwrap_or(false)
+    };
+    transaction
+        .transaction
+        .message
+        .instructions()
+        .iter()
+        .any(|instruction| is_invoked(instruction.program_id_index))
+        || transaction
+            .meta
+            .as_ref()
+            .and_then(|meta| meta.inner_instructions.as_ref())
+            .map(|inner_instructions| {
+                inner_instructions
+                    .iter()
+                    .flat_map(|inner_instructions| inner_instructions.instructions.iter())
+                    .any(|instruction| is_invoked(instruction.program_id_index))
+            })
+            .unwrap_or(false)
+}
+
+fn join<T: ToString>(values: impl IntoIterator<Item = T>) -> String {
+    values
+        .into_iter()
+        .map(|value| value.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+struct Exporter {
+    format: ExportFormat,
+    program_ids: HashSet<Pubkey>,
+    output_dir: PathBuf,
+    tables: Vec<Table>,
+    parquet_tables: Vec<ParquetTable>,
+}
+
+impl Exporter {
+    /// Open the output files in `output_dir`, resuming the export recorded in its checkpoint if
+    /// any.  Returns the exporter and the last exported slot
+    fn open(
+        output_dir: &Path,
+        format: ExportFormat,
+        program_ids: HashSet<Pubkey>,
+    ) -> Result<(Self, Option<Slot>), Box<dyn std::error::Error>> {
+        fs::create_dir_all(output_dir)?;
+        let checkpoint = match fs::read(output_dir.join(CHECKPOINT_FILE)) {
+            Ok(checkpoint) => Some(serde_json::from_slice::<Checkpoint>(&checkpoint)?),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+            Err(err) => return Err(err.into()),
+        };
+        if let Some(checkpoint) = &checkpoint {
+            let exported_program_ids: BTreeSet<_> = program_ids
+                .iter()
+                .map(|program_id| program_id.to_string())
+                .collect();
+            if checkpoint.format != format.name() || checkpoint.program_ids != exported_program_ids
+            {
+                return Err(format!(
+                    "{} holds a {} export of programs {:?}, which cannot be resumed with \
+                     different options",
+                    output_dir.display(),
+                    checkpoint.format,
+                    checkpoint.program_ids
+                )
+                .into());
+            }
+        }
+
+        let mut tables = vec![];
+        let mut parquet_tables = vec![];
+        for (name, columns) in format.tables() {
+            let file_lengths = checkpoint
+                .as_ref()
+                .map(|checkpoint| &checkpoint.file_lengths);
+            if format == ExportFormat::Parquet {
+                parquet_tables.push(ParquetTable::open(output_dir, name, columns, file_lengths)?);
+            } else {
+                let length =
+                    file_lengths.map(|file_lengths| file_lengths.get(name).copied().unwrap_or(0));
+                tables.push(Table::open(output_dir, name, columns, length)?);
+            }
+        }
+        let exporter = Self {
+            format,
+            program_ids,
+            output_dir: output_dir.to_path_buf(),
+            tables,
+            parquet_tables,
+        };
+        Ok((exporter, checkpoint.map(|checkpoint| checkpoint.slot)))
+    }
+
+    /// Write a block and the transactions it holds that pass the program filter, returning the
+    /// number of transactions written
+    fn export_block(&mut self, slot: Slot, block: ConfirmedBlock) -> io::Result<usize> {
+        let block_time = block.block_time;
+        let transactions: Vec<_> = block
+            .transactions
+            .into_iter()
+            .enumerate()
+            .filter(|(_, transaction)| {
+                self.program_ids.is_empty() || invokes_any(transaction, &self.program_ids)
+            })
+            .collect();
+        let exported = transactions.len();
+
+        match self.format {
+            ExportFormat::Json => {
+                self.tables[0].write_json(&BlockRecord {
+                    slot,
+                    parent_slot: block.parent_slot,
+                    blockhash: &block.blockhash,
+                    previous_blockhash: &block.previous_blockhash,
+                    block_time,
+                    transaction_count: exported,
+                    rewards: &block.rewards,
+                })?;
+                for (index, TransactionWithStatusMeta { transaction, meta }) in transactions {
+                    let loaded_addresses = meta
+                        .as_ref()
+                        .map(|meta| meta.loaded_addresses.clone())
+                        .unwrap_or_default();
+                    self.tables[1].write_json(&TransactionRecord {
+                        slot,
+                        block_time,
+                        index,
+                        signature: transaction.signatures[0].to_string(),
+                        transaction: EncodedTransaction::encode_versioned(
+                            transaction,
+                            UiTransactionEncoding::Json,
+                            &loaded_addresses,
+                        ),
+                        meta: meta.map(|meta| meta.into()),
+                    })?;
+                }
+            }
+            ExportFormat::Csv | ExportFormat::Parquet => {
+                self.write_record(
+                    0,
+                    vec![
+                        Field::Integer(Some(slot as i64)),
+                        Field::Integer(Some(block.parent_slot as i64)),
+                        Field::Text(Some(block.blockhash)),
+                        Field::Text(Some(block.previous_blockhash)),
+                        Field::Integer(block_time),
+                        Field::Integer(Some(exported as i64)),
+                    ],
+                )?;
+                for (index, transaction_with_meta) in transactions {
+                    let account_keys = transaction_with_meta.account_keys();
+                    let TransactionWithStatusMeta { transaction, meta } = transaction_with_meta;
+                    let program_ids: BTreeSet<_> = transaction
+                        .message
+                        .instructions()
+                        .iter()
+                        .filter_map(|instruction| {
+                            account_keys.get(instruction.program_id_index as usize)
+                        })
+                        .collect();
+                    let mut record = vec![
+                        Field::Integer(Some(slot as i64)),
+                        Field::Integer(block_time),
+                        Field::Integer(Some(index as i64)),
+                        Field::Text(Some(transaction.signatures[0].to_string())),
+                    ];
+                    match meta {
+                        Some(meta) => record.extend(vec![
+                            Field::Text(
+                                meta.status
+                                    .err()
+                                    .map(|err| serde_json::to_string(&err).unwrap()),
+                            ),
+                            Field::Integer(Some(meta.fee as i64)),
+                            Field::Text(Some(join(program_ids))),
+                            Field::Text(Some(join(account_keys.iter()))),
+                            Field::Text(Some(join(meta.pre_balances))),
+                            Field::Text(Some(join(meta.post_balances))),
+                            Field::Text(
+                                meta.log_messages
+                                    .map(|log_messages| log_messages.join("\n")),
+                            ),
+                        ]),
+                        None => record.extend(vec![
+                            Field::Text(None),
+                            Field::Integer(None),
+                            Field::Text(Some(join(program_ids))),
+                            Field::Text(Some(join(account_keys.iter()))),
+                            Field::Text(None),
+                            Field::Text(None),
+                            Field::Text(None),
+                        ]),
+                    }
+                    self.write_record(1, record)?;
+                }
+                for reward in block.rewards {
+                    self.write_record(
+                        2,
+                        vec![
+                            Field::Integer(Some(slot as i64)),
+                            Field::Text(Some(reward.pubkey)),
+                            Field::Integer(Some(reward.lamports)),
+                            Field::Integer(Some(reward.post_balance as i64)),
+                            Field::Text(
+                                reward
+                                    .reward_type
+                                    .map(|reward_type| reward_type.to_string()),
+                            ),
+                        ],
+                    )?;
+                }
+            }
+        }
+        Ok(exported)
+    }
+
+    /// Write a CSV record, or buffer a Parquet row, to the table at `index`
+    fn write_record(&mut self, index: usize, fields: Vec<Field>) -> io::Result<()> {
+        if self.format == ExportFormat::Parquet {
+            self.parquet_tables[index].write_row(fields);
+            Ok(())
+        } else {
+            let fields: Vec<_> = fields.iter().map(Field::to_csv).collect();
+            self.tables[index].write_csv(&fields)
+        }
+    }
+
+    /// Whether to checkpoint after the last exported block, Parquet files being written by
+    /// checkpoints only once enough rows are buffered
+    fn is_checkpoint_due(&self) -> bool {
+        self.format != ExportFormat::Parquet
+            || self
+                .parquet_tables
+                .iter()
+                .map(|table| table.rows.len())
+                .sum::<usize>()
+                >= PARQUET_ROWS_PER_CHECKPOINT
+    }
+
+    /// Flush the output files and record that every block up to `slot` was exported
+    fn checkpoint(&mut self, slot: Slot) -> io::Result<()> {
+        let mut file_lengths = BTreeMap::new();
+        for table in self.tables.iter_mut() {
+            file_lengths.insert(table.name.to_string(), table.flush()?);
+        }
+        for table in self.parquet_tables.iter_mut() {
+            file_lengths.extend(table.flush()?.clone());
+        }
+        let checkpoint = Checkpoint {
+            format: self.format.name().to_string(),
+            program_ids: self
+                .program_ids
+                .iter()
+                .map(|program_id| program_id.to_string())
+                .collect(),
+            slot,
+            file_lengths,
+        };
+        let path = self.output_dir.join(CHECKPOINT_FILE);
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, serde_json::to_vec(&checkpoint)?)?;
+        fs::rename(&tmp_path, &path)
+    }
+}
+
+fn export(
+    blockstore: &Blockstore,
+    output_dir: &Path,
+    format: ExportFormat,
+    program_ids: HashSet<Pubkey>,
+    starting_slot: Option<Slot>,
+    ending_slot: Option<Slot>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (mut exporter, last_exported_slot) = Exporter::open(output_dir, format, program_ids)?;
+    let mut starting_slot = match starting_slot {
+        Some(starting_slot) => starting_slot,
+        None => blockstore.get_first_available_block()?,
+    };
+    if let Some(last_exported_slot) = last_exported_slot {
+        info!("Resuming export after slot {}", last_exported_slot);
+        starting_slot = starting_slot.max(last_exported_slot + 1);
+    }
+    let ending_slot = ending_slot.unwrap_or_else(|| blockstore.max_root());
+
+    let mut blocks = 0;
+    let mut transactions = 0;
+    let mut unsaved_slot = None;
+    for slot in blockstore
+        .rooted_slot_iterator(starting_slot)?
+        .take_while(|slot| *slot <= ending_slot)
+    {
+        let block = blockstore
+            .get_confirmed_block(slot, false)
+            .map_err(|err| format!("Failed to read block {}: {:?}", slot, err))?;
+        transactions += exporter.export_block(slot, block)?;
+        if exporter.is_checkpoint_due() {
+            exporter.checkpoint(slot)?;
+            unsaved_slot = None;
+        } else {
+            unsaved_slot = Some(slot);
+        }
+        blocks += 1;
+        if blocks % 1000 == 0 {
+            info!("Exported {} blocks, through slot {}", blocks, slot);
+        }
+    }
+    if let Some(slot) = unsaved_slot {
+        exporter.checkpoint(slot)?;
+    }
+    println!(
+        "Exported {} blocks and {} transactions to {}",
+        blocks,
+        transactions,
+        output_dir.display()
+    );
+    Ok(())
+}
+
+pub trait ExportSubCommand {
+    fn export_subcommand(self) -> Self;
+}
+
+impl ExportSubCommand for App<'_, '_> {
+    fn export_subcommand(self) -> Self {
+        self.subcommand(
+            SubCommand::with_name("export")
+                .about(
+                    "Export rooted blocks and their transactions, with status metadata, rewards \
+                     and block times, for analysis",
+                )
+                .arg(
+                    Arg::with_name("output_dir")
+                        .index(1)
+                        .value_name("DIR")
+                        .takes_value(true)
+                        .required(true)
+                        .help(
+                            "Output directory. An export interrupted or limited to an ending \
+                             slot resumes from the checkpoint it keeps in this directory",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .takes_value(true)
+                        .possible_values(&["json", "csv", "parquet"])
+                        .default_value("json")
+                        .help(
+                            "json: blocks.ndjson and transactions.ndjson, one JSON record per \
+                             line; csv: blocks.csv, transactions.csv and rewards.csv, with a \
+                             column per field; parquet: the columns of the csv format, in \
+                             Parquet files in the blocks, transactions and rewards directories",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("starting_slot")
+                        .long("starting-slot")
+                        .validator(is_slot)
+                        .value_name("SLOT")
+                        .takes_value(true)
+                        .help("Start exporting at this slot [default: first available slot]"),
+                )
+                .arg(
+                    Arg::with_name("ending_slot")
+                        .long("ending-slot")
+                        .validator(is_slot)
+                        .value_name("SLOT")
+                        .takes_value(true)
+                        .help("Stop exporting at this slot [default: latest root]"),
+                )
+                .arg(
+                    Arg::with_name("program_id")
+                        .long("program-id")
+                        .validator(is_valid_pubkey)
+                        .value_name("PROGRAM_ID")
+                        .takes_value(true)
+                        .multiple(true)
+                        .help(
+                            "Only export the transactions that invoke this program, may be \
+                             specified multiple times",
+                        ),
+                ),
+        )
+    }
+}
+
+pub fn export_process_command(ledger_path: &Path, matches: &ArgMatches<'_>) {
+    let output_dir = value_t_or_exit!(matches, "output_dir", PathBuf);
+    let format = match matches.value_of("format") {
+        Some("csv") => ExportFormat::Csv,
+        Some("parquet") => ExportFormat::Parquet,
+        _ => ExportFormat::Json,
+    };
+    let program_ids = pubkeys_of(matches, "program_id")
+        .unwrap_or_default()
+        .into_iter()
+        .collect();
+    let starting_slot = value_t!(matches, "starting_slot", Slot).ok();
+    let ending_slot = value_t!(matches, "ending_slot", Slot).ok();
+    let blockstore = crate::open_blockstore(ledger_path, AccessType::TryPrimaryThenSecondary, None);
+
+    export(
+        &blockstore,
+        &output_dir,
+        format,
+        program_ids,
+        starting_slot,
+        ending_slot,
+    )
+    .unwrap_or_else(|err| {
+        eprintln!("Export failed: {}", err);
+        exit(1);
+    });
+}
@@ -24,7 +24,7 @@ use solana_ledger::{
     shred::Shred,
 };
 use solana_runtime::{
-    bank::{Bank, RewardCalculationEvent},
+    bank::{Bank, BpfTraceCallback, RewardCalculationEvent},
     bank_forks::{ArchiveFormat, BankForks, SnapshotConfig},
     hardened_unpack::{open_genesis_config, MAX_GENESIS_ARCHIVE_UNPACKED_SIZE},
     snapshot_utils,
@@ -39,10 +39,13 @@ use solana_sdk::{
     hash::Hash,
     inflation::Inflation,
     native_token::{lamports_to_sol, sol_to_lamports, Sol},
+    process_instruction::BpfTrace,
     pubkey::Pubkey,
     rent::Rent,
     shred_version::compute_shred_version,
+    signature::Signature,
     system_program,
+    transaction::Transaction,
 };
 use solana_stake_program::stake_state::{self, PointValue, StakeState};
 use solana_vote_program::{
@@ -61,8 +64,14 @@ use std::{
     sync::Arc,
 };
 
+mod bank_hash_diff;
+use bank_hash_diff::*;
 mod bigtable;
 use bigtable::*;
+mod export;
+use export::*;
+mod program_overrides;
+use program_overrides::*;
 
 #[derive(PartialEq)]
 enum LedgerOutputMethod {
@@ -118,12 +127,18 @@ fn output_entry(
                     })
                     .map(|transaction_status| transaction_status.into());
 
-                solana_cli_output::display::println_transaction(
-                    &transaction,
-                    &transaction_status,
-                    "      ",
-                    None,
-                );
+                match transaction.clone().into_legacy_transaction() {
+                    Some(transaction) => solana_cli_output::display::println_transaction(
+                        &transaction,
+                        &transaction_status,
+                        "      ",
+                        None,
+                    ),
+                    None => println!(
+                        "      Unsupported transaction version: {}",
+                        transaction.message.version().unwrap_or_default()
+                    ),
+                }
             }
         }
         LedgerOutputMethod::Json => {
@@ -184,9 +199,9 @@ fn output_slot(
             transactions += entry.transactions.len();
             hashes += entry.num_hashes;
             for transaction in &entry.transactions {
-                for instruction in &transaction.message().instructions {
-                    let program_id =
-                        transaction.message().account_keys[instruction.program_id_index as usize];
+                for instruction in transaction.message.instructions() {
+                    let program_id = transaction.message.static_account_keys()
+                        [instruction.program_id_index as usize];
                     *program_ids.entry(program_id).or_insert(0) += 1;
                 }
             }
@@ -657,7 +672,11 @@ fn load_bank_forks(
     wal_recovery_mode: Option<BlockstoreRecoveryMode>,
     snapshot_archive_path: Option<PathBuf>,
 ) -> bank_forks_utils::LoadResult {
-    let blockstore = open_blockstore(&ledger_path, access_type, wal_recovery_mode);
+    let blockstore = Arc::new(open_blockstore(
+        &ledger_path,
+        access_type,
+        wal_recovery_mode,
+    ));
     let snapshot_path = ledger_path.join(if blockstore.is_primary_access() {
         "snapshot"
     } else {
@@ -695,15 +714,30 @@ fn load_bank_forks(
         vec![non_primary_accounts_path]
     };
 
-    bank_forks_utils::load(
+    let (transaction_status_comparer, transaction_status_sender) = if process_options
+        .program_overrides
+        .is_empty()
+    {
+        (None, None)
+    } else {
+        let (comparer, sender) =
+            TransactionStatusComparer::new(blockstore.clone(), &process_options.program_overrides);
+        (Some(comparer), Some(sender))
+    };
+
+    let result = bank_forks_utils::load(
         &genesis_config,
         &blockstore,
         account_paths,
         None,
         snapshot_config.as_ref(),
         process_options,
-        None,
-    )
+        transaction_status_sender,
+    );
+    if let Some(transaction_status_comparer) = transaction_status_comparer {
+        transaction_status_comparer.join();
+    }
+    result
 }
 
 fn open_genesis_config_by(ledger_path: &Path, matches: &ArgMatches<'_>) -> GenesisConfig {
@@ -716,6 +750,52 @@ fn assert_capitalization(bank: &Bank) {
     assert!(bank.calculate_and_verify_capitalization());
 }
 
+/// Writes the compute profile and the instruction trace of each BPF program invoked by the
+/// replayed transactions to `<slot>-<signature>-<invocation>.profile` and `.trace` files
+#[derive(Debug)]
+struct BpfTraceWriter {
+    output_dir: PathBuf,
+    // Only the transactions with one of these signatures are traced, when set
+    signatures: Option<HashSet<Signature>>,
+    // Only the transactions referencing one of these programs are traced, when set
+    program_ids: Option<HashSet<Pubkey>>,
+}
+
+impl BpfTraceWriter {
+    fn write(&self, name: &str, contents: &str) {
+        let path = self.output_dir.join(name);
+        fs::write(&path, contents).unwrap_or_else(|err| {
+            eprintln!("Unable to write {}: {}", path.display(), err);
+            exit(1);
+        });
+    }
+}
+
+impl BpfTraceCallback for BpfTraceWriter {
+    fn is_traced(&self, _slot: Slot, transaction: &Transaction) -> bool {
+        let signature_matches = self.signatures.as_ref().map_or(true, |signatures| {
+            signatures.contains(&transaction.signatures[0])
+        });
+        let program_matches = self.program_ids.as_ref().map_or(true, |program_ids| {
+            let account_keys = &transaction.message.account_keys;
+            account_keys.iter().any(|key| program_ids.contains(key))
+        });
+        signature_matches && program_matches
+    }
+
+    fn callback(&self, slot: Slot, signature: &Signature, traces: Vec<BpfTrace>) {
+        for (index, trace) in traces.iter().enumerate() {
+            let name = format!("{}-{}-{}", slot, signature, index);
+            let mut profile = String::new();
+            trace.write_profile(&mut profile).unwrap();
+            self.write(&format!("{}.profile", name), &profile);
+            let mut instructions = String::new();
+            trace.write_instructions(&mut instructions).unwrap();
+            self.write(&format!("{}.trace", name), &instructions);
+        }
+    }
+}
+
 #[allow(clippy::cognitive_complexity)]
 fn main() {
     // Ignore SIGUSR1 to prevent long-running calls being killed by logrotate
@@ -776,6 +856,7 @@ fn main() {
         .long("allow-dead-slots")
         .takes_value(false)
         .help("Output dead slots as well");
+    let program_override_arg = program_override_arg();
     let default_genesis_archive_unpacked_size = MAX_GENESIS_ARCHIVE_UNPACKED_SIZE.to_string();
     let max_genesis_archive_unpacked_size_arg = Arg::with_name("max_genesis_archive_unpacked_size")
         .long("max-genesis-archive-unpacked-size")
@@ -853,6 +934,7 @@ fn main() {
                 .help("Return information in specified output format, currently only available for bigtable subcommands"),
         )
         .bigtable_subcommand()
+        .export_subcommand()
         .subcommand(
             SubCommand::with_name("print")
             .about("Print the ledger")
@@ -991,6 +1073,57 @@ fn main() {
             .about("Prints the hash of the working bank after reading the ledger")
             .arg(&max_genesis_archive_unpacked_size_arg)
         )
+        .subcommand(
+            SubCommand::with_name("bank-hash-diff")
+            .about("Replay the ledger alongside another ledger, or alongside itself from a \
+                    different starting point, and report the first slot whose bank hashes \
+                    differ along with the bank hash inputs and accounts that differ")
+            .arg(&no_snapshot_arg)
+            .arg(&hard_forks_arg)
+            .arg(&no_accounts_db_caching_arg)
+            .arg(&bpf_jit_arg)
+            .arg(&max_genesis_archive_unpacked_size_arg)
+            .arg(
+                Arg::with_name("starting_slot")
+                    .long("starting-slot")
+                    .value_name("SLOT")
+                    .validator(is_slot)
+                    .takes_value(true)
+                    .default_value("0")
+                    .help("Only compare the slots starting at this slot"),
+            )
+            .arg(
+                Arg::with_name("ending_slot")
+                    .long("ending-slot")
+                    .value_name("SLOT")
+                    .validator(is_slot)
+                    .takes_value(true)
+                    .help("Stop replaying at this slot"),
+            )
+            .arg(
+                Arg::with_name("other_ledger_path")
+                    .long("other-ledger")
+                    .value_name("DIR")
+                    .takes_value(true)
+                    .help("Ledger to compare with [default: the ledger itself, in which case \
+                           the two replays must start differently, see --no-snapshot and \
+                           --other-no-snapshot]"),
+            )
+            .arg(
+                Arg::with_name("other_snapshot_archive_path")
+                    .long("other-snapshot-archive-path")
+                    .value_name("DIR")
+                    .takes_value(true)
+                    .help("Start the other ledger from the latest snapshot archive in DIR \
+                           [default: the other ledger directory]"),
+            )
+            .arg(
+                Arg::with_name("other_no_snapshot")
+                    .long("other-no-snapshot")
+                    .takes_value(false)
+                    .help("Replay the other ledger from genesis"),
+            )
+        )
         .subcommand(
             SubCommand::with_name("bounds")
             .about("Print lowest and highest non-empty slots. Note that there may be empty slots within the bounds")
@@ -1030,6 +1163,37 @@ fn main() {
                     .takes_value(false)
                     .help("After verifying the ledger, print some information about the account stores."),
             )
+            .arg(
+                Arg::with_name("bpf_trace_dir")
+                    .long("bpf-trace-dir")
+                    .value_name("DIR")
+                    .takes_value(true)
+                    .help("Write the compute profile by function and the instruction trace of \
+                           every BPF program invoked by the replayed transactions to DIR. \
+                           Tracing slows down replay considerably"),
+            )
+            .arg(
+                Arg::with_name("bpf_trace_signature")
+                    .long("bpf-trace-signature")
+                    .value_name("SIGNATURE")
+                    .takes_value(true)
+                    .multiple(true)
+                    .requires("bpf_trace_dir")
+                    .help("Only trace the transactions with these signatures, the programs of \
+                           other transactions run untraced"),
+            )
+            .arg(
+                Arg::with_name("bpf_trace_program")
+                    .long("bpf-trace-program")
+                    .value_name("PUBKEY")
+                    .takes_value(true)
+                    .validator(is_pubkey)
+                    .multiple(true)
+                    .requires("bpf_trace_dir")
+                    .help("Only trace the transactions referencing these programs, the programs \
+                           of other transactions run untraced"),
+            )
+            .arg(&program_override_arg)
         ).subcommand(
             SubCommand::with_name("graph")
             .about("Create a Graphviz rendering of the ledger")
@@ -1058,6 +1222,7 @@ fn main() {
             .arg(&hard_forks_arg)
             .arg(&max_genesis_archive_unpacked_size_arg)
             .arg(&snapshot_version_arg)
+            .arg(&program_override_arg)
             .arg(
                 Arg::with_name("snapshot_slot")
                     .index(1)
@@ -1343,6 +1508,7 @@ fn main() {
 
     match matches.subcommand() {
         ("bigtable", Some(arg_matches)) => bigtable_process_command(&ledger_path, arg_matches),
+        ("export", Some(arg_matches)) => export_process_command(&ledger_path, arg_matches),
         ("print", Some(arg_matches)) => {
             let starting_slot = value_t_or_exit!(arg_matches, "starting_slot", Slot);
             let ending_slot = value_t!(arg_matches, "ending_slot", Slot).unwrap_or(Slot::MAX);
@@ -1518,6 +1684,52 @@ fn main() {
                 }
             }
         }
+        ("bank-hash-diff", Some(arg_matches)) => {
+            let other_ledger_path = value_t!(arg_matches, "other_ledger_path", PathBuf)
+                .unwrap_or_else(|_| ledger_path.clone());
+            let other_snapshot_archive_path =
+                value_t!(arg_matches, "other_snapshot_archive_path", PathBuf)
+                    .unwrap_or_else(|_| other_ledger_path.clone());
+            let sources = [
+                ReplaySource {
+                    name: "ledger",
+                    genesis_config: open_genesis_config_by(&ledger_path, arg_matches),
+                    snapshot_archive_path: if arg_matches.is_present("no_snapshot") {
+                        None
+                    } else {
+                        Some(snapshot_archive_path.unwrap_or_else(|| ledger_path.clone()))
+                    },
+                    ledger_path: ledger_path.clone(),
+                },
+                ReplaySource {
+                    name: "other",
+                    genesis_config: open_genesis_config_by(&other_ledger_path, arg_matches),
+                    snapshot_archive_path: if arg_matches.is_present("other_no_snapshot") {
+                        None
+                    } else {
+                        Some(other_snapshot_archive_path)
+                    },
+                    ledger_path: other_ledger_path,
+                },
+            ];
+            let process_options = ProcessOptions {
+                dev_halt_at_slot: value_t!(arg_matches, "ending_slot", Slot).ok(),
+                new_hard_forks: hardforks_of(arg_matches, "hard_forks"),
+                poh_verify: false,
+                bpf_jit: arg_matches.is_present("bpf_jit"),
+                accounts_db_caching_enabled: !arg_matches.is_present("no_accounts_db_caching"),
+                ..ProcessOptions::default()
+            };
+            let starting_slot = value_t_or_exit!(arg_matches, "starting_slot", Slot);
+            match diff_bank_hashes(sources, process_options, wal_recovery_mode, starting_slot) {
+                Ok(true) => println!("No bank hash differs"),
+                Ok(false) => exit(1),
+                Err(err) => {
+                    eprintln!("{}", err);
+                    exit(1);
+                }
+            }
+        }
         ("slot", Some(arg_matches)) => {
             let slots = values_t_or_exit!(arg_matches, "slots", Slot);
             let allow_dead_slots = arg_matches.is_present("allow_dead_slots");
@@ -1648,6 +1860,25 @@ fn main() {
             }
         }
         ("verify", Some(arg_matches)) => {
+            let bpf_trace_callback =
+                value_t!(arg_matches, "bpf_trace_dir", PathBuf)
+                    .ok()
+                    .map(|output_dir| {
+                        fs::create_dir_all(&output_dir).unwrap_or_else(|err| {
+                            eprintln!("Unable to create {}: {}", output_dir.display(), err);
+                            exit(1);
+                        });
+                        Arc::new(BpfTraceWriter {
+                            output_dir,
+                            signatures: arg_matches.values_of("bpf_trace_signature").map(|_| {
+                                values_t_or_exit!(arg_matches, "bpf_trace_signature", Signature)
+                                    .into_iter()
+                                    .collect()
+                            }),
+                            program_ids: pubkeys_of(arg_matches, "bpf_trace_program")
+                                .map(|program_ids| program_ids.into_iter().collect()),
+                        }) as Arc<dyn BpfTraceCallback + Send + Sync>
+                    });
             let process_options = ProcessOptions {
                 dev_halt_at_slot: value_t!(arg_matches, "halt_at_slot", Slot).ok(),
                 new_hard_forks: hardforks_of(arg_matches, "hard_forks"),
@@ -1655,6 +1886,8 @@ fn main() {
                 bpf_jit: arg_matches.is_present("bpf_jit"),
                 accounts_db_caching_enabled: !arg_matches.is_present("no_accounts_db_caching"),
                 allow_dead_slots: arg_matches.is_present("allow_dead_slots"),
+                bpf_trace_callback,
+                program_overrides: program_overrides_of(arg_matches, "program_override"),
                 ..ProcessOptions::default()
             };
             let print_accounts_stats = arg_matches.is_present("print_accounts_stats");
@@ -1762,10 +1995,19 @@ fn main() {
                             exit(1)
                         })
                     });
+            let program_overrides = program_overrides_of(arg_matches, "program_override");
+            if !program_overrides.is_empty() {
+                eprintln!(
+                    "Warning: replaying with program overrides, the snapshot will hold accounts, \
+                     a bank hash and a capitalization that the cluster never agreed on. Do not \
+                     start a validator from it"
+                );
+            }
             let process_options = ProcessOptions {
                 dev_halt_at_slot: Some(snapshot_slot),
                 new_hard_forks,
                 poh_verify: false,
+                program_overrides: program_overrides.clone(),
                 ..ProcessOptions::default()
             };
             let genesis_config = open_genesis_config_by(&ledger_path, arg_matches);
@@ -1969,6 +2211,18 @@ fn main() {
                         bank.hash(),
                         archive_file.display(),
                     );
+                    if !program_overrides.is_empty() {
+                        let marker_file =
+                            mark_overridden_snapshot(&archive_file, &program_overrides)
+                                .unwrap_or_else(|err| {
+                                    eprintln!("Unable to mark the snapshot as overridden: {}", err);
+                                    exit(1);
+                                });
+                        eprintln!(
+                            "Warning: the snapshot was created with program overrides, listed in {}",
+                            marker_file.display()
+                        );
+                    }
                     println!(
                         "Shred version: {}",
                         compute_shred_version(
@@ -0,0 +1,415 @@
+/// Replay with program overrides: the transactions invoking a BPF program are re-executed
+/// against a locally built ELF of it, and the outcome of every replayed transaction is compared
+/// with the transaction status recorded in the ledger
+use clap::{Arg, ArgMatches};
+use crossbeam_channel::{unbounded, Receiver};
+use itertools::izip;
+use solana_ledger::{
+    blockstore::Blockstore,
+    blockstore_processor::{TransactionStatusBatch, TransactionStatusSender},
+};
+use solana_runtime::{
+    bank::{Bank, InnerInstructionsList, TransactionLogMessages},
+    transaction_utils::OrderedIterator,
+};
+use solana_sdk::{
+    hash::hash, message::v0::LoadedAddresses, pubkey::Pubkey, transaction::Transaction,
+};
+use solana_transaction_status::{InnerInstructions, TransactionStatusMeta};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Debug,
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::exit,
+    sync::Arc,
+    thread::{self, JoinHandle},
+};
+
+pub fn program_override_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("program_override")
+        .long("program-override")
+        .value_name("ADDRESS BPF_PROGRAM.SO")
+        .takes_value(true)
+        .number_of_values(2)
+        .multiple(true)
+        .help(
+            "Replay the ledger with BPF_PROGRAM.SO in place of the program deployed at ADDRESS, \
+             printing the logs of the transactions that invoke it, and how the result of every \
+             transaction differs from its recorded status",
+        )
+}
+
+pub fn program_overrides_of(matches: &ArgMatches<'_>, name: &str) -> HashMap<Pubkey, Vec<u8>> {
+    let mut program_overrides = HashMap::new();
+    if let Some(values) = matches.values_of(name) {
+        let values: Vec<&str> = values.collect();
+        for address_program in values.chunks(2) {
+            if let [address, program] = address_program {
+                let address = address.parse::<Pubkey>().unwrap_or_else(|err| {
+                    eprintln!("Error: invalid address {}: {}", address, err);
+                    exit(1);
+                });
+                let elf = fs::read(Path::new(program)).unwrap_or_else(|err| {
+                    eprintln!("Error: unable to read {}: {}", program, err);
+                    exit(1);
+                });
+                program_overrides.insert(address, elf);
+            }
+        }
+    }
+    program_overrides
+}
+
+/// Mark a snapshot created with program overrides by writing the overridden addresses and the
+/// hashes of the programs that replaced them next to the archive, as
+/// `<ARCHIVE>.program-overrides`
+pub fn mark_overridden_snapshot(
+    archive_file: &Path,
+    program_overrides: &HashMap<Pubkey, Vec<u8>>,
+) -> io::Result<PathBuf> {
+    let mut marker_file = archive_file.as_os_str().to_os_string();
+    marker_file.push(".program-overrides");
+    let marker_file = PathBuf::from(marker_file);
+    let mut marker = File::create(&marker_file)?;
+    for (address, elf) in program_overrides {
+        writeln!(marker, "{} {}", address, hash(elf))?;
+    }
+    Ok(marker_file)
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct ComparisonStats {
+    transactions: usize,
+    overridden_transactions: usize,
+    differing_transactions: usize,
+}
+
+/// Receives the outcome of the replayed transactions and compares it with their recorded status
+pub struct TransactionStatusComparer {
+    thread_hdl: JoinHandle<ComparisonStats>,
+}
+
+impl TransactionStatusComparer {
+    pub fn new(
+        blockstore: Arc<Blockstore>,
+        program_overrides: &HashMap<Pubkey, Vec<u8>>,
+    ) -> (Self, TransactionStatusSender) {
+        let (sender, receiver) = unbounded();
+        let program_ids = program_overrides.keys().cloned().collect();
+        let thread_hdl = thread::Builder::new()
+            .name("solTxStatusDiff".to_string())
+            .spawn(move || Self::compare(&receiver, &blockstore, &program_ids))
+            .unwrap();
+        (
+            Self { thread_hdl },
+            TransactionStatusSender {
+                sender,
+                enable_cpi_and_log_storage: true,
+            },
+        )
+    }
+
+    fn compare(
+        receiver: &Receiver<TransactionStatusBatch>,
+        blockstore: &Blockstore,
+        program_ids: &HashSet<Pubkey>,
+    ) -> ComparisonStats {
+        let mut stats = ComparisonStats::default();
+        for TransactionStatusBatch {
+            bank,
+            transactions,
+            versioned_transactions,
+            iteration_order,
+            statuses,
+            balances,
+            inner_instructions,
+            transaction_logs,
+            transaction_return_data,
+            ..
+        } in receiver.iter()
+        {
+            let inner_instructions_iter: Box<dyn Iterator<Item = Option<InnerInstructionsList>>> =
+                match inner_instructions {
+                    Some(inner_instructions) => Box::new(inner_instructions.into_iter()),
+                    None => Box::new(std::iter::repeat_with(|| None)),
+                };
+            let transaction_logs_iter: Box<dyn Iterator<Item = TransactionLogMessages>> =
+                match transaction_logs {
+                    Some(transaction_logs) => Box::new(transaction_logs.into_iter()),
+                    None => Box::new(std::iter::repeat_with(Vec::new)),
+                };
+            for (
+                (index, transaction),
+                (status, _nonce_rollback),
+                pre_balances,
+                post_balances,
+                inner_instructions,
+                log_messages,
+                return_data,
+            ) in izip!(
+                OrderedIterator::new(&transactions, iteration_order.as_deref()),
+                statuses,
+                balances.pre_balances,
+                balances.post_balances,
+                inner_instructions_iter,
+                transaction_logs_iter,
+                transaction_return_data
+            ) {
+                if transaction.signatures.is_empty() {
+                    continue;
+                }
+                stats.transactions += 1;
+                let is_overridden = transaction
+                    .message
+                    .account_keys
+                    .iter()
+                    .any(|key| program_ids.contains(key));
+                let inner_instructions = inner_instructions.map(|inner_instructions| {
+                    inner_instructions
+                        .into_iter()
+                        .enumerate()
+                        .map(|(index, instructions)| InnerInstructions {
+                            index: index as u8,
+                            instructions,
+                        })
+                        .filter(|inner_instructions| !inner_instructions.instructions.is_empty())
+                        .collect()
+                });
+                let mut replayed = TransactionStatusMeta {
+                    status,
+                    fee: 0,
+                    pre_balances,
+                    post_balances,
+                    inner_instructions,
+                    log_messages: Some(log_messages),
+                    pre_token_balances: None,
+                    post_token_balances: None,
+                    return_data,
+                    loaded_addresses: LoadedAddresses::default(),
+                };
+                // Recorded statuses index accounts in the order the transaction was signed with
+                replayed.record_loaded_addresses(transaction, &versioned_transactions[index]);
+                let differences = Self::differences(&bank, blockstore, transaction, &replayed);
+                if !differences.is_empty() {
+                    stats.differing_transactions += 1;
+                }
+                if is_overridden {
+                    stats.overridden_transactions += 1;
+                }
+                if is_overridden || !differences.is_empty() {
+                    println!(
+                        "Slot {} transaction {}:",
+                        bank.slot(),
+                        transaction.signatures[0]
+                    );
+                    if is_overridden {
+                        for log_message in replayed.log_messages.iter().flatten() {
+                            println!("  {}", log_message);
+                        }
+                    }
+                    for difference in differences {
+                        println!("  {}", difference);
+                    }
+                }
+            }
+        }
+        stats
+    }
+
+    /// How the replayed outcome of `transaction` differs from its recorded status
+    fn differences(
+        bank: &Bank,
+        blockstore: &Blockstore,
+        transaction: &Transaction,
+        replayed: &TransactionStatusMeta,
+    ) -> Vec<String> {
+        let recorded =
+            match blockstore.read_transaction_status((transaction.signatures[0], bank.slot())) {
+                Ok(Some(recorded)) => recorded,
+                Ok(None) if !Bank::can_commit(&replayed.status) => return vec![],
+                Ok(None) => return vec!["no status recorded".to_string()],
+                Err(err) => return vec![format!("unable to read the recorded status: {:?}", err)],
+            };
+        let mut differences = vec![];
+        let mut compare = |field: &str, recorded: &dyn Debug, replayed: &dyn Debug| {
+            let (recorded, replayed) = (format!("{:?}", recorded), format!("{:?}", replayed));
+            if recorded != replayed {
+                differences.push(format!(
+                    "{} differs: recorded {}, replayed {}",
+                    field, recorded, replayed
+                ));
+            }
+        };
+        compare("status", &recorded.status, &replayed.status);
+        compare(
+            "pre balances",
+            &recorded.pre_balances,
+            &replayed.pre_balances,
+        );
+        compare(
+            "post balances",
+            &recorded.post_balances,
+            &replayed.post_balances,
+        );
+        // Statuses recorded without CPI recording carry no inner instructions
+        if recorded.inner_instructions.is_some() {
+            compare(
+                "inner instructions",
+                &recorded.inner_instructions,
+                &replayed.inner_instructions,
+            );
+        }
+        compare("return data", &recorded.return_data, &replayed.return_data);
+        differences
+    }
+
+    /// Wait until every replayed transaction is compared, which is once the sender is dropped
+    pub fn join(self) {
+        let stats = self.thread_hdl.join().unwrap();
+        println!(
+            "{} transactions replayed, {} invoked an overridden program, {} differ from their \
+             recorded status",
+            stats.transactions, stats.overridden_transactions, stats.differing_transactions
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_runtime::{bank::TransactionBalancesSet, genesis_utils::create_genesis_config};
+    use solana_sdk::{
+        instruction::{Instruction, InstructionError},
+        signature::Signer,
+        system_transaction,
+        transaction::TransactionError,
+    };
+    use solana_transaction_status::token_balances::TransactionTokenBalancesSet;
+
+    #[test]
+    fn test_compare_recorded_and_replayed_status() {
+        let ledger_path = solana_ledger::get_tmp_ledger_path!();
+        let blockstore = Arc::new(Blockstore::open(&ledger_path).unwrap());
+        let genesis = create_genesis_config(10_000);
+        let bank = Arc::new(Bank::new(&genesis.genesis_config));
+        let program_id = Pubkey::new_unique();
+        let transfer = system_transaction::transfer(
+            &genesis.mint_keypair,
+            &Pubkey::new_unique(),
+            1,
+            bank.last_blockhash(),
+        );
+        let overridden = Transaction::new_signed_with_payer(
+            &[Instruction {
+                program_id,
+                accounts: vec![],
+                data: vec![],
+            }],
+            Some(&genesis.mint_keypair.pubkey()),
+            &[&genesis.mint_keypair],
+            bank.last_blockhash(),
+        );
+
+        // Both transactions were recorded as successful
+        let recorded = TransactionStatusMeta {
+            status: Ok(()),
+            fee: 0,
+            pre_balances: vec![10_000, 0, 1],
+            post_balances: vec![9_999, 1, 1],
+            inner_instructions: Some(vec![]),
+            log_messages: Some(vec![]),
+            pre_token_balances: None,
+            post_token_balances: None,
+            return_data: None,
+            loaded_addresses: LoadedAddresses::default(),
+        };
+        for transaction in &[&transfer, &overridden] {
+            blockstore
+                .write_transaction_status(
+                    bank.slot(),
+                    transaction.signatures[0],
+                    vec![],
+                    vec![],
+                    &recorded,
+                )
+                .unwrap();
+        }
+
+        // The transfer replays as recorded, the overridden program now fails
+        let failure = Err(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(1),
+        ));
+        let program_overrides = vec![(program_id, vec![])].into_iter().collect();
+        let (comparer, sender) =
+            TransactionStatusComparer::new(blockstore.clone(), &program_overrides);
+        sender
+            .sender
+            .send(TransactionStatusBatch {
+                bank: bank.clone(),
+                transactions: vec![transfer.clone(), overridden.clone()],
+                versioned_transactions: vec![transfer.into(), overridden.clone().into()],
+                iteration_order: None,
+                statuses: vec![(Ok(()), None), (failure.clone(), None)],
+                balances: TransactionBalancesSet::new(
+                    vec![recorded.pre_balances.clone(); 2],
+                    vec![
+                        recorded.post_balances.clone(),
+                        recorded.pre_balances.clone(),
+                    ],
+                ),
+                token_balances: TransactionTokenBalancesSet::new(vec![vec![]; 2], vec![vec![]; 2]),
+                inner_instructions: Some(vec![Some(vec![]), Some(vec![])]),
+                transaction_logs: Some(vec![vec![], vec![]]),
+                transaction_return_data: vec![None, None],
+            })
+            .unwrap();
+        drop(sender);
+        assert_eq!(
+            comparer.thread_hdl.join().unwrap(),
+            ComparisonStats {
+                transactions: 2,
+                overridden_transactions: 1,
+                differing_transactions: 1,
+            }
+        );
+
+        let replayed = TransactionStatusMeta {
+            status: failure,
+            post_balances: recorded.pre_balances.clone(),
+            ..recorded
+        };
+        assert_eq!(
+            TransactionStatusComparer::differences(&bank, &blockstore, &overridden, &replayed),
+            vec![
+                "status differs: recorded Ok(()), replayed Err(InstructionError(0, Custom(1)))",
+                "post balances differs: recorded [9999, 1, 1], replayed [10000, 0, 1]",
+            ]
+        );
+
+        drop(blockstore);
+        Blockstore::destroy(&ledger_path).unwrap();
+    }
+
+    #[test]
+    fn test_mark_overridden_snapshot() {
+        let snapshot_dir = tempfile::tempdir().unwrap();
+        let archive_file = snapshot_dir.path().join("snapshot-1-hash.tar.zst");
+        let address = Pubkey::new_unique();
+        let program_overrides = vec![(address, vec![1, 2, 3])].into_iter().collect();
+
+        let marker_file = mark_overridden_snapshot(&archive_file, &program_overrides).unwrap();
+        assert_eq!(
+            marker_file,
+            snapshot_dir
+                .path()
+                .join("snapshot-1-hash.tar.zst.program-overrides")
+        );
+        assert_eq!(
+            fs::read_to_string(marker_file).unwrap(),
+            format!("{} {}\n", address, hash(&[1, 2, 3]))
+        );
+    }
+}
@@ -1,8 +1,34 @@
 use assert_cmd::prelude::*;
-use solana_ledger::create_new_tmp_ledger;
-use solana_ledger::genesis_utils::create_genesis_config;
-use std::process::Command;
-use std::process::Output;
+use parquet::{
+    file::reader::{FileReader, SerializedFileReader},
+    record::{Row, RowAccessor},
+};
+use solana_ledger::{
+    blockstore::{entries_to_test_shreds, Blockstore},
+    create_new_tmp_ledger,
+    entry::{create_ticks, next_entry},
+    genesis_utils::{create_genesis_config, GenesisConfigInfo},
+};
+use solana_sdk::{
+    account::Account,
+    bpf_loader,
+    clock::Slot,
+    hash::Hash,
+    instruction::Instruction,
+    native_token::sol_to_lamports,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    system_transaction,
+    transaction::Transaction,
+};
+use solana_transaction_status::TransactionStatusMeta;
+use std::{
+    fs,
+    io::Write,
+    path::Path,
+    process::{Command, Output},
+};
 
 fn run_ledger_tool(args: &[&str]) -> Output {
     Command::cargo_bin(env!("CARGO_PKG_NAME"))
@@ -47,3 +73,384 @@ fn nominal() {
     assert!(output.status.success());
     assert_eq!(count_newlines(&output.stdout), ticks + meta_lines);
 }
+
+#[test]
+fn verify_bpf_trace_signature() {
+    let GenesisConfigInfo {
+        mut genesis_config,
+        mint_keypair,
+        ..
+    } = create_genesis_config(sol_to_lamports(1.0));
+    let memo_id = Pubkey::new_unique();
+    let memo_program = include_bytes!("../../program-test/src/programs/spl_memo-3.0.0.so");
+    genesis_config.add_account(
+        memo_id,
+        Account {
+            lamports: Rent::default().minimum_balance(memo_program.len()),
+            data: memo_program.to_vec(),
+            owner: bpf_loader::id(),
+            executable: true,
+            rent_epoch: 0,
+        },
+    );
+    let (ledger_path, blockhash) = create_new_tmp_ledger!(&genesis_config);
+    let ledger_path = ledger_path.to_str().unwrap();
+
+    let memo = |data: &[u8]| {
+        Transaction::new_signed_with_payer(
+            &[Instruction {
+                program_id: memo_id,
+                accounts: vec![],
+                data: data.to_vec(),
+            }],
+            Some(&mint_keypair.pubkey()),
+            &[&mint_keypair],
+            blockhash,
+        )
+    };
+    let traced = memo(b"traced");
+    let untraced = memo(b"untraced");
+    let traced_signature = traced.signatures[0].to_string();
+    let mut entries = vec![next_entry(&blockhash, 1, vec![traced])];
+    entries.push(next_entry(&entries[0].hash, 1, vec![untraced]));
+    entries.extend(create_ticks(
+        genesis_config.ticks_per_slot,
+        0,
+        entries.last().unwrap().hash,
+    ));
+    let blockstore = Blockstore::open(Path::new(ledger_path)).unwrap();
+    let shreds = entries_to_test_shreds(entries, 1, 0, true, 0);
+    blockstore.insert_shreds(shreds, None, false).unwrap();
+    drop(blockstore);
+
+    // Only the selected transaction is traced
+    let trace_dir = tempfile::tempdir().unwrap();
+    let trace_dir = trace_dir.path();
+    let output = run_ledger_tool(&[
+        "-l",
+        &ledger_path,
+        "verify",
+        "--bpf-trace-dir",
+        trace_dir.to_str().unwrap(),
+        "--bpf-trace-signature",
+        &traced_signature,
+    ]);
+    assert!(output.status.success());
+    let mut trace_files = fs::read_dir(trace_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect::<Vec<_>>();
+    trace_files.sort();
+    assert_eq!(
+        trace_files,
+        vec![
+            format!("1-{}-0.profile", traced_signature),
+            format!("1-{}-0.trace", traced_signature),
+        ]
+    );
+
+    // Transactions that do not reference the selected programs are not traced
+    let trace_dir = tempfile::tempdir().unwrap();
+    let trace_dir = trace_dir.path();
+    let output = run_ledger_tool(&[
+        "-l",
+        &ledger_path,
+        "verify",
+        "--bpf-trace-dir",
+        trace_dir.to_str().unwrap(),
+        "--bpf-trace-program",
+        &Pubkey::new_unique().to_string(),
+    ]);
+    assert!(output.status.success());
+    assert_eq!(fs::read_dir(trace_dir).unwrap().count(), 0);
+}
+
+#[test]
+fn export() {
+    let genesis_config = create_genesis_config(100).genesis_config;
+    let (ledger_path, _blockhash) = create_new_tmp_ledger!(&genesis_config);
+    let ledger_path = ledger_path.to_str().unwrap();
+    let output_dir = tempfile::tempdir().unwrap();
+    let output_dir = output_dir.path().to_str().unwrap();
+
+    let output = run_ledger_tool(&["-l", &ledger_path, "export", &output_dir]);
+    assert!(output.status.success());
+    let blocks = std::fs::read(format!("{}/blocks.ndjson", output_dir)).unwrap();
+    assert_eq!(count_newlines(&blocks), 1);
+    assert!(std::path::Path::new(&format!("{}/checkpoint.json", output_dir)).exists());
+
+    // Exporting again resumes after the last exported block
+    let output = run_ledger_tool(&["-l", &ledger_path, "export", &output_dir]);
+    assert!(output.status.success());
+    let blocks = std::fs::read(format!("{}/blocks.ndjson", output_dir)).unwrap();
+    assert_eq!(count_newlines(&blocks), 1);
+
+    // A resumed export must use the same options
+    let output = run_ledger_tool(&["-l", &ledger_path, "export", &output_dir, "--format", "csv"]);
+    assert!(!output.status.success());
+}
+
+const EXPORTED_SLOTS: Slot = 3;
+
+/// Create a ledger rooted through `EXPORTED_SLOTS`, each slot after genesis holding a transfer
+/// and a transaction that invokes `program_id`
+fn create_ledger_with_transactions(program_id: &Pubkey) -> String {
+    let genesis_config = create_genesis_config(100).genesis_config;
+    let (ledger_path, blockhash) = create_new_tmp_ledger!(&genesis_config);
+    let blockstore = Blockstore::open(&ledger_path).unwrap();
+
+    let payer = Keypair::new();
+    let mut last_hash = blockhash;
+    for slot in 1..=EXPORTED_SLOTS {
+        let transfer = system_transaction::transfer(&payer, &Pubkey::new_unique(), 1, blockhash);
+        let invoke = Transaction::new_signed_with_payer(
+            &[Instruction::new(*program_id, &(), vec![])],
+            Some(&payer.pubkey()),
+            &[&payer],
+            blockhash,
+        );
+        for transaction in [&transfer, &invoke].iter() {
+            let status = TransactionStatusMeta {
+                fee: 5000,
+                pre_balances: vec![10, 0, 1],
+                post_balances: vec![4, 1, 1],
+                log_messages: Some(vec![format!("slot {}", slot)]),
+                ..TransactionStatusMeta::default()
+            };
+            blockstore
+                .write_transaction_status(
+                    slot,
+                    transaction.signatures[0],
+                    vec![&payer.pubkey()],
+                    vec![],
+                    &status,
+                )
+                .unwrap();
+        }
+
+        let mut entries = create_ticks(1, 1, last_hash);
+        entries.push(next_entry(
+            &entries.last().unwrap().hash,
+            1,
+            vec![transfer, invoke],
+        ));
+        last_hash = entries.last().unwrap().hash;
+        let shreds = entries_to_test_shreds(entries, slot, slot - 1, true, 0);
+        blockstore.insert_shreds(shreds, None, false).unwrap();
+    }
+    blockstore
+        .set_roots(&(1..=EXPORTED_SLOTS).collect::<Vec<_>>())
+        .unwrap();
+    ledger_path.to_str().unwrap().to_string()
+}
+
+fn read_json_lines(path: &Path) -> Vec<serde_json::Value> {
+    fs::read_to_string(path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+fn exported_slots(output_dir: &Path) -> Vec<u64> {
+    read_json_lines(&output_dir.join("blocks.ndjson"))
+        .iter()
+        .map(|block| block["slot"].as_u64().unwrap())
+        .collect()
+}
+
+fn read_csv(path: &Path) -> Vec<csv::StringRecord> {
+    csv::Reader::from_path(path)
+        .unwrap()
+        .records()
+        .collect::<Result<_, _>>()
+        .unwrap()
+}
+
+#[test]
+fn export_csv() {
+    let ledger_path = create_ledger_with_transactions(&Pubkey::new_unique());
+    let output_dir = tempfile::tempdir().unwrap();
+    let output_path = output_dir.path().to_str().unwrap();
+
+    let output = run_ledger_tool(&["-l", &ledger_path, "export", output_path, "--format", "csv"]);
+    assert!(output.status.success());
+
+    let blocks = read_csv(&output_dir.path().join("blocks.csv"));
+    let slots: Vec<_> = blocks.iter().map(|block| block[0].to_string()).collect();
+    assert_eq!(slots, vec!["0", "1", "2", "3"]);
+    assert_eq!(&blocks[1][5], "2");
+
+    let transactions = read_csv(&output_dir.path().join("transactions.csv"));
+    assert_eq!(transactions.len(), 2 * EXPORTED_SLOTS as usize);
+    let transaction = &transactions[0];
+    assert_eq!(&transaction[0], "1");
+    assert_eq!(&transaction[5], "5000");
+    assert_eq!(&transaction[8], "10 0 1");
+    assert_eq!(&transaction[9], "4 1 1");
+    assert_eq!(&transaction[10], "slot 1");
+}
+
+/// Read the rows of the Parquet files of an exported table
+fn read_parquet(output_dir: &Path, table: &str) -> Vec<Row> {
+    let mut files: Vec<_> = fs::read_dir(output_dir.join(table))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    files.sort();
+    files
+        .into_iter()
+        .flat_map(|file| {
+            let reader = SerializedFileReader::new(fs::File::open(file).unwrap()).unwrap();
+            reader.get_row_iter(None).unwrap().collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+#[test]
+fn export_parquet() {
+    let program_id = Pubkey::new_unique();
+    let program_id_arg = program_id.to_string();
+    let ledger_path = create_ledger_with_transactions(&program_id);
+    let output_dir = tempfile::tempdir().unwrap();
+    let output_path = output_dir.path().to_str().unwrap();
+    let export = |args: &[&str]| {
+        let mut export_args = vec![
+            "-l",
+            &ledger_path,
+            "export",
+            output_path,
+            "--format",
+            "parquet",
+            "--program-id",
+            &program_id_arg,
+        ];
+        export_args.extend(args);
+        run_ledger_tool(&export_args)
+    };
+    let exported_slots = || {
+        read_parquet(output_dir.path(), "blocks")
+            .iter()
+            .map(|block| block.get_long(0).unwrap())
+            .collect::<Vec<_>>()
+    };
+
+    assert!(export(&["--ending-slot", "1"]).status.success());
+    assert_eq!(exported_slots(), vec![0, 1]);
+
+    // Files written after the checkpoint by an interrupted export are dropped on resume
+    fs::write(
+        output_dir.path().join("blocks/part-00001.parquet"),
+        b"partial",
+    )
+    .unwrap();
+
+    assert!(export(&[]).status.success());
+    assert_eq!(exported_slots(), vec![0, 1, 2, 3]);
+    let blocks = read_parquet(output_dir.path(), "blocks");
+    assert_eq!(blocks[1].get_long(5).unwrap(), 1);
+
+    // Only the transactions that invoke the program are exported, with the columns of the
+    // csv format
+    let transactions = read_parquet(output_dir.path(), "transactions");
+    assert_eq!(transactions.len(), EXPORTED_SLOTS as usize);
+    let transaction = &transactions[0];
+    assert_eq!(transaction.get_long(0).unwrap(), 1);
+    assert_eq!(transaction.get_long(5).unwrap(), 5000);
+    assert_eq!(transaction.get_string(6).unwrap(), &program_id_arg);
+    assert_eq!(transaction.get_string(8).unwrap(), "10 0 1");
+    assert_eq!(transaction.get_string(9).unwrap(), "4 1 1");
+    assert_eq!(transaction.get_string(10).unwrap(), "slot 1");
+}
+
+#[test]
+fn export_program_id() {
+    let program_id = Pubkey::new_unique();
+    let ledger_path = create_ledger_with_transactions(&program_id);
+    let output_dir = tempfile::tempdir().unwrap();
+    let output_path = output_dir.path().to_str().unwrap();
+
+    let output = run_ledger_tool(&[
+        "-l",
+        &ledger_path,
+        "export",
+        output_path,
+        "--program-id",
+        &program_id.to_string(),
+    ]);
+    assert!(output.status.success());
+
+    // Every block is exported, with only the transactions that invoke the program
+    assert_eq!(exported_slots(output_dir.path()), vec![0, 1, 2, 3]);
+    let transactions = read_json_lines(&output_dir.path().join("transactions.ndjson"));
+    assert_eq!(transactions.len(), EXPORTED_SLOTS as usize);
+    for transaction in transactions {
+        let message = &transaction["transaction"]["message"];
+        let program_id_index = message["instructions"][0]["programIdIndex"]
+            .as_u64()
+            .unwrap();
+        assert_eq!(
+            message["accountKeys"][program_id_index as usize],
+            program_id.to_string()
+        );
+    }
+}
+
+#[test]
+fn export_slot_range() {
+    let ledger_path = create_ledger_with_transactions(&Pubkey::new_unique());
+    let output_dir = tempfile::tempdir().unwrap();
+    let output_path = output_dir.path().to_str().unwrap();
+
+    let output = run_ledger_tool(&[
+        "-l",
+        &ledger_path,
+        "export",
+        output_path,
+        "--starting-slot",
+        "2",
+        "--ending-slot",
+        "2",
+    ]);
+    assert!(output.status.success());
+
+    assert_eq!(exported_slots(output_dir.path()), vec![2]);
+    let transactions = read_json_lines(&output_dir.path().join("transactions.ndjson"));
+    assert_eq!(transactions.len(), 2);
+    assert!(transactions
+        .iter()
+        .all(|transaction| transaction["slot"] == 2));
+}
+
+#[test]
+fn export_resume() {
+    let ledger_path = create_ledger_with_transactions(&Pubkey::new_unique());
+    let output_dir = tempfile::tempdir().unwrap();
+    let output_path = output_dir.path().to_str().unwrap();
+
+    let output = run_ledger_tool(&[
+        "-l",
+        &ledger_path,
+        "export",
+        output_path,
+        "--ending-slot",
+        "1",
+    ]);
+    assert!(output.status.success());
+    assert_eq!(exported_slots(output_dir.path()), vec![0, 1]);
+
+    // Records written after the checkpoint by an interrupted export are dropped on resume
+    for name in &["blocks.ndjson", "transactions.ndjson"] {
+        let mut file = fs::OpenOptions::new()
+            .append(true)
+            .open(output_dir.path().join(name))
+            .unwrap();
+        file.write_all(b"{\"slot\":2,\"partial").unwrap();
+    }
+
+    let output = run_ledger_tool(&["-l", &ledger_path, "export", output_path]);
+    assert!(output.status.success());
+    assert_eq!(exported_slots(output_dir.path()), vec![0, 1, 2, 3]);
+    let transactions = read_json_lines(&output_dir.path().join("transactions.ndjson"));
+    assert_eq!(transactions.len(), 2 * EXPORTED_SLOTS as usize);
+}
@@ -5,7 +5,7 @@ pub use crate::{blockstore_db::BlockstoreError, blockstore_meta::SlotMeta};
 use crate::{
     blockstore_db::{
         columns as cf, AccessType, BlockstoreRecoveryMode, Column, Database, IteratorDirection,
-        IteratorMode, LedgerColumn, Result, WriteBatch,
+        IteratorMode, LedgerColumn, RawIterator, Result, WriteBatch,
     },
     blockstore_meta::*,
     entry::{create_ticks, Entry},
@@ -20,7 +20,6 @@ use rayon::{
     iter::{IntoParallelRefIterator, ParallelIterator},
     ThreadPool,
 };
-use rocksdb::DBRawIterator;
 use solana_measure::measure::Measure;
 use solana_metrics::{datapoint_debug, datapoint_error};
 use solana_rayon_threadlimit::get_thread_count;
@@ -33,7 +32,7 @@ use solana_sdk::{
     sanitize::Sanitize,
     signature::{Keypair, Signature, Signer},
     timing::timestamp,
-    transaction::Transaction,
+    transaction::VersionedTransaction,
 };
 use solana_storage_proto::StoredExtendedRewards;
 use solana_transaction_status::{
@@ -90,6 +89,47 @@ pub enum PurgeType {
     PrimaryIndex,
 }
 
+/// The column families of the blockstore, grouped by what they hold, so that each group can be
+/// retained for its own length of time
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ColumnGroup {
+    /// Shreds and the metadata needed to assemble and repair them
+    Shreds,
+    /// Rewards, block times and performance samples
+    BlockMetadata,
+    /// Transaction statuses and the address signatures index. The roots that make them readable
+    /// are purged once neither the shreds nor the transaction statuses of their slots are kept
+    TransactionStatus,
+}
+
+impl ColumnGroup {
+    pub const ALL: [ColumnGroup; 3] = [
+        ColumnGroup::Shreds,
+        ColumnGroup::BlockMetadata,
+        ColumnGroup::TransactionStatus,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColumnGroup::Shreds => "shreds",
+            ColumnGroup::BlockMetadata => "block-metadata",
+            ColumnGroup::TransactionStatus => "transaction-status",
+        }
+    }
+}
+
+impl std::str::FromStr for ColumnGroup {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        ColumnGroup::ALL
+            .iter()
+            .find(|group| group.name() == s)
+            .copied()
+            .ok_or_else(|| format!("Unknown column group: {}", s))
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum InsertDataShredError {
     Exists,
@@ -139,6 +179,9 @@ pub struct Blockstore {
     pub new_shreds_signals: Vec<SyncSender<bool>>,
     pub completed_slots_senders: Vec<SyncSender<Vec<Slot>>>,
     pub lowest_cleanup_slot: Arc<RwLock<u64>>,
+    // The last slot whose transaction statuses were purged, as stored in the
+    // PurgedTransactionStatusSlot column
+    highest_purged_transaction_status_slot: RwLock<Option<Slot>>,
     no_compaction: bool,
 }
 
@@ -277,7 +320,34 @@ impl Blockstore {
         let mut measure = Measure::start("open");
         info!("Opening database at {:?}", blockstore_path);
         let db = Database::open(&blockstore_path, access_type, recovery_mode)?;
+        let blockstore = Self::open_with_database(db)?;
+        measure.stop();
+        info!("{:?} {}", blockstore_path, measure);
+        Ok(blockstore)
+    }
+
+    /// Opens a blockstore held in memory, that starts out empty and is lost once dropped
+    pub fn open_in_memory() -> Result<Blockstore> {
+        Self::open_with_database(Database::open_in_memory())
+    }
+
+    /// Opens a blockstore held in memory, that starts out as a copy of the blockstore at
+    /// `ledger_path`. Nothing is written back to `ledger_path`
+    pub fn open_in_memory_from(
+        ledger_path: &Path,
+        recovery_mode: Option<BlockstoreRecoveryMode>,
+    ) -> Result<Blockstore> {
+        let blockstore_path = ledger_path.join(BLOCKSTORE_DIRECTORY);
+        let mut measure = Measure::start("open");
+        info!("Loading database at {:?} in memory", blockstore_path);
+        let db = Database::open_in_memory_from(&blockstore_path, recovery_mode)?;
+        let blockstore = Self::open_with_database(db)?;
+        measure.stop();
+        info!("{:?} {}", blockstore_path, measure);
+        Ok(blockstore)
+    }
 
+    fn open_with_database(db: Database) -> Result<Blockstore> {
         // Create the metadata column family
         let meta_cf = db.column();
 
@@ -327,8 +397,9 @@ impl Blockstore {
             })
             .unwrap_or(0);
 
-        measure.stop();
-        info!("{:?} {}", blockstore_path, measure);
+        let highest_purged_transaction_status_slot =
+            db.get::<cf::PurgedTransactionStatusSlot>(0)?;
+
         let blockstore = Blockstore {
             db,
             meta_cf,
@@ -351,6 +422,9 @@ impl Blockstore {
             insert_shreds_lock: Arc::new(Mutex::new(())),
             last_root,
             lowest_cleanup_slot: Arc::new(RwLock::new(0)),
+            highest_purged_transaction_status_slot: RwLock::new(
+                highest_purged_transaction_status_slot,
+            ),
             no_compaction: false,
         };
         if initialize_transaction_status_index {
@@ -364,23 +438,35 @@ impl Blockstore {
         recovery_mode: Option<BlockstoreRecoveryMode>,
         enforce_ulimit_nofile: bool,
     ) -> Result<BlockstoreSignals> {
-        let mut blockstore = Self::open_with_access_type(
+        let blockstore = Self::open_with_access_type(
             ledger_path,
             AccessType::PrimaryOnly,
             recovery_mode,
             enforce_ulimit_nofile,
         )?;
+        Ok(Self::with_signals(blockstore))
+    }
+
+    pub fn open_in_memory_with_signal(
+        ledger_path: &Path,
+        recovery_mode: Option<BlockstoreRecoveryMode>,
+    ) -> Result<BlockstoreSignals> {
+        let blockstore = Self::open_in_memory_from(ledger_path, recovery_mode)?;
+        Ok(Self::with_signals(blockstore))
+    }
+
+    fn with_signals(mut blockstore: Blockstore) -> BlockstoreSignals {
         let (ledger_signal_sender, ledger_signal_receiver) = sync_channel(1);
         let (completed_slots_sender, completed_slots_receiver) =
             sync_channel(MAX_COMPLETED_SLOTS_IN_CHANNEL);
         blockstore.new_shreds_signals = vec![ledger_signal_sender];
         blockstore.completed_slots_senders = vec![completed_slots_sender];
 
-        Ok(BlockstoreSignals {
+        BlockstoreSignals {
             blockstore,
             ledger_signal_receiver,
             completed_slots_receiver,
-        })
+        }
     }
 
     pub fn add_tree(
@@ -1560,7 +1646,7 @@ impl Blockstore {
     // indexes in the ledger in the range [start_index, end_index)
     // for the slot with the specified slot
     fn find_missing_indexes<C>(
-        db_iterator: &mut DBRawIterator,
+        db_iterator: &mut dyn RawIterator,
         slot: Slot,
         first_timestamp: u64,
         start_index: u64,
@@ -1643,12 +1729,9 @@ impl Blockstore {
         end_index: u64,
         max_missing: usize,
     ) -> Vec<u64> {
-        if let Ok(mut db_iterator) = self
-            .db
-            .raw_iterator_cf(self.db.cf_handle::<cf::ShredData>())
-        {
+        if let Ok(mut db_iterator) = self.db.raw_iterator_cf::<cf::ShredData>() {
             Self::find_missing_indexes::<cf::ShredData>(
-                &mut db_iterator,
+                db_iterator.as_mut(),
                 slot,
                 first_timestamp,
                 start_index,
@@ -1666,12 +1749,13 @@ impl Blockstore {
             ("method", "get_block_time".to_string(), String)
         );
         let lowest_cleanup_slot = self.lowest_cleanup_slot.read().unwrap();
-        // lowest_cleanup_slot is the last slot that was not cleaned up by
-        // LedgerCleanupService
-        if *lowest_cleanup_slot > 0 && *lowest_cleanup_slot >= slot {
+        let block_time = self.blocktime_cf.get(slot)?;
+        // lowest_cleanup_slot is the last slot whose shreds were cleaned up by
+        // LedgerCleanupService, block times may be retained for longer
+        if block_time.is_none() && *lowest_cleanup_slot > 0 && *lowest_cleanup_slot >= slot {
             return Err(BlockstoreError::SlotCleanedUp);
         }
-        self.blocktime_cf.get(slot)
+        Ok(block_time)
     }
 
     pub fn cache_block_time(&self, slot: Slot, timestamp: UnixTimestamp) -> Result<()> {
@@ -1695,20 +1779,44 @@ impl Blockstore {
             "blockstore-rpc-api",
             ("method", "get_confirmed_block".to_string(), String)
         );
+        self.get_block(slot, require_previous_blockhash, true)
+    }
+
+    /// Returns the block of a full slot whether or not the slot has been rooted
+    pub fn get_complete_block(
+        &self,
+        slot: Slot,
+        require_previous_blockhash: bool,
+    ) -> Result<ConfirmedBlock> {
+        datapoint_info!(
+            "blockstore-rpc-api",
+            ("method", "get_complete_block".to_string(), String)
+        );
+        self.get_block(slot, require_previous_blockhash, false)
+    }
+
+    fn get_block(
+        &self,
+        slot: Slot,
+        require_previous_blockhash: bool,
+        require_root: bool,
+    ) -> Result<ConfirmedBlock> {
         let lowest_cleanup_slot = self.lowest_cleanup_slot.read().unwrap();
         // lowest_cleanup_slot is the last slot that was not cleaned up by
         // LedgerCleanupService
         if *lowest_cleanup_slot > 0 && *lowest_cleanup_slot >= slot {
             return Err(BlockstoreError::SlotCleanedUp);
         }
-        if self.is_root(slot) {
+        if !require_root || self.is_root(slot) {
             let slot_meta_cf = self.db.column::<cf::SlotMeta>();
             let slot_meta = match slot_meta_cf.get(slot)? {
-                Some(slot_meta) => slot_meta,
-                None => {
+                Some(slot_meta) if require_root || slot_meta.is_full() => slot_meta,
+                Some(_) => return Err(BlockstoreError::SlotUnavailable),
+                None if require_root => {
                     info!("SlotMeta not found for rooted slot {}", slot);
                     return Err(BlockstoreError::SlotCleanedUp);
                 }
+                None => return Err(BlockstoreError::SlotUnavailable),
             };
 
             let slot_entries = self.get_slot_entries(slot, 0)?;
@@ -1720,7 +1828,7 @@ impl Blockstore {
                     .map(|transaction| {
                         if let Err(err) = transaction.sanitize() {
                             warn!(
-                                "Blockstore::get_confirmed_block sanitize failed: {:?}, \
+                                "Blockstore::get_block sanitize failed: {:?}, \
                                 slot: {:?}, \
                                 {:?}",
                                 err, slot, transaction,
@@ -1762,13 +1870,17 @@ impl Blockstore {
                 return Ok(block);
             }
         }
-        Err(BlockstoreError::SlotNotRooted)
+        if require_root {
+            Err(BlockstoreError::SlotNotRooted)
+        } else {
+            Err(BlockstoreError::SlotUnavailable)
+        }
     }
 
     fn map_transactions_to_statuses<'a>(
         &self,
         slot: Slot,
-        iterator: impl Iterator<Item = Transaction> + 'a,
+        iterator: impl Iterator<Item = VersionedTransaction> + 'a,
     ) -> Vec<TransactionWithStatusMeta> {
         iterator
             .map(|transaction| {
@@ -1959,9 +2071,15 @@ impl Blockstore {
             ("method", "get_confirmed_transaction".to_string(), String)
         );
         if let Some((slot, status)) = self.get_transaction_status(signature)? {
-            let transaction = self
-                .find_transaction_in_slot(slot, signature)?
-                .ok_or(BlockstoreError::TransactionStatusSlotMismatch)?; // Should not happen
+            let transaction = match self.find_transaction_in_slot(slot, signature) {
+                Ok(Some(transaction)) => transaction,
+                // Transaction statuses may be kept for longer than the shreds holding the
+                // transactions themselves
+                Err(BlockstoreError::SlotCleanedUp) => return Ok(None),
+                Ok(None) if self.meta(slot)?.is_none() => return Ok(None),
+                Ok(None) => return Err(BlockstoreError::TransactionStatusSlotMismatch), // Should not happen
+                Err(err) => return Err(err),
+            };
             let block_time = self.get_block_time(slot)?;
             Ok(Some(ConfirmedTransaction {
                 slot,
@@ -1980,7 +2098,7 @@ impl Blockstore {
         &self,
         slot: Slot,
         signature: Signature,
-    ) -> Result<Option<Transaction>> {
+    ) -> Result<Option<VersionedTransaction>> {
         let slot_entries = self.get_slot_entries(slot, 0)?;
         Ok(slot_entries
             .iter()
@@ -2080,31 +2198,13 @@ impl Blockstore {
                 match transaction_status {
                     None => return Ok(vec![]),
                     Some((slot, _)) => {
-                        let confirmed_block =
-                            self.get_confirmed_block(slot, false).map_err(|err| {
-                                BlockstoreError::Io(IoError::new(
-                                    ErrorKind::Other,
-                                    format!("Unable to get confirmed block: {}", err),
-                                ))
-                            })?;
-
-                        // Load all signatures for the block
-                        let mut slot_signatures: Vec<_> = confirmed_block
-                            .transactions
+                        // The signatures of the address in the slot, in the order they are
+                        // listed. The shreds of the slot may have been purged already
+                        let mut slot_signatures: Vec<_> = self
+                            .find_address_signatures(address, slot, slot)?
                             .into_iter()
-                            .filter_map(|transaction_with_meta| {
-                                transaction_with_meta
-                                    .transaction
-                                    .signatures
-                                    .into_iter()
-                                    .next()
-                            })
+                            .map(|(_, signature)| signature)
                             .collect();
-
-                        // Sort signatures as a way to entire a stable ordering within a slot, as
-                        // the AddressSignatures column is ordered by signatures within a slot,
-                        // not by block ordering
-                        slot_signatures.sort();
                         slot_signatures.reverse();
 
                         if let Some(pos) = slot_signatures.iter().position(|&x| x == before) {
@@ -2131,31 +2231,11 @@ impl Blockstore {
                 match transaction_status {
                     None => (0, HashSet::new()),
                     Some((slot, _)) => {
-                        let confirmed_block =
-                            self.get_confirmed_block(slot, false).map_err(|err| {
-                                BlockstoreError::Io(IoError::new(
-                                    ErrorKind::Other,
-                                    format!("Unable to get confirmed block: {}", err),
-                                ))
-                            })?;
-
-                        // Load all signatures for the block
-                        let mut slot_signatures: Vec<_> = confirmed_block
-                            .transactions
+                        let mut slot_signatures: Vec<_> = self
+                            .find_address_signatures(address, slot, slot)?
                             .into_iter()
-                            .filter_map(|transaction_with_meta| {
-                                transaction_with_meta
-                                    .transaction
-                                    .signatures
-                                    .into_iter()
-                                    .next()
-                            })
+                            .map(|(_, signature)| signature)
                             .collect();
-
-                        // Sort signatures as a way to entire a stable ordering within a slot, as
-                        // the AddressSignatures column is ordered by signatures within a slot,
-                        // not by block ordering
-                        slot_signatures.sort();
                         slot_signatures.reverse();
 
                         if let Some(pos) = slot_signatures.iter().position(|&x| x == until) {
@@ -2169,8 +2249,11 @@ impl Blockstore {
         };
         get_until_slot_timer.stop();
 
-        // Fetch the list of signatures that affect the given address
-        let first_available_block = self.get_first_available_block()?;
+        // Fetch the list of signatures that affect the given address. Transaction statuses may
+        // be kept for longer than the blocks holding them
+        let first_status_slot = self
+            .lowest_slot_of_column_group(ColumnGroup::TransactionStatus)?
+            .unwrap_or_default();
         let mut address_signatures = vec![];
 
         // Get signatures in `slot`
@@ -2213,7 +2296,7 @@ impl Blockstore {
                     }
                     if i == starting_primary_index
                         && key_address == address
-                        && slot >= first_available_block
+                        && slot >= first_status_slot
                     {
                         if self.is_root(slot) {
                             address_signatures.push((slot, signature));
@@ -2249,10 +2332,7 @@ impl Blockstore {
                 if slot < lowest_slot {
                     break;
                 }
-                if i == next_primary_index
-                    && key_address == address
-                    && slot >= first_available_block
-                {
+                if i == next_primary_index && key_address == address && slot >= first_status_slot {
                     if self.is_root(slot) {
                         address_signatures.push((slot, signature));
                     }
@@ -3516,7 +3596,7 @@ fn adjust_ulimit_nofile(enforce_ulimit_nofile: bool) -> Result<()> {
 pub mod tests {
     use super::*;
     use crate::{
-        entry::{next_entry, next_entry_mut},
+        entry::{next_entry, next_entry_mut, next_hash},
         genesis_utils::{create_genesis_config, GenesisConfigInfo},
         leader_schedule::{FixedSchedule, LeaderSchedule},
         shred::{max_ticks_per_n_shreds, DataShredHeader},
@@ -3529,10 +3609,14 @@ pub mod tests {
     use solana_sdk::{
         hash::{self, hash, Hash},
         instruction::CompiledInstruction,
+        message::{
+            v0::{self, LoadedAddresses, MessageAddressTableLookup},
+            MessageHeader, VersionedMessage,
+        },
         packet::PACKET_DATA_SIZE,
         pubkey::Pubkey,
         signature::Signature,
-        transaction::TransactionError,
+        transaction::{Transaction, TransactionError, TransactionReturnData},
     };
     use solana_storage_proto::convert::generated;
     use solana_transaction_status::{InnerInstructions, Reward, Rewards};
@@ -3992,6 +4076,73 @@ pub mod tests {
         Blockstore::destroy(&blockstore_path).expect("Expected successful database destruction");
     }
 
+    #[test]
+    fn test_open_in_memory() {
+        let blockstore = Blockstore::open_in_memory().unwrap();
+        let num_slots = 5;
+        let entries_per_slot = 10;
+        let (shreds, entries) = make_many_slot_entries(0, num_slots, entries_per_slot);
+        blockstore.insert_shreds(shreds, None, false).unwrap();
+
+        for (slot, slot_entries) in entries.chunks(entries_per_slot as usize).enumerate() {
+            let slot = slot as Slot;
+            assert!(blockstore.is_full(slot));
+            assert_eq!(blockstore.get_slot_entries(slot, 0).unwrap(), slot_entries);
+            assert!(blockstore
+                .find_missing_data_indexes(slot, 0, 0, 1, 1)
+                .is_empty());
+        }
+        let slots: Vec<Slot> = blockstore
+            .slot_meta_iterator(0)
+            .unwrap()
+            .map(|(slot, _)| slot)
+            .collect();
+        assert_eq!(slots, (0..num_slots).collect::<Vec<_>>());
+
+        blockstore.purge_and_compact_slots(0, 2);
+        for slot in 0..num_slots {
+            assert_eq!(blockstore.meta(slot).unwrap().is_some(), slot > 2);
+        }
+        assert!(blockstore.get_slot_entries(1, 0).unwrap().is_empty());
+        assert_eq!(
+            blockstore.get_slot_entries(3, 0).unwrap(),
+            entries[3 * entries_per_slot as usize..4 * entries_per_slot as usize]
+        );
+    }
+
+    #[test]
+    fn test_open_in_memory_from() {
+        let blockstore_path = get_tmp_ledger_path!();
+        let entries_per_slot = 10;
+        let (shreds, entries) = make_many_slot_entries(0, 2, entries_per_slot);
+        {
+            // A ledger without a blockstore opens empty, without creating one on disk
+            let blockstore = Blockstore::open_in_memory_from(&blockstore_path, None).unwrap();
+            assert!(blockstore.meta(0).unwrap().is_none());
+            assert!(!blockstore_path.join(BLOCKSTORE_DIRECTORY).exists());
+        }
+        {
+            let blockstore = Blockstore::open(&blockstore_path).unwrap();
+            blockstore.insert_shreds(shreds, None, false).unwrap();
+        }
+        {
+            let blockstore = Blockstore::open_in_memory_from(&blockstore_path, None).unwrap();
+            assert_eq!(blockstore.get_slot_entries(0, 0).unwrap(), entries[..10]);
+            assert_eq!(blockstore.get_slot_entries(1, 0).unwrap(), entries[10..]);
+
+            let (shreds, _) = make_many_slot_entries(2, 1, entries_per_slot);
+            blockstore.insert_shreds(shreds, None, false).unwrap();
+            assert!(blockstore.is_full(2));
+        }
+        {
+            // The slot inserted in memory never reached the disk
+            let blockstore = Blockstore::open(&blockstore_path).unwrap();
+            assert!(blockstore.is_full(1));
+            assert!(blockstore.meta(2).unwrap().is_none());
+        }
+        Blockstore::destroy(&blockstore_path).expect("Expected successful database destruction");
+    }
+
     // This test seems to be unnecessary with introduction of data shreds. There are no
     // guarantees that a particular shred index contains a complete entry
     #[test]
@@ -5695,7 +5846,9 @@ pub mod tests {
             .map(|transaction| {
                 let mut pre_balances: Vec<u64> = vec![];
                 let mut post_balances: Vec<u64> = vec![];
-                for (i, _account_key) in transaction.message.account_keys.iter().enumerate() {
+                for (i, _account_key) in
+                    transaction.message.static_account_keys().iter().enumerate()
+                {
                     pre_balances.push(i as u64 * 10);
                     post_balances.push(i as u64 * 11);
                 }
@@ -5713,6 +5866,8 @@ pub mod tests {
                             log_messages: Some(vec![]),
                             pre_token_balances: Some(vec![]),
                             post_token_balances: Some(vec![]),
+                            return_data: None,
+                            loaded_addresses: LoadedAddresses::default(),
                         },
                     )
                     .unwrap();
@@ -5729,6 +5884,8 @@ pub mod tests {
                             log_messages: Some(vec![]),
                             pre_token_balances: Some(vec![]),
                             post_token_balances: Some(vec![]),
+                            return_data: None,
+                            loaded_addresses: LoadedAddresses::default(),
                         },
                     )
                     .unwrap();
@@ -5743,6 +5900,8 @@ pub mod tests {
                         log_messages: Some(vec![]),
                         pre_token_balances: Some(vec![]),
                         post_token_balances: Some(vec![]),
+                        return_data: None,
+                        loaded_addresses: LoadedAddresses::default(),
                     }),
                 }
             })
@@ -5801,6 +5960,114 @@ pub mod tests {
         Blockstore::destroy(&ledger_path).expect("Expected successful database destruction");
     }
 
+    #[test]
+    fn test_get_complete_block() {
+        let slot = 10;
+        let entries = make_slot_entries_with_transactions(10);
+        let blockhash = get_last_hash(entries.iter()).unwrap();
+        let shreds = entries_to_test_shreds(entries.clone(), slot, slot - 1, true, 0);
+        let partial_shreds = entries_to_test_shreds(entries, slot + 1, slot, false, 0);
+        let ledger_path = get_tmp_ledger_path!();
+        let ledger = Blockstore::open(&ledger_path).unwrap();
+        ledger.insert_shreds(shreds, None, false).unwrap();
+        ledger.insert_shreds(partial_shreds, None, false).unwrap();
+
+        // Full slots are available before they are rooted
+        assert_matches!(
+            ledger.get_confirmed_block(slot, false).unwrap_err(),
+            BlockstoreError::SlotNotRooted
+        );
+        let block = ledger.get_complete_block(slot, false).unwrap();
+        assert_eq!(block.parent_slot, slot - 1);
+        assert_eq!(block.blockhash, blockhash.to_string());
+        assert_eq!(block.transactions.len(), 10);
+        assert!(block.transactions.iter().all(|tx| tx.meta.is_none()));
+
+        // Slots that are incomplete or missing are not
+        assert_matches!(
+            ledger.get_complete_block(slot + 1, false).unwrap_err(),
+            BlockstoreError::SlotUnavailable
+        );
+        assert_matches!(
+            ledger.get_complete_block(slot + 2, false).unwrap_err(),
+            BlockstoreError::SlotUnavailable
+        );
+
+        drop(ledger);
+        Blockstore::destroy(&ledger_path).expect("Expected successful database destruction");
+    }
+
+    #[test]
+    fn test_get_complete_block_with_versioned_transaction() {
+        let slot = 10;
+        let transaction = VersionedTransaction {
+            signatures: vec![Signature::new(&[1u8; 64])],
+            message: VersionedMessage::V0(v0::Message {
+                header: MessageHeader {
+                    num_required_signatures: 1,
+                    num_readonly_signed_accounts: 0,
+                    num_readonly_unsigned_accounts: 1,
+                },
+                account_keys: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+                recent_blockhash: Hash::default(),
+                instructions: vec![CompiledInstruction::new(1, &(), vec![0, 2])],
+                address_table_lookups: vec![MessageAddressTableLookup {
+                    account_key: Pubkey::new_unique(),
+                    writable_indexes: vec![0],
+                    readonly_indexes: vec![],
+                }],
+            }),
+        };
+        let entries = vec![Entry {
+            num_hashes: 1,
+            hash: next_hash(&Hash::default(), 1, &[transaction.clone()]),
+            transactions: vec![transaction.clone()],
+        }];
+        let shreds = entries_to_test_shreds(entries, slot, slot - 1, true, 0);
+        let ledger_path = get_tmp_ledger_path!();
+        let ledger = Blockstore::open(&ledger_path).unwrap();
+        ledger.insert_shreds(shreds, None, false).unwrap();
+
+        let loaded_address = Pubkey::new_unique();
+        let meta = TransactionStatusMeta {
+            pre_balances: vec![3, 2, 1],
+            post_balances: vec![2, 2, 2],
+            loaded_addresses: LoadedAddresses {
+                writable: vec![loaded_address],
+                readonly: vec![],
+            },
+            ..TransactionStatusMeta::default()
+        };
+        ledger
+            .write_transaction_status(
+                slot,
+                transaction.signatures[0],
+                vec![
+                    &transaction.message.static_account_keys()[0],
+                    &loaded_address,
+                ],
+                vec![&transaction.message.static_account_keys()[1]],
+                &meta,
+            )
+            .unwrap();
+
+        // The block holds the transaction as signed, with the addresses it loaded
+        let block = ledger.get_complete_block(slot, false).unwrap();
+        assert_eq!(
+            block.transactions,
+            vec![TransactionWithStatusMeta {
+                transaction: transaction.clone(),
+                meta: Some(meta),
+            }]
+        );
+        let mut account_keys = transaction.message.static_account_keys().to_vec();
+        account_keys.push(loaded_address);
+        assert_eq!(block.transactions[0].account_keys(), account_keys);
+
+        drop(ledger);
+        Blockstore::destroy(&ledger_path).expect("Expected successful database destruction");
+    }
+
     #[test]
     fn test_persist_transaction_status() {
         let blockstore_path = get_tmp_ledger_path!();
@@ -5817,6 +6084,14 @@ pub mod tests {
             let log_messages_vec = vec![String::from("Test message\n")];
             let pre_token_balances_vec = vec![];
             let post_token_balances_vec = vec![];
+            let loaded_addresses = LoadedAddresses {
+                writable: vec![Pubkey::new_unique()],
+                readonly: vec![Pubkey::new_unique()],
+            };
+            let return_data = TransactionReturnData {
+                program_id: Pubkey::new_unique(),
+                data: vec![1, 2, 3],
+            };
 
             // result not found
             assert!(transaction_status_cf
@@ -5838,7 +6113,9 @@ pub mod tests {
                         inner_instructions: Some(inner_instructions_vec.clone()),
                         log_messages: Some(log_messages_vec.clone()),
                         pre_token_balances: Some(pre_token_balances_vec.clone()),
-                        post_token_balances: Some(post_token_balances_vec.clone())
+                        post_token_balances: Some(post_token_balances_vec.clone()),
+                        return_data: Some(return_data.clone()),
+                        loaded_addresses: loaded_addresses.clone(),
                     },
                 )
                 .is_ok());
@@ -5853,6 +6130,8 @@ pub mod tests {
                 log_messages,
                 pre_token_balances,
                 post_token_balances,
+                return_data: stored_return_data,
+                loaded_addresses: stored_loaded_addresses,
             } = transaction_status_cf
                 .get((0, Signature::default(), 0))
                 .unwrap()
@@ -5865,6 +6144,8 @@ pub mod tests {
             assert_eq!(log_messages.unwrap(), log_messages_vec);
             assert_eq!(pre_token_balances.unwrap(), pre_token_balances_vec);
             assert_eq!(post_token_balances.unwrap(), post_token_balances_vec);
+            assert_eq!(stored_return_data.unwrap(), return_data);
+            assert_eq!(stored_loaded_addresses, loaded_addresses);
 
             // insert value
             assert!(transaction_status_cf
@@ -5878,7 +6159,9 @@ pub mod tests {
                         inner_instructions: Some(inner_instructions_vec.clone()),
                         log_messages: Some(log_messages_vec.clone()),
                         pre_token_balances: Some(pre_token_balances_vec.clone()),
-                        post_token_balances: Some(post_token_balances_vec.clone())
+                        post_token_balances: Some(post_token_balances_vec.clone()),
+                        return_data: Some(return_data.clone()),
+                        loaded_addresses: LoadedAddresses::default(),
                     },
                 )
                 .is_ok());
@@ -5893,6 +6176,8 @@ pub mod tests {
                 log_messages,
                 pre_token_balances,
                 post_token_balances,
+                return_data: stored_return_data,
+                loaded_addresses: stored_loaded_addresses,
             } = transaction_status_cf
                 .get((0, Signature::new(&[2u8; 64]), 9))
                 .unwrap()
@@ -5907,6 +6192,8 @@ pub mod tests {
             assert_eq!(log_messages.unwrap(), log_messages_vec);
             assert_eq!(pre_token_balances.unwrap(), pre_token_balances_vec);
             assert_eq!(post_token_balances.unwrap(), post_token_balances_vec);
+            assert_eq!(stored_return_data.unwrap(), return_data);
+            assert_eq!(stored_loaded_addresses, LoadedAddresses::default());
         }
         Blockstore::destroy(&blockstore_path).expect("Expected successful database destruction");
     }
@@ -6137,6 +6424,8 @@ pub mod tests {
                 log_messages: Some(vec![]),
                 pre_token_balances: Some(vec![]),
                 post_token_balances: Some(vec![]),
+                return_data: None,
+                loaded_addresses: LoadedAddresses::default(),
             };
 
             let signature1 = Signature::new(&[1u8; 64]);
@@ -6262,7 +6551,9 @@ pub mod tests {
             .map(|transaction| {
                 let mut pre_balances: Vec<u64> = vec![];
                 let mut post_balances: Vec<u64> = vec![];
-                for (i, _account_key) in transaction.message.account_keys.iter().enumerate() {
+                for (i, _account_key) in
+                    transaction.message.static_account_keys().iter().enumerate()
+                {
                     pre_balances.push(i as u64 * 10);
                     post_balances.push(i as u64 * 11);
                 }
@@ -6287,6 +6578,8 @@ pub mod tests {
                             log_messages: log_messages.clone(),
                             pre_token_balances: pre_token_balances.clone(),
                             post_token_balances: post_token_balances.clone(),
+                            return_data: None,
+                            loaded_addresses: LoadedAddresses::default(),
                         },
                     )
                     .unwrap();
@@ -6301,6 +6594,8 @@ pub mod tests {
                         log_messages,
                         pre_token_balances,
                         post_token_balances,
+                        return_data: None,
+                        loaded_addresses: LoadedAddresses::default(),
                     }),
                 }
             })
@@ -6329,6 +6624,94 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn test_transaction_status_kept_longer_than_shreds() {
+        let slot = 2;
+        let entries = make_slot_entries_with_transactions(5);
+        let shreds = entries_to_test_shreds(entries.clone(), slot, slot - 1, true, 0);
+        let ledger_path = get_tmp_ledger_path!();
+        let blockstore = Blockstore::open(&ledger_path).unwrap();
+        blockstore.insert_shreds(shreds, None, false).unwrap();
+        blockstore.set_roots(&[slot - 1, slot]).unwrap();
+
+        let address = solana_sdk::pubkey::new_rand();
+        let signatures: Vec<Signature> = entries
+            .iter()
+            .flat_map(|entry| entry.transactions.iter())
+            .map(|transaction| transaction.signatures[0])
+            .collect();
+        for signature in &signatures {
+            blockstore
+                .write_transaction_status(
+                    slot,
+                    *signature,
+                    vec![&address],
+                    vec![],
+                    &TransactionStatusMeta::default(),
+                )
+                .unwrap();
+        }
+        let all_signatures = blockstore
+            .get_confirmed_signatures_for_address2(address, slot, None, None, usize::MAX)
+            .unwrap();
+        assert_eq!(all_signatures.len(), signatures.len());
+
+        blockstore.purge_column_groups(&[ColumnGroup::Shreds], 0, slot, PurgeType::PrimaryIndex);
+        *blockstore.lowest_cleanup_slot.write().unwrap() = slot;
+        assert!(blockstore.is_root(slot));
+        for signature in &signatures {
+            assert!(blockstore
+                .get_transaction_status(*signature)
+                .unwrap()
+                .is_some());
+            // The transaction itself went with the shreds
+            assert_eq!(
+                blockstore.get_confirmed_transaction(*signature).unwrap(),
+                None
+            );
+        }
+        // Once the lowest cleanup slot is forgotten, as after a restart
+        *blockstore.lowest_cleanup_slot.write().unwrap() = 0;
+        assert_eq!(
+            blockstore.get_confirmed_transaction(signatures[0]).unwrap(),
+            None
+        );
+
+        assert_eq!(
+            blockstore
+                .get_confirmed_signatures_for_address2(address, slot, None, None, usize::MAX)
+                .unwrap(),
+            all_signatures
+        );
+        assert_eq!(
+            blockstore
+                .get_confirmed_signatures_for_address2(
+                    address,
+                    slot,
+                    Some(all_signatures[1].signature),
+                    None,
+                    1
+                )
+                .unwrap(),
+            vec![all_signatures[2].clone()]
+        );
+        assert_eq!(
+            blockstore
+                .get_confirmed_signatures_for_address2(
+                    address,
+                    slot,
+                    None,
+                    Some(all_signatures[3].signature),
+                    usize::MAX
+                )
+                .unwrap(),
+            all_signatures[..3].to_vec()
+        );
+
+        drop(blockstore);
+        Blockstore::destroy(&ledger_path).unwrap();
+    }
+
     #[test]
     fn test_empty_transaction_status() {
         let blockstore_path = get_tmp_ledger_path!();
@@ -6526,7 +6909,7 @@ pub mod tests {
                             .write_transaction_status(
                                 slot,
                                 transaction.signatures[0],
-                                transaction.message.account_keys.iter().collect(),
+                                transaction.message.static_account_keys().iter().collect(),
                                 vec![],
                                 &TransactionStatusMeta::default(),
                             )
@@ -6748,6 +7131,8 @@ pub mod tests {
                             log_messages: Some(vec![]),
                             pre_token_balances: Some(vec![]),
                             post_token_balances: Some(vec![]),
+                            return_data: None,
+                            loaded_addresses: LoadedAddresses::default(),
                         },
                     )
                     .unwrap();
@@ -6762,7 +7147,8 @@ pub mod tests {
                 vec![CompiledInstruction::new(1, &(), vec![0])],
             ));
 
-            let map = blockstore.map_transactions_to_statuses(slot, transactions.into_iter());
+            let map = blockstore
+                .map_transactions_to_statuses(slot, transactions.into_iter().map(Into::into));
             assert_eq!(map.len(), 5);
             for (x, m) in map.iter().take(4).enumerate() {
                 assert_eq!(m.meta.as_ref().unwrap().fee, x as u64);
@@ -13,21 +13,41 @@ impl Blockstore {
     /// Does not check for integrity and does not update slot metas that refer to deleted slots
     /// Modifies multiple column families simultaneously
     pub fn purge_slots(&self, from_slot: Slot, to_slot: Slot, purge_type: PurgeType) {
+        self.purge_column_groups(&ColumnGroup::ALL, from_slot, to_slot, purge_type)
+    }
+
+    /// Silently deletes the column families of `column_groups` in the range [from_slot,to_slot]
+    /// Dangerous; Use with care:
+    /// An exact purge of the transaction status columns reads the shreds of the purged slots, so
+    /// it must happen before the shreds are purged
+    pub fn purge_column_groups(
+        &self,
+        column_groups: &[ColumnGroup],
+        from_slot: Slot,
+        to_slot: Slot,
+        purge_type: PurgeType,
+    ) {
         let mut purge_stats = PurgeStats::default();
-        let purge_result =
-            self.run_purge_with_stats(from_slot, to_slot, purge_type, &mut purge_stats);
+        let purge_result = self.run_purge_with_stats(
+            column_groups,
+            from_slot,
+            to_slot,
+            purge_type,
+            &mut purge_stats,
+        );
 
         datapoint_info!(
             "blockstore-purge",
             ("from_slot", from_slot as i64, i64),
             ("to_slot", to_slot as i64, i64),
+            ("column_groups", format!("{:?}", column_groups), String),
             ("delete_range_us", purge_stats.delete_range as i64, i64),
             ("write_batch_us", purge_stats.write_batch as i64, i64)
         );
         if let Err(e) = purge_result {
             error!(
-                "Error: {:?}; Purge failed in range {:?} to {:?}",
-                e, from_slot, to_slot
+                "Error: {:?}; Purge of {:?} failed in range {:?} to {:?}",
+                e, column_groups, from_slot, to_slot
             );
         }
     }
@@ -99,12 +119,19 @@ impl Blockstore {
         to_slot: Slot,
         purge_type: PurgeType,
     ) -> Result<bool> {
-        self.run_purge_with_stats(from_slot, to_slot, purge_type, &mut PurgeStats::default())
+        self.run_purge_with_stats(
+            &ColumnGroup::ALL,
+            from_slot,
+            to_slot,
+            purge_type,
+            &mut PurgeStats::default(),
+        )
     }
 
     // Returns whether or not all columns successfully purged the slot range
     pub(crate) fn run_purge_with_stats(
         &self,
+        column_groups: &[ColumnGroup],
         from_slot: Slot,
         to_slot: Slot,
         purge_type: PurgeType,
@@ -118,67 +145,103 @@ impl Blockstore {
         let to_slot = to_slot.checked_add(1).unwrap_or(std::u64::MAX);
 
         let mut delete_range_timer = Measure::start("delete_range");
-        let mut columns_purged = self
-            .db
-            .delete_range_cf::<cf::SlotMeta>(&mut write_batch, from_slot, to_slot)
-            .is_ok()
-            & self
-                .db
-                .delete_range_cf::<cf::Root>(&mut write_batch, from_slot, to_slot)
-                .is_ok()
-            & self
-                .db
-                .delete_range_cf::<cf::ShredData>(&mut write_batch, from_slot, to_slot)
-                .is_ok()
-            & self
-                .db
-                .delete_range_cf::<cf::ShredCode>(&mut write_batch, from_slot, to_slot)
-                .is_ok()
-            & self
-                .db
-                .delete_range_cf::<cf::DeadSlots>(&mut write_batch, from_slot, to_slot)
-                .is_ok()
-            & self
-                .db
-                .delete_range_cf::<cf::DuplicateSlots>(&mut write_batch, from_slot, to_slot)
-                .is_ok()
-            & self
-                .db
-                .delete_range_cf::<cf::ErasureMeta>(&mut write_batch, from_slot, to_slot)
-                .is_ok()
-            & self
-                .db
-                .delete_range_cf::<cf::Orphans>(&mut write_batch, from_slot, to_slot)
-                .is_ok()
-            & self
-                .db
-                .delete_range_cf::<cf::Index>(&mut write_batch, from_slot, to_slot)
-                .is_ok()
-            & self
-                .db
-                .delete_range_cf::<cf::Rewards>(&mut write_batch, from_slot, to_slot)
-                .is_ok()
-            & self
-                .db
-                .delete_range_cf::<cf::Blocktime>(&mut write_batch, from_slot, to_slot)
-                .is_ok()
-            & self
-                .db
-                .delete_range_cf::<cf::PerfSamples>(&mut write_batch, from_slot, to_slot)
-                .is_ok();
         let mut w_active_transaction_status_index =
             self.active_transaction_status_index.write().unwrap();
-        match purge_type {
-            PurgeType::Exact => {
-                self.purge_special_columns_exact(&mut write_batch, from_slot, to_slot)?;
+        let mut columns_purged = true;
+        for column_group in column_groups {
+            match column_group {
+                ColumnGroup::Shreds => {
+                    columns_purged &= self
+                        .db
+                        .delete_range_cf::<cf::SlotMeta>(&mut write_batch, from_slot, to_slot)
+                        .is_ok()
+                        & self
+                            .db
+                            .delete_range_cf::<cf::ShredData>(&mut write_batch, from_slot, to_slot)
+                            .is_ok()
+                        & self
+                            .db
+                            .delete_range_cf::<cf::ShredCode>(&mut write_batch, from_slot, to_slot)
+                            .is_ok()
+                        & self
+                            .db
+                            .delete_range_cf::<cf::DeadSlots>(&mut write_batch, from_slot, to_slot)
+                            .is_ok()
+                        & self
+                            .db
+                            .delete_range_cf::<cf::DuplicateSlots>(
+                                &mut write_batch,
+                                from_slot,
+                                to_slot,
+                            )
+                            .is_ok()
+                        & self
+                            .db
+                            .delete_range_cf::<cf::ErasureMeta>(
+                                &mut write_batch,
+                                from_slot,
+                                to_slot,
+                            )
+                            .is_ok()
+                        & self
+                            .db
+                            .delete_range_cf::<cf::Orphans>(&mut write_batch, from_slot, to_slot)
+                            .is_ok()
+                        & self
+                            .db
+                            .delete_range_cf::<cf::Index>(&mut write_batch, from_slot, to_slot)
+                            .is_ok();
+                }
+                ColumnGroup::BlockMetadata => {
+                    columns_purged &= self
+                        .db
+                        .delete_range_cf::<cf::Rewards>(&mut write_batch, from_slot, to_slot)
+                        .is_ok()
+                        & self
+                            .db
+                            .delete_range_cf::<cf::Blocktime>(&mut write_batch, from_slot, to_slot)
+                            .is_ok()
+                        & self
+                            .db
+                            .delete_range_cf::<cf::PerfSamples>(
+                                &mut write_batch,
+                                from_slot,
+                                to_slot,
+                            )
+                            .is_ok();
+                }
+                ColumnGroup::TransactionStatus => match purge_type {
+                    PurgeType::Exact => {
+                        self.purge_special_columns_exact(&mut write_batch, from_slot, to_slot)?;
+                    }
+                    PurgeType::PrimaryIndex => {
+                        self.purge_special_columns_with_primary_index(
+                            &mut write_batch,
+                            &mut columns_purged,
+                            &mut w_active_transaction_status_index,
+                            to_slot,
+                        )?;
+                    }
+                },
             }
-            PurgeType::PrimaryIndex => {
-                self.purge_special_columns_with_primary_index(
-                    &mut write_batch,
-                    &mut columns_purged,
-                    &mut w_active_transaction_status_index,
-                    to_slot,
-                )?;
+        }
+        let purges_transaction_status = column_groups.contains(&ColumnGroup::TransactionStatus);
+        let highest_purged_transaction_status_slot =
+            (*self.highest_purged_transaction_status_slot.read().unwrap()).max(Some(to_slot - 1));
+        if purges_transaction_status {
+            // Stored with the ledger, so that roots are still purged after a restart
+            write_batch.put::<cf::PurgedTransactionStatusSlot>(
+                0,
+                &highest_purged_transaction_status_slot.unwrap(),
+            )?;
+        }
+        if purges_transaction_status || column_groups.contains(&ColumnGroup::Shreds) {
+            let to_root = self.purgeable_roots_end(column_groups, to_slot)?;
+            if to_root > from_slot {
+                columns_purged &= self
+                    .db
+                    .delete_range_cf::<cf::Root>(&mut write_batch, from_slot, to_root)
+                    .is_ok();
             }
         }
         delete_range_timer.stop();
@@ -191,79 +254,59 @@ impl Blockstore {
             return Err(e);
         }
         write_timer.stop();
+        if purges_transaction_status {
+            *self.highest_purged_transaction_status_slot.write().unwrap() =
+                highest_purged_transaction_status_slot;
+        }
         purge_stats.delete_range += delete_range_timer.as_us();
         purge_stats.write_batch += write_timer.as_us();
         Ok(columns_purged)
     }
 
+    /// The end, exclusive, of the roots that can be purged along with `column_groups` up to
+    /// `to_slot`, exclusive. A root makes both the shreds and the transaction statuses of its
+    /// slot readable, so it is only purged once neither is kept
+    fn purgeable_roots_end(&self, column_groups: &[ColumnGroup], to_slot: Slot) -> Result<Slot> {
+        let mut to_root = to_slot;
+        if !column_groups.contains(&ColumnGroup::Shreds) {
+            if let Some(lowest_slot) = self.lowest_slot_of_column_group(ColumnGroup::Shreds)? {
+                to_root = to_root.min(lowest_slot);
+            }
+        }
+        if !column_groups.contains(&ColumnGroup::TransactionStatus) {
+            let first_kept_slot = self
+                .highest_purged_transaction_status_slot
+                .read()
+                .unwrap()
+                .map(|slot| slot + 1)
+                .unwrap_or(0);
+            to_root = to_root.min(first_kept_slot);
+        }
+        Ok(to_root)
+    }
+
     pub fn compact_storage(&self, from_slot: Slot, to_slot: Slot) -> Result<bool> {
+        self.compact_column_groups(&ColumnGroup::ALL, from_slot, to_slot)
+    }
+
+    pub fn compact_column_groups(
+        &self,
+        column_groups: &[ColumnGroup],
+        from_slot: Slot,
+        to_slot: Slot,
+    ) -> Result<bool> {
         if self.no_compaction {
             info!("compact_storage: compaction disabled");
             return Ok(false);
         }
-        info!("compact_storage: from {} to {}", from_slot, to_slot);
+        info!(
+            "compact_storage: {:?} from {} to {}",
+            column_groups, from_slot, to_slot
+        );
         let mut compact_timer = Measure::start("compact_range");
-        let result = self
-            .meta_cf
-            .compact_range(from_slot, to_slot)
-            .unwrap_or(false)
-            && self
-                .db
-                .column::<cf::Root>()
-                .compact_range(from_slot, to_slot)
-                .unwrap_or(false)
-            && self
-                .data_shred_cf
-                .compact_range(from_slot, to_slot)
-                .unwrap_or(false)
-            && self
-                .code_shred_cf
-                .compact_range(from_slot, to_slot)
-                .unwrap_or(false)
-            && self
-                .dead_slots_cf
-                .compact_range(from_slot, to_slot)
-                .unwrap_or(false)
-            && self
-                .duplicate_slots_cf
-                .compact_range(from_slot, to_slot)
-                .unwrap_or(false)
-            && self
-                .erasure_meta_cf
-                .compact_range(from_slot, to_slot)
-                .unwrap_or(false)
-            && self
-                .orphans_cf
-                .compact_range(from_slot, to_slot)
-                .unwrap_or(false)
-            && self
-                .index_cf
-                .compact_range(from_slot, to_slot)
-                .unwrap_or(false)
-            && self
-                .transaction_status_cf
-                .compact_range(0, 2)
-                .unwrap_or(false)
-            && self
-                .address_signatures_cf
-                .compact_range(0, 2)
-                .unwrap_or(false)
-            && self
-                .transaction_status_index_cf
-                .compact_range(0, 2)
-                .unwrap_or(false)
-            && self
-                .rewards_cf
-                .compact_range(from_slot, to_slot)
-                .unwrap_or(false)
-            && self
-                .blocktime_cf
-                .compact_range(from_slot, to_slot)
-                .unwrap_or(false)
-            && self
-                .perf_samples_cf
-                .compact_range(from_slot, to_slot)
-                .unwrap_or(false);
+        let result = column_groups
+            .iter()
+            .all(|column_group| self.compact_column_group(*column_group, from_slot, to_slot));
         compact_timer.stop();
         if !result {
             info!("compact_storage incomplete");
@@ -275,6 +318,169 @@ impl Blockstore {
         Ok(result)
     }
 
+    fn compact_column_group(
+        &self,
+        column_group: ColumnGroup,
+        from_slot: Slot,
+        to_slot: Slot,
+    ) -> bool {
+        match column_group {
+            ColumnGroup::Shreds => {
+                self.meta_cf
+                    .compact_range(from_slot, to_slot)
+                    .unwrap_or(false)
+                    && self
+                        .data_shred_cf
+                        .compact_range(from_slot, to_slot)
+                        .unwrap_or(false)
+                    && self
+                        .code_shred_cf
+                        .compact_range(from_slot, to_slot)
+                        .unwrap_or(false)
+                    && self
+                        .dead_slots_cf
+                        .compact_range(from_slot, to_slot)
+                        .unwrap_or(false)
+                    && self
+                        .duplicate_slots_cf
+                        .compact_range(from_slot, to_slot)
+                        .unwrap_or(false)
+                    && self
+                        .erasure_meta_cf
+                        .compact_range(from_slot, to_slot)
+                        .unwrap_or(false)
+                    && self
+                        .orphans_cf
+                        .compact_range(from_slot, to_slot)
+                        .unwrap_or(false)
+                    && self
+                        .index_cf
+                        .compact_range(from_slot, to_slot)
+                        .unwrap_or(false)
+            }
+            ColumnGroup::BlockMetadata => {
+                self.rewards_cf
+                    .compact_range(from_slot, to_slot)
+                    .unwrap_or(false)
+                    && self
+                        .blocktime_cf
+                        .compact_range(from_slot, to_slot)
+                        .unwrap_or(false)
+                    && self
+                        .perf_samples_cf
+                        .compact_range(from_slot, to_slot)
+                        .unwrap_or(false)
+            }
+            ColumnGroup::TransactionStatus => {
+                self.db
+                    .column::<cf::Root>()
+                    .compact_range(from_slot, to_slot)
+                    .unwrap_or(false)
+                    && self
+                        .transaction_status_cf
+                        .compact_range(0, 2)
+                        .unwrap_or(false)
+                    && self
+                        .address_signatures_cf
+                        .compact_range(0, 2)
+                        .unwrap_or(false)
+                    && self
+                        .transaction_status_index_cf
+                        .compact_range(0, 2)
+                        .unwrap_or(false)
+            }
+        }
+    }
+
+    /// The bytes the column families of `column_group` occupy, on disk and in memory
+    pub fn column_group_storage_size(&self, column_group: ColumnGroup) -> Result<u64> {
+        let sizes = match column_group {
+            ColumnGroup::Shreds => vec![
+                self.meta_cf.storage_size()?,
+                self.data_shred_cf.storage_size()?,
+                self.code_shred_cf.storage_size()?,
+                self.dead_slots_cf.storage_size()?,
+                self.duplicate_slots_cf.storage_size()?,
+                self.erasure_meta_cf.storage_size()?,
+                self.orphans_cf.storage_size()?,
+                self.index_cf.storage_size()?,
+            ],
+            ColumnGroup::BlockMetadata => vec![
+                self.rewards_cf.storage_size()?,
+                self.blocktime_cf.storage_size()?,
+                self.perf_samples_cf.storage_size()?,
+            ],
+            ColumnGroup::TransactionStatus => vec![
+                self.db.column::<cf::Root>().storage_size()?,
+                self.transaction_status_cf.storage_size()?,
+                self.address_signatures_cf.storage_size()?,
+                self.transaction_status_index_cf.storage_size()?,
+            ],
+        };
+        Ok(sizes.into_iter().sum())
+    }
+
+    /// The lowest slot `column_group` still holds data for: the first slot meta for shreds, the
+    /// first root past the last purge for transaction statuses
+    pub fn lowest_slot_of_column_group(&self, column_group: ColumnGroup) -> Result<Option<Slot>> {
+        let lowest_slot = match column_group {
+            ColumnGroup::Shreds => self
+                .db
+                .iter::<cf::SlotMeta>(IteratorMode::Start)?
+                .next()
+                .map(|(slot, _)| slot),
+            ColumnGroup::BlockMetadata => vec![
+                self.db
+                    .iter::<cf::Rewards>(IteratorMode::Start)?
+                    .next()
+                    .map(|(slot, _)| slot),
+                self.db
+                    .iter::<cf::Blocktime>(IteratorMode::Start)?
+                    .next()
+                    .map(|(slot, _)| slot),
+                self.db
+                    .iter::<cf::PerfSamples>(IteratorMode::Start)?
+                    .next()
+                    .map(|(slot, _)| slot),
+            ]
+            .into_iter()
+            .flatten()
+            .min(),
+            ColumnGroup::TransactionStatus => {
+                let first_kept_slot = self
+                    .highest_purged_transaction_status_slot
+                    .read()
+                    .unwrap()
+                    .map(|slot| slot + 1)
+                    .unwrap_or(0);
+                self.db
+                    .iter::<cf::Root>(IteratorMode::From(
+                        first_kept_slot,
+                        IteratorDirection::Forward,
+                    ))?
+                    .next()
+                    .map(|(slot, _)| slot)
+            }
+        };
+        Ok(lowest_slot)
+    }
+
+    /// The highest slot of the leading run of slots whose block time is before `timestamp`
+    pub fn highest_slot_with_block_time_before(
+        &self,
+        timestamp: UnixTimestamp,
+    ) -> Result<Option<Slot>> {
+        let mut highest_slot = None;
+        for (slot, block_time) in self.db.iter::<cf::Blocktime>(IteratorMode::Start)? {
+            let block_time: UnixTimestamp = deserialize(&block_time)?;
+            if block_time >= timestamp {
+                break;
+            }
+            highest_slot = Some(slot);
+        }
+        Ok(highest_slot)
+    }
+
     /// Purges special columns (using a non-Slot primary-index) exactly, by deserializing each slot
     /// being purged and iterating through all transactions to determine the keys of individual
     /// records. **This method is very slow.**
@@ -296,9 +502,12 @@ impl Blockstore {
                 if let Some(&signature) = transaction.signatures.get(0) {
                     batch.delete::<cf::TransactionStatus>((0, signature, slot))?;
                     batch.delete::<cf::TransactionStatus>((1, signature, slot))?;
-                    for pubkey in transaction.message.account_keys {
-                        batch.delete::<cf::AddressSignatures>((0, pubkey, slot, signature))?;
-                        batch.delete::<cf::AddressSignatures>((1, pubkey, slot, signature))?;
+                    // Addresses that v0 messages load from lookup tables are not recorded in
+                    // the ledger, so their rows are left to the primary index purge. They only
+                    // refer to purged, and so unrooted, slots and are never returned
+                    for pubkey in transaction.message.static_account_keys() {
+                        batch.delete::<cf::AddressSignatures>((0, *pubkey, slot, signature))?;
+                        batch.delete::<cf::AddressSignatures>((1, *pubkey, slot, signature))?;
                     }
                 }
             }
@@ -348,10 +557,12 @@ pub mod tests {
         blockstore::tests::make_slot_entries_with_transactions, entry::next_entry_mut,
         get_tmp_ledger_path,
     };
+    use assert_matches::assert_matches;
     use bincode::serialize;
     use solana_sdk::{
         hash::{hash, Hash},
         message::Message,
+        transaction::Transaction,
     };
 
     // check that all columns are either empty or start at `min_slot`
@@ -447,6 +658,89 @@ pub mod tests {
         assert!(condition_met);
     }
 
+    #[test]
+    fn test_purge_roots_kept_by_other_column_groups() {
+        let blockstore_path = get_tmp_ledger_path!();
+        let blockstore = Blockstore::open(&blockstore_path).unwrap();
+        let (shreds, _) = make_many_slot_entries(0, 20, 5);
+        blockstore.insert_shreds(shreds, None, false).unwrap();
+        let roots: Vec<Slot> = (0..20).collect();
+        blockstore.set_roots(&roots).unwrap();
+        let first_root = |blockstore: &Blockstore| {
+            blockstore
+                .db
+                .iter::<cf::Root>(IteratorMode::Start)
+                .unwrap()
+                .next()
+                .map(|(slot, _)| slot)
+        };
+
+        // The transaction statuses of every slot are kept
+        blockstore.purge_column_groups(&[ColumnGroup::Shreds], 0, 9, PurgeType::PrimaryIndex);
+        assert_eq!(first_root(&blockstore), Some(0));
+
+        // The shreds of slots 10 and on are kept
+        blockstore.purge_column_groups(
+            &[ColumnGroup::TransactionStatus],
+            0,
+            14,
+            PurgeType::PrimaryIndex,
+        );
+        assert_eq!(first_root(&blockstore), Some(10));
+        assert_eq!(
+            blockstore
+                .lowest_slot_of_column_group(ColumnGroup::TransactionStatus)
+                .unwrap(),
+            Some(15)
+        );
+
+        blockstore.purge_column_groups(&[ColumnGroup::Shreds], 10, 16, PurgeType::PrimaryIndex);
+        assert_eq!(first_root(&blockstore), Some(15));
+
+        drop(blockstore);
+        Blockstore::destroy(&blockstore_path).expect("Expected successful database destruction");
+    }
+
+    #[test]
+    fn test_purge_roots_kept_by_other_column_groups_after_reopen() {
+        let blockstore_path = get_tmp_ledger_path!();
+        {
+            let blockstore = Blockstore::open(&blockstore_path).unwrap();
+            let (shreds, _) = make_many_slot_entries(0, 20, 5);
+            blockstore.insert_shreds(shreds, None, false).unwrap();
+            let roots: Vec<Slot> = (0..20).collect();
+            blockstore.set_roots(&roots).unwrap();
+            blockstore.purge_column_groups(
+                &[ColumnGroup::TransactionStatus],
+                0,
+                14,
+                PurgeType::PrimaryIndex,
+            );
+        }
+
+        // The purged transaction statuses are remembered across a restart
+        let blockstore = Blockstore::open(&blockstore_path).unwrap();
+        assert_eq!(
+            blockstore
+                .lowest_slot_of_column_group(ColumnGroup::TransactionStatus)
+                .unwrap(),
+            Some(15)
+        );
+        blockstore.purge_column_groups(&[ColumnGroup::Shreds], 0, 9, PurgeType::PrimaryIndex);
+        assert_eq!(
+            blockstore
+                .db
+                .iter::<cf::Root>(IteratorMode::Start)
+                .unwrap()
+                .next()
+                .map(|(slot, _)| slot),
+            Some(10)
+        );
+
+        drop(blockstore);
+        Blockstore::destroy(&blockstore_path).expect("Expected successful database destruction");
+    }
+
     #[test]
     fn test_purge_slots() {
         let blockstore_path = get_tmp_ledger_path!();
@@ -475,6 +769,86 @@ pub mod tests {
         Blockstore::destroy(&blockstore_path).expect("Expected successful database destruction");
     }
 
+    #[test]
+    fn test_purge_column_groups() {
+        let blockstore_path = get_tmp_ledger_path!();
+        let blockstore = Blockstore::open(&blockstore_path).unwrap();
+        let (shreds, _) = make_many_slot_entries(0, 50, 5);
+        blockstore.insert_shreds(shreds, None, false).unwrap();
+        let roots: Vec<Slot> = (0..50).collect();
+        blockstore.set_roots(&roots).unwrap();
+        for slot in 0..50 {
+            blockstore
+                .cache_block_time(slot, 1_000 + slot as i64)
+                .unwrap();
+        }
+        for column_group in ColumnGroup::ALL.iter() {
+            assert_eq!(
+                blockstore
+                    .lowest_slot_of_column_group(*column_group)
+                    .unwrap(),
+                Some(0)
+            );
+        }
+        assert_eq!(
+            blockstore
+                .highest_slot_with_block_time_before(1_020)
+                .unwrap(),
+            Some(19)
+        );
+        assert_eq!(
+            blockstore
+                .highest_slot_with_block_time_before(1_000)
+                .unwrap(),
+            None
+        );
+
+        // Purging the shreds keeps the block times and roots readable
+        blockstore.purge_column_groups(&[ColumnGroup::Shreds], 0, 9, PurgeType::PrimaryIndex);
+        *blockstore.lowest_cleanup_slot.write().unwrap() = 9;
+        assert_eq!(
+            blockstore
+                .lowest_slot_of_column_group(ColumnGroup::Shreds)
+                .unwrap(),
+            Some(10)
+        );
+        assert!(blockstore.meta(5).unwrap().is_none());
+        assert!(blockstore.is_root(5));
+        assert_eq!(blockstore.get_block_time(5).unwrap(), Some(1_005));
+
+        // Then the block times, which only affects the block metadata
+        blockstore.purge_column_groups(
+            &[ColumnGroup::BlockMetadata],
+            0,
+            19,
+            PurgeType::PrimaryIndex,
+        );
+        assert_eq!(
+            blockstore
+                .lowest_slot_of_column_group(ColumnGroup::BlockMetadata)
+                .unwrap(),
+            Some(20)
+        );
+        assert_matches!(
+            blockstore.get_block_time(5),
+            Err(BlockstoreError::SlotCleanedUp)
+        );
+        assert_eq!(blockstore.get_block_time(15).unwrap(), None);
+        assert!(blockstore.meta(15).unwrap().is_some());
+        assert_eq!(
+            blockstore
+                .lowest_slot_of_column_group(ColumnGroup::TransactionStatus)
+                .unwrap(),
+            Some(0)
+        );
+        assert!(blockstore
+            .compact_column_groups(&[ColumnGroup::BlockMetadata], 0, 19)
+            .unwrap());
+
+        drop(blockstore);
+        Blockstore::destroy(&blockstore_path).expect("Expected successful database destruction");
+    }
+
     #[test]
     fn test_purge_huge() {
         let blockstore_path = get_tmp_ledger_path!();
@@ -490,6 +864,17 @@ pub mod tests {
         Blockstore::destroy(&blockstore_path).expect("Expected successful database destruction");
     }
 
+    #[test]
+    fn test_purge_huge_in_memory() {
+        let blockstore = Blockstore::open_in_memory().unwrap();
+        let (shreds, _) = make_many_slot_entries(0, 5000, 10);
+        blockstore.insert_shreds(shreds, None, false).unwrap();
+
+        blockstore.purge_and_compact_slots(0, 4999);
+
+        test_all_empty_or_min(&blockstore, 5000);
+    }
+
     #[test]
     fn test_purge_front_of_ledger() {
         let blockstore_path = get_tmp_ledger_path!();
@@ -5,7 +5,7 @@ use log::*;
 use prost::Message;
 pub use rocksdb::Direction as IteratorDirection;
 use rocksdb::{
-    self, ColumnFamily, ColumnFamilyDescriptor, DBIterator, DBRawIterator, DBRecoveryMode,
+    self, ColumnFamily, ColumnFamilyDescriptor, DBRawIterator, DBRecoveryMode,
     IteratorMode as RocksIteratorMode, Options, WriteBatch as RWriteBatch, DB,
 };
 use serde::de::DeserializeOwned;
@@ -18,7 +18,15 @@ use solana_sdk::{
 };
 use solana_storage_proto::convert::generated;
 use solana_transaction_status::TransactionStatusMeta;
-use std::{collections::HashMap, fs, marker::PhantomData, path::Path, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Debug,
+    fs,
+    marker::PhantomData,
+    ops::Bound,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
 use thiserror::Error;
 
 const MAX_WRITE_BUFFER_SIZE: u64 = 256 * 1024 * 1024; // 256MB
@@ -56,6 +64,8 @@ const REWARDS_CF: &str = "rewards";
 const BLOCKTIME_CF: &str = "blocktime";
 /// Column family for Performance Samples
 const PERF_SAMPLES_CF: &str = "perf_samples";
+/// Column family for the last slot whose transaction statuses were purged
+const PURGED_TRANSACTION_STATUS_SLOT_CF: &str = "purged_transaction_status_slot";
 
 #[derive(Error, Debug)]
 pub enum BlockstoreError {
@@ -76,6 +86,7 @@ pub enum BlockstoreError {
     ProtobufEncodeError(#[from] prost::EncodeError),
     ProtobufDecodeError(#[from] prost::DecodeError),
     ParentEntriesUnavailable,
+    SlotUnavailable,
 }
 pub type Result<T> = std::result::Result<T, BlockstoreError>;
 
@@ -151,6 +162,10 @@ pub mod columns {
     #[derive(Debug)]
     /// The performance samples column
     pub struct PerfSamples;
+
+    #[derive(Debug)]
+    /// The purged transaction status slot column, holding a single slot under index 0
+    pub struct PurgedTransactionStatusSlot;
 }
 
 pub enum AccessType {
@@ -202,8 +217,131 @@ impl From<BlockstoreRecoveryMode> for DBRecoveryMode {
     }
 }
 
+fn columns() -> Vec<&'static str> {
+    use columns::{
+        AddressSignatures, Blocktime, DeadSlots, DuplicateSlots, ErasureMeta, Index, Orphans,
+        PerfSamples, PurgedTransactionStatusSlot, Rewards, Root, ShredCode, ShredData, SlotMeta,
+        TransactionStatus, TransactionStatusIndex,
+    };
+
+    vec![
+        ErasureMeta::NAME,
+        DeadSlots::NAME,
+        DuplicateSlots::NAME,
+        Index::NAME,
+        Orphans::NAME,
+        Root::NAME,
+        SlotMeta::NAME,
+        ShredData::NAME,
+        ShredCode::NAME,
+        TransactionStatus::NAME,
+        AddressSignatures::NAME,
+        TransactionStatusIndex::NAME,
+        Rewards::NAME,
+        Blocktime::NAME,
+        PerfSamples::NAME,
+        PurgedTransactionStatusSlot::NAME,
+    ]
+}
+
+/// Where an iterator over a column family starts, by raw key
+enum StorageIteratorMode<'a> {
+    Start,
+    End,
+    From(&'a [u8], IteratorDirection),
+}
+
+type StorageIterator<'a> = Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a>;
+
+/// A cursor over the raw keys and values of a column family
+pub trait RawIterator {
+    fn seek(&mut self, key: &[u8]);
+    fn seek_to_first(&mut self);
+    fn next(&mut self);
+    fn valid(&self) -> bool;
+    fn key(&self) -> Option<&[u8]>;
+    fn value(&self) -> Option<&[u8]>;
+}
+
+impl RawIterator for DBRawIterator<'_> {
+    fn seek(&mut self, key: &[u8]) {
+        DBRawIterator::seek(self, key)
+    }
+
+    fn seek_to_first(&mut self) {
+        DBRawIterator::seek_to_first(self)
+    }
+
+    fn next(&mut self) {
+        DBRawIterator::next(self)
+    }
+
+    fn valid(&self) -> bool {
+        DBRawIterator::valid(self)
+    }
+
+    fn key(&self) -> Option<&[u8]> {
+        DBRawIterator::key(self)
+    }
+
+    fn value(&self) -> Option<&[u8]> {
+        DBRawIterator::value(self)
+    }
+}
+
 #[derive(Debug)]
-struct Rocks(rocksdb::DB, ActualAccessType);
+enum WriteOp {
+    Put {
+        cf: &'static str,
+        key: Vec<u8>,
+        value: Vec<u8>,
+    },
+    Delete {
+        cf: &'static str,
+        key: Vec<u8>,
+    },
+    // Deletes the keys in [from, to)
+    DeleteRange {
+        cf: &'static str,
+        from: Vec<u8>,
+        to: Vec<u8>,
+    },
+}
+
+/// The key-value store holding the column families of a `Database`
+trait Storage: Debug + Send + Sync {
+    fn get_cf(&self, cf: &'static str, key: &[u8]) -> Result<Option<Vec<u8>>>;
+
+    fn put_cf(&self, cf: &'static str, key: &[u8], value: &[u8]) -> Result<()>;
+
+    fn iterator_cf(
+        &self,
+        cf: &'static str,
+        iterator_mode: StorageIteratorMode<'_>,
+    ) -> StorageIterator<'_>;
+
+    fn raw_iterator_cf(&self, cf: &'static str) -> Box<dyn RawIterator + '_>;
+
+    /// A batch that is written to this backend
+    fn batch(&self) -> WriteBatch<'_>;
+
+    fn compact_range_cf(&self, cf: &'static str, from: &[u8], to: &[u8]);
+
+    /// The bytes held by a column family
+    fn cf_size(&self, cf: &'static str) -> Result<u64>;
+
+    /// The bytes held by all the column families
+    fn storage_size(&self) -> Result<u64>;
+
+    fn is_primary_access(&self) -> bool;
+}
+
+#[derive(Debug)]
+struct Rocks {
+    db: rocksdb::DB,
+    access_type: ActualAccessType,
+    path: PathBuf,
+}
 
 impl Rocks {
     fn open(
@@ -213,8 +351,8 @@ impl Rocks {
     ) -> Result<Rocks> {
         use columns::{
             AddressSignatures, Blocktime, DeadSlots, DuplicateSlots, ErasureMeta, Index, Orphans,
-            PerfSamples, Rewards, Root, ShredCode, ShredData, SlotMeta, TransactionStatus,
-            TransactionStatusIndex,
+            PerfSamples, PurgedTransactionStatusSlot, Rewards, Root, ShredCode, ShredData,
+            SlotMeta, TransactionStatus, TransactionStatusIndex,
         };
 
         fs::create_dir_all(&path)?;
@@ -259,6 +397,10 @@ impl Rocks {
             ColumnFamilyDescriptor::new(Blocktime::NAME, get_cf_options(&access_type));
         let perf_samples_cf_descriptor =
             ColumnFamilyDescriptor::new(PerfSamples::NAME, get_cf_options(&access_type));
+        let purged_transaction_status_slot_cf_descriptor = ColumnFamilyDescriptor::new(
+            PurgedTransactionStatusSlot::NAME,
+            get_cf_options(&access_type),
+        );
 
         let cfs = vec![
             (SlotMeta::NAME, meta_cf_descriptor),
@@ -279,19 +421,28 @@ impl Rocks {
             (Rewards::NAME, rewards_cf_descriptor),
             (Blocktime::NAME, blocktime_cf_descriptor),
             (PerfSamples::NAME, perf_samples_cf_descriptor),
+            (
+                PurgedTransactionStatusSlot::NAME,
+                purged_transaction_status_slot_cf_descriptor,
+            ),
         ];
 
         // Open the database
         let db = match access_type {
-            AccessType::PrimaryOnly | AccessType::PrimaryOnlyForMaintenance => Rocks(
-                DB::open_cf_descriptors(&db_options, path, cfs.into_iter().map(|c| c.1))?,
-                ActualAccessType::Primary,
-            ),
+            AccessType::PrimaryOnly | AccessType::PrimaryOnlyForMaintenance => Rocks {
+                db: DB::open_cf_descriptors(&db_options, path, cfs.into_iter().map(|c| c.1))?,
+                access_type: ActualAccessType::Primary,
+                path: path.to_path_buf(),
+            },
             AccessType::TryPrimaryThenSecondary => {
                 let names: Vec<_> = cfs.iter().map(|c| c.0).collect();
 
                 match DB::open_cf_descriptors(&db_options, path, cfs.into_iter().map(|c| c.1)) {
-                    Ok(db) => Rocks(db, ActualAccessType::Primary),
+                    Ok(db) => Rocks {
+                        db,
+                        access_type: ActualAccessType::Primary,
+                        path: path.to_path_buf(),
+                    },
                     Err(err) => {
                         let secondary_path = path.join("solana-secondary");
 
@@ -302,10 +453,16 @@ impl Rocks {
                         // This is needed according to https://github.com/facebook/rocksdb/wiki/Secondary-instance
                         db_options.set_max_open_files(-1);
 
-                        Rocks(
-                            DB::open_cf_as_secondary(&db_options, path, &secondary_path, names)?,
-                            ActualAccessType::Secondary,
-                        )
+                        Rocks {
+                            db: DB::open_cf_as_secondary(
+                                &db_options,
+                                path,
+                                &secondary_path,
+                                names,
+                            )?,
+                            access_type: ActualAccessType::Secondary,
+                            path: path.to_path_buf(),
+                        }
                     }
                 }
             }
@@ -314,32 +471,6 @@ impl Rocks {
         Ok(db)
     }
 
-    fn columns(&self) -> Vec<&'static str> {
-        use columns::{
-            AddressSignatures, Blocktime, DeadSlots, DuplicateSlots, ErasureMeta, Index, Orphans,
-            PerfSamples, Rewards, Root, ShredCode, ShredData, SlotMeta, TransactionStatus,
-            TransactionStatusIndex,
-        };
-
-        vec![
-            ErasureMeta::NAME,
-            DeadSlots::NAME,
-            DuplicateSlots::NAME,
-            Index::NAME,
-            Orphans::NAME,
-            Root::NAME,
-            SlotMeta::NAME,
-            ShredData::NAME,
-            ShredCode::NAME,
-            TransactionStatus::NAME,
-            AddressSignatures::NAME,
-            TransactionStatusIndex::NAME,
-            Rewards::NAME,
-            Blocktime::NAME,
-            PerfSamples::NAME,
-        ]
-    }
-
     fn destroy(path: &Path) -> Result<()> {
         DB::destroy(&Options::default(), path)?;
 
@@ -347,52 +478,373 @@ impl Rocks {
     }
 
     fn cf_handle(&self, cf: &str) -> &ColumnFamily {
-        self.0
+        self.db
             .cf_handle(cf)
             .expect("should never get an unknown column")
     }
+}
 
-    fn get_cf(&self, cf: &ColumnFamily, key: &[u8]) -> Result<Option<Vec<u8>>> {
-        let opt = self.0.get_cf(cf, key)?.map(|db_vec| db_vec.to_vec());
+impl Storage for Rocks {
+    fn get_cf(&self, cf: &'static str, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        let opt = self
+            .db
+            .get_cf(self.cf_handle(cf), key)?
+            .map(|db_vec| db_vec.to_vec());
         Ok(opt)
     }
 
-    fn put_cf(&self, cf: &ColumnFamily, key: &[u8], value: &[u8]) -> Result<()> {
-        self.0.put_cf(cf, key, value)?;
+    fn put_cf(&self, cf: &'static str, key: &[u8], value: &[u8]) -> Result<()> {
+        self.db.put_cf(self.cf_handle(cf), key, value)?;
         Ok(())
     }
 
-    fn iterator_cf<C>(&self, cf: &ColumnFamily, iterator_mode: IteratorMode<C::Index>) -> DBIterator
-    where
-        C: Column,
-    {
-        let start_key;
+    fn iterator_cf(
+        &self,
+        cf: &'static str,
+        iterator_mode: StorageIteratorMode<'_>,
+    ) -> StorageIterator<'_> {
         let iterator_mode = match iterator_mode {
-            IteratorMode::From(start_from, direction) => {
-                start_key = C::key(start_from);
-                RocksIteratorMode::From(&start_key, direction)
+            StorageIteratorMode::From(start_key, direction) => {
+                RocksIteratorMode::From(start_key, direction)
             }
-            IteratorMode::Start => RocksIteratorMode::Start,
-            IteratorMode::End => RocksIteratorMode::End,
+            StorageIteratorMode::Start => RocksIteratorMode::Start,
+            StorageIteratorMode::End => RocksIteratorMode::End,
         };
-        self.0.iterator_cf(cf, iterator_mode)
+        Box::new(self.db.iterator_cf(self.cf_handle(cf), iterator_mode))
     }
 
-    fn raw_iterator_cf(&self, cf: &ColumnFamily) -> DBRawIterator {
-        self.0.raw_iterator_cf(cf)
+    fn raw_iterator_cf(&self, cf: &'static str) -> Box<dyn RawIterator + '_> {
+        Box::new(self.db.raw_iterator_cf(self.cf_handle(cf)))
     }
 
-    fn batch(&self) -> RWriteBatch {
-        RWriteBatch::default()
+    fn batch(&self) -> WriteBatch<'_> {
+        let map = columns()
+            .into_iter()
+            .map(|cf| (cf, self.cf_handle(cf)))
+            .collect();
+        WriteBatch {
+            batch: Box::new(RocksWriteBatch {
+                rocks: self,
+                write_batch: RWriteBatch::default(),
+                map,
+            }),
+        }
+    }
+
+    fn compact_range_cf(&self, cf: &'static str, from: &[u8], to: &[u8]) {
+        self.db
+            .compact_range_cf(self.cf_handle(cf), Some(from), Some(to));
+    }
+
+    /// The bytes held by a column family: its live SST files and its mem tables
+    fn cf_size(&self, cf: &'static str) -> Result<u64> {
+        let cf = self.cf_handle(cf);
+        let sst_files_size = self
+            .db
+            .property_int_value_cf(cf, "rocksdb.live-sst-files-size")?
+            .unwrap_or(0);
+        let mem_tables_size = self
+            .db
+            .property_int_value_cf(cf, "rocksdb.size-all-mem-tables")?
+            .unwrap_or(0);
+        Ok(sst_files_size + mem_tables_size)
+    }
+
+    fn storage_size(&self) -> Result<u64> {
+        Ok(fs_extra::dir::get_size(&self.path)?)
+    }
+
+    fn is_primary_access(&self) -> bool {
+        self.access_type == ActualAccessType::Primary
+    }
+}
+
+type MemoryColumn = BTreeMap<Vec<u8>, Vec<u8>>;
+
+/// Column families held in memory, for tests and tools that don't need the ledger to persist
+#[derive(Debug)]
+struct Memory {
+    columns: RwLock<HashMap<&'static str, MemoryColumn>>,
+}
+
+impl Memory {
+    fn new() -> Self {
+        Self {
+            columns: RwLock::new(
+                columns()
+                    .into_iter()
+                    .map(|cf| (cf, MemoryColumn::new()))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Copies every column family of `storage`
+    fn new_from(storage: &dyn Storage) -> Self {
+        let memory = Self::new();
+        {
+            let mut columns = memory.columns.write().unwrap();
+            for (cf, column) in columns.iter_mut() {
+                for (key, value) in storage.iterator_cf(cf, StorageIteratorMode::Start) {
+                    column.insert(key.into_vec(), value.into_vec());
+                }
+            }
+        }
+        memory
+    }
+
+    /// The first entry of a column family within the given bounds, searching forward or in
+    /// reverse
+    fn first_in_range(
+        &self,
+        cf: &str,
+        bounds: (Bound<&[u8]>, Bound<&[u8]>),
+        direction: IteratorDirection,
+    ) -> Option<(Vec<u8>, Vec<u8>)> {
+        let columns = self.columns.read().unwrap();
+        let mut range = columns[cf].range::<[u8], _>(bounds);
+        let entry = match direction {
+            IteratorDirection::Forward => range.next(),
+            IteratorDirection::Reverse => range.next_back(),
+        };
+        entry.map(|(key, value)| (key.clone(), value.clone()))
+    }
+}
+
+/// Iterates over a column family without holding its lock between items, so the column family
+/// can be written to while being iterated over
+struct MemoryIterator<'a> {
+    memory: &'a Memory,
+    cf: &'static str,
+    direction: IteratorDirection,
+    // The key to resume from, None once exhausted
+    bound: Option<Bound<Vec<u8>>>,
+}
+
+impl Iterator for MemoryIterator<'_> {
+    type Item = (Box<[u8]>, Box<[u8]>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let bound = self.bound.take()?;
+        let bound = match &bound {
+            Bound::Included(key) => Bound::Included(&key[..]),
+            Bound::Excluded(key) => Bound::Excluded(&key[..]),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+        let bounds = match self.direction {
+            IteratorDirection::Forward => (bound, Bound::Unbounded),
+            IteratorDirection::Reverse => (Bound::Unbounded, bound),
+        };
+        let (key, value) = self
+            .memory
+            .first_in_range(self.cf, bounds, self.direction)?;
+        self.bound = Some(Bound::Excluded(key.clone()));
+        Some((key.into_boxed_slice(), value.into_boxed_slice()))
     }
+}
 
-    fn write(&self, batch: RWriteBatch) -> Result<()> {
-        self.0.write(batch)?;
+struct MemoryRawIterator<'a> {
+    memory: &'a Memory,
+    cf: &'static str,
+    current: Option<(Vec<u8>, Vec<u8>)>,
+}
+
+impl RawIterator for MemoryRawIterator<'_> {
+    fn seek(&mut self, key: &[u8]) {
+        self.current = self.memory.first_in_range(
+            self.cf,
+            (Bound::Included(key), Bound::Unbounded),
+            IteratorDirection::Forward,
+        );
+    }
+
+    fn seek_to_first(&mut self) {
+        self.current = self.memory.first_in_range(
+            self.cf,
+            (Bound::Unbounded, Bound::Unbounded),
+            IteratorDirection::Forward,
+        );
+    }
+
+    fn next(&mut self) {
+        if let Some((key, _)) = self.current.take() {
+            self.current = self.memory.first_in_range(
+                self.cf,
+                (Bound::Excluded(&key[..]), Bound::Unbounded),
+                IteratorDirection::Forward,
+            );
+        }
+    }
+
+    fn valid(&self) -> bool {
+        self.current.is_some()
+    }
+
+    fn key(&self) -> Option<&[u8]> {
+        self.current.as_ref().map(|(key, _)| &key[..])
+    }
+
+    fn value(&self) -> Option<&[u8]> {
+        self.current.as_ref().map(|(_, value)| &value[..])
+    }
+}
+
+impl Storage for Memory {
+    fn get_cf(&self, cf: &'static str, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        Ok(self.columns.read().unwrap()[cf].get(key).cloned())
+    }
+
+    fn put_cf(&self, cf: &'static str, key: &[u8], value: &[u8]) -> Result<()> {
+        self.columns
+            .write()
+            .unwrap()
+            .get_mut(cf)
+            .expect("should never get an unknown column")
+            .insert(key.to_vec(), value.to_vec());
         Ok(())
     }
 
+    fn iterator_cf(
+        &self,
+        cf: &'static str,
+        iterator_mode: StorageIteratorMode<'_>,
+    ) -> StorageIterator<'_> {
+        let (bound, direction) = match iterator_mode {
+            StorageIteratorMode::Start => (Bound::Unbounded, IteratorDirection::Forward),
+            StorageIteratorMode::End => (Bound::Unbounded, IteratorDirection::Reverse),
+            StorageIteratorMode::From(key, direction) => (Bound::Included(key.to_vec()), direction),
+        };
+        Box::new(MemoryIterator {
+            memory: self,
+            cf,
+            direction,
+            bound: Some(bound),
+        })
+    }
+
+    fn raw_iterator_cf(&self, cf: &'static str) -> Box<dyn RawIterator + '_> {
+        Box::new(MemoryRawIterator {
+            memory: self,
+            cf,
+            current: None,
+        })
+    }
+
+    fn batch(&self) -> WriteBatch<'_> {
+        WriteBatch {
+            batch: Box::new(MemoryWriteBatch {
+                memory: self,
+                ops: vec![],
+            }),
+        }
+    }
+
+    fn compact_range_cf(&self, _cf: &'static str, _from: &[u8], _to: &[u8]) {}
+
+    fn cf_size(&self, cf: &'static str) -> Result<u64> {
+        Ok(self.columns.read().unwrap()[cf]
+            .iter()
+            .map(|(key, value)| (key.len() + value.len()) as u64)
+            .sum())
+    }
+
+    fn storage_size(&self) -> Result<u64> {
+        columns().into_iter().map(|cf| self.cf_size(cf)).sum()
+    }
+
     fn is_primary_access(&self) -> bool {
-        self.1 == ActualAccessType::Primary
+        true
+    }
+}
+
+/// Operations collected in the form the backend that created the batch applies
+trait StorageWriteBatch {
+    fn put_cf(&mut self, cf: &'static str, key: Vec<u8>, value: &[u8]);
+
+    fn delete_cf(&mut self, cf: &'static str, key: Vec<u8>);
+
+    /// Deletes the keys in [from, to)
+    fn delete_range_cf(&mut self, cf: &'static str, from: Vec<u8>, to: Vec<u8>);
+
+    /// Applies all the operations atomically, to the backend that created the batch
+    fn write(self: Box<Self>) -> Result<()>;
+}
+
+struct RocksWriteBatch<'a> {
+    rocks: &'a Rocks,
+    write_batch: RWriteBatch,
+    map: HashMap<&'static str, &'a ColumnFamily>,
+}
+
+impl StorageWriteBatch for RocksWriteBatch<'_> {
+    fn put_cf(&mut self, cf: &'static str, key: Vec<u8>, value: &[u8]) {
+        self.write_batch.put_cf(self.map[cf], key, value);
+    }
+
+    fn delete_cf(&mut self, cf: &'static str, key: Vec<u8>) {
+        self.write_batch.delete_cf(self.map[cf], key);
+    }
+
+    fn delete_range_cf(&mut self, cf: &'static str, from: Vec<u8>, to: Vec<u8>) {
+        self.write_batch.delete_range_cf(self.map[cf], from, to);
+    }
+
+    fn write(self: Box<Self>) -> Result<()> {
+        self.rocks.db.write(self.write_batch)?;
+        Ok(())
+    }
+}
+
+struct MemoryWriteBatch<'a> {
+    memory: &'a Memory,
+    ops: Vec<WriteOp>,
+}
+
+impl StorageWriteBatch for MemoryWriteBatch<'_> {
+    fn put_cf(&mut self, cf: &'static str, key: Vec<u8>, value: &[u8]) {
+        self.ops.push(WriteOp::Put {
+            cf,
+            key,
+            value: value.to_vec(),
+        });
+    }
+
+    fn delete_cf(&mut self, cf: &'static str, key: Vec<u8>) {
+        self.ops.push(WriteOp::Delete { cf, key });
+    }
+
+    fn delete_range_cf(&mut self, cf: &'static str, from: Vec<u8>, to: Vec<u8>) {
+        self.ops.push(WriteOp::DeleteRange { cf, from, to });
+    }
+
+    fn write(self: Box<Self>) -> Result<()> {
+        let mut columns = self.memory.columns.write().unwrap();
+        for op in self.ops {
+            match op {
+                WriteOp::Put { cf, key, value } => {
+                    columns.get_mut(cf).unwrap().insert(key, value);
+                }
+                WriteOp::Delete { cf, key } => {
+                    columns.get_mut(cf).unwrap().remove(&key);
+                }
+                WriteOp::DeleteRange { cf, from, to } => {
+                    if from < to {
+                        let column = columns.get_mut(cf).unwrap();
+                        let keys: Vec<_> = column
+                            .range::<[u8], _>((
+                                Bound::Included(&from[..]),
+                                Bound::Excluded(&to[..]),
+                            ))
+                            .map(|(key, _)| key.clone())
+                            .collect();
+                        for key in keys {
+                            column.remove(&key);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
     }
 }
 
@@ -576,6 +1028,34 @@ impl TypedColumn for columns::PerfSamples {
     type Type = blockstore_meta::PerfSample;
 }
 
+impl Column for columns::PurgedTransactionStatusSlot {
+    type Index = u64;
+
+    fn key(index: u64) -> Vec<u8> {
+        let mut key = vec![0; 8];
+        BigEndian::write_u64(&mut key[..], index);
+        key
+    }
+
+    fn index(key: &[u8]) -> u64 {
+        BigEndian::read_u64(&key[..8])
+    }
+
+    fn primary_index(index: u64) -> u64 {
+        index
+    }
+
+    fn as_index(index: u64) -> u64 {
+        index
+    }
+}
+impl ColumnName for columns::PurgedTransactionStatusSlot {
+    const NAME: &'static str = PURGED_TRANSACTION_STATUS_SLOT_CF;
+}
+impl TypedColumn for columns::PurgedTransactionStatusSlot {
+    type Type = Slot;
+}
+
 impl Column for columns::ShredCode {
     type Index = (u64, u64);
 
@@ -711,8 +1191,7 @@ impl TypedColumn for columns::ErasureMeta {
 
 #[derive(Debug, Clone)]
 pub struct Database {
-    backend: Arc<Rocks>,
-    path: Arc<Path>,
+    backend: Arc<dyn Storage>,
 }
 
 #[derive(Debug, Clone)]
@@ -720,13 +1199,27 @@ pub struct LedgerColumn<C>
 where
     C: Column,
 {
-    backend: Arc<Rocks>,
+    backend: Arc<dyn Storage>,
     column: PhantomData<C>,
 }
 
+/// Operations to apply atomically to the `Database` that created the batch
 pub struct WriteBatch<'a> {
-    write_batch: RWriteBatch,
-    map: HashMap<&'static str, &'a ColumnFamily>,
+    batch: Box<dyn StorageWriteBatch + 'a>,
+}
+
+fn storage_iterator_mode<C: Column>(
+    iterator_mode: IteratorMode<C::Index>,
+    start_key: &mut Vec<u8>,
+) -> StorageIteratorMode<'_> {
+    match iterator_mode {
+        IteratorMode::From(start_from, direction) => {
+            *start_key = C::key(start_from);
+            StorageIteratorMode::From(start_key, direction)
+        }
+        IteratorMode::Start => StorageIteratorMode::Start,
+        IteratorMode::End => StorageIteratorMode::End,
+    }
 }
 
 impl Database {
@@ -737,9 +1230,28 @@ impl Database {
     ) -> Result<Self> {
         let backend = Arc::new(Rocks::open(path, access_type, recovery_mode)?);
 
+        Ok(Database { backend })
+    }
+
+    /// An empty database held in memory
+    pub fn open_in_memory() -> Self {
+        Database {
+            backend: Arc::new(Memory::new()),
+        }
+    }
+
+    /// A database held in memory, starting from a copy of the database at `path`
+    pub fn open_in_memory_from(
+        path: &Path,
+        recovery_mode: Option<BlockstoreRecoveryMode>,
+    ) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::open_in_memory());
+        }
+        let rocks = Rocks::open(path, AccessType::TryPrimaryThenSecondary, recovery_mode)?;
+
         Ok(Database {
-            backend,
-            path: Arc::from(path),
+            backend: Arc::new(Memory::new_from(&rocks)),
         })
     }
 
@@ -753,7 +1265,7 @@ impl Database {
     where
         C: TypedColumn + ColumnName,
     {
-        if let Some(serialized_value) = self.backend.get_cf(self.cf_handle::<C>(), &C::key(key))? {
+        if let Some(serialized_value) = self.backend.get_cf(C::NAME, &C::key(key))? {
             let value = deserialize(&serialized_value)?;
 
             Ok(Some(value))
@@ -769,19 +1281,12 @@ impl Database {
     where
         C: Column + ColumnName,
     {
-        let cf = self.cf_handle::<C>();
-        let iter = self.backend.iterator_cf::<C>(cf, iterator_mode);
+        let mut start_key = vec![];
+        let iterator_mode = storage_iterator_mode::<C>(iterator_mode, &mut start_key);
+        let iter = self.backend.iterator_cf(C::NAME, iterator_mode);
         Ok(iter.map(|(key, value)| (C::index(&key), value)))
     }
 
-    #[inline]
-    pub fn cf_handle<C: ColumnName>(&self) -> &ColumnFamily
-    where
-        C: Column + ColumnName,
-    {
-        self.backend.cf_handle(C::NAME)
-    }
-
     pub fn column<C>(&self) -> LedgerColumn<C>
     where
         C: Column + ColumnName,
@@ -793,28 +1298,24 @@ impl Database {
     }
 
     #[inline]
-    pub fn raw_iterator_cf(&self, cf: &ColumnFamily) -> Result<DBRawIterator> {
-        Ok(self.backend.raw_iterator_cf(cf))
+    pub fn raw_iterator_cf<C>(&self) -> Result<Box<dyn RawIterator + '_>>
+    where
+        C: Column + ColumnName,
+    {
+        Ok(self.backend.raw_iterator_cf(C::NAME))
     }
 
-    pub fn batch(&self) -> Result<WriteBatch> {
-        let write_batch = self.backend.batch();
-        let map = self
-            .backend
-            .columns()
-            .into_iter()
-            .map(|desc| (desc, self.backend.cf_handle(desc)))
-            .collect();
-
-        Ok(WriteBatch { write_batch, map })
+    pub fn batch(&self) -> Result<WriteBatch<'_>> {
+        Ok(self.backend.batch())
     }
 
-    pub fn write(&self, batch: WriteBatch) -> Result<()> {
-        self.backend.write(batch.write_batch)
+    /// Writes `batch`, which always goes to the database it was created from
+    pub fn write(&self, batch: WriteBatch<'_>) -> Result<()> {
+        batch.batch.write()
     }
 
     pub fn storage_size(&self) -> Result<u64> {
-        Ok(fs_extra::dir::get_size(&self.path)?)
+        self.backend.storage_size()
     }
 
     // Adds a range to delete to the given write batch
@@ -822,10 +1323,9 @@ impl Database {
     where
         C: Column + ColumnName,
     {
-        let cf = self.cf_handle::<C>();
         let from_index = C::as_index(from);
         let to_index = C::as_index(to);
-        batch.delete_range_cf::<C>(cf, from_index, to_index)
+        batch.delete_range_cf::<C>(from_index, to_index)
     }
 
     pub fn is_primary_access(&self) -> bool {
@@ -838,15 +1338,16 @@ where
     C: Column + ColumnName,
 {
     pub fn get_bytes(&self, key: C::Index) -> Result<Option<Vec<u8>>> {
-        self.backend.get_cf(self.handle(), &C::key(key))
+        self.backend.get_cf(C::NAME, &C::key(key))
     }
 
     pub fn iter(
         &self,
         iterator_mode: IteratorMode<C::Index>,
     ) -> Result<impl Iterator<Item = (C::Index, Box<[u8]>)> + '_> {
-        let cf = self.handle();
-        let iter = self.backend.iterator_cf::<C>(cf, iterator_mode);
+        let mut start_key = vec![];
+        let iterator_mode = storage_iterator_mode::<C>(iterator_mode, &mut start_key);
+        let iter = self.backend.iterator_cf(C::NAME, iterator_mode);
         Ok(iter.map(|(key, value)| (C::index(&key), value)))
     }
 
@@ -888,27 +1389,26 @@ where
     where
         C::Index: PartialOrd + Copy,
     {
-        let cf = self.handle();
-        let from = Some(C::key(C::as_index(from)));
-        let to = Some(C::key(C::as_index(to)));
-        self.backend.0.compact_range_cf(cf, from, to);
+        let from = C::key(C::as_index(from));
+        let to = C::key(C::as_index(to));
+        self.backend.compact_range_cf(C::NAME, &from, &to);
         Ok(true)
     }
 
-    #[inline]
-    pub fn handle(&self) -> &ColumnFamily {
-        self.backend.cf_handle(C::NAME)
+    /// The bytes this column occupies, on disk and in memory
+    pub fn storage_size(&self) -> Result<u64> {
+        self.backend.cf_size(C::NAME)
     }
 
     #[cfg(test)]
     pub fn is_empty(&self) -> Result<bool> {
-        let mut iter = self.backend.raw_iterator_cf(self.handle());
+        let mut iter = self.backend.raw_iterator_cf(C::NAME);
         iter.seek_to_first();
         Ok(!iter.valid())
     }
 
     pub fn put_bytes(&self, key: C::Index, value: &[u8]) -> Result<()> {
-        self.backend.put_cf(self.handle(), &C::key(key), value)
+        self.backend.put_cf(C::NAME, &C::key(key), value)
     }
 }
 
@@ -917,7 +1417,7 @@ where
     C: TypedColumn + ColumnName,
 {
     pub fn get(&self, key: C::Index) -> Result<Option<C::Type>> {
-        if let Some(serialized_value) = self.backend.get_cf(self.handle(), &C::key(key))? {
+        if let Some(serialized_value) = self.backend.get_cf(C::NAME, &C::key(key))? {
             let value = deserialize(&serialized_value)?;
 
             Ok(Some(value))
@@ -930,7 +1430,7 @@ where
         let serialized_value = serialize(value)?;
 
         self.backend
-            .put_cf(self.handle(), &C::key(key), &serialized_value)
+            .put_cf(C::NAME, &C::key(key), &serialized_value)
     }
 }
 
@@ -942,7 +1442,7 @@ where
         &self,
         key: C::Index,
     ) -> Result<Option<C::Type>> {
-        if let Some(serialized_value) = self.backend.get_cf(self.handle(), &C::key(key))? {
+        if let Some(serialized_value) = self.backend.get_cf(C::NAME, &C::key(key))? {
             let value = match C::Type::decode(&serialized_value[..]) {
                 Ok(value) => value,
                 Err(_) => deserialize::<T>(&serialized_value)?.into(),
@@ -954,7 +1454,7 @@ where
     }
 
     pub fn get_protobuf(&self, key: C::Index) -> Result<Option<C::Type>> {
-        if let Some(serialized_value) = self.backend.get_cf(self.handle(), &C::key(key))? {
+        if let Some(serialized_value) = self.backend.get_cf(C::NAME, &C::key(key))? {
             Ok(Some(C::Type::decode(&serialized_value[..])?))
         } else {
             Ok(None)
@@ -964,19 +1464,18 @@ where
     pub fn put_protobuf(&self, key: C::Index, value: &C::Type) -> Result<()> {
         let mut buf = Vec::with_capacity(value.encoded_len());
         value.encode(&mut buf)?;
-        self.backend.put_cf(self.handle(), &C::key(key), &buf)
+        self.backend.put_cf(C::NAME, &C::key(key), &buf)
     }
 }
 
 impl<'a> WriteBatch<'a> {
     pub fn put_bytes<C: Column + ColumnName>(&mut self, key: C::Index, bytes: &[u8]) -> Result<()> {
-        self.write_batch
-            .put_cf(self.get_cf::<C>(), &C::key(key), bytes);
+        self.batch.put_cf(C::NAME, C::key(key), bytes);
         Ok(())
     }
 
     pub fn delete<C: Column + ColumnName>(&mut self, key: C::Index) -> Result<()> {
-        self.write_batch.delete_cf(self.get_cf::<C>(), &C::key(key));
+        self.batch.delete_cf(C::NAME, C::key(key));
         Ok(())
     }
 
@@ -986,24 +1485,16 @@ impl<'a> WriteBatch<'a> {
         value: &C::Type,
     ) -> Result<()> {
         let serialized_value = serialize(&value)?;
-        self.write_batch
-            .put_cf(self.get_cf::<C>(), &C::key(key), &serialized_value);
-        Ok(())
-    }
-
-    #[inline]
-    fn get_cf<C: Column + ColumnName>(&self) -> &'a ColumnFamily {
-        self.map[C::NAME]
+        self.put_bytes::<C>(key, &serialized_value)
     }
 
-    pub fn delete_range_cf<C: Column>(
+    pub fn delete_range_cf<C: Column + ColumnName>(
         &mut self,
-        cf: &ColumnFamily,
         from: C::Index,
         to: C::Index,
     ) -> Result<()> {
-        self.write_batch
-            .delete_range_cf(cf, C::key(from), C::key(to));
+        self.batch
+            .delete_range_cf(C::NAME, C::key(from), C::key(to));
         Ok(())
     }
 }
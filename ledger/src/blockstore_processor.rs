@@ -18,7 +18,7 @@ use solana_rayon_threadlimit::get_thread_count;
 use solana_runtime::{
     accounts_index::AccountIndex,
     bank::{
-        Bank, ExecuteTimings, InnerInstructionsList, TransactionBalancesSet,
+        Bank, BpfTraceCallback, ExecuteTimings, InnerInstructionsList, TransactionBalancesSet,
         TransactionExecutionResult, TransactionLogMessages, TransactionResults,
     },
     bank_forks::BankForks,
@@ -35,7 +35,9 @@ use solana_sdk::{
     hash::Hash,
     pubkey::Pubkey,
     signature::{Keypair, Signature},
-    transaction::{Result, Transaction, TransactionError},
+    transaction::{
+        Result, Transaction, TransactionError, TransactionReturnData, VersionedTransaction,
+    },
 };
 use solana_transaction_status::token_balances::{
     collect_token_balances, TransactionTokenBalancesSet,
@@ -103,6 +105,7 @@ fn get_first_error(
 
 fn execute_batch(
     batch: &TransactionBatch,
+    versioned_transactions: &[VersionedTransaction],
     bank: &Arc<Bank>,
     transaction_status_sender: Option<TransactionStatusSender>,
     replay_vote_sender: Option<&ReplayVoteSender>,
@@ -118,7 +121,7 @@ fn execute_batch(
         vec![]
     };
 
-    let (tx_results, balances, inner_instructions, transaction_logs) =
+    let (tx_results, balances, inner_instructions, transaction_logs, transaction_return_data) =
         batch.bank().load_execute_and_commit_transactions(
             batch,
             MAX_PROCESSING_AGE,
@@ -149,12 +152,14 @@ fn execute_batch(
         send_transaction_status_batch(
             bank.clone(),
             batch.transactions(),
+            versioned_transactions,
             batch.iteration_order_vec(),
             execution_results,
             balances,
             token_balances,
             inner_instructions,
             transaction_logs,
+            transaction_return_data,
             transaction_status_sender,
         );
     }
@@ -165,7 +170,7 @@ fn execute_batch(
 
 fn execute_batches(
     bank: &Arc<Bank>,
-    batches: &[TransactionBatch],
+    batches: &[(TransactionBatch, &[VersionedTransaction])],
     entry_callback: Option<&ProcessCallback>,
     transaction_status_sender: Option<TransactionStatusSender>,
     replay_vote_sender: Option<&ReplayVoteSender>,
@@ -177,20 +182,24 @@ fn execute_batches(
             thread_pool.borrow().install(|| {
                 batches
                     .into_par_iter()
-                    .map_with(transaction_status_sender, |sender, batch| {
-                        let mut timings = ExecuteTimings::default();
-                        let result = execute_batch(
-                            batch,
-                            bank,
-                            sender.clone(),
-                            replay_vote_sender,
-                            &mut timings,
-                        );
-                        if let Some(entry_callback) = entry_callback {
-                            entry_callback(bank);
-                        }
-                        (result, timings)
-                    })
+                    .map_with(
+                        transaction_status_sender,
+                        |sender, (batch, versioned_transactions)| {
+                            let mut timings = ExecuteTimings::default();
+                            let result = execute_batch(
+                                batch,
+                                versioned_transactions,
+                                bank,
+                                sender.clone(),
+                                replay_vote_sender,
+                                &mut timings,
+                            );
+                            if let Some(entry_callback) = entry_callback {
+                                entry_callback(bank);
+                            }
+                            (result, timings)
+                        },
+                    )
                     .unzip()
             })
         });
@@ -234,10 +243,23 @@ fn process_entries_with_callback(
     replay_vote_sender: Option<&ReplayVoteSender>,
     timings: &mut ExecuteTimings,
 ) -> Result<()> {
+    // Entries record transactions as they were signed, resolve them into the form the bank
+    // executes up front so that batches can borrow them
+    let resolved_transactions = entries
+        .iter()
+        .map(|entry| {
+            entry
+                .transactions
+                .iter()
+                .map(|tx| bank.resolve_versioned_transaction(tx))
+                .collect::<Result<Vec<_>>>()
+        })
+        .collect::<Result<Vec<_>>>()?;
+
     // accumulator for entries that can be processed in parallel
     let mut batches = vec![];
     let mut tick_hashes = vec![];
-    for entry in entries {
+    for (entry, transactions) in entries.iter().zip(&resolved_transactions) {
         if entry.is_tick() {
             // If it's a tick, save it for later
             tick_hashes.push(entry.hash);
@@ -263,7 +285,7 @@ fn process_entries_with_callback(
         // else loop on processing the entry
         loop {
             let iteration_order = if randomize {
-                let mut iteration_order: Vec<usize> = (0..entry.transactions.len()).collect();
+                let mut iteration_order: Vec<usize> = (0..transactions.len()).collect();
                 iteration_order.shuffle(&mut thread_rng());
                 Some(iteration_order)
             } else {
@@ -271,13 +293,15 @@ fn process_entries_with_callback(
             };
 
             // try to lock the accounts
-            let batch = bank.prepare_batch(&entry.transactions, iteration_order);
+            let batch = bank.prepare_batch(transactions, iteration_order);
 
             let first_lock_err = first_err(batch.lock_results());
 
             // if locking worked
             if first_lock_err.is_ok() {
-                batches.push(batch);
+                // keep the transactions as signed alongside the batch so that their statuses
+                // are recorded against the original message
+                batches.push((batch, &entry.transactions[..]));
                 // done with this entry
                 break;
             }
@@ -291,7 +315,7 @@ fn process_entries_with_callback(
                         "error",
                         format!(
                             "Lock accounts error, entry conflicts with itself, txs: {:?}",
-                            entry.transactions
+                            transactions
                         ),
                         String
                     )
@@ -361,6 +385,8 @@ pub struct ProcessOptions {
     pub full_leader_cache: bool,
     pub dev_halt_at_slot: Option<Slot>,
     pub entry_callback: Option<ProcessCallback>,
+    /// Called with every bank once its slot is replayed and the bank is frozen
+    pub slot_callback: Option<ProcessCallback>,
     pub override_num_threads: Option<usize>,
     pub new_hard_forks: Option<Vec<Slot>>,
     pub frozen_accounts: Vec<Pubkey>,
@@ -368,6 +394,9 @@ pub struct ProcessOptions {
     pub account_indexes: HashSet<AccountIndex>,
     pub accounts_db_caching_enabled: bool,
     pub allow_dead_slots: bool,
+    pub bpf_trace_callback: Option<Arc<dyn BpfTraceCallback + Send + Sync>>,
+    /// ELFs to run in place of the deployed BPF programs, by program id
+    pub program_overrides: HashMap<Pubkey, Vec<u8>>,
 }
 
 pub fn process_blockstore(
@@ -395,6 +424,7 @@ pub fn process_blockstore(
         opts.account_indexes.clone(),
         opts.accounts_db_caching_enabled,
     );
+    bank0.set_bpf_trace_callback(opts.bpf_trace_callback.clone());
     let bank0 = Arc::new(bank0);
     info!("processing ledger for slot 0...");
     let recyclers = VerifyRecyclers::default();
@@ -410,6 +440,7 @@ pub(crate) fn process_blockstore_from_root(
     recyclers: &VerifyRecyclers,
     transaction_status_sender: Option<TransactionStatusSender>,
 ) -> BlockstoreProcessorResult {
+    bank.set_bpf_trace_callback(opts.bpf_trace_callback.clone());
     do_process_blockstore_from_root(
         blockstore,
         Arc::new(bank),
@@ -773,6 +804,7 @@ fn process_bank_0(
 ) {
     assert_eq!(bank0.slot(), 0);
     let mut progress = ConfirmationProgress::new(bank0.last_blockhash());
+    apply_program_overrides(bank0, opts);
     confirm_full_slot(
         blockstore,
         bank0,
@@ -784,6 +816,16 @@ fn process_bank_0(
     )
     .expect("processing for bank 0 must succeed");
     bank0.freeze();
+    if let Some(slot_callback) = &opts.slot_callback {
+        slot_callback(bank0);
+    }
+}
+
+// Replace the programs of `opts.program_overrides` in a bank about to be replayed
+fn apply_program_overrides(bank: &Bank, opts: &ProcessOptions) {
+    for (program_id, elf) in &opts.program_overrides {
+        bank.override_program(program_id, elf);
+    }
 }
 
 // Given a bank, add its children to the pending slots queue if those children slots are
@@ -1070,6 +1112,7 @@ fn process_single_slot(
     transaction_status_sender: Option<TransactionStatusSender>,
     replay_vote_sender: Option<&ReplayVoteSender>,
 ) -> result::Result<(), BlockstoreProcessorError> {
+    apply_program_overrides(bank, opts);
     // Mark corrupt slots as dead so validators don't replay this slot and
     // see DuplicateSignature errors later in ReplayStage
     confirm_full_slot(blockstore, bank, opts, recyclers, progress, transaction_status_sender, replay_vote_sender).map_err(|err| {
@@ -1086,6 +1129,9 @@ fn process_single_slot(
     })?;
 
     bank.freeze(); // all banks handled by this routine are created from complete slots
+    if let Some(slot_callback) = &opts.slot_callback {
+        slot_callback(bank);
+    }
 
     Ok(())
 }
@@ -1093,12 +1139,16 @@ fn process_single_slot(
 pub struct TransactionStatusBatch {
     pub bank: Arc<Bank>,
     pub transactions: Vec<Transaction>,
+    /// The transactions as signed, in the same order as `transactions`, which hold the
+    /// address table lookups their resolved form was loaded from
+    pub versioned_transactions: Vec<VersionedTransaction>,
     pub iteration_order: Option<Vec<usize>>,
     pub statuses: Vec<TransactionExecutionResult>,
     pub balances: TransactionBalancesSet,
     pub token_balances: TransactionTokenBalancesSet,
     pub inner_instructions: Option<Vec<Option<InnerInstructionsList>>>,
     pub transaction_logs: Option<Vec<TransactionLogMessages>>,
+    pub transaction_return_data: Vec<Option<TransactionReturnData>>,
 }
 
 #[derive(Clone)]
@@ -1110,12 +1160,14 @@ pub struct TransactionStatusSender {
 pub fn send_transaction_status_batch(
     bank: Arc<Bank>,
     transactions: &[Transaction],
+    versioned_transactions: &[VersionedTransaction],
     iteration_order: Option<Vec<usize>>,
     statuses: Vec<TransactionExecutionResult>,
     balances: TransactionBalancesSet,
     token_balances: TransactionTokenBalancesSet,
     inner_instructions: Vec<Option<InnerInstructionsList>>,
     transaction_logs: Vec<TransactionLogMessages>,
+    transaction_return_data: Vec<Option<TransactionReturnData>>,
     transaction_status_sender: TransactionStatusSender,
 ) {
     let slot = bank.slot();
@@ -1130,12 +1182,14 @@ pub fn send_transaction_status_batch(
         .send(TransactionStatusBatch {
             bank,
             transactions: transactions.to_vec(),
+            versioned_transactions: versioned_transactions.to_vec(),
             iteration_order,
             statuses,
             balances,
             token_balances,
             inner_instructions,
             transaction_logs,
+            transaction_return_data,
         })
     {
         trace!(
@@ -1181,7 +1235,7 @@ pub fn fill_blockstore_slot_with_ticks(
 pub mod tests {
     use super::*;
     use crate::{
-        entry::{create_ticks, next_entry, next_entry_mut},
+        entry::{create_ticks, next_entry, next_entry_mut, next_hash},
         genesis_utils::{
             create_genesis_config, create_genesis_config_with_leader, GenesisConfigInfo,
         },
@@ -1189,6 +1243,7 @@ pub mod tests {
     use crossbeam_channel::unbounded;
     use matches::assert_matches;
     use rand::{thread_rng, Rng};
+    use solana_address_lookup_table_program::state::{AddressLookupTable, LookupTableMeta};
     use solana_runtime::genesis_utils::{
         self, create_genesis_config_with_vote_accounts, ValidatorVoteKeypairs,
     };
@@ -1196,11 +1251,13 @@ pub mod tests {
         account::Account,
         epoch_schedule::EpochSchedule,
         hash::Hash,
+        instruction::CompiledInstruction,
+        message::{v0, MessageHeader, VersionedMessage},
         pubkey::Pubkey,
         signature::{Keypair, Signer},
-        system_instruction::SystemError,
-        system_transaction,
-        transaction::{Transaction, TransactionError},
+        system_instruction::{self, SystemError},
+        system_program, system_transaction,
+        transaction::{Transaction, TransactionError, VersionedTransaction},
     };
     use solana_vote_program::{
         self,
@@ -2077,6 +2134,34 @@ pub mod tests {
         assert_eq!(*callback_counter.write().unwrap(), 2);
     }
 
+    #[test]
+    fn test_process_ledger_options_slot_callback() {
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(100);
+        let ticks_per_slot = genesis_config.ticks_per_slot;
+        let (ledger_path, blockhash) = create_new_tmp_ledger!(&genesis_config);
+        let blockstore = Blockstore::open(&ledger_path).unwrap();
+
+        // slot 0 <- slot 1 <- slot 2
+        let last_hash =
+            fill_blockstore_slot_with_ticks(&blockstore, ticks_per_slot, 1, 0, blockhash);
+        fill_blockstore_slot_with_ticks(&blockstore, ticks_per_slot, 2, 1, last_hash);
+
+        let replayed_slots: Arc<RwLock<Vec<Slot>>> = Arc::default();
+        let slot_callback = {
+            let replayed_slots = replayed_slots.clone();
+            Arc::new(move |bank: &Bank| {
+                assert!(bank.is_frozen());
+                replayed_slots.write().unwrap().push(bank.slot());
+            })
+        };
+        let opts = ProcessOptions {
+            slot_callback: Some(slot_callback),
+            ..ProcessOptions::default()
+        };
+        process_blockstore(&genesis_config, &blockstore, Vec::new(), opts).unwrap();
+        assert_eq!(*replayed_slots.read().unwrap(), vec![0, 1, 2]);
+    }
+
     #[test]
     fn test_process_entries_tick() {
         let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(1000);
@@ -2260,8 +2345,15 @@ pub mod tests {
         assert_eq!(bank.get_balance(&keypair2.pubkey()), 4);
 
         // Check all accounts are unlocked
-        let txs1 = &entry_1_to_mint.transactions[..];
-        let txs2 = &entry_2_to_3_mint_to_1.transactions[..];
+        let resolve = |entry: &Entry| -> Vec<Transaction> {
+            entry
+                .transactions
+                .iter()
+                .map(|tx| bank.resolve_versioned_transaction(tx).unwrap())
+                .collect()
+        };
+        let txs1 = &resolve(&entry_1_to_mint)[..];
+        let txs2 = &resolve(&entry_2_to_3_mint_to_1)[..];
         let batch1 = bank.prepare_batch(txs1, None);
         for result in batch1.lock_results() {
             assert!(result.is_ok());
@@ -2419,6 +2511,83 @@ pub mod tests {
         assert_eq!(bank.last_blockhash(), blockhash);
     }
 
+    #[test]
+    fn test_process_entries_versioned_transaction() {
+        let GenesisConfigInfo {
+            mut genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config(1000);
+        genesis_utils::activate_all_features(&mut genesis_config);
+        let bank0 = Arc::new(Bank::new(&genesis_config));
+
+        let to = solana_sdk::pubkey::new_rand();
+        let table_address = solana_sdk::pubkey::new_rand();
+        let lookup_table = AddressLookupTable {
+            meta: LookupTableMeta {
+                num_addresses: 1,
+                ..LookupTableMeta::new(mint_keypair.pubkey())
+            },
+            addresses: vec![to],
+        };
+        let mut table_account = Account::new(
+            1,
+            AddressLookupTable::data_len(1),
+            &solana_address_lookup_table_program::id(),
+        );
+        lookup_table
+            .serialize_into(&mut table_account.data)
+            .unwrap();
+        bank0.store_account(&table_address, &table_account);
+        let bank = Arc::new(Bank::new_from_parent(&bank0, &Pubkey::default(), 1));
+
+        // transfer to an account that is only referenced through the lookup table
+        let versioned_transfer = |table_address: Pubkey| {
+            let transfer_ix = system_instruction::transfer(&mint_keypair.pubkey(), &to, 1);
+            let message = v0::Message {
+                header: MessageHeader {
+                    num_required_signatures: 1,
+                    num_readonly_signed_accounts: 0,
+                    num_readonly_unsigned_accounts: 1,
+                },
+                account_keys: vec![mint_keypair.pubkey(), system_program::id()],
+                recent_blockhash: bank.last_blockhash(),
+                instructions: vec![CompiledInstruction {
+                    program_id_index: 1,
+                    accounts: vec![0, 2],
+                    data: transfer_ix.data,
+                }],
+                address_table_lookups: vec![v0::MessageAddressTableLookup {
+                    account_key: table_address,
+                    writable_indexes: vec![0],
+                    readonly_indexes: vec![],
+                }],
+            };
+            VersionedTransaction::try_new(VersionedMessage::V0(message), &[&mint_keypair]).unwrap()
+        };
+        let versioned_entry = |prev_hash: &Hash, tx: VersionedTransaction| Entry {
+            num_hashes: 1,
+            hash: next_hash(prev_hash, 1, &[tx.clone()]),
+            transactions: vec![tx],
+        };
+
+        let blockhash = bank.last_blockhash();
+        let entries = vec![versioned_entry(
+            &blockhash,
+            versioned_transfer(table_address),
+        )];
+        assert!(entries.verify_transaction_signatures(true));
+        assert_eq!(process_entries(&bank, &entries, true, None, None), Ok(()));
+        assert_eq!(bank.get_balance(&to), 1);
+
+        // the block is invalid if the addresses of a transaction can't be resolved
+        let entry = versioned_entry(&blockhash, versioned_transfer(Pubkey::new_unique()));
+        assert_eq!(
+            process_entries(&bank, &[entry], true, None, None),
+            Err(TransactionError::AddressLookupTableNotFound)
+        );
+    }
+
     #[test]
     fn test_process_entry_tx_random_execution_with_error() {
         let GenesisConfigInfo {
@@ -3030,6 +3199,7 @@ pub mod tests {
             _balances,
             _inner_instructions,
             _log_messages,
+            _return_data,
         ) = batch.bank().load_execute_and_commit_transactions(
             &batch,
             MAX_PROCESSING_AGE,
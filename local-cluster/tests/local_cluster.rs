@@ -16,6 +16,7 @@ use solana_core::{
     cluster_info::VALIDATOR_PORT_RANGE,
     consensus::{Tower, SWITCH_FORK_THRESHOLD, VOTE_THRESHOLD_DEPTH},
     gossip_service::discover_cluster,
+    ledger_cleanup_service::LedgerRetention,
     optimistic_confirmation_verifier::OptimisticConfirmationVerifier,
     validator::ValidatorConfig,
 };
@@ -70,7 +71,7 @@ fn test_ledger_cleanup_service() {
     error!("test_ledger_cleanup_service");
     let num_nodes = 3;
     let validator_config = ValidatorConfig {
-        max_ledger_shreds: Some(100),
+        ledger_retention: Some(LedgerRetention::with_max_ledger_shreds(100)),
         ..ValidatorConfig::default()
     };
     let mut config = ClusterConfig {
@@ -149,6 +150,31 @@ fn test_spend_and_verify_all_nodes_3() {
     );
 }
 
+#[test]
+#[serial]
+fn test_spend_and_verify_all_nodes_in_memory_blockstore() {
+    solana_logger::setup();
+    error!("test_spend_and_verify_all_nodes_in_memory_blockstore");
+    let num_nodes = 2;
+    let validator_config = ValidatorConfig {
+        blockstore_in_memory: true,
+        ..ValidatorConfig::default()
+    };
+    let mut config = ClusterConfig {
+        cluster_lamports: 10_000,
+        node_stakes: vec![100; num_nodes],
+        validator_configs: vec![validator_config; num_nodes],
+        ..ClusterConfig::default()
+    };
+    let local = LocalCluster::new(&mut config);
+    cluster_tests::spend_and_verify_all_nodes(
+        &local.entry_point_info,
+        &local.funding_keypair,
+        num_nodes,
+        HashSet::new(),
+    );
+}
+
 #[test]
 #[serial]
 fn test_local_cluster_signature_subscribe() {
@@ -12,7 +12,7 @@ use bincode::serialized_size;
 use rayon::ThreadPool;
 use solana_metrics::inc_new_counter_debug;
 use solana_rayon_threadlimit::get_thread_count;
-use solana_sdk::message::MessageHeader;
+use solana_sdk::message::{MessageHeader, MESSAGE_VERSION_PREFIX};
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::short_vec::decode_len;
 use solana_sdk::signature::Signature;
@@ -59,6 +59,7 @@ pub enum PacketError {
     InvalidSignatureLen,
     MismatchSignatureLen,
     PayerNotWritable,
+    UnsupportedVersion,
 }
 
 impl std::convert::From<std::boxed::Box<bincode::ErrorKind>> for PacketError {
@@ -143,16 +144,36 @@ fn do_get_packet_offsets(
         return Err(PacketError::InvalidSignatureLen);
     }
 
+    // A set high bit in the first message byte marks a versioned message whose
+    // MessageHeader follows the version prefix byte. Legacy messages start with
+    // the header directly.
+    let message_prefix = packet.data[msg_start_offset];
+    let msg_header_offset = if message_prefix & MESSAGE_VERSION_PREFIX != 0 {
+        let version = message_prefix & !MESSAGE_VERSION_PREFIX;
+        match version {
+            0 => {
+                // Packet should also have data for the version prefix byte
+                if (msg_start_offset + 1 + message_header_size + 1) > packet.meta.size {
+                    return Err(PacketError::InvalidSignatureLen);
+                }
+                msg_start_offset + 1
+            }
+            _ => return Err(PacketError::UnsupportedVersion),
+        }
+    } else {
+        msg_start_offset
+    };
+
     // read MessageHeader.num_required_signatures (serialized with u8)
-    let sig_len_maybe_trusted = packet.data[msg_start_offset] as usize;
+    let sig_len_maybe_trusted = packet.data[msg_header_offset] as usize;
 
-    let message_account_keys_len_offset = msg_start_offset + message_header_size;
+    let message_account_keys_len_offset = msg_header_offset + message_header_size;
 
     // This reads and compares the MessageHeader num_required_signatures and
     // num_readonly_signed_accounts bytes. If num_required_signatures is not larger than
     // num_readonly_signed_accounts, the first account is not debitable, and cannot be charged
     // required transaction fees.
-    if packet.data[msg_start_offset] <= packet.data[msg_start_offset + 1] {
+    if packet.data[msg_header_offset] <= packet.data[msg_header_offset + 1] {
         return Err(PacketError::PayerNotWritable);
     }
 
@@ -168,7 +189,7 @@ fn do_get_packet_offsets(
 
     let sig_start = current_offset as usize + sig_size;
     let msg_start = current_offset as usize + msg_start_offset;
-    let pubkey_start = msg_start + message_header_size + pubkey_len_size;
+    let pubkey_start = current_offset as usize + message_account_keys_len_offset + pubkey_len_size;
 
     if sig_len_maybe_trusted != sig_len_untrusted {
         return Err(PacketError::MismatchSignatureLen);
@@ -413,9 +434,10 @@ mod tests {
     use crate::test_tx::{test_multisig_tx, test_tx};
     use bincode::{deserialize, serialize};
     use solana_sdk::hash::Hash;
-    use solana_sdk::message::{Message, MessageHeader};
-    use solana_sdk::signature::Signature;
-    use solana_sdk::transaction::Transaction;
+    use solana_sdk::instruction::CompiledInstruction;
+    use solana_sdk::message::{v0, Message, MessageHeader, VersionedMessage};
+    use solana_sdk::signature::{Keypair, Signature, Signer};
+    use solana_sdk::transaction::{Transaction, VersionedTransaction};
 
     const SIG_OFFSET: usize = 1;
 
@@ -508,9 +530,11 @@ mod tests {
 
         let unsanitized_packet_offsets = sigverify::do_get_packet_offsets(&packet, 0);
 
+        // the high bit of the first message byte marks a versioned message, so the
+        // large signature count is read as an unknown message version
         assert_eq!(
             unsanitized_packet_offsets,
-            Err(PacketError::MismatchSignatureLen)
+            Err(PacketError::UnsupportedVersion)
         );
     }
 
@@ -645,6 +669,83 @@ mod tests {
         );
     }
 
+    fn make_packet_from_versioned_transaction(tx: VersionedTransaction) -> Packet {
+        let tx_bytes = serialize(&tx).unwrap();
+        let mut packet = Packet::default();
+        packet.meta.size = tx_bytes.len();
+        packet.data[..packet.meta.size].copy_from_slice(&tx_bytes);
+        packet
+    }
+
+    fn test_v0_tx() -> VersionedTransaction {
+        let keypair = Keypair::new();
+        let message = VersionedMessage::V0(v0::Message {
+            header: MessageHeader {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 1,
+            },
+            account_keys: vec![keypair.pubkey(), Pubkey::new_unique()],
+            recent_blockhash: Hash::new_unique(),
+            instructions: vec![CompiledInstruction {
+                program_id_index: 1,
+                accounts: vec![0, 2],
+                data: vec![1, 2, 3],
+            }],
+            address_table_lookups: vec![v0::MessageAddressTableLookup {
+                account_key: Pubkey::new_unique(),
+                writable_indexes: vec![0],
+                readonly_indexes: vec![],
+            }],
+        });
+        VersionedTransaction::try_new(message, &[&keypair]).unwrap()
+    }
+
+    #[test]
+    fn test_versioned_transaction_layout() {
+        let tx = test_v0_tx();
+        let tx_bytes = serialize(&tx).unwrap();
+        let packet = make_packet_from_versioned_transaction(tx.clone());
+        let packet_offsets = sigverify::get_packet_offsets(&packet, 0);
+
+        assert_eq!(packet_offsets.sig_len, 1);
+        assert_eq!(
+            memfind(&tx_bytes, &tx.signatures[0].as_ref()),
+            Some(packet_offsets.sig_start as usize)
+        );
+        assert_eq!(
+            memfind(&tx_bytes, &tx.message.static_account_keys()[0].as_ref()),
+            Some(packet_offsets.pubkey_start as usize)
+        );
+        assert_eq!(
+            memfind(&tx_bytes, &tx.message_data()),
+            Some(packet_offsets.msg_start as usize)
+        );
+        assert_eq!(
+            tx_bytes[packet_offsets.msg_start as usize],
+            MESSAGE_VERSION_PREFIX
+        );
+        assert_eq!(sigverify::verify_packet(&packet), 1);
+    }
+
+    #[test]
+    fn test_unsupported_message_version() {
+        let mut packet = make_packet_from_versioned_transaction(test_v0_tx());
+        let msg_start = sigverify::get_packet_offsets(&packet, 0).msg_start as usize;
+        packet.data[msg_start] = MESSAGE_VERSION_PREFIX | 1;
+
+        let res = sigverify::do_get_packet_offsets(&packet, 0);
+        assert_eq!(res, Err(PacketError::UnsupportedVersion));
+    }
+
+    #[test]
+    fn test_versioned_transaction_tampered() {
+        let mut packet = make_packet_from_versioned_transaction(test_v0_tx());
+        let last = packet.meta.size - 1;
+        packet.data[last] ^= 0xff;
+        assert_eq!(sigverify::verify_packet(&packet), 0);
+    }
+
     fn generate_packet_vec(
         packet: &Packet,
         num_packets_per_batch: usize,
@@ -0,0 +1,237 @@
+//! Accounts captured from a cluster, either in a snapshot archive or in a recording of its RPC
+//! responses, for reproducing its state offline
+
+use {
+    log::*,
+    serde_json::Value,
+    solana_account_decoder::UiAccount,
+    solana_client::recording_sender::{load_records, RpcRecordedResponse},
+    solana_runtime::snapshot_utils,
+    solana_sdk::{
+        account::Account,
+        account_utils::StateMut,
+        bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+        genesis_config::GenesisConfig,
+        pubkey::Pubkey,
+    },
+    std::{
+        collections::{HashMap, HashSet},
+        fs,
+        path::Path,
+        str::FromStr,
+    },
+};
+
+/// The programdata account of an upgradeable program, which must be cloned along with it
+fn programdata_address(account: &Account) -> Option<Pubkey> {
+    if !bpf_loader_upgradeable::check_id(&account.owner) {
+        return None;
+    }
+    match account.state() {
+        Ok(UpgradeableLoaderState::Program {
+            programdata_address,
+        }) => Some(programdata_address),
+        _ => None,
+    }
+}
+
+/// Look up `addresses`, adding the programdata accounts of the upgradeable programs among them
+fn get_accounts_with_programdata<F>(
+    addresses: &[Pubkey],
+    get_account: F,
+    source: &str,
+) -> Vec<(Pubkey, Account)>
+where
+    F: Fn(&Pubkey) -> Option<Account>,
+{
+    let mut accounts = vec![];
+    let mut added = HashSet::new();
+    for address in addresses {
+        if !added.insert(*address) {
+            continue;
+        }
+        let account = get_account(address)
+            .unwrap_or_else(|| panic!("Account {} not found in {}", address, source));
+        let programdata_address = programdata_address(&account);
+        accounts.push((*address, account));
+
+        if let Some(programdata_address) = programdata_address {
+            if added.insert(programdata_address) {
+                let programdata = get_account(&programdata_address).unwrap_or_else(|| {
+                    panic!(
+                        "Program data account {} of program {} not found in {}",
+                        programdata_address, address, source
+                    )
+                });
+                accounts.push((programdata_address, programdata));
+            }
+        }
+    }
+    accounts
+}
+
+pub(crate) fn load_from_snapshot_archive(
+    snapshot_archive: &Path,
+    genesis_config: &GenesisConfig,
+    addresses: &[Pubkey],
+) -> Vec<(Pubkey, Account)> {
+    let source = snapshot_archive.display().to_string();
+    let (_slot, _hash, archive_format) = snapshot_archive
+        .file_name()
+        .and_then(|file_name| file_name.to_str())
+        .and_then(snapshot_utils::snapshot_hash_of)
+        .unwrap_or_else(|| panic!("Invalid snapshot archive name: {}", source));
+
+    let unpack_dir = tempfile::tempdir()
+        .unwrap_or_else(|err| panic!("Failed to create a temporary directory: {}", err));
+    let account_paths = vec![unpack_dir.path().join("accounts")];
+    fs::create_dir_all(&account_paths[0])
+        .unwrap_or_else(|err| panic!("Failed to create accounts directory: {}", err));
+
+    let bank = snapshot_utils::bank_from_archive(
+        &account_paths,
+        &[],
+        unpack_dir.path(),
+        snapshot_archive,
+        archive_format,
+        genesis_config,
+        None,
+        None,
+        HashSet::new(),
+        false,
+    )
+    .unwrap_or_else(|err| panic!("Failed to load snapshot {}: {}", source, err));
+    info!("Loaded snapshot of slot {} from {}", bank.slot(), source);
+
+    get_accounts_with_programdata(addresses, |address| bank.get_account(address), &source)
+}
+
+/// `getAccountInfo` and `getMultipleAccounts` wrap their results in a context
+fn response_value(result: &Value) -> &Value {
+    match result.get("context") {
+        Some(_) => &result["value"],
+        None => result,
+    }
+}
+
+fn parse_address(address: &str, source: &str) -> Pubkey {
+    Pubkey::from_str(address)
+        .unwrap_or_else(|err| panic!("Invalid address {} in {}: {}", address, source, err))
+}
+
+/// A `null` account is one that did not exist when it was fetched
+fn decode_account(address: &str, ui_account: &Value, source: &str) -> (Pubkey, Option<Account>) {
+    let address = parse_address(address, source);
+    if ui_account.is_null() {
+        return (address, None);
+    }
+    let account = serde_json::from_value::<UiAccount>(ui_account.clone())
+        .ok()
+        .and_then(|ui_account| ui_account.decode())
+        .unwrap_or_else(|| {
+            panic!(
+                "Unable to decode account {} in {}, record it with a binary encoding",
+                address, source
+            )
+        });
+    (address, Some(account))
+}
+
+/// What a recording holds for an account: its latest state, `None` if it did not exist, or the
+/// error the cluster answered the request for it with
+type RecordedAccount = Result<Option<Account>, String>;
+
+/// Collect the accounts returned by `getAccountInfo`, `getMultipleAccounts` and
+/// `getProgramAccounts` in a recording written by a `RecordingSender`. When an account was
+/// fetched more than once, its latest state is kept. Failed `getProgramAccounts` requests, which
+/// cannot be attributed to an account, are returned separately.
+fn read_rpc_dump(path: &Path) -> (HashMap<Pubkey, RecordedAccount>, Vec<String>) {
+    let source = path.display().to_string();
+    let records =
+        load_records(path).unwrap_or_else(|err| panic!("Failed to read {}: {}", source, err));
+
+    let mut accounts = HashMap::new();
+    let mut failed_requests = vec![];
+    for record in records {
+        let params = &record.params;
+        let requested_addresses: Vec<_> = match record.method.as_str() {
+            "getAccountInfo" => params[0].as_str().into_iter().collect(),
+            "getMultipleAccounts" => params[0]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|address| address.as_str())
+                .collect(),
+            "getProgramAccounts" => vec![],
+            _ => continue,
+        };
+
+        let result = match &record.response {
+            RpcRecordedResponse::Result(result) => response_value(result),
+            RpcRecordedResponse::Error(error) => {
+                let failure = format!("{} {} failed: {}", record.method, params, error);
+                for address in &requested_addresses {
+                    let address = parse_address(address, &source);
+                    accounts.insert(address, Err(failure.clone()));
+                }
+                if requested_addresses.is_empty() {
+                    failed_requests.push(failure);
+                }
+                continue;
+            }
+        };
+
+        let fetched: Vec<_> = match record.method.as_str() {
+            "getProgramAccounts" => result
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|keyed_account| {
+                    Some(decode_account(
+                        keyed_account["pubkey"].as_str()?,
+                        &keyed_account["account"],
+                        &source,
+                    ))
+                })
+                .collect(),
+            "getAccountInfo" => requested_addresses
+                .into_iter()
+                .map(|address| decode_account(address, result, &source))
+                .collect(),
+            _ => requested_addresses
+                .into_iter()
+                .zip(result.as_array().into_iter().flatten())
+                .map(|(address, ui_account)| decode_account(address, ui_account, &source))
+                .collect(),
+        };
+        accounts.extend(
+            fetched
+                .into_iter()
+                .map(|(address, account)| (address, Ok(account))),
+        );
+    }
+    (accounts, failed_requests)
+}
+
+pub(crate) fn load_from_rpc_dump(path: &Path, addresses: &[Pubkey]) -> Vec<(Pubkey, Account)> {
+    let source = path.display().to_string();
+    let (accounts, failed_requests) = read_rpc_dump(path);
+    get_accounts_with_programdata(
+        addresses,
+        |address| match accounts.get(address) {
+            Some(Ok(account)) => account.clone(),
+            Some(Err(failure)) => panic!(
+                "Account {} could not be fetched when {} was recorded, {}",
+                address, source, failure
+            ),
+            None if !failed_requests.is_empty() => panic!(
+                "Account {} not found in {}, which also recorded failed requests: {}",
+                address,
+                source,
+                failed_requests.join(", ")
+            ),
+            None => None,
+        },
+        &source,
+    )
+}
@@ -0,0 +1,443 @@
+//! Randomized testing of a program against invariants
+//!
+//! A `Fuzzer` generates instructions for a program from an `InstructionSchema`, runs each of them
+//! in a fresh child of the test bank and checks the outcome against a set of invariants.  Every
+//! case is generated from a seed, so that a failing case can be replayed with
+//! `Fuzzer::run_case()`.
+
+use {
+    log::*,
+    rand::{distributions::Uniform, seq::SliceRandom, Rng},
+    rand_chacha::{rand_core::SeedableRng, ChaChaRng},
+    solana_program::{
+        instruction::{AccountMeta, Instruction},
+        message::Message,
+        pubkey::Pubkey,
+    },
+    solana_runtime::bank::Bank,
+    solana_sdk::{
+        account::Account,
+        clock::Epoch,
+        signature::{keypair_from_seed, Keypair, Signer},
+        transaction::{self, Transaction},
+    },
+    std::{
+        collections::HashMap,
+        ops::RangeInclusive,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc,
+        },
+    },
+    thiserror::Error,
+};
+
+/// A part of the generated instruction or account data
+#[derive(Clone, Debug)]
+pub enum DataField {
+    /// A fixed sequence of bytes, such as an instruction discriminant
+    Bytes(Vec<u8>),
+    /// One of the given sequences of bytes
+    OneOf(Vec<Vec<u8>>),
+    /// A little-endian unsigned integer of `size` bytes in `min..=max`.  The bounds are chosen
+    /// more often than the values between them.
+    Integer { size: usize, min: u64, max: u64 },
+    /// Random bytes, with a length in `min_len..=max_len`
+    RandomBytes { min_len: usize, max_len: usize },
+}
+
+impl DataField {
+    pub fn u8() -> Self {
+        Self::integer(1, 0, u8::MAX.into())
+    }
+
+    pub fn u16() -> Self {
+        Self::integer(2, 0, u16::MAX.into())
+    }
+
+    pub fn u32() -> Self {
+        Self::integer(4, 0, u32::MAX.into())
+    }
+
+    pub fn u64() -> Self {
+        Self::integer(8, 0, u64::MAX)
+    }
+
+    pub fn integer(size: usize, min: u64, max: u64) -> Self {
+        assert!(size <= 8, "Integers are at most 8 bytes long");
+        assert!(min <= max, "Empty integer range");
+        assert!(
+            size == 8 || max < 1u64 << (8 * size),
+            "The integer range does not fit in {} bytes",
+            size
+        );
+        Self::Integer { size, min, max }
+    }
+
+    fn generate(&self, rng: &mut ChaChaRng, data: &mut Vec<u8>) {
+        match self {
+            Self::Bytes(bytes) => data.extend_from_slice(bytes),
+            Self::OneOf(choices) => {
+                if let Some(choice) = choices.choose(rng) {
+                    data.extend_from_slice(choice);
+                }
+            }
+            Self::Integer { size, min, max } => {
+                let value = match rng.gen_range(0, 8) {
+                    0 => *min,
+                    1 => *max,
+                    _ => rng.sample(Uniform::new_inclusive(min, max)),
+                };
+                data.extend_from_slice(&value.to_le_bytes()[..*size]);
+            }
+            Self::RandomBytes { min_len, max_len } => {
+                let len = rng.sample(Uniform::new_inclusive(min_len, max_len));
+                data.extend((0..len).map(|_| rng.gen::<u8>()));
+            }
+        }
+    }
+}
+
+fn generate_data(fields: &[DataField], rng: &mut ChaChaRng) -> Vec<u8> {
+    let mut data = vec![];
+    for field in fields {
+        field.generate(rng, &mut data);
+    }
+    data
+}
+
+#[derive(Clone, Debug)]
+pub enum AccountKind {
+    /// An account of the test environment, such as a sysvar
+    Existing(Pubkey),
+    /// A new account, owned by one of `owners` or by the program if there are none
+    Generated {
+        lamports: RangeInclusive<u64>,
+        data: Vec<DataField>,
+        owners: Vec<Pubkey>,
+    },
+    /// The same account as an earlier one of the instruction
+    SameAs(usize),
+}
+
+/// An account passed to the generated instructions
+#[derive(Clone, Debug)]
+pub struct AccountSchema {
+    pub kind: AccountKind,
+    /// Only generated accounts, or aliases of them, may sign
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+impl AccountSchema {
+    pub fn existing(address: Pubkey, is_writable: bool) -> Self {
+        Self {
+            kind: AccountKind::Existing(address),
+            is_signer: false,
+            is_writable,
+        }
+    }
+
+    /// A writable account that does not sign
+    pub fn generated(
+        lamports: RangeInclusive<u64>,
+        data: Vec<DataField>,
+        owners: Vec<Pubkey>,
+    ) -> Self {
+        Self {
+            kind: AccountKind::Generated {
+                lamports,
+                data,
+                owners,
+            },
+            is_signer: false,
+            is_writable: true,
+        }
+    }
+
+    pub fn same_as(index: usize, is_signer: bool, is_writable: bool) -> Self {
+        Self {
+            kind: AccountKind::SameAs(index),
+            is_signer,
+            is_writable,
+        }
+    }
+}
+
+/// Describes the instructions generated by a `Fuzzer`
+#[derive(Clone, Debug, Default)]
+pub struct InstructionSchema {
+    pub data: Vec<DataField>,
+    pub accounts: Vec<AccountSchema>,
+}
+
+/// A generated instruction and the state of its accounts before it was processed
+#[derive(Clone, Debug)]
+pub struct FuzzCase {
+    pub seed: u64,
+    pub instruction: Instruction,
+    /// In the order of the instruction accounts
+    pub pre_accounts: Vec<(Pubkey, Account)>,
+}
+
+#[derive(Clone, Debug)]
+pub struct FuzzOutcome {
+    pub result: transaction::Result<()>,
+    pub log_messages: Vec<String>,
+    /// In the order of the instruction accounts
+    pub post_accounts: Vec<(Pubkey, Account)>,
+}
+
+#[derive(Error, Debug)]
+#[error(
+    "invariant \"{invariant}\" failed: {message}, replay with Fuzzer::run_case({})",
+    .case.seed
+)]
+pub struct FuzzFailure {
+    pub invariant: String,
+    pub message: String,
+    pub case: FuzzCase,
+    pub outcome: FuzzOutcome,
+}
+
+#[derive(Clone, Debug)]
+pub struct FuzzConfig {
+    pub cases: usize,
+    /// The seeds of the cases are derived from this one
+    pub seed: u64,
+}
+
+impl Default for FuzzConfig {
+    /// The `SOLANA_FUZZ_CASES` and `SOLANA_FUZZ_SEED` environment variables override the default
+    /// of 256 cases from a random seed
+    fn default() -> Self {
+        let env_var = |name| {
+            std::env::var(name).ok().map(|value| {
+                value
+                    .parse()
+                    .unwrap_or_else(|err| panic!("Invalid {}: {}", name, err))
+            })
+        };
+        Self {
+            cases: env_var("SOLANA_FUZZ_CASES").unwrap_or(256) as usize,
+            seed: env_var("SOLANA_FUZZ_SEED").unwrap_or_else(rand::random),
+        }
+    }
+}
+
+type Invariant = Box<dyn Fn(&FuzzCase, &FuzzOutcome) -> Result<(), String>>;
+
+pub struct Fuzzer {
+    bank: Arc<Bank>,
+    payer: Keypair,
+    program_id: Pubkey,
+    schema: InstructionSchema,
+    invariants: Vec<(String, Invariant)>,
+    next_slot: AtomicU64,
+}
+
+impl Fuzzer {
+    pub(crate) fn new(
+        bank: Arc<Bank>,
+        payer: Keypair,
+        program_id: Pubkey,
+        schema: InstructionSchema,
+    ) -> Self {
+        for (i, account) in schema.accounts.iter().enumerate() {
+            match account.kind {
+                AccountKind::Existing(address) if account.is_signer => {
+                    panic!("Existing account {} cannot sign", address)
+                }
+                AccountKind::SameAs(index) if index >= i => {
+                    panic!("Account {} can only alias an earlier account", i)
+                }
+                _ => {}
+            }
+        }
+        let next_slot = AtomicU64::new(bank.slot() + 1);
+        Self {
+            bank,
+            payer,
+            program_id,
+            schema,
+            invariants: vec![],
+            next_slot,
+        }
+    }
+
+    /// Check `invariant` after every case.  It returns a description of the violation, if any.
+    pub fn add_invariant<F>(&mut self, name: &str, invariant: F)
+    where
+        F: Fn(&FuzzCase, &FuzzOutcome) -> Result<(), String> + 'static,
+    {
+        self.invariants
+            .push((name.to_string(), Box::new(invariant)));
+    }
+
+    /// Run cases until one of them fails an invariant
+    pub fn run(&self, config: &FuzzConfig) -> Result<(), FuzzFailure> {
+        info!(
+            "Fuzzing {} with {} cases from seed {}",
+            self.program_id, config.cases, config.seed
+        );
+        let mut rng = ChaChaRng::seed_from_u64(config.seed);
+        for _ in 0..config.cases {
+            self.run_case(rng.gen())?;
+        }
+        Ok(())
+    }
+
+    /// Run the case generated from `seed`
+    pub fn run_case(&self, seed: u64) -> Result<FuzzOutcome, FuzzFailure> {
+        let (case, signers) = self.generate_case(seed);
+
+        // Each case runs in its own bank, so that it neither sees the state left by earlier cases
+        // nor is rejected as a duplicate of them
+        let bank = Bank::new_from_parent(
+            &self.bank,
+            &Pubkey::default(),
+            self.next_slot.fetch_add(1, Ordering::Relaxed),
+        );
+        for (address, account) in case.pre_accounts.iter() {
+            bank.store_account(address, account);
+        }
+        let message = Message::new(&[case.instruction.clone()], Some(&self.payer.pubkey()));
+        let mut keypairs = vec![&self.payer];
+        keypairs.extend(signers.iter());
+        let transaction = Transaction::new(&keypairs, message, self.bank.last_blockhash());
+        let details = bank.process_transaction_with_details(&transaction);
+
+        let post_accounts: HashMap<_, _> = details.post_accounts.into_iter().collect();
+        let outcome = FuzzOutcome {
+            result: details.status,
+            log_messages: details.log_messages,
+            post_accounts: case
+                .instruction
+                .accounts
+                .iter()
+                .map(|meta| (meta.pubkey, post_accounts[&meta.pubkey].clone()))
+                .collect(),
+        };
+        debug!("Case {}: {:?}", seed, outcome.result);
+
+        for (name, invariant) in self.invariants.iter() {
+            if let Err(message) = invariant(&case, &outcome) {
+                return Err(FuzzFailure {
+                    invariant: name.clone(),
+                    message,
+                    case,
+                    outcome,
+                });
+            }
+        }
+        Ok(outcome)
+    }
+
+    fn generate_case(&self, seed: u64) -> (FuzzCase, Vec<Keypair>) {
+        let mut rng = ChaChaRng::seed_from_u64(seed);
+        let data = generate_data(&self.schema.data, &mut rng);
+
+        let mut metas: Vec<AccountMeta> = vec![];
+        let mut pre_accounts: Vec<(Pubkey, Account)> = vec![];
+        let mut keypairs = HashMap::new();
+        for schema in self.schema.accounts.iter() {
+            let (address, account) = match &schema.kind {
+                AccountKind::Existing(address) => {
+                    (*address, self.bank.get_account(address).unwrap_or_default())
+                }
+                AccountKind::Generated {
+                    lamports,
+                    data,
+                    owners,
+                } => {
+                    let keypair = keypair_from_seed(&rng.gen::<[u8; 32]>()).unwrap();
+                    let account = Account {
+                        lamports: rng
+                            .sample(Uniform::new_inclusive(lamports.start(), lamports.end())),
+                        data: generate_data(data, &mut rng),
+                        owner: *owners.choose(&mut rng).unwrap_or(&self.program_id),
+                        executable: false,
+                        // Rent is not collected from generated accounts, so that it does not
+                        // blur their balances
+                        rent_epoch: Epoch::MAX,
+                    };
+                    let address = keypair.pubkey();
+                    keypairs.insert(address, keypair);
+                    (address, account)
+                }
+                AccountKind::SameAs(index) => pre_accounts[*index].clone(),
+            };
+            if schema.is_signer && !keypairs.contains_key(&address) {
+                panic!("Account {} cannot sign", address);
+            }
+            metas.push(AccountMeta {
+                pubkey: address,
+                is_signer: schema.is_signer,
+                is_writable: schema.is_writable,
+            });
+            pre_accounts.push((address, account));
+        }
+
+        let signers = metas
+            .iter()
+            .filter(|meta| meta.is_signer)
+            .filter_map(|meta| keypairs.remove(&meta.pubkey))
+            .collect();
+        let case = FuzzCase {
+            seed,
+            instruction: Instruction {
+                program_id: self.program_id,
+                accounts: metas,
+                data,
+            },
+            pre_accounts,
+        };
+        (case, signers)
+    }
+}
+
+fn unique_accounts(accounts: &[(Pubkey, Account)]) -> HashMap<Pubkey, &Account> {
+    accounts
+        .iter()
+        .map(|(address, account)| (*address, account))
+        .collect()
+}
+
+/// The instruction accounts hold as many lamports after the instruction as before it
+pub fn lamports_conserved(case: &FuzzCase, outcome: &FuzzOutcome) -> Result<(), String> {
+    let total = |accounts: &[(Pubkey, Account)]| -> u128 {
+        unique_accounts(accounts)
+            .values()
+            .map(|account| u128::from(account.lamports))
+            .sum()
+    };
+    let pre = total(&case.pre_accounts);
+    let post = total(&outcome.post_accounts);
+    if pre == post {
+        Ok(())
+    } else {
+        Err(format!("{} lamports before, {} after", pre, post))
+    }
+}
+
+/// The accounts not owned by the program keep their owner and data and are not debited.  This
+/// does not hold for programs that invoke other programs, which may modify the accounts they own.
+pub fn owner_rules(case: &FuzzCase, outcome: &FuzzOutcome) -> Result<(), String> {
+    let post_accounts = unique_accounts(&outcome.post_accounts);
+    for (address, pre) in unique_accounts(&case.pre_accounts) {
+        if pre.owner == case.instruction.program_id {
+            continue;
+        }
+        let post = post_accounts[&address];
+        if post.owner != pre.owner {
+            return Err(format!("account {} was reassigned", address));
+        }
+        if post.data != pre.data {
+            return Err(format!("data of account {} was modified", address));
+        }
+        if post.lamports < pre.lamports {
+            return Err(format!("account {} was debited", address));
+        }
+    }
+    Ok(())
+}
@@ -11,23 +11,25 @@ use {
         account_info::AccountInfo, entrypoint::ProgramResult, fee_calculator::FeeCalculator,
         hash::Hash, instruction::Instruction, instruction::InstructionError, message::Message,
         native_token::sol_to_lamports, program_error::ProgramError, program_stubs, pubkey::Pubkey,
-        rent::Rent,
+        rent::Rent, sysvar::Sysvar, sysvar::SysvarAddress,
     },
     solana_runtime::{
-        bank::{Bank, Builtin, ExecuteTimings},
+        bank::{Bank, BpfTraceCallback, Builtin, ExecuteTimings},
         bank_forks::BankForks,
         commitment::BlockCommitmentCache,
         genesis_utils::{create_genesis_config_with_leader, GenesisConfigInfo},
     },
     solana_sdk::{
-        account::Account,
-        clock::Slot,
+        account::{from_account, Account},
+        clock::{Epoch, Slot},
+        feature_set::return_data_syscall_enabled,
         genesis_config::GenesisConfig,
         keyed_account::KeyedAccount,
         process_instruction::{
-            stable_log, BpfComputeBudget, InvokeContext, ProcessInstructionWithContext,
+            stable_log, BpfComputeBudget, BpfTrace, InvokeContext, ProcessInstructionWithContext,
         },
-        signature::{Keypair, Signer},
+        signature::{Keypair, Signature, Signer},
+        transaction::TransactionReturnData,
     },
     solana_vote_program::vote_state::{VoteState, VoteStateVersions},
     std::{
@@ -41,7 +43,7 @@ use {
         rc::Rc,
         sync::{
             atomic::{AtomicBool, Ordering},
-            Arc, RwLock,
+            Arc, Mutex, RwLock,
         },
         time::{Duration, Instant},
     },
@@ -51,6 +53,8 @@ use {
 
 // Export types so test clients can limit their solana crate dependencies
 pub use solana_banks_client::BanksClient;
+mod cloned_accounts;
+pub mod fuzz;
 pub mod programs;
 
 #[macro_use]
@@ -83,6 +87,9 @@ pub enum ProgramTestError {
     /// The chosen warp slot is not in the future, so warp is not performed
     #[error("Warp slot not in the future")]
     InvalidWarpSlot,
+    /// The chosen warp epoch is not in the future, so warp is not performed
+    #[error("Warp epoch not in the future")]
+    InvalidWarpEpoch,
 }
 
 thread_local! {
@@ -109,6 +116,9 @@ pub fn builtin_process_instruction(
     invoke_context: &mut dyn InvokeContext,
 ) -> Result<(), InstructionError> {
     set_invoke_context(invoke_context);
+    if invoke_context.is_feature_active(&return_data_syscall_enabled::id()) {
+        invoke_context.set_return_data(None);
+    }
 
     // Copy all the accounts into a HashMap to ensure there are no duplicates
     let mut accounts: HashMap<Pubkey, Account> = keyed_accounts
@@ -336,6 +346,26 @@ impl program_stubs::SyscallStubs for SyscallStubs {
         stable_log::program_success(&logger, &program_id);
         Ok(())
     }
+
+    fn sol_set_return_data(&self, data: &[u8]) {
+        let invoke_context = get_invoke_context();
+        let return_data = if data.is_empty() {
+            None
+        } else {
+            let program_id = *invoke_context.get_caller().expect("get_caller");
+            Some(TransactionReturnData {
+                program_id,
+                data: data.to_vec(),
+            })
+        };
+        invoke_context.set_return_data(return_data);
+    }
+
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        get_invoke_context()
+            .get_return_data()
+            .map(|return_data| (return_data.program_id, return_data.data.clone()))
+    }
 }
 
 pub fn find_file(filename: &str) -> Option<PathBuf> {
@@ -408,11 +438,43 @@ fn setup_fee_calculator(bank: Bank) -> Bank {
     bank
 }
 
+/// Execution traces of the BPF programs invoked by a transaction
+pub type TransactionBpfTraces = (Signature, Vec<BpfTrace>);
+
+/// Execution traces of the BPF programs run by a test, see `ProgramTest::trace_bpf_programs`
+#[derive(Clone, Debug, Default)]
+pub struct BpfTraces(Arc<Mutex<Vec<TransactionBpfTraces>>>);
+
+impl BpfTraces {
+    /// Take the traces recorded so far, by transaction in processing order
+    pub fn take(&self) -> Vec<TransactionBpfTraces> {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+
+    /// Get the traces of the last processing of the transaction with `signature`
+    pub fn get(&self, signature: &Signature) -> Option<Vec<BpfTrace>> {
+        self.0
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .find(|(traced_signature, _)| traced_signature == signature)
+            .map(|(_, traces)| traces.clone())
+    }
+}
+
+impl BpfTraceCallback for BpfTraces {
+    fn callback(&self, _slot: Slot, signature: &Signature, traces: Vec<BpfTrace>) {
+        self.0.lock().unwrap().push((*signature, traces));
+    }
+}
+
 pub struct ProgramTest {
     accounts: Vec<(Pubkey, Account)>,
     builtins: Vec<Builtin>,
     bpf_compute_max_units: Option<u64>,
     prefer_bpf: bool,
+    bpf_traces: Option<BpfTraces>,
 }
 
 impl Default for ProgramTest {
@@ -443,6 +505,7 @@ impl Default for ProgramTest {
             builtins: vec![],
             bpf_compute_max_units: None,
             prefer_bpf,
+            bpf_traces: None,
         }
     }
 }
@@ -468,6 +531,14 @@ impl ProgramTest {
         self.bpf_compute_max_units = Some(bpf_compute_max_units);
     }
 
+    /// Record the execution trace and per-function compute usage of every BPF program run by the
+    /// test, programs loaded as native code are not traced.  Tracing makes programs run slower
+    pub fn trace_bpf_programs(&mut self) -> BpfTraces {
+        self.bpf_traces
+            .get_or_insert_with(BpfTraces::default)
+            .clone()
+    }
+
     /// Add an account to the test environment
     pub fn add_account(&mut self, address: Pubkey, account: Account) {
         self.accounts.push((address, account));
@@ -517,6 +588,61 @@ impl ProgramTest {
         );
     }
 
+    /// Add accounts from a snapshot archive of a cluster to the test environment, along with the
+    /// program data accounts of the upgradeable programs among them.
+    ///
+    /// `genesis_config` must be the genesis config of the cluster that produced the snapshot.
+    pub fn add_accounts_from_snapshot_archive<P: AsRef<Path>>(
+        &mut self,
+        snapshot_archive: P,
+        genesis_config: &GenesisConfig,
+        addresses: &[Pubkey],
+    ) {
+        self.add_cloned_accounts(cloned_accounts::load_from_snapshot_archive(
+            snapshot_archive.as_ref(),
+            genesis_config,
+            addresses,
+        ));
+    }
+
+    /// Add accounts from a recording of RPC requests to the test environment, along with the
+    /// program data accounts of the upgradeable programs among them.
+    ///
+    /// The recording holds one JSON record of the method, params and result of a request per
+    /// line, as written by the `RecordingSender` of `solana-client`.  Accounts are taken from the
+    /// responses to `getAccountInfo`, `getMultipleAccounts` and `getProgramAccounts` requests
+    /// made with a binary encoding.  A failed request for one of `addresses` is reported rather
+    /// than skipped.  The recording is searched for like account data files.
+    pub fn add_accounts_from_rpc_dump(&mut self, filename: &str, addresses: &[Pubkey]) {
+        let path = find_file(filename).unwrap_or_else(|| {
+            panic!("Unable to locate {}", filename);
+        });
+        self.add_cloned_accounts(cloned_accounts::load_from_rpc_dump(&path, addresses));
+    }
+
+    /// Add accounts cloned from a cluster, along with the upgradeable loader if any of them
+    /// belong to it
+    fn add_cloned_accounts(&mut self, accounts: Vec<(Pubkey, Account)>) {
+        let upgradeable_loader_id = solana_program::bpf_loader_upgradeable::id();
+        let needs_upgradeable_loader = accounts
+            .iter()
+            .any(|(_address, account)| account.owner == upgradeable_loader_id);
+        if needs_upgradeable_loader
+            && !self
+                .builtins
+                .iter()
+                .any(|builtin| builtin.id == upgradeable_loader_id)
+        {
+            let (name, id, process_instruction) = solana_bpf_loader_upgradeable_program!();
+            self.builtins
+                .push(Builtin::new(&name, id, process_instruction));
+        }
+
+        for (address, account) in accounts {
+            self.add_account(address, account);
+        }
+    }
+
     /// Add a BPF program to the test environment.
     ///
     /// `program_name` will also used to locate the BPF shared object in the current or fixtures
@@ -661,6 +787,9 @@ impl ProgramTest {
                 ..BpfComputeBudget::default()
             }));
         }
+        if let Some(bpf_traces) = &self.bpf_traces {
+            bank.set_bpf_trace_callback(Some(Arc::new(bpf_traces.clone())));
+        }
         let bank = setup_fee_calculator(bank);
         let slot = bank.slot();
         let last_blockhash = bank.last_blockhash();
@@ -718,6 +847,14 @@ impl ProgramTest {
             gci,
         )
     }
+
+    /// Create a `Fuzzer` for the program at `program_id`, which runs the instructions generated
+    /// from `schema` in the test environment
+    pub fn fuzzer(self, program_id: Pubkey, schema: fuzz::InstructionSchema) -> fuzz::Fuzzer {
+        let (bank_forks, _block_commitment_cache, _last_blockhash, gci) = self.setup_bank();
+        let bank = bank_forks.read().unwrap().working_bank();
+        fuzz::Fuzzer::new(bank, gci.mint_keypair, program_id, schema)
+    }
 }
 
 #[async_trait]
@@ -893,4 +1030,45 @@ impl ProgramTestContext {
         self.last_blockhash = bank.last_blockhash();
         Ok(())
     }
+
+    /// Force the working bank ahead to the first slot of a future epoch
+    pub fn warp_to_epoch(&mut self, warp_epoch: Epoch) -> Result<(), ProgramTestError> {
+        let warp_slot = {
+            let bank_forks = self.bank_forks.read().unwrap();
+            let bank = bank_forks.working_bank();
+            if warp_epoch <= bank.epoch() {
+                return Err(ProgramTestError::InvalidWarpEpoch);
+            }
+            bank.epoch_schedule().get_first_slot_in_epoch(warp_epoch)
+        };
+        self.warp_to_slot(warp_slot)
+    }
+
+    /// Overwrite an account in the working bank, taking effect for the
+    /// transactions that follow
+    pub fn set_account(&mut self, address: &Pubkey, account: &Account) {
+        let bank_forks = self.bank_forks.read().unwrap();
+        bank_forks.working_bank().store_account(address, account);
+    }
+
+    /// Get a sysvar from the working bank
+    pub fn get_sysvar<T: Sysvar + SysvarAddress>(&self) -> T {
+        let bank_forks = self.bank_forks.read().unwrap();
+        bank_forks
+            .working_bank()
+            .get_account(&T::id())
+            .and_then(|account| from_account(&account))
+            .unwrap_or_default()
+    }
+
+    /// Overwrite a sysvar in the working bank, for example to move the
+    /// `Clock::unix_timestamp` seen by programs forward.
+    ///
+    /// A warp recomputes the clock for the new slot, keeping a
+    /// `unix_timestamp` that was moved forward. Other sysvars keep their value
+    /// across warps.
+    pub fn set_sysvar<T: Sysvar + SysvarAddress>(&mut self, sysvar: &T) {
+        let bank_forks = self.bank_forks.read().unwrap();
+        bank_forks.working_bank().set_sysvar_for_tests(sysvar);
+    }
 }
@@ -0,0 +1,61 @@
+use {
+    solana_program::{instruction::Instruction, pubkey::Pubkey, system_instruction},
+    solana_program_test::ProgramTest,
+    solana_sdk::{signature::Signer, transaction::Transaction},
+    std::str::FromStr,
+};
+
+#[tokio::test]
+async fn trace_memo_program() {
+    let memo_id = Pubkey::from_str("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr").unwrap();
+    let mut program_test = ProgramTest::default();
+    let bpf_traces = program_test.trace_bpf_programs();
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            Instruction {
+                program_id: memo_id,
+                accounts: vec![],
+                data: b"traced".to_vec(),
+            },
+            system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1),
+        ],
+        Some(&payer.pubkey()),
+        &[&payer],
+        recent_blockhash,
+    );
+    let signature = transaction.signatures[0];
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    // Only the BPF program is traced
+    let traces = bpf_traces.get(&signature).unwrap();
+    assert_eq!(traces.len(), 1);
+    let trace = &traces[0];
+    assert_eq!(trace.program_id, memo_id);
+    assert_eq!(trace.invoke_depth, 1);
+    assert!(!trace.instructions.is_empty());
+    assert!(trace
+        .function_units
+        .iter()
+        .any(|(function, _)| function == "entrypoint"));
+    assert_eq!(
+        trace
+            .function_units
+            .iter()
+            .map(|(_, units)| units)
+            .sum::<u64>()
+            + trace.syscall_units(),
+        trace.consumed_units
+    );
+
+    let mut profile = String::new();
+    trace.write_profile(&mut profile).unwrap();
+    assert!(profile.starts_with(&format!(
+        "Program {} consumed {} compute units",
+        memo_id, trace.consumed_units
+    )));
+
+    assert_eq!(bpf_traces.take().len(), 1);
+    assert!(bpf_traces.take().is_empty());
+}
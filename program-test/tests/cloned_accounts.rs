@@ -0,0 +1,252 @@
+use {
+    serde_json::json,
+    solana_account_decoder::{UiAccount, UiAccountEncoding},
+    solana_program::{
+        bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+        instruction::Instruction,
+        pubkey::Pubkey,
+        rent::Rent,
+    },
+    solana_program_test::{programs::spl_programs, ProgramTest},
+    solana_runtime::{
+        bank::Bank, bank_forks::ArchiveFormat, genesis_utils::create_genesis_config, snapshot_utils,
+    },
+    solana_sdk::{
+        account::Account, account_utils::StateMut, signature::Signer, transaction::Transaction,
+    },
+    std::{fs::File, io::Write, str::FromStr, sync::Arc},
+};
+
+// An upgradeable deployment of the SPL memo program, and the address of its program data account
+fn upgradeable_memo_program(program_id: &Pubkey) -> (Pubkey, Vec<(Pubkey, Account)>) {
+    let rent = Rent::default();
+    let memo_id = Pubkey::from_str("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr").unwrap();
+    let (_, memo) = spl_programs(&rent)
+        .into_iter()
+        .find(|(address, _)| *address == memo_id)
+        .unwrap();
+
+    let (programdata_address, _) =
+        Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id());
+    let mut program = Account::new(
+        rent.minimum_balance(UpgradeableLoaderState::program_len().unwrap()),
+        UpgradeableLoaderState::program_len().unwrap(),
+        &bpf_loader_upgradeable::id(),
+    );
+    program
+        .set_state(&UpgradeableLoaderState::Program {
+            programdata_address,
+        })
+        .unwrap();
+    program.executable = true;
+
+    let programdata_len = UpgradeableLoaderState::programdata_len(memo.data.len()).unwrap();
+    let mut programdata = Account::new(
+        rent.minimum_balance(programdata_len),
+        programdata_len,
+        &bpf_loader_upgradeable::id(),
+    );
+    programdata
+        .set_state(&UpgradeableLoaderState::ProgramData {
+            slot: 0,
+            upgrade_authority_address: None,
+        })
+        .unwrap();
+    programdata.data[UpgradeableLoaderState::programdata_data_offset().unwrap()..]
+        .copy_from_slice(&memo.data);
+
+    (
+        programdata_address,
+        vec![(*program_id, program), (programdata_address, programdata)],
+    )
+}
+
+fn data_account() -> Account {
+    Account {
+        lamports: Rent::default().minimum_balance(3),
+        data: vec![1, 2, 3],
+        owner: Pubkey::new_unique(),
+        ..Account::default()
+    }
+}
+
+async fn check_cloned_accounts(
+    program_test: ProgramTest,
+    program_id: Pubkey,
+    programdata_address: Pubkey,
+    address: Pubkey,
+    account: Account,
+) {
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    // The upgradeable loader is added for the cloned program
+    assert!(banks_client
+        .get_account(bpf_loader_upgradeable::id())
+        .await
+        .unwrap()
+        .is_some());
+    assert!(banks_client
+        .get_account(programdata_address)
+        .await
+        .unwrap()
+        .is_some());
+    assert_eq!(
+        banks_client.get_account(address).await.unwrap(),
+        Some(account)
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[Instruction {
+            program_id,
+            accounts: vec![],
+            data: b"cloned".to_vec(),
+        }],
+        Some(&payer.pubkey()),
+        &[&payer],
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+}
+
+#[tokio::test]
+async fn clone_from_snapshot_archive() {
+    let genesis_config = create_genesis_config(1_000_000).genesis_config;
+    let program_id = Pubkey::new_unique();
+    let (programdata_address, program_accounts) = upgradeable_memo_program(&program_id);
+    let address = Pubkey::new_unique();
+    let account = data_account();
+
+    let bank = Bank::new(&genesis_config);
+    for (address, account) in program_accounts.iter() {
+        bank.store_account(address, account);
+    }
+    bank.store_account(&address, &account);
+    bank.set_capitalization();
+    let bank = Bank::warp_from_parent(&Arc::new(bank), &Pubkey::default(), 1);
+
+    let snapshot_dir = tempfile::tempdir().unwrap();
+    let snapshot_archive = snapshot_utils::bank_to_snapshot_archive(
+        snapshot_dir.path(),
+        &bank,
+        None,
+        snapshot_dir.path(),
+        ArchiveFormat::TarBzip2,
+        None,
+    )
+    .unwrap();
+
+    let mut program_test = ProgramTest::default();
+    program_test.add_accounts_from_snapshot_archive(
+        &snapshot_archive,
+        &genesis_config,
+        &[program_id, address],
+    );
+    check_cloned_accounts(
+        program_test,
+        program_id,
+        programdata_address,
+        address,
+        account,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn clone_from_rpc_dump() {
+    let program_id = Pubkey::new_unique();
+    let (programdata_address, program_accounts) = upgradeable_memo_program(&program_id);
+    let address = Pubkey::new_unique();
+    let account = data_account();
+    // An earlier state of the account, replaced by a later record
+    let stale_account = Account {
+        lamports: account.lamports + 1,
+        ..account.clone()
+    };
+    let ui_account = |address: &Pubkey, account: &Account| {
+        json!(UiAccount::encode(
+            address,
+            account.clone(),
+            UiAccountEncoding::Base64,
+            None,
+            None,
+        ))
+    };
+
+    let records = vec![
+        // A failure, followed by a successful fetch of the same account
+        json!({
+            "method": "getAccountInfo",
+            "params": [address.to_string(), {"encoding": "base64"}],
+            "error": {"code": -32005, "message": "Node is unhealthy"},
+        }),
+        json!({
+            "method": "getAccountInfo",
+            "params": [address.to_string(), {"encoding": "base64"}],
+            "result": {"context": {"slot": 1}, "value": ui_account(&address, &stale_account)},
+        }),
+        json!({
+            "method": "getProgramAccounts",
+            "params": [bpf_loader_upgradeable::id().to_string(), {"encoding": "base64"}],
+            "result": program_accounts
+                .iter()
+                .map(|(address, account)| json!({
+                    "pubkey": address.to_string(),
+                    "account": ui_account(address, account),
+                }))
+                .collect::<Vec<_>>(),
+        }),
+        json!({
+            "method": "getMultipleAccounts",
+            "params": [[Pubkey::new_unique().to_string(), address.to_string()], {"encoding": "base64"}],
+            "result": {"context": {"slot": 2}, "value": [null, ui_account(&address, &account)]},
+        }),
+    ];
+    let dump_dir = tempfile::tempdir().unwrap();
+    let dump_path = dump_dir.path().join("rpc.json");
+    let mut dump = File::create(&dump_path).unwrap();
+    for record in records {
+        writeln!(dump, "{}", record).unwrap();
+    }
+
+    let mut program_test = ProgramTest::default();
+    program_test.add_accounts_from_rpc_dump(dump_path.to_str().unwrap(), &[program_id, address]);
+    check_cloned_accounts(
+        program_test,
+        program_id,
+        programdata_address,
+        address,
+        account,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn clone_without_upgradeable_program() {
+    let address = Pubkey::new_unique();
+    let mut program_test = ProgramTest::default();
+    program_test.add_account(address, data_account());
+
+    let (mut banks_client, _payer, _recent_blockhash) = program_test.start().await;
+    assert!(banks_client
+        .get_account(bpf_loader_upgradeable::id())
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+#[should_panic(expected = "could not be fetched")]
+fn clone_from_rpc_dump_with_failed_request() {
+    let address = Pubkey::new_unique();
+    let record = json!({
+        "method": "getMultipleAccounts",
+        "params": [[address.to_string()], {"encoding": "base64"}],
+        "error": {"code": -32005, "message": "Node is unhealthy"},
+    });
+    let dump_dir = tempfile::tempdir().unwrap();
+    let dump_path = dump_dir.path().join("rpc.json");
+    writeln!(File::create(&dump_path).unwrap(), "{}", record).unwrap();
+
+    let mut program_test = ProgramTest::default();
+    program_test.add_accounts_from_rpc_dump(dump_path.to_str().unwrap(), &[address]);
+}
@@ -0,0 +1,109 @@
+use {
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        entrypoint::ProgramResult,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        system_program,
+    },
+    solana_program_test::{
+        fuzz::{
+            self, AccountSchema, DataField, FuzzCase, FuzzConfig, FuzzOutcome, InstructionSchema,
+        },
+        processor, ProgramTest,
+    },
+    std::convert::TryInto,
+};
+
+// Moves the lamports given as input from the first account to the second one
+fn process_instruction(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    input: &[u8],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let from = next_account_info(account_info_iter)?;
+    let to = next_account_info(account_info_iter)?;
+    let amount = u64::from_le_bytes(
+        input
+            .try_into()
+            .map_err(|_| ProgramError::InvalidInstructionData)?,
+    );
+    if from.lamports() < amount {
+        return Err(ProgramError::InsufficientFunds);
+    }
+    **from.lamports.borrow_mut() -= amount;
+    **to.lamports.borrow_mut() += amount;
+    Ok(())
+}
+
+// The transfer succeeds whenever the source holds enough lamports
+fn transfers_funded_amounts(case: &FuzzCase, outcome: &FuzzOutcome) -> Result<(), String> {
+    let amount = u64::from_le_bytes(case.instruction.data[..].try_into().unwrap());
+    let from = &case.pre_accounts[0].1;
+    if from.lamports >= amount && outcome.result.is_err() {
+        Err(format!(
+            "transfer of {} out of {} failed: {:?}",
+            amount, from.lamports, outcome.result
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+fn fuzzer(from_owners: Vec<Pubkey>) -> fuzz::Fuzzer {
+    let program_id = Pubkey::new_unique();
+    let program_test = ProgramTest::new(
+        "program-test-fuzzer",
+        program_id,
+        processor!(process_instruction),
+    );
+    let schema = InstructionSchema {
+        data: vec![DataField::integer(8, 0, 2_000)],
+        accounts: vec![
+            AccountSchema::generated(0..=1_000, vec![], from_owners),
+            AccountSchema::generated(0..=1_000, vec![DataField::u8()], vec![]),
+        ],
+    };
+    let mut fuzzer = program_test.fuzzer(program_id, schema);
+    fuzzer.add_invariant("lamports conserved", fuzz::lamports_conserved);
+    fuzzer.add_invariant("owner rules", fuzz::owner_rules);
+    fuzzer.add_invariant("funded transfers succeed", transfers_funded_amounts);
+    fuzzer
+}
+
+#[test]
+fn fuzz_transfer() {
+    // A fixed seed keeps the test deterministic, SOLANA_FUZZ_SEED explores others
+    let seed = std::env::var("SOLANA_FUZZ_SEED")
+        .map(|seed| seed.parse().expect("Invalid SOLANA_FUZZ_SEED"))
+        .unwrap_or(7);
+    let config = FuzzConfig { cases: 64, seed };
+    fuzzer(vec![]).run(&config).unwrap();
+}
+
+#[test]
+fn fuzz_transfer_from_foreign_account() {
+    // The runtime rejects debits of accounts the program does not own
+    let fuzzer = fuzzer(vec![system_program::id()]);
+    let failure = fuzzer
+        .run(&FuzzConfig {
+            cases: 64,
+            seed: 42,
+        })
+        .unwrap_err();
+    assert_eq!(failure.invariant, "funded transfers succeed");
+    assert!(failure.outcome.result.is_err());
+
+    // Replaying the failing case reproduces it
+    let replayed = fuzzer.run_case(failure.case.seed).unwrap_err();
+    assert_eq!(replayed.case.instruction, failure.case.instruction);
+    assert_eq!(replayed.case.pre_accounts, failure.case.pre_accounts);
+    assert_eq!(replayed.outcome.result, failure.outcome.result);
+}
+
+#[test]
+#[should_panic(expected = "does not fit in 1 bytes")]
+fn fuzz_integer_out_of_size() {
+    DataField::integer(1, 0, 300);
+}
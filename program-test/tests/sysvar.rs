@@ -0,0 +1,155 @@
+use {
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        clock::Clock,
+        entrypoint::ProgramResult,
+        epoch_schedule::EpochSchedule,
+        instruction::{AccountMeta, Instruction, InstructionError},
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        rent::Rent,
+        sysvar::{clock, rent, Sysvar},
+    },
+    solana_program_test::{
+        processor, ProgramTest, ProgramTestBanksClientExt, ProgramTestContext, ProgramTestError,
+    },
+    solana_sdk::{
+        account::{from_account, Account},
+        signature::Signer,
+        transaction::{Transaction, TransactionError},
+    },
+    std::convert::TryInto,
+};
+
+const EARLY_ERROR: u32 = 123456;
+
+// Succeeds once the clock has reached the unix timestamp given as input
+fn process_instruction(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    input: &[u8],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let clock = Clock::from_account_info(next_account_info(account_info_iter)?)?;
+    let unlock_timestamp = i64::from_le_bytes(input.try_into().unwrap());
+    if clock.unix_timestamp >= unlock_timestamp {
+        Ok(())
+    } else {
+        Err(ProgramError::Custom(EARLY_ERROR))
+    }
+}
+
+async fn unlock(
+    context: &mut ProgramTestContext,
+    program_id: Pubkey,
+    unlock_timestamp: i64,
+) -> Result<(), TransactionError> {
+    let instruction = Instruction::new(
+        program_id,
+        &unlock_timestamp,
+        vec![AccountMeta::new_readonly(clock::id(), false)],
+    );
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .map_err(|err| err.unwrap())
+}
+
+#[tokio::test]
+async fn clock_unix_timestamp_set() {
+    let program_id = Pubkey::new_unique();
+    let program_test = ProgramTest::new(
+        "program-test-sysvar",
+        program_id,
+        processor!(process_instruction),
+    );
+    let mut context = program_test.start_with_context().await;
+
+    let mut clock: Clock = context.get_sysvar();
+    let unlock_timestamp = clock.unix_timestamp + 365 * 24 * 60 * 60;
+    assert_eq!(
+        unlock(&mut context, program_id, unlock_timestamp).await,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(EARLY_ERROR)
+        ))
+    );
+
+    // Retrying the same transaction would be rejected as a duplicate
+    let (blockhash, _fee_calculator) = context
+        .banks_client
+        .get_new_blockhash(&context.last_blockhash)
+        .await
+        .unwrap();
+    context.last_blockhash = blockhash;
+
+    clock.unix_timestamp = unlock_timestamp;
+    context.set_sysvar(&clock);
+    assert_eq!(
+        context.get_sysvar::<Clock>().unix_timestamp,
+        unlock_timestamp
+    );
+    unlock(&mut context, program_id, unlock_timestamp)
+        .await
+        .unwrap();
+
+    // The clock does not go back to the estimated time after a warp
+    let epoch = context.get_sysvar::<Clock>().epoch;
+    context.warp_to_epoch(epoch + 1).unwrap();
+    let clock: Clock = context.get_sysvar();
+    assert_eq!(clock.epoch, epoch + 1);
+    assert_eq!(
+        clock.slot,
+        context
+            .get_sysvar::<EpochSchedule>()
+            .get_first_slot_in_epoch(epoch + 1)
+    );
+    assert!(clock.unix_timestamp >= unlock_timestamp);
+    unlock(&mut context, program_id, unlock_timestamp)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        context.warp_to_epoch(epoch).unwrap_err(),
+        ProgramTestError::InvalidWarpEpoch,
+    );
+}
+
+#[tokio::test]
+async fn rent_and_account_set() {
+    let program_test = ProgramTest::default();
+    let mut context = program_test.start_with_context().await;
+
+    let rent = Rent {
+        lamports_per_byte_year: Rent::default().lamports_per_byte_year * 2,
+        ..Rent::default()
+    };
+    context.set_sysvar(&rent);
+    let rent_account = context
+        .banks_client
+        .get_account(rent::id())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(from_account::<Rent>(&rent_account), Some(rent));
+
+    let address = Pubkey::new_unique();
+    let account = Account {
+        lamports: 42,
+        data: vec![1, 2, 3],
+        owner: Pubkey::new_unique(),
+        ..Account::default()
+    };
+    context.set_account(&address, &account);
+    assert_eq!(
+        context.banks_client.get_account(address).await.unwrap(),
+        Some(account)
+    );
+}
@@ -0,0 +1,165 @@
+use crate::{id, state::AddressLookupTable};
+use serde_derive::{Deserialize, Serialize};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_instruction, sysvar,
+};
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub enum ProgramInstruction {
+    /// Initialize an address lookup table account.
+    ///
+    /// The account must have been created by the system program with enough
+    /// space for the addresses it will hold and assigned to the address
+    /// lookup table program. The `InitializeLookupTable` instruction requires
+    /// no signers and MUST be included within the same Transaction as the
+    /// system program's `CreateAccount` instruction that creates the account
+    /// being initialized. Otherwise another party may initialize the account.
+    ///
+    /// # Account references
+    ///   0. `[WRITE]` Uninitialized address lookup table account
+    ///   1. `[]` Account that will be allowed to modify the lookup table
+    InitializeLookupTable,
+
+    /// Permanently freeze an address lookup table, making it immutable.
+    ///
+    /// # Account references
+    ///   0. `[WRITE]` Address lookup table account to freeze
+    ///   1. `[SIGNER]` Current authority
+    FreezeLookupTable,
+
+    /// Extend an address lookup table with new addresses
+    ///
+    /// # Account references
+    ///   0. `[WRITE]` Address lookup table account to extend
+    ///   1. `[SIGNER]` Current authority
+    ///   2. `[]` Clock sysvar
+    ExtendLookupTable { new_addresses: Vec<Pubkey> },
+
+    /// Deactivate an address lookup table, making it unusable and
+    /// eligible for closure after a short period of time.
+    ///
+    /// # Account references
+    ///   0. `[WRITE]` Address lookup table account to deactivate
+    ///   1. `[SIGNER]` Current authority
+    ///   2. `[]` Clock sysvar
+    DeactivateLookupTable,
+
+    /// Close an address lookup table account
+    ///
+    /// # Account references
+    ///   0. `[WRITE]` Address lookup table account to close
+    ///   1. `[SIGNER]` Current authority
+    ///   2. `[WRITE]` Recipient of closed account lamports
+    ///   3. `[]` Clock sysvar
+    CloseLookupTable,
+}
+
+/// Returns the instructions required to create and initialize an address
+/// lookup table account that can hold up to `max_addresses` addresses
+pub fn create_lookup_table(
+    payer_address: &Pubkey,
+    lookup_table_address: &Pubkey,
+    authority_address: &Pubkey,
+    lamports: u64,
+    max_addresses: usize,
+) -> Vec<Instruction> {
+    vec![
+        system_instruction::create_account(
+            payer_address,
+            lookup_table_address,
+            lamports,
+            AddressLookupTable::data_len(max_addresses) as u64,
+            &id(),
+        ),
+        initialize_lookup_table(lookup_table_address, authority_address),
+    ]
+}
+
+/// Returns an instruction that initializes a pre-allocated lookup table
+pub fn initialize_lookup_table(
+    lookup_table_address: &Pubkey,
+    authority_address: &Pubkey,
+) -> Instruction {
+    Instruction::new(
+        id(),
+        &ProgramInstruction::InitializeLookupTable,
+        vec![
+            AccountMeta::new(*lookup_table_address, false),
+            AccountMeta::new_readonly(*authority_address, false),
+        ],
+    )
+}
+
+/// Returns an instruction that freezes an address lookup table
+/// so that it can never be closed or extended again. Empty lookup
+/// tables cannot be frozen.
+pub fn freeze_lookup_table(
+    lookup_table_address: &Pubkey,
+    authority_address: &Pubkey,
+) -> Instruction {
+    Instruction::new(
+        id(),
+        &ProgramInstruction::FreezeLookupTable,
+        vec![
+            AccountMeta::new(*lookup_table_address, false),
+            AccountMeta::new_readonly(*authority_address, true),
+        ],
+    )
+}
+
+/// Returns an instruction which extends an address lookup table with
+/// new addresses
+pub fn extend_lookup_table(
+    lookup_table_address: &Pubkey,
+    authority_address: &Pubkey,
+    new_addresses: Vec<Pubkey>,
+) -> Instruction {
+    Instruction::new(
+        id(),
+        &ProgramInstruction::ExtendLookupTable { new_addresses },
+        vec![
+            AccountMeta::new(*lookup_table_address, false),
+            AccountMeta::new_readonly(*authority_address, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+    )
+}
+
+/// Returns an instruction that deactivates an address lookup table
+/// so that it cannot be extended again and will be unusable and safe
+/// for closure after a short amount of time.
+pub fn deactivate_lookup_table(
+    lookup_table_address: &Pubkey,
+    authority_address: &Pubkey,
+) -> Instruction {
+    Instruction::new(
+        id(),
+        &ProgramInstruction::DeactivateLookupTable,
+        vec![
+            AccountMeta::new(*lookup_table_address, false),
+            AccountMeta::new_readonly(*authority_address, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+    )
+}
+
+/// Returns an instruction that closes a deactivated address lookup table
+/// account after it has cooled down
+pub fn close_lookup_table(
+    lookup_table_address: &Pubkey,
+    authority_address: &Pubkey,
+    recipient_address: &Pubkey,
+) -> Instruction {
+    Instruction::new(
+        id(),
+        &ProgramInstruction::CloseLookupTable,
+        vec![
+            AccountMeta::new(*lookup_table_address, false),
+            AccountMeta::new_readonly(*authority_address, true),
+            AccountMeta::new(*recipient_address, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+    )
+}
@@ -0,0 +1,9 @@
+//! Address lookup table program, which stores lists of addresses that
+//! versioned transactions can reference by index instead of listing them
+//! in their message
+
+pub mod instruction;
+pub mod processor;
+pub mod state;
+
+solana_sdk::declare_id!("AddressLookupTab1e1111111111111111111111111");
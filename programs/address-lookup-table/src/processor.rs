@@ -0,0 +1,426 @@
+//! Address lookup table program
+
+use crate::{
+    instruction::ProgramInstruction,
+    state::{
+        AddressLookupTable, LookupTableMeta, ProgramState, DEACTIVATION_COOLDOWN,
+        LOOKUP_TABLE_META_SIZE,
+    },
+};
+use solana_sdk::{
+    account_utils::State,
+    ic_msg,
+    instruction::InstructionError,
+    keyed_account::{from_keyed_account, next_keyed_account, KeyedAccount},
+    process_instruction::InvokeContext,
+    program_utils::limited_deserialize,
+    pubkey::Pubkey,
+    sysvar::clock::Clock,
+};
+
+pub fn process_instruction(
+    program_id: &Pubkey,
+    keyed_accounts: &[KeyedAccount],
+    instruction_data: &[u8],
+    invoke_context: &mut dyn InvokeContext,
+) -> Result<(), InstructionError> {
+    let keyed_accounts_iter = &mut keyed_accounts.iter();
+    let lookup_table_account = next_keyed_account(keyed_accounts_iter)?;
+
+    if lookup_table_account.owner()? != *program_id {
+        ic_msg!(
+            invoke_context,
+            "Lookup table owner should be the address lookup table program"
+        );
+        return Err(InstructionError::IncorrectProgramId);
+    }
+
+    match limited_deserialize(instruction_data)? {
+        ProgramInstruction::InitializeLookupTable => {
+            let authority_account = next_keyed_account(keyed_accounts_iter)?;
+
+            let state: ProgramState = lookup_table_account.state()?;
+            if state != ProgramState::Uninitialized {
+                ic_msg!(invoke_context, "Lookup table is already initialized");
+                return Err(InstructionError::AccountAlreadyInitialized);
+            }
+            if lookup_table_account.data_len()? < LOOKUP_TABLE_META_SIZE {
+                ic_msg!(invoke_context, "Lookup table account data is too small");
+                return Err(InstructionError::AccountDataTooSmall);
+            }
+
+            lookup_table_account.set_state(&ProgramState::LookupTable(LookupTableMeta::new(
+                *authority_account.unsigned_key(),
+            )))?;
+        }
+        ProgramInstruction::FreezeLookupTable => {
+            let authority_account = next_keyed_account(keyed_accounts_iter)?;
+
+            let mut lookup_table = get_lookup_table(lookup_table_account)?;
+            check_authority(&lookup_table.meta, authority_account, invoke_context)?;
+            if !lookup_table.meta.is_active() {
+                ic_msg!(invoke_context, "Deactivated tables cannot be frozen");
+                return Err(InstructionError::InvalidArgument);
+            }
+            if lookup_table.addresses.is_empty() {
+                ic_msg!(invoke_context, "Empty lookup tables cannot be frozen");
+                return Err(InstructionError::InvalidInstructionData);
+            }
+
+            lookup_table.meta.authority = None;
+            lookup_table.serialize_into(&mut lookup_table_account.try_account_ref_mut()?.data)?;
+        }
+        ProgramInstruction::ExtendLookupTable { new_addresses } => {
+            let authority_account = next_keyed_account(keyed_accounts_iter)?;
+            let clock = from_keyed_account::<Clock>(next_keyed_account(keyed_accounts_iter)?)?;
+
+            let mut lookup_table = get_lookup_table(lookup_table_account)?;
+            check_authority(&lookup_table.meta, authority_account, invoke_context)?;
+            if !lookup_table.meta.is_active() {
+                ic_msg!(invoke_context, "Deactivated tables cannot be extended");
+                return Err(InstructionError::InvalidArgument);
+            }
+            if new_addresses.is_empty() {
+                ic_msg!(invoke_context, "Must extend with at least one address");
+                return Err(InstructionError::InvalidInstructionData);
+            }
+
+            let old_num_addresses = lookup_table.addresses.len();
+            let new_num_addresses = old_num_addresses.saturating_add(new_addresses.len());
+            let capacity = AddressLookupTable::capacity(lookup_table_account.data_len()?);
+            if new_num_addresses > capacity {
+                ic_msg!(
+                    invoke_context,
+                    "Extended lookup table length {} would exceed capacity of {}",
+                    new_num_addresses,
+                    capacity,
+                );
+                return Err(InstructionError::InvalidInstructionData);
+            }
+
+            if clock.slot != lookup_table.meta.last_extended_slot {
+                lookup_table.meta.last_extended_slot = clock.slot;
+                lookup_table.meta.last_extended_slot_start_index = old_num_addresses as u8;
+            }
+            lookup_table.meta.num_addresses = new_num_addresses as u16;
+            lookup_table.addresses.extend(new_addresses);
+            lookup_table.serialize_into(&mut lookup_table_account.try_account_ref_mut()?.data)?;
+        }
+        ProgramInstruction::DeactivateLookupTable => {
+            let authority_account = next_keyed_account(keyed_accounts_iter)?;
+            let clock = from_keyed_account::<Clock>(next_keyed_account(keyed_accounts_iter)?)?;
+
+            let mut lookup_table = get_lookup_table(lookup_table_account)?;
+            check_authority(&lookup_table.meta, authority_account, invoke_context)?;
+            if !lookup_table.meta.is_active() {
+                ic_msg!(invoke_context, "Lookup table is already deactivated");
+                return Err(InstructionError::InvalidArgument);
+            }
+
+            lookup_table.meta.deactivation_slot = clock.slot;
+            lookup_table.serialize_into(&mut lookup_table_account.try_account_ref_mut()?.data)?;
+        }
+        ProgramInstruction::CloseLookupTable => {
+            let authority_account = next_keyed_account(keyed_accounts_iter)?;
+            let recipient_account = next_keyed_account(keyed_accounts_iter)?;
+            let clock = from_keyed_account::<Clock>(next_keyed_account(keyed_accounts_iter)?)?;
+
+            if recipient_account.unsigned_key() == lookup_table_account.unsigned_key() {
+                ic_msg!(
+                    invoke_context,
+                    "Lookup table cannot be the recipient of reclaimed lamports"
+                );
+                return Err(InstructionError::InvalidArgument);
+            }
+
+            let lookup_table = get_lookup_table(lookup_table_account)?;
+            check_authority(&lookup_table.meta, authority_account, invoke_context)?;
+            if lookup_table.meta.is_active() {
+                ic_msg!(invoke_context, "Lookup table is not deactivated");
+                return Err(InstructionError::InvalidArgument);
+            }
+            if lookup_table.meta.is_usable(clock.slot) {
+                ic_msg!(
+                    invoke_context,
+                    "Lookup table cannot be closed until slot {}",
+                    lookup_table
+                        .meta
+                        .deactivation_slot
+                        .saturating_add(DEACTIVATION_COOLDOWN)
+                        .saturating_add(1),
+                );
+                return Err(InstructionError::InvalidArgument);
+            }
+
+            let mut lookup_table_account = lookup_table_account.try_account_ref_mut()?;
+            recipient_account.try_account_ref_mut()?.lamports += lookup_table_account.lamports;
+            lookup_table_account.lamports = 0;
+            lookup_table_account
+                .data
+                .iter_mut()
+                .for_each(|byte| *byte = 0);
+        }
+    }
+
+    Ok(())
+}
+
+fn get_lookup_table(
+    lookup_table_account: &KeyedAccount,
+) -> Result<AddressLookupTable, InstructionError> {
+    AddressLookupTable::deserialize(&lookup_table_account.try_account_ref()?.data)
+}
+
+fn check_authority(
+    meta: &LookupTableMeta,
+    authority_account: &KeyedAccount,
+    invoke_context: &dyn InvokeContext,
+) -> Result<(), InstructionError> {
+    let authority = match meta.authority {
+        Some(authority) => authority,
+        None => {
+            ic_msg!(invoke_context, "Lookup table is frozen");
+            return Err(InstructionError::Immutable);
+        }
+    };
+    if authority != *authority_account.unsigned_key() {
+        ic_msg!(invoke_context, "Incorrect lookup table authority");
+        return Err(InstructionError::IncorrectAuthority);
+    }
+    if authority_account.signer_key().is_none() {
+        ic_msg!(invoke_context, "Lookup table authority did not sign");
+        return Err(InstructionError::MissingRequiredSignature);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{id, instruction};
+    use solana_sdk::{
+        account::{self, Account},
+        instruction::Instruction,
+        process_instruction::MockInvokeContext,
+        sysvar,
+    };
+    use std::cell::RefCell;
+
+    fn process(
+        instruction: &Instruction,
+        accounts: &[(Pubkey, &RefCell<Account>)],
+    ) -> Result<(), InstructionError> {
+        let keyed_accounts: Vec<_> = instruction
+            .accounts
+            .iter()
+            .map(|meta| {
+                let (key, account) = accounts
+                    .iter()
+                    .find(|(key, _)| *key == meta.pubkey)
+                    .unwrap();
+                KeyedAccount::new(key, meta.is_signer, account)
+            })
+            .collect();
+        process_instruction(
+            &id(),
+            &keyed_accounts,
+            &instruction.data,
+            &mut MockInvokeContext::default(),
+        )
+    }
+
+    fn clock_account(slot: u64) -> RefCell<Account> {
+        RefCell::new(account::create_account(
+            &Clock {
+                slot,
+                ..Clock::default()
+            },
+            1,
+        ))
+    }
+
+    fn new_lookup_table_account(max_addresses: usize) -> RefCell<Account> {
+        RefCell::new(Account::new(
+            1_000,
+            AddressLookupTable::data_len(max_addresses),
+            &id(),
+        ))
+    }
+
+    #[test]
+    fn test_initialize() {
+        let table_address = Pubkey::new_unique();
+        let authority_address = Pubkey::new_unique();
+        let table_account = new_lookup_table_account(2);
+        let authority_account = RefCell::new(Account::default());
+        let accounts = [
+            (table_address, &table_account),
+            (authority_address, &authority_account),
+        ];
+        let ix = instruction::initialize_lookup_table(&table_address, &authority_address);
+
+        assert_eq!(process(&ix, &accounts), Ok(()));
+        let table = AddressLookupTable::deserialize(&table_account.borrow().data).unwrap();
+        assert_eq!(table.meta, LookupTableMeta::new(authority_address));
+        assert!(table.addresses.is_empty());
+
+        assert_eq!(
+            process(&ix, &accounts),
+            Err(InstructionError::AccountAlreadyInitialized)
+        );
+
+        let wrong_owner_account = RefCell::new(Account::new(1, 100, &Pubkey::new_unique()));
+        assert_eq!(
+            process(
+                &ix,
+                &[
+                    (table_address, &wrong_owner_account),
+                    (authority_address, &authority_account),
+                ]
+            ),
+            Err(InstructionError::IncorrectProgramId)
+        );
+    }
+
+    #[test]
+    fn test_extend() {
+        let table_address = Pubkey::new_unique();
+        let authority_address = Pubkey::new_unique();
+        let table_account = new_lookup_table_account(2);
+        let authority_account = RefCell::new(Account::default());
+        let clock = clock_account(10);
+        let accounts = [
+            (table_address, &table_account),
+            (authority_address, &authority_account),
+            (sysvar::clock::id(), &clock),
+        ];
+        process(
+            &instruction::initialize_lookup_table(&table_address, &authority_address),
+            &accounts,
+        )
+        .unwrap();
+
+        let new_addresses = vec![Pubkey::new_unique()];
+        let ix = instruction::extend_lookup_table(
+            &table_address,
+            &authority_address,
+            new_addresses.clone(),
+        );
+        assert_eq!(process(&ix, &accounts), Ok(()));
+        let table = AddressLookupTable::deserialize(&table_account.borrow().data).unwrap();
+        assert_eq!(table.addresses, new_addresses);
+        assert_eq!(table.meta.last_extended_slot, 10);
+        assert_eq!(table.meta.last_extended_slot_start_index, 0);
+
+        // a second extension in the same slot keeps the original start index
+        assert_eq!(process(&ix, &accounts), Ok(()));
+        let table = AddressLookupTable::deserialize(&table_account.borrow().data).unwrap();
+        assert_eq!(table.meta.num_addresses, 2);
+        assert_eq!(table.meta.last_extended_slot_start_index, 0);
+
+        // the table is full
+        assert_eq!(
+            process(&ix, &accounts),
+            Err(InstructionError::InvalidInstructionData)
+        );
+
+        let mut unsigned_ix = ix;
+        unsigned_ix.accounts[1].is_signer = false;
+        assert_eq!(
+            process(&unsigned_ix, &accounts),
+            Err(InstructionError::MissingRequiredSignature)
+        );
+    }
+
+    #[test]
+    fn test_freeze() {
+        let table_address = Pubkey::new_unique();
+        let authority_address = Pubkey::new_unique();
+        let table_account = new_lookup_table_account(2);
+        let authority_account = RefCell::new(Account::default());
+        let clock = clock_account(10);
+        let accounts = [
+            (table_address, &table_account),
+            (authority_address, &authority_account),
+            (sysvar::clock::id(), &clock),
+        ];
+        process(
+            &instruction::initialize_lookup_table(&table_address, &authority_address),
+            &accounts,
+        )
+        .unwrap();
+
+        let ix = instruction::freeze_lookup_table(&table_address, &authority_address);
+        assert_eq!(
+            process(&ix, &accounts),
+            Err(InstructionError::InvalidInstructionData)
+        );
+
+        process(
+            &instruction::extend_lookup_table(
+                &table_address,
+                &authority_address,
+                vec![Pubkey::new_unique()],
+            ),
+            &accounts,
+        )
+        .unwrap();
+        assert_eq!(process(&ix, &accounts), Ok(()));
+        let table = AddressLookupTable::deserialize(&table_account.borrow().data).unwrap();
+        assert_eq!(table.meta.authority, None);
+
+        assert_eq!(process(&ix, &accounts), Err(InstructionError::Immutable));
+    }
+
+    #[test]
+    fn test_deactivate_and_close() {
+        let table_address = Pubkey::new_unique();
+        let authority_address = Pubkey::new_unique();
+        let recipient_address = Pubkey::new_unique();
+        let table_account = new_lookup_table_account(2);
+        let authority_account = RefCell::new(Account::default());
+        let recipient_account = RefCell::new(Account::default());
+        let clock = clock_account(10);
+        let accounts = [
+            (table_address, &table_account),
+            (authority_address, &authority_account),
+            (recipient_address, &recipient_account),
+            (sysvar::clock::id(), &clock),
+        ];
+        process(
+            &instruction::initialize_lookup_table(&table_address, &authority_address),
+            &accounts,
+        )
+        .unwrap();
+
+        let close_ix =
+            instruction::close_lookup_table(&table_address, &authority_address, &recipient_address);
+        assert_eq!(
+            process(&close_ix, &accounts),
+            Err(InstructionError::InvalidArgument)
+        );
+
+        let deactivate_ix =
+            instruction::deactivate_lookup_table(&table_address, &authority_address);
+        assert_eq!(process(&deactivate_ix, &accounts), Ok(()));
+        let table = AddressLookupTable::deserialize(&table_account.borrow().data).unwrap();
+        assert_eq!(table.meta.deactivation_slot, 10);
+        assert_eq!(
+            process(&deactivate_ix, &accounts),
+            Err(InstructionError::InvalidArgument)
+        );
+
+        // still cooling down
+        *clock.borrow_mut() = clock_account(10 + DEACTIVATION_COOLDOWN).into_inner();
+        assert_eq!(
+            process(&close_ix, &accounts),
+            Err(InstructionError::InvalidArgument)
+        );
+
+        *clock.borrow_mut() = clock_account(11 + DEACTIVATION_COOLDOWN).into_inner();
+        assert_eq!(process(&close_ix, &accounts), Ok(()));
+        assert_eq!(table_account.borrow().lamports, 0);
+        assert_eq!(recipient_account.borrow().lamports, 1_000);
+        assert!(table_account.borrow().data.iter().all(|byte| *byte == 0));
+    }
+}
@@ -0,0 +1,258 @@
+//! Address lookup table account state
+
+use serde_derive::{Deserialize, Serialize};
+use solana_sdk::{clock::Slot, instruction::InstructionError, pubkey::Pubkey};
+use std::mem::size_of;
+
+/// The maximum number of addresses that a lookup table can hold
+pub const LOOKUP_TABLE_MAX_ADDRESSES: usize = 256;
+
+/// The serialized size of lookup table metadata, reserved at the start of
+/// every lookup table account before the stored addresses
+pub const LOOKUP_TABLE_META_SIZE: usize = 56;
+
+/// Number of slots a deactivated lookup table must wait before it can be
+/// closed. Any transaction that was signed while the table was still active
+/// will have an expired blockhash by then, so the table address cannot be
+/// reused to resolve a signed transaction to different accounts.
+pub const DEACTIVATION_COOLDOWN: Slot = solana_sdk::slot_hashes::MAX_ENTRIES as Slot;
+
+/// Program account states
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub enum ProgramState {
+    /// Account is not initialized.
+    Uninitialized,
+    /// Initialized `LookupTable` account.
+    LookupTable(LookupTableMeta),
+}
+
+/// Address lookup table metadata
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct LookupTableMeta {
+    /// Deactivated lookup tables cannot be modified, but remain usable until
+    /// `DEACTIVATION_COOLDOWN` slots have passed, after which they may be
+    /// closed
+    pub deactivation_slot: Slot,
+    /// The slot that the table was last extended. Address tables may only
+    /// be used to lookup addresses that were extended before the current
+    /// slot.
+    pub last_extended_slot: Slot,
+    /// The start index where the table was last extended from during the
+    /// `last_extended_slot`.
+    pub last_extended_slot_start_index: u8,
+    /// Number of addresses stored in the table
+    pub num_addresses: u16,
+    /// Authority address which must sign for each modification. Frozen
+    /// tables have no authority and can never be modified again.
+    pub authority: Option<Pubkey>,
+}
+
+impl Default for LookupTableMeta {
+    fn default() -> Self {
+        Self {
+            deactivation_slot: Slot::MAX,
+            last_extended_slot: 0,
+            last_extended_slot_start_index: 0,
+            num_addresses: 0,
+            authority: None,
+        }
+    }
+}
+
+impl LookupTableMeta {
+    pub fn new(authority: Pubkey) -> Self {
+        LookupTableMeta {
+            authority: Some(authority),
+            ..LookupTableMeta::default()
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.deactivation_slot == Slot::MAX
+    }
+
+    /// Whether transactions in `current_slot` may look up addresses in the
+    /// table. A deactivated table stays usable until its cooldown ends, and
+    /// only then may it be closed.
+    pub fn is_usable(&self, current_slot: Slot) -> bool {
+        current_slot <= self.deactivation_slot.saturating_add(DEACTIVATION_COOLDOWN)
+    }
+
+    /// Number of stored addresses that transactions in `current_slot` may
+    /// reference; addresses appended during `current_slot` are excluded
+    pub fn active_addresses_len(&self, current_slot: Slot) -> usize {
+        if current_slot > self.last_extended_slot {
+            usize::from(self.num_addresses)
+        } else {
+            usize::from(self.last_extended_slot_start_index)
+        }
+    }
+}
+
+/// Deserialized contents of an initialized lookup table account
+#[derive(Debug, PartialEq, Clone)]
+pub struct AddressLookupTable {
+    pub meta: LookupTableMeta,
+    pub addresses: Vec<Pubkey>,
+}
+
+impl AddressLookupTable {
+    /// Account data length required to store up to `max_addresses`
+    pub fn data_len(max_addresses: usize) -> usize {
+        LOOKUP_TABLE_META_SIZE + max_addresses * size_of::<Pubkey>()
+    }
+
+    /// Number of addresses that an account with `data_len` bytes can hold
+    pub fn capacity(data_len: usize) -> usize {
+        (data_len.saturating_sub(LOOKUP_TABLE_META_SIZE) / size_of::<Pubkey>())
+            .min(LOOKUP_TABLE_MAX_ADDRESSES)
+    }
+
+    pub fn deserialize(data: &[u8]) -> Result<Self, InstructionError> {
+        let meta = match bincode::deserialize::<ProgramState>(data)
+            .map_err(|_| InstructionError::InvalidAccountData)?
+        {
+            ProgramState::LookupTable(meta) => meta,
+            ProgramState::Uninitialized => return Err(InstructionError::UninitializedAccount),
+        };
+
+        let addresses_end =
+            LOOKUP_TABLE_META_SIZE + usize::from(meta.num_addresses) * size_of::<Pubkey>();
+        let addresses = data
+            .get(LOOKUP_TABLE_META_SIZE..addresses_end)
+            .ok_or(InstructionError::InvalidAccountData)?
+            .chunks(size_of::<Pubkey>())
+            .map(Pubkey::new)
+            .collect();
+
+        Ok(Self { meta, addresses })
+    }
+
+    /// Write the table metadata and addresses into `data`
+    pub fn serialize_into(&self, data: &mut [u8]) -> Result<(), InstructionError> {
+        let addresses_end = LOOKUP_TABLE_META_SIZE + self.addresses.len() * size_of::<Pubkey>();
+        if data.len() < addresses_end {
+            return Err(InstructionError::AccountDataTooSmall);
+        }
+        bincode::serialize_into(
+            &mut data[..LOOKUP_TABLE_META_SIZE],
+            &ProgramState::LookupTable(self.meta.clone()),
+        )
+        .map_err(|_| InstructionError::GenericError)?;
+        for (chunk, address) in data[LOOKUP_TABLE_META_SIZE..addresses_end]
+            .chunks_mut(size_of::<Pubkey>())
+            .zip(self.addresses.iter())
+        {
+            chunk.copy_from_slice(address.as_ref());
+        }
+        Ok(())
+    }
+
+    /// Resolve `indexes` into addresses that are usable during `current_slot`,
+    /// returning `None` if any index is out of range
+    pub fn lookup(&self, current_slot: Slot, indexes: &[u8]) -> Option<Vec<Pubkey>> {
+        let active_addresses_len = self.meta.active_addresses_len(current_slot);
+        indexes
+            .iter()
+            .map(|index| {
+                let index = usize::from(*index);
+                if index < active_addresses_len {
+                    self.addresses.get(index).copied()
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_meta_size() {
+        let meta = ProgramState::LookupTable(LookupTableMeta {
+            authority: Some(Pubkey::new_unique()),
+            ..LookupTableMeta::default()
+        });
+        let meta_size = bincode::serialized_size(&meta).unwrap();
+        assert!(meta_size as usize <= LOOKUP_TABLE_META_SIZE);
+    }
+
+    #[test]
+    fn test_capacity() {
+        assert_eq!(AddressLookupTable::capacity(0), 0);
+        assert_eq!(AddressLookupTable::capacity(LOOKUP_TABLE_META_SIZE), 0);
+        assert_eq!(
+            AddressLookupTable::capacity(AddressLookupTable::data_len(10)),
+            10
+        );
+        assert_eq!(
+            AddressLookupTable::capacity(AddressLookupTable::data_len(300)),
+            LOOKUP_TABLE_MAX_ADDRESSES
+        );
+    }
+
+    #[test]
+    fn test_serialize_roundtrip() {
+        let table = AddressLookupTable {
+            meta: LookupTableMeta {
+                num_addresses: 2,
+                ..LookupTableMeta::new(Pubkey::new_unique())
+            },
+            addresses: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+        };
+        let mut data = vec![0; AddressLookupTable::data_len(4)];
+        table.serialize_into(&mut data).unwrap();
+        assert_eq!(AddressLookupTable::deserialize(&data).unwrap(), table);
+
+        let mut small_data = vec![0; AddressLookupTable::data_len(1)];
+        assert_eq!(
+            table.serialize_into(&mut small_data),
+            Err(InstructionError::AccountDataTooSmall)
+        );
+
+        assert_eq!(
+            AddressLookupTable::deserialize(&[0; LOOKUP_TABLE_META_SIZE]),
+            Err(InstructionError::UninitializedAccount)
+        );
+    }
+
+    #[test]
+    fn test_is_usable_during_deactivation_cooldown() {
+        let meta = LookupTableMeta::new(Pubkey::new_unique());
+        assert!(meta.is_usable(Slot::MAX));
+
+        let meta = LookupTableMeta {
+            deactivation_slot: 10,
+            ..meta
+        };
+        assert!(!meta.is_active());
+        assert!(meta.is_usable(10));
+        assert!(meta.is_usable(10 + DEACTIVATION_COOLDOWN));
+        assert!(!meta.is_usable(11 + DEACTIVATION_COOLDOWN));
+    }
+
+    #[test]
+    fn test_lookup_excludes_current_slot_extensions() {
+        let addresses = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+        let table = AddressLookupTable {
+            meta: LookupTableMeta {
+                last_extended_slot: 5,
+                last_extended_slot_start_index: 1,
+                num_addresses: 2,
+                ..LookupTableMeta::new(Pubkey::new_unique())
+            },
+            addresses: addresses.clone(),
+        };
+
+        assert_eq!(table.lookup(5, &[0]), Some(vec![addresses[0]]));
+        assert_eq!(table.lookup(5, &[1]), None);
+        assert_eq!(
+            table.lookup(6, &[1, 0]),
+            Some(vec![addresses[1], addresses[0]])
+        );
+        assert_eq!(table.lookup(6, &[2]), None);
+    }
+}
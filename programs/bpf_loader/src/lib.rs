@@ -15,7 +15,8 @@ use crate::{
     syscalls::SyscallError,
 };
 use solana_rbpf::{
-    ebpf::MM_HEAP_START,
+    disassembler,
+    ebpf::{self, ELF_INSN_DUMP_OFFSET, INSN_SIZE, MM_HEAP_START, MM_PROGRAM_START},
     error::{EbpfError, UserDefinedError},
     memory_region::MemoryRegion,
     vm::{Config, EbpfVm, Executable, InstructionMeter},
@@ -27,19 +28,22 @@ use solana_sdk::{
     bpf_loader_upgradeable::{self, UpgradeableLoaderState},
     clock::Clock,
     entrypoint::SUCCESS,
-    feature_set::matching_buffer_upgrade_authorities,
+    feature_set::{
+        close_upgradeable_program_accounts, matching_buffer_upgrade_authorities,
+        return_data_syscall_enabled, upgradeable_loader_extend_program,
+    },
     ic_logger_msg, ic_msg,
     instruction::InstructionError,
     keyed_account::{from_keyed_account, next_keyed_account, KeyedAccount},
     loader_instruction::LoaderInstruction,
     loader_upgradeable_instruction::UpgradeableLoaderInstruction,
-    process_instruction::{stable_log, ComputeMeter, Executor, InvokeContext},
+    process_instruction::{stable_log, BpfTrace, ComputeMeter, Executor, InvokeContext},
     program_utils::limited_deserialize,
     pubkey::Pubkey,
     rent::Rent,
     system_instruction::{self, MAX_PERMITTED_DATA_LENGTH},
 };
-use std::{cell::RefCell, fmt::Debug, rc::Rc, sync::Arc};
+use std::{cell::RefCell, collections::HashMap, fmt::Debug, rc::Rc, sync::Arc};
 use thiserror::Error;
 
 solana_sdk::declare_builtin!(
@@ -72,6 +76,7 @@ pub fn create_and_cache_executor(
     invoke_context: &mut dyn InvokeContext,
     use_jit: bool,
 ) -> Result<Arc<BpfExecutor>, InstructionError> {
+    let bpf_tracing = invoke_context.is_bpf_tracing_enabled();
     let bpf_compute_budget = invoke_context.get_bpf_compute_budget();
     let mut program = Executable::<BpfError, ThisInstructionMeter>::from_elf(
         data,
@@ -80,7 +85,7 @@ pub fn create_and_cache_executor(
             max_call_depth: bpf_compute_budget.max_call_depth,
             stack_frame_size: bpf_compute_budget.stack_frame_size,
             enable_instruction_meter: true,
-            enable_instruction_tracing: false,
+            enable_instruction_tracing: bpf_tracing,
         },
     )
     .map_err(|e| map_ebpf_error(invoke_context, e))?;
@@ -94,7 +99,8 @@ pub fn create_and_cache_executor(
         InstructionError::ProgramEnvironmentSetupFailure
     })?;
     program.set_syscall_registry(syscall_registry);
-    if use_jit {
+    // The JIT does not trace, so traced programs are always interpreted
+    if use_jit && !bpf_tracing {
         if let Err(err) = program.jit_compile() {
             ic_msg!(invoke_context, "Failed to compile program {:?}", err);
             return Err(InstructionError::ProgramFailedToCompile);
@@ -217,6 +223,13 @@ fn process_instruction_common(
                         );
                         return Err(InstructionError::InvalidArgument);
                     }
+                    if !matches!(
+                        programdata.state()?,
+                        UpgradeableLoaderState::ProgramData { .. }
+                    ) {
+                        ic_logger_msg!(logger, "Program has been closed");
+                        return Err(InstructionError::InvalidAccountData);
+                    }
                     (
                         programdata,
                         &keyed_accounts[1..],
@@ -663,6 +676,183 @@ fn process_loader_upgradeable_instruction(
 
             ic_logger_msg!(logger, "New authority {:?}", new_authority);
         }
+        UpgradeableLoaderInstruction::Close => {
+            if !invoke_context.is_feature_active(&close_upgradeable_program_accounts::id()) {
+                return Err(InstructionError::InvalidInstructionData);
+            }
+            let close_account = next_keyed_account(account_iter)?;
+            let recipient_account = next_keyed_account(account_iter)?;
+            if close_account.unsigned_key() == recipient_account.unsigned_key() {
+                ic_logger_msg!(logger, "Recipient is the same as the account being closed");
+                return Err(InstructionError::InvalidArgument);
+            }
+
+            match close_account.state()? {
+                UpgradeableLoaderState::Uninitialized => {}
+                UpgradeableLoaderState::Buffer { authority_address } => {
+                    let authority = next_keyed_account(account_iter)?;
+                    if authority_address == None {
+                        ic_logger_msg!(logger, "Buffer is immutable");
+                        return Err(InstructionError::Immutable);
+                    }
+                    if authority_address != Some(*authority.unsigned_key()) {
+                        ic_logger_msg!(logger, "Incorrect buffer authority provided");
+                        return Err(InstructionError::IncorrectAuthority);
+                    }
+                    if authority.signer_key().is_none() {
+                        ic_logger_msg!(logger, "Buffer authority did not sign");
+                        return Err(InstructionError::MissingRequiredSignature);
+                    }
+                }
+                UpgradeableLoaderState::ProgramData {
+                    slot: _,
+                    upgrade_authority_address,
+                } => {
+                    let authority = next_keyed_account(account_iter)?;
+                    let program = next_keyed_account(account_iter)?;
+                    if !program.is_writable() {
+                        ic_logger_msg!(logger, "Program account is not writable");
+                        return Err(InstructionError::InvalidArgument);
+                    }
+                    if &program.owner()? != program_id {
+                        ic_logger_msg!(logger, "Program account not owned by loader");
+                        return Err(InstructionError::IncorrectProgramId);
+                    }
+                    if let UpgradeableLoaderState::Program {
+                        programdata_address,
+                    } = program.state()?
+                    {
+                        if programdata_address != *close_account.unsigned_key() {
+                            ic_logger_msg!(logger, "Program and ProgramData account mismatch");
+                            return Err(InstructionError::InvalidArgument);
+                        }
+                    } else {
+                        ic_logger_msg!(logger, "Invalid Program account");
+                        return Err(InstructionError::InvalidAccountData);
+                    }
+                    if upgrade_authority_address == None {
+                        ic_logger_msg!(logger, "Program not upgradeable");
+                        return Err(InstructionError::Immutable);
+                    }
+                    if upgrade_authority_address != Some(*authority.unsigned_key()) {
+                        ic_logger_msg!(logger, "Incorrect upgrade authority provided");
+                        return Err(InstructionError::IncorrectAuthority);
+                    }
+                    if authority.signer_key().is_none() {
+                        ic_logger_msg!(logger, "Upgrade authority did not sign");
+                        return Err(InstructionError::MissingRequiredSignature);
+                    }
+                }
+                _ => {
+                    ic_logger_msg!(logger, "Account does not support closing");
+                    return Err(InstructionError::InvalidArgument);
+                }
+            }
+
+            // Drain the account to the recipient and mark it uninitialized
+
+            let recipient_lamports = recipient_account
+                .lamports()?
+                .checked_add(close_account.lamports()?)
+                .ok_or(InstructionError::ArithmeticOverflow)?;
+            recipient_account.try_account_ref_mut()?.lamports = recipient_lamports;
+            close_account.try_account_ref_mut()?.lamports = 0;
+            close_account.set_state(&UpgradeableLoaderState::Uninitialized)?;
+
+            ic_logger_msg!(logger, "Closed {:?}", close_account.unsigned_key());
+        }
+        UpgradeableLoaderInstruction::ExtendProgram { additional_bytes } => {
+            if !invoke_context.is_feature_active(&upgradeable_loader_extend_program::id()) {
+                return Err(InstructionError::InvalidInstructionData);
+            }
+            let programdata = next_keyed_account(account_iter)?;
+            let program = next_keyed_account(account_iter)?;
+            let rent = from_keyed_account::<Rent>(next_keyed_account(account_iter)?)?;
+            let system = next_keyed_account(account_iter)?;
+            let payer = next_keyed_account(account_iter)?;
+
+            if additional_bytes == 0 {
+                ic_logger_msg!(logger, "Additional bytes must be greater than 0");
+                return Err(InstructionError::InvalidInstructionData);
+            }
+
+            // Verify Program account
+
+            if &program.owner()? != program_id {
+                ic_logger_msg!(logger, "Program account not owned by loader");
+                return Err(InstructionError::IncorrectProgramId);
+            }
+            if let UpgradeableLoaderState::Program {
+                programdata_address,
+            } = program.state()?
+            {
+                if programdata_address != *programdata.unsigned_key() {
+                    ic_logger_msg!(logger, "Program and ProgramData account mismatch");
+                    return Err(InstructionError::InvalidArgument);
+                }
+            } else {
+                ic_logger_msg!(logger, "Invalid Program account");
+                return Err(InstructionError::InvalidAccountData);
+            }
+
+            // Verify ProgramData account
+
+            if !programdata.is_writable() {
+                ic_logger_msg!(logger, "ProgramData account not writeable");
+                return Err(InstructionError::InvalidArgument);
+            }
+            if &programdata.owner()? != program_id {
+                ic_logger_msg!(logger, "ProgramData account not owned by loader");
+                return Err(InstructionError::IncorrectProgramId);
+            }
+            if let UpgradeableLoaderState::ProgramData {
+                slot: _,
+                upgrade_authority_address,
+            } = programdata.state()?
+            {
+                if upgrade_authority_address == None {
+                    ic_logger_msg!(logger, "Cannot extend an immutable program");
+                    return Err(InstructionError::Immutable);
+                }
+            } else {
+                ic_logger_msg!(logger, "Invalid ProgramData account");
+                return Err(InstructionError::InvalidAccountData);
+            }
+
+            let new_len = programdata
+                .data_len()?
+                .saturating_add(additional_bytes as usize);
+            if new_len > MAX_PERMITTED_DATA_LENGTH as usize {
+                ic_logger_msg!(logger, "Extended ProgramData length is too large");
+                return Err(InstructionError::InvalidArgument);
+            }
+
+            // Fund the ProgramData account to rent-exemption at its new length
+
+            let required_payment = rent
+                .minimum_balance(new_len)
+                .saturating_sub(programdata.lamports()?);
+            if required_payment > 0 {
+                MessageProcessor::native_invoke(
+                    invoke_context,
+                    system_instruction::transfer(
+                        payer.unsigned_key(),
+                        programdata.unsigned_key(),
+                        required_payment,
+                    ),
+                    &[payer, programdata, system],
+                    &[],
+                )?;
+            }
+
+            programdata.try_account_ref_mut()?.data.resize(new_len, 0);
+
+            ic_logger_msg!(
+                logger,
+                "Extended ProgramData account by {} bytes",
+                additional_bytes
+            );
+        }
     }
 
     Ok(())
@@ -754,6 +944,99 @@ impl Debug for BpfExecutor {
     }
 }
 
+impl BpfExecutor {
+    /// Symbolize the instructions traced during an invocation of the program, attributing each
+    /// of them to the function that was running it
+    fn bpf_trace(
+        &self,
+        program_id: &Pubkey,
+        invoke_depth: usize,
+        instructions: Vec<[u64; 12]>,
+        consumed_units: u64,
+    ) -> BpfTrace {
+        let (text_vaddr, text_bytes) = self.program.get_text_bytes().unwrap_or((0, &[]));
+        let text: HashMap<u64, disassembler::HlInsn> = disassembler::to_insn_vec(text_bytes)
+            .into_iter()
+            .map(|insn| (insn.ptr as u64, insn))
+            .collect();
+
+        // Symbols are numbered from ELF_INSN_DUMP_OFFSET instructions into the ELF, while traced
+        // program counters count instructions from the start of the text section.  Deployed
+        // programs are usually stripped of all symbols but the entrypoint
+        let text_start = text_vaddr.saturating_sub(MM_PROGRAM_START) / INSN_SIZE as u64;
+        let mut names: HashMap<u64, String> = self
+            .program
+            .get_symbols()
+            .1
+            .into_iter()
+            .filter_map(|(pc, (name, _))| {
+                let start = ((pc + ELF_INSN_DUMP_OFFSET) as u64).checked_sub(text_start)?;
+                Some((start, format!("{:#}", rustc_demangle::demangle(&name))))
+            })
+            .collect();
+        if let Ok(entrypoint) = self.program.get_entrypoint_instruction_offset() {
+            names
+                .entry(entrypoint as u64)
+                .or_insert_with(|| "entrypoint".to_string());
+        }
+
+        // Follow calls and returns to know which function each instruction belongs to, syscalls
+        // return to the instruction following the call
+        let mut units = HashMap::<u64, u64>::new();
+        let mut call_stack = instructions
+            .first()
+            .map(|registers| vec![registers[11]])
+            .unwrap_or_default();
+        for (index, registers) in instructions.iter().enumerate() {
+            let pc = registers[11];
+            if let Some(function) = call_stack.last() {
+                *units.entry(*function).or_default() += 1;
+            }
+            match text.get(&pc).map(|insn| insn.opc) {
+                Some(ebpf::CALL_IMM) | Some(ebpf::CALL_REG) => {
+                    if let Some(next) = instructions.get(index + 1) {
+                        if next[11] != pc + 1 {
+                            call_stack.push(next[11]);
+                        }
+                    }
+                }
+                Some(ebpf::EXIT) if call_stack.len() > 1 => {
+                    call_stack.pop();
+                }
+                _ => {}
+            }
+        }
+        let mut function_units: Vec<_> = units
+            .into_iter()
+            .map(|(start, units)| {
+                let name = names
+                    .get(&start)
+                    .cloned()
+                    .unwrap_or_else(|| format!("function at {}", start));
+                (name, units)
+            })
+            .collect();
+        function_units.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        let disassembly = instructions
+            .iter()
+            .filter_map(|registers| {
+                let pc = registers[11];
+                text.get(&pc).map(|insn| (pc, insn.desc.clone()))
+            })
+            .collect();
+
+        BpfTrace {
+            program_id: *program_id,
+            invoke_depth,
+            instructions,
+            disassembly,
+            consumed_units,
+            function_units,
+        }
+    }
+}
+
 impl Executor for BpfExecutor {
     fn execute(
         &self,
@@ -772,7 +1055,11 @@ impl Executor for BpfExecutor {
         let parameter_accounts = keyed_accounts_iter.as_slice();
         let mut parameter_bytes =
             serialize_parameters(loader_id, program_id, parameter_accounts, &instruction_data)?;
-        {
+        if invoke_context.is_feature_active(&return_data_syscall_enabled::id()) {
+            // Clear any return data left over from a previous invocation
+            invoke_context.set_return_data(None);
+        }
+        let (result, traced_instructions, consumed_units) = {
             let compute_meter = invoke_context.get_compute_meter();
             let mut vm = match create_vm(
                 loader_id,
@@ -791,7 +1078,8 @@ impl Executor for BpfExecutor {
             stable_log::program_invoke(&logger, program_id, invoke_depth);
             let mut instruction_meter = ThisInstructionMeter::new(compute_meter.clone());
             let before = compute_meter.borrow().get_remaining();
-            let result = if use_jit {
+            let tracing = self.program.get_config().enable_instruction_tracing;
+            let result = if use_jit && !tracing {
                 vm.execute_program_jit(&mut instruction_meter)
             } else {
                 vm.execute_program_interpreted(&mut instruction_meter)
@@ -804,28 +1092,42 @@ impl Executor for BpfExecutor {
                 before - after,
                 before
             );
-            match result {
-                Ok(status) => {
-                    if status != SUCCESS {
-                        let error: InstructionError = status.into();
-                        stable_log::program_failure(&logger, program_id, &error);
-                        return Err(error);
-                    }
-                }
-                Err(error) => {
-                    let error = match error {
-                        EbpfError::UserError(BpfError::SyscallError(
-                            SyscallError::InstructionError(error),
-                        )) => error,
-                        err => {
-                            ic_logger_msg!(logger, "Program failed to complete: {}", err);
-                            InstructionError::ProgramFailedToComplete
-                        }
-                    };
+            let traced_instructions = if tracing {
+                Some(vm.get_tracer().log.clone())
+            } else {
+                None
+            };
+            (result, traced_instructions, before - after)
+        };
+        if let Some(instructions) = traced_instructions {
+            invoke_context.record_bpf_trace(self.bpf_trace(
+                program_id,
+                invoke_depth,
+                instructions,
+                consumed_units,
+            ));
+        }
+        match result {
+            Ok(status) => {
+                if status != SUCCESS {
+                    let error: InstructionError = status.into();
                     stable_log::program_failure(&logger, program_id, &error);
                     return Err(error);
                 }
             }
+            Err(error) => {
+                let error = match error {
+                    EbpfError::UserError(BpfError::SyscallError(
+                        SyscallError::InstructionError(error),
+                    )) => error,
+                    err => {
+                        ic_logger_msg!(logger, "Program failed to complete: {}", err);
+                        InstructionError::ProgramFailedToComplete
+                    }
+                };
+                stable_log::program_failure(&logger, program_id, &error);
+                return Err(error);
+            }
         }
         deserialize_parameters(loader_id, parameter_accounts, &parameter_bytes)?;
         stable_log::program_success(&logger, program_id);
@@ -1104,9 +1406,15 @@ mod tests {
                 stack_frame_size: 4096,
                 log_pubkey_units: 100,
                 max_cpi_instruction_size: usize::MAX,
+                return_data_base_cost: 100,
+                return_data_bytes_per_unit: 250,
+                keccak256_base_cost: 85,
+                keccak256_byte_cost: 1,
+                secp256k1_recover_cost: 25_000,
             },
             Rc::new(RefCell::new(Executors::default())),
             None,
+            None,
             Arc::new(FeatureSet::default()),
         );
         assert_eq!(
@@ -1131,6 +1439,50 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_bpf_loader_tracing() {
+        let program_id = bpf_loader::id();
+        let program_key = solana_sdk::pubkey::new_rand();
+        let mut file = File::open("test_elfs/noop_aligned.so").expect("file open failed");
+        let mut elf = Vec::new();
+        file.read_to_end(&mut elf).unwrap();
+        let program_account = Account::new_ref(1, 0, &program_id);
+        program_account.borrow_mut().data = elf;
+        program_account.borrow_mut().executable = true;
+        let keyed_accounts = vec![KeyedAccount::new(&program_key, false, &program_account)];
+
+        let mut invoke_context = MockInvokeContext {
+            bpf_traces: Some(RefCell::default()),
+            ..MockInvokeContext::default()
+        };
+        assert_eq!(
+            Ok(()),
+            process_instruction(&program_key, &keyed_accounts, &[], &mut invoke_context)
+        );
+
+        let traces = invoke_context.bpf_traces.unwrap().into_inner();
+        assert_eq!(traces.len(), 1);
+        let trace = &traces[0];
+        assert_eq!(trace.program_id, program_key);
+        assert!(!trace.instructions.is_empty());
+        assert!(trace.consumed_units >= trace.instructions.len() as u64);
+        assert_eq!(
+            trace
+                .function_units
+                .iter()
+                .map(|(_, units)| units)
+                .sum::<u64>(),
+            trace.instructions.len() as u64
+        );
+        assert!(trace
+            .function_units
+            .iter()
+            .any(|(function, _)| function == "entrypoint"));
+        for registers in trace.instructions.iter() {
+            assert!(trace.disassembly.contains_key(&registers[11]));
+        }
+    }
+
     #[test]
     fn test_bpf_loader_serialize_unaligned() {
         let program_id = bpf_loader_deprecated::id();
@@ -3222,6 +3574,356 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_bpf_loader_upgradeable_close() {
+        let instruction = bincode::serialize(&UpgradeableLoaderInstruction::Close).unwrap();
+        let authority_address = Pubkey::new_unique();
+        let authority_account = Account::new_ref(1, 0, &Pubkey::new_unique());
+        let recipient_address = Pubkey::new_unique();
+        let recipient_account = Account::new_ref(1, 0, &Pubkey::new_unique());
+        let buffer_address = Pubkey::new_unique();
+        let buffer_account = Account::new_ref(
+            42,
+            UpgradeableLoaderState::buffer_len(0).unwrap(),
+            &bpf_loader_upgradeable::id(),
+        );
+        buffer_account
+            .borrow_mut()
+            .set_state(&UpgradeableLoaderState::Buffer {
+                authority_address: Some(authority_address),
+            })
+            .unwrap();
+        let uninitialized_address = Pubkey::new_unique();
+        let uninitialized_account = Account::new_ref(
+            42,
+            UpgradeableLoaderState::programdata_len(0).unwrap(),
+            &bpf_loader_upgradeable::id(),
+        );
+        let program_address = Pubkey::new_unique();
+        let (programdata_address, _) = Pubkey::find_program_address(
+            &[program_address.as_ref()],
+            &bpf_loader_upgradeable::id(),
+        );
+        let programdata_account = Account::new_ref(
+            42,
+            UpgradeableLoaderState::programdata_len(0).unwrap(),
+            &bpf_loader_upgradeable::id(),
+        );
+        programdata_account
+            .borrow_mut()
+            .set_state(&UpgradeableLoaderState::ProgramData {
+                slot: 0,
+                upgrade_authority_address: Some(authority_address),
+            })
+            .unwrap();
+        let program_account = Account::new_ref(
+            1,
+            UpgradeableLoaderState::program_len().unwrap(),
+            &bpf_loader_upgradeable::id(),
+        );
+        program_account.borrow_mut().executable = true;
+        program_account
+            .borrow_mut()
+            .set_state(&UpgradeableLoaderState::Program {
+                programdata_address,
+            })
+            .unwrap();
+
+        // Case: close a buffer account
+        assert_eq!(
+            Ok(()),
+            process_instruction(
+                &bpf_loader_upgradeable::id(),
+                &[
+                    KeyedAccount::new(&buffer_address, false, &buffer_account),
+                    KeyedAccount::new(&recipient_address, false, &recipient_account),
+                    KeyedAccount::new_readonly(&authority_address, true, &authority_account),
+                ],
+                &instruction,
+                &mut MockInvokeContext::default()
+            )
+        );
+        assert_eq!(0, buffer_account.borrow().lamports);
+        assert_eq!(43, recipient_account.borrow().lamports);
+        let state: UpgradeableLoaderState = buffer_account.borrow().state().unwrap();
+        assert_eq!(state, UpgradeableLoaderState::Uninitialized);
+
+        // Case: close with wrong authority
+        buffer_account
+            .borrow_mut()
+            .set_state(&UpgradeableLoaderState::Buffer {
+                authority_address: Some(authority_address),
+            })
+            .unwrap();
+        let incorrect_authority_address = Pubkey::new_unique();
+        assert_eq!(
+            Err(InstructionError::IncorrectAuthority),
+            process_instruction(
+                &bpf_loader_upgradeable::id(),
+                &[
+                    KeyedAccount::new(&buffer_address, false, &buffer_account),
+                    KeyedAccount::new(&recipient_address, false, &recipient_account),
+                    KeyedAccount::new_readonly(
+                        &incorrect_authority_address,
+                        true,
+                        &authority_account
+                    ),
+                ],
+                &instruction,
+                &mut MockInvokeContext::default()
+            )
+        );
+
+        // Case: authority did not sign
+        assert_eq!(
+            Err(InstructionError::MissingRequiredSignature),
+            process_instruction(
+                &bpf_loader_upgradeable::id(),
+                &[
+                    KeyedAccount::new(&buffer_address, false, &buffer_account),
+                    KeyedAccount::new(&recipient_address, false, &recipient_account),
+                    KeyedAccount::new_readonly(&authority_address, false, &authority_account),
+                ],
+                &instruction,
+                &mut MockInvokeContext::default()
+            )
+        );
+
+        // Case: recipient is the account being closed
+        assert_eq!(
+            Err(InstructionError::InvalidArgument),
+            process_instruction(
+                &bpf_loader_upgradeable::id(),
+                &[
+                    KeyedAccount::new(&buffer_address, false, &buffer_account),
+                    KeyedAccount::new(&buffer_address, false, &buffer_account),
+                    KeyedAccount::new_readonly(&authority_address, true, &authority_account),
+                ],
+                &instruction,
+                &mut MockInvokeContext::default()
+            )
+        );
+
+        // Case: recipient lamports overflow
+        let recipient_lamports = recipient_account.borrow().lamports;
+        recipient_account.borrow_mut().lamports = u64::MAX;
+        assert_eq!(
+            Err(InstructionError::ArithmeticOverflow),
+            process_instruction(
+                &bpf_loader_upgradeable::id(),
+                &[
+                    KeyedAccount::new(&uninitialized_address, false, &uninitialized_account),
+                    KeyedAccount::new(&recipient_address, false, &recipient_account),
+                ],
+                &instruction,
+                &mut MockInvokeContext::default()
+            )
+        );
+        assert_eq!(42, uninitialized_account.borrow().lamports);
+        recipient_account.borrow_mut().lamports = recipient_lamports;
+
+        // Case: close an uninitialized account
+        assert_eq!(
+            Ok(()),
+            process_instruction(
+                &bpf_loader_upgradeable::id(),
+                &[
+                    KeyedAccount::new(&uninitialized_address, false, &uninitialized_account),
+                    KeyedAccount::new(&recipient_address, false, &recipient_account),
+                ],
+                &instruction,
+                &mut MockInvokeContext::default()
+            )
+        );
+        assert_eq!(0, uninitialized_account.borrow().lamports);
+        assert_eq!(85, recipient_account.borrow().lamports);
+
+        // Case: close a ProgramData account without the Program account
+        assert_eq!(
+            Err(InstructionError::NotEnoughAccountKeys),
+            process_instruction(
+                &bpf_loader_upgradeable::id(),
+                &[
+                    KeyedAccount::new(&programdata_address, false, &programdata_account),
+                    KeyedAccount::new(&recipient_address, false, &recipient_account),
+                    KeyedAccount::new_readonly(&authority_address, true, &authority_account),
+                ],
+                &instruction,
+                &mut MockInvokeContext::default()
+            )
+        );
+
+        // Case: close a ProgramData account
+        assert_eq!(
+            Ok(()),
+            process_instruction(
+                &bpf_loader_upgradeable::id(),
+                &[
+                    KeyedAccount::new(&programdata_address, false, &programdata_account),
+                    KeyedAccount::new(&recipient_address, false, &recipient_account),
+                    KeyedAccount::new_readonly(&authority_address, true, &authority_account),
+                    KeyedAccount::new(&program_address, false, &program_account),
+                ],
+                &instruction,
+                &mut MockInvokeContext::default()
+            )
+        );
+        assert_eq!(0, programdata_account.borrow().lamports);
+        assert_eq!(127, recipient_account.borrow().lamports);
+        let state: UpgradeableLoaderState = programdata_account.borrow().state().unwrap();
+        assert_eq!(state, UpgradeableLoaderState::Uninitialized);
+
+        // Case: closed program can no longer be invoked
+        assert_eq!(
+            Err(InstructionError::InvalidAccountData),
+            process_instruction(
+                &program_address,
+                &[
+                    KeyedAccount::new(&program_address, false, &program_account),
+                    KeyedAccount::new(&programdata_address, false, &programdata_account),
+                ],
+                &[],
+                &mut MockInvokeContext::default()
+            )
+        );
+    }
+
+    #[test]
+    fn test_bpf_loader_upgradeable_extend_program() {
+        let (genesis_config, mint_keypair) = create_genesis_config(1_000_000_000);
+        let mut bank = Bank::new(&genesis_config);
+        bank.feature_set = Arc::new(FeatureSet::all_enabled());
+        bank.add_builtin(
+            "solana_bpf_loader_upgradeable_program",
+            bpf_loader_upgradeable::id(),
+            process_instruction,
+        );
+        let bank = Arc::new(bank);
+        let bank_client = BankClient::new_shared(&bank);
+
+        let mut file = File::open("test_elfs/noop_aligned.so").expect("file open failed");
+        let mut elf = Vec::new();
+        file.read_to_end(&mut elf).unwrap();
+        let program_address = Pubkey::new_unique();
+        let (programdata_address, _) = Pubkey::find_program_address(
+            &[program_address.as_ref()],
+            &bpf_loader_upgradeable::id(),
+        );
+        let upgrade_authority_address = Pubkey::new_unique();
+        let programdata_len = UpgradeableLoaderState::programdata_len(elf.len()).unwrap();
+        let mut programdata_account = Account::new(
+            bank.get_minimum_balance_for_rent_exemption(programdata_len),
+            programdata_len,
+            &bpf_loader_upgradeable::id(),
+        );
+        programdata_account
+            .set_state(&UpgradeableLoaderState::ProgramData {
+                slot: 0,
+                upgrade_authority_address: Some(upgrade_authority_address),
+            })
+            .unwrap();
+        programdata_account.data[UpgradeableLoaderState::programdata_data_offset().unwrap()..]
+            .copy_from_slice(&elf);
+        let mut program_account = Account::new(
+            bank.get_minimum_balance_for_rent_exemption(
+                UpgradeableLoaderState::program_len().unwrap(),
+            ),
+            UpgradeableLoaderState::program_len().unwrap(),
+            &bpf_loader_upgradeable::id(),
+        );
+        program_account.executable = true;
+        program_account
+            .set_state(&UpgradeableLoaderState::Program {
+                programdata_address,
+            })
+            .unwrap();
+
+        // Case: extend a program
+        bank.store_account(&program_address, &program_account);
+        bank.store_account(&programdata_address, &programdata_account);
+        let additional_bytes = 1024;
+        let required_payment = bank
+            .get_minimum_balance_for_rent_exemption(programdata_len + additional_bytes)
+            - programdata_account.lamports;
+        let before = bank.get_balance(&mint_keypair.pubkey());
+        let message = Message::new(
+            &[bpf_loader_upgradeable::extend_program(
+                &program_address,
+                &mint_keypair.pubkey(),
+                additional_bytes as u32,
+            )],
+            Some(&mint_keypair.pubkey()),
+        );
+        assert!(bank_client
+            .send_and_confirm_message(&[&mint_keypair], message)
+            .is_ok());
+        assert_eq!(
+            bank.get_balance(&mint_keypair.pubkey()),
+            before
+                - required_payment
+                - bank
+                    .get_fee_calculator(&bank.last_blockhash())
+                    .unwrap()
+                    .lamports_per_signature
+        );
+        let post_programdata_account = bank.get_account(&programdata_address).unwrap();
+        assert_eq!(
+            post_programdata_account.data.len(),
+            programdata_len + additional_bytes
+        );
+        assert_eq!(
+            post_programdata_account.lamports,
+            bank.get_minimum_balance_for_rent_exemption(programdata_len + additional_bytes)
+        );
+        assert_eq!(
+            post_programdata_account.data[..programdata_len],
+            programdata_account.data[..]
+        );
+
+        // Case: zero additional bytes
+        bank.clear_signatures();
+        let message = Message::new(
+            &[bpf_loader_upgradeable::extend_program(
+                &program_address,
+                &mint_keypair.pubkey(),
+                0,
+            )],
+            Some(&mint_keypair.pubkey()),
+        );
+        assert_eq!(
+            TransactionError::InstructionError(0, InstructionError::InvalidInstructionData),
+            bank_client
+                .send_and_confirm_message(&[&mint_keypair], message)
+                .unwrap_err()
+                .unwrap()
+        );
+
+        // Case: immutable program
+        bank.clear_signatures();
+        programdata_account
+            .set_state(&UpgradeableLoaderState::ProgramData {
+                slot: 0,
+                upgrade_authority_address: None,
+            })
+            .unwrap();
+        bank.store_account(&programdata_address, &programdata_account);
+        let message = Message::new(
+            &[bpf_loader_upgradeable::extend_program(
+                &program_address,
+                &mint_keypair.pubkey(),
+                additional_bytes as u32,
+            )],
+            Some(&mint_keypair.pubkey()),
+        );
+        assert_eq!(
+            TransactionError::InstructionError(0, InstructionError::Immutable),
+            bank_client
+                .send_and_confirm_message(&[&mint_keypair], message)
+                .unwrap_err()
+                .unwrap()
+        );
+    }
+
     #[test]
     #[ignore]
     fn test_fuzz() {
@@ -16,18 +16,28 @@ use solana_sdk::{
     bpf_loader, bpf_loader_deprecated,
     bpf_loader_upgradeable::{self, UpgradeableLoaderState},
     entrypoint::{MAX_PERMITTED_DATA_INCREASE, SUCCESS},
-    feature_set::{per_byte_logging_cost, ristretto_mul_syscall_enabled, use_loaded_executables},
+    feature_set::{
+        keccak256_syscall_enabled, per_byte_logging_cost, return_data_syscall_enabled,
+        ristretto_mul_syscall_enabled, secp256k1_recover_syscall_enabled, use_loaded_executables,
+    },
     hash::{Hasher, HASH_BYTES},
     ic_msg,
     instruction::{AccountMeta, Instruction, InstructionError},
+    keccak,
     keyed_account::KeyedAccount,
     native_loader,
     process_instruction::{stable_log, ComputeMeter, InvokeContext, Logger},
+    program::MAX_RETURN_DATA,
     pubkey::{Pubkey, PubkeyError, MAX_SEEDS},
+    secp256k1_recover::{
+        Secp256k1RecoverError, SECP256K1_PUBLIC_KEY_LENGTH, SECP256K1_SIGNATURE_LENGTH,
+    },
+    transaction::TransactionReturnData,
 };
 use std::{
     alloc::Layout,
     cell::{Ref, RefCell, RefMut},
+    convert::TryFrom,
     mem::{align_of, size_of},
     rc::Rc,
     slice::from_raw_parts_mut,
@@ -65,6 +75,8 @@ pub enum SyscallError {
     InstructionTooLarge(usize, usize),
     #[error("Too many accounts passed to inner instruction")]
     TooManyAccounts,
+    #[error("Return data too large ({0} > {1})")]
+    ReturnDataTooLarge(u64, u64),
 }
 impl From<SyscallError> for EbpfError<BpfError> {
     fn from(error: SyscallError) -> Self {
@@ -110,6 +122,15 @@ pub fn register_syscalls(
 
     syscall_registry.register_syscall_by_name(b"sol_sha256", SyscallSha256::call)?;
 
+    if invoke_context.is_feature_active(&keccak256_syscall_enabled::id()) {
+        syscall_registry.register_syscall_by_name(b"sol_keccak256", SyscallKeccak256::call)?;
+    }
+
+    if invoke_context.is_feature_active(&secp256k1_recover_syscall_enabled::id()) {
+        syscall_registry
+            .register_syscall_by_name(b"sol_secp256k1_recover", SyscallSecp256k1Recover::call)?;
+    }
+
     if invoke_context.is_feature_active(&ristretto_mul_syscall_enabled::id()) {
         syscall_registry
             .register_syscall_by_name(b"sol_ristretto_mul", SyscallRistrettoMul::call)?;
@@ -129,6 +150,13 @@ pub fn register_syscalls(
         .register_syscall_by_name(b"sol_invoke_signed_rust", SyscallInvokeSignedRust::call)?;
     syscall_registry.register_syscall_by_name(b"sol_alloc_free_", SyscallAllocFree::call)?;
 
+    if invoke_context.is_feature_active(&return_data_syscall_enabled::id()) {
+        syscall_registry
+            .register_syscall_by_name(b"sol_set_return_data", SyscallSetReturnData::call)?;
+        syscall_registry
+            .register_syscall_by_name(b"sol_get_return_data", SyscallGetReturnData::call)?;
+    }
+
     Ok(syscall_registry)
 }
 
@@ -216,6 +244,29 @@ pub fn bind_syscall_context_objects<'a>(
         None,
     )?;
 
+    bind_feature_gated_syscall_context_object!(
+        vm,
+        invoke_context,
+        &keccak256_syscall_enabled::id(),
+        Box::new(SyscallKeccak256 {
+            base_cost: bpf_compute_budget.keccak256_base_cost,
+            byte_cost: bpf_compute_budget.keccak256_byte_cost,
+            compute_meter: invoke_context.get_compute_meter(),
+            loader_id,
+        }),
+    );
+
+    bind_feature_gated_syscall_context_object!(
+        vm,
+        invoke_context,
+        &secp256k1_recover_syscall_enabled::id(),
+        Box::new(SyscallSecp256k1Recover {
+            cost: bpf_compute_budget.secp256k1_recover_cost,
+            compute_meter: invoke_context.get_compute_meter(),
+            loader_id,
+        }),
+    );
+
     bind_feature_gated_syscall_context_object!(
         vm,
         invoke_context,
@@ -265,6 +316,27 @@ pub fn bind_syscall_context_objects<'a>(
         None,
     )?;
 
+    // Return data syscalls
+
+    bind_feature_gated_syscall_context_object!(
+        vm,
+        invoke_context.borrow(),
+        &return_data_syscall_enabled::id(),
+        Box::new(SyscallSetReturnData {
+            invoke_context: invoke_context.clone(),
+            loader_id,
+        }),
+    );
+    bind_feature_gated_syscall_context_object!(
+        vm,
+        invoke_context.borrow(),
+        &return_data_syscall_enabled::id(),
+        Box::new(SyscallGetReturnData {
+            invoke_context: invoke_context.clone(),
+            loader_id,
+        }),
+    );
+
     // Memory allocator
 
     vm.bind_syscall_context_object(
@@ -804,6 +876,150 @@ impl<'a> SyscallObject<BpfError> for SyscallSha256<'a> {
     }
 }
 
+/// Keccak256
+pub struct SyscallKeccak256<'a> {
+    base_cost: u64,
+    byte_cost: u64,
+    compute_meter: Rc<RefCell<dyn ComputeMeter>>,
+    loader_id: &'a Pubkey,
+}
+impl<'a> SyscallObject<BpfError> for SyscallKeccak256<'a> {
+    fn call(
+        &mut self,
+        vals_addr: u64,
+        vals_len: u64,
+        result_addr: u64,
+        _arg4: u64,
+        _arg5: u64,
+        memory_mapping: &MemoryMapping,
+        result: &mut Result<u64, EbpfError<BpfError>>,
+    ) {
+        question_mark!(self.compute_meter.consume(self.base_cost), result);
+        let hash_result = question_mark!(
+            translate_slice_mut::<u8>(
+                memory_mapping,
+                result_addr,
+                keccak::HASH_BYTES as u64,
+                self.loader_id
+            ),
+            result
+        );
+        let mut hasher = keccak::Hasher::default();
+        if vals_len > 0 {
+            let vals = question_mark!(
+                translate_slice::<&[u8]>(memory_mapping, vals_addr, vals_len, self.loader_id),
+                result
+            );
+            for val in vals.iter() {
+                let bytes = question_mark!(
+                    translate_slice::<u8>(
+                        memory_mapping,
+                        val.as_ptr() as u64,
+                        val.len() as u64,
+                        self.loader_id
+                    ),
+                    result
+                );
+                question_mark!(
+                    self.compute_meter
+                        .consume(self.byte_cost * (val.len() as u64 / 2)),
+                    result
+                );
+                hasher.hash(bytes);
+            }
+        }
+        hash_result.copy_from_slice(&hasher.result().to_bytes());
+        *result = Ok(0);
+    }
+}
+
+/// secp256k1 public key recovery
+///
+/// Returns 0 and writes the 64-byte uncompressed public key on success, otherwise returns the
+/// `Secp256k1RecoverError` code
+pub struct SyscallSecp256k1Recover<'a> {
+    cost: u64,
+    compute_meter: Rc<RefCell<dyn ComputeMeter>>,
+    loader_id: &'a Pubkey,
+}
+impl<'a> SyscallObject<BpfError> for SyscallSecp256k1Recover<'a> {
+    fn call(
+        &mut self,
+        hash_addr: u64,
+        recovery_id_val: u64,
+        signature_addr: u64,
+        result_addr: u64,
+        _arg5: u64,
+        memory_mapping: &MemoryMapping,
+        result: &mut Result<u64, EbpfError<BpfError>>,
+    ) {
+        question_mark!(self.compute_meter.consume(self.cost), result);
+
+        let hash = question_mark!(
+            translate_slice::<u8>(
+                memory_mapping,
+                hash_addr,
+                keccak::HASH_BYTES as u64,
+                self.loader_id
+            ),
+            result
+        );
+        let signature = question_mark!(
+            translate_slice::<u8>(
+                memory_mapping,
+                signature_addr,
+                SECP256K1_SIGNATURE_LENGTH as u64,
+                self.loader_id
+            ),
+            result
+        );
+        let secp256k1_recover_result = question_mark!(
+            translate_slice_mut::<u8>(
+                memory_mapping,
+                result_addr,
+                SECP256K1_PUBLIC_KEY_LENGTH as u64,
+                self.loader_id
+            ),
+            result
+        );
+
+        let message = match secp256k1::Message::parse_slice(hash) {
+            Ok(message) => message,
+            Err(_) => {
+                *result = Ok(Secp256k1RecoverError::InvalidHash.into());
+                return;
+            }
+        };
+        let recovery_id = match u8::try_from(recovery_id_val)
+            .ok()
+            .and_then(|recovery_id| secp256k1::RecoveryId::parse(recovery_id).ok())
+        {
+            Some(recovery_id) => recovery_id,
+            None => {
+                *result = Ok(Secp256k1RecoverError::InvalidRecoveryId.into());
+                return;
+            }
+        };
+        let signature = match secp256k1::Signature::parse_slice(signature) {
+            Ok(signature) => signature,
+            Err(_) => {
+                *result = Ok(Secp256k1RecoverError::InvalidSignature.into());
+                return;
+            }
+        };
+        let public_key = match secp256k1::recover(&message, &signature, &recovery_id) {
+            Ok(key) => key.serialize(),
+            Err(_) => {
+                *result = Ok(Secp256k1RecoverError::InvalidSignature.into());
+                return;
+            }
+        };
+
+        secp256k1_recover_result.copy_from_slice(&public_key[1..65]);
+        *result = Ok(0);
+    }
+}
+
 /// Ristretto point multiply
 pub struct SyscallRistrettoMul<'a> {
     cost: u64,
@@ -841,6 +1057,124 @@ impl<'a> SyscallObject<BpfError> for SyscallRistrettoMul<'a> {
     }
 }
 
+/// Set return data
+pub struct SyscallSetReturnData<'a> {
+    invoke_context: Rc<RefCell<&'a mut dyn InvokeContext>>,
+    loader_id: &'a Pubkey,
+}
+impl<'a> SyscallObject<BpfError> for SyscallSetReturnData<'a> {
+    fn call(
+        &mut self,
+        addr: u64,
+        len: u64,
+        _arg3: u64,
+        _arg4: u64,
+        _arg5: u64,
+        memory_mapping: &MemoryMapping,
+        result: &mut Result<u64, EbpfError<BpfError>>,
+    ) {
+        let mut invoke_context = question_mark!(
+            self.invoke_context
+                .try_borrow_mut()
+                .map_err(|_| SyscallError::InvokeContextBorrowFailed),
+            result
+        );
+        let bpf_compute_budget = invoke_context.get_bpf_compute_budget();
+        let cost = len
+            .checked_div(bpf_compute_budget.return_data_bytes_per_unit)
+            .unwrap_or(u64::MAX)
+            .saturating_add(bpf_compute_budget.return_data_base_cost);
+        question_mark!(invoke_context.get_compute_meter().consume(cost), result);
+
+        if len > MAX_RETURN_DATA as u64 {
+            *result = Err(SyscallError::ReturnDataTooLarge(len, MAX_RETURN_DATA as u64).into());
+            return;
+        }
+        let return_data = if len == 0 {
+            None
+        } else {
+            let data = question_mark!(
+                translate_slice::<u8>(memory_mapping, addr, len, self.loader_id),
+                result
+            )
+            .to_vec();
+            let program_id = *question_mark!(
+                invoke_context
+                    .get_caller()
+                    .map_err(SyscallError::InstructionError),
+                result
+            );
+            Some(TransactionReturnData { program_id, data })
+        };
+        invoke_context.set_return_data(return_data);
+
+        *result = Ok(0);
+    }
+}
+
+/// Get return data
+pub struct SyscallGetReturnData<'a> {
+    invoke_context: Rc<RefCell<&'a mut dyn InvokeContext>>,
+    loader_id: &'a Pubkey,
+}
+impl<'a> SyscallObject<BpfError> for SyscallGetReturnData<'a> {
+    fn call(
+        &mut self,
+        return_data_addr: u64,
+        len: u64,
+        program_id_addr: u64,
+        _arg4: u64,
+        _arg5: u64,
+        memory_mapping: &MemoryMapping,
+        result: &mut Result<u64, EbpfError<BpfError>>,
+    ) {
+        let invoke_context = question_mark!(
+            self.invoke_context
+                .try_borrow()
+                .map_err(|_| SyscallError::InvokeContextBorrowFailed),
+            result
+        );
+        let bpf_compute_budget = invoke_context.get_bpf_compute_budget();
+        question_mark!(
+            invoke_context
+                .get_compute_meter()
+                .consume(bpf_compute_budget.return_data_base_cost),
+            result
+        );
+
+        let (program_id, data) = match invoke_context.get_return_data() {
+            Some(return_data) => (&return_data.program_id, &return_data.data[..]),
+            None => {
+                *result = Ok(0);
+                return;
+            }
+        };
+        let length = len.min(data.len() as u64);
+        if length != 0 {
+            let cost = length
+                .saturating_add(size_of::<Pubkey>() as u64)
+                .checked_div(bpf_compute_budget.return_data_bytes_per_unit)
+                .unwrap_or(u64::MAX);
+            question_mark!(invoke_context.get_compute_meter().consume(cost), result);
+
+            let return_data_result = question_mark!(
+                translate_slice_mut::<u8>(memory_mapping, return_data_addr, length, self.loader_id),
+                result
+            );
+            return_data_result.copy_from_slice(&data[..length as usize]);
+
+            let program_id_result = question_mark!(
+                translate_type_mut::<Pubkey>(memory_mapping, program_id_addr, self.loader_id),
+                result
+            );
+            *program_id_result = *program_id;
+        }
+
+        // Return the full length of the return data, which may exceed `len`
+        *result = Ok(data.len() as u64);
+    }
+}
+
 // Cross-program invocation syscalls
 
 struct AccountReferences<'a> {
@@ -1714,7 +2048,7 @@ mod tests {
     use solana_sdk::{
         bpf_loader,
         hash::hashv,
-        process_instruction::{MockComputeMeter, MockLogger},
+        process_instruction::{MockComputeMeter, MockInvokeContext, MockLogger},
     };
     use std::str::FromStr;
 
@@ -2434,4 +2768,273 @@ mod tests {
             result
         );
     }
+
+    #[test]
+    fn test_syscall_keccak256() {
+        let bytes1 = "Gaggablaghblagh!";
+        let bytes2 = "flurbos";
+
+        struct MockSlice {
+            pub addr: u64,
+            pub len: usize,
+        }
+        let mock_slice1 = MockSlice {
+            addr: 4096,
+            len: bytes1.len(),
+        };
+        let mock_slice2 = MockSlice {
+            addr: 8192,
+            len: bytes2.len(),
+        };
+        let bytes_to_hash = [mock_slice1, mock_slice2];
+        let hash_result = [0; keccak::HASH_BYTES];
+        let ro_len = bytes_to_hash.len() as u64;
+        let ro_va = 96;
+        let rw_va = 192;
+        let memory_mapping = MemoryMapping::new(
+            vec![
+                MemoryRegion::new_from_slice(bytes1.as_bytes(), 4096, 0, false),
+                MemoryRegion::new_from_slice(bytes2.as_bytes(), 8192, 0, false),
+                MemoryRegion {
+                    host_addr: bytes_to_hash.as_ptr() as *const _ as u64,
+                    vm_addr: ro_va,
+                    len: 32,
+                    vm_gap_shift: 63,
+                    is_writable: false,
+                },
+                MemoryRegion::new_from_slice(&hash_result, rw_va, 0, true),
+            ],
+            &DEFAULT_CONFIG,
+        );
+        let compute_meter: Rc<RefCell<dyn ComputeMeter>> =
+            Rc::new(RefCell::new(MockComputeMeter {
+                remaining: (bytes1.len() + bytes2.len()) as u64,
+            }));
+        let mut syscall = SyscallKeccak256 {
+            base_cost: 0,
+            byte_cost: 2,
+            compute_meter,
+            loader_id: &bpf_loader::id(),
+        };
+
+        let mut result: Result<u64, EbpfError<BpfError>> = Ok(0);
+        syscall.call(ro_va, ro_len, rw_va, 0, 0, &memory_mapping, &mut result);
+        result.unwrap();
+
+        let hash_local = keccak::hashv(&[bytes1.as_ref(), bytes2.as_ref()]).to_bytes();
+        assert_eq!(hash_result, hash_local);
+
+        let mut result: Result<u64, EbpfError<BpfError>> = Ok(0);
+        syscall.call(
+            ro_va,
+            ro_len,
+            rw_va - 1, // AccessViolation
+            0,
+            0,
+            &memory_mapping,
+            &mut result,
+        );
+        assert_access_violation!(result, rw_va - 1, keccak::HASH_BYTES as u64);
+
+        syscall.call(ro_va, ro_len, rw_va, 0, 0, &memory_mapping, &mut result);
+        assert_eq!(
+            Err(EbpfError::UserError(BpfError::SyscallError(
+                SyscallError::InstructionError(InstructionError::ComputationalBudgetExceeded)
+            ))),
+            result
+        );
+    }
+
+    #[test]
+    fn test_syscall_secp256k1_recover() {
+        let secret_key = secp256k1::SecretKey::parse(&[7u8; 32]).unwrap();
+        let public_key = secp256k1::PublicKey::from_secret_key(&secret_key);
+        let hash = keccak::hash(b"hello world").to_bytes();
+        let (signature, recovery_id) =
+            secp256k1::sign(&secp256k1::Message::parse(&hash), &secret_key);
+        let signature = signature.serialize();
+        let bad_signature = [0u8; SECP256K1_SIGNATURE_LENGTH];
+        let recovered = [0u8; SECP256K1_PUBLIC_KEY_LENGTH];
+
+        let hash_va = 4096;
+        let signature_va = 8192;
+        let bad_signature_va = 12288;
+        let recovered_va = 16384;
+        let memory_mapping = MemoryMapping::new(
+            vec![
+                MemoryRegion::new_from_slice(&hash, hash_va, 0, false),
+                MemoryRegion::new_from_slice(&signature, signature_va, 0, false),
+                MemoryRegion::new_from_slice(&bad_signature, bad_signature_va, 0, false),
+                MemoryRegion::new_from_slice(&recovered, recovered_va, 0, true),
+            ],
+            &DEFAULT_CONFIG,
+        );
+        let compute_meter: Rc<RefCell<dyn ComputeMeter>> =
+            Rc::new(RefCell::new(MockComputeMeter { remaining: 3 }));
+        let mut syscall = SyscallSecp256k1Recover {
+            cost: 1,
+            compute_meter,
+            loader_id: &bpf_loader::id(),
+        };
+
+        let mut result: Result<u64, EbpfError<BpfError>> = Ok(0);
+        syscall.call(
+            hash_va,
+            recovery_id.serialize() as u64,
+            signature_va,
+            recovered_va,
+            0,
+            &memory_mapping,
+            &mut result,
+        );
+        assert_eq!(result, Ok(0));
+        assert_eq!(recovered[..], public_key.serialize()[1..]);
+
+        let mut result: Result<u64, EbpfError<BpfError>> = Ok(0);
+        syscall.call(
+            hash_va,
+            256,
+            signature_va,
+            recovered_va,
+            0,
+            &memory_mapping,
+            &mut result,
+        );
+        assert_eq!(result, Ok(Secp256k1RecoverError::InvalidRecoveryId.into()));
+
+        let mut result: Result<u64, EbpfError<BpfError>> = Ok(0);
+        syscall.call(
+            hash_va,
+            recovery_id.serialize() as u64,
+            bad_signature_va,
+            recovered_va,
+            0,
+            &memory_mapping,
+            &mut result,
+        );
+        assert_eq!(result, Ok(Secp256k1RecoverError::InvalidSignature.into()));
+
+        let mut result: Result<u64, EbpfError<BpfError>> = Ok(0);
+        syscall.call(
+            hash_va,
+            recovery_id.serialize() as u64,
+            signature_va,
+            recovered_va,
+            0,
+            &memory_mapping,
+            &mut result,
+        );
+        assert_eq!(
+            Err(EbpfError::UserError(BpfError::SyscallError(
+                SyscallError::InstructionError(InstructionError::ComputationalBudgetExceeded)
+            ))),
+            result
+        );
+    }
+
+    #[test]
+    fn test_syscall_set_get_return_data() {
+        let program_id = solana_sdk::pubkey::new_rand();
+        let data = [1u8, 2, 3, 4];
+        let data_va = 4096;
+        let return_data = [0u8; 4];
+        let return_data_va = 8192;
+        let program_id_result = Pubkey::default();
+        let program_id_va = 12288;
+        let memory_mapping = MemoryMapping::new(
+            vec![
+                MemoryRegion::new_from_slice(&data, data_va, 0, false),
+                MemoryRegion::new_from_slice(&return_data, return_data_va, 0, true),
+                MemoryRegion::new_from_slice(program_id_result.as_ref(), program_id_va, 0, true),
+            ],
+            &DEFAULT_CONFIG,
+        );
+        let mut mock_invoke_context = MockInvokeContext {
+            key: program_id,
+            ..MockInvokeContext::default()
+        };
+        let invoke_context: Rc<RefCell<&mut dyn InvokeContext>> =
+            Rc::new(RefCell::new(&mut mock_invoke_context));
+        let loader_id = bpf_loader::id();
+
+        // Nothing set yet
+        let mut get_syscall = SyscallGetReturnData {
+            invoke_context: invoke_context.clone(),
+            loader_id: &loader_id,
+        };
+        let mut result: Result<u64, EbpfError<BpfError>> = Ok(u64::MAX);
+        get_syscall.call(
+            return_data_va,
+            return_data.len() as u64,
+            program_id_va,
+            0,
+            0,
+            &memory_mapping,
+            &mut result,
+        );
+        assert_eq!(result.unwrap(), 0);
+
+        let mut set_syscall = SyscallSetReturnData {
+            invoke_context: invoke_context.clone(),
+            loader_id: &loader_id,
+        };
+        let mut result: Result<u64, EbpfError<BpfError>> = Ok(u64::MAX);
+        set_syscall.call(
+            data_va,
+            data.len() as u64,
+            0,
+            0,
+            0,
+            &memory_mapping,
+            &mut result,
+        );
+        assert_eq!(result.unwrap(), 0);
+        assert_eq!(
+            invoke_context.borrow().get_return_data(),
+            Some(&TransactionReturnData {
+                program_id,
+                data: data.to_vec(),
+            })
+        );
+
+        // Read back fewer bytes than were set, full length is still returned
+        let mut result: Result<u64, EbpfError<BpfError>> = Ok(u64::MAX);
+        get_syscall.call(
+            return_data_va,
+            2,
+            program_id_va,
+            0,
+            0,
+            &memory_mapping,
+            &mut result,
+        );
+        assert_eq!(result.unwrap(), data.len() as u64);
+        assert_eq!(return_data, [1, 2, 0, 0]);
+        assert_eq!(program_id_result, program_id);
+
+        // Too large
+        let mut result: Result<u64, EbpfError<BpfError>> = Ok(u64::MAX);
+        set_syscall.call(
+            data_va,
+            MAX_RETURN_DATA as u64 + 1,
+            0,
+            0,
+            0,
+            &memory_mapping,
+            &mut result,
+        );
+        assert_eq!(
+            result.unwrap_err(),
+            EbpfError::UserError(BpfError::SyscallError(SyscallError::ReturnDataTooLarge(
+                MAX_RETURN_DATA as u64 + 1,
+                MAX_RETURN_DATA as u64
+            )))
+        );
+
+        // Setting empty data clears it
+        let mut result: Result<u64, EbpfError<BpfError>> = Ok(u64::MAX);
+        set_syscall.call(data_va, 0, 0, 0, 0, &memory_mapping, &mut result);
+        assert_eq!(result.unwrap(), 0);
+        assert_eq!(invoke_context.borrow().get_return_data(), None);
+    }
 }
@@ -15,6 +15,7 @@ use dashmap::{
 };
 use log::*;
 use rand::{thread_rng, Rng};
+use solana_address_lookup_table_program::state::AddressLookupTable;
 use solana_sdk::{
     account::Account,
     account_utils::StateMut,
@@ -24,7 +25,10 @@ use solana_sdk::{
     fee_calculator::{FeeCalculator, FeeConfig},
     genesis_config::ClusterType,
     hash::Hash,
-    message::Message,
+    message::{
+        v0::{LoadedAddresses, MessageAddressTableLookup},
+        Message,
+    },
     native_loader, nonce,
     pubkey::Pubkey,
     transaction::Result,
@@ -415,6 +419,8 @@ impl Accounts {
         let fee_config = FeeConfig {
             secp256k1_program_enabled: feature_set
                 .is_active(&feature_set::secp256k1_program_enabled::id()),
+            compute_budget_program_enabled: feature_set
+                .is_active(&feature_set::compute_budget_program::id()),
         };
         OrderedIterator::new(txs, txs_iteration_order)
             .zip(lock_results.into_iter())
@@ -467,6 +473,44 @@ impl Accounts {
             .collect()
     }
 
+    /// Resolve the addresses referenced by a versioned message's address table lookups.
+    /// Deactivated lookup tables can be used until their cooldown ends, and addresses
+    /// appended to a table during `current_slot` cannot be referenced until the next slot.
+    pub fn load_lookup_table_addresses(
+        &self,
+        ancestors: &Ancestors,
+        address_table_lookups: &[MessageAddressTableLookup],
+        current_slot: Slot,
+    ) -> Result<LoadedAddresses> {
+        let mut loaded_addresses = LoadedAddresses::default();
+        for lookup in address_table_lookups {
+            let (table_account, _) = self
+                .load_slow(ancestors, &lookup.account_key)
+                .ok_or(TransactionError::AddressLookupTableNotFound)?;
+            if table_account.owner != solana_address_lookup_table_program::id() {
+                return Err(TransactionError::InvalidAddressLookupTableOwner);
+            }
+
+            let lookup_table = AddressLookupTable::deserialize(&table_account.data)
+                .map_err(|_| TransactionError::InvalidAddressLookupTableData)?;
+            if !lookup_table.meta.is_usable(current_slot) {
+                return Err(TransactionError::AddressLookupTableNotFound);
+            }
+
+            loaded_addresses.writable.extend(
+                lookup_table
+                    .lookup(current_slot, &lookup.writable_indexes)
+                    .ok_or(TransactionError::InvalidAddressLookupTableIndex)?,
+            );
+            loaded_addresses.readonly.extend(
+                lookup_table
+                    .lookup(current_slot, &lookup.readonly_indexes)
+                    .ok_or(TransactionError::InvalidAddressLookupTableIndex)?,
+            );
+        }
+        Ok(loaded_addresses)
+    }
+
     /// Slow because lock is held for 1 operation instead of many
     pub fn load_slow(&self, ancestors: &Ancestors, pubkey: &Pubkey) -> Option<(Account, Slot)> {
         let (account, slot) = self
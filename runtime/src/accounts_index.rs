@@ -3,7 +3,7 @@ use crate::{
     inline_spl_token_v2_0::{self, SPL_TOKEN_ACCOUNT_MINT_OFFSET, SPL_TOKEN_ACCOUNT_OWNER_OFFSET},
     secondary_index::*,
 };
-use dashmap::DashSet;
+use dashmap::{DashMap, DashSet};
 use ouroboros::self_referencing;
 use solana_measure::measure::Measure;
 use solana_sdk::{
@@ -20,6 +20,7 @@ use std::{
         Bound::{Excluded, Included, Unbounded},
         Range, RangeBounds,
     },
+    str::FromStr,
     sync::{
         atomic::{AtomicU64, Ordering},
         Arc, RwLock, RwLockReadGuard, RwLockWriteGuard,
@@ -63,6 +64,7 @@ pub enum IndexKey {
     ProgramId(Pubkey),
     SplTokenMint(Pubkey),
     SplTokenOwner(Pubkey),
+    ProgramData(ProgramDataIndex, Pubkey),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -70,6 +72,72 @@ pub enum AccountIndex {
     ProgramId,
     SplTokenMint,
     SplTokenOwner,
+    ProgramData(ProgramDataIndex),
+}
+
+/// Indexes the accounts owned by `program_id` by the `length` bytes of account
+/// data found at `offset`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ProgramDataIndex {
+    pub program_id: Pubkey,
+    pub offset: usize,
+    pub length: usize,
+}
+
+impl ProgramDataIndex {
+    /// Maximum length of an indexed field
+    pub const MAX_LENGTH: usize = PUBKEY_BYTES;
+
+    /// Index key of an account, or None if its data is too short to hold the field
+    pub fn key(&self, account_data: &[u8]) -> Option<Pubkey> {
+        let end = self.offset.checked_add(self.length)?;
+        account_data
+            .get(self.offset..end)
+            .and_then(|field| self.key_from_field(field))
+    }
+
+    /// Index key of a field value, which is the value padded with zeros. Returns
+    /// None if the value is not `length` bytes long
+    pub fn key_from_field(&self, field: &[u8]) -> Option<Pubkey> {
+        if field.len() != self.length || field.len() > Self::MAX_LENGTH {
+            return None;
+        }
+        let mut key = [0; PUBKEY_BYTES];
+        key[..field.len()].copy_from_slice(field);
+        Some(Pubkey::new_from_array(key))
+    }
+}
+
+impl FromStr for ProgramDataIndex {
+    type Err = String;
+
+    /// Parses a `PROGRAM_ID:OFFSET:LENGTH` index specification
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split(':');
+        let (program_id, offset, length) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(program_id), Some(offset), Some(length)) if parts.next().is_none() => {
+                (program_id, offset, length)
+            }
+            _ => return Err(format!("expected PROGRAM_ID:OFFSET:LENGTH, found {}", s)),
+        };
+        let program_id = Pubkey::from_str(program_id)
+            .map_err(|err| format!("invalid program id {}: {:?}", program_id, err))?;
+        let offset =
+            usize::from_str(offset).map_err(|err| format!("invalid offset {}: {}", offset, err))?;
+        let length =
+            usize::from_str(length).map_err(|err| format!("invalid length {}: {}", length, err))?;
+        if length == 0 || length > Self::MAX_LENGTH {
+            return Err(format!(
+                "length must be in the range [1, {}]",
+                Self::MAX_LENGTH
+            ));
+        }
+        Ok(Self {
+            program_id,
+            offset,
+            length,
+        })
+    }
 }
 
 #[derive(Debug)]
@@ -262,6 +330,7 @@ pub struct AccountsIndex<T> {
     program_id_index: SecondaryIndex<DashMapSecondaryIndexEntry>,
     spl_token_mint_index: SecondaryIndex<DashMapSecondaryIndexEntry>,
     spl_token_owner_index: SecondaryIndex<RwLockSecondaryIndexEntry>,
+    program_data_indexes: DashMap<ProgramDataIndex, SecondaryIndex<DashMapSecondaryIndexEntry>>,
     roots_tracker: RwLock<RootsTracker>,
     ongoing_scan_roots: RwLock<BTreeMap<Slot, u64>>,
     zero_lamport_pubkeys: DashSet<Pubkey>,
@@ -450,6 +519,11 @@ impl<T: 'static + Clone + IsCached + ZeroLamport> AccountsIndex<T> {
                     Some(max_root),
                 );
             }
+            ScanTypes::Indexed(IndexKey::ProgramData(program_data_index, key)) => {
+                if let Some(index) = self.program_data_indexes.get(&program_data_index) {
+                    self.do_scan_secondary_index(ancestors, func, &index, &key, Some(max_root));
+                }
+            }
         }
 
         {
@@ -859,6 +933,26 @@ impl<T: 'static + Clone + IsCached + ZeroLamport> AccountsIndex<T> {
                 self.spl_token_mint_index.insert(&mint_key, pubkey, slot);
             }
         }
+
+        for account_index in account_indexes {
+            if let AccountIndex::ProgramData(program_data_index) = account_index {
+                if *account_owner != program_data_index.program_id {
+                    continue;
+                }
+                if let Some(key) = program_data_index.key(account_data) {
+                    let index = self
+                        .program_data_indexes
+                        .get(program_data_index)
+                        .unwrap_or_else(|| {
+                            self.program_data_indexes
+                                .entry(*program_data_index)
+                                .or_insert_with(SecondaryIndex::default)
+                                .downgrade()
+                        });
+                    index.insert(&key, pubkey, slot);
+                }
+            }
+        }
     }
 
     // Same functionally to upsert, but doesn't take the read lock
@@ -965,6 +1059,14 @@ impl<T: 'static + Clone + IsCached + ZeroLamport> AccountsIndex<T> {
             self.spl_token_mint_index
                 .remove_by_inner_key(inner_key, slots_to_remove);
         }
+
+        for account_index in account_indexes {
+            if let AccountIndex::ProgramData(program_data_index) = account_index {
+                if let Some(index) = self.program_data_indexes.get(program_data_index) {
+                    index.remove_by_inner_key(inner_key, slots_to_remove);
+                }
+            }
+        }
     }
 
     fn purge_older_root_entries(
@@ -2120,6 +2222,123 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn test_program_data_index_from_str() {
+        let program_id = Pubkey::new_unique();
+        assert_eq!(
+            format!("{}:4:8", program_id).parse::<ProgramDataIndex>(),
+            Ok(ProgramDataIndex {
+                program_id,
+                offset: 4,
+                length: 8,
+            })
+        );
+        assert!(format!("{}:4", program_id)
+            .parse::<ProgramDataIndex>()
+            .is_err());
+        assert!(format!("{}:4:8:1", program_id)
+            .parse::<ProgramDataIndex>()
+            .is_err());
+        assert!("III:4:8".parse::<ProgramDataIndex>().is_err());
+        assert!(format!("{}:-1:8", program_id)
+            .parse::<ProgramDataIndex>()
+            .is_err());
+        assert!(format!("{}:4:0", program_id)
+            .parse::<ProgramDataIndex>()
+            .is_err());
+        assert!(format!("{}:4:33", program_id)
+            .parse::<ProgramDataIndex>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_program_data_secondary_index() {
+        let program_data_index = ProgramDataIndex {
+            program_id: Pubkey::new_unique(),
+            offset: 4,
+            length: 8,
+        };
+        let mut account_index = HashSet::new();
+        account_index.insert(AccountIndex::ProgramData(program_data_index));
+        let index = AccountsIndex::<bool>::default();
+
+        let account_key = Pubkey::new_unique();
+        let slot = 1;
+        let mut account_data = vec![0; 16];
+        account_data[4..12].copy_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]);
+        let index_key = program_data_index
+            .key_from_field(&account_data[4..12])
+            .unwrap();
+        assert_eq!(program_data_index.key(&account_data), Some(index_key));
+        assert_eq!(
+            program_data_index.key_from_field(&account_data[4..11]),
+            None
+        );
+
+        // Wrong program id
+        index.upsert(
+            slot,
+            &account_key,
+            &Pubkey::default(),
+            &account_data,
+            &account_index,
+            true,
+            &mut vec![],
+        );
+        assert!(index.program_data_indexes.is_empty());
+
+        // Account data too short to hold the field
+        index.upsert(
+            slot,
+            &account_key,
+            &program_data_index.program_id,
+            &account_data[..11],
+            &account_index,
+            true,
+            &mut vec![],
+        );
+        assert!(index.program_data_indexes.is_empty());
+
+        // Just right. Inserting the same index multiple times should be ok
+        for _ in 0..2 {
+            index.upsert(
+                slot,
+                &account_key,
+                &program_data_index.program_id,
+                &account_data,
+                &account_index,
+                true,
+                &mut vec![],
+            );
+            check_secondary_index_unique(
+                &index.program_data_indexes.get(&program_data_index).unwrap(),
+                slot,
+                &index_key,
+                &account_key,
+            );
+        }
+
+        let ancestors: Ancestors = vec![(0, 0), (slot, 1)].into_iter().collect();
+        let mut found_keys = vec![];
+        index.index_scan_accounts(
+            &ancestors,
+            IndexKey::ProgramData(program_data_index, index_key),
+            |pubkey, _| found_keys.push(*pubkey),
+        );
+        assert_eq!(found_keys, vec![account_key]);
+
+        index
+            .get_account_write_entry(&account_key)
+            .unwrap()
+            .slot_list_mut(|slot_list| slot_list.clear());
+
+        // Everything should be deleted
+        index.handle_dead_keys(&[&account_key], &account_index);
+        let secondary_index = index.program_data_indexes.get(&program_data_index).unwrap();
+        assert!(secondary_index.index.is_empty());
+        assert!(secondary_index.reverse_index.is_empty());
+    }
+
     fn run_test_secondary_indexes_same_slot_and_forks<
         SecondaryIndexEntryType: SecondaryIndexEntry + Default + Sync + Send,
     >(
@@ -10,6 +10,7 @@ use crate::{
     accounts_db::{ErrorCounters, SnapshotStorages},
     accounts_index::{AccountIndex, Ancestors, IndexKey},
     blockhash_queue::BlockhashQueue,
+    bpf_trace_collector::BpfTraceCollector,
     builtins::{self, ActivationType},
     epoch_stakes::{EpochStakes, NodeVoteAccounts},
     inline_spl_token_v2_0,
@@ -48,11 +49,11 @@ use solana_sdk::{
     incinerator,
     inflation::Inflation,
     instruction::CompiledInstruction,
-    message::Message,
+    message::{Message, VersionedMessage},
     native_loader,
     native_token::sol_to_lamports,
     nonce, nonce_account,
-    process_instruction::{BpfComputeBudget, Executor, ProcessInstructionWithContext},
+    process_instruction::{BpfComputeBudget, BpfTrace, Executor, ProcessInstructionWithContext},
     program_utils::limited_deserialize,
     pubkey::Pubkey,
     recent_blockhashes_account,
@@ -67,7 +68,9 @@ use solana_sdk::{
     system_transaction,
     sysvar::{self},
     timing::years_as_slots,
-    transaction::{self, Result, Transaction, TransactionError},
+    transaction::{
+        self, Result, Transaction, TransactionError, TransactionReturnData, VersionedTransaction,
+    },
 };
 use solana_stake_program::stake_state::{
     self, Delegation, InflationPointCalculationEvent, PointValue,
@@ -215,11 +218,12 @@ pub struct Builtins {
 
 const MAX_CACHED_EXECUTORS: usize = 100; // 10 MB assuming programs are around 100k
 
-/// LFU Cache of executors
+/// LFU Cache of executors, keyed by program and by whether they trace the program, as
+/// banks that trace BPF programs need executors that record every instruction they run
 #[derive(Debug)]
 struct CachedExecutors {
     max: usize,
-    executors: HashMap<Pubkey, (AtomicU64, Arc<dyn Executor>)>,
+    executors: HashMap<(Pubkey, bool), (AtomicU64, Arc<dyn Executor>)>,
 }
 impl Default for CachedExecutors {
     fn default() -> Self {
@@ -262,16 +266,19 @@ impl CachedExecutors {
             executors: HashMap::new(),
         }
     }
-    fn get(&self, pubkey: &Pubkey) -> Option<Arc<dyn Executor>> {
-        self.executors.get(pubkey).map(|(count, executor)| {
-            count.fetch_add(1, Relaxed);
-            executor.clone()
-        })
+    fn get(&self, pubkey: &Pubkey, traced: bool) -> Option<Arc<dyn Executor>> {
+        self.executors
+            .get(&(*pubkey, traced))
+            .map(|(count, executor)| {
+                count.fetch_add(1, Relaxed);
+                executor.clone()
+            })
     }
-    fn put(&mut self, pubkey: &Pubkey, executor: Arc<dyn Executor>) {
-        if !self.executors.contains_key(pubkey) && self.executors.len() >= self.max {
+    fn put(&mut self, pubkey: &Pubkey, traced: bool, executor: Arc<dyn Executor>) {
+        let key = (*pubkey, traced);
+        if !self.executors.contains_key(&key) && self.executors.len() >= self.max {
             let mut least = u64::MAX;
-            let default_key = Pubkey::default();
+            let default_key = (Pubkey::default(), false);
             let mut least_key = &default_key;
             for (key, (count, _)) in self.executors.iter() {
                 let count = count.load(Relaxed);
@@ -283,12 +290,11 @@ impl CachedExecutors {
             let least_key = *least_key;
             let _ = self.executors.remove(&least_key);
         }
-        let _ = self
-            .executors
-            .insert(*pubkey, (AtomicU64::new(0), executor));
+        let _ = self.executors.insert(key, (AtomicU64::new(0), executor));
     }
     fn remove(&mut self, pubkey: &Pubkey) {
-        let _ = self.executors.remove(pubkey);
+        let _ = self.executors.remove(&(*pubkey, false));
+        let _ = self.executors.remove(&(*pubkey, true));
     }
 }
 
@@ -401,6 +407,49 @@ pub type InnerInstructionsList = Vec<InnerInstructions>;
 /// A list of log messages emitted during a transaction
 pub type TransactionLogMessages = Vec<String>;
 
+/// The outcome of a single transaction along with what was recorded during its execution
+#[derive(Clone, Debug, PartialEq)]
+pub struct TransactionExecutionDetails {
+    pub status: Result<()>,
+    pub log_messages: TransactionLogMessages,
+    pub inner_instructions: InnerInstructionsList,
+    pub return_data: Option<TransactionReturnData>,
+    /// Compute units consumed by the instructions of the transaction
+    pub executed_units: u64,
+    /// The accounts of the transaction after its execution, in message order
+    pub post_accounts: Vec<(Pubkey, Account)>,
+}
+
+/// Everything that goes into the hash of a frozen bank
+#[derive(Clone, Debug, PartialEq)]
+pub struct BankHashInputs {
+    pub parent_hash: Hash,
+    /// Hash of the accounts stored in the bank's slot
+    pub accounts_delta_hash: Hash,
+    pub signature_count: u64,
+    pub last_blockhash: Hash,
+    /// Only present when the bank's slot is a hard fork
+    pub hard_fork_data: Option<[u8; 8]>,
+}
+
+impl BankHashInputs {
+    pub fn hash(&self) -> Hash {
+        let mut signature_count_buf = [0u8; 8];
+        LittleEndian::write_u64(&mut signature_count_buf[..], self.signature_count);
+
+        let hash = hashv(&[
+            self.parent_hash.as_ref(),
+            self.accounts_delta_hash.as_ref(),
+            &signature_count_buf,
+            self.last_blockhash.as_ref(),
+        ]);
+        match &self.hard_fork_data {
+            Some(buf) => extend_and_hash(&hash, buf),
+            None => hash,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, AbiExample, AbiEnumVisitor, Debug, PartialEq)]
 pub enum TransactionLogCollectorFilter {
     All,
@@ -701,6 +750,16 @@ pub trait DropCallback: fmt::Debug {
     fn clone_box(&self) -> Box<dyn DropCallback + Send + Sync>;
 }
 
+/// Receives the execution traces of the BPF programs invoked by each transaction a bank processes
+pub trait BpfTraceCallback: fmt::Debug {
+    /// Whether to trace the BPF programs invoked by `transaction` in `slot`, the programs of
+    /// untraced transactions run as usual and their transactions are not passed to `callback`
+    fn is_traced(&self, _slot: Slot, _transaction: &Transaction) -> bool {
+        true
+    }
+    fn callback(&self, slot: Slot, signature: &Signature, traces: Vec<BpfTrace>);
+}
+
 #[derive(Debug, PartialEq, Serialize, Deserialize, AbiExample, Clone, Copy)]
 pub struct RewardInfo {
     pub reward_type: RewardType,
@@ -718,6 +777,16 @@ impl AbiExample for OptionalDropCallback {
     }
 }
 
+#[derive(Debug, Default)]
+pub struct OptionalBpfTraceCallback(Option<Arc<dyn BpfTraceCallback + Send + Sync>>);
+
+#[cfg(RUSTC_WITH_SPECIALIZATION)]
+impl AbiExample for OptionalBpfTraceCallback {
+    fn example() -> Self {
+        Self(None)
+    }
+}
+
 /// Manager for the state of all accounts and programs after processing its entries.
 /// AbiExample is needed even without Serialize/Deserialize; actual (de-)serialization
 /// are implemented elsewhere for versioning
@@ -865,6 +934,9 @@ pub struct Bank {
 
     pub drop_callback: RwLock<OptionalDropCallback>,
 
+    // Traces BPF programs when set, inherited by child banks
+    bpf_trace_callback: RwLock<OptionalBpfTraceCallback>,
+
     pub freeze_started: AtomicBool,
 }
 
@@ -1053,6 +1125,9 @@ impl Bank {
                     .as_ref()
                     .map(|drop_callback| drop_callback.clone_box()),
             )),
+            bpf_trace_callback: RwLock::new(OptionalBpfTraceCallback(
+                parent.bpf_trace_callback.read().unwrap().0.clone(),
+            )),
             freeze_started: AtomicBool::new(false),
         };
 
@@ -1106,6 +1181,15 @@ impl Bank {
         *self.drop_callback.write().unwrap() = OptionalDropCallback(callback);
     }
 
+    /// Trace the BPF programs invoked by the transactions this bank and its descendants process,
+    /// passing the traces of each transaction to `callback`
+    pub fn set_bpf_trace_callback(
+        &self,
+        callback: Option<Arc<dyn BpfTraceCallback + Send + Sync>>,
+    ) {
+        *self.bpf_trace_callback.write().unwrap() = OptionalBpfTraceCallback(callback);
+    }
+
     /// Like `new_from_parent` but additionally:
     /// * Doesn't assume that the parent is anywhere near `slot`, parent could be millions of slots
     /// in the past
@@ -1197,6 +1281,7 @@ impl Bank {
             transaction_log_collector: new(),
             feature_set: new(),
             drop_callback: RwLock::new(OptionalDropCallback(None)),
+            bpf_trace_callback: RwLock::new(OptionalBpfTraceCallback(None)),
             freeze_started: AtomicBool::new(fields.hash != Hash::default()),
         };
         bank.finish_init(genesis_config, additional_builtins);
@@ -1517,6 +1602,18 @@ impl Bank {
         });
     }
 
+    /// Overwrite a sysvar account with `sysvar`, as seen by programs. The
+    /// bank's own state, such as its rent collector, is unaffected, and
+    /// sysvars that the bank updates every slot are replaced by the next bank.
+    pub fn set_sysvar_for_tests<T: sysvar::Sysvar + sysvar::SysvarAddress>(&self, sysvar: &T) {
+        self.update_sysvar_account(&T::id(), |account| {
+            create_account(
+                sysvar,
+                self.inherit_specially_retained_account_balance(account),
+            )
+        });
+    }
+
     fn update_stake_history(&self, epoch: Option<Epoch>) {
         if epoch == Some(self.epoch()) {
             return;
@@ -2381,6 +2478,43 @@ impl Bank {
             .map_or(Ok(()), |sig| self.get_signature_status(sig).unwrap())
     }
 
+    /// Resolve a versioned transaction into the legacy form that this bank executes,
+    /// loading any addresses that a v0 message references through lookup tables.
+    ///
+    /// The resolved transaction keeps the signatures of `tx`, which cover the message of
+    /// `tx` rather than the resolved message. Signatures must be verified against `tx`,
+    /// which is also the form that is recorded in entries.
+    pub fn resolve_versioned_transaction(&self, tx: &VersionedTransaction) -> Result<Transaction> {
+        tx.sanitize()?;
+        match &tx.message {
+            VersionedMessage::Legacy(message) => Ok(Transaction {
+                signatures: tx.signatures.clone(),
+                message: message.clone(),
+            }),
+            VersionedMessage::V0(message) => {
+                if !self
+                    .feature_set
+                    .is_active(&feature_set::versioned_tx_message_enabled::id())
+                {
+                    return Err(TransactionError::UnsupportedVersion);
+                }
+                let loaded_addresses = self.rc.accounts.load_lookup_table_addresses(
+                    &self.ancestors,
+                    &message.address_table_lookups,
+                    self.slot(),
+                )?;
+                let message = message
+                    .clone()
+                    .try_into_legacy_message(loaded_addresses)
+                    .ok_or(TransactionError::SanitizeFailure)?;
+                Ok(Transaction {
+                    signatures: tx.signatures.clone(),
+                    message,
+                })
+            }
+        }
+    }
+
     pub fn prepare_batch<'a, 'b>(
         &'a self,
         txs: &'b [Transaction],
@@ -2410,17 +2544,41 @@ impl Bank {
     pub fn simulate_transaction(
         &self,
         transaction: Transaction,
-    ) -> (Result<()>, TransactionLogMessages) {
+    ) -> (
+        Result<()>,
+        TransactionLogMessages,
+        Option<TransactionReturnData>,
+    ) {
         assert!(self.is_frozen(), "simulation bank must be frozen");
 
+        let details = self.simulate_transaction_with_details(transaction, false);
+        (details.status, details.log_messages, details.return_data)
+    }
+
+    /// Run a transaction against the bank without committing the results, even
+    /// if the bank is not frozen, recording the instructions it invokes
+    pub fn simulate_transaction_unchecked(
+        &self,
+        transaction: Transaction,
+    ) -> TransactionExecutionDetails {
+        self.simulate_transaction_with_details(transaction, true)
+    }
+
+    fn simulate_transaction_with_details(
+        &self,
+        transaction: Transaction,
+        enable_cpi_recording: bool,
+    ) -> TransactionExecutionDetails {
         let txs = &[transaction];
         let batch = self.prepare_simulation_batch(txs);
 
         let (
-            _loaded_accounts,
+            loaded_accounts,
             executed,
-            _inner_instructions,
-            log_messages,
+            mut inner_instructions,
+            mut log_messages,
+            mut return_data,
+            mut executed_units,
             _retryable_transactions,
             _transaction_count,
             _signature_count,
@@ -2430,17 +2588,91 @@ impl Bank {
             // for processing. During forwarding, the transaction could expire if the
             // delay is not accounted for.
             MAX_PROCESSING_AGE - MAX_TRANSACTION_FORWARDING_DELAY,
-            false,
+            enable_cpi_recording,
             true,
             &mut ExecuteTimings::default(),
         );
 
-        let transaction_result = executed[0].0.clone().map(|_| ());
-        let log_messages = log_messages
-            .get(0)
-            .map_or(vec![], |messages| messages.to_vec());
+        let status = executed[0].0.clone();
+        let mut post_accounts = self.get_transaction_accounts(&txs[0]);
+        if let (Ok(()), Ok(loaded_transaction)) = (&status, &loaded_accounts[0].0) {
+            // Only writable accounts may have been changed by the execution
+            for (i, ((_pubkey, account), loaded_account)) in post_accounts
+                .iter_mut()
+                .zip(&loaded_transaction.accounts)
+                .enumerate()
+            {
+                if txs[0].message.is_writable(i) {
+                    *account = loaded_account.clone();
+                }
+            }
+        }
 
-        (transaction_result, log_messages)
+        TransactionExecutionDetails {
+            status,
+            log_messages: log_messages.pop().unwrap_or_default(),
+            inner_instructions: inner_instructions.pop().flatten().unwrap_or_default(),
+            return_data: return_data.pop().flatten(),
+            executed_units: executed_units.pop().unwrap_or_default(),
+            post_accounts,
+        }
+    }
+
+    /// Process a Transaction, committing its results, and return what was recorded
+    /// during its execution
+    pub fn process_transaction_with_details(
+        &self,
+        tx: &Transaction,
+    ) -> TransactionExecutionDetails {
+        let txs = &[tx.clone()];
+        let batch = self.prepare_batch(txs, None);
+        let mut timings = ExecuteTimings::default();
+
+        let (
+            mut loaded_accounts,
+            executed,
+            mut inner_instructions,
+            mut log_messages,
+            mut return_data,
+            mut executed_units,
+            _retryable_transactions,
+            tx_count,
+            signature_count,
+        ) = self.load_and_execute_transactions(
+            &batch,
+            MAX_PROCESSING_AGE,
+            true,
+            true,
+            &mut timings,
+        );
+        let results = self.commit_transactions(
+            txs,
+            None,
+            &mut loaded_accounts,
+            &executed,
+            tx_count,
+            signature_count,
+            &mut timings,
+        );
+
+        TransactionExecutionDetails {
+            // Fees are collected from failed instructions, so their status is only in the
+            // execution results
+            status: results.execution_results[0].0.clone(),
+            log_messages: log_messages.pop().unwrap_or_default(),
+            inner_instructions: inner_instructions.pop().flatten().unwrap_or_default(),
+            return_data: return_data.pop().flatten(),
+            executed_units: executed_units.pop().unwrap_or_default(),
+            post_accounts: self.get_transaction_accounts(tx),
+        }
+    }
+
+    fn get_transaction_accounts(&self, tx: &Transaction) -> Vec<(Pubkey, Account)> {
+        tx.message
+            .account_keys
+            .iter()
+            .map(|pubkey| (*pubkey, self.get_account(pubkey).unwrap_or_default()))
+            .collect()
     }
 
     pub fn unlock_accounts(&self, batch: &mut TransactionBatch) {
@@ -2770,6 +3002,7 @@ impl Bank {
         &self,
         message: &Message,
         loaders: &[Vec<(Pubkey, Account)>],
+        traced: bool,
     ) -> Rc<RefCell<Executors>> {
         let mut num_executors = message.account_keys.len();
         for instruction_loaders in loaders.iter() {
@@ -2780,13 +3013,13 @@ impl Bank {
         let cache = cow_cache.read().unwrap();
 
         for key in message.account_keys.iter() {
-            if let Some(executor) = cache.get(key) {
+            if let Some(executor) = cache.get(key, traced) {
                 executors.insert(*key, executor);
             }
         }
         for instruction_loaders in loaders.iter() {
             for (key, _) in instruction_loaders.iter() {
-                if let Some(executor) = cache.get(key) {
+                if let Some(executor) = cache.get(key, traced) {
                     executors.insert(*key, executor);
                 }
             }
@@ -2799,13 +3032,13 @@ impl Bank {
     }
 
     /// Add executors back to the bank's cache if modified
-    fn update_executors(&self, executors: Rc<RefCell<Executors>>) {
+    fn update_executors(&self, executors: Rc<RefCell<Executors>>, traced: bool) {
         let executors = executors.borrow();
         if executors.is_dirty {
             let mut cow_cache = self.cached_executors.write().unwrap();
             let mut cache = cow_cache.write().unwrap();
             for (key, executor) in executors.executors.iter() {
-                cache.put(key, (*executor).clone());
+                cache.put(key, traced, (*executor).clone());
             }
         }
     }
@@ -2830,6 +3063,8 @@ impl Bank {
         Vec<TransactionExecutionResult>,
         Vec<Option<InnerInstructionsList>>,
         Vec<TransactionLogMessages>,
+        Vec<Option<TransactionReturnData>>,
+        Vec<u64>,
         Vec<usize>,
         u64,
         u64,
@@ -2877,19 +3112,30 @@ impl Bank {
         let mut inner_instructions: Vec<Option<InnerInstructionsList>> =
             Vec::with_capacity(txs.len());
         let mut transaction_log_messages = Vec::with_capacity(txs.len());
+        let mut transaction_return_data = Vec::with_capacity(txs.len());
+        let mut transaction_executed_units = Vec::with_capacity(txs.len());
         let bpf_compute_budget = self
             .bpf_compute_budget
             .unwrap_or_else(BpfComputeBudget::new);
+        let bpf_trace_callback = self.bpf_trace_callback.read().unwrap().0.clone();
 
         let executed: Vec<TransactionExecutionResult> = loaded_accounts
             .iter_mut()
             .zip(OrderedIterator::new(txs, batch.iteration_order()))
             .map(|(accs, (_, tx))| match accs {
-                (Err(e), _nonce_rollback) => (Err(e.clone()), None),
+                (Err(e), _nonce_rollback) => {
+                    transaction_return_data.push(None);
+                    transaction_executed_units.push(0);
+                    (Err(e.clone()), None)
+                }
                 (Ok(loaded_transaction), nonce_rollback) => {
                     signature_count += u64::from(tx.message().header.num_required_signatures);
 
-                    let executors = self.get_executors(&tx.message, &loaded_transaction.loaders);
+                    let traced = bpf_trace_callback
+                        .as_ref()
+                        .map_or(false, |callback| callback.is_traced(self.slot(), tx));
+                    let executors =
+                        self.get_executors(&tx.message, &loaded_transaction.loaders, traced);
 
                     let (account_refcells, account_dep_refcells, loader_refcells) =
                         Self::accounts_to_refcells(
@@ -2913,6 +3159,14 @@ impl Bank {
                         None
                     };
 
+                    let bpf_trace_collector = if traced {
+                        Some(Rc::new(BpfTraceCollector::default()))
+                    } else {
+                        None
+                    };
+
+                    let mut return_data = None;
+                    let mut executed_units = 0;
                     let process_result = self.message_processor.process_message(
                         tx.message(),
                         &loader_refcells,
@@ -2922,9 +3176,14 @@ impl Bank {
                         log_collector.clone(),
                         executors.clone(),
                         instruction_recorders.as_deref(),
+                        bpf_trace_collector.clone(),
                         self.feature_set.clone(),
                         bpf_compute_budget,
+                        &mut return_data,
+                        &mut executed_units,
                     );
+                    transaction_return_data.push(return_data);
+                    transaction_executed_units.push(executed_units);
 
                     if enable_log_recording {
                         let log_messages: TransactionLogMessages =
@@ -2935,6 +3194,15 @@ impl Bank {
                         transaction_log_messages.push(log_messages);
                     }
 
+                    if let (Some(callback), Some(bpf_trace_collector)) =
+                        (&bpf_trace_callback, bpf_trace_collector)
+                    {
+                        let traces = Rc::try_unwrap(bpf_trace_collector)
+                            .unwrap_or_default()
+                            .into();
+                        callback.callback(self.slot(), &tx.signatures[0], traces);
+                    }
+
                     Self::compile_recorded_instructions(
                         &mut inner_instructions,
                         instruction_recorders,
@@ -2949,7 +3217,7 @@ impl Bank {
                     );
 
                     if process_result.is_ok() {
-                        self.update_executors(executors);
+                        self.update_executors(executors, traced);
                     }
 
                     let nonce_rollback =
@@ -3057,6 +3325,8 @@ impl Bank {
             executed,
             inner_instructions,
             transaction_log_messages,
+            transaction_return_data,
+            transaction_executed_units,
             retryable_txs,
             tx_count,
             signature_count,
@@ -3074,6 +3344,7 @@ impl Bank {
 
         let fee_config = FeeConfig {
             secp256k1_program_enabled: self.secp256k1_program_enabled(),
+            compute_budget_program_enabled: self.compute_budget_program_enabled(),
         };
 
         let results = OrderedIterator::new(txs, iteration_order)
@@ -3760,6 +4031,7 @@ impl Bank {
         TransactionBalancesSet,
         Vec<Option<InnerInstructionsList>>,
         Vec<TransactionLogMessages>,
+        Vec<Option<TransactionReturnData>>,
     ) {
         let pre_balances = if collect_balances {
             self.collect_balances(batch)
@@ -3772,6 +4044,8 @@ impl Bank {
             executed,
             inner_instructions,
             transaction_logs,
+            transaction_return_data,
+            _executed_units,
             _,
             tx_count,
             signature_count,
@@ -3802,6 +4076,7 @@ impl Bank {
             TransactionBalancesSet::new(pre_balances, post_balances),
             inner_instructions,
             transaction_logs,
+            transaction_return_data,
         )
     }
 
@@ -3908,6 +4183,61 @@ impl Bank {
         self.store_account(pubkey, new_account);
     }
 
+    /// Replace the ELF of a deployed BPF program with `elf`, to replay transactions against a
+    /// modified build of the program.  Programs of the upgradeable loader have their program
+    /// data rewritten, keeping the deployment slot and authority.  Returns whether the bank was
+    /// modified, which it isn't if the program is not deployed in this bank or already runs
+    /// `elf`
+    pub fn override_program(&self, program_id: &Pubkey, elf: &[u8]) -> bool {
+        use solana_sdk::{
+            account_utils::StateMut,
+            bpf_loader, bpf_loader_deprecated,
+            bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+        };
+
+        let program_account = match self.get_account(program_id) {
+            Some(program_account) if program_account.executable => program_account,
+            _ => return false,
+        };
+        let (address, mut account, data_offset) =
+            if program_account.owner == bpf_loader_upgradeable::id() {
+                let programdata_address = match program_account.state() {
+                    Ok(UpgradeableLoaderState::Program {
+                        programdata_address,
+                    }) => programdata_address,
+                    _ => return false,
+                };
+                match self.get_account(&programdata_address) {
+                    Some(programdata_account) => (
+                        programdata_address,
+                        programdata_account,
+                        UpgradeableLoaderState::programdata_data_offset().unwrap(),
+                    ),
+                    None => return false,
+                }
+            } else if program_account.owner == bpf_loader::id()
+                || program_account.owner == bpf_loader_deprecated::id()
+            {
+                (*program_id, program_account, 0)
+            } else {
+                warn!("{} is not a BPF program, not overriding it", program_id);
+                return false;
+            };
+        if account.data.len() < data_offset || account.data[data_offset..] == *elf {
+            return false;
+        }
+
+        account.data.truncate(data_offset);
+        account.data.extend_from_slice(elf);
+        account.lamports = account
+            .lamports
+            .max(self.rent_collector.rent.minimum_balance(account.data.len()));
+        self.store_account_and_update_capitalization(&address, &account);
+        self.remove_executor(program_id);
+        info!("Overrode program {} in slot {}", program_id, self.slot());
+        true
+    }
+
     pub fn withdraw(&self, pubkey: &Pubkey, lamports: u64) -> Result<()> {
         match self.get_account(pubkey) {
             Some(mut account) => {
@@ -4167,25 +4497,11 @@ impl Bank {
     fn hash_internal_state(&self) -> Hash {
         // If there are no accounts, return the hash of the previous state and the latest blockhash
         let accounts_delta_hash = self.rc.accounts.bank_hash_info_at(self.slot());
-        let mut signature_count_buf = [0u8; 8];
-        LittleEndian::write_u64(&mut signature_count_buf[..], self.signature_count() as u64);
-
-        let mut hash = hashv(&[
-            self.parent_hash.as_ref(),
-            accounts_delta_hash.hash.as_ref(),
-            &signature_count_buf,
-            self.last_blockhash().as_ref(),
-        ]);
-
-        if let Some(buf) = self
-            .hard_forks
-            .read()
-            .unwrap()
-            .get_hash_data(self.slot(), self.parent_slot())
-        {
+        let hash_inputs = self.hash_inputs_with_accounts_delta_hash(accounts_delta_hash.hash);
+        if hash_inputs.hard_fork_data.is_some() {
             info!("hard fork at bank {}", self.slot());
-            hash = extend_and_hash(&hash, &buf)
         }
+        let hash = hash_inputs.hash();
 
         info!(
             "bank frozen: {} hash: {} accounts_delta: {} signature_count: {} last_blockhash: {} capitalization: {}",
@@ -4205,6 +4521,33 @@ impl Bank {
         hash
     }
 
+    fn hash_inputs_with_accounts_delta_hash(&self, accounts_delta_hash: Hash) -> BankHashInputs {
+        BankHashInputs {
+            parent_hash: self.parent_hash,
+            accounts_delta_hash,
+            signature_count: self.signature_count(),
+            last_blockhash: self.last_blockhash(),
+            hard_fork_data: self
+                .hard_forks
+                .read()
+                .unwrap()
+                .get_hash_data(self.slot(), self.parent_slot()),
+        }
+    }
+
+    /// The inputs of the hash of this bank, to find out why two banks of the same slot hash
+    /// differently
+    pub fn hash_inputs(&self) -> BankHashInputs {
+        self.hash_inputs_with_accounts_delta_hash(self.rc.accounts.bank_hash_at(self.slot()))
+    }
+
+    /// The accounts stored in this bank's slot, whose hashes make up the accounts delta hash
+    pub fn get_accounts_delta(&self) -> Vec<(Pubkey, Account)> {
+        let mut accounts = self.rc.accounts.load_by_program_slot(self.slot(), None);
+        accounts.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        accounts
+    }
+
     /// Recalculate the hash_internal_state from the account stores. Would be used to verify a
     /// snapshot.
     #[must_use]
@@ -4616,6 +4959,11 @@ impl Bank {
             .is_active(&feature_set::secp256k1_program_enabled::id())
     }
 
+    pub fn compute_budget_program_enabled(&self) -> bool {
+        self.feature_set
+            .is_active(&feature_set::compute_budget_program::id())
+    }
+
     pub fn no_overflow_rent_distribution_enabled(&self) -> bool {
         self.feature_set
             .is_active(&feature_set::no_overflow_rent_distribution::id())
@@ -4990,6 +5338,7 @@ pub(crate) mod tests {
     use solana_sdk::{
         account_utils::StateMut,
         clock::{DEFAULT_SLOTS_PER_EPOCH, DEFAULT_TICKS_PER_SLOT},
+        compute_budget::ComputeBudgetInstruction,
         epoch_schedule::MINIMUM_SLOTS_PER_EPOCH,
         feature::Feature,
         genesis_config::create_genesis_config,
@@ -7933,6 +8282,105 @@ pub(crate) mod tests {
         assert!(bank2.verify_bank_hash());
     }
 
+    #[test]
+    fn test_bank_hash_inputs() {
+        let (genesis_config, mint_keypair) = create_genesis_config(2_000);
+        // Separate banks, as banks of the same slot share their accounts delta
+        let bank0 = Arc::new(Bank::new(&genesis_config));
+        let other_bank0 = Arc::new(Bank::new(&genesis_config));
+        bank0.freeze();
+        other_bank0.freeze();
+        let bank1 = new_from_parent(&bank0);
+        let bank2 = new_from_parent(&other_bank0);
+
+        let pubkey = solana_sdk::pubkey::new_rand();
+        bank1.transfer(1_000, &mint_keypair, &pubkey).unwrap();
+        bank2.transfer(999, &mint_keypair, &pubkey).unwrap();
+        bank1.freeze();
+        bank2.freeze();
+
+        let hash_inputs1 = bank1.hash_inputs();
+        let hash_inputs2 = bank2.hash_inputs();
+        assert_eq!(hash_inputs1.hash(), bank1.hash());
+        assert_eq!(hash_inputs2.hash(), bank2.hash());
+        assert_eq!(hash_inputs1.parent_hash, hash_inputs2.parent_hash);
+        assert_eq!(hash_inputs1.signature_count, 1);
+        assert_eq!(hash_inputs1.hard_fork_data, None);
+        assert_ne!(
+            hash_inputs1.accounts_delta_hash,
+            hash_inputs2.accounts_delta_hash
+        );
+
+        let delta1 = bank1.get_accounts_delta();
+        let delta2 = bank2.get_accounts_delta();
+        let account1 = delta1.iter().find(|(key, _)| *key == pubkey).unwrap();
+        let account2 = delta2.iter().find(|(key, _)| *key == pubkey).unwrap();
+        assert_eq!(account1.1.lamports, 1_000);
+        assert_eq!(account2.1.lamports, 999);
+        assert!(delta1.iter().any(|(key, _)| *key == mint_keypair.pubkey()));
+    }
+
+    #[test]
+    fn test_override_program() {
+        use solana_sdk::{
+            account_utils::StateMut,
+            bpf_loader,
+            bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+        };
+
+        let (genesis_config, _mint_keypair) = create_genesis_config(sol_to_lamports(1.));
+        let bank = Bank::new(&genesis_config);
+
+        let program_id = solana_sdk::pubkey::new_rand();
+        let mut program_account = Account::new(1, 3, &bpf_loader::id());
+        program_account.executable = true;
+        program_account.data = vec![1, 2, 3];
+        bank.store_account_and_update_capitalization(&program_id, &program_account);
+
+        let elf = vec![4; 100];
+        assert!(bank.override_program(&program_id, &elf));
+        let program_account = bank.get_account(&program_id).unwrap();
+        assert_eq!(program_account.data, elf);
+        assert_eq!(
+            program_account.lamports,
+            bank.get_minimum_balance_for_rent_exemption(elf.len())
+        );
+        assert!(!bank.override_program(&program_id, &elf));
+        assert!(!bank.override_program(&solana_sdk::pubkey::new_rand(), &elf));
+
+        let program_id = solana_sdk::pubkey::new_rand();
+        let programdata_address = solana_sdk::pubkey::new_rand();
+        let mut program_account = Account::new(
+            1,
+            UpgradeableLoaderState::program_len().unwrap(),
+            &bpf_loader_upgradeable::id(),
+        );
+        program_account.executable = true;
+        program_account
+            .set_state(&UpgradeableLoaderState::Program {
+                programdata_address,
+            })
+            .unwrap();
+        let data_offset = UpgradeableLoaderState::programdata_data_offset().unwrap();
+        let programdata_state = UpgradeableLoaderState::ProgramData {
+            slot: 0,
+            upgrade_authority_address: Some(solana_sdk::pubkey::new_rand()),
+        };
+        let mut programdata_account =
+            Account::new(1, data_offset + 3, &bpf_loader_upgradeable::id());
+        programdata_account.set_state(&programdata_state).unwrap();
+        bank.store_account_and_update_capitalization(&program_id, &program_account);
+        bank.store_account_and_update_capitalization(&programdata_address, &programdata_account);
+
+        assert!(bank.override_program(&program_id, &elf));
+        let programdata_account = bank.get_account(&programdata_address).unwrap();
+        assert_eq!(programdata_account.data[data_offset..], elf[..]);
+        assert_eq!(programdata_account.state(), Ok(programdata_state));
+        assert_eq!(bank.get_account(&program_id).unwrap(), program_account);
+
+        assert!(bank.calculate_and_verify_capitalization());
+    }
+
     #[test]
     fn test_bank_hash_internal_state_verify() {
         solana_logger::setup();
@@ -9706,18 +10154,24 @@ pub(crate) mod tests {
         let txs = vec![tx0, tx1, tx2];
 
         let lock_result = bank0.prepare_batch(&txs, None);
-        let (transaction_results, transaction_balances_set, inner_instructions, transaction_logs) =
-            bank0.load_execute_and_commit_transactions(
-                &lock_result,
-                MAX_PROCESSING_AGE,
-                true,
-                false,
-                false,
-                &mut ExecuteTimings::default(),
-            );
+        let (
+            transaction_results,
+            transaction_balances_set,
+            inner_instructions,
+            transaction_logs,
+            transaction_return_data,
+        ) = bank0.load_execute_and_commit_transactions(
+            &lock_result,
+            MAX_PROCESSING_AGE,
+            true,
+            false,
+            false,
+            &mut ExecuteTimings::default(),
+        );
 
         assert!(inner_instructions[0].iter().all(|ix| ix.is_empty()));
         assert_eq!(transaction_logs.len(), 0);
+        assert!(transaction_return_data.iter().all(Option::is_none));
 
         assert_eq!(transaction_balances_set.pre_balances.len(), 3);
         assert_eq!(transaction_balances_set.post_balances.len(), 3);
@@ -10887,30 +11341,47 @@ pub(crate) mod tests {
         let executor: Arc<dyn Executor> = Arc::new(TestExecutor {});
         let mut cache = CachedExecutors::new(3);
 
-        cache.put(&key1, executor.clone());
-        cache.put(&key2, executor.clone());
-        cache.put(&key3, executor.clone());
-        assert!(cache.get(&key1).is_some());
-        assert!(cache.get(&key2).is_some());
-        assert!(cache.get(&key3).is_some());
-
-        assert!(cache.get(&key1).is_some());
-        assert!(cache.get(&key1).is_some());
-        assert!(cache.get(&key2).is_some());
-        cache.put(&key4, executor.clone());
-        assert!(cache.get(&key1).is_some());
-        assert!(cache.get(&key2).is_some());
-        assert!(cache.get(&key3).is_none());
-        assert!(cache.get(&key4).is_some());
-
-        assert!(cache.get(&key4).is_some());
-        assert!(cache.get(&key4).is_some());
-        assert!(cache.get(&key4).is_some());
-        cache.put(&key3, executor.clone());
-        assert!(cache.get(&key1).is_some());
-        assert!(cache.get(&key2).is_none());
-        assert!(cache.get(&key3).is_some());
-        assert!(cache.get(&key4).is_some());
+        cache.put(&key1, false, executor.clone());
+        cache.put(&key2, false, executor.clone());
+        cache.put(&key3, false, executor.clone());
+        assert!(cache.get(&key1, false).is_some());
+        assert!(cache.get(&key2, false).is_some());
+        assert!(cache.get(&key3, false).is_some());
+
+        assert!(cache.get(&key1, false).is_some());
+        assert!(cache.get(&key1, false).is_some());
+        assert!(cache.get(&key2, false).is_some());
+        cache.put(&key4, false, executor.clone());
+        assert!(cache.get(&key1, false).is_some());
+        assert!(cache.get(&key2, false).is_some());
+        assert!(cache.get(&key3, false).is_none());
+        assert!(cache.get(&key4, false).is_some());
+
+        assert!(cache.get(&key4, false).is_some());
+        assert!(cache.get(&key4, false).is_some());
+        assert!(cache.get(&key4, false).is_some());
+        cache.put(&key3, false, executor.clone());
+        assert!(cache.get(&key1, false).is_some());
+        assert!(cache.get(&key2, false).is_none());
+        assert!(cache.get(&key3, false).is_some());
+        assert!(cache.get(&key4, false).is_some());
+    }
+
+    #[test]
+    fn test_cached_executors_traced() {
+        let key = solana_sdk::pubkey::new_rand();
+        let executor: Arc<dyn Executor> = Arc::new(TestExecutor {});
+        let mut cache = CachedExecutors::new(3);
+
+        cache.put(&key, false, executor.clone());
+        assert!(cache.get(&key, true).is_none());
+        cache.put(&key, true, executor);
+        assert!(cache.get(&key, false).is_some());
+        assert!(cache.get(&key, true).is_some());
+
+        cache.remove(&key);
+        assert!(cache.get(&key, false).is_none());
+        assert!(cache.get(&key, true).is_none());
     }
 
     #[test]
@@ -10950,8 +11421,8 @@ pub(crate) mod tests {
         executors.insert(key4, executor.clone());
         let executors = Rc::new(RefCell::new(executors));
         executors.borrow_mut().is_dirty = false;
-        bank.update_executors(executors);
-        let executors = bank.get_executors(&message, loaders);
+        bank.update_executors(executors, false);
+        let executors = bank.get_executors(&message, loaders, false);
         assert_eq!(executors.borrow().executors.len(), 0);
 
         // do work
@@ -10961,8 +11432,8 @@ pub(crate) mod tests {
         executors.insert(key3, executor.clone());
         executors.insert(key4, executor.clone());
         let executors = Rc::new(RefCell::new(executors));
-        bank.update_executors(executors);
-        let executors = bank.get_executors(&message, loaders);
+        bank.update_executors(executors, false);
+        let executors = bank.get_executors(&message, loaders, false);
         assert_eq!(executors.borrow().executors.len(), 4);
         assert!(executors.borrow().executors.contains_key(&key1));
         assert!(executors.borrow().executors.contains_key(&key2));
@@ -10971,7 +11442,7 @@ pub(crate) mod tests {
 
         // Check inheritance
         let bank = Bank::new_from_parent(&Arc::new(bank), &solana_sdk::pubkey::new_rand(), 1);
-        let executors = bank.get_executors(&message, loaders);
+        let executors = bank.get_executors(&message, loaders, false);
         assert_eq!(executors.borrow().executors.len(), 4);
         assert!(executors.borrow().executors.contains_key(&key1));
         assert!(executors.borrow().executors.contains_key(&key2));
@@ -10982,7 +11453,7 @@ pub(crate) mod tests {
         bank.remove_executor(&key2);
         bank.remove_executor(&key3);
         bank.remove_executor(&key4);
-        let executors = bank.get_executors(&message, loaders);
+        let executors = bank.get_executors(&message, loaders, false);
         assert_eq!(executors.borrow().executors.len(), 0);
         assert!(!executors.borrow().executors.contains_key(&key1));
         assert!(!executors.borrow().executors.contains_key(&key2));
@@ -11007,33 +11478,33 @@ pub(crate) mod tests {
         let mut executors = Executors::default();
         executors.insert(key1, executor.clone());
         let executors = Rc::new(RefCell::new(executors));
-        root.update_executors(executors);
-        let executors = root.get_executors(&Message::default(), loaders);
+        root.update_executors(executors, false);
+        let executors = root.get_executors(&Message::default(), loaders, false);
         assert_eq!(executors.borrow().executors.len(), 1);
 
         let fork1 = Bank::new_from_parent(&root, &Pubkey::default(), 1);
         let fork2 = Bank::new_from_parent(&root, &Pubkey::default(), 1);
 
-        let executors = fork1.get_executors(&Message::default(), loaders);
+        let executors = fork1.get_executors(&Message::default(), loaders, false);
         assert_eq!(executors.borrow().executors.len(), 1);
-        let executors = fork2.get_executors(&Message::default(), loaders);
+        let executors = fork2.get_executors(&Message::default(), loaders, false);
         assert_eq!(executors.borrow().executors.len(), 1);
 
         let mut executors = Executors::default();
         executors.insert(key2, executor.clone());
         let executors = Rc::new(RefCell::new(executors));
-        fork1.update_executors(executors);
+        fork1.update_executors(executors, false);
 
-        let executors = fork1.get_executors(&Message::default(), loaders);
+        let executors = fork1.get_executors(&Message::default(), loaders, false);
         assert_eq!(executors.borrow().executors.len(), 2);
-        let executors = fork2.get_executors(&Message::default(), loaders);
+        let executors = fork2.get_executors(&Message::default(), loaders, false);
         assert_eq!(executors.borrow().executors.len(), 1);
 
         fork1.remove_executor(&key1);
 
-        let executors = fork1.get_executors(&Message::default(), loaders);
+        let executors = fork1.get_executors(&Message::default(), loaders, false);
         assert_eq!(executors.borrow().executors.len(), 1);
-        let executors = fork2.get_executors(&Message::default(), loaders);
+        let executors = fork2.get_executors(&Message::default(), loaders, false);
         assert_eq!(executors.borrow().executors.len(), 1);
     }
 
@@ -11209,6 +11680,14 @@ pub(crate) mod tests {
         genesis_config
             .accounts
             .remove(&feature_set::deprecate_rewards_sysvar::id());
+        // native programs added after simple capitalization aren't covered by its
+        // one-time adjustment
+        genesis_config
+            .accounts
+            .remove(&feature_set::address_lookup_table_program::id());
+        genesis_config
+            .accounts
+            .remove(&feature_set::compute_budget_program::id());
 
         // intentionally create bogus native programs
         #[allow(clippy::unnecessary_wraps)]
@@ -12220,4 +12699,234 @@ pub(crate) mod tests {
             genesis_config.epoch_schedule.get_first_slot_in_epoch(1),
         );
     }
+
+    #[test]
+    fn test_resolve_versioned_transaction() {
+        use solana_address_lookup_table_program::state::{
+            AddressLookupTable, LookupTableMeta, DEACTIVATION_COOLDOWN,
+        };
+        use solana_sdk::message::v0;
+
+        let (mut genesis_config, mint_keypair) = create_genesis_config(1_000_000);
+        activate_all_features(&mut genesis_config);
+        let bank0 = Arc::new(Bank::new(&genesis_config));
+
+        let to = solana_sdk::pubkey::new_rand();
+        let table_address = solana_sdk::pubkey::new_rand();
+        let lookup_table = AddressLookupTable {
+            meta: LookupTableMeta {
+                num_addresses: 1,
+                ..LookupTableMeta::new(mint_keypair.pubkey())
+            },
+            addresses: vec![to],
+        };
+        let mut table_account = Account::new(
+            1,
+            AddressLookupTable::data_len(1),
+            &solana_address_lookup_table_program::id(),
+        );
+        lookup_table
+            .serialize_into(&mut table_account.data)
+            .unwrap();
+        bank0.store_account(&table_address, &table_account);
+
+        // transfer to an account that is only referenced through the lookup table
+        let transfer_ix = system_instruction::transfer(&mint_keypair.pubkey(), &to, 42);
+        let message = v0::Message {
+            header: MessageHeader {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 1,
+            },
+            account_keys: vec![mint_keypair.pubkey(), system_program::id()],
+            recent_blockhash: genesis_config.hash(),
+            instructions: vec![CompiledInstruction {
+                program_id_index: 1,
+                accounts: vec![0, 2],
+                data: transfer_ix.data,
+            }],
+            address_table_lookups: vec![v0::MessageAddressTableLookup {
+                account_key: table_address,
+                writable_indexes: vec![0],
+                readonly_indexes: vec![],
+            }],
+        };
+        let tx =
+            VersionedTransaction::try_new(VersionedMessage::V0(message), &[&mint_keypair]).unwrap();
+        assert!(tx.verify().is_ok());
+
+        // addresses appended during the current slot are not usable yet
+        assert_eq!(
+            bank0.resolve_versioned_transaction(&tx),
+            Err(TransactionError::InvalidAddressLookupTableIndex)
+        );
+
+        let bank1 = Bank::new_from_parent(&bank0, &Pubkey::default(), 1);
+        let resolved_tx = bank1.resolve_versioned_transaction(&tx).unwrap();
+        assert_eq!(
+            resolved_tx.message.account_keys,
+            vec![mint_keypair.pubkey(), to, system_program::id()]
+        );
+        assert_eq!(bank1.process_transaction(&resolved_tx), Ok(()));
+        assert_eq!(bank1.get_balance(&to), 42);
+
+        // lookup tables must be owned by the address lookup table program
+        let mut foreign_account = table_account.clone();
+        foreign_account.owner = Pubkey::default();
+        bank1.store_account(&table_address, &foreign_account);
+        assert_eq!(
+            bank1.resolve_versioned_transaction(&tx),
+            Err(TransactionError::InvalidAddressLookupTableOwner)
+        );
+
+        // deactivated lookup tables stay usable until their cooldown ends
+        let deactivated_table = AddressLookupTable {
+            meta: LookupTableMeta {
+                deactivation_slot: 1,
+                ..lookup_table.meta
+            },
+            ..lookup_table
+        };
+        deactivated_table
+            .serialize_into(&mut table_account.data)
+            .unwrap();
+        bank1.store_account(&table_address, &table_account);
+        assert!(bank1.resolve_versioned_transaction(&tx).is_ok());
+        let bank1 = Arc::new(bank1);
+        let cooling_down_bank =
+            Bank::new_from_parent(&bank1, &Pubkey::default(), 1 + DEACTIVATION_COOLDOWN);
+        assert!(cooling_down_bank.resolve_versioned_transaction(&tx).is_ok());
+        let cooled_down_bank =
+            Bank::new_from_parent(&bank1, &Pubkey::default(), 2 + DEACTIVATION_COOLDOWN);
+        assert_eq!(
+            cooled_down_bank.resolve_versioned_transaction(&tx),
+            Err(TransactionError::AddressLookupTableNotFound)
+        );
+    }
+
+    #[test]
+    fn test_compute_budget_priority_fee() {
+        let (mut genesis_config, mint_keypair) = create_genesis_config(1_000_000);
+        genesis_config.fee_rate_governor = FeeRateGovernor::new(5, 0);
+        activate_all_features(&mut genesis_config);
+        let bank = Bank::new(&genesis_config);
+
+        let key = solana_sdk::pubkey::new_rand();
+        let message = Message::new(
+            &[
+                ComputeBudgetInstruction::set_compute_unit_limit(10_000),
+                ComputeBudgetInstruction::set_compute_unit_price(1_000_000),
+                system_instruction::transfer(&mint_keypair.pubkey(), &key, 100),
+            ],
+            Some(&mint_keypair.pubkey()),
+        );
+        let tx = Transaction::new(&[&mint_keypair], message, bank.last_blockhash());
+        let expected_fee = 5 + 10_000;
+        assert_eq!(
+            bank.get_fee_calculator(&bank.last_blockhash())
+                .unwrap()
+                .calculate_fee(tx.message()),
+            expected_fee
+        );
+
+        assert_eq!(bank.process_transaction(&tx), Ok(()));
+        assert_eq!(bank.get_balance(&key), 100);
+        assert_eq!(
+            bank.get_balance(&mint_keypair.pubkey()),
+            1_000_000 - 100 - expected_fee
+        );
+
+        // The priority fee is charged even if the transaction fails
+        let message = Message::new(
+            &[
+                ComputeBudgetInstruction::set_compute_unit_limit(10_000),
+                ComputeBudgetInstruction::set_compute_unit_price(1_000_000),
+                system_instruction::transfer(&mint_keypair.pubkey(), &key, 1_000_000),
+            ],
+            Some(&mint_keypair.pubkey()),
+        );
+        let tx = Transaction::new(&[&mint_keypair], message, bank.last_blockhash());
+        assert!(bank.process_transaction(&tx).is_err());
+        assert_eq!(
+            bank.get_balance(&mint_keypair.pubkey()),
+            1_000_000 - 100 - 2 * expected_fee
+        );
+    }
+
+    #[test]
+    fn test_bpf_trace_callback() {
+        #[derive(Debug, Default)]
+        struct TraceRecorder(std::sync::Mutex<Vec<(Slot, Signature, Vec<BpfTrace>)>>);
+        impl BpfTraceCallback for TraceRecorder {
+            fn callback(&self, slot: Slot, signature: &Signature, traces: Vec<BpfTrace>) {
+                self.0.lock().unwrap().push((slot, *signature, traces));
+            }
+        }
+
+        let (genesis_config, mint_keypair) = create_genesis_config(1_000_000);
+        let bank0 = Bank::new(&genesis_config);
+        let recorder = Arc::new(TraceRecorder::default());
+        bank0.set_bpf_trace_callback(Some(recorder.clone()));
+
+        // Child banks inherit the callback, which is passed the traces of every transaction
+        let bank1 = Bank::new_from_parent(&Arc::new(bank0), &Pubkey::default(), 1);
+        let tx = system_transaction::transfer(
+            &mint_keypair,
+            &solana_sdk::pubkey::new_rand(),
+            1,
+            bank1.last_blockhash(),
+        );
+        assert_eq!(bank1.process_transaction(&tx), Ok(()));
+        assert_eq!(
+            *recorder.0.lock().unwrap(),
+            vec![(1, tx.signatures[0], vec![])]
+        );
+
+        bank1.set_bpf_trace_callback(None);
+        let tx = system_transaction::transfer(
+            &mint_keypair,
+            &solana_sdk::pubkey::new_rand(),
+            1,
+            bank1.last_blockhash(),
+        );
+        assert_eq!(bank1.process_transaction(&tx), Ok(()));
+        assert_eq!(recorder.0.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_bpf_trace_callback_is_traced() {
+        #[derive(Debug)]
+        struct SignatureTracer(Signature, std::sync::Mutex<Vec<Signature>>);
+        impl BpfTraceCallback for SignatureTracer {
+            fn is_traced(&self, _slot: Slot, transaction: &Transaction) -> bool {
+                transaction.signatures[0] == self.0
+            }
+            fn callback(&self, _slot: Slot, signature: &Signature, _traces: Vec<BpfTrace>) {
+                self.1.lock().unwrap().push(*signature);
+            }
+        }
+
+        let (genesis_config, mint_keypair) = create_genesis_config(1_000_000);
+        let bank = Bank::new(&genesis_config);
+        let transfer = |lamports| {
+            system_transaction::transfer(
+                &mint_keypair,
+                &solana_sdk::pubkey::new_rand(),
+                lamports,
+                bank.last_blockhash(),
+            )
+        };
+        let traced_tx = transfer(1);
+        let untraced_tx = transfer(2);
+        let tracer = Arc::new(SignatureTracer(
+            traced_tx.signatures[0],
+            std::sync::Mutex::default(),
+        ));
+        bank.set_bpf_trace_callback(Some(tracer.clone()));
+
+        // Only the transactions the callback selects are traced
+        assert_eq!(bank.process_transaction(&untraced_tx), Ok(()));
+        assert_eq!(bank.process_transaction(&traced_tx), Ok(()));
+        assert_eq!(*tracer.1.lock().unwrap(), vec![traced_tx.signatures[0]]);
+    }
 }
@@ -0,0 +1,20 @@
+use solana_sdk::process_instruction::BpfTrace;
+use std::cell::RefCell;
+
+/// Collects the execution traces of the BPF programs invoked by a transaction
+#[derive(Default)]
+pub struct BpfTraceCollector {
+    traces: RefCell<Vec<BpfTrace>>,
+}
+
+impl BpfTraceCollector {
+    pub fn record(&self, trace: BpfTrace) {
+        self.traces.borrow_mut().push(trace);
+    }
+}
+
+impl From<BpfTraceCollector> for Vec<BpfTrace> {
+    fn from(bpf_trace_collector: BpfTraceCollector) -> Self {
+        bpf_trace_collector.traces.into_inner()
+    }
+}
@@ -13,6 +13,7 @@ pub mod bank_forks;
 pub mod bank_utils;
 mod blockhash_queue;
 pub mod bloom;
+pub mod bpf_trace_collector;
 pub mod builtins;
 pub mod commitment;
 pub mod contains;
@@ -1,6 +1,6 @@
 use crate::{
-    instruction_recorder::InstructionRecorder, log_collector::LogCollector,
-    native_loader::NativeLoader, rent_collector::RentCollector,
+    bpf_trace_collector::BpfTraceCollector, instruction_recorder::InstructionRecorder,
+    log_collector::LogCollector, native_loader::NativeLoader, rent_collector::RentCollector,
 };
 use log::*;
 use serde::{Deserialize, Serialize};
@@ -8,20 +8,24 @@ use solana_sdk::{
     account::Account,
     account_utils::StateMut,
     bpf_loader_upgradeable::{self, UpgradeableLoaderState},
-    feature_set::{instructions_sysvar_enabled, track_writable_deescalation, FeatureSet},
+    compute_budget::ComputeBudgetRequest,
+    feature_set::{
+        compute_budget_program, instructions_sysvar_enabled, return_data_syscall_enabled,
+        track_writable_deescalation, upgradeable_loader_extend_program, FeatureSet,
+    },
     ic_msg,
     instruction::{CompiledInstruction, Instruction, InstructionError},
     keyed_account::{create_keyed_readonly_accounts, KeyedAccount},
     message::Message,
     native_loader,
     process_instruction::{
-        BpfComputeBudget, ComputeMeter, Executor, InvokeContext, Logger,
+        BpfComputeBudget, BpfTrace, ComputeMeter, Executor, InvokeContext, Logger,
         ProcessInstructionWithContext,
     },
     pubkey::Pubkey,
     rent::Rent,
     system_program,
-    transaction::TransactionError,
+    transaction::{TransactionError, TransactionReturnData},
 };
 use std::{
     cell::{Ref, RefCell},
@@ -75,6 +79,7 @@ impl PreAccount {
         is_writable: Option<bool>,
         rent: &Rent,
         post: &Account,
+        upgradeable_loader_extend_program: bool,
     ) -> Result<(), InstructionError> {
         let pre = self.account.borrow();
 
@@ -115,10 +120,15 @@ impl PreAccount {
         }
 
         // Only the system program can change the size of the data
-        //  and only if the system program owns the account
+        //  and only if the system program owns the account,
+        //  except that the upgradeable loader may grow the accounts it owns
         if pre.data.len() != post.data.len()
             && (!system_program::check_id(program_id) // line coverage used to get branch coverage
                 || !system_program::check_id(&pre.owner))
+            && !(upgradeable_loader_extend_program
+                && bpf_loader_upgradeable::check_id(program_id)
+                && bpf_loader_upgradeable::check_id(&pre.owner)
+                && pre.data.len() < post.data.len())
         {
             return Err(InstructionError::AccountDataSizeChanged);
         }
@@ -221,7 +231,9 @@ pub struct ThisInvokeContext<'a> {
     compute_meter: Rc<RefCell<dyn ComputeMeter>>,
     executors: Rc<RefCell<Executors>>,
     instruction_recorder: Option<InstructionRecorder>,
+    bpf_trace_collector: Option<Rc<BpfTraceCollector>>,
     feature_set: Arc<FeatureSet>,
+    return_data: Option<TransactionReturnData>,
 }
 impl<'a> ThisInvokeContext<'a> {
     #[allow(clippy::too_many_arguments)]
@@ -235,6 +247,7 @@ impl<'a> ThisInvokeContext<'a> {
         bpf_compute_budget: BpfComputeBudget,
         executors: Rc<RefCell<Executors>>,
         instruction_recorder: Option<InstructionRecorder>,
+        bpf_trace_collector: Option<Rc<BpfTraceCollector>>,
         feature_set: Arc<FeatureSet>,
     ) -> Self {
         let mut program_ids = Vec::with_capacity(bpf_compute_budget.max_invoke_depth);
@@ -252,7 +265,9 @@ impl<'a> ThisInvokeContext<'a> {
             })),
             executors,
             instruction_recorder,
+            bpf_trace_collector,
             feature_set,
+            return_data: None,
         }
     }
 }
@@ -283,6 +298,8 @@ impl<'a> InvokeContext for ThisInvokeContext<'a> {
     ) -> Result<(), InstructionError> {
         let track_writable_deescalation =
             self.is_feature_active(&track_writable_deescalation::id());
+        let upgradeable_loader_extend_program =
+            self.is_feature_active(&upgradeable_loader_extend_program::id());
         match self.program_ids.last() {
             Some(program_id) => MessageProcessor::verify_and_update(
                 message,
@@ -292,6 +309,7 @@ impl<'a> InvokeContext for ThisInvokeContext<'a> {
                 program_id,
                 &self.rent,
                 track_writable_deescalation,
+                upgradeable_loader_extend_program,
                 caller_privileges,
             ),
             None => Err(InstructionError::GenericError), // Should never happen
@@ -346,6 +364,20 @@ impl<'a> InvokeContext for ThisInvokeContext<'a> {
             }
         })
     }
+    fn set_return_data(&mut self, return_data: Option<TransactionReturnData>) {
+        self.return_data = return_data;
+    }
+    fn get_return_data(&self) -> Option<&TransactionReturnData> {
+        self.return_data.as_ref()
+    }
+    fn is_bpf_tracing_enabled(&self) -> bool {
+        self.bpf_trace_collector.is_some()
+    }
+    fn record_bpf_trace(&self, trace: BpfTrace) {
+        if let Some(bpf_trace_collector) = &self.bpf_trace_collector {
+            bpf_trace_collector.record(trace);
+        }
+    }
 }
 pub struct ThisLogger {
     log_collector: Option<Rc<LogCollector>>,
@@ -770,6 +802,11 @@ impl MessageProcessor {
 
             // Invoke callee
             invoke_context.push(program_id)?;
+            if invoke_context.is_feature_active(&return_data_syscall_enabled::id()) {
+                // The caller's return data must not be visible to it as the callee's, whatever
+                // loader the callee runs on
+                invoke_context.set_return_data(None);
+            }
 
             let mut message_processor = MessageProcessor::default();
             for (program_id, process_instruction) in invoke_context.get_programs().iter() {
@@ -836,6 +873,7 @@ impl MessageProcessor {
         executable_accounts: &[(Pubkey, RefCell<Account>)],
         accounts: &[Rc<RefCell<Account>>],
         rent: &Rent,
+        upgradeable_loader_extend_program: bool,
     ) -> Result<(), InstructionError> {
         // Verify all executable accounts have zero outstanding refs
         Self::verify_account_references(executable_accounts)?;
@@ -854,6 +892,7 @@ impl MessageProcessor {
                     Some(message.is_writable(account_index)),
                     rent,
                     &account,
+                    upgradeable_loader_extend_program,
                 )?;
                 pre_sum += u128::from(pre_accounts[unique_index].lamports());
                 post_sum += u128::from(account.lamports);
@@ -878,6 +917,7 @@ impl MessageProcessor {
         program_id: &Pubkey,
         rent: &Rent,
         track_writable_deescalation: bool,
+        upgradeable_loader_extend_program: bool,
         caller_privileges: Option<&[bool]>,
     ) -> Result<(), InstructionError> {
         // Verify the per-account instruction results
@@ -903,7 +943,13 @@ impl MessageProcessor {
                             .try_borrow_mut()
                             .map_err(|_| InstructionError::AccountBorrowOutstanding)?;
 
-                        pre_account.verify(&program_id, is_writable, &rent, &account)?;
+                        pre_account.verify(
+                            &program_id,
+                            is_writable,
+                            &rent,
+                            &account,
+                            upgradeable_loader_extend_program,
+                        )?;
                         pre_sum += u128::from(pre_account.lamports());
                         post_sum += u128::from(account.lamports);
 
@@ -941,9 +987,12 @@ impl MessageProcessor {
         log_collector: Option<Rc<LogCollector>>,
         executors: Rc<RefCell<Executors>>,
         instruction_recorder: Option<InstructionRecorder>,
+        bpf_trace_collector: Option<Rc<BpfTraceCollector>>,
         instruction_index: usize,
         feature_set: Arc<FeatureSet>,
         bpf_compute_budget: BpfComputeBudget,
+        compute_meter: Option<Rc<RefCell<dyn ComputeMeter>>>,
+        return_data: &mut Option<TransactionReturnData>,
     ) -> Result<(), InstructionError> {
         // Fixup the special instructions key if present
         // before the account pre-values are taken care of
@@ -972,16 +1021,23 @@ impl MessageProcessor {
             bpf_compute_budget,
             executors,
             instruction_recorder,
+            bpf_trace_collector,
             feature_set,
         );
+        invoke_context.return_data = return_data.take();
+        if let Some(compute_meter) = compute_meter {
+            invoke_context.compute_meter = compute_meter;
+        }
         let keyed_accounts =
             Self::create_keyed_accounts(message, instruction, executable_accounts, accounts);
-        self.process_instruction(
+        let result = self.process_instruction(
             program_id,
             &keyed_accounts,
             &instruction.data,
             &mut invoke_context,
-        )?;
+        );
+        *return_data = invoke_context.return_data.take();
+        result?;
         Self::verify(
             message,
             instruction,
@@ -989,6 +1045,7 @@ impl MessageProcessor {
             executable_accounts,
             accounts,
             &rent_collector.rent,
+            invoke_context.is_feature_active(&upgradeable_loader_extend_program::id()),
         )?;
         Ok(())
     }
@@ -996,6 +1053,11 @@ impl MessageProcessor {
     /// Process a message.
     /// This method calls each instruction in the message over the set of loaded Accounts
     /// The accounts are committed back to the bank only if every instruction succeeds
+    /// The last return data set by any invoked program is left in `return_data`
+    /// A compute unit limit requested through the compute budget program replaces the
+    /// per-instruction budget with a single meter shared by every instruction
+    /// The compute units consumed by the instructions that ran are added to `executed_units`
+    /// The execution traces of the BPF programs are recorded in `bpf_trace_collector` if present
     #[allow(clippy::too_many_arguments)]
     pub fn process_message(
         &self,
@@ -1007,14 +1069,38 @@ impl MessageProcessor {
         log_collector: Option<Rc<LogCollector>>,
         executors: Rc<RefCell<Executors>>,
         instruction_recorders: Option<&[InstructionRecorder]>,
+        bpf_trace_collector: Option<Rc<BpfTraceCollector>>,
         feature_set: Arc<FeatureSet>,
         bpf_compute_budget: BpfComputeBudget,
+        return_data: &mut Option<TransactionReturnData>,
+        executed_units: &mut u64,
     ) -> Result<(), TransactionError> {
+        let mut bpf_compute_budget = bpf_compute_budget;
+        let mut compute_meter = None;
+        if feature_set.is_active(&compute_budget_program::id()) {
+            let compute_budget_request = ComputeBudgetRequest::try_from_message(message)
+                .map_err(|(index, err)| TransactionError::InstructionError(index, err))?;
+            if let Some(units) = compute_budget_request.compute_unit_limit {
+                bpf_compute_budget.max_units = u64::from(units);
+                let meter: Rc<RefCell<dyn ComputeMeter>> =
+                    Rc::new(RefCell::new(ThisComputeMeter {
+                        remaining: bpf_compute_budget.max_units,
+                    }));
+                compute_meter = Some(meter);
+            }
+        }
+
         for (instruction_index, instruction) in message.instructions.iter().enumerate() {
             let instruction_recorder = instruction_recorders
                 .as_ref()
                 .map(|recorders| recorders[instruction_index].clone());
-            self.execute_instruction(
+            let instruction_compute_meter = compute_meter.clone().unwrap_or_else(|| {
+                Rc::new(RefCell::new(ThisComputeMeter {
+                    remaining: bpf_compute_budget.max_units,
+                }))
+            });
+            let remaining = instruction_compute_meter.borrow().get_remaining();
+            let result = self.execute_instruction(
                 message,
                 instruction,
                 &loaders[instruction_index],
@@ -1024,11 +1110,18 @@ impl MessageProcessor {
                 log_collector.clone(),
                 executors.clone(),
                 instruction_recorder,
+                bpf_trace_collector.clone(),
                 instruction_index,
                 feature_set.clone(),
                 bpf_compute_budget,
-            )
-            .map_err(|err| TransactionError::InstructionError(instruction_index as u8, err))?;
+                Some(instruction_compute_meter.clone()),
+                return_data,
+            );
+            *executed_units = executed_units.saturating_add(
+                remaining.saturating_sub(instruction_compute_meter.borrow().get_remaining()),
+            );
+            result
+                .map_err(|err| TransactionError::InstructionError(instruction_index as u8, err))?;
         }
         Ok(())
     }
@@ -1038,6 +1131,7 @@ impl MessageProcessor {
 mod tests {
     use super::*;
     use solana_sdk::{
+        compute_budget::ComputeBudgetInstruction,
         instruction::{AccountMeta, Instruction, InstructionError},
         message::Message,
         native_loader::create_loadable_account,
@@ -1075,6 +1169,7 @@ mod tests {
             BpfComputeBudget::default(),
             Rc::new(RefCell::new(Executors::default())),
             None,
+            None,
             Arc::new(FeatureSet::all_enabled()),
         );
 
@@ -1189,6 +1284,7 @@ mod tests {
         rent: Rent,
         pre: PreAccount,
         post: Account,
+        upgradeable_loader_extend_program: bool,
     }
     impl Change {
         pub fn new(owner: &Pubkey, program_id: &Pubkey) -> Self {
@@ -1211,6 +1307,7 @@ mod tests {
                     lamports: std::u64::MAX,
                     ..Account::default()
                 },
+                upgradeable_loader_extend_program: false,
             }
         }
         pub fn read_only(mut self) -> Self {
@@ -1241,12 +1338,17 @@ mod tests {
             self.post.rent_epoch = post;
             self
         }
+        pub fn upgradeable_loader_extend_program(mut self) -> Self {
+            self.upgradeable_loader_extend_program = true;
+            self
+        }
         pub fn verify(&self) -> Result<(), InstructionError> {
             self.pre.verify(
                 &self.program_id,
                 Some(self.is_writable),
                 &self.rent,
                 &self.post,
+                self.upgradeable_loader_extend_program,
             )
         }
     }
@@ -1438,6 +1540,37 @@ mod tests {
         Err(InstructionError::AccountDataSizeChanged),
         "system program should not be able to change the data length of accounts it does not own"
         );
+        assert_eq!(
+            Change::new(&bpf_loader_upgradeable::id(), &bpf_loader_upgradeable::id())
+                .data(vec![0], vec![0, 0])
+                .verify(),
+            Err(InstructionError::AccountDataSizeChanged),
+            "upgradeable loader should not be able to change the data len before extend is enabled"
+        );
+        assert_eq!(
+            Change::new(&bpf_loader_upgradeable::id(), &bpf_loader_upgradeable::id())
+                .data(vec![0], vec![0, 0])
+                .upgradeable_loader_extend_program()
+                .verify(),
+            Ok(()),
+            "upgradeable loader should be able to grow the data len of accounts it owns"
+        );
+        assert_eq!(
+            Change::new(&bpf_loader_upgradeable::id(), &bpf_loader_upgradeable::id())
+                .data(vec![0, 0], vec![0])
+                .upgradeable_loader_extend_program()
+                .verify(),
+            Err(InstructionError::AccountDataSizeChanged),
+            "upgradeable loader should not be able to shrink the data len"
+        );
+        assert_eq!(
+            Change::new(&alice_program_id, &bpf_loader_upgradeable::id())
+                .data(vec![0], vec![0, 0])
+                .upgradeable_loader_extend_program()
+                .verify(),
+            Err(InstructionError::AccountDataSizeChanged),
+            "upgradeable loader should not be able to grow accounts it does not own"
+        );
     }
 
     #[test]
@@ -1644,8 +1777,11 @@ mod tests {
             None,
             executors.clone(),
             None,
+            None,
             Arc::new(FeatureSet::all_enabled()),
             BpfComputeBudget::new(),
+            &mut None,
+            &mut 0,
         );
         assert_eq!(result, Ok(()));
         assert_eq!(accounts[0].borrow().lamports, 100);
@@ -1669,8 +1805,11 @@ mod tests {
             None,
             executors.clone(),
             None,
+            None,
             Arc::new(FeatureSet::all_enabled()),
             BpfComputeBudget::new(),
+            &mut None,
+            &mut 0,
         );
         assert_eq!(
             result,
@@ -1698,8 +1837,11 @@ mod tests {
             None,
             executors,
             None,
+            None,
             Arc::new(FeatureSet::all_enabled()),
             BpfComputeBudget::new(),
+            &mut None,
+            &mut 0,
         );
         assert_eq!(
             result,
@@ -1811,8 +1953,11 @@ mod tests {
             None,
             executors.clone(),
             None,
+            None,
             Arc::new(FeatureSet::all_enabled()),
             BpfComputeBudget::new(),
+            &mut None,
+            &mut 0,
         );
         assert_eq!(
             result,
@@ -1840,8 +1985,11 @@ mod tests {
             None,
             executors.clone(),
             None,
+            None,
             Arc::new(FeatureSet::all_enabled()),
             BpfComputeBudget::new(),
+            &mut None,
+            &mut 0,
         );
         assert_eq!(result, Ok(()));
 
@@ -1866,8 +2014,11 @@ mod tests {
             None,
             executors,
             None,
+            None,
             Arc::new(FeatureSet::all_enabled()),
             BpfComputeBudget::new(),
+            &mut None,
+            &mut 0,
         );
         assert_eq!(result, Ok(()));
         assert_eq!(accounts[0].borrow().lamports, 80);
@@ -1875,6 +2026,139 @@ mod tests {
         assert_eq!(accounts[0].borrow().data, vec![42]);
     }
 
+    #[test]
+    fn test_process_message_compute_unit_limit() {
+        fn mock_consume_process_instruction(
+            _program_id: &Pubkey,
+            _keyed_accounts: &[KeyedAccount],
+            data: &[u8],
+            invoke_context: &mut dyn InvokeContext,
+        ) -> Result<(), InstructionError> {
+            let units: u64 =
+                bincode::deserialize(data).map_err(|_| InstructionError::InvalidInstructionData)?;
+            invoke_context
+                .get_compute_meter()
+                .borrow_mut()
+                .consume(units)
+        }
+
+        let mock_program_id = Pubkey::new(&[2u8; 32]);
+        let rent_collector = RentCollector::default();
+        let mut message_processor = MessageProcessor::default();
+        message_processor.add_program(mock_program_id, mock_consume_process_instruction);
+        message_processor.add_program(
+            solana_sdk::compute_budget::id(),
+            solana_compute_budget_program::process_instruction,
+        );
+
+        let payer = solana_sdk::pubkey::new_rand();
+        let executed_units = std::cell::Cell::new(0);
+        let process_message = |instructions: &[Instruction], feature_set: FeatureSet| {
+            let message = Message::new(instructions, Some(&payer));
+            let accounts: Vec<Rc<RefCell<Account>>> = message
+                .account_keys
+                .iter()
+                .map(|_| Account::new_ref(100, 0, &mock_program_id))
+                .collect();
+            let loaders: Vec<Vec<(Pubkey, RefCell<Account>)>> = message
+                .instructions
+                .iter()
+                .map(|instruction| {
+                    let program_id = *instruction.program_id(&message.account_keys);
+                    let account = RefCell::new(create_loadable_account("mock_program", 1));
+                    vec![(program_id, account)]
+                })
+                .collect();
+            let mut units = 0;
+            let result = message_processor.process_message(
+                &message,
+                &loaders,
+                &accounts,
+                &[],
+                &rent_collector,
+                None,
+                Rc::new(RefCell::new(Executors::default())),
+                None,
+                None,
+                Arc::new(feature_set),
+                BpfComputeBudget::new(),
+                &mut None,
+                &mut units,
+            );
+            executed_units.set(units);
+            result
+        };
+        let consume = |units: u64| Instruction::new(mock_program_id, &units, vec![]);
+
+        // Without a requested limit, every instruction has its own budget
+        let default_units = BpfComputeBudget::new().max_units;
+        assert_eq!(
+            process_message(
+                &[consume(default_units), consume(default_units)],
+                FeatureSet::all_enabled()
+            ),
+            Ok(())
+        );
+        assert_eq!(executed_units.get(), default_units * 2);
+
+        // A requested limit is shared by all instructions of the message
+        let instructions = [
+            ComputeBudgetInstruction::set_compute_unit_limit(150),
+            consume(100),
+            consume(100),
+        ];
+        assert_eq!(
+            process_message(&instructions, FeatureSet::all_enabled()),
+            Err(TransactionError::InstructionError(
+                2,
+                InstructionError::ComputationalBudgetExceeded
+            ))
+        );
+        // Units consumed up to the failure are counted
+        assert_eq!(executed_units.get(), 150);
+        assert_eq!(
+            process_message(
+                &[
+                    ComputeBudgetInstruction::set_compute_unit_limit(default_units as u32 * 2),
+                    consume(default_units + 1),
+                ],
+                FeatureSet::all_enabled()
+            ),
+            Ok(())
+        );
+
+        // Requests are ignored until the compute budget program is enabled
+        let mut feature_set = FeatureSet::all_enabled();
+        feature_set.active.remove(&compute_budget_program::id());
+        feature_set.inactive.insert(compute_budget_program::id());
+        assert_eq!(
+            process_message(
+                &[
+                    ComputeBudgetInstruction::set_compute_unit_limit(150),
+                    consume(100),
+                    consume(100),
+                ],
+                feature_set
+            ),
+            Ok(())
+        );
+
+        // Duplicate requests fail the message
+        assert_eq!(
+            process_message(
+                &[
+                    ComputeBudgetInstruction::set_compute_unit_limit(150),
+                    ComputeBudgetInstruction::set_compute_unit_limit(150),
+                ],
+                FeatureSet::all_enabled()
+            ),
+            Err(TransactionError::InstructionError(
+                1,
+                InstructionError::InvalidInstructionData
+            ))
+        );
+    }
+
     #[test]
     fn test_process_cross_program() {
         #[derive(Debug, Serialize, Deserialize)]
@@ -1952,6 +2236,7 @@ mod tests {
             BpfComputeBudget::default(),
             Rc::new(RefCell::new(Executors::default())),
             None,
+            None,
             Arc::new(FeatureSet::all_enabled()),
         );
         let metas = vec![
@@ -2018,6 +2303,32 @@ mod tests {
                 case.1
             );
         }
+
+        // Return data left by the caller is cleared before a native callee runs
+        accounts[1].borrow_mut().data[0] = 0;
+        invoke_context.set_return_data(Some(TransactionReturnData {
+            program_id: caller_program_id,
+            data: vec![1, 2, 3],
+        }));
+        let instruction = Instruction::new(callee_program_id, &MockInstruction::NoopSuccess, metas);
+        let message = Message::new(&[instruction], None);
+        let caller_privileges = message
+            .account_keys
+            .iter()
+            .enumerate()
+            .map(|(i, _)| message.is_writable(i))
+            .collect::<Vec<bool>>();
+        assert_eq!(
+            MessageProcessor::process_cross_program_instruction(
+                &message,
+                &executable_accounts,
+                &accounts,
+                &caller_privileges,
+                &mut invoke_context,
+            ),
+            Ok(())
+        );
+        assert_eq!(invoke_context.get_return_data(), None);
     }
 
     #[test]
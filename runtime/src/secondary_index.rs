@@ -259,7 +259,7 @@ impl<SecondaryIndexEntryType: SecondaryIndexEntry + Default + Sync + Send>
 
     // Note passing `None` is dangerous unless you're sure there's no other competing threads
     // writing updates to the index for this Pubkey at the same time!
-    pub fn remove_by_inner_key<'a, C>(&'a self, inner_key: &Pubkey, slots_to_remove: Option<&'a C>)
+    pub fn remove_by_inner_key<'a, C>(&self, inner_key: &Pubkey, slots_to_remove: Option<&'a C>)
     where
         C: Contains<'a, Slot>,
     {
@@ -437,6 +437,51 @@ static uint64_t sol_sha256(
     const uint8_t *result
 );
 
+/**
+ * Length of a Keccak256 hash result
+ */
+#define KECCAK_RESULT_LENGTH 32
+
+/**
+ * Keccak256
+ *
+ * @param bytes Array of byte arrays
+ * @param bytes_len Number of byte arrays
+ * @param result 32 byte array to hold the result
+ */
+static uint64_t sol_keccak256(
+    const SolBytes *bytes,
+    int bytes_len,
+    uint8_t *result
+);
+
+/**
+ * Length of a secp256k1 signature
+ */
+#define SECP256K1_SIGNATURE_LENGTH 64
+
+/**
+ * Length of a recovered secp256k1 public key
+ */
+#define SECP256K1_RECOVER_RESULT_LENGTH 64
+
+/**
+ * Recover a secp256k1 public key from a signed message
+ *
+ * @param hash 32 byte hash of the signed message
+ * @param recovery_id Recovery id of the signature (0-3)
+ * @param signature 64 byte signature
+ * @param result 64 byte array to hold the uncompressed public key
+ * @return 0 on success, otherwise 1 for an invalid hash, 2 for an invalid
+ *         recovery id or 3 for an invalid signature
+ */
+static uint64_t sol_secp256k1_recover(
+    const uint8_t *hash,
+    uint64_t recovery_id,
+    const uint8_t *signature,
+    uint8_t *result
+);
+
 /**
  * Account Meta
  */
@@ -567,6 +612,36 @@ static uint64_t sol_invoke(
 
 /**@}*/
 
+/**
+ * Return data
+ * @{
+ */
+
+/**
+ * Maximum size of return data
+ */
+#define MAX_RETURN_DATA 1024
+
+/**
+ * Set the return data
+ *
+ * @param bytes byte array to set
+ * @param bytes_len length of byte array. This may not exceed MAX_RETURN_DATA.
+ */
+void sol_set_return_data(const uint8_t *bytes, uint64_t bytes_len);
+
+/**
+ * Get the return data
+ *
+ * @param bytes byte buffer
+ * @param bytes_len maximum length of buffer
+ * @param program_id the program_id which set the return data. Only set if there was some return data (the function returns non-zero).
+ * @param result length of return data (may exceed bytes_len if the return data is longer)
+ */
+uint64_t sol_get_return_data(const uint8_t *bytes, uint64_t bytes_len, SolPubkey *program_id);
+
+/**@}*/
+
 /**
  * Debugging utilities
  * @{
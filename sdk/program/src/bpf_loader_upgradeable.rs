@@ -227,6 +227,63 @@ pub fn set_upgrade_authority(
     Instruction::new(id(), &UpgradeableLoaderInstruction::SetAuthority, metas)
 }
 
+/// Returns the instructions required to close a buffer account
+pub fn close_buffer(
+    buffer_address: &Pubkey,
+    recipient_address: &Pubkey,
+    authority_address: &Pubkey,
+) -> Instruction {
+    Instruction::new(
+        id(),
+        &UpgradeableLoaderInstruction::Close,
+        vec![
+            AccountMeta::new(*buffer_address, false),
+            AccountMeta::new(*recipient_address, false),
+            AccountMeta::new_readonly(*authority_address, true),
+        ],
+    )
+}
+
+/// Returns the instructions required to close a program, withdrawing the
+/// ProgramData account's lamports
+pub fn close_program(
+    program_address: &Pubkey,
+    recipient_address: &Pubkey,
+    authority_address: &Pubkey,
+) -> Instruction {
+    let (programdata_address, _) = Pubkey::find_program_address(&[program_address.as_ref()], &id());
+    Instruction::new(
+        id(),
+        &UpgradeableLoaderInstruction::Close,
+        vec![
+            AccountMeta::new(programdata_address, false),
+            AccountMeta::new(*recipient_address, false),
+            AccountMeta::new_readonly(*authority_address, true),
+            AccountMeta::new(*program_address, false),
+        ],
+    )
+}
+
+/// Returns the instruction required to extend a program's ProgramData account
+pub fn extend_program(
+    program_address: &Pubkey,
+    payer_address: &Pubkey,
+    additional_bytes: u32,
+) -> Instruction {
+    let (programdata_address, _) = Pubkey::find_program_address(&[program_address.as_ref()], &id());
+    Instruction::new(
+        id(),
+        &UpgradeableLoaderInstruction::ExtendProgram { additional_bytes },
+        vec![
+            AccountMeta::new(programdata_address, false),
+            AccountMeta::new(*program_address, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(crate::system_program::id(), false),
+            AccountMeta::new(*payer_address, true),
+        ],
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -287,5 +344,20 @@ mod tests {
                 &bincode::serialize(&UpgradeableLoaderInstruction::SetAuthority).unwrap()
             )
         );
+        assert_eq!(
+            false,
+            is_upgrade_instruction(
+                &bincode::serialize(&UpgradeableLoaderInstruction::Close).unwrap()
+            )
+        );
+        assert_eq!(
+            false,
+            is_upgrade_instruction(
+                &bincode::serialize(&UpgradeableLoaderInstruction::ExtendProgram {
+                    additional_bytes: 0,
+                })
+                .unwrap()
+            )
+        );
     }
 }
@@ -0,0 +1,202 @@
+//! The compute budget native program, whose instructions let a transaction
+//! request a transaction-wide compute unit limit and pay a per-compute-unit
+//! price for prioritized processing.
+
+use crate::{
+    instruction::{Instruction, InstructionError},
+    message::Message,
+};
+
+crate::declare_id!("ComputeBudget111111111111111111111111111111");
+
+/// Compute units available to each instruction of a transaction that does
+/// not request a transaction-wide limit
+pub const DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT: u32 = 200_000;
+
+/// Largest transaction-wide compute unit limit that may be requested,
+/// larger requests are capped to this value
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// Compute unit prices are denominated in micro-lamports
+pub const MICRO_LAMPORTS_PER_LAMPORT: u64 = 1_000_000;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum ComputeBudgetInstruction {
+    /// Request a transaction-wide compute unit limit. The limit is shared
+    /// by every instruction in the transaction instead of each instruction
+    /// receiving the default per-instruction budget.
+    ///
+    /// No account references
+    SetComputeUnitLimit(u32),
+
+    /// Set a compute unit price in micro-lamports, paying an additional
+    /// fee of `price * compute unit limit` for higher transaction priority.
+    ///
+    /// No account references
+    SetComputeUnitPrice(u64),
+}
+
+impl ComputeBudgetInstruction {
+    /// Create a `SetComputeUnitLimit` `Instruction`
+    pub fn set_compute_unit_limit(units: u32) -> Instruction {
+        Instruction::new(
+            id(),
+            &ComputeBudgetInstruction::SetComputeUnitLimit(units),
+            vec![],
+        )
+    }
+
+    /// Create a `SetComputeUnitPrice` `Instruction`
+    pub fn set_compute_unit_price(micro_lamports: u64) -> Instruction {
+        Instruction::new(
+            id(),
+            &ComputeBudgetInstruction::SetComputeUnitPrice(micro_lamports),
+            vec![],
+        )
+    }
+}
+
+/// The compute budget requested by the compute budget instructions of a
+/// message
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ComputeBudgetRequest {
+    /// Transaction-wide compute unit limit, capped to `MAX_COMPUTE_UNIT_LIMIT`
+    pub compute_unit_limit: Option<u32>,
+    /// Price of each compute unit in micro-lamports
+    pub compute_unit_price: u64,
+    /// Number of instructions not addressed to the compute budget program
+    num_budgeted_instructions: u32,
+}
+
+impl ComputeBudgetRequest {
+    /// Collect the compute budget instructions of `message`. On failure,
+    /// returns the index of the offending instruction and its error; each
+    /// compute budget instruction may only appear once per message.
+    pub fn try_from_message(message: &Message) -> Result<Self, (u8, InstructionError)> {
+        let mut request = Self::default();
+        let mut requested_price = false;
+        for (index, instruction) in message.instructions.iter().enumerate() {
+            let is_compute_budget_instruction = message
+                .account_keys
+                .get(usize::from(instruction.program_id_index))
+                .map(check_id)
+                .unwrap_or(false);
+            if !is_compute_budget_instruction {
+                request.num_budgeted_instructions =
+                    request.num_budgeted_instructions.saturating_add(1);
+                continue;
+            }
+
+            let invalid_instruction_data = (index as u8, InstructionError::InvalidInstructionData);
+            match bincode::deserialize(&instruction.data) {
+                Ok(ComputeBudgetInstruction::SetComputeUnitLimit(units))
+                    if request.compute_unit_limit.is_none() =>
+                {
+                    request.compute_unit_limit = Some(units.min(MAX_COMPUTE_UNIT_LIMIT));
+                }
+                Ok(ComputeBudgetInstruction::SetComputeUnitPrice(micro_lamports))
+                    if !requested_price =>
+                {
+                    request.compute_unit_price = micro_lamports;
+                    requested_price = true;
+                }
+                _ => return Err(invalid_instruction_data),
+            }
+        }
+        Ok(request)
+    }
+
+    /// Number of compute units the transaction may consume, which is the
+    /// requested limit or else the default limit of every instruction
+    pub fn compute_units(&self) -> u64 {
+        match self.compute_unit_limit {
+            Some(units) => u64::from(units),
+            None => u64::from(DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT)
+                .saturating_mul(u64::from(self.num_budgeted_instructions)),
+        }
+    }
+
+    /// Additional fee in lamports paid for the requested priority, rounded up
+    pub fn priority_fee(&self) -> u64 {
+        let micro_lamports =
+            u128::from(self.compute_unit_price).saturating_mul(u128::from(self.compute_units()));
+        let lamports = micro_lamports.saturating_add(u128::from(MICRO_LAMPORTS_PER_LAMPORT - 1))
+            / u128::from(MICRO_LAMPORTS_PER_LAMPORT);
+        lamports.min(u128::from(u64::MAX)) as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{pubkey::Pubkey, system_instruction};
+
+    fn request(
+        instructions: &[Instruction],
+    ) -> Result<ComputeBudgetRequest, (u8, InstructionError)> {
+        let payer = Pubkey::new_unique();
+        ComputeBudgetRequest::try_from_message(&Message::new(instructions, Some(&payer)))
+    }
+
+    #[test]
+    fn test_try_from_message() {
+        let transfer =
+            system_instruction::transfer(&Pubkey::new_unique(), &Pubkey::new_unique(), 1);
+        let default_request = request(&[transfer.clone()]).unwrap();
+        assert_eq!(default_request.compute_unit_limit, None);
+        assert_eq!(default_request.compute_unit_price, 0);
+        assert_eq!(
+            default_request.compute_units(),
+            u64::from(DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT)
+        );
+        assert_eq!(default_request.priority_fee(), 0);
+
+        let request = request(&[
+            ComputeBudgetInstruction::set_compute_unit_limit(MAX_COMPUTE_UNIT_LIMIT + 1),
+            ComputeBudgetInstruction::set_compute_unit_price(1),
+            transfer,
+        ])
+        .unwrap();
+        assert_eq!(request.compute_unit_limit, Some(MAX_COMPUTE_UNIT_LIMIT));
+        assert_eq!(request.compute_unit_price, 1);
+        assert_eq!(request.compute_units(), u64::from(MAX_COMPUTE_UNIT_LIMIT));
+        // 1.4 lamports, rounded up
+        assert_eq!(request.priority_fee(), 2);
+    }
+
+    #[test]
+    fn test_try_from_message_invalid() {
+        assert_eq!(
+            request(&[
+                ComputeBudgetInstruction::set_compute_unit_price(1),
+                ComputeBudgetInstruction::set_compute_unit_price(2),
+            ]),
+            Err((1, InstructionError::InvalidInstructionData))
+        );
+        assert_eq!(
+            request(&[
+                ComputeBudgetInstruction::set_compute_unit_limit(1),
+                ComputeBudgetInstruction::set_compute_unit_limit(1),
+            ]),
+            Err((1, InstructionError::InvalidInstructionData))
+        );
+        assert_eq!(
+            request(&[Instruction {
+                program_id: id(),
+                accounts: vec![],
+                data: vec![],
+            }]),
+            Err((0, InstructionError::InvalidInstructionData))
+        );
+    }
+
+    #[test]
+    fn test_priority_fee_saturates() {
+        let request = request(&[
+            ComputeBudgetInstruction::set_compute_unit_limit(MAX_COMPUTE_UNIT_LIMIT),
+            ComputeBudgetInstruction::set_compute_unit_price(u64::MAX),
+        ])
+        .unwrap();
+        assert_eq!(request.priority_fee(), u64::MAX);
+    }
+}
@@ -1,4 +1,5 @@
 use crate::clock::DEFAULT_MS_PER_SLOT;
+use crate::compute_budget::ComputeBudgetRequest;
 use crate::message::Message;
 use crate::secp256k1_program;
 use log::*;
@@ -21,12 +22,14 @@ impl Default for FeeCalculator {
 
 pub struct FeeConfig {
     pub secp256k1_program_enabled: bool,
+    pub compute_budget_program_enabled: bool,
 }
 
 impl Default for FeeConfig {
     fn default() -> Self {
         Self {
             secp256k1_program_enabled: true,
+            compute_budget_program_enabled: true,
         }
     }
 }
@@ -57,8 +60,19 @@ impl FeeCalculator {
             }
         }
 
-        self.lamports_per_signature
-            * (u64::from(message.header.num_required_signatures) + num_secp256k1_signatures)
+        // An invalid compute budget request fails the transaction when it is
+        // processed, so only the signature fee is charged for it
+        let priority_fee = if fee_config.compute_budget_program_enabled {
+            ComputeBudgetRequest::try_from_message(message)
+                .map(|request| request.priority_fee())
+                .unwrap_or(0)
+        } else {
+            0
+        };
+
+        (self.lamports_per_signature
+            * (u64::from(message.header.num_required_signatures) + num_secp256k1_signatures))
+            .saturating_add(priority_fee)
     }
 }
 
@@ -262,7 +276,8 @@ mod tests {
             FeeCalculator::new(1).calculate_fee_with_config(
                 &message,
                 &FeeConfig {
-                    secp256k1_program_enabled: false
+                    secp256k1_program_enabled: false,
+                    ..FeeConfig::default()
                 }
             ),
             1
@@ -274,6 +289,55 @@ mod tests {
         assert_eq!(FeeCalculator::new(1).calculate_fee(&message), 11);
     }
 
+    #[test]
+    fn test_fee_calculator_calculate_fee_compute_budget() {
+        use crate::compute_budget::ComputeBudgetInstruction;
+        let pubkey0 = Pubkey::new(&[0; 32]);
+        let pubkey1 = Pubkey::new(&[1; 32]);
+        let ix0 = system_instruction::transfer(&pubkey0, &pubkey1, 1);
+
+        // 1_000 micro-lamports for each of 200_000 default compute units
+        let message = Message::new(
+            &[
+                ComputeBudgetInstruction::set_compute_unit_price(1_000),
+                ix0.clone(),
+            ],
+            Some(&pubkey0),
+        );
+        assert_eq!(FeeCalculator::new(1).calculate_fee(&message), 201);
+        assert_eq!(
+            FeeCalculator::new(1).calculate_fee_with_config(
+                &message,
+                &FeeConfig {
+                    compute_budget_program_enabled: false,
+                    ..FeeConfig::default()
+                }
+            ),
+            1
+        );
+
+        let message = Message::new(
+            &[
+                ComputeBudgetInstruction::set_compute_unit_limit(1_000),
+                ComputeBudgetInstruction::set_compute_unit_price(1_000),
+                ix0.clone(),
+            ],
+            Some(&pubkey0),
+        );
+        assert_eq!(FeeCalculator::new(1).calculate_fee(&message), 2);
+
+        // invalid requests are only charged the signature fee
+        let message = Message::new(
+            &[
+                ComputeBudgetInstruction::set_compute_unit_price(1_000),
+                ComputeBudgetInstruction::set_compute_unit_price(1_000),
+                ix0,
+            ],
+            Some(&pubkey0),
+        );
+        assert_eq!(FeeCalculator::new(1).calculate_fee(&message), 1);
+    }
+
     #[test]
     fn test_fee_rate_governor_derived_default() {
         solana_logger::setup();
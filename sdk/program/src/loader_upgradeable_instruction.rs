@@ -107,4 +107,40 @@ pub enum UpgradeableLoaderInstruction {
     ///   2. `[]` The new authority, optional, if omitted then the program will
     ///      not be upgradeable.
     SetAuthority,
+
+    /// Close an account owned by the upgradeable loader and withdraw all of
+    /// its lamports.
+    ///
+    /// Uninitialized accounts may be closed without an authority.  Buffer
+    /// accounts require the buffer authority and ProgramData accounts require
+    /// the program's upgrade authority.  Closing a ProgramData account leaves
+    /// the associated Program account unusable.
+    ///
+    /// # Account references
+    ///   0. `[writable]` The account to close, Uninitialized, Buffer or
+    ///      ProgramData.
+    ///   1. `[writable]` The account to deposit the closed account's lamports.
+    ///   2. `[signer]` The account's authority, optional, required for
+    ///      initialized accounts.
+    ///   3. `[writable]` The associated Program account if the account to close
+    ///      is a ProgramData account.
+    Close,
+
+    /// Extend a program's ProgramData account by the specified number of bytes.
+    ///
+    /// Allows a program to be upgraded to a length larger than the
+    /// `max_data_len` it was deployed with.  The payer funds any additional
+    /// lamports required to keep the ProgramData account rent-exempt.  Only
+    /// upgradeable programs can be extended.
+    ///
+    /// # Account references
+    ///   0. `[writable]` The ProgramData account.
+    ///   1. `[writable]` The ProgramData account's associated Program account.
+    ///   2. `[]` Rent sysvar.
+    ///   3. `[]` System program (`solana_sdk::system_program::id()`).
+    ///   4. `[writable, signer]` The payer account.
+    ExtendProgram {
+        /// Number of bytes to extend the program data.
+        additional_bytes: u32,
+    },
 }
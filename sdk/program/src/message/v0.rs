@@ -0,0 +1,371 @@
+//! Version 0 message format, which can load additional accounts through
+//! on-chain address lookup tables
+
+use crate::{
+    hash::Hash,
+    instruction::CompiledInstruction,
+    message::{Message as LegacyMessage, MessageHeader},
+    pubkey::Pubkey,
+    sanitize::{Sanitize, SanitizeError},
+    short_vec,
+};
+use std::convert::TryFrom;
+
+/// Maximum number of accounts, static and loaded, that a message may reference
+pub const MAX_ACCOUNT_KEYS: usize = 256;
+
+/// Address table lookups describe an on-chain address lookup table to use
+/// for loading more readonly and writable accounts in a single transaction
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq, Clone, AbiExample)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageAddressTableLookup {
+    /// Address of the lookup table account
+    pub account_key: Pubkey,
+    /// List of indexes used to load writable account addresses
+    #[serde(with = "short_vec")]
+    pub writable_indexes: Vec<u8>,
+    /// List of indexes used to load readonly account addresses
+    #[serde(with = "short_vec")]
+    pub readonly_indexes: Vec<u8>,
+}
+
+/// Collection of addresses loaded from on-chain lookup tables, split by
+/// their lock type
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq, Clone, AbiExample)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadedAddresses {
+    /// List of addresses for writable loaded accounts
+    pub writable: Vec<Pubkey>,
+    /// List of addresses for readonly loaded accounts
+    pub readonly: Vec<Pubkey>,
+}
+
+impl LoadedAddresses {
+    pub fn len(&self) -> usize {
+        self.writable.len().saturating_add(self.readonly.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// A transaction message which can reference accounts that are not listed
+/// in `account_keys` by loading them from address lookup tables.
+///
+/// Instruction account indexes first address the static `account_keys`,
+/// then all writable addresses loaded through `address_table_lookups`, and
+/// finally all readonly addresses loaded through `address_table_lookups`.
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq, Clone, AbiExample)]
+#[serde(rename_all = "camelCase")]
+pub struct Message {
+    /// The message header, identifying signed and read-only `account_keys`
+    /// NOTE: Serialization-related changes must be paired with the direct read at sigverify.
+    pub header: MessageHeader,
+
+    /// List of accounts loaded by this transaction, excluding those loaded
+    /// from lookup tables
+    #[serde(with = "short_vec")]
+    pub account_keys: Vec<Pubkey>,
+
+    /// The id of a recent ledger entry.
+    pub recent_blockhash: Hash,
+
+    /// Programs that will be executed in sequence and committed in one atomic transaction if all
+    /// succeed.
+    #[serde(with = "short_vec")]
+    pub instructions: Vec<CompiledInstruction>,
+
+    /// List of address table lookups used to load additional accounts
+    /// for this transaction
+    #[serde(with = "short_vec")]
+    pub address_table_lookups: Vec<MessageAddressTableLookup>,
+}
+
+impl Sanitize for Message {
+    fn sanitize(&self) -> Result<(), SanitizeError> {
+        let num_static_account_keys = self.account_keys.len();
+
+        // signing area and read-only non-signing area should not overlap
+        if usize::from(self.header.num_required_signatures)
+            .saturating_add(usize::from(self.header.num_readonly_unsigned_accounts))
+            > num_static_account_keys
+        {
+            return Err(SanitizeError::IndexOutOfBounds);
+        }
+
+        // there should be at least 1 RW fee-payer account.
+        if self.header.num_readonly_signed_accounts >= self.header.num_required_signatures {
+            return Err(SanitizeError::IndexOutOfBounds);
+        }
+
+        let mut num_loaded_accounts: usize = 0;
+        for lookup in &self.address_table_lookups {
+            let num_lookup_indexes = lookup
+                .writable_indexes
+                .len()
+                .saturating_add(lookup.readonly_indexes.len());
+
+            // each lookup table must be used to load at least one account
+            if num_lookup_indexes == 0 {
+                return Err(SanitizeError::InvalidValue);
+            }
+
+            num_loaded_accounts = num_loaded_accounts.saturating_add(num_lookup_indexes);
+        }
+
+        // the combined number of static and loaded accounts must be addressable
+        // by a u8 instruction account index
+        let total_account_keys = num_static_account_keys.saturating_add(num_loaded_accounts);
+        if total_account_keys > MAX_ACCOUNT_KEYS {
+            return Err(SanitizeError::IndexOutOfBounds);
+        }
+
+        for ci in &self.instructions {
+            // programs cannot be loaded from lookup tables
+            if usize::from(ci.program_id_index) >= num_static_account_keys {
+                return Err(SanitizeError::IndexOutOfBounds);
+            }
+            // A program cannot be a payer.
+            if ci.program_id_index == 0 {
+                return Err(SanitizeError::IndexOutOfBounds);
+            }
+            for ai in &ci.accounts {
+                if usize::from(*ai) >= total_account_keys {
+                    return Err(SanitizeError::IndexOutOfBounds);
+                }
+            }
+        }
+        self.account_keys.sanitize()?;
+        self.recent_blockhash.sanitize()?;
+        self.instructions.sanitize()?;
+        Ok(())
+    }
+}
+
+impl Message {
+    /// Serialize this message with a version prefix, producing the bytes
+    /// that signers must sign
+    pub fn serialize(&self) -> Vec<u8> {
+        bincode::serialize(&(super::MESSAGE_VERSION_PREFIX, self)).unwrap()
+    }
+
+    /// Returns the number of writable and readonly addresses that will be
+    /// loaded through `address_table_lookups`
+    pub fn num_lookup_addresses(&self) -> (usize, usize) {
+        self.address_table_lookups
+            .iter()
+            .fold((0, 0), |(writable, readonly), lookup| {
+                (
+                    writable + lookup.writable_indexes.len(),
+                    readonly + lookup.readonly_indexes.len(),
+                )
+            })
+    }
+
+    /// Combine this message with the addresses loaded for its lookups into
+    /// an equivalent legacy message that the runtime can execute.
+    ///
+    /// Account keys are ordered as static signed keys, static unsigned
+    /// writable keys, loaded writable keys, static unsigned readonly keys and
+    /// finally loaded readonly keys; instruction account indexes are remapped
+    /// to match. Returns `None` if `loaded_addresses` does not match the
+    /// number of addresses requested by `address_table_lookups`.
+    pub fn try_into_legacy_message(
+        self,
+        loaded_addresses: LoadedAddresses,
+    ) -> Option<LegacyMessage> {
+        let (num_writable_lookups, num_readonly_lookups) = self.num_lookup_addresses();
+        if loaded_addresses.writable.len() != num_writable_lookups
+            || loaded_addresses.readonly.len() != num_readonly_lookups
+        {
+            return None;
+        }
+
+        let num_static_account_keys = self.account_keys.len();
+        let num_readonly_unsigned_accounts =
+            usize::from(self.header.num_readonly_unsigned_accounts);
+        let num_loaded_writable = loaded_addresses.writable.len();
+        let readonly_unsigned_start =
+            num_static_account_keys.checked_sub(num_readonly_unsigned_accounts)?;
+
+        let map_index = |index: u8| -> u8 {
+            let index = usize::from(index);
+            let mapped = if index < readonly_unsigned_start {
+                index
+            } else if index < num_static_account_keys {
+                index + num_loaded_writable
+            } else if index < num_static_account_keys + num_loaded_writable {
+                index - num_readonly_unsigned_accounts
+            } else {
+                index
+            };
+            mapped as u8
+        };
+
+        let instructions = self
+            .instructions
+            .into_iter()
+            .map(|ci| CompiledInstruction {
+                program_id_index: map_index(ci.program_id_index),
+                accounts: ci.accounts.into_iter().map(map_index).collect(),
+                data: ci.data,
+            })
+            .collect();
+
+        let mut account_keys = self.account_keys;
+        let static_readonly_keys = account_keys.split_off(readonly_unsigned_start);
+        account_keys.extend(loaded_addresses.writable);
+        account_keys.extend(static_readonly_keys);
+        account_keys.extend(loaded_addresses.readonly);
+
+        let num_readonly_unsigned_accounts =
+            u8::try_from(num_readonly_unsigned_accounts + num_readonly_lookups).ok()?;
+        Some(LegacyMessage::new_with_compiled_instructions(
+            self.header.num_required_signatures,
+            self.header.num_readonly_signed_accounts,
+            num_readonly_unsigned_accounts,
+            account_keys,
+            self.recent_blockhash,
+            instructions,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn simple_message() -> Message {
+        Message {
+            header: MessageHeader {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 1,
+            },
+            account_keys: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+            address_table_lookups: vec![MessageAddressTableLookup {
+                account_key: Pubkey::new_unique(),
+                writable_indexes: vec![1],
+                readonly_indexes: vec![0],
+            }],
+            ..Message::default()
+        }
+    }
+
+    #[test]
+    fn test_sanitize() {
+        assert!(simple_message().sanitize().is_ok());
+    }
+
+    #[test]
+    fn test_sanitize_with_empty_lookup() {
+        let mut message = simple_message();
+        message.address_table_lookups[0].writable_indexes.clear();
+        message.address_table_lookups[0].readonly_indexes.clear();
+        assert_eq!(message.sanitize(), Err(SanitizeError::InvalidValue));
+    }
+
+    #[test]
+    fn test_sanitize_with_table_lookup_program_id() {
+        let mut message = simple_message();
+        message.instructions = vec![CompiledInstruction {
+            program_id_index: 2,
+            accounts: vec![],
+            data: vec![],
+        }];
+        assert_eq!(message.sanitize(), Err(SanitizeError::IndexOutOfBounds));
+    }
+
+    #[test]
+    fn test_sanitize_with_lookup_account_index() {
+        let mut message = simple_message();
+        message.instructions = vec![CompiledInstruction {
+            program_id_index: 1,
+            accounts: vec![0, 1, 2, 3],
+            data: vec![],
+        }];
+        assert!(message.sanitize().is_ok());
+
+        message.instructions[0].accounts.push(4);
+        assert_eq!(message.sanitize(), Err(SanitizeError::IndexOutOfBounds));
+    }
+
+    #[test]
+    fn test_sanitize_with_max_account_keys() {
+        let mut message = simple_message();
+        message.address_table_lookups[0].writable_indexes = (0..=252).collect();
+        assert!(message.sanitize().is_ok());
+
+        message.address_table_lookups[0].writable_indexes.push(253);
+        assert_eq!(message.sanitize(), Err(SanitizeError::IndexOutOfBounds));
+    }
+
+    #[test]
+    fn test_try_into_legacy_message() {
+        let payer = Pubkey::new_unique();
+        let writable_key = Pubkey::new_unique();
+        let program_id = Pubkey::new_unique();
+        let loaded_writable = Pubkey::new_unique();
+        let loaded_readonly = Pubkey::new_unique();
+        let message = Message {
+            header: MessageHeader {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 1,
+            },
+            account_keys: vec![payer, writable_key, program_id],
+            recent_blockhash: Hash::new_unique(),
+            instructions: vec![CompiledInstruction {
+                program_id_index: 2,
+                accounts: vec![0, 1, 3, 4],
+                data: vec![42],
+            }],
+            address_table_lookups: vec![MessageAddressTableLookup {
+                account_key: Pubkey::new_unique(),
+                writable_indexes: vec![0],
+                readonly_indexes: vec![1],
+            }],
+        };
+        let recent_blockhash = message.recent_blockhash;
+
+        assert_eq!(
+            message
+                .clone()
+                .try_into_legacy_message(LoadedAddresses::default()),
+            None
+        );
+
+        let legacy_message = message
+            .try_into_legacy_message(LoadedAddresses {
+                writable: vec![loaded_writable],
+                readonly: vec![loaded_readonly],
+            })
+            .unwrap();
+        assert_eq!(
+            legacy_message,
+            LegacyMessage::new_with_compiled_instructions(
+                1,
+                0,
+                2,
+                vec![
+                    payer,
+                    writable_key,
+                    loaded_writable,
+                    program_id,
+                    loaded_readonly
+                ],
+                recent_blockhash,
+                vec![CompiledInstruction {
+                    program_id_index: 3,
+                    accounts: vec![0, 1, 2, 4],
+                    data: vec![42],
+                }],
+            )
+        );
+        assert!(legacy_message.sanitize().is_ok());
+        assert!(legacy_message.is_writable(2));
+        assert!(!legacy_message.is_writable(3));
+        assert!(!legacy_message.is_writable(4));
+    }
+}
@@ -37,6 +37,12 @@ macro_rules! declare_sysvar_id(
             }
         }
 
+        impl $crate::sysvar::SysvarAddress for $type {
+            fn id() -> $crate::pubkey::Pubkey {
+                id()
+            }
+        }
+
         #[cfg(test)]
         #[test]
         fn test_sysvar_id() {
@@ -54,6 +60,12 @@ pub trait SysvarId {
     fn check_id(pubkey: &Pubkey) -> bool;
 }
 
+/// The address of a sysvar account, for the sysvars declared with
+/// `declare_sysvar_id!`
+pub trait SysvarAddress: SysvarId {
+    fn id() -> Pubkey;
+}
+
 // utilities for moving into and out of Accounts
 pub trait Sysvar:
     SysvarId + Default + Sized + serde::Serialize + serde::de::DeserializeOwned
@@ -123,6 +123,38 @@ pub mod check_init_vote_data {
     solana_sdk::declare_id!("3ccR6QpxGYsAbWyfevEtBNGfWV4xBffxRj2tD6A9i39F");
 }
 
+pub mod return_data_syscall_enabled {
+    solana_sdk::declare_id!("GcQbxU9qvWQ9ZYX24jjfax3toggJhSvKFa3ymoic4zhf");
+}
+
+pub mod keccak256_syscall_enabled {
+    solana_sdk::declare_id!("CVWgZwDU7rKVorb45jDXwhSAkedKj1NUutzBEh1fDJvt");
+}
+
+pub mod secp256k1_recover_syscall_enabled {
+    solana_sdk::declare_id!("3yCkwJMTnvaJ6KQuJuikhbQEr1SSk5hbutomdcVoSmbY");
+}
+
+pub mod close_upgradeable_program_accounts {
+    solana_sdk::declare_id!("8ipk8qFpiaBnijT9eu95ZDJMZcsWLJmJBQFS7mpjpPeD");
+}
+
+pub mod upgradeable_loader_extend_program {
+    solana_sdk::declare_id!("6GKZBPLed7Cajn8XHXACPmsTdg7VadZY6KfLBrx9jWJ7");
+}
+
+pub mod versioned_tx_message_enabled {
+    solana_sdk::declare_id!("7gLNpKQqmqx5k4emutVogcW7fWoB5mgvo7LrNwsaeioJ");
+}
+
+pub mod address_lookup_table_program {
+    solana_sdk::declare_id!("E2Kxnay8aJc3foKFKby1v38t5zYjfq5LYetrFCkVHcKg");
+}
+
+pub mod compute_budget_program {
+    solana_sdk::declare_id!("AB1SQ5yDay9duubEd3Ajn6md9Q7sxFLFRqhQWe2TUyX");
+}
+
 lazy_static! {
     /// Map of feature identifiers to user-visible description
     pub static ref FEATURE_NAMES: HashMap<Pubkey, &'static str> = [
@@ -153,7 +185,15 @@ lazy_static! {
         (full_inflation::mainnet::certusone::vote::id(), "Community vote allowing Certus One to enable full inflation"),
         (warp_timestamp_again::id(), "warp timestamp again, adjust bounding to 25% fast 80% slow #15204"),
         (per_byte_logging_cost::id(), "charge the compute budget per byte for logging"),
-        (check_init_vote_data::id(), "check initialized Vote data")
+        (check_init_vote_data::id(), "check initialized Vote data"),
+        (return_data_syscall_enabled::id(), "enable sol_{set,get}_return_data syscalls"),
+        (keccak256_syscall_enabled::id(), "keccak256 syscall"),
+        (secp256k1_recover_syscall_enabled::id(), "secp256k1_recover syscall"),
+        (close_upgradeable_program_accounts::id(), "enable closing upgradeable program accounts"),
+        (upgradeable_loader_extend_program::id(), "enable extending upgradeable program data"),
+        (versioned_tx_message_enabled::id(), "enable versioned transaction message processing"),
+        (address_lookup_table_program::id(), "address lookup table program"),
+        (compute_budget_program::id(), "compute budget program"),
         /*************** ADD NEW FEATURES HERE ***************/
     ]
     .iter()
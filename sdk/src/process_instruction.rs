@@ -4,8 +4,15 @@ use solana_sdk::{
     keyed_account::KeyedAccount,
     message::Message,
     pubkey::Pubkey,
+    transaction::TransactionReturnData,
+};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fmt::{self, Debug},
+    rc::Rc,
+    sync::Arc,
 };
-use std::{cell::RefCell, fmt::Debug, rc::Rc, sync::Arc};
 
 // Prototype of a native loader entry point
 ///
@@ -60,6 +67,14 @@ pub trait InvokeContext {
     fn is_feature_active(&self, feature_id: &Pubkey) -> bool;
     /// Get an account from a pre-account
     fn get_account(&self, pubkey: &Pubkey) -> Option<RefCell<Account>>;
+    /// Set the return data of the current invocation, `None` clears it
+    fn set_return_data(&mut self, return_data: Option<TransactionReturnData>);
+    /// Get the return data most recently set by any invoked program
+    fn get_return_data(&self) -> Option<&TransactionReturnData>;
+    /// Whether loaders should record the execution traces of the programs they run
+    fn is_bpf_tracing_enabled(&self) -> bool;
+    /// Record the execution trace of a program invocation
+    fn record_bpf_trace(&self, trace: BpfTrace);
 }
 
 /// Convenience macro to log a message with an `Rc<RefCell<dyn Logger>>`
@@ -120,6 +135,16 @@ pub struct BpfComputeBudget {
     pub log_pubkey_units: u64,
     /// Maximum cross-program invocation instruction size
     pub max_cpi_instruction_size: usize,
+    /// Base number of compute units consumed to set or get return data
+    pub return_data_base_cost: u64,
+    /// Number of return data bytes per compute unit consumed
+    pub return_data_bytes_per_unit: u64,
+    /// Base number of compute units consumed to call Keccak256
+    pub keccak256_base_cost: u64,
+    /// Incremental number of units consumed by Keccak256 (based on bytes)
+    pub keccak256_byte_cost: u64,
+    /// Number of compute units consumed to call secp256k1_recover
+    pub secp256k1_recover_cost: u64,
 }
 impl Default for BpfComputeBudget {
     fn default() -> Self {
@@ -141,6 +166,11 @@ impl BpfComputeBudget {
             stack_frame_size: 4_096,
             log_pubkey_units: 100,
             max_cpi_instruction_size: 1280, // IPv6 Min MTU size
+            return_data_base_cost: 100,
+            return_data_bytes_per_unit: 250,
+            keccak256_base_cost: 85,
+            keccak256_byte_cost: 1,
+            secp256k1_recover_cost: 25_000,
         }
     }
 }
@@ -229,6 +259,72 @@ pub trait Executor: Debug + Send + Sync {
     ) -> Result<(), InstructionError>;
 }
 
+/// Execution trace of a BPF program invocation, recorded when BPF tracing is enabled
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BpfTrace {
+    /// Program that was invoked
+    pub program_id: Pubkey,
+    /// Depth of the invocation, 1 for the programs invoked by the transaction itself
+    pub invoke_depth: usize,
+    /// Registers r0 to r10 and the program counter before each executed instruction
+    pub instructions: Vec<[u64; 12]>,
+    /// Disassembly of the executed instructions, by program counter
+    pub disassembly: HashMap<u64, String>,
+    /// Compute units consumed by the invocation, including those of its syscalls and of the
+    /// programs it invoked
+    pub consumed_units: u64,
+    /// Compute units consumed by the instructions of each function of the program, named after
+    /// its ELF symbols, most expensive first
+    pub function_units: Vec<(String, u64)>,
+}
+
+impl BpfTrace {
+    /// Compute units consumed outside of the program's own instructions, by its syscalls and the
+    /// programs it invoked
+    pub fn syscall_units(&self) -> u64 {
+        self.consumed_units
+            .saturating_sub(self.function_units.iter().map(|(_, units)| units).sum())
+    }
+
+    /// Write a line per executed instruction with the registers, the program counter and the
+    /// disassembly of the instruction
+    pub fn write_instructions<W: fmt::Write>(&self, out: &mut W) -> fmt::Result {
+        for (index, registers) in self.instructions.iter().enumerate() {
+            let pc = registers[11];
+            writeln!(
+                out,
+                "{:5} {:016X?} {:5}: {}",
+                index,
+                &registers[..11],
+                pc,
+                self.disassembly
+                    .get(&pc)
+                    .map(String::as_str)
+                    .unwrap_or("<unknown>")
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Write a line per function with the compute units it consumed, followed by the units
+    /// consumed by syscalls and invoked programs
+    pub fn write_profile<W: fmt::Write>(&self, out: &mut W) -> fmt::Result {
+        writeln!(
+            out,
+            "Program {} consumed {} compute units",
+            self.program_id, self.consumed_units
+        )?;
+        for (function, units) in self.function_units.iter() {
+            writeln!(out, "{:10} {}", units, function)?;
+        }
+        writeln!(
+            out,
+            "{:10} <syscalls and invoked programs>",
+            self.syscall_units()
+        )
+    }
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct MockComputeMeter {
     pub remaining: u64,
@@ -267,6 +363,8 @@ pub struct MockInvokeContext {
     pub compute_meter: MockComputeMeter,
     pub programs: Vec<(Pubkey, ProcessInstructionWithContext)>,
     pub invoke_depth: usize,
+    pub return_data: Option<TransactionReturnData>,
+    pub bpf_traces: Option<RefCell<Vec<BpfTrace>>>,
 }
 impl Default for MockInvokeContext {
     fn default() -> Self {
@@ -279,6 +377,8 @@ impl Default for MockInvokeContext {
             },
             programs: vec![],
             invoke_depth: 0,
+            return_data: None,
+            bpf_traces: None,
         }
     }
 }
@@ -328,4 +428,18 @@ impl InvokeContext for MockInvokeContext {
     fn get_account(&self, _pubkey: &Pubkey) -> Option<RefCell<Account>> {
         None
     }
+    fn set_return_data(&mut self, return_data: Option<TransactionReturnData>) {
+        self.return_data = return_data;
+    }
+    fn get_return_data(&self) -> Option<&TransactionReturnData> {
+        self.return_data.as_ref()
+    }
+    fn is_bpf_tracing_enabled(&self) -> bool {
+        self.bpf_traces.is_some()
+    }
+    fn record_bpf_trace(&self, trace: BpfTrace) {
+        if let Some(bpf_traces) = &self.bpf_traces {
+            bpf_traces.borrow_mut().push(trace);
+        }
+    }
 }
@@ -19,6 +19,10 @@ use crate::{
 use std::result;
 use thiserror::Error;
 
+mod versioned;
+
+pub use versioned::*;
+
 /// Reasons a transaction might be rejected.
 #[derive(
     Error, Serialize, Deserialize, Debug, PartialEq, Eq, Clone, AbiExample, AbiEnumVisitor,
@@ -94,6 +98,26 @@ pub enum TransactionError {
 
     #[error("Transactions are currently disabled due to cluster maintenance")]
     ClusterMaintenance,
+
+    /// Transaction version is unsupported
+    #[error("Transaction version is unsupported")]
+    UnsupportedVersion,
+
+    /// Transaction loads an address table account that doesn't exist
+    #[error("Transaction loads an address table account that doesn't exist")]
+    AddressLookupTableNotFound,
+
+    /// Transaction loads an address table account with an invalid owner
+    #[error("Transaction loads an address table account with an invalid owner")]
+    InvalidAddressLookupTableOwner,
+
+    /// Transaction loads an address table account with invalid data
+    #[error("Transaction loads an address table account with invalid data")]
+    InvalidAddressLookupTableData,
+
+    /// Transaction address table lookup uses an invalid index
+    #[error("Transaction address table lookup uses an invalid index")]
+    InvalidAddressLookupTableIndex,
 }
 
 pub type Result<T> = result::Result<T, TransactionError>;
@@ -104,6 +128,14 @@ impl From<SanitizeError> for TransactionError {
     }
 }
 
+/// Data most recently set by a program via `sol_set_return_data` while processing a transaction
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Default, Clone, AbiExample)]
+pub struct TransactionReturnData {
+    /// The program that set the return data
+    pub program_id: Pubkey,
+    pub data: Vec<u8>,
+}
+
 /// An atomic transaction
 #[frozen_abi(digest = "2Kr1C1pRytLsmUbg8p2nLoZyrjrEQCriAYLTCYvwj1Fo")]
 #[derive(Debug, PartialEq, Default, Eq, Clone, Serialize, Deserialize, AbiExample)]
@@ -339,25 +371,7 @@ impl Transaction {
     }
 
     pub fn verify_precompiles(&self) -> Result<()> {
-        for instruction in &self.message().instructions {
-            // The Transaction may not be sanitized at this point
-            if instruction.program_id_index as usize >= self.message().account_keys.len() {
-                return Err(TransactionError::AccountNotFound);
-            }
-            let program_id = &self.message().account_keys[instruction.program_id_index as usize];
-            if crate::secp256k1_program::check_id(program_id) {
-                let instruction_datas: Vec<_> = self
-                    .message()
-                    .instructions
-                    .iter()
-                    .map(|instruction| instruction.data.as_ref())
-                    .collect();
-                let data = &instruction.data;
-                let e = verify_eth_addresses(data, &instruction_datas);
-                e.map_err(|_| TransactionError::InvalidAccountIndex)?;
-            }
-        }
-        Ok(())
+        verify_precompiles(&self.message.account_keys, &self.message.instructions)
     }
 
     /// Get the positions of the pubkeys in `account_keys` associated with signing keypairs
@@ -427,6 +441,27 @@ pub fn get_nonce_pubkey_from_instruction<'a>(
     })
 }
 
+// Verify the data of every instruction that invokes a precompiled program. Program ids are
+// always static account keys, so this also applies to versioned messages.
+fn verify_precompiles(account_keys: &[Pubkey], instructions: &[CompiledInstruction]) -> Result<()> {
+    for instruction in instructions {
+        // The Transaction may not be sanitized at this point
+        let program_id = account_keys
+            .get(instruction.program_id_index as usize)
+            .ok_or(TransactionError::AccountNotFound)?;
+        if crate::secp256k1_program::check_id(program_id) {
+            let instruction_datas: Vec<_> = instructions
+                .iter()
+                .map(|instruction| instruction.data.as_ref())
+                .collect();
+            let data = &instruction.data;
+            let e = verify_eth_addresses(data, &instruction_datas);
+            e.map_err(|_| TransactionError::InvalidAccountIndex)?;
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,228 @@
+//! Defines a transaction which supports multiple versions of messages.
+
+#![cfg(feature = "full")]
+
+use crate::{
+    hash::Hash,
+    message::VersionedMessage,
+    sanitize::{Sanitize, SanitizeError},
+    short_vec,
+    signature::{Signature, SignerError},
+    signers::Signers,
+    transaction::{Result, Transaction, TransactionError},
+};
+use std::cmp::Ordering;
+
+/// An atomic transaction which may use a versioned message
+#[derive(Debug, PartialEq, Default, Eq, Clone, Serialize, Deserialize)]
+pub struct VersionedTransaction {
+    /// List of signatures
+    /// NOTE: Serialization-related changes must be paired with the direct read at sigverify.
+    #[serde(with = "short_vec")]
+    pub signatures: Vec<Signature>,
+    /// Message to sign.
+    pub message: VersionedMessage,
+}
+
+impl Sanitize for VersionedTransaction {
+    fn sanitize(&self) -> std::result::Result<(), SanitizeError> {
+        self.message.sanitize()?;
+
+        // Once the message is sanitized, we know that the number of required
+        // signatures is less than or equal to the number of static account keys.
+        match usize::from(self.message.header().num_required_signatures).cmp(&self.signatures.len())
+        {
+            Ordering::Greater => Err(SanitizeError::IndexOutOfBounds),
+            Ordering::Less => Err(SanitizeError::InvalidValue),
+            Ordering::Equal => Ok(()),
+        }
+    }
+}
+
+impl From<Transaction> for VersionedTransaction {
+    fn from(transaction: Transaction) -> Self {
+        Self {
+            signatures: transaction.signatures,
+            message: VersionedMessage::Legacy(transaction.message),
+        }
+    }
+}
+
+impl VersionedTransaction {
+    /// Signs a versioned message and if successful, returns a signed
+    /// transaction.
+    pub fn try_new<T: Signers>(
+        message: VersionedMessage,
+        keypairs: &T,
+    ) -> std::result::Result<Self, SignerError> {
+        let static_account_keys = message.static_account_keys();
+        let num_required_signatures = usize::from(message.header().num_required_signatures);
+        if static_account_keys.len() < num_required_signatures {
+            return Err(SignerError::InvalidInput("invalid message".to_string()));
+        }
+
+        let signer_keys = keypairs.pubkeys();
+        let expected_signer_keys = &static_account_keys[0..num_required_signatures];
+        match signer_keys.len().cmp(&expected_signer_keys.len()) {
+            Ordering::Greater => Err(SignerError::KeypairPubkeyMismatch),
+            Ordering::Less => Err(SignerError::NotEnoughSigners),
+            Ordering::Equal => Ok(()),
+        }?;
+
+        if signer_keys
+            .iter()
+            .zip(expected_signer_keys)
+            .any(|(signer_key, expected_key)| signer_key != expected_key)
+        {
+            return Err(SignerError::KeypairPubkeyMismatch);
+        }
+
+        let signatures = keypairs.try_sign_message(&message.serialize())?;
+        Ok(Self {
+            signatures,
+            message,
+        })
+    }
+
+    /// Returns a legacy transaction if the transaction message is legacy.
+    pub fn into_legacy_transaction(self) -> Option<Transaction> {
+        match self.message {
+            VersionedMessage::Legacy(message) => Some(Transaction {
+                signatures: self.signatures,
+                message,
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn recent_blockhash(&self) -> &Hash {
+        self.message.recent_blockhash()
+    }
+
+    /// Return the serialized message data to sign.
+    pub fn message_data(&self) -> Vec<u8> {
+        self.message.serialize()
+    }
+
+    /// Verify the transaction and return a list of verification results
+    pub fn verify_with_results(&self) -> Vec<bool> {
+        let message_bytes = self.message_data();
+        self.signatures
+            .iter()
+            .zip(self.message.static_account_keys())
+            .map(|(signature, pubkey)| signature.verify(pubkey.as_ref(), &message_bytes))
+            .collect()
+    }
+
+    /// Verify the transaction
+    pub fn verify(&self) -> Result<()> {
+        if !self
+            .verify_with_results()
+            .iter()
+            .all(|verify_result| *verify_result)
+        {
+            Err(TransactionError::SignatureFailure)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Verify the precompiled programs invoked by the transaction
+    pub fn verify_precompiles(&self) -> Result<()> {
+        super::verify_precompiles(
+            self.message.static_account_keys(),
+            self.message.instructions(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        instruction::CompiledInstruction,
+        message::{v0, Message, MessageHeader},
+        pubkey::Pubkey,
+        signature::{Keypair, Signer},
+        system_instruction,
+    };
+
+    fn v0_message(payer: &Pubkey) -> VersionedMessage {
+        VersionedMessage::V0(v0::Message {
+            header: MessageHeader {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 1,
+            },
+            account_keys: vec![*payer, Pubkey::new_unique()],
+            recent_blockhash: Hash::new_unique(),
+            instructions: vec![CompiledInstruction {
+                program_id_index: 1,
+                accounts: vec![0, 2],
+                data: vec![],
+            }],
+            address_table_lookups: vec![v0::MessageAddressTableLookup {
+                account_key: Pubkey::new_unique(),
+                writable_indexes: vec![0],
+                readonly_indexes: vec![],
+            }],
+        })
+    }
+
+    #[test]
+    fn test_try_new() {
+        let keypair = Keypair::new();
+        let message = v0_message(&keypair.pubkey());
+
+        let tx = VersionedTransaction::try_new(message.clone(), &[&keypair]).unwrap();
+        assert!(tx.sanitize().is_ok());
+        assert!(tx.verify().is_ok());
+
+        let bytes = bincode::serialize(&tx).unwrap();
+        assert_eq!(tx, bincode::deserialize(&bytes).unwrap());
+        assert!(tx.into_legacy_transaction().is_none());
+
+        assert_eq!(
+            VersionedTransaction::try_new(message.clone(), &[&Keypair::new()]),
+            Err(SignerError::KeypairPubkeyMismatch)
+        );
+        assert_eq!(
+            VersionedTransaction::try_new(message, &[&keypair, &Keypair::new()]),
+            Err(SignerError::KeypairPubkeyMismatch)
+        );
+        assert_eq!(
+            VersionedTransaction::try_new(v0_message(&keypair.pubkey()), &Vec::<&Keypair>::new()),
+            Err(SignerError::NotEnoughSigners)
+        );
+    }
+
+    #[test]
+    fn test_sanitize_signatures() {
+        let keypair = Keypair::new();
+        let mut tx =
+            VersionedTransaction::try_new(v0_message(&keypair.pubkey()), &[&keypair]).unwrap();
+        tx.signatures.push(Signature::default());
+        assert_eq!(tx.sanitize(), Err(SanitizeError::InvalidValue));
+        tx.signatures.clear();
+        assert_eq!(tx.sanitize(), Err(SanitizeError::IndexOutOfBounds));
+    }
+
+    #[test]
+    fn test_legacy_transaction_compatibility() {
+        let keypair = Keypair::new();
+        let to = Pubkey::new_unique();
+        let ix = system_instruction::transfer(&keypair.pubkey(), &to, 42);
+        let message = Message::new(&[ix], Some(&keypair.pubkey()));
+        let tx = Transaction::new(&[&keypair], message, Hash::new_unique());
+
+        let versioned_tx = VersionedTransaction::from(tx.clone());
+        assert!(versioned_tx.verify().is_ok());
+
+        // legacy transactions serialize identically with either type
+        let bytes = bincode::serialize(&tx).unwrap();
+        assert_eq!(bytes, bincode::serialize(&versioned_tx).unwrap());
+        let versioned_tx_from_bytes: VersionedTransaction = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(versioned_tx_from_bytes, versioned_tx);
+        assert_eq!(versioned_tx.into_legacy_transaction(), Some(tx));
+    }
+}
@@ -3,6 +3,7 @@ use log::*;
 use serde::{Deserialize, Serialize};
 use solana_sdk::{
     clock::{Slot, UnixTimestamp},
+    message::v0::LoadedAddresses,
     pubkey::Pubkey,
     signature::Signature,
     sysvar::is_sysvar_id,
@@ -144,7 +145,12 @@ struct StoredConfirmedBlockTransaction {
 impl From<TransactionWithStatusMeta> for StoredConfirmedBlockTransaction {
     fn from(value: TransactionWithStatusMeta) -> Self {
         Self {
-            transaction: value.transaction,
+            // The bincode format predates versioned transactions, blocks are only uploaded as
+            // protobuf
+            transaction: value
+                .transaction
+                .into_legacy_transaction()
+                .expect("bincode blocks only hold legacy transactions"),
             meta: value.meta.map(|meta| meta.into()),
         }
     }
@@ -153,7 +159,7 @@ impl From<TransactionWithStatusMeta> for StoredConfirmedBlockTransaction {
 impl From<StoredConfirmedBlockTransaction> for TransactionWithStatusMeta {
     fn from(value: StoredConfirmedBlockTransaction) -> Self {
         Self {
-            transaction: value.transaction,
+            transaction: value.transaction.into(),
             meta: value.meta.map(|meta| meta.into()),
         }
     }
@@ -188,6 +194,8 @@ impl From<StoredConfirmedBlockTransactionStatusMeta> for TransactionStatusMeta {
             log_messages: None,
             pre_token_balances: None,
             post_token_balances: None,
+            return_data: None,
+            loaded_addresses: LoadedAddresses::default(),
         }
     }
 }
@@ -538,7 +546,7 @@ impl LedgerStorage {
     ) -> Result<()> {
         let mut bytes_written = 0;
 
-        let mut by_addr: HashMap<&Pubkey, Vec<TransactionByAddrInfo>> = HashMap::new();
+        let mut by_addr: HashMap<Pubkey, Vec<TransactionByAddrInfo>> = HashMap::new();
 
         let mut tx_cells = vec![];
         for (index, transaction_with_meta) in confirmed_block.transactions.iter().enumerate() {
@@ -547,7 +555,7 @@ impl LedgerStorage {
             let index = index as u32;
             let signature = transaction.signatures[0];
 
-            for address in &transaction.message.account_keys {
+            for address in transaction_with_meta.account_keys() {
                 if !is_sysvar_id(&address) {
                     by_addr
                         .entry(address)
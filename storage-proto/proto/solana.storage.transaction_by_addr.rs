@@ -66,6 +66,11 @@ pub enum TransactionErrorType {
     InvalidProgramForExecution = 13,
     SanitizeFailure = 14,
     ClusterMaintenance = 15,
+    UnsupportedVersion = 16,
+    AddressLookupTableNotFound = 17,
+    InvalidAddressLookupTableOwner = 18,
+    InvalidAddressLookupTableData = 19,
+    InvalidAddressLookupTableIndex = 20,
 }
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
@@ -116,4 +121,5 @@ pub enum InstructionErrorType {
     IncorrectAuthority = 43,
     BorshIoError = 44,
     AccountNotRentExempt = 45,
+    ArithmeticOverflow = 46,
 }
@@ -4,11 +4,16 @@ use solana_sdk::{
     hash::Hash,
     instruction::CompiledInstruction,
     instruction::InstructionError,
-    message::{Message, MessageHeader},
+    message::{
+        v0::{self, LoadedAddresses, MessageAddressTableLookup},
+        Message, MessageHeader, VersionedMessage,
+    },
     pubkey::Pubkey,
     signature::Signature,
     transaction::Transaction,
     transaction::TransactionError,
+    transaction::TransactionReturnData,
+    transaction::VersionedTransaction,
 };
 use solana_transaction_status::{
     ConfirmedBlock, InnerInstructions, Reward, RewardType, TransactionByAddrInfo,
@@ -225,6 +230,8 @@ impl From<Message> for generated::Message {
                 .collect(),
             recent_blockhash: value.recent_blockhash.to_bytes().into(),
             instructions: value.instructions.into_iter().map(|ix| ix.into()).collect(),
+            versioned: false,
+            address_table_lookups: vec![],
         }
     }
 }
@@ -244,6 +251,103 @@ impl From<generated::Message> for Message {
     }
 }
 
+impl From<VersionedTransaction> for generated::Transaction {
+    fn from(value: VersionedTransaction) -> Self {
+        Self {
+            signatures: value
+                .signatures
+                .into_iter()
+                .map(|signature| <Signature as AsRef<[u8]>>::as_ref(&signature).into())
+                .collect(),
+            message: Some(value.message.into()),
+        }
+    }
+}
+
+impl From<generated::Transaction> for VersionedTransaction {
+    fn from(value: generated::Transaction) -> Self {
+        Self {
+            signatures: value
+                .signatures
+                .into_iter()
+                .map(|x| Signature::new(&x))
+                .collect(),
+            message: value.message.expect("message is required").into(),
+        }
+    }
+}
+
+impl From<VersionedMessage> for generated::Message {
+    fn from(value: VersionedMessage) -> Self {
+        match value {
+            VersionedMessage::Legacy(message) => message.into(),
+            VersionedMessage::V0(message) => Self {
+                header: Some(message.header.into()),
+                account_keys: message
+                    .account_keys
+                    .into_iter()
+                    .map(|key| <Pubkey as AsRef<[u8]>>::as_ref(&key).into())
+                    .collect(),
+                recent_blockhash: message.recent_blockhash.to_bytes().into(),
+                instructions: message
+                    .instructions
+                    .into_iter()
+                    .map(|ix| ix.into())
+                    .collect(),
+                versioned: true,
+                address_table_lookups: message
+                    .address_table_lookups
+                    .into_iter()
+                    .map(|lookup| lookup.into())
+                    .collect(),
+            },
+        }
+    }
+}
+
+impl From<generated::Message> for VersionedMessage {
+    fn from(value: generated::Message) -> Self {
+        if !value.versioned {
+            return Self::Legacy(value.into());
+        }
+        Self::V0(v0::Message {
+            header: value.header.expect("header is required").into(),
+            account_keys: value
+                .account_keys
+                .into_iter()
+                .map(|key| Pubkey::new(&key))
+                .collect(),
+            recent_blockhash: Hash::new(&value.recent_blockhash),
+            instructions: value.instructions.into_iter().map(|ix| ix.into()).collect(),
+            address_table_lookups: value
+                .address_table_lookups
+                .into_iter()
+                .map(|lookup| lookup.into())
+                .collect(),
+        })
+    }
+}
+
+impl From<MessageAddressTableLookup> for generated::MessageAddressTableLookup {
+    fn from(value: MessageAddressTableLookup) -> Self {
+        Self {
+            account_key: <Pubkey as AsRef<[u8]>>::as_ref(&value.account_key).into(),
+            writable_indexes: value.writable_indexes,
+            readonly_indexes: value.readonly_indexes,
+        }
+    }
+}
+
+impl From<generated::MessageAddressTableLookup> for MessageAddressTableLookup {
+    fn from(value: generated::MessageAddressTableLookup) -> Self {
+        Self {
+            account_key: Pubkey::new(&value.account_key),
+            writable_indexes: value.writable_indexes,
+            readonly_indexes: value.readonly_indexes,
+        }
+    }
+}
+
 impl From<MessageHeader> for generated::MessageHeader {
     fn from(value: MessageHeader) -> Self {
         Self {
@@ -275,6 +379,8 @@ impl From<TransactionStatusMeta> for generated::TransactionStatusMeta {
             log_messages,
             pre_token_balances,
             post_token_balances,
+            return_data,
+            loaded_addresses,
         } = value;
         let err = match status {
             Ok(()) => None,
@@ -298,6 +404,17 @@ impl From<TransactionStatusMeta> for generated::TransactionStatusMeta {
             .into_iter()
             .map(|balance| balance.into())
             .collect();
+        let return_data = return_data.map(|return_data| return_data.into());
+        let loaded_writable_addresses = loaded_addresses
+            .writable
+            .into_iter()
+            .map(|key| <Pubkey as AsRef<[u8]>>::as_ref(&key).into())
+            .collect();
+        let loaded_readonly_addresses = loaded_addresses
+            .readonly
+            .into_iter()
+            .map(|key| <Pubkey as AsRef<[u8]>>::as_ref(&key).into())
+            .collect();
 
         Self {
             err,
@@ -308,6 +425,9 @@ impl From<TransactionStatusMeta> for generated::TransactionStatusMeta {
             log_messages,
             pre_token_balances,
             post_token_balances,
+            return_data,
+            loaded_writable_addresses,
+            loaded_readonly_addresses,
         }
     }
 }
@@ -325,6 +445,9 @@ impl TryFrom<generated::TransactionStatusMeta> for TransactionStatusMeta {
             log_messages,
             pre_token_balances,
             post_token_balances,
+            return_data,
+            loaded_writable_addresses,
+            loaded_readonly_addresses,
         } = value;
         let status = match &err {
             None => Ok(()),
@@ -349,6 +472,17 @@ impl TryFrom<generated::TransactionStatusMeta> for TransactionStatusMeta {
                 .map(|balance| balance.into())
                 .collect(),
         );
+        let return_data = return_data.map(|return_data| return_data.into());
+        let loaded_addresses = LoadedAddresses {
+            writable: loaded_writable_addresses
+                .into_iter()
+                .map(|key| Pubkey::new(&key))
+                .collect(),
+            readonly: loaded_readonly_addresses
+                .into_iter()
+                .map(|key| Pubkey::new(&key))
+                .collect(),
+        };
         Ok(Self {
             status,
             fee,
@@ -358,10 +492,30 @@ impl TryFrom<generated::TransactionStatusMeta> for TransactionStatusMeta {
             log_messages,
             pre_token_balances,
             post_token_balances,
+            return_data,
+            loaded_addresses,
         })
     }
 }
 
+impl From<TransactionReturnData> for generated::ReturnData {
+    fn from(value: TransactionReturnData) -> Self {
+        Self {
+            program_id: value.program_id.to_bytes().into(),
+            data: value.data,
+        }
+    }
+}
+
+impl From<generated::ReturnData> for TransactionReturnData {
+    fn from(value: generated::ReturnData) -> Self {
+        Self {
+            program_id: Pubkey::new(&value.program_id),
+            data: value.data,
+        }
+    }
+}
+
 impl From<InnerInstructions> for generated::InnerInstructions {
     fn from(value: InnerInstructions) -> Self {
         Self {
@@ -494,6 +648,7 @@ impl TryFrom<tx_by_addr::TransactionError> for TransactionError {
                     41 => InstructionError::ProgramFailedToCompile,
                     42 => InstructionError::Immutable,
                     43 => InstructionError::IncorrectAuthority,
+                    46 => InstructionError::ArithmeticOverflow,
                     _ => return Err("Invalid InstructionError"),
                 };
 
@@ -520,6 +675,11 @@ impl TryFrom<tx_by_addr::TransactionError> for TransactionError {
             13 => TransactionError::InvalidProgramForExecution,
             14 => TransactionError::SanitizeFailure,
             15 => TransactionError::ClusterMaintenance,
+            16 => TransactionError::UnsupportedVersion,
+            17 => TransactionError::AddressLookupTableNotFound,
+            18 => TransactionError::InvalidAddressLookupTableOwner,
+            19 => TransactionError::InvalidAddressLookupTableData,
+            20 => TransactionError::InvalidAddressLookupTableIndex,
             _ => return Err("Invalid TransactionError"),
         })
     }
@@ -572,6 +732,21 @@ impl From<TransactionError> for tx_by_addr::TransactionError {
                 TransactionError::ClusterMaintenance => {
                     tx_by_addr::TransactionErrorType::ClusterMaintenance
                 }
+                TransactionError::UnsupportedVersion => {
+                    tx_by_addr::TransactionErrorType::UnsupportedVersion
+                }
+                TransactionError::AddressLookupTableNotFound => {
+                    tx_by_addr::TransactionErrorType::AddressLookupTableNotFound
+                }
+                TransactionError::InvalidAddressLookupTableOwner => {
+                    tx_by_addr::TransactionErrorType::InvalidAddressLookupTableOwner
+                }
+                TransactionError::InvalidAddressLookupTableData => {
+                    tx_by_addr::TransactionErrorType::InvalidAddressLookupTableData
+                }
+                TransactionError::InvalidAddressLookupTableIndex => {
+                    tx_by_addr::TransactionErrorType::InvalidAddressLookupTableIndex
+                }
                 TransactionError::InstructionError(_, _) => {
                     tx_by_addr::TransactionErrorType::InstructionError
                 }
@@ -717,6 +892,9 @@ impl From<TransactionError> for tx_by_addr::TransactionError {
                             InstructionError::AccountNotRentExempt => {
                                 tx_by_addr::InstructionErrorType::AccountNotRentExempt
                             }
+                            InstructionError::ArithmeticOverflow => {
+                                tx_by_addr::InstructionErrorType::ArithmeticOverflow
+                            }
                         } as i32,
                         custom: match instruction_error {
                             InstructionError::Custom(custom) => {
@@ -889,6 +1067,46 @@ mod test {
             tx_by_addr_transaction_error.try_into().unwrap()
         );
 
+        let transaction_error = TransactionError::UnsupportedVersion;
+        let tx_by_addr_transaction_error: tx_by_addr::TransactionError =
+            transaction_error.clone().into();
+        assert_eq!(
+            transaction_error,
+            tx_by_addr_transaction_error.try_into().unwrap()
+        );
+
+        let transaction_error = TransactionError::AddressLookupTableNotFound;
+        let tx_by_addr_transaction_error: tx_by_addr::TransactionError =
+            transaction_error.clone().into();
+        assert_eq!(
+            transaction_error,
+            tx_by_addr_transaction_error.try_into().unwrap()
+        );
+
+        let transaction_error = TransactionError::InvalidAddressLookupTableOwner;
+        let tx_by_addr_transaction_error: tx_by_addr::TransactionError =
+            transaction_error.clone().into();
+        assert_eq!(
+            transaction_error,
+            tx_by_addr_transaction_error.try_into().unwrap()
+        );
+
+        let transaction_error = TransactionError::InvalidAddressLookupTableData;
+        let tx_by_addr_transaction_error: tx_by_addr::TransactionError =
+            transaction_error.clone().into();
+        assert_eq!(
+            transaction_error,
+            tx_by_addr_transaction_error.try_into().unwrap()
+        );
+
+        let transaction_error = TransactionError::InvalidAddressLookupTableIndex;
+        let tx_by_addr_transaction_error: tx_by_addr::TransactionError =
+            transaction_error.clone().into();
+        assert_eq!(
+            transaction_error,
+            tx_by_addr_transaction_error.try_into().unwrap()
+        );
+
         let transaction_error = TransactionError::DuplicateSignature;
         let tx_by_addr_transaction_error: tx_by_addr::TransactionError =
             transaction_error.clone().into();
@@ -1132,6 +1350,15 @@ mod test {
             tx_by_addr_transaction_error.try_into().unwrap()
         );
 
+        let transaction_error =
+            TransactionError::InstructionError(10, InstructionError::ArithmeticOverflow);
+        let tx_by_addr_transaction_error: tx_by_addr::TransactionError =
+            transaction_error.clone().into();
+        assert_eq!(
+            transaction_error,
+            tx_by_addr_transaction_error.try_into().unwrap()
+        );
+
         let transaction_error =
             TransactionError::InstructionError(10, InstructionError::IncorrectProgramId);
         let tx_by_addr_transaction_error: tx_by_addr::TransactionError =
@@ -1359,4 +1586,67 @@ mod test {
             tx_by_addr_transaction_error.try_into().unwrap()
         );
     }
+
+    #[test]
+    fn test_versioned_transaction_encode() {
+        let transaction = VersionedTransaction {
+            signatures: vec![Signature::default()],
+            message: VersionedMessage::V0(v0::Message {
+                header: MessageHeader {
+                    num_required_signatures: 1,
+                    num_readonly_signed_accounts: 0,
+                    num_readonly_unsigned_accounts: 1,
+                },
+                account_keys: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+                recent_blockhash: Hash::new_unique(),
+                instructions: vec![CompiledInstruction {
+                    program_id_index: 1,
+                    accounts: vec![0, 2],
+                    data: vec![],
+                }],
+                address_table_lookups: vec![MessageAddressTableLookup {
+                    account_key: Pubkey::new_unique(),
+                    writable_indexes: vec![3],
+                    readonly_indexes: vec![4],
+                }],
+            }),
+        };
+        let with_meta = TransactionWithStatusMeta {
+            transaction,
+            meta: Some(TransactionStatusMeta {
+                loaded_addresses: LoadedAddresses {
+                    writable: vec![Pubkey::new_unique()],
+                    readonly: vec![Pubkey::new_unique()],
+                },
+                ..TransactionStatusMeta::default()
+            }),
+        };
+        let gen_with_meta: generated::ConfirmedTransaction = with_meta.clone().into();
+        assert!(
+            gen_with_meta
+                .transaction
+                .as_ref()
+                .unwrap()
+                .message
+                .as_ref()
+                .unwrap()
+                .versioned
+        );
+        let decoded: TransactionWithStatusMeta = gen_with_meta.try_into().unwrap();
+        assert_eq!(decoded.transaction, with_meta.transaction);
+        assert_eq!(
+            decoded.meta.unwrap().loaded_addresses,
+            with_meta.meta.unwrap().loaded_addresses
+        );
+
+        // Legacy transactions are stored as before
+        let legacy_transaction = Transaction::default();
+        let gen_transaction: generated::Transaction =
+            VersionedTransaction::from(legacy_transaction.clone()).into();
+        assert_eq!(gen_transaction, legacy_transaction.clone().into());
+        assert_eq!(
+            VersionedTransaction::from(gen_transaction),
+            legacy_transaction.into()
+        );
+    }
 }
@@ -831,8 +831,27 @@ pub fn test_process_distribute_tokens_with_client(
     sender_keypair: Keypair,
     transfer_amount: Option<u64>,
 ) {
-    let exit = Arc::new(AtomicBool::default());
     let fee_payer = Keypair::new();
+    let alice_pubkey = solana_sdk::pubkey::new_rand();
+    test_process_distribute_tokens_with_keys(
+        client,
+        sender_keypair,
+        fee_payer,
+        alice_pubkey,
+        transfer_amount,
+    );
+}
+
+/// Distribute tokens from `sender_keypair` to `alice_pubkey`, with fees paid by `fee_payer`.
+/// Fixed keys make the transactions sent the same from run to run
+pub fn test_process_distribute_tokens_with_keys(
+    client: &RpcClient,
+    sender_keypair: Keypair,
+    fee_payer: Keypair,
+    alice_pubkey: Pubkey,
+    transfer_amount: Option<u64>,
+) {
+    let exit = Arc::new(AtomicBool::default());
     let transaction = transfer(
         client,
         sol_to_lamports(1.0),
@@ -853,7 +872,6 @@ pub fn test_process_distribute_tokens_with_client(
     } else {
         sol_to_lamports(1000.0)
     };
-    let alice_pubkey = solana_sdk::pubkey::new_rand();
     let allocations_file = NamedTempFile::new().unwrap();
     let input_csv = allocations_file.path().to_str().unwrap().to_string();
     let mut wtr = csv::WriterBuilder::new().from_writer(allocations_file);
@@ -1,7 +1,17 @@
-use solana_client::rpc_client::RpcClient;
+use solana_client::{
+    http_sender::HttpSender, recording_sender::RecordingSender, replay_sender::ReplaySender,
+    rpc_client::RpcClient,
+};
 use solana_core::test_validator::TestValidator;
-use solana_sdk::signature::{Keypair, Signer};
-use solana_tokens::commands::test_process_distribute_tokens_with_client;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    pubkey::Pubkey,
+    signature::{keypair_from_seed, Keypair, Signer},
+};
+use solana_tokens::commands::{
+    test_process_distribute_tokens_with_client, test_process_distribute_tokens_with_keys,
+};
+use std::{env, fs};
 
 #[test]
 fn test_process_distribute_with_rpc_client() {
@@ -13,3 +23,38 @@ fn test_process_distribute_with_rpc_client() {
     let client = RpcClient::new(test_validator.rpc_url());
     test_process_distribute_tokens_with_client(&client, mint_keypair, None);
 }
+
+// A session with a local validator, replayed so that the test runs without
+// one. Run the test with SOLANA_RECORD_RPC set to record it again.
+const RPC_RECORDING: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/distribute_tokens.jsonl"
+);
+
+#[test]
+fn test_process_distribute_with_replayed_rpc_client() {
+    solana_logger::setup();
+
+    // Fixed keys, so that the transactions sent match the recording
+    let mint_keypair = keypair_from_seed(&[3; 32]).unwrap();
+    let fee_payer = keypair_from_seed(&[1; 32]).unwrap();
+    let alice_pubkey = Pubkey::new(&[2; 32]);
+
+    let (client, _test_validator) = if env::var_os("SOLANA_RECORD_RPC").is_some() {
+        let test_validator = TestValidator::with_no_fees(mint_keypair.pubkey());
+        let _ = fs::remove_file(RPC_RECORDING);
+        let sender =
+            RecordingSender::new(HttpSender::new(test_validator.rpc_url()), RPC_RECORDING).unwrap();
+        (
+            RpcClient::new_sender(sender, CommitmentConfig::default()),
+            Some(test_validator),
+        )
+    } else {
+        let sender = ReplaySender::load(RPC_RECORDING).unwrap();
+        (
+            RpcClient::new_sender(sender, CommitmentConfig::default()),
+            None,
+        )
+    };
+    test_process_distribute_tokens_with_keys(&client, mint_keypair, fee_payer, alice_pubkey, None);
+}
@@ -14,7 +14,7 @@ pub mod parse_vote;
 pub mod token_balances;
 
 use crate::{
-    parse_accounts::{parse_accounts, ParsedAccount},
+    parse_accounts::{parse_accounts, parse_v0_accounts, ParsedAccount},
     parse_instruction::{parse, ParsedInstruction},
 };
 use solana_account_decoder::parse_token::UiTokenAmount;
@@ -24,11 +24,16 @@ use solana_sdk::{
     commitment_config::CommitmentConfig,
     deserialize_utils::default_on_eof,
     instruction::CompiledInstruction,
-    message::{Message, MessageHeader},
+    message::{
+        v0::{LoadedAddresses, MessageAddressTableLookup},
+        MessageHeader, VersionedMessage,
+    },
     pubkey::Pubkey,
     sanitize::Sanitize,
     signature::Signature,
-    transaction::{Result, Transaction, TransactionError},
+    transaction::{
+        Result, Transaction, TransactionError, TransactionReturnData, VersionedTransaction,
+    },
 };
 use std::fmt;
 /// A duplicate representation of an Instruction for pretty JSON serialization
@@ -47,13 +52,13 @@ pub enum UiParsedInstruction {
 }
 
 impl UiInstruction {
-    fn parse(instruction: &CompiledInstruction, message: &Message) -> Self {
-        let program_id = instruction.program_id(&message.account_keys);
-        if let Ok(parsed_instruction) = parse(program_id, instruction, &message.account_keys) {
+    fn parse(instruction: &CompiledInstruction, account_keys: &[Pubkey]) -> Self {
+        let program_id = instruction.program_id(account_keys);
+        if let Ok(parsed_instruction) = parse(program_id, instruction, account_keys) {
             UiInstruction::Parsed(UiParsedInstruction::Parsed(parsed_instruction))
         } else {
             UiInstruction::Parsed(UiParsedInstruction::PartiallyDecoded(
-                UiPartiallyDecodedInstruction::from(instruction, &message.account_keys),
+                UiPartiallyDecodedInstruction::from(instruction, account_keys),
             ))
         }
     }
@@ -144,13 +149,13 @@ impl From<TransactionTokenBalance> for UiTransactionTokenBalance {
 }
 
 impl UiInnerInstructions {
-    fn parse(inner_instructions: InnerInstructions, message: &Message) -> Self {
+    fn parse(inner_instructions: InnerInstructions, account_keys: &[Pubkey]) -> Self {
         Self {
             index: inner_instructions.index,
             instructions: inner_instructions
                 .instructions
                 .iter()
-                .map(|ix| UiInstruction::parse(ix, message))
+                .map(|ix| UiInstruction::parse(ix, account_keys))
                 .collect(),
         }
     }
@@ -184,6 +189,13 @@ pub struct TransactionStatusMeta {
     pub pre_token_balances: Option<Vec<TransactionTokenBalance>>,
     #[serde(deserialize_with = "default_on_eof")]
     pub post_token_balances: Option<Vec<TransactionTokenBalance>>,
+    #[serde(deserialize_with = "default_on_eof")]
+    pub return_data: Option<TransactionReturnData>,
+    /// Addresses a versioned transaction loaded from lookup tables. Account indexes in the
+    /// rest of the meta refer to the static account keys followed by these writable and then
+    /// readonly addresses
+    #[serde(deserialize_with = "default_on_eof")]
+    pub loaded_addresses: LoadedAddresses,
 }
 
 impl Default for TransactionStatusMeta {
@@ -197,6 +209,8 @@ impl Default for TransactionStatusMeta {
             log_messages: None,
             pre_token_balances: None,
             post_token_balances: None,
+            return_data: None,
+            loaded_addresses: LoadedAddresses::default(),
         }
     }
 }
@@ -214,10 +228,42 @@ pub struct UiTransactionStatusMeta {
     pub log_messages: Option<Vec<String>>,
     pub pre_token_balances: Option<Vec<UiTransactionTokenBalance>>,
     pub post_token_balances: Option<Vec<UiTransactionTokenBalance>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub return_data: Option<UiTransactionReturnData>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub loaded_addresses: Option<UiLoadedAddresses>,
+}
+
+/// A duplicate representation of LoadedAddresses
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UiLoadedAddresses {
+    pub writable: Vec<String>,
+    pub readonly: Vec<String>,
+}
+
+impl UiLoadedAddresses {
+    fn from_meta(loaded_addresses: &LoadedAddresses) -> Option<Self> {
+        if loaded_addresses.is_empty() {
+            return None;
+        }
+        Some(Self {
+            writable: loaded_addresses
+                .writable
+                .iter()
+                .map(|pubkey| pubkey.to_string())
+                .collect(),
+            readonly: loaded_addresses
+                .readonly
+                .iter()
+                .map(|pubkey| pubkey.to_string())
+                .collect(),
+        })
+    }
 }
 
 impl UiTransactionStatusMeta {
-    fn parse(meta: TransactionStatusMeta, message: &Message) -> Self {
+    fn parse(meta: TransactionStatusMeta, account_keys: &[Pubkey]) -> Self {
         Self {
             err: meta.status.clone().err(),
             status: meta.status,
@@ -226,7 +272,7 @@ impl UiTransactionStatusMeta {
             post_balances: meta.post_balances,
             inner_instructions: meta.inner_instructions.map(|ixs| {
                 ixs.into_iter()
-                    .map(|ix| UiInnerInstructions::parse(ix, message))
+                    .map(|ix| UiInnerInstructions::parse(ix, account_keys))
                     .collect()
             }),
             log_messages: meta.log_messages,
@@ -236,6 +282,8 @@ impl UiTransactionStatusMeta {
             post_token_balances: meta
                 .post_token_balances
                 .map(|balance| balance.into_iter().map(|balance| balance.into()).collect()),
+            return_data: meta.return_data.map(|return_data| return_data.into()),
+            loaded_addresses: UiLoadedAddresses::from_meta(&meta.loaded_addresses),
         }
     }
 }
@@ -258,6 +306,34 @@ impl From<TransactionStatusMeta> for UiTransactionStatusMeta {
             post_token_balances: meta
                 .post_token_balances
                 .map(|balance| balance.into_iter().map(|balance| balance.into()).collect()),
+            return_data: meta.return_data.map(|return_data| return_data.into()),
+            loaded_addresses: UiLoadedAddresses::from_meta(&meta.loaded_addresses),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum UiReturnDataEncoding {
+    Base64,
+}
+
+/// A duplicate representation of TransactionReturnData for pretty JSON serialization
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UiTransactionReturnData {
+    pub program_id: String,
+    pub data: (String, UiReturnDataEncoding),
+}
+
+impl From<TransactionReturnData> for UiTransactionReturnData {
+    fn from(return_data: TransactionReturnData) -> Self {
+        Self {
+            program_id: return_data.program_id.to_string(),
+            data: (
+                base64::encode(return_data.data),
+                UiReturnDataEncoding::Base64,
+            ),
         }
     }
 }
@@ -364,7 +440,7 @@ impl ConfirmedBlock {
     }
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct EncodedConfirmedBlock {
     pub previous_blockhash: String,
@@ -426,6 +502,27 @@ pub struct UiRawMessage {
     pub account_keys: Vec<String>,
     pub recent_blockhash: String,
     pub instructions: Vec<UiCompiledInstruction>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address_table_lookups: Option<Vec<UiAddressTableLookup>>,
+}
+
+/// A duplicate representation of a MessageAddressTableLookup, in raw format, for pretty JSON serialization
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UiAddressTableLookup {
+    pub account_key: String,
+    pub writable_indexes: Vec<u8>,
+    pub readonly_indexes: Vec<u8>,
+}
+
+impl From<&MessageAddressTableLookup> for UiAddressTableLookup {
+    fn from(lookup: &MessageAddressTableLookup) -> Self {
+        Self {
+            account_key: lookup.account_key.to_string(),
+            writable_indexes: lookup.writable_indexes.clone(),
+            readonly_indexes: lookup.readonly_indexes.clone(),
+        }
+    }
 }
 
 /// A duplicate representation of a Message, in parsed format, for pretty JSON serialization
@@ -435,27 +532,49 @@ pub struct UiParsedMessage {
     pub account_keys: Vec<ParsedAccount>,
     pub recent_blockhash: String,
     pub instructions: Vec<UiInstruction>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address_table_lookups: Option<Vec<UiAddressTableLookup>>,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TransactionWithStatusMeta {
-    pub transaction: Transaction,
+    pub transaction: VersionedTransaction,
     pub meta: Option<TransactionStatusMeta>,
 }
 
 impl TransactionWithStatusMeta {
+    /// Static account keys followed by the addresses loaded from lookup tables, in the order
+    /// that the meta's account indexes refer to
+    pub fn account_keys(&self) -> Vec<Pubkey> {
+        let mut account_keys = self.transaction.message.static_account_keys().to_vec();
+        if let Some(meta) = &self.meta {
+            account_keys.extend(&meta.loaded_addresses.writable);
+            account_keys.extend(&meta.loaded_addresses.readonly);
+        }
+        account_keys
+    }
+
     fn encode(self, encoding: UiTransactionEncoding) -> EncodedTransactionWithStatusMeta {
-        let message = self.transaction.message();
-        let meta = self.meta.map(|meta| meta.encode(encoding, message));
+        let account_keys = self.account_keys();
+        let loaded_addresses = self
+            .meta
+            .as_ref()
+            .map(|meta| meta.loaded_addresses.clone())
+            .unwrap_or_default();
+        let meta = self.meta.map(|meta| meta.encode(encoding, &account_keys));
         EncodedTransactionWithStatusMeta {
-            transaction: EncodedTransaction::encode(self.transaction, encoding),
+            transaction: EncodedTransaction::encode_versioned(
+                self.transaction,
+                encoding,
+                &loaded_addresses,
+            ),
             meta,
         }
     }
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct EncodedTransactionWithStatusMeta {
     pub transaction: EncodedTransaction,
@@ -463,9 +582,83 @@ pub struct EncodedTransactionWithStatusMeta {
 }
 
 impl TransactionStatusMeta {
-    fn encode(self, encoding: UiTransactionEncoding, message: &Message) -> UiTransactionStatusMeta {
+    /// Records the addresses that `transaction` loaded through the lookup tables of the
+    /// `versioned_transaction` it was resolved from, and moves every account index from the
+    /// resolved order to the signed one: static keys, then loaded writable and loaded readonly
+    /// addresses. Statuses of legacy transactions are left as they are.
+    pub fn record_loaded_addresses(
+        &mut self,
+        transaction: &Transaction,
+        versioned_transaction: &VersionedTransaction,
+    ) {
+        let message = match &versioned_transaction.message {
+            VersionedMessage::Legacy(_) => return,
+            VersionedMessage::V0(message) => message,
+        };
+        let account_keys = &transaction.message.account_keys;
+        let num_static_account_keys = message.account_keys.len();
+        let (num_loaded_writable, _) = message.num_lookup_addresses();
+        let loaded_writable_start =
+            num_static_account_keys - usize::from(message.header.num_readonly_unsigned_accounts);
+        let loaded_writable_end = loaded_writable_start + num_loaded_writable;
+        let loaded_readonly_start = num_static_account_keys + num_loaded_writable;
+        self.loaded_addresses = LoadedAddresses {
+            writable: account_keys[loaded_writable_start..loaded_writable_end].to_vec(),
+            readonly: account_keys[loaded_readonly_start..].to_vec(),
+        };
+
+        // Inverse of the reordering done by `v0::Message::try_into_legacy_message`
+        let account_order: Vec<u8> = (0..account_keys.len())
+            .map(|index| {
+                let position = if index < loaded_writable_start {
+                    index
+                } else if index < loaded_writable_end {
+                    num_static_account_keys + index - loaded_writable_start
+                } else if index < loaded_readonly_start {
+                    index - num_loaded_writable
+                } else {
+                    index
+                };
+                position as u8
+            })
+            .collect();
+        let reorder_balances = |balances: &mut Vec<u64>| {
+            let mut reordered = vec![0; balances.len()];
+            for (balance, position) in balances.iter().zip(&account_order) {
+                reordered[usize::from(*position)] = *balance;
+            }
+            *balances = reordered;
+        };
+        reorder_balances(&mut self.pre_balances);
+        reorder_balances(&mut self.post_balances);
+        let token_balances = self
+            .pre_token_balances
+            .iter_mut()
+            .chain(self.post_token_balances.iter_mut())
+            .flatten();
+        for token_balance in token_balances {
+            token_balance.account_index = account_order[usize::from(token_balance.account_index)];
+        }
+        let inner_instructions = self
+            .inner_instructions
+            .iter_mut()
+            .flatten()
+            .flat_map(|inner_instructions| inner_instructions.instructions.iter_mut());
+        for instruction in inner_instructions {
+            instruction.program_id_index = account_order[usize::from(instruction.program_id_index)];
+            for account in instruction.accounts.iter_mut() {
+                *account = account_order[usize::from(*account)];
+            }
+        }
+    }
+
+    fn encode(
+        self,
+        encoding: UiTransactionEncoding,
+        account_keys: &[Pubkey],
+    ) -> UiTransactionStatusMeta {
         match encoding {
-            UiTransactionEncoding::JsonParsed => UiTransactionStatusMeta::parse(self, message),
+            UiTransactionEncoding::JsonParsed => UiTransactionStatusMeta::parse(self, account_keys),
             _ => self.into(),
         }
     }
@@ -528,6 +721,7 @@ impl EncodedTransaction {
                             .iter()
                             .map(|instruction| instruction.into())
                             .collect(),
+                        address_table_lookups: None,
                     })
                 } else {
                     UiMessage::Parsed(UiParsedMessage {
@@ -538,9 +732,10 @@ impl EncodedTransaction {
                             .instructions
                             .iter()
                             .map(|instruction| {
-                                UiInstruction::parse(instruction, &transaction.message)
+                                UiInstruction::parse(instruction, &transaction.message.account_keys)
                             })
                             .collect(),
+                        address_table_lookups: None,
                     })
                 };
                 EncodedTransaction::Json(UiTransaction {
@@ -576,6 +771,123 @@ impl EncodedTransaction {
         };
         transaction.filter(|transaction| transaction.sanitize().is_ok())
     }
+
+    /// Encode a transaction which may use a versioned message. Legacy
+    /// transactions are encoded exactly as `encode` would. `JsonParsed` lists
+    /// the accounts of v0 messages with the `loaded_addresses` that they
+    /// loaded from lookup tables, and falls back to the raw json
+    /// representation when those are unknown.
+    pub fn encode_versioned(
+        transaction: VersionedTransaction,
+        encoding: UiTransactionEncoding,
+        loaded_addresses: &LoadedAddresses,
+    ) -> Self {
+        let (signatures, message) = match transaction.message {
+            VersionedMessage::Legacy(message) => {
+                let transaction = Transaction {
+                    signatures: transaction.signatures,
+                    message,
+                };
+                return Self::encode(transaction, encoding);
+            }
+            VersionedMessage::V0(message) => (transaction.signatures, message),
+        };
+        let (num_writable_lookups, num_readonly_lookups) = message.num_lookup_addresses();
+        let is_resolved = loaded_addresses.writable.len() == num_writable_lookups
+            && loaded_addresses.readonly.len() == num_readonly_lookups;
+        match encoding {
+            UiTransactionEncoding::Binary
+            | UiTransactionEncoding::Base58
+            | UiTransactionEncoding::Base64 => {
+                let transaction = VersionedTransaction {
+                    signatures,
+                    message: VersionedMessage::V0(message),
+                };
+                let data = bincode::serialize(&transaction).unwrap();
+                if encoding == UiTransactionEncoding::Base64 {
+                    EncodedTransaction::Binary(base64::encode(data), encoding)
+                } else {
+                    EncodedTransaction::Binary(
+                        bs58::encode(data).into_string(),
+                        UiTransactionEncoding::Base58,
+                    )
+                }
+            }
+            UiTransactionEncoding::JsonParsed if is_resolved => {
+                let mut account_keys = message.account_keys.clone();
+                account_keys.extend(&loaded_addresses.writable);
+                account_keys.extend(&loaded_addresses.readonly);
+                EncodedTransaction::Json(UiTransaction {
+                    signatures: signatures.iter().map(|sig| sig.to_string()).collect(),
+                    message: UiMessage::Parsed(UiParsedMessage {
+                        account_keys: parse_v0_accounts(&message, loaded_addresses),
+                        recent_blockhash: message.recent_blockhash.to_string(),
+                        instructions: message
+                            .instructions
+                            .iter()
+                            .map(|instruction| UiInstruction::parse(instruction, &account_keys))
+                            .collect(),
+                        address_table_lookups: Some(
+                            message
+                                .address_table_lookups
+                                .iter()
+                                .map(|lookup| lookup.into())
+                                .collect(),
+                        ),
+                    }),
+                })
+            }
+            UiTransactionEncoding::Json | UiTransactionEncoding::JsonParsed => {
+                EncodedTransaction::Json(UiTransaction {
+                    signatures: signatures.iter().map(|sig| sig.to_string()).collect(),
+                    message: UiMessage::Raw(UiRawMessage {
+                        header: message.header,
+                        account_keys: message
+                            .account_keys
+                            .iter()
+                            .map(|pubkey| pubkey.to_string())
+                            .collect(),
+                        recent_blockhash: message.recent_blockhash.to_string(),
+                        instructions: message
+                            .instructions
+                            .iter()
+                            .map(|instruction| instruction.into())
+                            .collect(),
+                        address_table_lookups: Some(
+                            message
+                                .address_table_lookups
+                                .iter()
+                                .map(|lookup| lookup.into())
+                                .collect(),
+                        ),
+                    }),
+                })
+            }
+        }
+    }
+
+    pub fn decode_versioned(&self) -> Option<VersionedTransaction> {
+        let transaction: Option<VersionedTransaction> = match self {
+            EncodedTransaction::Json(_) => None,
+            EncodedTransaction::LegacyBinary(blob) => bs58::decode(blob)
+                .into_vec()
+                .ok()
+                .and_then(|bytes| bincode::deserialize(&bytes).ok()),
+            EncodedTransaction::Binary(blob, encoding) => match *encoding {
+                UiTransactionEncoding::Base58 => bs58::decode(blob)
+                    .into_vec()
+                    .ok()
+                    .and_then(|bytes| bincode::deserialize(&bytes).ok()),
+                UiTransactionEncoding::Base64 => base64::decode(blob)
+                    .ok()
+                    .and_then(|bytes| bincode::deserialize(&bytes).ok()),
+                UiTransactionEncoding::Binary
+                | UiTransactionEncoding::Json
+                | UiTransactionEncoding::JsonParsed => None,
+            },
+        };
+        transaction.filter(|transaction| transaction.sanitize().is_ok())
+    }
 }
 
 // A serialized `Vec<TransactionByAddrInfo>` is stored in the `tx-by-addr` table.  The row keys are
@@ -685,4 +997,241 @@ mod test {
         };
         assert!(status.satisfies_commitment(CommitmentConfig::confirmed()));
     }
+
+    #[test]
+    fn test_encode_versioned_transaction() {
+        use solana_sdk::{hash::Hash, message::v0};
+
+        let lookup_table_key = Pubkey::new_unique();
+        let transaction = VersionedTransaction {
+            signatures: vec![Signature::default()],
+            message: VersionedMessage::V0(v0::Message {
+                header: MessageHeader {
+                    num_required_signatures: 1,
+                    num_readonly_signed_accounts: 0,
+                    num_readonly_unsigned_accounts: 1,
+                },
+                account_keys: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+                recent_blockhash: Hash::new_unique(),
+                instructions: vec![CompiledInstruction {
+                    program_id_index: 1,
+                    accounts: vec![0, 2],
+                    data: vec![],
+                }],
+                address_table_lookups: vec![MessageAddressTableLookup {
+                    account_key: lookup_table_key,
+                    writable_indexes: vec![3],
+                    readonly_indexes: vec![],
+                }],
+            }),
+        };
+
+        let loaded_address = Pubkey::new_unique();
+        let loaded_addresses = LoadedAddresses {
+            writable: vec![loaded_address],
+            readonly: vec![],
+        };
+
+        for encoding in &[UiTransactionEncoding::Base58, UiTransactionEncoding::Base64] {
+            let encoded = EncodedTransaction::encode_versioned(
+                transaction.clone(),
+                *encoding,
+                &loaded_addresses,
+            );
+            assert_eq!(encoded.decode_versioned(), Some(transaction.clone()));
+            assert!(encoded.decode().is_none());
+        }
+
+        // Without the loaded addresses, accounts cannot be parsed
+        match EncodedTransaction::encode_versioned(
+            transaction.clone(),
+            UiTransactionEncoding::JsonParsed,
+            &LoadedAddresses::default(),
+        ) {
+            EncodedTransaction::Json(UiTransaction {
+                message: UiMessage::Raw(message),
+                ..
+            }) => assert_eq!(
+                message.address_table_lookups,
+                Some(vec![UiAddressTableLookup {
+                    account_key: lookup_table_key.to_string(),
+                    writable_indexes: vec![3],
+                    readonly_indexes: vec![],
+                }])
+            ),
+            encoded => panic!("unexpected encoding: {:?}", encoded),
+        }
+
+        match EncodedTransaction::encode_versioned(
+            transaction.clone(),
+            UiTransactionEncoding::JsonParsed,
+            &loaded_addresses,
+        ) {
+            EncodedTransaction::Json(UiTransaction {
+                message: UiMessage::Parsed(message),
+                ..
+            }) => {
+                assert_eq!(message.account_keys.len(), 3);
+                assert_eq!(
+                    message.account_keys[2],
+                    ParsedAccount {
+                        pubkey: loaded_address.to_string(),
+                        writable: true,
+                        signer: false,
+                    }
+                );
+                assert_eq!(
+                    message.instructions,
+                    vec![UiInstruction::Parsed(
+                        UiParsedInstruction::PartiallyDecoded(UiPartiallyDecodedInstruction {
+                            program_id: transaction.message.static_account_keys()[1].to_string(),
+                            accounts: vec![
+                                transaction.message.static_account_keys()[0].to_string(),
+                                loaded_address.to_string(),
+                            ],
+                            data: String::new(),
+                        })
+                    )]
+                );
+            }
+            encoded => panic!("unexpected encoding: {:?}", encoded),
+        }
+
+        // The meta lists the loaded addresses, and parses inner instructions against them
+        let with_meta = TransactionWithStatusMeta {
+            transaction,
+            meta: Some(TransactionStatusMeta {
+                inner_instructions: Some(vec![InnerInstructions {
+                    index: 0,
+                    instructions: vec![CompiledInstruction {
+                        program_id_index: 1,
+                        accounts: vec![2],
+                        data: vec![],
+                    }],
+                }]),
+                loaded_addresses,
+                ..TransactionStatusMeta::default()
+            }),
+        };
+        let meta = with_meta
+            .encode(UiTransactionEncoding::JsonParsed)
+            .meta
+            .unwrap();
+        assert_eq!(
+            meta.loaded_addresses,
+            Some(UiLoadedAddresses {
+                writable: vec![loaded_address.to_string()],
+                readonly: vec![],
+            })
+        );
+        match &meta.inner_instructions.unwrap()[0].instructions[0] {
+            UiInstruction::Parsed(UiParsedInstruction::PartiallyDecoded(instruction)) => {
+                assert_eq!(instruction.accounts, vec![loaded_address.to_string()])
+            }
+            instruction => panic!("unexpected instruction: {:?}", instruction),
+        }
+
+        // legacy transactions encode the same way through either entry point
+        let legacy_transaction = Transaction::default();
+        assert_eq!(
+            EncodedTransaction::encode_versioned(
+                legacy_transaction.clone().into(),
+                UiTransactionEncoding::Json,
+                &LoadedAddresses::default(),
+            ),
+            EncodedTransaction::encode(legacy_transaction, UiTransactionEncoding::Json)
+        );
+    }
+
+    #[test]
+    fn test_record_loaded_addresses() {
+        use solana_account_decoder::parse_token::token_amount_to_ui_amount;
+        use solana_sdk::{hash::Hash, message::v0};
+
+        let static_keys: Vec<_> = (0..4).map(|_| Pubkey::new_unique()).collect();
+        let loaded_addresses = LoadedAddresses {
+            writable: vec![Pubkey::new_unique()],
+            readonly: vec![Pubkey::new_unique()],
+        };
+        let message = v0::Message {
+            header: MessageHeader {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 2,
+            },
+            account_keys: static_keys.clone(),
+            recent_blockhash: Hash::default(),
+            instructions: vec![CompiledInstruction {
+                program_id_index: 3,
+                accounts: vec![0, 4, 5],
+                data: vec![],
+            }],
+            address_table_lookups: vec![MessageAddressTableLookup {
+                account_key: Pubkey::new_unique(),
+                writable_indexes: vec![0],
+                readonly_indexes: vec![1],
+            }],
+        };
+        let transaction = Transaction {
+            signatures: vec![Signature::default()],
+            message: message
+                .clone()
+                .try_into_legacy_message(loaded_addresses.clone())
+                .unwrap(),
+        };
+        let versioned_transaction = VersionedTransaction {
+            signatures: vec![Signature::default()],
+            message: VersionedMessage::V0(message),
+        };
+
+        // Give each account the balance of its position in the signed order
+        let signed_keys: Vec<_> = static_keys
+            .iter()
+            .chain(&loaded_addresses.writable)
+            .chain(&loaded_addresses.readonly)
+            .collect();
+        let position = |key: &Pubkey| signed_keys.iter().position(|k| *k == key).unwrap();
+        let balances: Vec<u64> = transaction
+            .message
+            .account_keys
+            .iter()
+            .map(|key| position(key) as u64)
+            .collect();
+        let loaded_writable_index = transaction
+            .message
+            .account_keys
+            .iter()
+            .position(|key| *key == loaded_addresses.writable[0])
+            .unwrap();
+        let resolved_meta = TransactionStatusMeta {
+            pre_balances: balances.clone(),
+            post_balances: balances,
+            inner_instructions: Some(vec![InnerInstructions {
+                index: 0,
+                instructions: vec![transaction.message.instructions[0].clone()],
+            }]),
+            pre_token_balances: Some(vec![TransactionTokenBalance {
+                account_index: loaded_writable_index as u8,
+                mint: String::default(),
+                ui_token_amount: token_amount_to_ui_amount(0, 0),
+            }]),
+            ..TransactionStatusMeta::default()
+        };
+
+        let mut meta = resolved_meta.clone();
+        meta.record_loaded_addresses(&transaction, &versioned_transaction);
+        assert_eq!(meta.loaded_addresses, loaded_addresses);
+        assert_eq!(meta.pre_balances, vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(meta.post_balances, vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(
+            meta.inner_instructions.unwrap()[0].instructions[0],
+            versioned_transaction.message.instructions()[0]
+        );
+        assert_eq!(meta.pre_token_balances.unwrap()[0].account_index, 4);
+
+        // Statuses of legacy transactions are already in their signed order
+        let mut meta = resolved_meta.clone();
+        meta.record_loaded_addresses(&transaction, &transaction.clone().into());
+        assert_eq!(meta, resolved_meta);
+    }
 }
@@ -1,4 +1,7 @@
-use solana_sdk::message::Message;
+use solana_sdk::message::{
+    v0::{self, LoadedAddresses},
+    Message,
+};
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
@@ -20,10 +23,47 @@ pub fn parse_accounts(message: &Message) -> Vec<ParsedAccount> {
     accounts
 }
 
+/// Parse the accounts of a v0 message, listing the static account keys followed by the
+/// writable and then the readonly addresses loaded from lookup tables
+pub fn parse_v0_accounts(
+    message: &v0::Message,
+    loaded_addresses: &LoadedAddresses,
+) -> Vec<ParsedAccount> {
+    let num_required_signatures = message.header.num_required_signatures as usize;
+    let num_writable_signed_accounts =
+        num_required_signatures - message.header.num_readonly_signed_accounts as usize;
+    let num_writable_static_accounts =
+        message.account_keys.len() - message.header.num_readonly_unsigned_accounts as usize;
+    let mut accounts: Vec<ParsedAccount> = vec![];
+    for (i, account_key) in message.account_keys.iter().enumerate() {
+        accounts.push(ParsedAccount {
+            pubkey: account_key.to_string(),
+            writable: i < num_writable_signed_accounts
+                || (i >= num_required_signatures && i < num_writable_static_accounts),
+            signer: i < num_required_signatures,
+        });
+    }
+    for account_key in &loaded_addresses.writable {
+        accounts.push(ParsedAccount {
+            pubkey: account_key.to_string(),
+            writable: true,
+            signer: false,
+        });
+    }
+    for account_key in &loaded_addresses.readonly {
+        accounts.push(ParsedAccount {
+            pubkey: account_key.to_string(),
+            writable: false,
+            signer: false,
+        });
+    }
+    accounts
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
-    use solana_sdk::message::MessageHeader;
+    use solana_sdk::{message::MessageHeader, pubkey::Pubkey};
 
     #[test]
     fn test_parse_accounts() {
@@ -67,4 +107,57 @@ mod test {
             ]
         );
     }
+
+    #[test]
+    fn test_parse_v0_accounts() {
+        let pubkey0 = Pubkey::new_unique();
+        let pubkey1 = Pubkey::new_unique();
+        let pubkey2 = Pubkey::new_unique();
+        let pubkey3 = Pubkey::new_unique();
+        let pubkey4 = Pubkey::new_unique();
+        let message = v0::Message {
+            header: MessageHeader {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 1,
+            },
+            account_keys: vec![pubkey0, pubkey1, pubkey2],
+            ..v0::Message::default()
+        };
+        let loaded_addresses = LoadedAddresses {
+            writable: vec![pubkey3],
+            readonly: vec![pubkey4],
+        };
+
+        assert_eq!(
+            parse_v0_accounts(&message, &loaded_addresses),
+            vec![
+                ParsedAccount {
+                    pubkey: pubkey0.to_string(),
+                    writable: true,
+                    signer: true,
+                },
+                ParsedAccount {
+                    pubkey: pubkey1.to_string(),
+                    writable: true,
+                    signer: false,
+                },
+                ParsedAccount {
+                    pubkey: pubkey2.to_string(),
+                    writable: false,
+                    signer: false,
+                },
+                ParsedAccount {
+                    pubkey: pubkey3.to_string(),
+                    writable: true,
+                    signer: false,
+                },
+                ParsedAccount {
+                    pubkey: pubkey4.to_string(),
+                    writable: false,
+                    signer: false,
+                },
+            ]
+        );
+    }
 }
@@ -131,6 +131,39 @@ pub fn parse_bpf_upgradeable_loader(
                 }),
             })
         }
+        UpgradeableLoaderInstruction::Close => {
+            check_num_bpf_upgradeable_loader_accounts(&instruction.accounts, 3)?;
+            let mut value = json!({
+                "account": account_keys[instruction.accounts[0] as usize].to_string(),
+                "recipient": account_keys[instruction.accounts[1] as usize].to_string(),
+                "authority": account_keys[instruction.accounts[2] as usize].to_string(),
+            });
+            let map = value.as_object_mut().unwrap();
+            if instruction.accounts.len() > 3 {
+                map.insert(
+                    "programAccount".to_string(),
+                    json!(account_keys[instruction.accounts[3] as usize].to_string()),
+                );
+            }
+            Ok(ParsedInstructionEnum {
+                instruction_type: "close".to_string(),
+                info: value,
+            })
+        }
+        UpgradeableLoaderInstruction::ExtendProgram { additional_bytes } => {
+            check_num_bpf_upgradeable_loader_accounts(&instruction.accounts, 5)?;
+            Ok(ParsedInstructionEnum {
+                instruction_type: "extendProgram".to_string(),
+                info: json!({
+                    "additionalBytes": additional_bytes,
+                    "programDataAccount": account_keys[instruction.accounts[0] as usize].to_string(),
+                    "programAccount": account_keys[instruction.accounts[1] as usize].to_string(),
+                    "rentSysvar": account_keys[instruction.accounts[2] as usize].to_string(),
+                    "systemProgram": account_keys[instruction.accounts[3] as usize].to_string(),
+                    "payerAccount": account_keys[instruction.accounts[4] as usize].to_string(),
+                }),
+            })
+        }
     }
 }
 
@@ -352,5 +385,60 @@ mod test {
             }
         );
         assert!(parse_bpf_upgradeable_loader(&message.instructions[0], &keys[0..1]).is_err());
+
+        let instruction =
+            solana_sdk::bpf_loader_upgradeable::close_buffer(&keys[1], &keys[2], &keys[0]);
+        let message = Message::new(&[instruction], None);
+        assert_eq!(
+            parse_bpf_upgradeable_loader(&message.instructions[0], &keys[0..3]).unwrap(),
+            ParsedInstructionEnum {
+                instruction_type: "close".to_string(),
+                info: json!({
+                    "account": keys[1].to_string(),
+                    "recipient": keys[2].to_string(),
+                    "authority": keys[0].to_string(),
+                }),
+            }
+        );
+        assert!(parse_bpf_upgradeable_loader(&message.instructions[0], &keys[0..2]).is_err());
+
+        let instruction =
+            solana_sdk::bpf_loader_upgradeable::close_program(&keys[3], &keys[2], &keys[0]);
+        let message = Message::new(&[instruction], None);
+        let account_keys = &message.account_keys;
+        assert_eq!(
+            parse_bpf_upgradeable_loader(&message.instructions[0], account_keys).unwrap(),
+            ParsedInstructionEnum {
+                instruction_type: "close".to_string(),
+                info: json!({
+                    "account": account_keys[message.instructions[0].accounts[0] as usize].to_string(),
+                    "recipient": keys[2].to_string(),
+                    "authority": keys[0].to_string(),
+                    "programAccount": keys[3].to_string(),
+                }),
+            }
+        );
+
+        let instruction =
+            solana_sdk::bpf_loader_upgradeable::extend_program(&keys[1], &keys[0], 42);
+        let message = Message::new(&[instruction], None);
+        let account_keys = &message.account_keys;
+        assert_eq!(
+            parse_bpf_upgradeable_loader(&message.instructions[0], account_keys).unwrap(),
+            ParsedInstructionEnum {
+                instruction_type: "extendProgram".to_string(),
+                info: json!({
+                    "additionalBytes": 42,
+                    "programDataAccount": account_keys[message.instructions[0].accounts[0] as usize].to_string(),
+                    "programAccount": keys[1].to_string(),
+                    "rentSysvar": solana_sdk::sysvar::rent::id().to_string(),
+                    "systemProgram": solana_sdk::system_program::id().to_string(),
+                    "payerAccount": keys[0].to_string(),
+                }),
+            }
+        );
+        assert!(
+            parse_bpf_upgradeable_loader(&message.instructions[0], &account_keys[0..3]).is_err()
+        );
     }
 }
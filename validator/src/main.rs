@@ -13,9 +13,12 @@ use solana_clap_utils::{
     },
     keypair::SKIP_SEED_PHRASE_VALIDATION_ARG,
 };
-use solana_client::{rpc_client::RpcClient, rpc_request::MAX_MULTIPLE_ACCOUNTS};
+use solana_client::{
+    rpc_client::RpcClient,
+    rpc_request::{MAX_BATCH_REQUESTS, MAX_MULTIPLE_ACCOUNTS},
+};
 use solana_core::ledger_cleanup_service::{
-    DEFAULT_MAX_LEDGER_SHREDS, DEFAULT_MIN_MAX_LEDGER_SHREDS,
+    LedgerRetention, DEFAULT_MAX_LEDGER_SHREDS, DEFAULT_MIN_MAX_LEDGER_SHREDS,
 };
 use solana_core::{
     cluster_info::{ClusterInfo, Node, MINIMUM_VALIDATOR_PORT_RANGE_WIDTH, VALIDATOR_PORT_RANGE},
@@ -27,16 +30,16 @@ use solana_core::{
     validator::{is_snapshot_config_invalid, Validator, ValidatorConfig},
 };
 use solana_download_utils::{download_genesis_if_missing, download_snapshot};
-use solana_ledger::blockstore_db::BlockstoreRecoveryMode;
+use solana_ledger::{blockstore::ColumnGroup, blockstore_db::BlockstoreRecoveryMode};
 use solana_perf::recycler::enable_recycler_warming;
 use solana_runtime::{
-    accounts_index::AccountIndex,
+    accounts_index::{AccountIndex, ProgramDataIndex},
     bank_forks::{ArchiveFormat, SnapshotConfig, SnapshotVersion},
     hardened_unpack::{unpack_genesis_archive, MAX_GENESIS_ARCHIVE_UNPACKED_SIZE},
     snapshot_utils::get_highest_snapshot_archive_path,
 };
 use solana_sdk::{
-    clock::Slot,
+    clock::{Slot, SECONDS_PER_DAY},
     commitment_config::CommitmentConfig,
     genesis_config::GenesisConfig,
     hash::Hash,
@@ -83,6 +86,41 @@ fn port_range_validator(port_range: String) -> Result<(), String> {
     }
 }
 
+fn parse_column_group_limit(value: &str) -> Result<(ColumnGroup, u64), String> {
+    let mut parts = value.splitn(2, '=');
+    match (parts.next(), parts.next()) {
+        (Some(column_group), Some(limit)) => Ok((
+            column_group.parse::<ColumnGroup>()?,
+            limit
+                .parse::<u64>()
+                .map_err(|err| format!("Invalid limit {}: {}", limit, err))?,
+        )),
+        _ => Err(format!("Expected COLUMN_GROUP=LIMIT, found {}", value)),
+    }
+}
+
+fn column_group_limit_validator(value: String) -> Result<(), String> {
+    parse_column_group_limit(&value).map(|_| ())
+}
+
+fn column_group_days_validator(value: String) -> Result<(), String> {
+    let (_, days) = parse_column_group_limit(&value)?;
+    days.checked_mul(SECONDS_PER_DAY)
+        .map(|_| ())
+        .ok_or_else(|| format!("Too many days: {}", days))
+}
+
+fn column_group_limits_of(matches: &ArgMatches<'_>, name: &str) -> Vec<(ColumnGroup, u64)> {
+    matches
+        .values_of(name)
+        .map(|values| {
+            values
+                .map(|value| parse_column_group_limit(value).unwrap())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 fn hash_validator(hash: String) -> Result<(), String> {
     Hash::from_str(&hash)
         .map(|_| ())
@@ -778,6 +816,7 @@ pub fn main() {
         &format!("{}-{}", VALIDATOR_PORT_RANGE.0, VALIDATOR_PORT_RANGE.1);
     let default_genesis_archive_unpacked_size = &MAX_GENESIS_ARCHIVE_UNPACKED_SIZE.to_string();
     let default_rpc_max_multiple_accounts = &MAX_MULTIPLE_ACCOUNTS.to_string();
+    let default_rpc_max_batch_requests = &MAX_BATCH_REQUESTS.to_string();
     let default_rpc_pubsub_max_connections = PubSubConfig::default().max_connections.to_string();
     let default_rpc_pubsub_max_fragment_size =
         PubSubConfig::default().max_fragment_size.to_string();
@@ -982,6 +1021,22 @@ pub fn main() {
                 .help("Override the default maximum accounts accepted by \
                        the getMultipleAccounts JSON RPC method")
         )
+        .arg(
+            Arg::with_name("rpc_max_batch_requests")
+                .long("rpc-max-batch-requests")
+                .value_name("MAX REQUESTS")
+                .takes_value(true)
+                .validator(|s| {
+                    let max_batch_requests = usize::from_str(&s).map_err(|e| e.to_string())?;
+                    if max_batch_requests == 0 {
+                        return Err("the batch limit must be at least 1".to_string());
+                    }
+                    Ok(())
+                })
+                .default_value(default_rpc_max_batch_requests)
+                .help("Override the default maximum number of requests accepted \
+                       in a single JSON RPC batch")
+        )
         .arg(
             Arg::with_name("health_check_slot_distance")
                 .long("health-check-slot-distance")
@@ -1114,6 +1169,31 @@ pub fn main() {
                 /* .default_value() intentionally not used here! */
                 .help("Keep this amount of shreds in root slots."),
         )
+        .arg(
+            Arg::with_name("ledger_retention_bytes")
+                .long("ledger-retention-bytes")
+                .value_name("COLUMN_GROUP=BYTES")
+                .takes_value(true)
+                .multiple(true)
+                .validator(column_group_limit_validator)
+                .help("Keep the COLUMN_GROUP data of the ledger under BYTES bytes. \
+                       COLUMN_GROUP is one of shreds, block-metadata or transaction-status. \
+                       A column group given a retention limit is no longer limited by \
+                       --limit-ledger-size, except for shreds"),
+        )
+        .arg(
+            Arg::with_name("ledger_retention_days")
+                .long("ledger-retention-days")
+                .value_name("COLUMN_GROUP=DAYS")
+                .takes_value(true)
+                .multiple(true)
+                .validator(column_group_days_validator)
+                .help("Keep the COLUMN_GROUP data of the ledger for the root slots of the last \
+                       DAYS days, as dated by their block time. \
+                       COLUMN_GROUP is one of shreds, block-metadata or transaction-status. \
+                       A column group given a retention limit is no longer limited by \
+                       --limit-ledger-size, except for shreds"),
+        )
         .arg(
             Arg::with_name("skip_poh_verify")
                 .long("skip-poh-verify")
@@ -1291,6 +1371,13 @@ pub fn main() {
                 .takes_value(false)
                 .help("Enable the unstable RPC PubSub `voteSubscribe` subscription"),
         )
+        .arg(
+            Arg::with_name("rpc_pubsub_enable_block_subscription")
+                .long("rpc-pubsub-enable-block-subscription")
+                .requires("enable_rpc_transaction_history")
+                .takes_value(false)
+                .help("Enable the unstable RPC PubSub `blockSubscribe` subscription"),
+        )
         .arg(
             Arg::with_name("rpc_pubsub_max_connections")
                 .long("rpc-pubsub-max-connections")
@@ -1431,6 +1518,18 @@ pub fn main() {
                 .value_name("INDEX")
                 .help("Enable an accounts index, indexed by the selected account field"),
         )
+        .arg(
+            Arg::with_name("account_index_program_data")
+                .long("account-index-program-data")
+                .takes_value(true)
+                .multiple(true)
+                .value_name("PROGRAM_ID:OFFSET:LENGTH")
+                .validator(|s| s.parse::<ProgramDataIndex>().map(|_| ()))
+                .help("Enable an accounts index of the accounts owned by PROGRAM_ID, indexed by \
+                       the LENGTH bytes of account data at OFFSET. LENGTH is at most 32 bytes. \
+                       getProgramAccounts requests with a memcmp filter matching the whole \
+                       field are served from the index"),
+        )
         .arg(
             Arg::with_name("no_accounts_db_caching")
                 .long("no-accounts-db-caching")
@@ -1569,6 +1668,12 @@ pub fn main() {
             "spl-token-owner" => AccountIndex::SplTokenOwner,
             _ => unreachable!(),
         })
+        .chain(
+            matches
+                .values_of("account_index_program_data")
+                .unwrap_or_default()
+                .map(|value| AccountIndex::ProgramData(value.parse().unwrap())),
+        )
         .collect();
 
     let restricted_repair_only_mode = matches.is_present("restricted_repair_only_mode");
@@ -1601,6 +1706,7 @@ pub fn main() {
                 "rpc_max_multiple_accounts",
                 usize
             )),
+            max_batch_requests: Some(value_t_or_exit!(matches, "rpc_max_batch_requests", usize)),
             health_check_slot_distance: value_t_or_exit!(
                 matches,
                 "health_check_slot_distance",
@@ -1623,6 +1729,7 @@ pub fn main() {
         }),
         pubsub_config: PubSubConfig {
             enable_vote_subscription: matches.is_present("rpc_pubsub_enable_vote_subscription"),
+            enable_block_subscription: matches.is_present("rpc_pubsub_enable_block_subscription"),
             max_connections: value_t_or_exit!(matches, "rpc_pubsub_max_connections", usize),
             max_fragment_size: value_t_or_exit!(matches, "rpc_pubsub_max_fragment_size", usize),
             max_in_buffer_capacity: value_t_or_exit!(
@@ -1790,6 +1897,17 @@ pub fn main() {
         exit(1);
     }
 
+    let mut ledger_retention = LedgerRetention::default();
+    let mut retained_column_groups = HashSet::new();
+    for (column_group, max_bytes) in column_group_limits_of(&matches, "ledger_retention_bytes") {
+        ledger_retention.limits_mut(column_group).max_bytes = Some(max_bytes);
+        retained_column_groups.insert(column_group);
+    }
+    for (column_group, days) in column_group_limits_of(&matches, "ledger_retention_days") {
+        ledger_retention.limits_mut(column_group).max_age =
+            Some(Duration::from_secs(days * SECONDS_PER_DAY));
+        retained_column_groups.insert(column_group);
+    }
     if matches.is_present("limit_ledger_size") {
         let limit_ledger_size = match matches.value_of("limit_ledger_size") {
             Some(_) => value_t_or_exit!(matches, "limit_ledger_size", u64),
@@ -1802,7 +1920,15 @@ pub fn main() {
             );
             exit(1);
         }
-        validator_config.max_ledger_shreds = Some(limit_ledger_size);
+        ledger_retention.shreds.max_shreds = Some(limit_ledger_size);
+        for column_group in &[ColumnGroup::BlockMetadata, ColumnGroup::TransactionStatus] {
+            if !retained_column_groups.contains(column_group) {
+                ledger_retention.limits_mut(*column_group).max_shreds = Some(limit_ledger_size);
+            }
+        }
+    }
+    if !ledger_retention.is_unlimited() {
+        validator_config.ledger_retention = Some(ledger_retention);
     }
 
     if matches.is_present("halt_on_trusted_validators_accounts_hash_mismatch") {